@@ -1,5 +1,6 @@
 use anyhow::Result;
-use futures::{future::BoxFuture, stream::BoxStream};
+use futures::{future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
 
 use crate::{auth::CredentialProvider, models::LanguageModel};
 
@@ -7,12 +8,49 @@ pub trait CompletionRequest: Send + Sync {
     fn data(&self) -> serde_json::Result<String>;
 }
 
+/// A function that the model can ask to have called, described by a JSON
+/// schema for its parameters.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// A request from the model to call one of the tools it was given, with the
+/// arguments encoded as a JSON string.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompletionEvent {
+    Text(String),
+    ToolCall(ToolCall),
+}
+
 pub trait CompletionProvider: CredentialProvider {
     fn base_model(&self) -> Box<dyn LanguageModel>;
     fn complete(
         &self,
         prompt: Box<dyn CompletionRequest>,
     ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>>;
+    /// Streams a completion that may contain calls to the tools included in
+    /// the request. Providers that don't support tools only produce text.
+    fn complete_with_tools(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<CompletionEvent>>>> {
+        let completion = self.complete(prompt);
+        async move {
+            let stream = completion.await?;
+            Ok(stream.map(|text| text.map(CompletionEvent::Text)).boxed())
+        }
+        .boxed()
+    }
     fn box_clone(&self) -> Box<dyn CompletionProvider>;
 }
 
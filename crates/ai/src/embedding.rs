@@ -73,6 +73,15 @@ pub trait EmbeddingProvider: CredentialProvider {
     fn base_model(&self) -> Box<dyn LanguageModel>;
     async fn embed_batch(&self, spans: Vec<String>) -> Result<Vec<Embedding>>;
     fn max_tokens_per_batch(&self) -> usize;
+    /// The largest number of spans to embed in a single request, for servers
+    /// that limit the size of a batch regardless of its token count.
+    fn max_spans_per_batch(&self) -> usize {
+        usize::MAX
+    }
+    /// How many requests can be sent to the provider at once.
+    fn max_concurrent_batches(&self) -> usize {
+        1
+    }
     fn rate_limit_expiration(&self) -> Option<Instant>;
 }
 
@@ -3,7 +3,7 @@ pub enum TruncationDirection {
     End,
 }
 
-pub trait LanguageModel {
+pub trait LanguageModel: Send + Sync {
     fn name(&self) -> String;
     fn count_tokens(&self, content: &str) -> anyhow::Result<usize>;
     fn truncate(
@@ -0,0 +1,65 @@
+/// A piece of context attached to a conversation, such as the contents of a
+/// file or the output of a command, formatted so that the model can tell where
+/// it came from and where it ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PromptContextSection {
+    pub title: String,
+    pub language_name: Option<String>,
+    pub content: String,
+}
+
+impl PromptContextSection {
+    pub fn new(title: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            language_name: None,
+            content: content.into(),
+        }
+    }
+
+    pub fn with_language_name(mut self, language_name: Option<String>) -> Self {
+        self.language_name = language_name.map(|name| name.to_lowercase());
+        self
+    }
+
+    pub fn to_prompt(&self) -> String {
+        // Use a fence that is longer than any run of backticks in the content,
+        // so that code blocks within the content don't end the section early.
+        let mut longest_backtick_run = 0;
+        let mut backtick_run = 0;
+        for ch in self.content.chars() {
+            if ch == '`' {
+                backtick_run += 1;
+                longest_backtick_run = longest_backtick_run.max(backtick_run);
+            } else {
+                backtick_run = 0;
+            }
+        }
+        let fence = "`".repeat(longest_backtick_run.max(2) + 1);
+
+        let language_name = self.language_name.as_deref().unwrap_or_default();
+        let content = self.content.trim_end_matches('\n');
+        format!("{}\n{fence}{language_name}\n{content}\n{fence}", self.title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_section_to_prompt() {
+        let section = PromptContextSection::new("File `src/main.rs`", "fn main() {}\n")
+            .with_language_name(Some("Rust".into()));
+        assert_eq!(
+            section.to_prompt(),
+            "File `src/main.rs`\n```rust\nfn main() {}\n```"
+        );
+
+        let section = PromptContextSection::new("File `README.md`", "```sh\ncargo run\n```");
+        assert_eq!(
+            section.to_prompt(),
+            "File `README.md`\n````\n```sh\ncargo run\n```\n````"
+        );
+    }
+}
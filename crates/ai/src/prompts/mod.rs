@@ -1,4 +1,5 @@
 pub mod base;
+pub mod context_section;
 pub mod file_context;
 pub mod generate;
 pub mod preamble;
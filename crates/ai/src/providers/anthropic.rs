@@ -0,0 +1,7 @@
+pub mod completion;
+pub mod model;
+
+pub use completion::*;
+pub use model::AnthropicLanguageModel;
+
+pub const ANTHROPIC_API_URL: &'static str = "https://api.anthropic.com/v1";
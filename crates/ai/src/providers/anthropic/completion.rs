@@ -0,0 +1,459 @@
+use anyhow::{anyhow, Result};
+use futures::{
+    future::BoxFuture, io::BufReader, stream::BoxStream, AsyncBufReadExt, AsyncReadExt, FutureExt,
+    Stream, StreamExt,
+};
+use gpui::{AppContext, BackgroundExecutor};
+use isahc::{http::StatusCode, Request, RequestExt};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{env, sync::Arc};
+use util::ResultExt;
+
+use crate::{
+    auth::{CredentialProvider, ProviderCredential},
+    completion::{CompletionProvider, CompletionRequest},
+    models::LanguageModel,
+    providers::open_ai::{OpenAiRequest, Role},
+};
+
+use crate::providers::anthropic::{AnthropicLanguageModel, ANTHROPIC_API_URL};
+
+const ANTHROPIC_VERSION: &'static str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: usize = 4096;
+
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct AnthropicMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnthropicRequest {
+    pub model: String,
+    pub messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub max_tokens: usize,
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    pub temperature: f32,
+}
+
+impl From<OpenAiRequest> for AnthropicRequest {
+    /// The Messages API takes the system prompt as a separate parameter, and
+    /// requires user and assistant messages to alternate, so system messages
+    /// are pulled out and consecutive messages with the same role are merged.
+    /// Tools aren't supported yet, so tool results are passed along as user
+    /// messages.
+    fn from(request: OpenAiRequest) -> Self {
+        let mut system = Vec::new();
+        let mut messages = Vec::<AnthropicMessage>::new();
+        for message in request.messages {
+            if message.content.is_empty() {
+                continue;
+            }
+
+            let role = match message.role {
+                Role::Tool => Role::User,
+                role => role,
+            };
+            match role {
+                Role::System => system.push(message.content),
+                role => match messages.last_mut() {
+                    Some(last_message) if last_message.role == role => {
+                        last_message.content.push_str("\n\n");
+                        last_message.content.push_str(&message.content);
+                    }
+                    _ => messages.push(AnthropicMessage {
+                        role,
+                        content: message.content,
+                    }),
+                },
+            }
+        }
+
+        Self {
+            model: request.model,
+            messages,
+            system: (!system.is_empty()).then(|| system.join("\n\n")),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            stream: request.stream,
+            stop_sequences: request.stop,
+            // Anthropic's temperatures range from 0 to 1, rather than OpenAI's 0 to 2.
+            temperature: request.temperature.clamp(0., 1.),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicStreamEvent {
+    ContentBlockDelta {
+        delta: AnthropicTextDelta,
+    },
+    MessageStop,
+    Error {
+        error: AnthropicError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct AnthropicTextDelta {
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct AnthropicError {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+}
+
+/// Parses a line of the server-sent event stream returned by the Messages API.
+/// Only `data:` lines carry events; the `event:` lines repeat their type.
+pub fn parse_stream_line(line: &str) -> Result<Option<AnthropicStreamEvent>> {
+    if let Some(data) = line.strip_prefix("data: ") {
+        Ok(Some(serde_json::from_str(data)?))
+    } else {
+        Ok(None)
+    }
+}
+
+pub async fn stream_completion(
+    api_url: String,
+    headers: Vec<(String, String)>,
+    credential: ProviderCredential,
+    executor: BackgroundExecutor,
+    request: AnthropicRequest,
+) -> Result<impl Stream<Item = Result<String>>> {
+    let api_key = match credential {
+        ProviderCredential::Credentials { api_key } => api_key,
+        _ => {
+            return Err(anyhow!("no credentials provider for completion"));
+        }
+    };
+
+    let (tx, rx) = futures::channel::mpsc::unbounded::<Result<String>>();
+
+    let json_data = serde_json::to_string(&request)?;
+    let mut request = Request::post(format!("{api_url}/messages"))
+        .header("Content-Type", "application/json")
+        .header("x-api-key", api_key)
+        .header("anthropic-version", ANTHROPIC_VERSION);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let mut response = request.body(json_data)?.send_async().await?;
+
+    let status = response.status();
+    if status == StatusCode::OK {
+        executor
+            .spawn(async move {
+                let mut lines = BufReader::new(response.body_mut()).lines();
+                while let Some(line) = lines.next().await {
+                    let event = match line
+                        .map_err(anyhow::Error::from)
+                        .and_then(|line| parse_stream_line(&line))
+                    {
+                        Ok(Some(event)) => event,
+                        Ok(None) => continue,
+                        Err(error) => {
+                            tx.unbounded_send(Err(error)).ok();
+                            break;
+                        }
+                    };
+
+                    match event {
+                        AnthropicStreamEvent::ContentBlockDelta { delta } => {
+                            if tx.unbounded_send(Ok(delta.text)).is_err() {
+                                break;
+                            }
+                        }
+                        AnthropicStreamEvent::MessageStop => break,
+                        AnthropicStreamEvent::Error { error } => {
+                            tx.unbounded_send(Err(anyhow!("{}", error.message))).ok();
+                            break;
+                        }
+                        AnthropicStreamEvent::Other => {}
+                    }
+                }
+
+                anyhow::Ok(())
+            })
+            .detach();
+
+        Ok(rx)
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        #[derive(Deserialize)]
+        struct AnthropicResponse {
+            error: AnthropicError,
+        }
+
+        match serde_json::from_str::<AnthropicResponse>(&body) {
+            Ok(response) if !response.error.message.is_empty() => Err(anyhow!(
+                "Failed to connect to Anthropic API: {}",
+                response.error.message,
+            )),
+
+            _ => Err(anyhow!(
+                "Failed to connect to Anthropic API: {} {}",
+                response.status(),
+                body,
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AnthropicCompletionProvider {
+    model: AnthropicLanguageModel,
+    api_url: String,
+    headers: Vec<(String, String)>,
+    credential: Arc<RwLock<ProviderCredential>>,
+    executor: BackgroundExecutor,
+}
+
+impl AnthropicCompletionProvider {
+    pub async fn new(model_name: String, executor: BackgroundExecutor) -> Self {
+        let model = AnthropicLanguageModel::load(&model_name);
+        let credential = Arc::new(RwLock::new(ProviderCredential::NoCredentials));
+        Self {
+            model,
+            api_url: ANTHROPIC_API_URL.into(),
+            headers: Vec::new(),
+            credential,
+            executor,
+        }
+    }
+
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn with_context_window(mut self, context_window: usize) -> Self {
+        self.model = self.model.with_capacity(context_window);
+        self
+    }
+}
+
+impl CredentialProvider for AnthropicCompletionProvider {
+    fn has_credentials(&self) -> bool {
+        match *self.credential.read() {
+            ProviderCredential::Credentials { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn retrieve_credentials(&self, cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
+        let existing_credential = self.credential.read().clone();
+        let retrieved_credential = match existing_credential {
+            ProviderCredential::Credentials { .. } => {
+                return async move { existing_credential }.boxed()
+            }
+            _ => {
+                // The environment's key belongs to Anthropic, so it must not be
+                // sent to other servers that speak the same API.
+                let env_api_key = (self.api_url == ANTHROPIC_API_URL)
+                    .then(|| env::var("ANTHROPIC_API_KEY").log_err())
+                    .flatten();
+                if let Some(api_key) = env_api_key {
+                    async move { ProviderCredential::Credentials { api_key } }.boxed()
+                } else {
+                    let credentials = cx.read_credentials(&self.api_url);
+                    async move {
+                        if let Some(Some((_, api_key))) = credentials.await.log_err() {
+                            if let Some(api_key) = String::from_utf8(api_key).log_err() {
+                                ProviderCredential::Credentials { api_key }
+                            } else {
+                                ProviderCredential::NoCredentials
+                            }
+                        } else {
+                            ProviderCredential::NoCredentials
+                        }
+                    }
+                    .boxed()
+                }
+            }
+        };
+
+        async move {
+            let retrieved_credential = retrieved_credential.await;
+            *self.credential.write() = retrieved_credential.clone();
+            retrieved_credential
+        }
+        .boxed()
+    }
+
+    fn save_credentials(
+        &self,
+        cx: &mut AppContext,
+        credential: ProviderCredential,
+    ) -> BoxFuture<()> {
+        *self.credential.write() = credential.clone();
+        let write_credentials = match credential {
+            ProviderCredential::Credentials { api_key } => {
+                Some(cx.write_credentials(&self.api_url, "x-api-key", api_key.as_bytes()))
+            }
+            _ => None,
+        };
+
+        async move {
+            if let Some(write_credentials) = write_credentials {
+                write_credentials.await.log_err();
+            }
+        }
+        .boxed()
+    }
+
+    fn delete_credentials(&self, cx: &mut AppContext) -> BoxFuture<()> {
+        *self.credential.write() = ProviderCredential::NoCredentials;
+        let delete_credentials = cx.delete_credentials(&self.api_url);
+        async move {
+            delete_credentials.await.log_err();
+        }
+        .boxed()
+    }
+}
+
+impl CompletionProvider for AnthropicCompletionProvider {
+    fn base_model(&self) -> Box<dyn LanguageModel> {
+        let model: Box<dyn LanguageModel> = Box::new(self.model.clone());
+        model
+    }
+    fn complete(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        let request = prompt
+            .data()
+            .and_then(|data| serde_json::from_str::<OpenAiRequest>(&data));
+        let credential = self.credential.read().clone();
+        let api_url = self.api_url.clone();
+        let headers = self.headers.clone();
+        let executor = self.executor.clone();
+        async move {
+            let request = AnthropicRequest::from(request?);
+            let response =
+                stream_completion(api_url, headers, credential, executor, request).await?;
+            Ok(response.boxed())
+        }
+        .boxed()
+    }
+    fn box_clone(&self) -> Box<dyn CompletionProvider> {
+        Box::new((*self).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::open_ai::RequestMessage;
+
+    #[test]
+    fn test_request_from_open_ai_request() {
+        let request = AnthropicRequest::from(OpenAiRequest {
+            model: "claude-3-opus-20240229".into(),
+            messages: vec![
+                RequestMessage {
+                    role: Role::System,
+                    content: "Be brief.".into(),
+                    ..Default::default()
+                },
+                RequestMessage {
+                    role: Role::User,
+                    content: "Hello".into(),
+                    ..Default::default()
+                },
+                RequestMessage {
+                    role: Role::User,
+                    content: "Are you there?".into(),
+                    ..Default::default()
+                },
+                RequestMessage {
+                    role: Role::Assistant,
+                    content: "".into(),
+                    ..Default::default()
+                },
+                RequestMessage {
+                    role: Role::Assistant,
+                    content: "Yes.".into(),
+                    ..Default::default()
+                },
+            ],
+            stream: true,
+            stop: vec!["|END|>".into()],
+            temperature: 1.5,
+            tools: Vec::new(),
+        });
+
+        assert_eq!(request.system.as_deref(), Some("Be brief."));
+        assert_eq!(
+            request.messages,
+            vec![
+                AnthropicMessage {
+                    role: Role::User,
+                    content: "Hello\n\nAre you there?".into(),
+                },
+                AnthropicMessage {
+                    role: Role::Assistant,
+                    content: "Yes.".into(),
+                },
+            ]
+        );
+        assert_eq!(request.stop_sequences, vec!["|END|>".to_string()]);
+        assert_eq!(request.temperature, 1.);
+    }
+
+    #[test]
+    fn test_parse_stream_line() {
+        assert_eq!(
+            parse_stream_line("event: content_block_delta").unwrap(),
+            None
+        );
+        assert_eq!(parse_stream_line("").unwrap(), None);
+        assert_eq!(
+            parse_stream_line(
+                r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#
+            )
+            .unwrap(),
+            Some(AnthropicStreamEvent::ContentBlockDelta {
+                delta: AnthropicTextDelta { text: "Hi".into() }
+            })
+        );
+        assert_eq!(
+            parse_stream_line(r#"data: {"type":"ping"}"#).unwrap(),
+            Some(AnthropicStreamEvent::Other)
+        );
+        assert_eq!(
+            parse_stream_line(r#"data: {"type":"message_stop"}"#).unwrap(),
+            Some(AnthropicStreamEvent::MessageStop)
+        );
+        assert_eq!(
+            parse_stream_line(
+                r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+            )
+            .unwrap(),
+            Some(AnthropicStreamEvent::Error {
+                error: AnthropicError {
+                    kind: "overloaded_error".into(),
+                    message: "Overloaded".into(),
+                }
+            })
+        );
+        assert!(parse_stream_line("data: {").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::models::{LanguageModel, TruncationDirection};
+
+/// Anthropic doesn't publish the tokenizer used by its current models, so
+/// token counts are estimated from the length of the text. Claude models
+/// average more than three characters per token on English text and code, so
+/// this overestimates counts and keeps prompts within the context window.
+const CHARS_PER_TOKEN: usize = 3;
+
+#[derive(Clone)]
+pub struct AnthropicLanguageModel {
+    name: String,
+    capacity: usize,
+}
+
+impl AnthropicLanguageModel {
+    pub fn load(model_name: &str) -> Self {
+        let capacity = if model_name.starts_with("claude-3") || model_name == "claude-2.1" {
+            200_000
+        } else {
+            100_000
+        };
+        AnthropicLanguageModel {
+            name: model_name.to_string(),
+            capacity,
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+impl LanguageModel for AnthropicLanguageModel {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn count_tokens(&self, content: &str) -> anyhow::Result<usize> {
+        let chars = content.chars().count();
+        anyhow::Ok((chars + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN)
+    }
+    fn truncate(
+        &self,
+        content: &str,
+        length: usize,
+        direction: TruncationDirection,
+    ) -> anyhow::Result<String> {
+        if self.count_tokens(content)? <= length {
+            return anyhow::Ok(content.to_string());
+        }
+
+        let chars = length.saturating_mul(CHARS_PER_TOKEN);
+        anyhow::Ok(match direction {
+            TruncationDirection::End => content.chars().take(chars).collect(),
+            TruncationDirection::Start => content.chars().skip(chars).collect(),
+        })
+    }
+    fn capacity(&self) -> anyhow::Result<usize> {
+        anyhow::Ok(self.capacity)
+    }
+}
@@ -0,0 +1,9 @@
+pub mod completion;
+pub mod embedding;
+pub mod model;
+
+pub use completion::*;
+pub use embedding::*;
+pub use model::OllamaLanguageModel;
+
+pub const OLLAMA_API_URL: &'static str = "http://localhost:11434";
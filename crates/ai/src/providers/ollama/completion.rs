@@ -0,0 +1,277 @@
+use anyhow::{anyhow, Result};
+use futures::{
+    future::BoxFuture, io::BufReader, stream::BoxStream, AsyncBufReadExt, AsyncReadExt, FutureExt,
+    Stream, StreamExt,
+};
+use gpui::{AppContext, BackgroundExecutor};
+use isahc::{http::StatusCode, Request, RequestExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{CredentialProvider, ProviderCredential},
+    completion::{CompletionProvider, CompletionRequest},
+    models::LanguageModel,
+    providers::open_ai::{OpenAiRequest, RequestMessage},
+};
+
+use crate::providers::ollama::{OllamaLanguageModel, OLLAMA_API_URL};
+
+#[derive(Debug, Serialize)]
+pub struct OllamaRequest {
+    pub model: String,
+    pub messages: Vec<RequestMessage>,
+    pub stream: bool,
+    pub options: OllamaOptions,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct OllamaOptions {
+    pub temperature: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Ollama truncates prompts to 2048 tokens unless told otherwise, so the
+    /// configured context window is sent along with every request.
+    pub num_ctx: usize,
+}
+
+impl OllamaRequest {
+    pub fn new(request: OpenAiRequest, context_window: usize) -> Self {
+        Self {
+            model: request.model,
+            messages: request.messages,
+            stream: request.stream,
+            options: OllamaOptions {
+                temperature: request.temperature,
+                stop: request.stop,
+                num_ctx: context_window,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OllamaStreamEvent {
+    #[serde(default)]
+    pub message: Option<OllamaResponseMessage>,
+    #[serde(default)]
+    pub done: bool,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OllamaResponseMessage {
+    pub content: String,
+}
+
+/// Parses a line of the newline-delimited JSON stream returned by `/api/chat`.
+pub fn parse_stream_line(line: &str) -> Result<Option<OllamaStreamEvent>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OllamaStreamLine {
+        Error { error: String },
+        Event(OllamaStreamEvent),
+    }
+
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+
+    match serde_json::from_str(line)? {
+        OllamaStreamLine::Error { error } => Err(anyhow!("{error}")),
+        OllamaStreamLine::Event(event) => Ok(Some(event)),
+    }
+}
+
+pub async fn stream_completion(
+    api_url: String,
+    headers: Vec<(String, String)>,
+    executor: BackgroundExecutor,
+    request: OllamaRequest,
+) -> Result<impl Stream<Item = Result<String>>> {
+    let (tx, rx) = futures::channel::mpsc::unbounded::<Result<String>>();
+
+    let json_data = serde_json::to_string(&request)?;
+    let mut request =
+        Request::post(format!("{api_url}/api/chat")).header("Content-Type", "application/json");
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let mut response = request.body(json_data)?.send_async().await?;
+
+    let status = response.status();
+    if status == StatusCode::OK {
+        executor
+            .spawn(async move {
+                let mut lines = BufReader::new(response.body_mut()).lines();
+                while let Some(line) = lines.next().await {
+                    let event = match line
+                        .map_err(anyhow::Error::from)
+                        .and_then(|line| parse_stream_line(&line))
+                    {
+                        Ok(Some(event)) => event,
+                        Ok(None) => continue,
+                        Err(error) => {
+                            tx.unbounded_send(Err(error)).ok();
+                            break;
+                        }
+                    };
+
+                    if let Some(message) = event.message {
+                        if tx.unbounded_send(Ok(message.content)).is_err() {
+                            break;
+                        }
+                    }
+
+                    if event.done {
+                        break;
+                    }
+                }
+
+                anyhow::Ok(())
+            })
+            .detach();
+
+        Ok(rx)
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        #[derive(Deserialize)]
+        struct OllamaResponse {
+            error: String,
+        }
+
+        match serde_json::from_str::<OllamaResponse>(&body) {
+            Ok(response) if !response.error.is_empty() => Err(anyhow!(
+                "Failed to connect to Ollama API: {}",
+                response.error,
+            )),
+
+            _ => Err(anyhow!(
+                "Failed to connect to Ollama API: {} {}",
+                response.status(),
+                body,
+            )),
+        }
+    }
+}
+
+/// Completes prompts with a model served by a local Ollama server, which
+/// doesn't require an API key.
+#[derive(Clone)]
+pub struct OllamaCompletionProvider {
+    model: OllamaLanguageModel,
+    api_url: String,
+    headers: Vec<(String, String)>,
+    executor: BackgroundExecutor,
+}
+
+impl OllamaCompletionProvider {
+    pub fn new(model_name: String, executor: BackgroundExecutor) -> Self {
+        Self {
+            model: OllamaLanguageModel::load(&model_name),
+            api_url: OLLAMA_API_URL.into(),
+            headers: Vec::new(),
+            executor,
+        }
+    }
+
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn with_context_window(mut self, context_window: usize) -> Self {
+        self.model = self.model.with_capacity(context_window);
+        self
+    }
+}
+
+impl CredentialProvider for OllamaCompletionProvider {
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    fn retrieve_credentials(&self, _cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
+        async { ProviderCredential::NotNeeded }.boxed()
+    }
+
+    fn save_credentials(
+        &self,
+        _cx: &mut AppContext,
+        _credential: ProviderCredential,
+    ) -> BoxFuture<()> {
+        async {}.boxed()
+    }
+
+    fn delete_credentials(&self, _cx: &mut AppContext) -> BoxFuture<()> {
+        async {}.boxed()
+    }
+}
+
+impl CompletionProvider for OllamaCompletionProvider {
+    fn base_model(&self) -> Box<dyn LanguageModel> {
+        let model: Box<dyn LanguageModel> = Box::new(self.model.clone());
+        model
+    }
+    fn complete(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        let request = prompt
+            .data()
+            .and_then(|data| serde_json::from_str::<OpenAiRequest>(&data));
+        let context_window = self.model.capacity().unwrap_or_default();
+        let api_url = self.api_url.clone();
+        let headers = self.headers.clone();
+        let executor = self.executor.clone();
+        async move {
+            let request = OllamaRequest::new(request?, context_window);
+            let response = stream_completion(api_url, headers, executor, request).await?;
+            Ok(response.boxed())
+        }
+        .boxed()
+    }
+    fn box_clone(&self) -> Box<dyn CompletionProvider> {
+        Box::new((*self).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_line() {
+        assert_eq!(parse_stream_line("").unwrap(), None);
+        assert_eq!(
+            parse_stream_line(
+                r#"{"model":"llama2","message":{"role":"assistant","content":"Hi"},"done":false}"#
+            )
+            .unwrap(),
+            Some(OllamaStreamEvent {
+                message: Some(OllamaResponseMessage {
+                    content: "Hi".into()
+                }),
+                done: false,
+            })
+        );
+        assert_eq!(
+            parse_stream_line(r#"{"model":"llama2","done":true,"eval_count":12}"#).unwrap(),
+            Some(OllamaStreamEvent {
+                message: None,
+                done: true,
+            })
+        );
+        assert_eq!(
+            parse_stream_line(r#"{"error":"model 'llama2' not found"}"#)
+                .unwrap_err()
+                .to_string(),
+            "model 'llama2' not found"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::{future::BoxFuture, AsyncReadExt, FutureExt};
+use gpui::AppContext;
+use isahc::{http::StatusCode, Request, RequestExt};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::{
+    auth::{CredentialProvider, ProviderCredential},
+    embedding::{Embedding, EmbeddingProvider},
+    models::LanguageModel,
+    providers::ollama::{OllamaLanguageModel, OLLAMA_API_URL},
+};
+
+/// How many tokens to embed in one request when no limit is configured.
+const DEFAULT_MAX_TOKENS_PER_BATCH: usize = 8192;
+
+#[derive(Serialize)]
+struct OllamaEmbeddingRequest<'a> {
+    model: &'a str,
+    input: Vec<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbeddingResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Embeds spans with a model served by a local Ollama server, so that code
+/// never leaves the machine.
+#[derive(Clone)]
+pub struct OllamaEmbeddingProvider {
+    model: OllamaLanguageModel,
+    api_url: String,
+    headers: Vec<(String, String)>,
+    max_tokens_per_batch: usize,
+}
+
+impl OllamaEmbeddingProvider {
+    pub fn new(model_name: String) -> Self {
+        Self {
+            model: OllamaLanguageModel::load(&model_name),
+            api_url: OLLAMA_API_URL.into(),
+            headers: Vec::new(),
+            max_tokens_per_batch: DEFAULT_MAX_TOKENS_PER_BATCH,
+        }
+    }
+
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets the model's context window, which bounds the size of each span.
+    pub fn with_context_window(mut self, context_window: usize) -> Self {
+        self.model = self.model.with_capacity(context_window);
+        self
+    }
+
+    pub fn with_max_tokens_per_batch(mut self, max_tokens_per_batch: usize) -> Self {
+        self.max_tokens_per_batch = max_tokens_per_batch;
+        self
+    }
+}
+
+impl CredentialProvider for OllamaEmbeddingProvider {
+    fn has_credentials(&self) -> bool {
+        true
+    }
+
+    fn retrieve_credentials(&self, _cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
+        async { ProviderCredential::NotNeeded }.boxed()
+    }
+
+    fn save_credentials(
+        &self,
+        _cx: &mut AppContext,
+        _credential: ProviderCredential,
+    ) -> BoxFuture<()> {
+        async {}.boxed()
+    }
+
+    fn delete_credentials(&self, _cx: &mut AppContext) -> BoxFuture<()> {
+        async {}.boxed()
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    fn base_model(&self) -> Box<dyn LanguageModel> {
+        let model: Box<dyn LanguageModel> = Box::new(self.model.clone());
+        model
+    }
+
+    fn max_tokens_per_batch(&self) -> usize {
+        self.max_tokens_per_batch
+    }
+
+    fn rate_limit_expiration(&self) -> Option<Instant> {
+        None
+    }
+
+    async fn embed_batch(&self, spans: Vec<String>) -> Result<Vec<Embedding>> {
+        let model_name = self.model.name();
+        let body = serde_json::to_string(&OllamaEmbeddingRequest {
+            model: &model_name,
+            input: spans.iter().map(|span| span.as_str()).collect(),
+        })?;
+        let mut request = Request::post(format!("{}/api/embed", self.api_url))
+            .header("Content-Type", "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let mut response = request.body(body)?.send_async().await?;
+
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+        if response.status() != StatusCode::OK {
+            return Err(anyhow!(
+                "ollama embedding request failed: {} {}",
+                response.status(),
+                body
+            ));
+        }
+
+        let response: OllamaEmbeddingResponse = serde_json::from_str(&body)?;
+        if response.embeddings.len() != spans.len() {
+            return Err(anyhow!(
+                "ollama returned {} embeddings for {} spans",
+                response.embeddings.len(),
+                spans.len()
+            ));
+        }
+        Ok(response
+            .embeddings
+            .into_iter()
+            .map(Embedding::from)
+            .collect())
+    }
+}
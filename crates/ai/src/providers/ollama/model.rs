@@ -0,0 +1,57 @@
+use crate::models::{LanguageModel, TruncationDirection};
+
+/// The context window Ollama uses when a request doesn't specify one.
+pub const OLLAMA_DEFAULT_CONTEXT_WINDOW: usize = 2048;
+
+/// Ollama serves models with many different tokenizers, none of which are
+/// available to us, so token counts are estimated from the length of the text.
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Clone)]
+pub struct OllamaLanguageModel {
+    name: String,
+    capacity: usize,
+}
+
+impl OllamaLanguageModel {
+    pub fn load(model_name: &str) -> Self {
+        OllamaLanguageModel {
+            name: model_name.to_string(),
+            capacity: OLLAMA_DEFAULT_CONTEXT_WINDOW,
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+impl LanguageModel for OllamaLanguageModel {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn count_tokens(&self, content: &str) -> anyhow::Result<usize> {
+        let chars = content.chars().count();
+        anyhow::Ok((chars + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN)
+    }
+    fn truncate(
+        &self,
+        content: &str,
+        length: usize,
+        direction: TruncationDirection,
+    ) -> anyhow::Result<String> {
+        if self.count_tokens(content)? <= length {
+            return anyhow::Ok(content.to_string());
+        }
+
+        let chars = length.saturating_mul(CHARS_PER_TOKEN);
+        anyhow::Ok(match direction {
+            TruncationDirection::End => content.chars().take(chars).collect(),
+            TruncationDirection::Start => content.chars().skip(chars).collect(),
+        })
+    }
+    fn capacity(&self) -> anyhow::Result<usize> {
+        anyhow::Ok(self.capacity)
+    }
+}
@@ -17,26 +17,34 @@ use util::ResultExt;
 
 use crate::{
     auth::{CredentialProvider, ProviderCredential},
-    completion::{CompletionProvider, CompletionRequest},
+    completion::{
+        CompletionEvent, CompletionProvider, CompletionRequest, ToolCall, ToolDefinition,
+    },
     models::LanguageModel,
 };
 
 use crate::providers::open_ai::{OpenAiLanguageModel, OPEN_AI_API_URL};
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Default, Serialize, Deserialize, Debug, Eq, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Role {
+    #[default]
     User,
     Assistant,
     System,
+    /// The result of a tool call requested by the assistant.
+    Tool,
 }
 
 impl Role {
+    /// Switches between the roles that can be chosen for a message. Tool
+    /// messages always answer a tool call, so their role never changes.
     pub fn cycle(&mut self) {
         *self = match self {
             Role::User => Role::Assistant,
             Role::Assistant => Role::System,
             Role::System => Role::User,
+            Role::Tool => Role::Tool,
         }
     }
 }
@@ -47,23 +55,97 @@ impl Display for Role {
             Role::User => write!(f, "User"),
             Role::Assistant => write!(f, "Assistant"),
             Role::System => write!(f, "System"),
+            Role::Tool => write!(f, "Tool"),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Clone, Default, Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct RequestMessage {
     pub role: Role,
     pub content: String,
+    /// The tools the assistant asked to call in this message.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<RequestToolCall>,
+    /// The call that a tool message is the result of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolKind {
+    #[default]
+    Function,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct RequestToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: ToolKind,
+    pub function: FunctionCall,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+impl From<ToolCall> for RequestToolCall {
+    fn from(call: ToolCall) -> Self {
+        Self {
+            id: call.id,
+            kind: ToolKind::Function,
+            function: FunctionCall {
+                name: call.name,
+                arguments: call.arguments,
+            },
+        }
+    }
+}
+
+impl From<RequestToolCall> for ToolCall {
+    fn from(call: RequestToolCall) -> Self {
+        Self {
+            id: call.id,
+            name: call.function.name,
+            arguments: call.function.arguments,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RequestTool {
+    #[serde(rename = "type")]
+    pub kind: ToolKind,
+    pub function: ToolDefinition,
+}
+
+impl From<ToolDefinition> for RequestTool {
+    fn from(function: ToolDefinition) -> Self {
+        Self {
+            kind: ToolKind::Function,
+            function,
+        }
+    }
 }
 
-#[derive(Debug, Default, Serialize)]
+/// A chat completion request in the OpenAI format.
+///
+/// Every request is built in this format, and providers that speak a
+/// different API translate it into their own.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct OpenAiRequest {
     pub model: String,
     pub messages: Vec<RequestMessage>,
     pub stream: bool,
+    #[serde(default)]
     pub stop: Vec<String>,
     pub temperature: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<RequestTool>,
 }
 
 impl CompletionRequest for OpenAiRequest {
@@ -76,6 +158,23 @@ impl CompletionRequest for OpenAiRequest {
 pub struct ResponseMessage {
     pub role: Option<Role>,
     pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCallDelta>,
+}
+
+/// A fragment of a tool call. The first fragment for each call contains its
+/// id and name, and the arguments are streamed across the following ones.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct ToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -102,14 +201,68 @@ pub struct OpenAiResponseStreamEvent {
     pub usage: Option<OpenAiUsage>,
 }
 
+/// Turns a streamed response into completion events, accumulating tool call
+/// fragments in `pending_tool_calls` until the model finishes its message.
+fn completion_events(
+    pending_tool_calls: &mut Vec<ToolCall>,
+    mut response: OpenAiResponseStreamEvent,
+) -> Vec<CompletionEvent> {
+    let mut events = Vec::new();
+    let Some(choice) = response.choices.pop() else {
+        return events;
+    };
+
+    if let Some(text) = choice.delta.content {
+        if !text.is_empty() {
+            events.push(CompletionEvent::Text(text));
+        }
+    }
+
+    for delta in choice.delta.tool_calls {
+        if pending_tool_calls.len() <= delta.index {
+            pending_tool_calls.resize_with(delta.index + 1, || ToolCall {
+                id: String::new(),
+                name: String::new(),
+                arguments: String::new(),
+            });
+        }
+        let call = &mut pending_tool_calls[delta.index];
+        if let Some(id) = delta.id {
+            call.id = id;
+        }
+        if let Some(function) = delta.function {
+            if let Some(name) = function.name {
+                call.name.push_str(&name);
+            }
+            if let Some(arguments) = function.arguments {
+                call.arguments.push_str(&arguments);
+            }
+        }
+    }
+
+    if choice.finish_reason.is_some() {
+        events.extend(
+            pending_tool_calls
+                .drain(..)
+                .filter(|call| !call.name.is_empty())
+                .map(CompletionEvent::ToolCall),
+        );
+    }
+
+    events
+}
+
 pub async fn stream_completion(
+    api_url: String,
+    headers: Vec<(String, String)>,
     credential: ProviderCredential,
     executor: BackgroundExecutor,
     request: Box<dyn CompletionRequest>,
 ) -> Result<impl Stream<Item = Result<OpenAiResponseStreamEvent>>> {
     let api_key = match credential {
-        ProviderCredential::Credentials { api_key } => api_key,
-        _ => {
+        ProviderCredential::Credentials { api_key } => Some(api_key),
+        ProviderCredential::NotNeeded => None,
+        ProviderCredential::NoCredentials => {
             return Err(anyhow!("no credentials provider for completion"));
         }
     };
@@ -117,12 +270,15 @@ pub async fn stream_completion(
     let (tx, rx) = futures::channel::mpsc::unbounded::<Result<OpenAiResponseStreamEvent>>();
 
     let json_data = request.data()?;
-    let mut response = Request::post(format!("{OPEN_AI_API_URL}/chat/completions"))
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .body(json_data)?
-        .send_async()
-        .await?;
+    let mut request = Request::post(format!("{api_url}/chat/completions"))
+        .header("Content-Type", "application/json");
+    if let Some(api_key) = api_key {
+        request = request.header("Authorization", format!("Bearer {}", api_key));
+    }
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let mut response = request.body(json_data)?.send_async().await?;
 
     let status = response.status();
     if status == StatusCode::OK {
@@ -196,6 +352,8 @@ pub async fn stream_completion(
 #[derive(Clone)]
 pub struct OpenAiCompletionProvider {
     model: OpenAiLanguageModel,
+    api_url: String,
+    headers: Vec<(String, String)>,
     credential: Arc<RwLock<ProviderCredential>>,
     executor: BackgroundExecutor,
 }
@@ -208,31 +366,62 @@ impl OpenAiCompletionProvider {
         let credential = Arc::new(RwLock::new(ProviderCredential::NoCredentials));
         Self {
             model,
+            api_url: OPEN_AI_API_URL.into(),
+            headers: Vec::new(),
             credential,
             executor,
         }
     }
+
+    /// Sends requests to an OpenAI-compatible API at the given URL instead of OpenAI's.
+    /// API keys are stored separately for each URL.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    /// Adds headers that are sent along with every request.
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn with_context_window(mut self, context_window: usize) -> Self {
+        self.model = self.model.with_capacity(context_window);
+        self
+    }
+
+    /// Sends requests without an API key, for local servers that don't require one.
+    pub fn without_api_key(self) -> Self {
+        *self.credential.write() = ProviderCredential::NotNeeded;
+        self
+    }
 }
 
 impl CredentialProvider for OpenAiCompletionProvider {
     fn has_credentials(&self) -> bool {
         match *self.credential.read() {
-            ProviderCredential::Credentials { .. } => true,
-            _ => false,
+            ProviderCredential::Credentials { .. } | ProviderCredential::NotNeeded => true,
+            ProviderCredential::NoCredentials => false,
         }
     }
 
     fn retrieve_credentials(&self, cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
         let existing_credential = self.credential.read().clone();
         let retrieved_credential = match existing_credential {
-            ProviderCredential::Credentials { .. } => {
+            ProviderCredential::Credentials { .. } | ProviderCredential::NotNeeded => {
                 return async move { existing_credential }.boxed()
             }
             _ => {
-                if let Some(api_key) = env::var("OPENAI_API_KEY").log_err() {
+                // The environment's key belongs to OpenAI, so it must not be
+                // sent to other servers that speak the same API.
+                let env_api_key = (self.api_url == OPEN_AI_API_URL)
+                    .then(|| env::var("OPENAI_API_KEY").log_err())
+                    .flatten();
+                if let Some(api_key) = env_api_key {
                     async move { ProviderCredential::Credentials { api_key } }.boxed()
                 } else {
-                    let credentials = cx.read_credentials(OPEN_AI_API_URL);
+                    let credentials = cx.read_credentials(&self.api_url);
                     async move {
                         if let Some(Some((_, api_key))) = credentials.await.log_err() {
                             if let Some(api_key) = String::from_utf8(api_key).log_err() {
@@ -266,7 +455,7 @@ impl CredentialProvider for OpenAiCompletionProvider {
         let credential = credential.clone();
         let write_credentials = match credential {
             ProviderCredential::Credentials { api_key } => {
-                Some(cx.write_credentials(OPEN_AI_API_URL, "Bearer", api_key.as_bytes()))
+                Some(cx.write_credentials(&self.api_url, "Bearer", api_key.as_bytes()))
             }
             _ => None,
         };
@@ -281,7 +470,7 @@ impl CredentialProvider for OpenAiCompletionProvider {
 
     fn delete_credentials(&self, cx: &mut AppContext) -> BoxFuture<()> {
         *self.credential.write() = ProviderCredential::NoCredentials;
-        let delete_credentials = cx.delete_credentials(OPEN_AI_API_URL);
+        let delete_credentials = cx.delete_credentials(&self.api_url);
         async move {
             delete_credentials.await.log_err();
         }
@@ -303,7 +492,13 @@ impl CompletionProvider for OpenAiCompletionProvider {
         // which is currently model based, due to the language model.
         // At some point in the future we should rectify this.
         let credential = self.credential.read().clone();
-        let request = stream_completion(credential, self.executor.clone(), prompt);
+        let request = stream_completion(
+            self.api_url.clone(),
+            self.headers.clone(),
+            credential,
+            self.executor.clone(),
+            prompt,
+        );
         async move {
             let response = request.await?;
             let stream = response
@@ -318,7 +513,120 @@ impl CompletionProvider for OpenAiCompletionProvider {
         }
         .boxed()
     }
+    fn complete_with_tools(
+        &self,
+        prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<CompletionEvent>>>> {
+        let credential = self.credential.read().clone();
+        let request = stream_completion(
+            self.api_url.clone(),
+            self.headers.clone(),
+            credential,
+            self.executor.clone(),
+            prompt,
+        );
+        async move {
+            let response = request.await?;
+            let mut pending_tool_calls = Vec::new();
+            let stream = response
+                .map(move |response| match response {
+                    Ok(response) => completion_events(&mut pending_tool_calls, response)
+                        .into_iter()
+                        .map(Ok)
+                        .collect(),
+                    Err(error) => vec![Err(error)],
+                })
+                .flat_map(futures::stream::iter)
+                .boxed();
+            Ok(stream)
+        }
+        .boxed()
+    }
     fn box_clone(&self) -> Box<dyn CompletionProvider> {
         Box::new((*self).clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_events_accumulate_tool_calls() {
+        fn event(json: &str) -> OpenAiResponseStreamEvent {
+            serde_json::from_str(json).unwrap()
+        }
+
+        let mut pending_tool_calls = Vec::new();
+        assert_eq!(
+            completion_events(
+                &mut pending_tool_calls,
+                event(
+                    r#"{"object":"chat.completion.chunk","created":0,"model":"gpt-4","choices":[{"index":0,"delta":{"role":"assistant","content":"Let me look."},"finish_reason":null}]}"#
+                )
+            ),
+            vec![CompletionEvent::Text("Let me look.".into())]
+        );
+        assert_eq!(
+            completion_events(
+                &mut pending_tool_calls,
+                event(
+                    r#"{"object":"chat.completion.chunk","created":0,"model":"gpt-4","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"read_file","arguments":"{\"pa"}}]},"finish_reason":null}]}"#
+                )
+            ),
+            vec![]
+        );
+        assert_eq!(
+            completion_events(
+                &mut pending_tool_calls,
+                event(
+                    r#"{"object":"chat.completion.chunk","created":0,"model":"gpt-4","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"th\":\"a.rs\"}"}}]},"finish_reason":null}]}"#
+                )
+            ),
+            vec![]
+        );
+        assert_eq!(
+            completion_events(
+                &mut pending_tool_calls,
+                event(
+                    r#"{"object":"chat.completion.chunk","created":0,"model":"gpt-4","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#
+                )
+            ),
+            vec![CompletionEvent::ToolCall(ToolCall {
+                id: "call_1".into(),
+                name: "read_file".into(),
+                arguments: r#"{"path":"a.rs"}"#.into(),
+            })]
+        );
+        assert!(pending_tool_calls.is_empty());
+    }
+
+    #[test]
+    fn test_serialize_tool_messages() {
+        let message = RequestMessage {
+            role: Role::Assistant,
+            tool_calls: vec![ToolCall {
+                id: "call_1".into(),
+                name: "read_file".into(),
+                arguments: "{}".into(),
+            }
+            .into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"role":"assistant","content":"","tool_calls":[{"id":"call_1","type":"function","function":{"name":"read_file","arguments":"{}"}}]}"#
+        );
+
+        let message = RequestMessage {
+            role: Role::Tool,
+            content: "fn main() {}".into(),
+            tool_call_id: Some("call_1".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"role":"tool","content":"fn main() {}","tool_call_id":"call_1"}"#
+        );
+    }
+}
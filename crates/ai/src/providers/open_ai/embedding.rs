@@ -33,9 +33,14 @@ lazy_static! {
     pub(crate) static ref OPEN_AI_BPE_TOKENIZER: CoreBPE = cl100k_base().unwrap();
 }
 
+const OPEN_AI_EMBEDDING_MODEL: &'static str = "text-embedding-ada-002";
+
 #[derive(Clone)]
 pub struct OpenAiEmbeddingProvider {
     model: OpenAiLanguageModel,
+    api_url: String,
+    headers: Vec<(String, String)>,
+    max_tokens_per_batch: usize,
     credential: Arc<RwLock<ProviderCredential>>,
     pub client: Arc<dyn HttpClient>,
     pub executor: BackgroundExecutor,
@@ -45,7 +50,7 @@ pub struct OpenAiEmbeddingProvider {
 
 #[derive(Serialize)]
 struct OpenAiEmbeddingRequest<'a> {
-    model: &'static str,
+    model: &'a str,
     input: Vec<&'a str>,
 }
 
@@ -70,17 +75,28 @@ struct OpenAiEmbeddingUsage {
 
 impl OpenAiEmbeddingProvider {
     pub async fn new(client: Arc<dyn HttpClient>, executor: BackgroundExecutor) -> Self {
+        Self::for_model(OPEN_AI_EMBEDDING_MODEL.into(), client, executor).await
+    }
+
+    pub async fn for_model(
+        model_name: String,
+        client: Arc<dyn HttpClient>,
+        executor: BackgroundExecutor,
+    ) -> Self {
         let (rate_limit_count_tx, rate_limit_count_rx) = watch::channel_with(None);
         let rate_limit_count_tx = Arc::new(Mutex::new(rate_limit_count_tx));
 
         // Loading the model is expensive, so ensure this runs off the main thread.
         let model = executor
-            .spawn(async move { OpenAiLanguageModel::load("text-embedding-ada-002") })
+            .spawn(async move { OpenAiLanguageModel::load(&model_name) })
             .await;
         let credential = Arc::new(RwLock::new(ProviderCredential::NoCredentials));
 
         OpenAiEmbeddingProvider {
             model,
+            api_url: OPEN_AI_API_URL.into(),
+            headers: Vec::new(),
+            max_tokens_per_batch: 50000,
             credential,
             client,
             executor,
@@ -89,10 +105,35 @@ impl OpenAiEmbeddingProvider {
         }
     }
 
-    fn get_api_key(&self) -> Result<String> {
+    /// Sends requests to an OpenAI-compatible API at the given URL instead of OpenAI's.
+    /// API keys are stored separately for each URL.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    /// Adds headers that are sent along with every request.
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn with_max_tokens_per_batch(mut self, max_tokens_per_batch: usize) -> Self {
+        self.max_tokens_per_batch = max_tokens_per_batch;
+        self
+    }
+
+    /// Sends requests without an API key, for local servers that don't require one.
+    pub fn without_api_key(self) -> Self {
+        *self.credential.write() = ProviderCredential::NotNeeded;
+        self
+    }
+
+    fn get_api_key(&self) -> Result<Option<String>> {
         match self.credential.read().clone() {
-            ProviderCredential::Credentials { api_key } => Ok(api_key),
-            _ => Err(anyhow!("api credentials not provided")),
+            ProviderCredential::Credentials { api_key } => Ok(Some(api_key)),
+            ProviderCredential::NotNeeded => Ok(None),
+            ProviderCredential::NoCredentials => Err(anyhow!("api credentials not provided")),
         }
     }
 
@@ -130,23 +171,29 @@ impl OpenAiEmbeddingProvider {
     }
     async fn send_request(
         &self,
-        api_key: &str,
+        api_key: Option<&str>,
         spans: Vec<&str>,
         request_timeout: u64,
     ) -> Result<Response<AsyncBody>> {
-        let request = Request::post(format!("{OPEN_AI_API_URL}/embeddings"))
+        let mut request = Request::post(format!("{}/embeddings", self.api_url))
             .redirect_policy(isahc::config::RedirectPolicy::Follow)
             .timeout(Duration::from_secs(request_timeout))
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .body(
-                serde_json::to_string(&OpenAiEmbeddingRequest {
-                    input: spans.clone(),
-                    model: "text-embedding-ada-002",
-                })
-                .unwrap()
-                .into(),
-            )?;
+            .header("Content-Type", "application/json");
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let model_name = self.model.name();
+        let request = request.body(
+            serde_json::to_string(&OpenAiEmbeddingRequest {
+                input: spans.clone(),
+                model: &model_name,
+            })
+            .unwrap()
+            .into(),
+        )?;
 
         Ok(self.client.send(request).await?)
     }
@@ -155,22 +202,27 @@ impl OpenAiEmbeddingProvider {
 impl CredentialProvider for OpenAiEmbeddingProvider {
     fn has_credentials(&self) -> bool {
         match *self.credential.read() {
-            ProviderCredential::Credentials { .. } => true,
-            _ => false,
+            ProviderCredential::Credentials { .. } | ProviderCredential::NotNeeded => true,
+            ProviderCredential::NoCredentials => false,
         }
     }
 
     fn retrieve_credentials(&self, cx: &mut AppContext) -> BoxFuture<ProviderCredential> {
         let existing_credential = self.credential.read().clone();
         let retrieved_credential = match existing_credential {
-            ProviderCredential::Credentials { .. } => {
+            ProviderCredential::Credentials { .. } | ProviderCredential::NotNeeded => {
                 return async move { existing_credential }.boxed()
             }
             _ => {
-                if let Some(api_key) = env::var("OPENAI_API_KEY").log_err() {
+                // The environment's key belongs to OpenAI, so it must not be
+                // sent to other servers that speak the same API.
+                let env_api_key = (self.api_url == OPEN_AI_API_URL)
+                    .then(|| env::var("OPENAI_API_KEY").log_err())
+                    .flatten();
+                if let Some(api_key) = env_api_key {
                     async move { ProviderCredential::Credentials { api_key } }.boxed()
                 } else {
-                    let credentials = cx.read_credentials(OPEN_AI_API_URL);
+                    let credentials = cx.read_credentials(&self.api_url);
                     async move {
                         if let Some(Some((_, api_key))) = credentials.await.log_err() {
                             if let Some(api_key) = String::from_utf8(api_key).log_err() {
@@ -204,7 +256,7 @@ impl CredentialProvider for OpenAiEmbeddingProvider {
         let credential = credential.clone();
         let write_credentials = match credential {
             ProviderCredential::Credentials { api_key } => {
-                Some(cx.write_credentials(OPEN_AI_API_URL, "Bearer", api_key.as_bytes()))
+                Some(cx.write_credentials(&self.api_url, "Bearer", api_key.as_bytes()))
             }
             _ => None,
         };
@@ -219,7 +271,7 @@ impl CredentialProvider for OpenAiEmbeddingProvider {
 
     fn delete_credentials(&self, cx: &mut AppContext) -> BoxFuture<()> {
         *self.credential.write() = ProviderCredential::NoCredentials;
-        let delete_credentials = cx.delete_credentials(OPEN_AI_API_URL);
+        let delete_credentials = cx.delete_credentials(&self.api_url);
         async move {
             delete_credentials.await.log_err();
         }
@@ -235,7 +287,11 @@ impl EmbeddingProvider for OpenAiEmbeddingProvider {
     }
 
     fn max_tokens_per_batch(&self) -> usize {
-        50000
+        self.max_tokens_per_batch
+    }
+
+    fn max_concurrent_batches(&self) -> usize {
+        8
     }
 
     fn rate_limit_expiration(&self) -> Option<Instant> {
@@ -255,7 +311,7 @@ impl EmbeddingProvider for OpenAiEmbeddingProvider {
         while request_number < MAX_RETRIES {
             response = self
                 .send_request(
-                    &api_key,
+                    api_key.as_deref(),
                     spans.iter().map(|x| &**x).collect(),
                     request_timeout,
                 )
@@ -10,7 +10,7 @@ use parking_lot::Mutex;
 
 use crate::{
     auth::{CredentialProvider, ProviderCredential},
-    completion::{CompletionProvider, CompletionRequest},
+    completion::{CompletionEvent, CompletionProvider, CompletionRequest, ToolCall},
     embedding::{Embedding, EmbeddingProvider},
     models::{LanguageModel, TruncationDirection},
 };
@@ -56,12 +56,16 @@ impl LanguageModel for FakeLanguageModel {
 
 pub struct FakeEmbeddingProvider {
     pub embedding_count: AtomicUsize,
+    max_spans_per_batch: usize,
+    batch_sizes: Mutex<Vec<usize>>,
 }
 
 impl Clone for FakeEmbeddingProvider {
     fn clone(&self) -> Self {
         FakeEmbeddingProvider {
             embedding_count: AtomicUsize::new(self.embedding_count.load(Ordering::SeqCst)),
+            max_spans_per_batch: self.max_spans_per_batch,
+            batch_sizes: Mutex::new(self.batch_sizes.lock().clone()),
         }
     }
 }
@@ -70,15 +74,27 @@ impl Default for FakeEmbeddingProvider {
     fn default() -> Self {
         FakeEmbeddingProvider {
             embedding_count: AtomicUsize::default(),
+            max_spans_per_batch: usize::MAX,
+            batch_sizes: Mutex::default(),
         }
     }
 }
 
 impl FakeEmbeddingProvider {
+    pub fn with_max_spans_per_batch(mut self, max_spans_per_batch: usize) -> Self {
+        self.max_spans_per_batch = max_spans_per_batch;
+        self
+    }
+
     pub fn embedding_count(&self) -> usize {
         self.embedding_count.load(atomic::Ordering::SeqCst)
     }
 
+    /// The number of spans in each batch that was embedded, in order.
+    pub fn batch_sizes(&self) -> Vec<usize> {
+        self.batch_sizes.lock().clone()
+    }
+
     pub fn embed_sync(&self, span: &str) -> Embedding {
         let mut result = vec![1.0; 26];
         for letter in span.chars() {
@@ -131,6 +147,10 @@ impl EmbeddingProvider for FakeEmbeddingProvider {
         1000
     }
 
+    fn max_spans_per_batch(&self) -> usize {
+        self.max_spans_per_batch
+    }
+
     fn rate_limit_expiration(&self) -> Option<Instant> {
         None
     }
@@ -138,19 +158,23 @@ impl EmbeddingProvider for FakeEmbeddingProvider {
     async fn embed_batch(&self, spans: Vec<String>) -> anyhow::Result<Vec<Embedding>> {
         self.embedding_count
             .fetch_add(spans.len(), atomic::Ordering::SeqCst);
+        self.batch_sizes.lock().push(spans.len());
 
         anyhow::Ok(spans.iter().map(|span| self.embed_sync(span)).collect())
     }
 }
 
+/// Streams completions that tests send explicitly. Completions that are still
+/// pending are kept open, and the most recently requested one receives the
+/// text that's sent.
 pub struct FakeCompletionProvider {
-    last_completion_tx: Mutex<Option<mpsc::Sender<String>>>,
+    completion_txs: Mutex<Vec<mpsc::Sender<CompletionEvent>>>,
 }
 
 impl Clone for FakeCompletionProvider {
     fn clone(&self) -> Self {
         Self {
-            last_completion_tx: Mutex::new(None),
+            completion_txs: Mutex::new(Vec::new()),
         }
     }
 }
@@ -158,17 +182,32 @@ impl Clone for FakeCompletionProvider {
 impl FakeCompletionProvider {
     pub fn new() -> Self {
         Self {
-            last_completion_tx: Mutex::new(None),
+            completion_txs: Mutex::new(Vec::new()),
         }
     }
 
+    pub fn pending_completion_count(&self) -> usize {
+        self.completion_txs.lock().len()
+    }
+
     pub fn send_completion(&self, completion: impl Into<String>) {
-        let mut tx = self.last_completion_tx.lock();
-        tx.as_mut().unwrap().try_send(completion.into()).unwrap();
+        let mut txs = self.completion_txs.lock();
+        txs.last_mut()
+            .unwrap()
+            .try_send(CompletionEvent::Text(completion.into()))
+            .unwrap();
+    }
+
+    pub fn send_tool_call(&self, tool_call: ToolCall) {
+        let mut txs = self.completion_txs.lock();
+        txs.last_mut()
+            .unwrap()
+            .try_send(CompletionEvent::ToolCall(tool_call))
+            .unwrap();
     }
 
     pub fn finish_completion(&self) {
-        self.last_completion_tx.lock().take().unwrap();
+        self.completion_txs.lock().pop().unwrap();
     }
 }
 
@@ -204,8 +243,23 @@ impl CompletionProvider for FakeCompletionProvider {
         _prompt: Box<dyn CompletionRequest>,
     ) -> BoxFuture<'static, anyhow::Result<BoxStream<'static, anyhow::Result<String>>>> {
         let (tx, rx) = mpsc::channel(1);
-        *self.last_completion_tx.lock() = Some(tx);
-        async move { Ok(rx.map(|rx| Ok(rx)).boxed()) }.boxed()
+        self.completion_txs.lock().push(tx);
+        let stream = rx.filter_map(|event| async move {
+            match event {
+                CompletionEvent::Text(text) => Some(Ok(text)),
+                CompletionEvent::ToolCall(_) => None,
+            }
+        });
+        async move { Ok(stream.boxed()) }.boxed()
+    }
+    fn complete_with_tools(
+        &self,
+        _prompt: Box<dyn CompletionRequest>,
+    ) -> BoxFuture<'static, anyhow::Result<BoxStream<'static, anyhow::Result<CompletionEvent>>>>
+    {
+        let (tx, rx) = mpsc::channel(1);
+        self.completion_txs.lock().push(tx);
+        async move { Ok(rx.map(Ok).boxed()) }.boxed()
     }
     fn box_clone(&self) -> Box<dyn CompletionProvider> {
         Box::new((*self).clone())
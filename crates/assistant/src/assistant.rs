@@ -1,22 +1,26 @@
 pub mod assistant_panel;
 mod assistant_settings;
 mod codegen;
+mod commit_message;
+mod completion_provider;
+mod conversation_history;
+mod fill_in_the_middle;
+mod persistence;
+mod prompt_library;
 mod prompts;
+mod proposed_edits;
+mod slash_command;
 mod streaming_diff;
+mod tools;
 
-use ai::providers::open_ai::Role;
-use anyhow::Result;
+use ai::{completion::ToolCall, providers::open_ai::Role};
 pub use assistant_panel::AssistantPanel;
-use assistant_settings::OpenAiModel;
 use chrono::{DateTime, Local};
 use collections::HashMap;
-use fs::Fs;
-use futures::StreamExt;
+pub use fill_in_the_middle::FillInTheMiddleCompletionProvider;
 use gpui::{actions, AppContext, SharedString};
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{cmp::Reverse, ffi::OsStr, path::PathBuf, sync::Arc};
-use util::paths::CONVERSATIONS_DIR;
+use std::ops::Range;
 
 actions!(
     assistant,
@@ -31,6 +35,15 @@ actions!(
         InlineAssist,
         ToggleIncludeConversation,
         ToggleRetrieveContext,
+        AcceptProposedHunk,
+        RejectProposedHunk,
+        AcceptAllProposedEdits,
+        RejectAllProposedEdits,
+        GenerateCommitMessage,
+        ForkConversation,
+        ExportConversation,
+        ImportConversation,
+        CyclePrompt,
     ]
 );
 
@@ -44,6 +57,12 @@ struct MessageMetadata {
     role: Role,
     sent_at: DateTime<Local>,
     status: MessageStatus,
+    /// The tools the assistant asked to call at the end of this message.
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+    /// For tool messages, the call that this message is the result of.
+    #[serde(default)]
+    tool_call_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,12 +72,27 @@ enum MessageStatus {
     Error(SharedString),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct SavedMessage {
     id: MessageId,
     start: usize,
 }
 
+#[derive(
+    Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+struct ContextSectionId(usize);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedContextSection {
+    id: ContextSectionId,
+    command_range: Range<usize>,
+    output_range: Range<usize>,
+    name: String,
+    argument: Option<String>,
+    title: String,
+}
+
 #[derive(Serialize, Deserialize)]
 struct SavedConversation {
     id: Option<String>,
@@ -68,56 +102,31 @@ struct SavedConversation {
     messages: Vec<SavedMessage>,
     message_metadata: HashMap<MessageId, MessageMetadata>,
     summary: String,
-    model: OpenAiModel,
+    model: String,
+    /// The name of the model's provider. Conversations saved before providers
+    /// were configurable don't have one.
+    #[serde(default)]
+    provider: Option<String>,
+    #[serde(default)]
+    context_sections: Vec<SavedContextSection>,
+    /// The id of the conversation that this one was forked from.
+    #[serde(default)]
+    forked_from: Option<String>,
+    /// The name of the prompt from the prompt library that is used as the
+    /// conversation's system prompt.
+    #[serde(default)]
+    prompt: Option<String>,
 }
 
 impl SavedConversation {
     const VERSION: &'static str = "0.1.0";
 }
 
-struct SavedConversationMetadata {
-    title: String,
-    path: PathBuf,
-    mtime: chrono::DateTime<chrono::Local>,
-}
-
-impl SavedConversationMetadata {
-    pub async fn list(fs: Arc<dyn Fs>) -> Result<Vec<Self>> {
-        fs.create_dir(&CONVERSATIONS_DIR).await?;
-
-        let mut paths = fs.read_dir(&CONVERSATIONS_DIR).await?;
-        let mut conversations = Vec::<SavedConversationMetadata>::new();
-        while let Some(path) = paths.next().await {
-            let path = path?;
-            if path.extension() != Some(OsStr::new("json")) {
-                continue;
-            }
-
-            let pattern = r" - \d+.zed.json$";
-            let re = Regex::new(pattern).unwrap();
-
-            let metadata = fs.metadata(&path).await?;
-            if let Some((file_name, metadata)) = path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .zip(metadata)
-            {
-                let title = re.replace(file_name, "");
-                conversations.push(Self {
-                    title: title.into_owned(),
-                    path,
-                    mtime: metadata.mtime.into(),
-                });
-            }
-        }
-        conversations.sort_unstable_by_key(|conversation| Reverse(conversation.mtime));
-
-        Ok(conversations)
-    }
-}
-
 pub fn init(cx: &mut AppContext) {
+    slash_command::init(cx);
+    tools::init(cx);
     assistant_panel::init(cx);
+    commit_message::init(cx);
 }
 
 #[cfg(test)]
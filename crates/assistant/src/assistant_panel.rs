@@ -1,16 +1,30 @@
 use crate::{
-    assistant_settings::{AssistantDockPosition, AssistantSettings, OpenAiModel},
+    assistant_settings::{
+        AssistantDockPosition, AssistantSettings, LanguageModelProviderKind,
+        LanguageModelProviderSettings, LanguageModelReference,
+    },
     codegen::{self, Codegen, CodegenKind},
+    completion_provider::build_completion_provider,
+    conversation_history::{
+        import_legacy_conversations, load_conversation, save_conversation,
+        SavedConversationMetadata,
+    },
+    prompt_library::{PromptLibrary, PromptVariables},
     prompts::generate_content_prompt,
-    Assist, CycleMessageRole, InlineAssist, MessageId, MessageMetadata, MessageStatus,
-    NewConversation, QuoteSelection, ResetKey, Role, SavedConversation, SavedConversationMetadata,
-    SavedMessage, Split, ToggleFocus, ToggleIncludeConversation, ToggleRetrieveContext,
+    slash_command::{parse_slash_command, SlashCommandCompletionProvider, SlashCommandRegistry},
+    tools::AssistantToolRegistry,
+    AcceptAllProposedEdits, AcceptProposedHunk, Assist, ContextSectionId, CycleMessageRole,
+    CyclePrompt, ExportConversation, ForkConversation, ImportConversation, InlineAssist, MessageId,
+    MessageMetadata, MessageStatus, NewConversation, QuoteSelection, RejectAllProposedEdits,
+    RejectProposedHunk, ResetKey, Role, SavedContextSection, SavedConversation, SavedMessage,
+    Split, ToggleFocus, ToggleIncludeConversation, ToggleRetrieveContext,
 };
-use ai::prompts::repository_context::PromptCodeSnippet;
+use ai::prompts::{context_section::PromptContextSection, repository_context::PromptCodeSnippet};
 use ai::{
     auth::ProviderCredential,
-    completion::{CompletionProvider, CompletionRequest},
-    providers::open_ai::{OpenAiCompletionProvider, OpenAiRequest, RequestMessage},
+    completion::{CompletionEvent, CompletionProvider, CompletionRequest, ToolCall},
+    models::LanguageModel,
+    providers::open_ai::{OpenAiRequest, RequestMessage, OPEN_AI_API_URL},
 };
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Local};
@@ -22,8 +36,8 @@ use editor::{
         BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle, ToDisplayPoint,
     },
     scroll::{Autoscroll, AutoscrollStrategy},
-    Anchor, Editor, EditorElement, EditorEvent, EditorStyle, MultiBufferSnapshot, ToOffset,
-    ToPoint,
+    Anchor, Editor, EditorElement, EditorEvent, EditorStyle, MultiBuffer, MultiBufferSnapshot,
+    ToOffset, ToPoint,
 };
 use fs::Fs;
 use futures::StreamExt;
@@ -31,22 +45,25 @@ use gpui::{
     canvas, div, point, relative, rems, uniform_list, Action, AnyElement, AppContext,
     AsyncAppContext, AsyncWindowContext, AvailableSpace, ClipboardItem, Context, EventEmitter,
     FocusHandle, FocusableView, FontStyle, FontWeight, HighlightStyle, InteractiveElement,
-    IntoElement, Model, ModelContext, ParentElement, Pixels, PromptLevel, Render, SharedString,
-    StatefulInteractiveElement, Styled, Subscription, Task, TextStyle, UniformListScrollHandle,
-    View, ViewContext, VisualContext, WeakModel, WeakView, WhiteSpace, WindowContext,
+    IntoElement, Model, ModelContext, ParentElement, PathPromptOptions, Pixels, PromptLevel,
+    Render, SharedString, StatefulInteractiveElement, Styled, Subscription, Task, TextStyle,
+    UniformListScrollHandle, View, ViewContext, VisualContext, WeakModel, WeakView, WhiteSpace,
+    WindowContext,
+};
+use language::{
+    language_settings::SoftWrap, Buffer, BufferId, BufferSnapshot, LanguageRegistry, Point,
+    Selection, ToOffset as _, ToPoint as _,
 };
-use language::{language_settings::SoftWrap, Buffer, BufferId, LanguageRegistry, ToOffset as _};
 use project::Project;
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use semantic_index::{SemanticIndex, SemanticIndexStatus};
-use settings::Settings;
+use settings::{Settings, SettingsStore};
 use std::{
     cell::Cell,
     cmp,
     fmt::Write,
     iter,
     ops::Range,
-    path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
     time::{Duration, Instant},
@@ -57,7 +74,7 @@ use ui::{
     utils::{DateTimeType, FormatDistance},
     ButtonLike, Tab, TabBar, Tooltip,
 };
-use util::{paths::CONVERSATIONS_DIR, post_inc, ResultExt, TryFutureExt};
+use util::{paths::HOME, post_inc, ResultExt, TryFutureExt};
 use uuid::Uuid;
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
@@ -75,6 +92,11 @@ pub fn init(cx: &mut AppContext) {
                 })
                 .register_action(AssistantPanel::inline_assist)
                 .register_action(AssistantPanel::cancel_last_inline_assist)
+                .register_action(AssistantPanel::accept_inline_hunk)
+                .register_action(AssistantPanel::reject_inline_hunk)
+                .register_action(AssistantPanel::accept_all_inline_hunks)
+                .register_action(AssistantPanel::reject_all_inline_hunks)
+                .register_action(AssistantPanel::import_conversation)
                 .register_action(ConversationEditor::quote_selection);
         },
     )
@@ -90,10 +112,14 @@ pub struct AssistantPanel {
     editors: Vec<View<ConversationEditor>>,
     saved_conversations: Vec<SavedConversationMetadata>,
     saved_conversations_scroll_handle: UniformListScrollHandle,
+    history_query_editor: View<Editor>,
+    pending_history_refresh: Task<()>,
+    prompt_library: Model<PromptLibrary>,
     zoomed: bool,
     focus_handle: FocusHandle,
     toolbar: View<Toolbar>,
     completion_provider: Arc<dyn CompletionProvider>,
+    pending_completion_provider: Task<Result<()>>,
     api_key_editor: Option<View<Editor>>,
     languages: Arc<LanguageRegistry>,
     fs: Arc<dyn Fs>,
@@ -102,14 +128,16 @@ pub struct AssistantPanel {
     pending_inline_assists: HashMap<usize, PendingInlineAssist>,
     pending_inline_assist_ids_by_editor: HashMap<WeakView<Editor>, Vec<usize>>,
     include_conversation_in_next_inline_assist: bool,
+    library_prompt_in_next_inline_assist: Option<SharedString>,
     inline_prompt_history: VecDeque<String>,
-    _watch_saved_conversations: Task<Result<()>>,
     semantic_index: Option<Model<SemanticIndex>>,
     retrieve_context_in_next_inline_assist: bool,
 }
 
 impl AssistantPanel {
     const INLINE_PROMPT_HISTORY_MAX_LEN: usize = 20;
+    /// How many of an inline assist's selections are generated at the same time.
+    const MAX_CONCURRENT_INLINE_CODEGENS: usize = 4;
 
     pub fn load(
         workspace: WeakView<Workspace>,
@@ -117,38 +145,35 @@ impl AssistantPanel {
     ) -> Task<Result<View<Self>>> {
         cx.spawn(|mut cx| async move {
             let fs = workspace.update(&mut cx, |workspace, _| workspace.app_state().fs.clone())?;
-            let saved_conversations = SavedConversationMetadata::list(fs.clone())
+            import_legacy_conversations(fs).await.log_err();
+            let saved_conversations = cx
+                .background_executor()
+                .spawn(async move { SavedConversationMetadata::list() })
                 .await
                 .log_err()
                 .unwrap_or_default();
-            // Defaulting currently to GPT4, allow for this to be set via config.
-            let completion_provider =
-                OpenAiCompletionProvider::new("gpt-4".into(), cx.background_executor().clone())
-                    .await;
+            let completion_provider = cx
+                .update(|cx| {
+                    let default_model = &AssistantSettings::get_global(cx).default_model;
+                    build_completion_provider(default_model, cx)
+                })?
+                .await;
 
             // TODO: deserialize state.
             let workspace_handle = workspace.clone();
             workspace.update(&mut cx, |workspace, cx| {
                 cx.new_view::<Self>(|cx| {
-                    const CONVERSATION_WATCH_DURATION: Duration = Duration::from_millis(100);
-                    let _watch_saved_conversations = cx.spawn(move |this, mut cx| async move {
-                        let mut events = fs
-                            .watch(&CONVERSATIONS_DIR, CONVERSATION_WATCH_DURATION)
-                            .await;
-                        while events.next().await.is_some() {
-                            let saved_conversations = SavedConversationMetadata::list(fs.clone())
-                                .await
-                                .log_err()
-                                .unwrap_or_default();
-                            this.update(&mut cx, |this, cx| {
-                                this.saved_conversations = saved_conversations;
-                                cx.notify();
-                            })
-                            .ok();
-                        }
-
-                        anyhow::Ok(())
+                    let history_query_editor = cx.new_view(|cx| {
+                        let mut editor = Editor::single_line(cx);
+                        editor.set_placeholder_text("Search conversations…", cx);
+                        editor
                     });
+                    let history_query_subscription =
+                        cx.subscribe(&history_query_editor, |this, _, event, cx| {
+                            if let EditorEvent::BufferEdited = event {
+                                this.refresh_saved_conversations(cx);
+                            }
+                        });
 
                     let toolbar = cx.new_view(|cx| {
                         let mut toolbar = Toolbar::new();
@@ -158,11 +183,28 @@ impl AssistantPanel {
                     });
 
                     let semantic_index = SemanticIndex::global(cx);
+                    let prompt_library = cx.new_model(|cx| {
+                        PromptLibrary::new(
+                            workspace.app_state().fs.clone(),
+                            workspace.project().clone(),
+                            cx,
+                        )
+                    });
 
                     let focus_handle = cx.focus_handle();
                     cx.on_focus_in(&focus_handle, Self::focus_in).detach();
                     cx.on_focus_out(&focus_handle, Self::focus_out).detach();
 
+                    let mut default_provider = Self::default_provider(cx);
+                    let settings_subscription =
+                        cx.observe_global::<SettingsStore>(move |this, cx| {
+                            let new_default_provider = Self::default_provider(cx);
+                            if new_default_provider != default_provider {
+                                default_provider = new_default_provider;
+                                this.reload_completion_provider(cx);
+                            }
+                        });
+
                     Self {
                         workspace: workspace_handle,
                         active_editor_index: Default::default(),
@@ -170,22 +212,26 @@ impl AssistantPanel {
                         editors: Default::default(),
                         saved_conversations,
                         saved_conversations_scroll_handle: Default::default(),
+                        history_query_editor,
+                        pending_history_refresh: Task::ready(()),
+                        prompt_library,
                         zoomed: false,
                         focus_handle,
                         toolbar,
-                        completion_provider: Arc::new(completion_provider),
+                        completion_provider,
+                        pending_completion_provider: Task::ready(Ok(())),
                         api_key_editor: None,
                         languages: workspace.app_state().languages.clone(),
                         fs: workspace.app_state().fs.clone(),
                         width: None,
                         height: None,
-                        subscriptions: Default::default(),
+                        subscriptions: vec![settings_subscription, history_query_subscription],
                         next_inline_assist_id: 0,
                         pending_inline_assists: Default::default(),
                         pending_inline_assist_ids_by_editor: Default::default(),
                         include_conversation_in_next_inline_assist: false,
+                        library_prompt_in_next_inline_assist: None,
                         inline_prompt_history: Default::default(),
-                        _watch_saved_conversations,
                         semantic_index,
                         retrieve_context_in_next_inline_assist: false,
                     }
@@ -194,6 +240,36 @@ impl AssistantPanel {
         })
     }
 
+    fn default_provider(
+        cx: &AppContext,
+    ) -> (LanguageModelReference, LanguageModelProviderSettings) {
+        let settings = AssistantSettings::get_global(cx);
+        let default_model = settings.default_model.clone();
+        let provider = settings.provider_for(&default_model);
+        (default_model, provider)
+    }
+
+    /// Switches to a provider for the default model after it was changed in
+    /// the settings. Existing conversations keep the model they were using.
+    fn reload_completion_provider(&mut self, cx: &mut ViewContext<Self>) {
+        let default_model = AssistantSettings::get_global(cx).default_model.clone();
+        let completion_provider = build_completion_provider(&default_model, cx);
+        self.pending_completion_provider = cx.spawn(|this, mut cx| async move {
+            let completion_provider = completion_provider.await;
+            cx.update(|cx| completion_provider.retrieve_credentials(cx))?
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.completion_provider = completion_provider;
+                if this.has_credentials() {
+                    this.api_key_editor.take();
+                } else if this.api_key_editor.is_none() {
+                    this.build_api_key_editor(cx);
+                }
+                cx.notify();
+            })
+        });
+    }
+
     fn focus_in(&mut self, cx: &mut ViewContext<Self>) {
         self.toolbar
             .update(cx, |toolbar, cx| toolbar.focus_changed(true, cx));
@@ -267,40 +343,25 @@ impl AssistantPanel {
         cx: &mut ViewContext<Self>,
         project: &Model<Project>,
     ) {
-        let selection = editor.read(cx).selections.newest_anchor().clone();
-        if selection.start.excerpt_id != selection.end.excerpt_id {
+        let (selections, newest_selection) = {
+            let selections = &editor.read(cx).selections;
+            (selections.all::<Point>(cx), selections.newest::<Point>(cx))
+        };
+        let multibuffer = editor.read(cx).buffer().clone();
+        let codegen_kinds = inline_assist_kinds(&selections, multibuffer.read(cx), cx);
+        if codegen_kinds.is_empty() {
             return;
         }
-        let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
-
-        // Extend the selection to the start and the end of the line.
-        let mut point_selection = selection.map(|selection| selection.to_point(&snapshot));
-        if point_selection.end > point_selection.start {
-            point_selection.start.column = 0;
-            // If the selection ends at the start of the line, we don't want to include it.
-            if point_selection.end.column == 0 {
-                point_selection.end.row -= 1;
-            }
-            point_selection.end.column = snapshot.line_len(point_selection.end.row);
-        }
-
-        let codegen_kind = if point_selection.start == point_selection.end {
-            CodegenKind::Generate {
-                position: snapshot.anchor_after(point_selection.start),
-            }
-        } else {
-            CodegenKind::Transform {
-                range: snapshot.anchor_before(point_selection.start)
-                    ..snapshot.anchor_after(point_selection.end),
-            }
-        };
+        let snapshot = multibuffer.read(cx).snapshot(cx);
 
         let inline_assist_id = post_inc(&mut self.next_inline_assist_id);
-        let provider = self.completion_provider.clone();
-
-        let codegen = cx.new_model(|cx| {
-            Codegen::new(editor.read(cx).buffer().clone(), codegen_kind, provider, cx)
-        });
+        let codegens = codegen_kinds
+            .into_iter()
+            .map(|codegen_kind| {
+                let provider = self.completion_provider.clone();
+                cx.new_model(|cx| Codegen::new(multibuffer.clone(), codegen_kind, provider, cx))
+            })
+            .collect::<Vec<_>>();
 
         if let Some(semantic_index) = self.semantic_index.clone() {
             let project = project.clone();
@@ -330,22 +391,32 @@ impl AssistantPanel {
                 measurements.clone(),
                 self.include_conversation_in_next_inline_assist,
                 self.inline_prompt_history.clone(),
-                codegen.clone(),
+                codegens.clone(),
                 self.workspace.clone(),
                 cx,
                 self.retrieve_context_in_next_inline_assist,
                 self.semantic_index.clone(),
                 project.clone(),
+                self.library_prompt_in_next_inline_assist.clone(),
+                self.prompt_library.clone(),
             )
         });
+        // The prompt is shown next to the newest selection.
+        let newest_range = expand_to_lines(newest_selection.range(), &snapshot);
+        let block_position = if newest_selection.reversed {
+            newest_range.start
+        } else {
+            newest_range.end
+        };
         let block_id = editor.update(cx, |editor, cx| {
             editor.change_selections(None, cx, |selections| {
-                selections.select_anchor_ranges([selection.head()..selection.head()])
+                let head = newest_selection.head();
+                selections.select_ranges([head..head])
             });
             editor.insert_blocks(
                 [BlockProperties {
                     style: BlockStyle::Flex,
-                    position: snapshot.anchor_before(point_selection.head()),
+                    position: snapshot.anchor_before(block_position),
                     height: 2,
                     render: Arc::new({
                         let inline_assistant = inline_assistant.clone();
@@ -357,7 +428,7 @@ impl AssistantPanel {
                             inline_assistant.clone().into_any_element()
                         }
                     }),
-                    disposition: if selection.reversed {
+                    disposition: if newest_selection.reversed {
                         BlockDisposition::Above
                     } else {
                         BlockDisposition::Below
@@ -368,73 +439,51 @@ impl AssistantPanel {
             )[0]
         });
 
+        let mut subscriptions = vec![
+            cx.subscribe(&inline_assistant, Self::handle_inline_assistant_event),
+            cx.subscribe(editor, {
+                let inline_assistant = inline_assistant.downgrade();
+                move |_, editor, event, cx| {
+                    if let Some(inline_assistant) = inline_assistant.upgrade() {
+                        if let EditorEvent::SelectionsChanged { local } = event {
+                            if *local && inline_assistant.focus_handle(cx).contains_focused(cx) {
+                                cx.focus_view(&editor);
+                            }
+                        }
+                    }
+                }
+            }),
+        ];
+        for codegen in &codegens {
+            subscriptions.push(cx.observe(codegen, {
+                let editor = editor.downgrade();
+                move |this, _, cx| {
+                    if let Some(editor) = editor.upgrade() {
+                        this.update_highlights_for_editor(&editor, cx);
+                    }
+                }
+            }));
+            subscriptions.push(cx.subscribe(
+                codegen,
+                move |this, codegen, event, cx| match event {
+                    codegen::Event::Undone => {
+                        this.remove_inline_codegen(inline_assist_id, &codegen, cx)
+                    }
+                    codegen::Event::Finished => {
+                        this.handle_inline_codegen_finished(inline_assist_id, cx)
+                    }
+                },
+            ));
+        }
         self.pending_inline_assists.insert(
             inline_assist_id,
             PendingInlineAssist {
                 editor: editor.downgrade(),
                 inline_assistant: Some((block_id, inline_assistant.clone())),
-                codegen: codegen.clone(),
+                codegens,
+                queued_requests: VecDeque::new(),
                 project: project.downgrade(),
-                _subscriptions: vec![
-                    cx.subscribe(&inline_assistant, Self::handle_inline_assistant_event),
-                    cx.subscribe(editor, {
-                        let inline_assistant = inline_assistant.downgrade();
-                        move |_, editor, event, cx| {
-                            if let Some(inline_assistant) = inline_assistant.upgrade() {
-                                if let EditorEvent::SelectionsChanged { local } = event {
-                                    if *local
-                                        && inline_assistant.focus_handle(cx).contains_focused(cx)
-                                    {
-                                        cx.focus_view(&editor);
-                                    }
-                                }
-                            }
-                        }
-                    }),
-                    cx.observe(&codegen, {
-                        let editor = editor.downgrade();
-                        move |this, _, cx| {
-                            if let Some(editor) = editor.upgrade() {
-                                this.update_highlights_for_editor(&editor, cx);
-                            }
-                        }
-                    }),
-                    cx.subscribe(&codegen, move |this, codegen, event, cx| match event {
-                        codegen::Event::Undone => {
-                            this.finish_inline_assist(inline_assist_id, false, cx)
-                        }
-                        codegen::Event::Finished => {
-                            let pending_assist = if let Some(pending_assist) =
-                                this.pending_inline_assists.get(&inline_assist_id)
-                            {
-                                pending_assist
-                            } else {
-                                return;
-                            };
-
-                            let error = codegen
-                                .read(cx)
-                                .error()
-                                .map(|error| format!("Inline assistant error: {}", error));
-                            if let Some(error) = error {
-                                if pending_assist.inline_assistant.is_none() {
-                                    if let Some(workspace) = this.workspace.upgrade() {
-                                        workspace.update(cx, |workspace, cx| {
-                                            workspace.show_toast(
-                                                Toast::new(inline_assist_id, error),
-                                                cx,
-                                            );
-                                        })
-                                    }
-
-                                    this.finish_inline_assist(inline_assist_id, false, cx);
-                                }
-                            } else {
-                                this.finish_inline_assist(inline_assist_id, false, cx);
-                            }
-                        }
-                    }),
-                ],
+                _subscriptions: subscriptions,
             },
         );
         self.pending_inline_assist_ids_by_editor
@@ -444,6 +493,197 @@ impl AssistantPanel {
         self.update_highlights_for_editor(&editor, cx);
     }
 
+    fn handle_inline_codegen_finished(
+        &mut self,
+        inline_assist_id: usize,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.start_queued_inline_codegens(inline_assist_id, cx);
+        let Some(pending_assist) = self.pending_inline_assists.get(&inline_assist_id) else {
+            return;
+        };
+        if !pending_assist.queued_requests.is_empty()
+            || pending_assist
+                .codegens
+                .iter()
+                .any(|codegen| !codegen.read(cx).idle())
+        {
+            return;
+        }
+
+        let error = pending_assist.codegens.iter().find_map(|codegen| {
+            codegen
+                .read(cx)
+                .error()
+                .map(|error| format!("Inline assistant error: {}", error))
+        });
+        if let Some(error) = error {
+            if pending_assist.inline_assistant.is_none() {
+                if let Some(workspace) = self.workspace.upgrade() {
+                    workspace.update(cx, |workspace, cx| {
+                        workspace.show_toast(Toast::new(inline_assist_id, error), cx);
+                    })
+                }
+
+                self.finish_inline_assist(inline_assist_id, false, cx);
+            }
+        } else if pending_assist.codegens.len() == 1 {
+            self.finish_inline_assist(inline_assist_id, false, cx);
+        }
+        // Assists over several selections stay pending once they're done, so
+        // that each of their hunks can be accepted or rejected.
+    }
+
+    /// Stops tracking one of an inline assist's code generations, finishing the
+    /// assist once none are left.
+    fn remove_inline_codegen(
+        &mut self,
+        inline_assist_id: usize,
+        codegen: &Model<Codegen>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(pending_assist) = self.pending_inline_assists.get_mut(&inline_assist_id) else {
+            return;
+        };
+        pending_assist
+            .codegens
+            .retain(|pending_codegen| pending_codegen != codegen);
+        if pending_assist.codegens.is_empty() {
+            self.finish_inline_assist(inline_assist_id, false, cx);
+        } else if let Some(editor) = pending_assist.editor.upgrade() {
+            self.update_highlights_for_editor(&editor, cx);
+        }
+    }
+
+    fn accept_inline_hunk(
+        workspace: &mut Workspace,
+        _: &AcceptProposedHunk,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        Self::review_inline_hunk(workspace, true, cx);
+    }
+
+    fn reject_inline_hunk(
+        workspace: &mut Workspace,
+        _: &RejectProposedHunk,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        Self::review_inline_hunk(workspace, false, cx);
+    }
+
+    /// Keeps or undoes the edits of the inline assist hunk under the cursor.
+    fn review_inline_hunk(
+        workspace: &mut Workspace,
+        accept: bool,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(panel) = workspace.panel::<AssistantPanel>(cx) else {
+            cx.propagate();
+            return;
+        };
+        let Some(editor) = workspace
+            .active_item(cx)
+            .and_then(|item| item.act_as::<Editor>(cx))
+        else {
+            cx.propagate();
+            return;
+        };
+
+        let handled = panel.update(cx, |panel, cx| {
+            let Some((inline_assist_id, codegen)) = panel.inline_hunk_at_cursor(&editor, cx) else {
+                return false;
+            };
+            if !accept {
+                codegen.update(cx, |codegen, cx| codegen.undo(cx));
+            }
+            panel.remove_inline_codegen(inline_assist_id, &codegen, cx);
+            true
+        });
+        if !handled {
+            cx.propagate();
+        }
+    }
+
+    fn accept_all_inline_hunks(
+        workspace: &mut Workspace,
+        _: &AcceptAllProposedEdits,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        Self::review_all_inline_hunks(workspace, true, cx);
+    }
+
+    fn reject_all_inline_hunks(
+        workspace: &mut Workspace,
+        _: &RejectAllProposedEdits,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        Self::review_all_inline_hunks(workspace, false, cx);
+    }
+
+    /// Keeps or undoes the edits of every inline assist in the active editor.
+    fn review_all_inline_hunks(
+        workspace: &mut Workspace,
+        accept: bool,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(panel) = workspace.panel::<AssistantPanel>(cx) else {
+            cx.propagate();
+            return;
+        };
+        let Some(editor) = workspace
+            .active_item(cx)
+            .and_then(|item| item.act_as::<Editor>(cx))
+        else {
+            cx.propagate();
+            return;
+        };
+
+        let handled = panel.update(cx, |panel, cx| {
+            let Some(inline_assist_ids) = panel
+                .pending_inline_assist_ids_by_editor
+                .get(&editor.downgrade())
+                .cloned()
+            else {
+                return false;
+            };
+            for inline_assist_id in inline_assist_ids {
+                panel.finish_inline_assist(inline_assist_id, !accept, cx);
+            }
+            true
+        });
+        if !handled {
+            cx.propagate();
+        }
+    }
+
+    /// The finished inline assist hunk that contains the editor's newest cursor.
+    fn inline_hunk_at_cursor(
+        &self,
+        editor: &View<Editor>,
+        cx: &AppContext,
+    ) -> Option<(usize, Model<Codegen>)> {
+        let inline_assist_ids = self
+            .pending_inline_assist_ids_by_editor
+            .get(&editor.downgrade())?;
+        let editor = editor.read(cx);
+        let cursor = editor.selections.newest_anchor().head();
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        inline_assist_ids.iter().rev().find_map(|inline_assist_id| {
+            let pending_assist = self.pending_inline_assists.get(inline_assist_id)?;
+            let codegen = pending_assist.codegens.iter().find(|codegen| {
+                if pending_assist.is_queued(codegen) {
+                    return false;
+                }
+                let codegen = codegen.read(cx);
+                let range = codegen.range();
+                codegen.idle()
+                    && range.start.cmp(&cursor, &snapshot).is_le()
+                    && range.end.cmp(&cursor, &snapshot).is_ge()
+            })?;
+            Some((*inline_assist_id, codegen.clone()))
+        })
+    }
+
     fn handle_inline_assistant_event(
         &mut self,
         inline_assistant: View<InlineAssistant>,
@@ -456,6 +696,7 @@ impl AssistantPanel {
                 prompt,
                 include_conversation,
                 retrieve_context,
+                library_prompt,
             } => {
                 self.confirm_inline_assist(
                     assist_id,
@@ -463,6 +704,7 @@ impl AssistantPanel {
                     *include_conversation,
                     cx,
                     *retrieve_context,
+                    library_prompt.clone(),
                 );
             }
             InlineAssistantEvent::Canceled => {
@@ -479,6 +721,9 @@ impl AssistantPanel {
             InlineAssistantEvent::RetrieveContextToggled { retrieve_context } => {
                 self.retrieve_context_in_next_inline_assist = *retrieve_context
             }
+            InlineAssistantEvent::LibraryPromptChanged { library_prompt } => {
+                self.library_prompt_in_next_inline_assist = library_prompt.clone();
+            }
         }
     }
 
@@ -531,9 +776,9 @@ impl AssistantPanel {
                 self.update_highlights_for_editor(&editor, cx);
 
                 if undo {
-                    pending_assist
-                        .codegen
-                        .update(cx, |codegen, cx| codegen.undo(cx));
+                    for codegen in &pending_assist.codegens {
+                        codegen.update(cx, |codegen, cx| codegen.undo(cx));
+                    }
                 }
             }
         }
@@ -561,6 +806,7 @@ impl AssistantPanel {
         include_conversation: bool,
         cx: &mut ViewContext<Self>,
         retrieve_context: bool,
+        library_prompt: Option<SharedString>,
     ) {
         let conversation = if include_conversation {
             self.active_editor()
@@ -603,48 +849,73 @@ impl AssistantPanel {
             self.inline_prompt_history.pop_front();
         }
 
-        let codegen = pending_assist.codegen.clone();
         let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
-        let range = codegen.read(cx).range();
-        let start = snapshot.point_to_buffer_offset(range.start);
-        let end = snapshot.point_to_buffer_offset(range.end);
-        let (buffer, range) = if let Some((start, end)) = start.zip(end) {
-            let (start_buffer, start_buffer_offset) = start;
-            let (end_buffer, end_buffer_offset) = end;
-            if start_buffer.remote_id() == end_buffer.remote_id() {
-                (start_buffer.clone(), start_buffer_offset..end_buffer_offset)
-            } else {
-                self.finish_inline_assist(inline_assist_id, false, cx);
-                return;
+        let prompt_library = self.prompt_library.read(cx);
+        let project_rules = prompt_library.rules_for(library_prompt.as_deref());
+
+        // Each selection is transformed with its own request, built from the
+        // buffer that contains it.
+        let mut targets = Vec::new();
+        for codegen in &pending_assist.codegens {
+            let range = codegen.read(cx).range();
+            let start = snapshot.point_to_buffer_offset(range.start);
+            let end = snapshot.point_to_buffer_offset(range.end);
+            let Some(((start_buffer, start), (end_buffer, end))) = start.zip(end) else {
+                continue;
+            };
+            if start_buffer.remote_id() != end_buffer.remote_id() {
+                continue;
             }
-        } else {
-            self.finish_inline_assist(inline_assist_id, false, cx);
-            return;
-        };
+            let buffer = start_buffer.clone();
+            let range = start..end;
 
-        let language = buffer.language_at(range.start);
-        let language_name = if let Some(language) = language.as_ref() {
-            if Arc::ptr_eq(language, &language::PLAIN_TEXT) {
-                None
+            let language = buffer.language_at(range.start);
+            let language_name = if let Some(language) = language.as_ref() {
+                if Arc::ptr_eq(language, &language::PLAIN_TEXT) {
+                    None
+                } else {
+                    Some(language.name())
+                }
             } else {
-                Some(language.name())
-            }
-        } else {
-            None
-        };
+                None
+            };
 
-        // Higher Temperature increases the randomness of model outputs.
-        // If Markdown or No Language is Known, increase the randomness for more creative output
-        // If Code, decrease temperature to get more deterministic outputs
-        let temperature = if let Some(language) = language_name.clone() {
-            if language.to_string() != "Markdown".to_string() {
-                0.5
+            let variables = PromptVariables {
+                language: language_name.as_ref().map(|name| name.to_string()),
+                file_path: buffer
+                    .file()
+                    .map(|file| file.full_path(cx).to_string_lossy().into_owned()),
+                selection: (!range.is_empty())
+                    .then(|| buffer.text_for_range(range.clone()).collect::<String>()),
+            };
+            let preamble = prompt_library.render(library_prompt.as_deref(), &variables);
+
+            // Higher Temperature increases the randomness of model outputs.
+            // If Markdown or No Language is Known, increase the randomness for more creative output
+            // If Code, decrease temperature to get more deterministic outputs
+            let temperature = if let Some(language) = language_name.clone() {
+                if language.to_string() != "Markdown".to_string() {
+                    0.5
+                } else {
+                    1.0
+                }
             } else {
                 1.0
-            }
-        } else {
-            1.0
-        };
+            };
+
+            targets.push(InlineAssistTarget {
+                codegen: codegen.clone(),
+                buffer,
+                range,
+                language_name: language_name.map(|name| name.to_string()),
+                preamble,
+                temperature,
+            });
+        }
+        if targets.is_empty() {
+            self.finish_inline_assist(inline_assist_id, false, cx);
+            return;
+        }
 
         let user_prompt = user_prompt.to_string();
 
@@ -680,27 +951,37 @@ impl AssistantPanel {
             Task::ready(Ok(Vec::new()))
         };
 
-        let mut model = AssistantSettings::get_global(cx)
-            .default_open_ai_model
-            .clone();
-        let model_name = model.full_name();
+        let mut model = AssistantSettings::get_global(cx).default_model.clone();
+        let language_model: Arc<dyn LanguageModel> = self.completion_provider.base_model().into();
 
-        let prompt = cx.background_executor().spawn(async move {
+        let prompts = cx.background_executor().spawn(async move {
             let snippets = snippets.await?;
-
-            let language_name = language_name.as_deref();
-            generate_content_prompt(
-                user_prompt,
-                language_name,
-                buffer,
-                range,
-                snippets,
-                model_name,
-                project_name,
-            )
+            targets
+                .into_iter()
+                .map(|target| {
+                    let prompt = generate_content_prompt(
+                        user_prompt.clone(),
+                        target.preamble,
+                        target.language_name.as_deref(),
+                        target.buffer,
+                        target.range,
+                        snippets.clone(),
+                        language_model.clone(),
+                        project_name.clone(),
+                    )?;
+                    anyhow::Ok((target.codegen, prompt, target.temperature))
+                })
+                .collect::<Result<Vec<_>>>()
         });
 
         let mut messages = Vec::new();
+        if let Some(project_rules) = project_rules {
+            messages.push(RequestMessage {
+                role: Role::System,
+                content: project_rules,
+                ..Default::default()
+            });
+        }
         if let Some(conversation) = conversation {
             let conversation = conversation.read(cx);
             let buffer = conversation.buffer.read(cx);
@@ -709,32 +990,78 @@ impl AssistantPanel {
                     .messages(cx)
                     .map(|message| message.to_open_ai_message(buffer)),
             );
-            model = conversation.model.clone();
+            // The inline assist's completion provider was created for the
+            // default model, so it can't talk to another provider's models.
+            if conversation.model.provider == model.provider {
+                model = conversation.model.clone();
+            }
         }
 
-        cx.spawn(|_, mut cx| async move {
+        cx.spawn(|this, mut cx| async move {
             // I Don't know if we want to return a ? here.
-            let prompt = prompt.await?;
+            let prompts = prompts.await?;
 
-            messages.push(RequestMessage {
-                role: Role::User,
-                content: prompt,
-            });
+            // Every selection streams its own completion, so that several of
+            // them are generated concurrently.
+            let requests = prompts
+                .into_iter()
+                .map(|(codegen, prompt, temperature)| {
+                    let mut messages = messages.clone();
+                    messages.push(RequestMessage {
+                        role: Role::User,
+                        content: prompt,
+                        ..Default::default()
+                    });
 
-            let request = Box::new(OpenAiRequest {
-                model: model.full_name().into(),
-                messages,
-                stream: true,
-                stop: vec!["|END|>".to_string()],
-                temperature,
-            });
+                    let request: Box<dyn CompletionRequest> = Box::new(OpenAiRequest {
+                        model: model.model.clone(),
+                        messages,
+                        stream: true,
+                        stop: vec!["|END|>".to_string()],
+                        temperature,
+                        tools: Vec::new(),
+                    });
+                    (codegen, request)
+                })
+                .collect::<VecDeque<_>>();
 
-            codegen.update(&mut cx, |codegen, cx| codegen.start(request, cx))?;
-            anyhow::Ok(())
+            this.update(&mut cx, |this, cx| {
+                if let Some(pending_assist) = this.pending_inline_assists.get_mut(&inline_assist_id)
+                {
+                    pending_assist.queued_requests = requests;
+                    this.start_queued_inline_codegens(inline_assist_id, cx);
+                }
+            })
         })
         .detach();
     }
 
+    /// Starts generating an inline assist's queued selections, without running
+    /// more than [`Self::MAX_CONCURRENT_INLINE_CODEGENS`] of them at a time.
+    fn start_queued_inline_codegens(
+        &mut self,
+        inline_assist_id: usize,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(pending_assist) = self.pending_inline_assists.get_mut(&inline_assist_id) else {
+            return;
+        };
+        let mut running = pending_assist
+            .codegens
+            .iter()
+            .filter(|codegen| !codegen.read(cx).idle())
+            .count();
+        while running < Self::MAX_CONCURRENT_INLINE_CODEGENS {
+            let Some((codegen, request)) = pending_assist.queued_requests.pop_front() else {
+                break;
+            };
+            if pending_assist.codegens.contains(&codegen) {
+                codegen.update(cx, |codegen, cx| codegen.start(request, cx));
+                running += 1;
+            }
+        }
+    }
+
     fn update_highlights_for_editor(&self, editor: &View<Editor>, cx: &mut ViewContext<Self>) {
         let mut background_ranges = Vec::new();
         let mut foreground_ranges = Vec::new();
@@ -746,9 +1073,11 @@ impl AssistantPanel {
 
         for inline_assist_id in inline_assist_ids {
             if let Some(pending_assist) = self.pending_inline_assists.get(inline_assist_id) {
-                let codegen = pending_assist.codegen.read(cx);
-                background_ranges.push(codegen.range());
-                foreground_ranges.extend(codegen.last_equal_ranges().iter().cloned());
+                for codegen in &pending_assist.codegens {
+                    let codegen = codegen.read(cx);
+                    background_ranges.push(codegen.range());
+                    foreground_ranges.extend(codegen.last_equal_ranges().iter().cloned());
+                }
             }
         }
 
@@ -792,6 +1121,7 @@ impl AssistantPanel {
                 self.languages.clone(),
                 self.fs.clone(),
                 self.workspace.clone(),
+                self.prompt_library.clone(),
                 cx,
             )
         });
@@ -827,11 +1157,30 @@ impl AssistantPanel {
             self.toolbar.update(cx, |toolbar, cx| {
                 toolbar.set_active_item(None, cx);
             });
+            self.refresh_saved_conversations(cx);
         }
 
         cx.notify();
     }
 
+    /// Reloads the conversation history, keeping only the conversations that
+    /// match the history's search query.
+    fn refresh_saved_conversations(&mut self, cx: &mut ViewContext<Self>) {
+        let query = self.history_query_editor.read(cx).text(cx);
+        let saved_conversations = cx
+            .background_executor()
+            .spawn(async move { SavedConversationMetadata::search(&query) });
+        self.pending_history_refresh = cx.spawn(|this, mut cx| async move {
+            if let Some(saved_conversations) = saved_conversations.await.log_err() {
+                this.update(&mut cx, |this, cx| {
+                    this.saved_conversations = saved_conversations;
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+    }
+
     fn handle_conversation_editor_event(
         &mut self,
         _: View<ConversationEditor>,
@@ -840,6 +1189,9 @@ impl AssistantPanel {
     ) {
         match event {
             ConversationEditorEvent::TabContentChanged => cx.notify(),
+            ConversationEditorEvent::Forked(id) => self
+                .open_conversation(id.clone(), cx)
+                .detach_and_log_err(cx),
         }
     }
 
@@ -1066,32 +1418,47 @@ impl AssistantPanel {
         cx: &mut ViewContext<Self>,
     ) -> impl IntoElement {
         let conversation = &self.saved_conversations[index];
-        let path = conversation.path.clone();
+        let id = conversation.id.clone();
 
         ButtonLike::new(index)
             .on_click(cx.listener(move |this, _, cx| {
-                this.open_conversation(path.clone(), cx)
+                this.open_conversation(id.clone(), cx)
                     .detach_and_log_err(cx)
             }))
             .full_width()
             .child(
-                div()
-                    .flex()
+                v_flex()
                     .w_full()
-                    .gap_2()
                     .child(
-                        Label::new(conversation.mtime.format("%F %I:%M%p").to_string())
-                            .color(Color::Muted)
-                            .size(LabelSize::Small),
+                        h_flex()
+                            .w_full()
+                            .gap_2()
+                            .child(
+                                Label::new(conversation.mtime.format("%F %I:%M%p").to_string())
+                                    .color(Color::Muted)
+                                    .size(LabelSize::Small),
+                            )
+                            .child(Label::new(conversation.title.clone()).size(LabelSize::Small))
+                            .when(conversation.forked_from.is_some(), |this| {
+                                this.child(
+                                    Label::new("fork")
+                                        .color(Color::Muted)
+                                        .size(LabelSize::XSmall),
+                                )
+                            }),
                     )
-                    .child(Label::new(conversation.title.clone()).size(LabelSize::Small)),
+                    .children(conversation.matching_excerpt.clone().map(|excerpt| {
+                        Label::new(excerpt)
+                            .color(Color::Muted)
+                            .size(LabelSize::XSmall)
+                    })),
             )
     }
 
-    fn open_conversation(&mut self, path: PathBuf, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+    fn open_conversation(&mut self, id: String, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
         cx.focus(&self.focus_handle);
 
-        if let Some(ix) = self.editor_index_for_path(&path, cx) {
+        if let Some(ix) = self.editor_index_for_id(&id, cx) {
             self.set_active_editor_index(Some(ix), cx);
             return Task::ready(Ok(()));
         }
@@ -1099,33 +1466,78 @@ impl AssistantPanel {
         let fs = self.fs.clone();
         let workspace = self.workspace.clone();
         let languages = self.languages.clone();
+        let prompt_library = self.prompt_library.clone();
         cx.spawn(|this, mut cx| async move {
-            let saved_conversation = fs.load(&path).await?;
-            let saved_conversation = serde_json::from_str(&saved_conversation)?;
+            let saved_conversation = cx
+                .background_executor()
+                .spawn({
+                    let id = id.clone();
+                    async move { load_conversation(&id) }
+                })
+                .await?;
             let conversation =
-                Conversation::deserialize(saved_conversation, path.clone(), languages, &mut cx)
-                    .await?;
+                Conversation::deserialize(saved_conversation, languages, &mut cx).await?;
 
             this.update(&mut cx, |this, cx| {
                 // If, by the time we've loaded the conversation, the user has already opened
                 // the same conversation, we don't want to open it again.
-                if let Some(ix) = this.editor_index_for_path(&path, cx) {
+                if let Some(ix) = this.editor_index_for_id(&id, cx) {
                     this.set_active_editor_index(Some(ix), cx);
                 } else {
                     let editor = cx.new_view(|cx| {
-                        ConversationEditor::for_conversation(conversation, fs, workspace, cx)
+                        ConversationEditor::for_conversation(
+                            conversation,
+                            fs,
+                            workspace,
+                            prompt_library,
+                            cx,
+                        )
                     });
                     this.add_conversation(editor, cx);
                 }
             })?;
             Ok(())
         })
-    }
-
-    fn editor_index_for_path(&self, path: &Path, cx: &AppContext) -> Option<usize> {
-        self.editors
-            .iter()
-            .position(|editor| editor.read(cx).conversation.read(cx).path.as_deref() == Some(path))
+    }
+
+    fn editor_index_for_id(&self, id: &str, cx: &AppContext) -> Option<usize> {
+        self.editors
+            .iter()
+            .position(|editor| editor.read(cx).conversation.read(cx).id.as_deref() == Some(id))
+    }
+
+    fn import_conversation(
+        workspace: &mut Workspace,
+        _: &ImportConversation,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(assistant) = workspace.panel::<AssistantPanel>(cx) else {
+            return;
+        };
+        let fs = workspace.app_state().fs.clone();
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            let Some(path) = paths.await?.and_then(|paths| paths.into_iter().next()) else {
+                return Ok(());
+            };
+            let markdown = fs.load(&path).await?;
+            let model = cx.update(|cx| AssistantSettings::get_global(cx).default_model.clone())?;
+            let conversation = SavedConversation::from_markdown(&markdown, &model)?;
+            save_conversation(&conversation, Local::now()).await?;
+
+            let id = conversation.id.unwrap_or_default();
+            workspace.update(&mut cx, |workspace, cx| {
+                workspace.focus_panel::<AssistantPanel>(cx);
+            })?;
+            assistant
+                .update(&mut cx, |assistant, cx| assistant.open_conversation(id, cx))?
+                .await
+        })
+        .detach_and_log_err(cx);
     }
 
     fn has_credentials(&mut self) -> bool {
@@ -1156,7 +1568,7 @@ fn build_api_key_editor(cx: &mut WindowContext) -> View<Editor> {
 impl Render for AssistantPanel {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         if let Some(api_key_editor) = self.api_key_editor.clone() {
-            const INSTRUCTIONS: [&'static str; 6] = [
+            const OPEN_AI_INSTRUCTIONS: [&'static str; 6] = [
                 "To use the assistant panel or inline assistant, you need to add your OpenAI API key.",
                 " - You can create an API key at: platform.openai.com/api-keys",
                 " - Make sure your OpenAI account has credits",
@@ -1165,13 +1577,40 @@ impl Render for AssistantPanel {
                 "Paste your OpenAI API key and press Enter to use the assistant:"
             ];
 
+            let (default_model, provider) = Self::default_provider(cx);
+            let instructions: Vec<SharedString> = if provider.kind
+                == LanguageModelProviderKind::OpenAi
+                && provider.api_url == OPEN_AI_API_URL
+            {
+                OPEN_AI_INSTRUCTIONS
+                    .iter()
+                    .map(|instruction| (*instruction).into())
+                    .collect()
+            } else {
+                vec![
+                    format!(
+                        "To use {} with the assistant panel or inline assistant, you need to add an API key for {}.",
+                        default_model.model, provider.api_url
+                    )
+                    .into(),
+                    " ".into(),
+                    format!(
+                        "Paste your {} API key and press Enter to use the assistant:",
+                        provider.name
+                    )
+                    .into(),
+                ]
+            };
+
             v_flex()
                 .p_4()
                 .size_full()
                 .on_action(cx.listener(AssistantPanel::save_credentials))
                 .track_focus(&self.focus_handle)
                 .children(
-                    INSTRUCTIONS.map(|instruction| Label::new(instruction).size(LabelSize::Small)),
+                    instructions
+                        .into_iter()
+                        .map(|instruction| Label::new(instruction).size(LabelSize::Small)),
                 )
                 .child(
                     h_flex()
@@ -1264,7 +1703,7 @@ impl Render for AssistantPanel {
                             let view = cx.view().clone();
                             let scroll_handle = self.saved_conversations_scroll_handle.clone();
                             let conversation_count = self.saved_conversations.len();
-                            canvas(move |bounds, cx| {
+                            let history = canvas(move |bounds, cx| {
                                 uniform_list(
                                     view,
                                     "saved_conversations",
@@ -1283,8 +1722,20 @@ impl Render for AssistantPanel {
                                     cx,
                                 );
                             })
-                            .size_full()
-                            .into_any_element()
+                            .size_full();
+                            v_flex()
+                                .size_full()
+                                .child(
+                                    h_flex()
+                                        .w_full()
+                                        .px_2()
+                                        .py_1()
+                                        .border_b_1()
+                                        .border_color(cx.theme().colors().border_variant)
+                                        .child(self.history_query_editor.clone()),
+                                )
+                                .child(div().flex_1().child(history))
+                                .into_any_element()
                         }),
                 )
         }
@@ -1388,6 +1839,8 @@ enum ConversationEvent {
     MessagesEdited,
     SummaryChanged,
     StreamedCompletion,
+    ContextSectionUpdated(ContextSectionId),
+    ToolCallsRequested(MessageId),
 }
 
 #[derive(Default)]
@@ -1396,6 +1849,36 @@ struct Summary {
     done: bool,
 }
 
+#[derive(Clone, Debug)]
+enum ContextSectionStatus {
+    Pending,
+    Done,
+    Error(SharedString),
+}
+
+/// The output of a slash command, which is inserted into the conversation's
+/// buffer below the line containing the command.
+#[derive(Clone, Debug)]
+struct ContextSection {
+    id: ContextSectionId,
+    command_range: Range<language::Anchor>,
+    output_range: Option<Range<language::Anchor>>,
+    name: String,
+    argument: Option<String>,
+    title: String,
+    status: ContextSectionStatus,
+    token_count: Option<usize>,
+}
+
+impl ContextSection {
+    /// The range that is folded when the section is collapsed, which starts at
+    /// the end of the command's line.
+    fn fold_range(&self, buffer: &Buffer) -> Option<Range<usize>> {
+        let output_range = self.output_range.as_ref()?;
+        Some(self.command_range.end.to_offset(buffer)..output_range.end.to_offset(buffer))
+    }
+}
+
 struct Conversation {
     id: Option<String>,
     buffer: Model<Buffer>,
@@ -1406,14 +1889,24 @@ struct Conversation {
     pending_summary: Task<Option<()>>,
     completion_count: usize,
     pending_completions: Vec<PendingCompletion>,
-    model: OpenAiModel,
+    model: LanguageModelReference,
     token_count: Option<usize>,
     max_token_count: usize,
     pending_token_count: Task<Option<()>>,
     pending_save: Task<Result<()>>,
-    path: Option<PathBuf>,
+    forked_from: Option<String>,
+    /// The name of the prompt from the prompt library to use as the system prompt.
+    prompt: Option<SharedString>,
+    /// The system message sent with each request, rendered from the prompt and
+    /// the project's rules when the conversation was last submitted.
+    system_message: Option<String>,
     _subscriptions: Vec<Subscription>,
     completion_provider: Arc<dyn CompletionProvider>,
+    pending_completion_provider: Task<Option<()>>,
+    context_sections: Vec<ContextSection>,
+    next_context_section_id: ContextSectionId,
+    pending_context_sections: HashMap<ContextSectionId, Task<()>>,
+    pending_tool_calls: HashMap<String, Task<()>>,
 }
 
 impl EventEmitter<ConversationEvent> for Conversation {}
@@ -1439,8 +1932,7 @@ impl Conversation {
             buffer
         });
 
-        let settings = AssistantSettings::get_global(cx);
-        let model = settings.default_open_ai_model.clone();
+        let model = AssistantSettings::get_global(cx).default_model.clone();
 
         let mut this = Self {
             id: Some(Uuid::new_v4().to_string()),
@@ -1452,14 +1944,21 @@ impl Conversation {
             completion_count: Default::default(),
             pending_completions: Default::default(),
             token_count: None,
-            max_token_count: tiktoken_rs::model::get_context_size(&model.full_name()),
+            max_token_count: Self::max_token_count(completion_provider.as_ref()),
             pending_token_count: Task::ready(None),
-            model: model.clone(),
+            model,
             _subscriptions: vec![cx.subscribe(&buffer, Self::handle_buffer_event)],
             pending_save: Task::ready(Ok(())),
-            path: None,
+            forked_from: None,
+            prompt: None,
+            system_message: None,
             buffer,
             completion_provider,
+            pending_completion_provider: Task::ready(None),
+            context_sections: Vec::new(),
+            next_context_section_id: Default::default(),
+            pending_context_sections: Default::default(),
+            pending_tool_calls: Default::default(),
         };
         let message = MessageAnchor {
             id: MessageId(post_inc(&mut this.next_message_id.0)),
@@ -1472,6 +1971,8 @@ impl Conversation {
                 role: Role::User,
                 sent_at: Local::now(),
                 status: MessageStatus::Done,
+                tool_calls: Vec::new(),
+                tool_call_id: None,
             },
         );
 
@@ -1498,13 +1999,33 @@ impl Conversation {
                 .as_ref()
                 .map(|summary| summary.text.clone())
                 .unwrap_or_default(),
-            model: self.model.clone(),
+            model: self.model.model.clone(),
+            provider: Some(self.model.provider.clone()),
+            context_sections: self
+                .context_sections
+                .iter()
+                .filter_map(|section| {
+                    let buffer = self.buffer.read(cx);
+                    let output_range = section.output_range.as_ref()?;
+                    Some(SavedContextSection {
+                        id: section.id,
+                        command_range: section.command_range.start.to_offset(buffer)
+                            ..section.command_range.end.to_offset(buffer),
+                        output_range: output_range.start.to_offset(buffer)
+                            ..output_range.end.to_offset(buffer),
+                        name: section.name.clone(),
+                        argument: section.argument.clone(),
+                        title: section.title.clone(),
+                    })
+                })
+                .collect(),
+            forked_from: self.forked_from.clone(),
+            prompt: self.prompt.as_ref().map(|prompt| prompt.to_string()),
         }
     }
 
     async fn deserialize(
         saved_conversation: SavedConversation,
-        path: PathBuf,
         language_registry: Arc<LanguageRegistry>,
         cx: &mut AsyncAppContext,
     ) -> Result<Model<Self>> {
@@ -1512,20 +2033,19 @@ impl Conversation {
             Some(id) => Some(id),
             None => Some(Uuid::new_v4().to_string()),
         };
-        let model = saved_conversation.model;
-        let completion_provider: Arc<dyn CompletionProvider> = Arc::new(
-            OpenAiCompletionProvider::new(
-                model.full_name().into(),
-                cx.background_executor().clone(),
-            )
-            .await,
-        );
+        let model = cx.update(|cx| {
+            AssistantSettings::get_global(cx)
+                .resolve_model(saved_conversation.provider, saved_conversation.model)
+        })?;
+        let completion_provider = cx.update(|cx| build_completion_provider(&model, cx))?.await;
         cx.update(|cx| completion_provider.retrieve_credentials(cx))?
             .await;
 
         let markdown = language_registry.language_for_name("Markdown");
         let mut message_anchors = Vec::new();
         let mut next_message_id = MessageId(0);
+        let mut context_sections = Vec::new();
+        let mut next_context_section_id = ContextSectionId(0);
         let buffer = cx.new_model(|cx| {
             let mut buffer = Buffer::new(
                 0,
@@ -1539,6 +2059,24 @@ impl Conversation {
                 });
                 next_message_id = cmp::max(next_message_id, MessageId(message.id.0 + 1));
             }
+            for section in saved_conversation.context_sections {
+                context_sections.push(ContextSection {
+                    id: section.id,
+                    command_range: buffer.anchor_after(section.command_range.start)
+                        ..buffer.anchor_before(section.command_range.end),
+                    output_range: Some(
+                        buffer.anchor_after(section.output_range.start)
+                            ..buffer.anchor_before(section.output_range.end),
+                    ),
+                    name: section.name,
+                    argument: section.argument,
+                    title: section.title,
+                    status: ContextSectionStatus::Done,
+                    token_count: None,
+                });
+                next_context_section_id =
+                    cmp::max(next_context_section_id, ContextSectionId(section.id.0 + 1));
+            }
             buffer.set_language_registry(language_registry);
             cx.spawn(|buffer, mut cx| async move {
                 let markdown = markdown.await?;
@@ -1565,14 +2103,21 @@ impl Conversation {
                 completion_count: Default::default(),
                 pending_completions: Default::default(),
                 token_count: None,
-                max_token_count: tiktoken_rs::model::get_context_size(&model.full_name()),
+                max_token_count: Self::max_token_count(completion_provider.as_ref()),
                 pending_token_count: Task::ready(None),
                 model,
                 _subscriptions: vec![cx.subscribe(&buffer, Self::handle_buffer_event)],
                 pending_save: Task::ready(Ok(())),
-                path: Some(path),
+                forked_from: saved_conversation.forked_from,
+                prompt: saved_conversation.prompt.map(Into::into),
+                system_message: None,
                 buffer,
                 completion_provider,
+                pending_completion_provider: Task::ready(None),
+                context_sections,
+                next_context_section_id,
+                pending_context_sections: Default::default(),
+                pending_tool_calls: Default::default(),
             };
             this.count_remaining_tokens(cx);
             this
@@ -1587,6 +2132,7 @@ impl Conversation {
     ) {
         match event {
             language::Event::Edited => {
+                self.remove_invalid_context_sections(cx);
                 self.count_remaining_tokens(cx);
                 cx.emit(ConversationEvent::MessagesEdited);
             }
@@ -1597,42 +2143,54 @@ impl Conversation {
     fn count_remaining_tokens(&mut self, cx: &mut ModelContext<Self>) {
         let messages = self
             .messages(cx)
-            .into_iter()
-            .filter_map(|message| {
-                Some(tiktoken_rs::ChatCompletionRequestMessage {
-                    role: match message.role {
-                        Role::User => "user".into(),
-                        Role::Assistant => "assistant".into(),
-                        Role::System => "system".into(),
-                    },
-                    content: Some(
-                        self.buffer
-                            .read(cx)
-                            .text_for_range(message.offset_range)
-                            .collect(),
-                    ),
-                    name: None,
-                    function_call: None,
-                })
+            .map(|message| {
+                self.buffer
+                    .read(cx)
+                    .text_for_range(message.offset_range)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let context_sections = self
+            .context_sections
+            .iter()
+            .filter_map(|section| {
+                let output_range = section.output_range.clone()?;
+                let output = self
+                    .buffer
+                    .read(cx)
+                    .text_for_range(output_range)
+                    .collect::<String>();
+                Some((section.id, output))
             })
             .collect::<Vec<_>>();
-        let model = self.model.clone();
+        let model = self.completion_provider.base_model();
         self.pending_token_count = cx.spawn(|this, mut cx| {
             async move {
                 cx.background_executor()
                     .timer(Duration::from_millis(200))
                     .await;
-                let token_count = cx
+                let (token_count, context_section_token_counts) = cx
                     .background_executor()
                     .spawn(async move {
-                        tiktoken_rs::num_tokens_from_messages(&model.full_name(), &messages)
+                        let token_count = messages.iter().try_fold(0, |token_count, message| {
+                            anyhow::Ok(token_count + model.count_tokens(message)?)
+                        })?;
+                        let context_section_token_counts = context_sections
+                            .iter()
+                            .map(|(id, output)| anyhow::Ok((*id, model.count_tokens(output)?)))
+                            .collect::<Result<HashMap<_, _>>>()?;
+                        anyhow::Ok((token_count, context_section_token_counts))
                     })
                     .await?;
 
                 this.update(&mut cx, |this, cx| {
-                    this.max_token_count =
-                        tiktoken_rs::model::get_context_size(&this.model.full_name());
+                    this.max_token_count = Self::max_token_count(this.completion_provider.as_ref());
                     this.token_count = Some(token_count);
+                    for section in &mut this.context_sections {
+                        if let Some(token_count) = context_section_token_counts.get(&section.id) {
+                            section.token_count = Some(*token_count);
+                        }
+                    }
                     cx.notify()
                 })?;
                 anyhow::Ok(())
@@ -1641,16 +2199,170 @@ impl Conversation {
         });
     }
 
+    fn max_token_count(completion_provider: &dyn CompletionProvider) -> usize {
+        completion_provider
+            .base_model()
+            .capacity()
+            .log_err()
+            .unwrap_or_default()
+    }
+
     fn remaining_tokens(&self) -> Option<isize> {
         Some(self.max_token_count as isize - self.token_count? as isize)
     }
 
-    fn set_model(&mut self, model: OpenAiModel, cx: &mut ModelContext<Self>) {
+    fn set_model(&mut self, model: LanguageModelReference, cx: &mut ModelContext<Self>) {
         self.model = model;
-        self.count_remaining_tokens(cx);
+        let completion_provider = build_completion_provider(&self.model, cx);
+        self.pending_completion_provider = cx.spawn(|this, mut cx| {
+            async move {
+                let completion_provider = completion_provider.await;
+                cx.update(|cx| completion_provider.retrieve_credentials(cx))?
+                    .await;
+                this.update(&mut cx, |this, cx| {
+                    this.completion_provider = completion_provider;
+                    this.count_remaining_tokens(cx);
+                    cx.notify();
+                })
+            }
+            .log_err()
+        });
+        cx.notify();
+    }
+
+    fn set_prompt(&mut self, prompt: Option<SharedString>, cx: &mut ModelContext<Self>) {
+        self.prompt = prompt;
+        self.save(Some(Duration::from_millis(500)), cx);
         cx.notify();
     }
 
+    fn insert_context_section(
+        &mut self,
+        command_range: Range<language::Anchor>,
+        name: String,
+        argument: Option<String>,
+        output: Task<Result<PromptContextSection>>,
+        cx: &mut ModelContext<Self>,
+    ) -> ContextSectionId {
+        let id = ContextSectionId(post_inc(&mut self.next_context_section_id.0));
+        let title = self
+            .buffer
+            .read(cx)
+            .text_for_range(command_range.clone())
+            .collect::<String>();
+        self.context_sections.push(ContextSection {
+            id,
+            command_range,
+            output_range: None,
+            name,
+            argument,
+            title,
+            status: ContextSectionStatus::Pending,
+            token_count: None,
+        });
+        self.resolve_context_section(id, output, cx);
+        id
+    }
+
+    fn refresh_context_section(
+        &mut self,
+        id: ContextSectionId,
+        argument: Option<String>,
+        output: Task<Result<PromptContextSection>>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(section) = self
+            .context_sections
+            .iter_mut()
+            .find(|section| section.id == id)
+        {
+            section.argument = argument;
+            section.status = ContextSectionStatus::Pending;
+            self.resolve_context_section(id, output, cx);
+        }
+    }
+
+    fn resolve_context_section(
+        &mut self,
+        id: ContextSectionId,
+        output: Task<Result<PromptContextSection>>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let task = cx.spawn(|this, mut cx| async move {
+            let output = output.await;
+            this.update(&mut cx, |this, cx| {
+                this.pending_context_sections.remove(&id);
+                let Some(section) = this
+                    .context_sections
+                    .iter_mut()
+                    .find(|section| section.id == id)
+                else {
+                    return;
+                };
+
+                match output {
+                    Ok(output) => {
+                        let text = output.to_prompt();
+                        this.buffer.update(cx, |buffer, cx| {
+                            // Replace the previous output, including the newline
+                            // that separates it from the command.
+                            let command_end = section.command_range.end.to_offset(buffer);
+                            let output_end = section
+                                .output_range
+                                .as_ref()
+                                .map_or(command_end, |range| range.end.to_offset(buffer));
+                            buffer.edit([(command_end..output_end, format!("\n{text}"))], None, cx);
+                            let output_start = command_end + 1;
+                            section.output_range = Some(
+                                buffer.anchor_after(output_start)
+                                    ..buffer.anchor_before(output_start + text.len()),
+                            );
+                        });
+                        section.title = output.title;
+                        section.status = ContextSectionStatus::Done;
+                    }
+                    Err(error) => {
+                        section.status = ContextSectionStatus::Error(
+                            error.to_string().trim().to_string().into(),
+                        );
+                    }
+                }
+                cx.emit(ConversationEvent::ContextSectionUpdated(id));
+                cx.notify();
+            })
+            .ok();
+        });
+        self.pending_context_sections.insert(id, task);
+    }
+
+    /// Forgets about context sections whose command was edited into a
+    /// different command or removed.
+    fn remove_invalid_context_sections(&mut self, cx: &mut ModelContext<Self>) {
+        let buffer = self.buffer.read(cx);
+        let mut removed_ids = Vec::new();
+        self.context_sections.retain(|section| {
+            let command = buffer
+                .text_for_range(section.command_range.clone())
+                .collect::<String>();
+            let is_valid =
+                parse_slash_command(&command).map_or(false, |command| command.name == section.name);
+            if !is_valid {
+                removed_ids.push(section.id);
+            }
+            is_valid
+        });
+        for id in removed_ids {
+            self.pending_context_sections.remove(&id);
+        }
+    }
+
+    fn context_section_for_row(&self, row: u32, cx: &AppContext) -> Option<&ContextSection> {
+        let buffer = self.buffer.read(cx);
+        self.context_sections
+            .iter()
+            .find(|section| section.command_range.start.to_point(buffer).row == row)
+    }
+
     fn assist(
         &mut self,
         selected_messages: HashSet<MessageId>,
@@ -1699,102 +2411,218 @@ impl Conversation {
                 return Default::default();
             }
 
-            let request: Box<dyn CompletionRequest> = Box::new(OpenAiRequest {
-                model: self.model.full_name().to_string(),
-                messages: self
-                    .messages(cx)
-                    .filter(|message| matches!(message.status, MessageStatus::Done))
-                    .map(|message| message.to_open_ai_message(self.buffer.read(cx)))
-                    .collect(),
-                stream: true,
-                stop: vec![],
-                temperature: 1.0,
-            });
-
-            let stream = self.completion_provider.complete(request);
-            let assistant_message = self
-                .insert_message_after(last_message_id, Role::Assistant, MessageStatus::Pending, cx)
-                .unwrap();
+            let assistant_message = self.stream_assistant_message(last_message_id, cx);
 
             // Queue up the user's next reply.
             let user_message = self
                 .insert_message_after(assistant_message.id, Role::User, MessageStatus::Done, cx)
                 .unwrap();
             user_messages.push(user_message);
+        }
 
-            let task = cx.spawn({
-                |this, mut cx| async move {
-                    let assistant_message_id = assistant_message.id;
-                    let stream_completion = async {
-                        let mut messages = stream.await?;
-
-                        while let Some(message) = messages.next().await {
-                            let text = message?;
-
-                            this.update(&mut cx, |this, cx| {
-                                let message_ix = this
-                                    .message_anchors
-                                    .iter()
-                                    .position(|message| message.id == assistant_message_id)?;
-                                this.buffer.update(cx, |buffer, cx| {
-                                    let offset = this.message_anchors[message_ix + 1..]
-                                        .iter()
-                                        .find(|message| message.start.is_valid(buffer))
-                                        .map_or(buffer.len(), |message| {
-                                            message.start.to_offset(buffer).saturating_sub(1)
-                                        });
-                                    buffer.edit([(offset..offset, text)], None, cx);
-                                });
-                                cx.emit(ConversationEvent::StreamedCompletion);
+        user_messages
+    }
 
-                                Some(())
-                            })?;
-                            smol::future::yield_now().await;
-                        }
+    fn cancel_last_assist(&mut self) -> bool {
+        self.pending_completions.pop().is_some()
+    }
 
-                        this.update(&mut cx, |this, cx| {
-                            this.pending_completions
-                                .retain(|completion| completion.id != this.completion_count);
-                            this.summarize(cx);
-                        })?;
+    /// Inserts an assistant message after the given message, and streams the
+    /// model's reply to the messages before it into it.
+    fn stream_assistant_message(
+        &mut self,
+        previous_message_id: MessageId,
+        cx: &mut ModelContext<Self>,
+    ) -> MessageAnchor {
+        let tools = if AssistantSettings::get_global(cx).tools {
+            cx.try_global::<AssistantToolRegistry>()
+                .map(|registry| registry.definitions().into_iter().map(Into::into).collect())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let system_message = self.system_message.clone().map(|content| RequestMessage {
+            role: Role::System,
+            content,
+            ..Default::default()
+        });
+        let request: Box<dyn CompletionRequest> = Box::new(OpenAiRequest {
+            model: self.model.model.clone(),
+            messages: system_message
+                .into_iter()
+                .chain(
+                    self.messages(cx)
+                        .filter(|message| matches!(message.status, MessageStatus::Done))
+                        .map(|message| message.to_open_ai_message(self.buffer.read(cx)))
+                        .filter(|message| {
+                            !message.content.is_empty() || !message.tool_calls.is_empty()
+                        }),
+                )
+                .collect(),
+            stream: true,
+            stop: vec![],
+            temperature: 1.0,
+            tools,
+        });
 
-                        anyhow::Ok(())
-                    };
+        let stream = self.completion_provider.complete_with_tools(request);
+        let assistant_message = self
+            .insert_message_after(
+                previous_message_id,
+                Role::Assistant,
+                MessageStatus::Pending,
+                cx,
+            )
+            .unwrap();
 
-                    let result = stream_completion.await;
+        let task = cx.spawn({
+            |this, mut cx| async move {
+                let assistant_message_id = assistant_message.id;
+                let stream_completion = async {
+                    let mut events = stream.await?;
+
+                    while let Some(event) = events.next().await {
+                        match event? {
+                            CompletionEvent::Text(text) => {
+                                this.update(&mut cx, |this, cx| {
+                                    let message_ix = this
+                                        .message_anchors
+                                        .iter()
+                                        .position(|message| message.id == assistant_message_id)?;
+                                    this.buffer.update(cx, |buffer, cx| {
+                                        let offset = this.message_anchors[message_ix + 1..]
+                                            .iter()
+                                            .find(|message| message.start.is_valid(buffer))
+                                            .map_or(buffer.len(), |message| {
+                                                message.start.to_offset(buffer).saturating_sub(1)
+                                            });
+                                        buffer.edit([(offset..offset, text)], None, cx);
+                                    });
+                                    cx.emit(ConversationEvent::StreamedCompletion);
+
+                                    Some(())
+                                })?;
+                            }
+                            CompletionEvent::ToolCall(tool_call) => {
+                                this.update(&mut cx, |this, cx| {
+                                    if let Some(metadata) =
+                                        this.messages_metadata.get_mut(&assistant_message_id)
+                                    {
+                                        metadata.tool_calls.push(tool_call);
+                                        cx.emit(ConversationEvent::MessagesEdited);
+                                    }
+                                })?;
+                            }
+                        }
+                        smol::future::yield_now().await;
+                    }
 
                     this.update(&mut cx, |this, cx| {
-                        if let Some(metadata) =
-                            this.messages_metadata.get_mut(&assistant_message.id)
-                        {
-                            match result {
-                                Ok(_) => {
-                                    metadata.status = MessageStatus::Done;
-                                }
-                                Err(error) => {
-                                    metadata.status = MessageStatus::Error(SharedString::from(
-                                        error.to_string().trim().to_string(),
+                        this.pending_completions
+                            .retain(|completion| completion.id != this.completion_count);
+                        this.summarize(cx);
+                    })?;
+
+                    anyhow::Ok(())
+                };
+
+                let result = stream_completion.await;
+
+                this.update(&mut cx, |this, cx| {
+                    if let Some(metadata) = this.messages_metadata.get_mut(&assistant_message.id) {
+                        match result {
+                            Ok(_) => {
+                                metadata.status = MessageStatus::Done;
+                                if !metadata.tool_calls.is_empty() {
+                                    cx.emit(ConversationEvent::ToolCallsRequested(
+                                        assistant_message_id,
                                     ));
                                 }
                             }
-                            cx.notify();
+                            Err(error) => {
+                                metadata.status = MessageStatus::Error(SharedString::from(
+                                    error.to_string().trim().to_string(),
+                                ));
+                            }
                         }
-                    })
-                    .ok();
-                }
-            });
+                        cx.notify();
+                    }
+                })
+                .ok();
+            }
+        });
 
-            self.pending_completions.push(PendingCompletion {
-                id: post_inc(&mut self.completion_count),
-                _task: task,
-            });
-        }
+        self.pending_completions.push(PendingCompletion {
+            id: post_inc(&mut self.completion_count),
+            _task: task,
+        });
 
-        user_messages
+        assistant_message
     }
 
-    fn cancel_last_assist(&mut self) -> bool {
-        self.pending_completions.pop().is_some()
+    /// Inserts the result of a tool call requested by an assistant message, and
+    /// once every call requested by that message has a result, streams the
+    /// assistant's next message.
+    fn resolve_tool_call(
+        &mut self,
+        assistant_message_id: MessageId,
+        tool_call: ToolCall,
+        output: Task<Result<String>>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let task = cx.spawn(|this, mut cx| async move {
+            let output = output.await;
+            this.update(&mut cx, |this, cx| {
+                this.pending_tool_calls.remove(&tool_call.id);
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => format!("Error: {}", error.to_string().trim()),
+                };
+
+                // Tool results follow the message that requested them, in the
+                // order they complete.
+                let messages = this.messages(cx).collect::<Vec<_>>();
+                let Some(assistant_message_ix) = messages
+                    .iter()
+                    .position(|message| message.id == assistant_message_id)
+                else {
+                    return;
+                };
+                let tool_messages = messages[assistant_message_ix + 1..]
+                    .iter()
+                    .take_while(|message| message.role == Role::Tool)
+                    .collect::<Vec<_>>();
+                let previous_message_id = tool_messages
+                    .last()
+                    .map_or(assistant_message_id, |message| message.id);
+                let Some(tool_message) = this.insert_message_after(
+                    previous_message_id,
+                    Role::Tool,
+                    MessageStatus::Done,
+                    cx,
+                ) else {
+                    return;
+                };
+                if let Some(metadata) = this.messages_metadata.get_mut(&tool_message.id) {
+                    metadata.tool_call_id = Some(tool_call.id.clone());
+                }
+                this.buffer.update(cx, |buffer, cx| {
+                    let offset = tool_message.start.to_offset(buffer);
+                    buffer.edit([(offset..offset, output)], None, cx);
+                });
+
+                let requested_call_count = this
+                    .messages_metadata
+                    .get(&assistant_message_id)
+                    .map_or(0, |metadata| metadata.tool_calls.len());
+                if tool_messages.len() + 1 == requested_call_count {
+                    this.stream_assistant_message(tool_message.id, cx);
+                }
+                cx.emit(ConversationEvent::MessagesEdited);
+            })
+            .ok();
+        });
+        self.pending_tool_calls.insert(tool_call.id.clone(), task);
     }
 
     fn cycle_message_roles(&mut self, ids: HashSet<MessageId>, cx: &mut ModelContext<Self>) {
@@ -1848,6 +2676,8 @@ impl Conversation {
                     role,
                     sent_at: Local::now(),
                     status,
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
                 },
             );
             cx.emit(ConversationEvent::MessagesEdited);
@@ -1908,6 +2738,8 @@ impl Conversation {
                     role,
                     sent_at: Local::now(),
                     status: MessageStatus::Done,
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
                 },
             );
 
@@ -1953,6 +2785,8 @@ impl Conversation {
                             role,
                             sent_at: Local::now(),
                             status: MessageStatus::Done,
+                            tool_calls: Vec::new(),
+                            tool_call_id: None,
                         },
                     );
                     (Some(selection), Some(suffix))
@@ -1981,13 +2815,15 @@ impl Conversation {
                     role: Role::User,
                     content: "Summarize the conversation into a short title without punctuation"
                         .into(),
+                    ..Default::default()
                 }));
             let request: Box<dyn CompletionRequest> = Box::new(OpenAiRequest {
-                model: self.model.full_name().to_string(),
+                model: self.model.model.clone(),
                 messages: messages.collect(),
                 stream: true,
                 stop: vec![],
                 temperature: 1.0,
+                tools: Vec::new(),
             });
 
             let stream = self.completion_provider.complete(request);
@@ -2086,63 +2922,26 @@ impl Conversation {
                     role: metadata.role,
                     sent_at: metadata.sent_at,
                     status: metadata.status.clone(),
+                    tool_calls: metadata.tool_calls.clone(),
+                    tool_call_id: metadata.tool_call_id.clone(),
                 });
             }
             None
         })
     }
 
-    fn save(
-        &mut self,
-        debounce: Option<Duration>,
-        fs: Arc<dyn Fs>,
-        cx: &mut ModelContext<Conversation>,
-    ) {
+    fn save(&mut self, debounce: Option<Duration>, cx: &mut ModelContext<Conversation>) {
         self.pending_save = cx.spawn(|this, mut cx| async move {
             if let Some(debounce) = debounce {
                 cx.background_executor().timer(debounce).await;
             }
 
-            let (old_path, summary) = this.read_with(&cx, |this, _| {
-                let path = this.path.clone();
-                let summary = if let Some(summary) = this.summary.as_ref() {
-                    if summary.done {
-                        Some(summary.text.clone())
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-                (path, summary)
+            let summary_done = this.read_with(&cx, |this, _| {
+                this.summary.as_ref().map_or(false, |summary| summary.done)
             })?;
-
-            if let Some(summary) = summary {
+            if summary_done {
                 let conversation = this.read_with(&cx, |this, cx| this.serialize(cx))?;
-                let path = if let Some(old_path) = old_path {
-                    old_path
-                } else {
-                    let mut discriminant = 1;
-                    let mut new_path;
-                    loop {
-                        new_path = CONVERSATIONS_DIR.join(&format!(
-                            "{} - {}.zed.json",
-                            summary.trim(),
-                            discriminant
-                        ));
-                        if fs.is_file(&new_path).await {
-                            discriminant += 1;
-                        } else {
-                            break;
-                        }
-                    }
-                    new_path
-                };
-
-                fs.create_dir(CONVERSATIONS_DIR.as_ref()).await?;
-                fs.atomic_write(path.clone(), serde_json::to_string(&conversation).unwrap())
-                    .await?;
-                this.update(&mut cx, |this, _| this.path = Some(path))?;
+                save_conversation(&conversation, Local::now()).await?;
             }
 
             Ok(())
@@ -2157,6 +2956,8 @@ struct PendingCompletion {
 
 enum ConversationEditorEvent {
     TabContentChanged,
+    /// A fork of the conversation was saved with the given id.
+    Forked(String),
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -2169,8 +2970,10 @@ struct ConversationEditor {
     conversation: Model<Conversation>,
     fs: Arc<dyn Fs>,
     workspace: WeakView<Workspace>,
+    prompt_library: Model<PromptLibrary>,
     editor: View<Editor>,
     blocks: HashSet<BlockId>,
+    expanded_context_sections: HashSet<ContextSectionId>,
     scroll_position: Option<ScrollPosition>,
     _subscriptions: Vec<Subscription>,
 }
@@ -2181,29 +2984,40 @@ impl ConversationEditor {
         language_registry: Arc<LanguageRegistry>,
         fs: Arc<dyn Fs>,
         workspace: WeakView<Workspace>,
+        prompt_library: Model<PromptLibrary>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
         let conversation =
             cx.new_model(|cx| Conversation::new(language_registry, cx, completion_provider));
-        Self::for_conversation(conversation, fs, workspace, cx)
+        Self::for_conversation(conversation, fs, workspace, prompt_library, cx)
     }
 
     fn for_conversation(
         conversation: Model<Conversation>,
         fs: Arc<dyn Fs>,
         workspace: WeakView<Workspace>,
+        prompt_library: Model<PromptLibrary>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
+        conversation.update(cx, |conversation, cx| {
+            conversation.buffer.update(cx, |buffer, cx| {
+                buffer.set_completion_triggers(vec!["/".into()], cx)
+            })
+        });
         let editor = cx.new_view(|cx| {
             let mut editor = Editor::for_buffer(conversation.read(cx).buffer.clone(), None, cx);
             editor.set_soft_wrap_mode(SoftWrap::EditorWidth, cx);
             editor.set_show_gutter(false, cx);
             editor.set_show_wrap_guides(false, cx);
+            editor.set_completion_provider(Box::new(SlashCommandCompletionProvider::new(
+                workspace.clone(),
+            )));
             editor
         });
 
         let _subscriptions = vec![
             cx.observe(&conversation, |_, _, cx| cx.notify()),
+            cx.observe(&prompt_library, |_, _, cx| cx.notify()),
             cx.subscribe(&conversation, Self::handle_conversation_event),
             cx.subscribe(&editor, Self::handle_editor_event),
         ];
@@ -2212,11 +3026,21 @@ impl ConversationEditor {
             conversation,
             editor,
             blocks: Default::default(),
+            expanded_context_sections: Default::default(),
             scroll_position: None,
             fs,
             workspace,
+            prompt_library,
             _subscriptions,
         };
+        let context_section_ids = this
+            .conversation
+            .read(cx)
+            .context_sections
+            .iter()
+            .map(|section| section.id)
+            .collect::<Vec<_>>();
+        this.fold_context_sections(context_section_ids, cx);
         this.update_message_headers(cx);
         this
     }
@@ -2230,8 +3054,10 @@ impl ConversationEditor {
         );
 
         let cursors = self.cursors(cx);
+        let system_message = self.system_message(cx);
 
         let user_messages = self.conversation.update(cx, |conversation, cx| {
+            conversation.system_message = system_message;
             let selected_messages = conversation
                 .messages_for_offsets(cursors, cx)
                 .into_iter()
@@ -2250,23 +3076,170 @@ impl ConversationEditor {
             .collect::<Vec<_>>();
         if !new_selections.is_empty() {
             self.editor.update(cx, |editor, cx| {
-                editor.change_selections(
-                    Some(Autoscroll::Strategy(AutoscrollStrategy::Fit)),
-                    cx,
-                    |selections| selections.select_ranges(new_selections),
-                );
+                editor.change_selections(
+                    Some(Autoscroll::Strategy(AutoscrollStrategy::Fit)),
+                    cx,
+                    |selections| selections.select_ranges(new_selections),
+                );
+            });
+            // Avoid scrolling to the new cursor position so the assistant's output is stable.
+            cx.defer(|this, _| this.scroll_position = None);
+        }
+    }
+
+    fn cancel_last_assist(&mut self, _: &editor::actions::Cancel, cx: &mut ViewContext<Self>) {
+        if !self
+            .conversation
+            .update(cx, |conversation, _| conversation.cancel_last_assist())
+        {
+            cx.propagate();
+        }
+    }
+
+    /// Runs the slash command on the cursor's line when pressing enter at the
+    /// end of it, before letting the editor insert the newline.
+    fn newline(&mut self, _: &editor::actions::Newline, cx: &mut ViewContext<Self>) {
+        self.run_slash_command_at_cursor(cx);
+        cx.propagate();
+    }
+
+    fn run_slash_command_at_cursor(&mut self, cx: &mut ViewContext<Self>) -> Option<()> {
+        let cursor = self.editor.read(cx).selections.newest::<usize>(cx);
+        if !cursor.is_empty() {
+            return None;
+        }
+
+        let conversation = self.conversation.read(cx);
+        let buffer = conversation.buffer.read(cx);
+        let cursor = buffer.offset_to_point(cursor.head());
+        if cursor.column != buffer.line_len(cursor.row)
+            || conversation
+                .context_section_for_row(cursor.row, cx)
+                .is_some()
+        {
+            return None;
+        }
+
+        let line_start = Point::new(cursor.row, 0);
+        let line = buffer
+            .text_for_range(line_start..cursor)
+            .collect::<String>();
+        let command = parse_slash_command(&line)?;
+        let slash_command = SlashCommandRegistry::global(cx).command(command.name)?;
+        if slash_command.requires_argument() && command.argument.is_none() {
+            return None;
+        }
+
+        let command_range = buffer.anchor_after(line_start)..buffer.anchor_before(cursor);
+        let name = command.name.to_string();
+        let argument = command.argument.map(str::to_string);
+        let output = slash_command.run(argument.as_deref(), self.workspace.clone(), cx);
+        self.conversation.update(cx, |conversation, cx| {
+            conversation.insert_context_section(command_range, name, argument, output, cx)
+        });
+        Some(())
+    }
+
+    fn refresh_context_section(&mut self, id: ContextSectionId, cx: &mut ViewContext<Self>) {
+        let conversation = self.conversation.read(cx);
+        let Some(section) = conversation
+            .context_sections
+            .iter()
+            .find(|section| section.id == id)
+        else {
+            return;
+        };
+        let buffer = conversation.buffer.read(cx);
+        let fold_range = section.fold_range(buffer);
+
+        // Pick up any changes made to the command's argument since it last ran.
+        let command = buffer
+            .text_for_range(section.command_range.clone())
+            .collect::<String>();
+        let Some(argument) = parse_slash_command(&command)
+            .filter(|command| command.name == section.name)
+            .map(|command| command.argument.map(str::to_string))
+        else {
+            return;
+        };
+        let Some(slash_command) = SlashCommandRegistry::global(cx).command(&section.name) else {
+            return;
+        };
+
+        if let Some(fold_range) = fold_range {
+            self.editor.update(cx, |editor, cx| {
+                editor.unfold_ranges([fold_range], true, false, cx)
             });
-            // Avoid scrolling to the new cursor position so the assistant's output is stable.
-            cx.defer(|this, _| this.scroll_position = None);
         }
+        let output = slash_command.run(argument.as_deref(), self.workspace.clone(), cx);
+        self.conversation.update(cx, |conversation, cx| {
+            conversation.refresh_context_section(id, argument, output, cx)
+        });
     }
 
-    fn cancel_last_assist(&mut self, _: &editor::actions::Cancel, cx: &mut ViewContext<Self>) {
-        if !self
+    fn run_tool_calls(&mut self, message_id: MessageId, cx: &mut ViewContext<Self>) {
+        let Some(tool_calls) = self
             .conversation
-            .update(cx, |conversation, _| conversation.cancel_last_assist())
-        {
-            cx.propagate();
+            .read(cx)
+            .messages_metadata
+            .get(&message_id)
+            .map(|metadata| metadata.tool_calls.clone())
+        else {
+            return;
+        };
+
+        for tool_call in tool_calls {
+            let output = match AssistantToolRegistry::global(cx).tool(&tool_call.name) {
+                Some(tool) => tool.run(&tool_call.arguments, self.workspace.clone(), cx),
+                None => Task::ready(Err(anyhow!("no such tool: {}", tool_call.name))),
+            };
+            self.conversation.update(cx, |conversation, cx| {
+                conversation.resolve_tool_call(message_id, tool_call, output, cx)
+            });
+        }
+    }
+
+    fn toggle_context_section(&mut self, id: ContextSectionId, cx: &mut ViewContext<Self>) {
+        if self.expanded_context_sections.remove(&id) {
+            self.fold_context_sections([id], cx);
+        } else {
+            let conversation = self.conversation.read(cx);
+            let fold_range = conversation
+                .context_sections
+                .iter()
+                .find(|section| section.id == id)
+                .and_then(|section| section.fold_range(conversation.buffer.read(cx)));
+            if let Some(fold_range) = fold_range {
+                self.expanded_context_sections.insert(id);
+                self.editor.update(cx, |editor, cx| {
+                    editor.unfold_ranges([fold_range], true, true, cx)
+                });
+            }
+        }
+        self.update_message_headers(cx);
+    }
+
+    fn fold_context_sections(
+        &mut self,
+        ids: impl IntoIterator<Item = ContextSectionId>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let conversation = self.conversation.read(cx);
+        let buffer = conversation.buffer.read(cx);
+        let fold_ranges = ids
+            .into_iter()
+            .filter(|id| !self.expanded_context_sections.contains(id))
+            .filter_map(|id| {
+                conversation
+                    .context_sections
+                    .iter()
+                    .find(|section| section.id == id)?
+                    .fold_range(buffer)
+            })
+            .collect::<Vec<_>>();
+        if !fold_ranges.is_empty() {
+            self.editor
+                .update(cx, |editor, cx| editor.fold_ranges(fold_ranges, false, cx));
         }
     }
 
@@ -2300,15 +3273,22 @@ impl ConversationEditor {
             ConversationEvent::MessagesEdited => {
                 self.update_message_headers(cx);
                 self.conversation.update(cx, |conversation, cx| {
-                    conversation.save(Some(Duration::from_millis(500)), self.fs.clone(), cx);
+                    conversation.save(Some(Duration::from_millis(500)), cx);
                 });
             }
             ConversationEvent::SummaryChanged => {
                 cx.emit(ConversationEditorEvent::TabContentChanged);
                 self.conversation.update(cx, |conversation, cx| {
-                    conversation.save(None, self.fs.clone(), cx);
+                    conversation.save(None, cx);
                 });
             }
+            ConversationEvent::ContextSectionUpdated(id) => {
+                self.fold_context_sections([*id], cx);
+                self.update_message_headers(cx);
+            }
+            ConversationEvent::ToolCallsRequested(message_id) => {
+                self.run_tool_calls(*message_id, cx);
+            }
             ConversationEvent::StreamedCompletion => {
                 self.editor.update(cx, |editor, cx| {
                     if let Some(scroll_position) = self.scroll_position {
@@ -2371,11 +3351,12 @@ impl ConversationEditor {
     }
 
     fn update_message_headers(&mut self, cx: &mut ViewContext<Self>) {
+        let this = cx.view().downgrade();
         self.editor.update(cx, |editor, cx| {
             let buffer = editor.buffer().read(cx).snapshot(cx);
             let excerpt_id = *buffer.as_singleton().unwrap().0;
             let old_blocks = std::mem::take(&mut self.blocks);
-            let new_blocks = self
+            let mut new_blocks = self
                 .conversation
                 .read(cx)
                 .messages(cx)
@@ -2385,6 +3366,7 @@ impl ConversationEditor {
                     style: BlockStyle::Sticky,
                     render: Arc::new({
                         let conversation = self.conversation.clone();
+                        let this = this.clone();
                         move |_cx| {
                             let message_id = message.id;
                             let sender = ButtonLike::new("role")
@@ -2393,6 +3375,7 @@ impl ConversationEditor {
                                     Role::User => Label::new("You").color(Color::Default),
                                     Role::Assistant => Label::new("Assistant").color(Color::Info),
                                     Role::System => Label::new("System").color(Color::Warning),
+                                    Role::Tool => Label::new("Tool").color(Color::Muted),
                                 })
                                 .tooltip(|cx| {
                                     Tooltip::with_meta(
@@ -2433,6 +3416,16 @@ impl ConversationEditor {
                                     .size(LabelSize::XSmall)
                                     .color(Color::Muted),
                                 )
+                                .children((!message.tool_calls.is_empty()).then(|| {
+                                    let names = message
+                                        .tool_calls
+                                        .iter()
+                                        .map(|tool_call| tool_call.name.as_str())
+                                        .collect::<Vec<_>>();
+                                    Label::new(format!("Called {}", names.join(", ")))
+                                        .size(LabelSize::XSmall)
+                                        .color(Color::Muted)
+                                }))
                                 .children(
                                     if let MessageStatus::Error(error) = message.status.clone() {
                                         Some(
@@ -2445,6 +3438,27 @@ impl ConversationEditor {
                                         None
                                     },
                                 )
+                                .child(
+                                    IconButton::new(("fork_message", message_id.0), IconName::Copy)
+                                        .icon_size(IconSize::XSmall)
+                                        .icon_color(Color::Muted)
+                                        .tooltip(|cx| {
+                                            Tooltip::for_action(
+                                                "Fork from Here",
+                                                &ForkConversation,
+                                                cx,
+                                            )
+                                        })
+                                        .on_click({
+                                            let this = this.clone();
+                                            move |_, cx| {
+                                                this.update(cx, |this, cx| {
+                                                    this.fork_from_message(message_id, cx)
+                                                })
+                                                .ok();
+                                            }
+                                        }),
+                                )
                                 .into_any_element()
                         }
                     }),
@@ -2452,6 +3466,96 @@ impl ConversationEditor {
                 })
                 .collect::<Vec<_>>();
 
+            for section in self.conversation.read(cx).context_sections.iter().cloned() {
+                let is_expanded = self.expanded_context_sections.contains(&section.id);
+                new_blocks.push(BlockProperties {
+                    position: buffer.anchor_in_excerpt(excerpt_id, section.command_range.start),
+                    height: 1,
+                    style: BlockStyle::Sticky,
+                    render: Arc::new({
+                        let this = this.clone();
+                        move |_cx| {
+                            let section_id = section.id;
+                            h_flex()
+                                .id(("context_section_header", section_id.0))
+                                .gap_1()
+                                .child(
+                                    IconButton::new(
+                                        ("toggle_context_section", section_id.0),
+                                        if is_expanded {
+                                            IconName::ChevronDown
+                                        } else {
+                                            IconName::ChevronRight
+                                        },
+                                    )
+                                    .icon_size(IconSize::Small)
+                                    .disabled(section.output_range.is_none())
+                                    .tooltip(move |cx| {
+                                        Tooltip::text(
+                                            if is_expanded { "Collapse" } else { "Expand" },
+                                            cx,
+                                        )
+                                    })
+                                    .on_click({
+                                        let this = this.clone();
+                                        move |_, cx| {
+                                            this.update(cx, |this, cx| {
+                                                this.toggle_context_section(section_id, cx)
+                                            })
+                                            .ok();
+                                        }
+                                    }),
+                                )
+                                .child(
+                                    Label::new(section.title.clone())
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                )
+                                .children(section.token_count.map(|token_count| {
+                                    Label::new(format!("{token_count} tokens"))
+                                        .size(LabelSize::XSmall)
+                                        .color(Color::Muted)
+                                }))
+                                .children(match section.status.clone() {
+                                    ContextSectionStatus::Pending => Some(
+                                        Label::new("Loading…")
+                                            .size(LabelSize::XSmall)
+                                            .color(Color::Muted)
+                                            .into_any_element(),
+                                    ),
+                                    ContextSectionStatus::Error(error) => Some(
+                                        div()
+                                            .id("error")
+                                            .tooltip(move |cx| Tooltip::text(error.clone(), cx))
+                                            .child(Icon::new(IconName::XCircle))
+                                            .into_any_element(),
+                                    ),
+                                    ContextSectionStatus::Done => None,
+                                })
+                                .child(
+                                    IconButton::new(
+                                        ("refresh_context_section", section_id.0),
+                                        IconName::ArrowCircle,
+                                    )
+                                    .icon_size(IconSize::Small)
+                                    .tooltip(|cx| Tooltip::text("Refresh", cx))
+                                    .on_click({
+                                        let this = this.clone();
+                                        move |_, cx| {
+                                            this.update(cx, |this, cx| {
+                                                this.refresh_context_section(section_id, cx)
+                                            })
+                                            .ok();
+                                        }
+                                    }),
+                                )
+                                .into_any_element()
+                        }
+                    }),
+                    disposition: BlockDisposition::Above,
+                });
+            }
+
             editor.remove_blocks(old_blocks, None, cx);
             let ids = editor.insert_blocks(new_blocks, None, cx);
             self.blocks = HashSet::from_iter(ids);
@@ -2567,14 +3671,83 @@ impl ConversationEditor {
     }
 
     fn save(&mut self, _: &Save, cx: &mut ViewContext<Self>) {
+        self.conversation
+            .update(cx, |conversation, cx| conversation.save(None, cx));
+    }
+
+    fn fork_conversation(&mut self, _: &ForkConversation, cx: &mut ViewContext<Self>) {
+        let cursor = self.editor.read(cx).selections.newest::<usize>(cx).head();
+        if let Some(message) = self.conversation.read(cx).message_for_offset(cursor, cx) {
+            self.fork_from_message(message.id, cx);
+        }
+    }
+
+    /// Saves a copy of the conversation up to and including the given message
+    /// and opens it in the panel.
+    fn fork_from_message(&mut self, message_id: MessageId, cx: &mut ViewContext<Self>) {
+        let Some(fork) = self.conversation.read(cx).serialize(cx).fork(message_id) else {
+            return;
+        };
+        cx.spawn(|this, mut cx| async move {
+            save_conversation(&fork, Local::now()).await?;
+            this.update(&mut cx, |_, cx| {
+                cx.emit(ConversationEditorEvent::Forked(fork.id.unwrap_or_default()))
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn export_conversation(&mut self, _: &ExportConversation, cx: &mut ViewContext<Self>) {
+        let markdown = self.conversation.read(cx).serialize(cx).to_markdown();
+        let directory = self
+            .workspace
+            .upgrade()
+            .and_then(|workspace| {
+                let project = workspace.read(cx).project().read(cx);
+                let worktree = project.visible_worktrees(cx).next()?;
+                Some(worktree.read(cx).abs_path().to_path_buf())
+            })
+            .unwrap_or_else(|| HOME.clone());
+        let path = cx.prompt_for_new_path(&directory);
+        let fs = self.fs.clone();
+        cx.spawn(|_, _| async move {
+            if let Some(path) = path.await? {
+                fs.atomic_write(path, markdown).await?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Renders the conversation's prompt for the active editor in the
+    /// workspace, followed by the project's rules.
+    fn system_message(&self, cx: &AppContext) -> Option<String> {
+        let variables = self
+            .workspace
+            .upgrade()
+            .and_then(|workspace| workspace.read(cx).active_item(cx)?.act_as::<Editor>(cx))
+            .map(|editor| PromptVariables::for_editor(&editor, cx))
+            .unwrap_or_default();
+        let prompt = self.conversation.read(cx).prompt.clone();
+        self.prompt_library
+            .read(cx)
+            .system_message(prompt.as_deref(), &variables)
+    }
+
+    fn cycle_prompt(&mut self, _: &CyclePrompt, cx: &mut ViewContext<Self>) {
+        let current_prompt = self.conversation.read(cx).prompt.clone();
+        let next_prompt = self
+            .prompt_library
+            .read(cx)
+            .next_prompt(current_prompt.as_deref());
         self.conversation.update(cx, |conversation, cx| {
-            conversation.save(None, self.fs.clone(), cx)
+            conversation.set_prompt(next_prompt, cx)
         });
     }
 
     fn cycle_model(&mut self, cx: &mut ViewContext<Self>) {
         self.conversation.update(cx, |conversation, cx| {
-            let new_model = conversation.model.cycle();
+            let new_model = AssistantSettings::get_global(cx).next_model(&conversation.model);
             conversation.set_model(new_model, cx);
         });
     }
@@ -2589,13 +3762,31 @@ impl ConversationEditor {
     }
 
     fn render_current_model(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        Button::new(
-            "current_model",
-            self.conversation.read(cx).model.short_name(),
+        let model = &self.conversation.read(cx).model;
+        let model_name = AssistantSettings::get_global(cx)
+            .model_display_name(model)
+            .to_string();
+        Button::new("current_model", model_name)
+            .style(ButtonStyle::Filled)
+            .tooltip(move |cx| Tooltip::text("Change Model", cx))
+            .on_click(cx.listener(|this, _, cx| this.cycle_model(cx)))
+    }
+
+    fn render_current_prompt(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
+        let prompt = self.conversation.read(cx).prompt.clone();
+        if prompt.is_none() && self.prompt_library.read(cx).prompts().is_empty() {
+            return None;
+        }
+
+        Some(
+            Button::new(
+                "current_prompt",
+                prompt.unwrap_or_else(|| "No Prompt".into()),
+            )
+            .style(ButtonStyle::Filled)
+            .tooltip(|cx| Tooltip::for_action("Change Prompt", &CyclePrompt, cx))
+            .on_click(cx.listener(|this, _, cx| this.cycle_prompt(&CyclePrompt, cx))),
         )
-        .style(ButtonStyle::Filled)
-        .tooltip(move |cx| Tooltip::text("Change Model", cx))
-        .on_click(cx.listener(|this, _, cx| this.cycle_model(cx)))
     }
 
     fn render_remaining_tokens(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
@@ -2621,8 +3812,12 @@ impl Render for ConversationEditor {
             .capture_action(cx.listener(ConversationEditor::save))
             .capture_action(cx.listener(ConversationEditor::copy))
             .capture_action(cx.listener(ConversationEditor::cycle_message_role))
+            .capture_action(cx.listener(ConversationEditor::newline))
             .on_action(cx.listener(ConversationEditor::assist))
             .on_action(cx.listener(ConversationEditor::split))
+            .on_action(cx.listener(ConversationEditor::fork_conversation))
+            .on_action(cx.listener(ConversationEditor::export_conversation))
+            .on_action(cx.listener(ConversationEditor::cycle_prompt))
             .size_full()
             .relative()
             .child(
@@ -2638,6 +3833,7 @@ impl Render for ConversationEditor {
                     .gap_1()
                     .top_3()
                     .right_5()
+                    .children(self.render_current_prompt(cx))
                     .child(self.render_current_model(cx))
                     .children(self.render_remaining_tokens(cx)),
             )
@@ -2665,6 +3861,8 @@ pub struct Message {
     role: Role,
     sent_at: DateTime<Local>,
     status: MessageStatus,
+    tool_calls: Vec<ToolCall>,
+    tool_call_id: Option<String>,
 }
 
 impl Message {
@@ -2675,6 +3873,8 @@ impl Message {
         RequestMessage {
             role: self.role,
             content: content.trim_end().into(),
+            tool_calls: self.tool_calls.iter().cloned().map(Into::into).collect(),
+            tool_call_id: self.tool_call_id.clone(),
         }
     }
 }
@@ -2684,6 +3884,7 @@ enum InlineAssistantEvent {
         prompt: String,
         include_conversation: bool,
         retrieve_context: bool,
+        library_prompt: Option<SharedString>,
     },
     Canceled,
     Dismissed,
@@ -2693,6 +3894,9 @@ enum InlineAssistantEvent {
     RetrieveContextToggled {
         retrieve_context: bool,
     },
+    LibraryPromptChanged {
+        library_prompt: Option<SharedString>,
+    },
 }
 
 struct InlineAssistant {
@@ -2705,13 +3909,15 @@ struct InlineAssistant {
     prompt_history: VecDeque<String>,
     prompt_history_ix: Option<usize>,
     pending_prompt: String,
-    codegen: Model<Codegen>,
+    codegens: Vec<Model<Codegen>>,
     _subscriptions: Vec<Subscription>,
     retrieve_context: bool,
     semantic_index: Option<Model<SemanticIndex>>,
     semantic_permissioned: Option<bool>,
     project: WeakModel<Project>,
     maintain_rate_limit: Option<Task<()>>,
+    library_prompt: Option<SharedString>,
+    prompt_library: Model<PromptLibrary>,
 }
 
 impl EventEmitter<InlineAssistantEvent> for InlineAssistant {}
@@ -2728,6 +3934,7 @@ impl Render for InlineAssistant {
             .on_action(cx.listener(Self::cancel))
             .on_action(cx.listener(Self::toggle_include_conversation))
             .on_action(cx.listener(Self::toggle_retrieve_context))
+            .on_action(cx.listener(Self::cycle_prompt))
             .on_action(cx.listener(Self::move_up))
             .on_action(cx.listener(Self::move_down))
             .child(
@@ -2766,7 +3973,7 @@ impl Render for InlineAssistant {
                     } else {
                         None
                     })
-                    .children(if let Some(error) = self.codegen.read(cx).error() {
+                    .children(if let Some(error) = self.error(cx) {
                         let error_message = SharedString::from(error.to_string());
                         Some(
                             div()
@@ -2784,6 +3991,7 @@ impl Render for InlineAssistant {
                     .ml(measurements.anchor_x - measurements.gutter_width)
                     .child(self.render_prompt_editor(cx)),
             )
+            .children(self.render_library_prompt(cx))
             .children(if self.retrieve_context {
                 self.retrieve_context_status(cx)
             } else {
@@ -2804,18 +4012,25 @@ impl InlineAssistant {
         measurements: Rc<Cell<BlockMeasurements>>,
         include_conversation: bool,
         prompt_history: VecDeque<String>,
-        codegen: Model<Codegen>,
+        codegens: Vec<Model<Codegen>>,
         workspace: WeakView<Workspace>,
         cx: &mut ViewContext<Self>,
         retrieve_context: bool,
         semantic_index: Option<Model<SemanticIndex>>,
         project: Model<Project>,
+        library_prompt: Option<SharedString>,
+        prompt_library: Model<PromptLibrary>,
     ) -> Self {
         let prompt_editor = cx.new_view(|cx| {
             let mut editor = Editor::single_line(cx);
-            let placeholder = match codegen.read(cx).kind() {
-                CodegenKind::Transform { .. } => "Enter transformation prompt…",
-                CodegenKind::Generate { .. } => "Enter generation prompt…",
+            let transform = codegens
+                .iter()
+                .any(|codegen| matches!(codegen.read(cx).kind(), CodegenKind::Transform { .. }));
+            let placeholder = match (transform, codegens.len()) {
+                (true, 1) => "Enter transformation prompt…".to_string(),
+                (true, count) => format!("Enter transformation prompt for {count} selections…"),
+                (false, 1) => "Enter generation prompt…".to_string(),
+                (false, count) => format!("Enter generation prompt for {count} cursors…"),
             };
             editor.set_placeholder_text(placeholder, cx);
             editor
@@ -2823,9 +4038,12 @@ impl InlineAssistant {
         cx.focus_view(&prompt_editor);
 
         let mut subscriptions = vec![
-            cx.observe(&codegen, Self::handle_codegen_changed),
             cx.subscribe(&prompt_editor, Self::handle_prompt_editor_events),
+            cx.observe(&prompt_library, |_, _, cx| cx.notify()),
         ];
+        for codegen in &codegens {
+            subscriptions.push(cx.observe(codegen, Self::handle_codegen_changed));
+        }
 
         if let Some(semantic_index) = semantic_index.clone() {
             subscriptions.push(cx.observe(&semantic_index, Self::semantic_index_changed));
@@ -2841,13 +4059,15 @@ impl InlineAssistant {
             prompt_history,
             prompt_history_ix: None,
             pending_prompt: String::new(),
-            codegen,
+            codegens,
             _subscriptions: subscriptions,
             retrieve_context,
             semantic_permissioned: None,
             semantic_index,
             project: project.downgrade(),
             maintain_rate_limit: None,
+            library_prompt,
+            prompt_library,
         };
 
         assistant.index_project(cx).log_err();
@@ -2916,7 +4136,7 @@ impl InlineAssistant {
     }
 
     fn handle_codegen_changed(&mut self, _: Model<Codegen>, cx: &mut ViewContext<Self>) {
-        let is_read_only = !self.codegen.read(cx).idle();
+        let is_read_only = self.codegens.iter().any(|codegen| !codegen.read(cx).idle());
         self.prompt_editor.update(cx, |editor, cx| {
             let was_read_only = editor.read_only(cx);
             if was_read_only != is_read_only {
@@ -2931,6 +4151,12 @@ impl InlineAssistant {
         cx.notify();
     }
 
+    fn error<'a>(&self, cx: &'a AppContext) -> Option<&'a anyhow::Error> {
+        self.codegens
+            .iter()
+            .find_map(|codegen| codegen.read(cx).error())
+    }
+
     fn cancel(&mut self, _: &editor::actions::Cancel, cx: &mut ViewContext<Self>) {
         cx.emit(InlineAssistantEvent::Canceled);
     }
@@ -2948,6 +4174,7 @@ impl InlineAssistant {
                 prompt,
                 include_conversation: self.include_conversation,
                 retrieve_context: self.retrieve_context,
+                library_prompt: self.library_prompt.clone(),
             });
             self.confirmed = true;
             cx.notify();
@@ -3114,6 +4341,37 @@ impl InlineAssistant {
         cx.notify();
     }
 
+    fn cycle_prompt(&mut self, _: &CyclePrompt, cx: &mut ViewContext<Self>) {
+        self.library_prompt = self
+            .prompt_library
+            .read(cx)
+            .next_prompt(self.library_prompt.as_deref());
+        cx.emit(InlineAssistantEvent::LibraryPromptChanged {
+            library_prompt: self.library_prompt.clone(),
+        });
+        cx.notify();
+    }
+
+    fn render_library_prompt(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
+        if self.library_prompt.is_none() && self.prompt_library.read(cx).prompts().is_empty() {
+            return None;
+        }
+
+        let label = self
+            .library_prompt
+            .clone()
+            .unwrap_or_else(|| "No Prompt".into());
+        Some(
+            div().pr_2().child(
+                Button::new("library_prompt", label)
+                    .label_size(LabelSize::Small)
+                    .disabled(self.confirmed)
+                    .tooltip(|cx| Tooltip::for_action("Change Prompt", &CyclePrompt, cx))
+                    .on_click(cx.listener(|this, _, cx| this.cycle_prompt(&CyclePrompt, cx))),
+            ),
+        )
+    }
+
     fn move_up(&mut self, _: &MoveUp, cx: &mut ViewContext<Self>) {
         if let Some(ix) = self.prompt_history_ix {
             if ix > 0 {
@@ -3192,11 +4450,111 @@ struct BlockMeasurements {
 struct PendingInlineAssist {
     editor: WeakView<Editor>,
     inline_assistant: Option<(BlockId, View<InlineAssistant>)>,
-    codegen: Model<Codegen>,
+    /// One code generation per selection, each streaming its own edits.
+    codegens: Vec<Model<Codegen>>,
+    /// The requests for selections that are waiting for others to finish generating.
+    queued_requests: VecDeque<(Model<Codegen>, Box<dyn CompletionRequest>)>,
     _subscriptions: Vec<Subscription>,
     project: WeakModel<Project>,
 }
 
+impl PendingInlineAssist {
+    fn is_queued(&self, codegen: &Model<Codegen>) -> bool {
+        self.queued_requests
+            .iter()
+            .any(|(queued_codegen, _)| queued_codegen == codegen)
+    }
+}
+
+/// Extends a non-empty range to the start of its first line and the end of
+/// its last line. A range that ends at the start of a line doesn't include it.
+fn expand_to_lines(mut range: Range<Point>, snapshot: &MultiBufferSnapshot) -> Range<Point> {
+    if range.end > range.start {
+        range.start.column = 0;
+        if range.end.column == 0 {
+            range.end.row -= 1;
+        }
+        range.end.column = snapshot.line_len(range.end.row);
+    }
+    range
+}
+
+/// The code generations to run for an inline assist over the given
+/// selections. Selections that span several excerpts of a multibuffer are
+/// split into one transformation per excerpt, and overlapping transformations
+/// are merged.
+fn inline_assist_kinds(
+    selections: &[Selection<Point>],
+    buffer: &MultiBuffer,
+    cx: &AppContext,
+) -> Vec<CodegenKind> {
+    let snapshot = buffer.snapshot(cx);
+    let mut transform_ranges = Vec::<Range<Point>>::new();
+    let mut generate_positions = Vec::<Point>::new();
+    for selection in selections {
+        if selection.is_empty() {
+            generate_positions.push(selection.head());
+            continue;
+        }
+
+        for (excerpt_buffer, range, excerpt_id) in
+            buffer.range_to_buffer_ranges(selection.range(), cx)
+        {
+            if range.is_empty() {
+                continue;
+            }
+            let excerpt_buffer = excerpt_buffer.read(cx);
+            let start = snapshot
+                .anchor_in_excerpt(excerpt_id, excerpt_buffer.anchor_after(range.start))
+                .to_point(&snapshot);
+            let end = snapshot
+                .anchor_in_excerpt(excerpt_id, excerpt_buffer.anchor_before(range.end))
+                .to_point(&snapshot);
+            let range = expand_to_lines(start..end, &snapshot);
+            match transform_ranges.last_mut() {
+                Some(last_range) if range.start <= last_range.end => {
+                    last_range.end = cmp::max(last_range.end, range.end);
+                }
+                _ => transform_ranges.push(range),
+            }
+        }
+    }
+
+    // Cursors inside of a transformed range would edit the same text twice.
+    generate_positions.dedup();
+    generate_positions.retain(|position| {
+        !transform_ranges
+            .iter()
+            .any(|range| range.start <= *position && *position <= range.end)
+    });
+
+    let mut kinds = transform_ranges
+        .into_iter()
+        .map(|range| CodegenKind::Transform {
+            range: snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end),
+        })
+        .collect::<Vec<_>>();
+    kinds.extend(
+        generate_positions
+            .into_iter()
+            .map(|position| CodegenKind::Generate {
+                position: snapshot.anchor_after(position),
+            }),
+    );
+    kinds
+}
+
+/// A selection that an inline assist transforms, and what's needed to build
+/// the request for it.
+struct InlineAssistTarget {
+    codegen: Model<Codegen>,
+    buffer: BufferSnapshot,
+    range: Range<usize>,
+    language_name: Option<String>,
+    preamble: Option<String>,
+    temperature: f32,
+}
+
 fn merge_ranges(ranges: &mut Vec<Range<Anchor>>, buffer: &MultiBufferSnapshot) {
     ranges.sort_unstable_by(|a, b| {
         a.start
@@ -3224,8 +4582,10 @@ mod tests {
     use super::*;
     use crate::MessageId;
     use ai::test::FakeCompletionProvider;
-    use gpui::{AppContext, TestAppContext};
+    use editor::ExcerptRange;
+    use gpui::{AppContext, TestAppContext, VisualTestContext};
     use settings::SettingsStore;
+    use workspace::AppState;
 
     #[gpui::test]
     fn test_inserting_and_removing_messages(cx: &mut AppContext) {
@@ -3585,7 +4945,6 @@ mod tests {
 
         let deserialized_conversation = Conversation::deserialize(
             conversation.read_with(cx, |conversation, cx| conversation.serialize(cx)),
-            Default::default(),
             registry.clone(),
             &mut cx.to_async(),
         )
@@ -3607,6 +4966,259 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_context_sections(cx: &mut TestAppContext) {
+        let settings_store = cx.update(SettingsStore::test);
+        cx.set_global(settings_store);
+        cx.update(init);
+        let registry = Arc::new(LanguageRegistry::test());
+        let completion_provider = Arc::new(FakeCompletionProvider::new());
+        let conversation =
+            cx.new_model(|cx| Conversation::new(registry.clone(), cx, completion_provider));
+        let buffer = conversation.read_with(cx, |conversation, _| conversation.buffer.clone());
+        buffer.update(cx, |buffer, cx| {
+            buffer.edit([(0..0, "/file a.txt\nwhat does this do?")], None, cx)
+        });
+
+        let command_range = buffer.read_with(cx, |buffer, _| {
+            buffer.anchor_after(0)..buffer.anchor_before("/file a.txt".len())
+        });
+        let section_id = conversation.update(cx, |conversation, cx| {
+            conversation.insert_context_section(
+                command_range,
+                "file".into(),
+                Some("a.txt".into()),
+                Task::ready(Ok(PromptContextSection::new("File `a.txt`", "one"))),
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "/file a.txt\nFile `a.txt`\n```\none\n```\nwhat does this do?"
+        );
+        conversation.read_with(cx, |conversation, cx| {
+            let section = &conversation.context_sections[0];
+            assert_eq!(section.title, "File `a.txt`");
+            assert!(matches!(section.status, ContextSectionStatus::Done));
+            assert_eq!(
+                section.fold_range(conversation.buffer.read(cx)),
+                Some(11..36)
+            );
+        });
+
+        // Refreshing the section replaces its output.
+        conversation.update(cx, |conversation, cx| {
+            conversation.refresh_context_section(
+                section_id,
+                Some("a.txt".into()),
+                Task::ready(Ok(PromptContextSection::new("File `a.txt`", "two\nthree"))),
+                cx,
+            )
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "/file a.txt\nFile `a.txt`\n```\ntwo\nthree\n```\nwhat does this do?"
+        );
+
+        let deserialized_conversation = Conversation::deserialize(
+            conversation.read_with(cx, |conversation, cx| conversation.serialize(cx)),
+            registry.clone(),
+            &mut cx.to_async(),
+        )
+        .await
+        .unwrap();
+        deserialized_conversation.read_with(cx, |conversation, cx| {
+            let section = &conversation.context_sections[0];
+            assert_eq!(section.id, section_id);
+            assert_eq!(section.name, "file");
+            assert_eq!(section.argument.as_deref(), Some("a.txt"));
+            assert_eq!(
+                section.fold_range(conversation.buffer.read(cx)),
+                Some(11..42)
+            );
+        });
+
+        // Removing the command removes the section.
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..11, "")], None, cx));
+        conversation.read_with(cx, |conversation, _| {
+            assert!(conversation.context_sections.is_empty())
+        });
+    }
+
+    #[gpui::test]
+    fn test_inline_assist_kinds(cx: &mut AppContext) {
+        let buffer_1 = cx.new_model(|_| Buffer::new(0, BufferId::new(1).unwrap(), "aaa\nbbb\nccc"));
+        let buffer_2 = cx.new_model(|_| Buffer::new(0, BufferId::new(2).unwrap(), "xxx\nyyy\nzzz"));
+        let multibuffer = cx.new_model(|cx| {
+            let mut multibuffer = MultiBuffer::new(0, language::Capability::ReadWrite);
+            multibuffer.push_excerpts(
+                buffer_1,
+                [ExcerptRange {
+                    context: Point::new(0, 0)..Point::new(1, 3),
+                    primary: None,
+                }],
+                cx,
+            );
+            multibuffer.push_excerpts(
+                buffer_2,
+                [ExcerptRange {
+                    context: Point::new(1, 0)..Point::new(2, 3),
+                    primary: None,
+                }],
+                cx,
+            );
+            multibuffer
+        });
+        let snapshot = multibuffer.read(cx).snapshot(cx);
+        assert_eq!(snapshot.text(), "aaa\nbbb\nyyy\nzzz");
+
+        let selections = [
+            Point::new(0, 0)..Point::new(0, 1),
+            Point::new(0, 2)..Point::new(0, 3),
+            Point::new(1, 1)..Point::new(2, 1),
+            Point::new(3, 1)..Point::new(3, 1),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, range)| Selection {
+            id,
+            start: range.start,
+            end: range.end,
+            reversed: false,
+            goal: language::SelectionGoal::None,
+        })
+        .collect::<Vec<_>>();
+
+        // Selections on the same line are merged, and a selection spanning two
+        // excerpts is transformed separately in each of them.
+        let kinds = inline_assist_kinds(&selections, multibuffer.read(cx), cx)
+            .into_iter()
+            .map(|kind| match kind {
+                CodegenKind::Transform { range } => {
+                    Ok(range.start.to_point(&snapshot)..range.end.to_point(&snapshot))
+                }
+                CodegenKind::Generate { position } => Err(position.to_point(&snapshot)),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                Ok(Point::new(0, 0)..Point::new(0, 3)),
+                Ok(Point::new(1, 0)..Point::new(1, 3)),
+                Ok(Point::new(2, 0)..Point::new(2, 3)),
+                Err(Point::new(3, 1)),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_inline_assist_over_several_selections(cx: &mut TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let app_state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            Project::init_settings(cx);
+            init(cx);
+            app_state
+        });
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let panel = workspace
+            .update(cx, |_, cx| {
+                AssistantPanel::load(cx.view().downgrade(), cx.to_async())
+            })
+            .await
+            .unwrap();
+        let completion_provider = Arc::new(FakeCompletionProvider::new());
+        panel.update(cx, |panel, _| {
+            panel.completion_provider = completion_provider.clone() as Arc<dyn CompletionProvider>;
+        });
+        workspace.update(cx, |workspace, cx| workspace.add_panel(panel.clone(), cx));
+
+        let buffer = cx.new_model(|_| {
+            Buffer::new(
+                0,
+                BufferId::new(1).unwrap(),
+                "one\ntwo\nthree\nfour\nfive\n",
+            )
+        });
+        let multibuffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+        let editor =
+            cx.new_view(|cx| Editor::for_multibuffer(multibuffer, Some(project.clone()), cx));
+        workspace.update(cx, |workspace, cx| {
+            workspace.add_item(Box::new(editor.clone()), cx)
+        });
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |selections| {
+                selections.select_ranges((0..5).map(|row| Point::new(row, 1)..Point::new(row, 2)))
+            })
+        });
+
+        panel.update(cx, |panel, cx| {
+            panel.new_inline_assist(&editor, cx, &project);
+            panel.confirm_inline_assist(0, "uppercase", false, cx, false, None);
+        });
+        cx.run_until_parked();
+
+        // Only some of the selections are generated at once, and the rest start
+        // as the others finish. The most recently started one is streamed first.
+        let limit = AssistantPanel::MAX_CONCURRENT_INLINE_CODEGENS;
+        assert_eq!(completion_provider.pending_completion_count(), limit);
+        for word in ["FOUR", "FIVE", "THREE", "TWO", "ONE"] {
+            let (start, end) = word.split_at(2);
+            completion_provider.send_completion(start);
+            cx.run_until_parked();
+            completion_provider.send_completion(end);
+            cx.run_until_parked();
+            completion_provider.finish_completion();
+            cx.run_until_parked();
+            assert!(completion_provider.pending_completion_count() <= limit);
+        }
+        assert_eq!(completion_provider.pending_completion_count(), 0);
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "ONE\nTWO\nTHREE\nFOUR\nFIVE\n"
+        );
+
+        // Each selection's edits can be rejected or accepted on their own.
+        let review_hunk_at = |row: u32, accept: bool, cx: &mut VisualTestContext| {
+            editor.update(cx, |editor, cx| {
+                editor.change_selections(None, cx, |selections| {
+                    selections.select_ranges([Point::new(row, 1)..Point::new(row, 1)])
+                })
+            });
+            workspace.update(cx, |workspace, cx| {
+                AssistantPanel::review_inline_hunk(workspace, accept, cx)
+            });
+        };
+        review_hunk_at(1, false, cx);
+        review_hunk_at(2, true, cx);
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "ONE\ntwo\nTHREE\nFOUR\nFIVE\n"
+        );
+        panel.read_with(cx, |panel, _| {
+            assert_eq!(panel.pending_inline_assists[&0].codegens.len(), 3);
+        });
+
+        // Rejecting the rest keeps the accepted edits.
+        workspace.update(cx, |workspace, cx| {
+            AssistantPanel::review_all_inline_hunks(workspace, false, cx)
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            buffer.read_with(cx, |buffer, _| buffer.text()),
+            "one\ntwo\nTHREE\nfour\nfive\n"
+        );
+        panel.read_with(cx, |panel, _| {
+            assert!(panel.pending_inline_assists.is_empty())
+        });
+    }
+
     fn messages(
         conversation: &Model<Conversation>,
         cx: &AppContext,
@@ -3633,8 +5245,9 @@ fn report_assistant_event(
     let telemetry = client.telemetry();
 
     let model = AssistantSettings::get_global(cx)
-        .default_open_ai_model
+        .default_model
+        .model
         .clone();
 
-    telemetry.report_assistant_event(conversation_id, assistant_kind, model.full_name())
+    telemetry.report_assistant_event(conversation_id, assistant_kind, model)
 }
@@ -1,45 +1,81 @@
+use ai::providers::open_ai::OPEN_AI_API_URL;
 use anyhow;
 use gpui::Pixels;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use std::collections::BTreeMap;
 
+/// The API spoken by a language model provider.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageModelProviderKind {
+    /// OpenAI's chat completions API, or any server compatible with it.
+    OpenAi,
+    /// Anthropic's messages API.
+    Anthropic,
+    /// The native chat API of an Ollama server.
+    Ollama,
+}
+
+/// A server that language models can be requested from.
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
-pub enum OpenAiModel {
-    #[serde(rename = "gpt-3.5-turbo-0613")]
-    ThreePointFiveTurbo,
-    #[serde(rename = "gpt-4-0613")]
-    Four,
-    #[serde(rename = "gpt-4-1106-preview")]
-    FourTurbo,
-}
-
-impl OpenAiModel {
-    pub fn full_name(&self) -> &'static str {
-        match self {
-            OpenAiModel::ThreePointFiveTurbo => "gpt-3.5-turbo-0613",
-            OpenAiModel::Four => "gpt-4-0613",
-            OpenAiModel::FourTurbo => "gpt-4-1106-preview",
-        }
-    }
+pub struct LanguageModelProviderSettings {
+    /// The name used to refer to this provider from other settings.
+    pub name: String,
+    /// The API spoken by the provider.
+    pub kind: LanguageModelProviderKind,
+    /// The base URL of the provider's API.
+    pub api_url: String,
+    /// Whether requests must include an API key. Local OpenAI-compatible
+    /// servers usually don't need one. Only used by "open_ai" providers.
+    #[serde(default = "default_requires_api_key")]
+    pub requires_api_key: bool,
+    /// Extra headers to send with every request.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The models that the assistant panel lets you choose from.
+    #[serde(default)]
+    pub models: Vec<LanguageModelSettings>,
+}
 
-    pub fn short_name(&self) -> &'static str {
-        match self {
-            OpenAiModel::ThreePointFiveTurbo => "gpt-3.5-turbo",
-            OpenAiModel::Four => "gpt-4",
-            OpenAiModel::FourTurbo => "gpt-4-turbo",
-        }
+fn default_requires_api_key() -> bool {
+    true
+}
+
+impl LanguageModelProviderSettings {
+    pub fn model(&self, name: &str) -> Option<&LanguageModelSettings> {
+        self.models.iter().find(|model| model.name == name)
     }
+}
 
-    pub fn cycle(&self) -> Self {
-        match self {
-            OpenAiModel::ThreePointFiveTurbo => OpenAiModel::Four,
-            OpenAiModel::Four => OpenAiModel::FourTurbo,
-            OpenAiModel::FourTurbo => OpenAiModel::ThreePointFiveTurbo,
-        }
+/// A model offered by a provider.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct LanguageModelSettings {
+    /// The name of the model, as sent to the provider.
+    pub name: String,
+    /// A shorter name to display in the assistant panel.
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// How many tokens fit in the model's context window. When omitted, a
+    /// provider-specific default is used.
+    #[serde(default)]
+    pub context_window: Option<usize>,
+}
+
+impl LanguageModelSettings {
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
     }
 }
 
+/// Identifies a model by the name of its provider and its own name.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+pub struct LanguageModelReference {
+    pub provider: String,
+    pub model: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AssistantDockPosition {
@@ -54,7 +90,96 @@ pub struct AssistantSettings {
     pub dock: AssistantDockPosition,
     pub default_width: Pixels,
     pub default_height: Pixels,
-    pub default_open_ai_model: OpenAiModel,
+    pub default_model: LanguageModelReference,
+    pub providers: Vec<LanguageModelProviderSettings>,
+    pub tools: bool,
+    pub inline_completions: InlineCompletionSettings,
+    pub commit_messages: CommitMessageSettings,
+}
+
+impl AssistantSettings {
+    pub fn provider(&self, name: &str) -> Option<&LanguageModelProviderSettings> {
+        self.providers.iter().find(|provider| provider.name == name)
+    }
+
+    /// Returns the settings of the given model's provider, falling back to
+    /// OpenAI if no provider with that name is configured.
+    pub fn provider_for(&self, model: &LanguageModelReference) -> LanguageModelProviderSettings {
+        self.provider(&model.provider)
+            .cloned()
+            .unwrap_or_else(|| LanguageModelProviderSettings {
+                name: model.provider.clone(),
+                kind: LanguageModelProviderKind::OpenAi,
+                api_url: OPEN_AI_API_URL.into(),
+                requires_api_key: true,
+                headers: Default::default(),
+                models: Default::default(),
+            })
+    }
+
+    pub fn model(&self, model: &LanguageModelReference) -> Option<&LanguageModelSettings> {
+        self.provider(&model.provider)?.model(&model.model)
+    }
+
+    pub fn model_display_name<'a>(&'a self, model: &'a LanguageModelReference) -> &'a str {
+        self.model(model)
+            .map_or(model.model.as_str(), |model| model.display_name())
+    }
+
+    /// Returns the model listed after the given one, across all providers.
+    pub fn next_model(&self, model: &LanguageModelReference) -> LanguageModelReference {
+        let models = self
+            .providers
+            .iter()
+            .flat_map(|provider| {
+                provider.models.iter().map(|model| LanguageModelReference {
+                    provider: provider.name.clone(),
+                    model: model.name.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let next_ix = models
+            .iter()
+            .position(|candidate| candidate == model)
+            .map_or(0, |ix| ix + 1);
+        models
+            .get(next_ix)
+            .or(models.first())
+            .cloned()
+            .unwrap_or_else(|| model.clone())
+    }
+
+    /// Finds the provider of a model saved without one, which is the case for
+    /// conversations saved before providers were configurable.
+    pub fn resolve_model(&self, provider: Option<String>, model: String) -> LanguageModelReference {
+        let provider = provider.unwrap_or_else(|| {
+            self.providers
+                .iter()
+                .find(|provider| provider.model(&model).is_some())
+                .map_or_else(
+                    || self.default_model.provider.clone(),
+                    |provider| provider.name.clone(),
+                )
+        });
+        LanguageModelReference { provider, model }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct InlineCompletionSettings {
+    pub provider: String,
+    pub model: String,
+    pub prompt_template: String,
+    pub stop: Vec<String>,
+    pub prefix_lines: u32,
+    pub suffix_lines: u32,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct CommitMessageSettings {
+    pub prompt_template: String,
+    pub recent_commits: usize,
+    pub max_diff_len: usize,
 }
 
 /// Assistant panel settings
@@ -76,10 +201,72 @@ pub struct AssistantSettingsContent {
     ///
     /// Default: 320
     pub default_height: Option<f32>,
-    /// The default OpenAI model to use when starting new conversations.
+    /// The model to use when starting new conversations.
+    ///
+    /// Default: {"provider": "openai", "model": "gpt-4-1106-preview"}
+    pub default_model: Option<LanguageModelReference>,
+    /// Deprecated: use `default_model` instead.
+    pub default_open_ai_model: Option<String>,
+    /// The servers that language models can be requested from.
+    pub providers: Option<Vec<LanguageModelProviderSettings>>,
+    /// Whether the assistant can call tools that read files, search the project
+    /// and query symbols, and propose edits for you to review.
     ///
-    /// Default: gpt-4-1106-preview
-    pub default_open_ai_model: Option<OpenAiModel>,
+    /// Default: true
+    pub tools: Option<bool>,
+    /// Settings for the fill-in-the-middle model that provides inline completions
+    /// when `features.inline_completion_provider` is set to `assistant`.
+    pub inline_completions: Option<InlineCompletionSettingsContent>,
+    /// Settings for generating commit messages from the staged changes.
+    pub commit_messages: Option<CommitMessageSettingsContent>,
+}
+
+/// Fill-in-the-middle inline completion settings
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct InlineCompletionSettingsContent {
+    /// The name of the provider that serves the model.
+    ///
+    /// Default: ollama
+    pub provider: Option<String>,
+    /// The model used to fill in the text at the cursor.
+    ///
+    /// Default: codellama:7b-code
+    pub model: Option<String>,
+    /// The prompt sent to the model. `{prefix}` and `{suffix}` are replaced with
+    /// the text before and after the cursor.
+    ///
+    /// Default: <PRE> {prefix} <SUF>{suffix} <MID>
+    pub prompt_template: Option<String>,
+    /// Sequences that end the completion.
+    ///
+    /// Default: ["<EOT>"]
+    pub stop: Option<Vec<String>>,
+    /// How many lines before the cursor to include in the prompt.
+    ///
+    /// Default: 64
+    pub prefix_lines: Option<u32>,
+    /// How many lines after the cursor to include in the prompt.
+    ///
+    /// Default: 16
+    pub suffix_lines: Option<u32>,
+}
+
+/// Commit message generation settings
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct CommitMessageSettingsContent {
+    /// The prompt sent to the default model. `{diff}` is replaced with the
+    /// staged diff, and `{recent_commits}` with the subjects of recent commits.
+    pub prompt_template: Option<String>,
+    /// How many recent commit subjects to include in the prompt as examples
+    /// of the repository's style.
+    ///
+    /// Default: 10
+    pub recent_commits: Option<usize>,
+    /// The maximum length of the staged diff included in the prompt, in bytes.
+    /// Longer diffs are truncated.
+    ///
+    /// Default: 24000
+    pub max_diff_len: Option<usize>,
 }
 
 impl Settings for AssistantSettings {
@@ -92,6 +279,19 @@ impl Settings for AssistantSettings {
         user_values: &[&Self::FileContent],
         _: &mut gpui::AppContext,
     ) -> anyhow::Result<Self> {
-        Self::load_via_json_merge(default_value, user_values)
+        let mut settings = Self::load_via_json_merge(default_value, user_values)?;
+        let sets_default_model = user_values
+            .iter()
+            .any(|value| value.default_model.is_some());
+        if !sets_default_model {
+            if let Some(model) = user_values
+                .iter()
+                .rev()
+                .find_map(|value| value.default_open_ai_model.clone())
+            {
+                settings.default_model = settings.resolve_model(None, model);
+            }
+        }
+        Ok(settings)
     }
 }
@@ -0,0 +1,332 @@
+use crate::{
+    assistant_settings::{AssistantSettings, CommitMessageSettings},
+    completion_provider::build_completion_provider,
+    prompt_library::fill_template,
+    GenerateCommitMessage,
+};
+use ai::{
+    completion::CompletionProvider,
+    providers::open_ai::{OpenAiRequest, RequestMessage, Role},
+};
+use anyhow::{anyhow, Context as _, Result};
+use editor::Editor;
+use fs::repository::GitRepository;
+use futures::StreamExt;
+use gpui::{AppContext, Global, Model, Task, ViewContext, WeakModel};
+use language::{Buffer, ToOffset};
+use parking_lot::Mutex;
+use settings::Settings;
+use std::{ops::Range, sync::Arc};
+use workspace::Workspace;
+
+/// The name of the bundled language used for `COMMIT_EDITMSG` and friends.
+const GIT_COMMIT_LANGUAGE: &str = "Git Commit";
+
+/// Commit messages that are being streamed into buffers, so that generating a
+/// message again for the same buffer cancels the previous one.
+#[derive(Default)]
+struct PendingCommitMessages(Vec<(WeakModel<Buffer>, Task<()>)>);
+
+impl Global for PendingCommitMessages {}
+
+pub(crate) fn init(cx: &mut AppContext) {
+    cx.set_global(PendingCommitMessages::default());
+    cx.observe_new_views(
+        |workspace: &mut Workspace, _cx: &mut ViewContext<Workspace>| {
+            workspace.register_action(generate_commit_message_for_active_editor);
+        },
+    )
+    .detach();
+}
+
+fn generate_commit_message_for_active_editor(
+    workspace: &mut Workspace,
+    _: &GenerateCommitMessage,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace
+        .active_item(cx)
+        .and_then(|item| item.act_as::<Editor>(cx))
+    else {
+        return;
+    };
+    let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+        return;
+    };
+    let (is_commit_message, message_path) = {
+        let buffer = buffer.read(cx);
+        let is_commit_message = buffer.language().map_or(false, |language| {
+            language.name().as_ref() == GIT_COMMIT_LANGUAGE
+        });
+        let message_path = buffer
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx));
+        (is_commit_message, message_path)
+    };
+    if !is_commit_message {
+        return;
+    }
+    let Some(message_path) = message_path else {
+        return;
+    };
+
+    // Git writes the message file into the repository's git directory, which is
+    // also where linked worktrees keep their own HEAD and index.
+    let fs = workspace.app_state().fs.clone();
+    let settings = AssistantSettings::get_global(cx);
+    let commit_settings = settings.commit_messages.clone();
+    let model = settings.default_model.clone();
+    let completion_provider = build_completion_provider(&model, cx);
+    let buffer_handle = buffer.downgrade();
+    let task = cx.spawn(|workspace, mut cx| async move {
+        let result = async {
+            let git_dir = message_path
+                .parent()
+                .ok_or_else(|| anyhow!("commit message has no parent directory"))?
+                .to_path_buf();
+            let repo = cx
+                .background_executor()
+                .spawn(async move { fs.open_repo(&git_dir) })
+                .await
+                .ok_or_else(|| anyhow!("the commit message isn't in a git directory"))?;
+            let completion_provider = completion_provider.await;
+            if !completion_provider.has_credentials() {
+                cx.update(|cx| completion_provider.retrieve_credentials(cx))?
+                    .await;
+            }
+            cx.update(|cx| {
+                generate_commit_message(
+                    buffer,
+                    repo,
+                    completion_provider,
+                    model.model,
+                    commit_settings,
+                    cx,
+                )
+            })?
+            .await
+        }
+        .await;
+
+        if let Err(error) = result {
+            log::error!("failed to generate commit message: {error:?}");
+            workspace
+                .update(&mut cx, |workspace, cx| workspace.show_error(&error, cx))
+                .ok();
+        }
+    });
+
+    let pending = &mut cx.global_mut::<PendingCommitMessages>().0;
+    pending.retain(|(buffer, _)| buffer.upgrade().is_some() && *buffer != buffer_handle);
+    pending.push((buffer_handle, task));
+}
+
+/// Replaces the message in a commit buffer with one written by the model for
+/// the repository's staged changes, streaming it in as it is generated. The
+/// comment lines that git appends to the message are kept.
+pub(crate) fn generate_commit_message(
+    buffer: Model<Buffer>,
+    repo: Arc<Mutex<dyn GitRepository>>,
+    completion_provider: Arc<dyn CompletionProvider>,
+    model: String,
+    settings: CommitMessageSettings,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    let git_state = cx.background_executor().spawn(async move {
+        let repo = repo.lock();
+        let diff = repo
+            .staged_diff()
+            .context("failed to read the staged diff")?;
+        let subjects = repo
+            .recent_commit_subjects(settings.recent_commits)
+            .context("failed to read recent commits")?;
+        anyhow::Ok((diff, subjects, settings))
+    });
+
+    cx.spawn(|mut cx| async move {
+        let (diff, subjects, settings) = git_state.await?;
+        if diff.trim().is_empty() {
+            return Err(anyhow!("there are no staged changes to describe"));
+        }
+
+        let prompt = commit_message_prompt(
+            &settings.prompt_template,
+            truncate_diff(&diff, settings.max_diff_len),
+            &subjects,
+        );
+        let request = Box::new(OpenAiRequest {
+            model,
+            messages: vec![RequestMessage {
+                role: Role::User,
+                content: prompt,
+                ..Default::default()
+            }],
+            stream: true,
+            stop: Vec::new(),
+            temperature: 0.2,
+            tools: Vec::new(),
+        });
+        let mut chunks = completion_provider.complete(request).await?;
+
+        // Clear the existing message, keeping a blank line before git's comments,
+        // and insert each chunk at an anchor that stays after the inserted text.
+        let position = buffer.update(&mut cx, |buffer, cx| {
+            let range = message_range(&buffer.text());
+            let separator = if range.end < buffer.len() { "\n\n" } else { "" };
+            buffer.edit([(range, separator)], None, cx);
+            buffer.anchor_after(0)
+        })?;
+
+        let mut started = false;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            let chunk = if started {
+                chunk.as_str()
+            } else {
+                chunk.trim_start()
+            };
+            if chunk.is_empty() {
+                continue;
+            }
+            started = true;
+            buffer.update(&mut cx, |buffer, cx| {
+                let offset = position.to_offset(buffer);
+                buffer.edit([(offset..offset, chunk)], None, cx);
+            })?;
+        }
+
+        // Models often end their reply with a newline, which would otherwise be
+        // followed by the separator.
+        buffer.update(&mut cx, |buffer, cx| {
+            let end = position.to_offset(buffer);
+            let message = buffer.text_for_range(0..end).collect::<String>();
+            let trimmed_len = message.trim_end().len();
+            if trimmed_len < end {
+                buffer.edit([(trimmed_len..end, "")], None, cx);
+            }
+        })?;
+        Ok(())
+    })
+}
+
+/// The range of the message in a commit buffer, which ends where git's comment
+/// lines begin.
+fn message_range(text: &str) -> Range<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.starts_with('#') {
+            break;
+        }
+        offset += line.len();
+    }
+    0..offset
+}
+
+/// Truncates the diff to at most `max_len` bytes, at a line boundary.
+fn truncate_diff(diff: &str, max_len: usize) -> &str {
+    if diff.len() <= max_len {
+        return diff;
+    }
+    let mut end = max_len;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = diff[..end].rfind('\n').map_or(end, |ix| ix + 1);
+    &diff[..end]
+}
+
+/// Substitutes `{diff}` and `{recent_commits}` in the template.
+fn commit_message_prompt(template: &str, diff: &str, recent_commits: &[String]) -> String {
+    fill_template(
+        template,
+        &[
+            ("diff", diff),
+            ("recent_commits", &recent_commits.join("\n")),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai::test::FakeCompletionProvider;
+    use fs::{FakeFs, Fs};
+    use gpui::{Context, TestAppContext};
+    use language::BufferId;
+    use std::path::Path;
+
+    #[gpui::test]
+    async fn test_generate_commit_message(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/repo", serde_json::json!({ ".git": {}, "a.txt": "" }))
+            .await;
+        let dot_git = Path::new("/repo/.git");
+        fs.set_staged_diff_for_repo(dot_git, "--- a/a.txt\n+++ b/a.txt\n+hello\n");
+        fs.set_commit_subjects_for_repo(dot_git, &["Add a.txt", "Initial commit"]);
+        let repo = fs.open_repo(dot_git).unwrap();
+
+        let buffer = cx.new_model(|cx| {
+            Buffer::new(
+                0,
+                BufferId::new(cx.entity_id().as_u64()).unwrap(),
+                "old message\n# Please enter the commit message\n",
+            )
+        });
+        let completion_provider = Arc::new(FakeCompletionProvider::new());
+        let settings = CommitMessageSettings {
+            prompt_template: "{recent_commits}|{diff}".into(),
+            recent_commits: 1,
+            max_diff_len: 1000,
+        };
+        let task = cx.update(|cx| {
+            generate_commit_message(
+                buffer.clone(),
+                repo,
+                completion_provider.clone(),
+                "model".into(),
+                settings,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+
+        completion_provider.send_completion("\nUpdate a.txt");
+        cx.run_until_parked();
+        completion_provider.send_completion(" greeting\n");
+        cx.run_until_parked();
+        completion_provider.finish_completion();
+        task.await.unwrap();
+        buffer.read_with(cx, |buffer, _| {
+            assert_eq!(
+                buffer.text(),
+                "Update a.txt greeting\n\n# Please enter the commit message\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_commit_message_prompt() {
+        assert_eq!(
+            commit_message_prompt(
+                "{recent_commits}\n---\n{diff}",
+                "+{recent_commits}\n",
+                &["Fix a".to_string(), "Add b".to_string()]
+            ),
+            "Fix a\nAdd b\n---\n+{recent_commits}\n"
+        );
+    }
+
+    #[test]
+    fn test_message_range() {
+        assert_eq!(message_range("fix\n\nbody\n# comment\n"), 0..10);
+        assert_eq!(message_range("# comment\n"), 0..0);
+        assert_eq!(message_range("no comments"), 0..11);
+    }
+
+    #[test]
+    fn test_truncate_diff() {
+        assert_eq!(truncate_diff("+a\n+b\n", 10), "+a\n+b\n");
+        assert_eq!(truncate_diff("+a\n+bcd\n", 5), "+a\n");
+    }
+}
@@ -0,0 +1,68 @@
+use crate::assistant_settings::{
+    AssistantSettings, LanguageModelProviderKind, LanguageModelReference,
+};
+use ai::{
+    completion::CompletionProvider,
+    providers::{
+        anthropic::AnthropicCompletionProvider, ollama::OllamaCompletionProvider,
+        open_ai::OpenAiCompletionProvider,
+    },
+};
+use gpui::{AppContext, Task};
+use settings::Settings;
+use std::sync::Arc;
+
+/// Creates a provider for the given model, talking to the server configured
+/// for it in the assistant's `providers` settings.
+pub fn build_completion_provider(
+    model: &LanguageModelReference,
+    cx: &AppContext,
+) -> Task<Arc<dyn CompletionProvider>> {
+    let provider = AssistantSettings::get_global(cx).provider_for(model);
+    let context_window = provider
+        .model(&model.model)
+        .and_then(|model| model.context_window);
+    let headers = provider.headers.into_iter().collect::<Vec<_>>();
+    let api_url = provider.api_url;
+    let requires_api_key = provider.requires_api_key;
+    let kind = provider.kind;
+    let model_name = model.model.clone();
+    let executor = cx.background_executor().clone();
+    cx.foreground_executor().spawn(async move {
+        let provider: Arc<dyn CompletionProvider> = match kind {
+            LanguageModelProviderKind::OpenAi => {
+                let mut provider = OpenAiCompletionProvider::new(model_name, executor)
+                    .await
+                    .with_api_url(api_url)
+                    .with_headers(headers);
+                if let Some(context_window) = context_window {
+                    provider = provider.with_context_window(context_window);
+                }
+                if !requires_api_key {
+                    provider = provider.without_api_key();
+                }
+                Arc::new(provider)
+            }
+            LanguageModelProviderKind::Anthropic => {
+                let mut provider = AnthropicCompletionProvider::new(model_name, executor)
+                    .await
+                    .with_api_url(api_url)
+                    .with_headers(headers);
+                if let Some(context_window) = context_window {
+                    provider = provider.with_context_window(context_window);
+                }
+                Arc::new(provider)
+            }
+            LanguageModelProviderKind::Ollama => {
+                let mut provider = OllamaCompletionProvider::new(model_name, executor)
+                    .with_api_url(api_url)
+                    .with_headers(headers);
+                if let Some(context_window) = context_window {
+                    provider = provider.with_context_window(context_window);
+                }
+                Arc::new(provider)
+            }
+        };
+        provider
+    })
+}
@@ -0,0 +1,445 @@
+use crate::{
+    assistant_settings::LanguageModelReference, persistence::ASSISTANT_DB, MessageId,
+    MessageMetadata, MessageStatus, SavedConversation, SavedMessage,
+};
+use ai::providers::open_ai::Role;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, TimeZone};
+use collections::{HashMap, HashSet};
+use fs::Fs;
+use futures::StreamExt;
+use std::{ffi::OsStr, sync::Arc};
+use util::{paths::CONVERSATIONS_DIR, ResultExt};
+use uuid::Uuid;
+
+/// How many matching messages a history search considers.
+const SEARCH_MESSAGE_LIMIT: usize = 200;
+/// The maximum length of the excerpt shown for a search result, in characters.
+const MATCH_EXCERPT_LEN: usize = 120;
+
+pub(crate) struct SavedConversationMetadata {
+    pub id: String,
+    pub title: String,
+    pub forked_from: Option<String>,
+    pub mtime: DateTime<Local>,
+    /// For search results, an excerpt of the best matching message.
+    pub matching_excerpt: Option<String>,
+}
+
+impl SavedConversationMetadata {
+    /// Lists the saved conversations, most recently updated first.
+    pub fn list() -> Result<Vec<Self>> {
+        Ok(ASSISTANT_DB
+            .conversations()?
+            .into_iter()
+            .map(|(id, title, forked_from, updated_at)| Self {
+                id,
+                title,
+                forked_from,
+                mtime: Local
+                    .timestamp_millis_opt(updated_at)
+                    .single()
+                    .unwrap_or_else(Local::now),
+                matching_excerpt: None,
+            })
+            .collect())
+    }
+
+    /// Lists the conversations with messages containing every word of the query,
+    /// best matches first.
+    pub fn search(query: &str) -> Result<Vec<Self>> {
+        let Some(fts_query) = full_text_query(query) else {
+            return Self::list();
+        };
+
+        let mut conversations = Self::list()?
+            .into_iter()
+            .map(|conversation| (conversation.id.clone(), conversation))
+            .collect::<HashMap<_, _>>();
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        let mut results = Vec::new();
+        for (conversation_id, _, body) in
+            ASSISTANT_DB.search_messages(&fts_query, SEARCH_MESSAGE_LIMIT)?
+        {
+            if let Some(mut conversation) = conversations.remove(&conversation_id) {
+                conversation.matching_excerpt = Some(matching_excerpt(&body, &words));
+                results.push(conversation);
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Saves a conversation to the database and indexes its messages for search.
+pub(crate) async fn save_conversation(
+    conversation: &SavedConversation,
+    updated_at: DateTime<Local>,
+) -> Result<()> {
+    let id = conversation
+        .id
+        .clone()
+        .ok_or_else(|| anyhow!("conversation has no id"))?;
+    let messages = conversation
+        .message_texts()
+        .map(|(id, _, text)| (id.0, text.trim().to_string()))
+        .collect();
+    ASSISTANT_DB
+        .save_conversation(
+            id,
+            conversation.summary.trim().to_string(),
+            conversation.forked_from.clone(),
+            updated_at.timestamp_millis(),
+            serde_json::to_string(conversation)?,
+            messages,
+        )
+        .await
+}
+
+pub(crate) fn load_conversation(id: &str) -> Result<SavedConversation> {
+    let data = ASSISTANT_DB
+        .conversation_data(id)?
+        .ok_or_else(|| anyhow!("no conversation with id {id}"))?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// Imports the conversations that were saved as JSON files before they were
+/// stored in the database. The files are left in place.
+pub(crate) async fn import_legacy_conversations(fs: Arc<dyn Fs>) -> Result<()> {
+    if !fs
+        .metadata(&CONVERSATIONS_DIR)
+        .await?
+        .map_or(false, |metadata| metadata.is_dir)
+    {
+        return Ok(());
+    }
+
+    let mut paths = fs.read_dir(&CONVERSATIONS_DIR).await?;
+    while let Some(path) = paths.next().await {
+        let path = path?;
+        if path.extension() != Some(OsStr::new("json")) {
+            continue;
+        }
+        let Some(mut conversation) = fs
+            .load(&path)
+            .await
+            .log_err()
+            .and_then(|text| serde_json::from_str::<SavedConversation>(&text).log_err())
+        else {
+            continue;
+        };
+
+        // Conversations saved by older versions don't have an id, so use their
+        // path to avoid importing them again.
+        let id = conversation
+            .id
+            .get_or_insert_with(|| path.to_string_lossy().into_owned())
+            .clone();
+        if ASSISTANT_DB.has_conversation(&id)? {
+            continue;
+        }
+        let mtime = fs
+            .metadata(&path)
+            .await?
+            .map_or_else(Local::now, |metadata| metadata.mtime.into());
+        save_conversation(&conversation, mtime).await?;
+    }
+    Ok(())
+}
+
+impl SavedConversation {
+    /// The id, role and text of each message, in order.
+    fn message_texts(&self) -> impl Iterator<Item = (MessageId, Role, &str)> {
+        self.messages.iter().enumerate().map(|(ix, message)| {
+            let end = self
+                .messages
+                .get(ix + 1)
+                .map_or(self.text.len(), |next| next.start);
+            let role = self
+                .message_metadata
+                .get(&message.id)
+                .map_or(Role::User, |metadata| metadata.role);
+            (message.id, role, &self.text[message.start..end])
+        })
+    }
+
+    /// Creates a new conversation containing the messages up to and including
+    /// the given one, which can then diverge from this one.
+    pub fn fork(&self, message_id: MessageId) -> Option<Self> {
+        let ix = self
+            .messages
+            .iter()
+            .position(|message| message.id == message_id)?;
+        let end = self
+            .messages
+            .get(ix + 1)
+            .map_or(self.text.len(), |next| next.start);
+        let messages = self.messages[..=ix].to_vec();
+        let message_ids = messages
+            .iter()
+            .map(|message| message.id)
+            .collect::<HashSet<_>>();
+        let title = self.summary.trim();
+
+        Some(Self {
+            id: Some(Uuid::new_v4().to_string()),
+            zed: self.zed.clone(),
+            version: self.version.clone(),
+            text: self.text[..end].to_string(),
+            message_metadata: self
+                .message_metadata
+                .iter()
+                .filter(|(id, _)| message_ids.contains(id))
+                .map(|(id, metadata)| (*id, metadata.clone()))
+                .collect(),
+            messages,
+            summary: if title.is_empty() {
+                "Fork".into()
+            } else {
+                format!("{title} (fork)")
+            },
+            model: self.model.clone(),
+            provider: self.provider.clone(),
+            context_sections: self
+                .context_sections
+                .iter()
+                .filter(|section| section.output_range.end <= end)
+                .cloned()
+                .collect(),
+            forked_from: self.id.clone(),
+        })
+    }
+
+    /// Renders the conversation as Markdown, with a second-level heading
+    /// naming the role of each message.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n", self.summary.trim());
+        for (_, role, text) in self.message_texts() {
+            markdown.push_str(&format!("\n## {role}\n\n"));
+            let text = text.trim();
+            if !text.is_empty() {
+                markdown.push_str(text);
+                markdown.push('\n');
+            }
+        }
+        markdown
+    }
+
+    /// Parses a conversation exported with [`SavedConversation::to_markdown`].
+    /// Text before the first role heading becomes a message from the user.
+    pub fn from_markdown(markdown: &str, model: &LanguageModelReference) -> Result<Self> {
+        let mut title = None;
+        let mut messages = Vec::<(Role, String)>::new();
+        for line in markdown.lines() {
+            if title.is_none() && messages.is_empty() {
+                if let Some(heading) = line.strip_prefix("# ") {
+                    title = Some(heading.trim().to_string());
+                    continue;
+                }
+            }
+
+            let role = match line.trim_end() {
+                "## User" => Some(Role::User),
+                "## Assistant" => Some(Role::Assistant),
+                "## System" => Some(Role::System),
+                // Tool results can't be sent without the call they answer, which
+                // isn't exported, so they are imported as user messages.
+                "## Tool" => Some(Role::User),
+                _ => None,
+            };
+            if let Some(role) = role {
+                messages.push((role, String::new()));
+            } else if let Some((_, body)) = messages.last_mut() {
+                body.push_str(line);
+                body.push('\n');
+            } else if !line.trim().is_empty() {
+                messages.push((Role::User, format!("{line}\n")));
+            }
+        }
+        if messages.is_empty() {
+            return Err(anyhow!("no messages found in markdown"));
+        }
+
+        let mut text = String::new();
+        let mut saved_messages = Vec::new();
+        let mut message_metadata = HashMap::default();
+        let message_count = messages.len();
+        for (ix, (role, body)) in messages.into_iter().enumerate() {
+            let id = MessageId(ix);
+            saved_messages.push(SavedMessage {
+                id,
+                start: text.len(),
+            });
+            message_metadata.insert(
+                id,
+                MessageMetadata {
+                    role,
+                    sent_at: Local::now(),
+                    status: MessageStatus::Done,
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                },
+            );
+            text.push_str(body.trim());
+            if ix + 1 < message_count {
+                text.push('\n');
+            }
+        }
+
+        Ok(Self {
+            id: Some(Uuid::new_v4().to_string()),
+            zed: "conversation".into(),
+            version: Self::VERSION.into(),
+            text,
+            messages: saved_messages,
+            message_metadata,
+            summary: title.unwrap_or_else(|| "Imported Conversation".into()),
+            model: model.model.clone(),
+            provider: Some(model.provider.clone()),
+            context_sections: Vec::new(),
+            forked_from: None,
+            prompt: None,
+        })
+    }
+}
+
+/// Builds an FTS5 query matching messages that contain every word, treating
+/// each word as a prefix and any FTS5 operators in it as plain text.
+fn full_text_query(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Returns the first line of the message that contains one of the words,
+/// truncated to [`MATCH_EXCERPT_LEN`] characters.
+fn matching_excerpt(body: &str, words: &[&str]) -> String {
+    let words = words
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>();
+    let line = body
+        .lines()
+        .find(|line| {
+            let line = line.to_lowercase();
+            words.iter().any(|word| line.contains(word.as_str()))
+        })
+        .or_else(|| body.lines().next())
+        .unwrap_or_default()
+        .trim();
+    if line.chars().count() > MATCH_EXCERPT_LEN {
+        let mut excerpt = line.chars().take(MATCH_EXCERPT_LEN).collect::<String>();
+        excerpt.push('…');
+        excerpt
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_conversation(messages: &[(Role, &str)]) -> SavedConversation {
+        let markdown = messages
+            .iter()
+            .map(|(role, text)| format!("## {role}\n\n{text}\n"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let model = LanguageModelReference {
+            provider: "openai".into(),
+            model: "gpt-4".into(),
+        };
+        SavedConversation::from_markdown(&format!("# Title\n\n{markdown}"), &model).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let conversation = saved_conversation(&[
+            (Role::System, "Be brief."),
+            (Role::User, "What is Rust?\n\n## Not a role"),
+            (Role::Assistant, "A programming language."),
+        ]);
+        assert_eq!(conversation.summary, "Title");
+        assert_eq!(
+            conversation.text,
+            "Be brief.\nWhat is Rust?\n\n## Not a role\nA programming language."
+        );
+        assert_eq!(
+            conversation
+                .message_texts()
+                .map(|(_, role, text)| (role, text))
+                .collect::<Vec<_>>(),
+            [
+                (Role::System, "Be brief.\n"),
+                (Role::User, "What is Rust?\n\n## Not a role\n"),
+                (Role::Assistant, "A programming language."),
+            ]
+        );
+
+        let markdown = conversation.to_markdown();
+        assert_eq!(
+            markdown,
+            "# Title\n\n## System\n\nBe brief.\n\n## User\n\nWhat is Rust?\n\n## Not a role\n\n## Assistant\n\nA programming language.\n"
+        );
+        let model = LanguageModelReference {
+            provider: "openai".into(),
+            model: "gpt-4".into(),
+        };
+        let reimported = SavedConversation::from_markdown(&markdown, &model).unwrap();
+        assert_eq!(reimported.text, conversation.text);
+        assert_eq!(reimported.to_markdown(), markdown);
+
+        let untitled = SavedConversation::from_markdown("Hello\n", &model).unwrap();
+        assert_eq!(untitled.summary, "Imported Conversation");
+        assert_eq!(untitled.text, "Hello");
+        assert!(SavedConversation::from_markdown("# Empty\n", &model).is_err());
+    }
+
+    #[test]
+    fn test_fork() {
+        let conversation = saved_conversation(&[
+            (Role::User, "one"),
+            (Role::Assistant, "two"),
+            (Role::User, "three"),
+        ]);
+
+        let fork = conversation.fork(MessageId(1)).unwrap();
+        assert_ne!(fork.id, conversation.id);
+        assert_eq!(fork.forked_from, conversation.id);
+        assert_eq!(fork.summary, "Title (fork)");
+        assert_eq!(fork.text, "one\ntwo\n");
+        assert_eq!(fork.messages.len(), 2);
+        assert_eq!(fork.message_metadata.len(), 2);
+        assert!(!fork.message_metadata.contains_key(&MessageId(2)));
+
+        assert_eq!(
+            conversation.fork(MessageId(2)).unwrap().text,
+            conversation.text
+        );
+        assert!(conversation.fork(MessageId(3)).is_none());
+    }
+
+    #[test]
+    fn test_full_text_query() {
+        assert_eq!(full_text_query("  "), None);
+        assert_eq!(
+            full_text_query("parse \"toml\" OR"),
+            Some("\"parse\"* \"\"\"toml\"\"\"* \"OR\"*".into())
+        );
+    }
+
+    #[test]
+    fn test_matching_excerpt() {
+        assert_eq!(
+            matching_excerpt("first line\nsecond TOML line\n", &["toml"]),
+            "second TOML line"
+        );
+        assert_eq!(matching_excerpt("no\nmatch", &["toml"]), "no");
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    assistant_settings::{AssistantSettings, InlineCompletionSettings, LanguageModelReference},
+    completion_provider::build_completion_provider,
+};
+use ai::{
+    completion::CompletionProvider,
+    providers::open_ai::{OpenAiRequest, RequestMessage, Role},
+};
+use editor::{Direction, InlineCompletionProvider};
+use futures::{future::Shared, FutureExt, StreamExt};
+use gpui::{AppContext, EntityId, Model, ModelContext, Task};
+use language::{language_settings::all_language_settings, Bias, Buffer, Point, ToOffset, ToPoint};
+use settings::Settings;
+use std::{sync::Arc, time::Duration};
+use util::TryFutureExt;
+
+pub const FILL_IN_THE_MIDDLE_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Provides inline completions by asking a fill-in-the-middle model to generate
+/// the text between the code before and after the cursor.
+pub struct FillInTheMiddleCompletionProvider {
+    completion_provider: Shared<Task<Arc<dyn CompletionProvider>>>,
+    completion: Option<FillInTheMiddleCompletion>,
+    pending_refresh: Task<Option<()>>,
+}
+
+struct FillInTheMiddleCompletion {
+    buffer_id: EntityId,
+    position: language::Anchor,
+    text: String,
+}
+
+impl FillInTheMiddleCompletionProvider {
+    pub fn new(completion_provider: Arc<dyn CompletionProvider>) -> Self {
+        Self {
+            completion_provider: Task::ready(completion_provider).shared(),
+            completion: None,
+            pending_refresh: Task::ready(None),
+        }
+    }
+
+    /// Creates a provider that talks to the model configured in the assistant's
+    /// `inline_completions` settings.
+    pub fn load(cx: &mut AppContext) -> Self {
+        let completion_provider = build_completion_provider(&Self::configured_model(cx), cx);
+        Self {
+            completion_provider: completion_provider.shared(),
+            completion: None,
+            pending_refresh: Task::ready(None),
+        }
+    }
+
+    /// The model that providers created with [`Self::load`] talk to.
+    pub fn configured_model(cx: &AppContext) -> LanguageModelReference {
+        let settings = &AssistantSettings::get_global(cx).inline_completions;
+        LanguageModelReference {
+            provider: settings.provider.clone(),
+            model: settings.model.clone(),
+        }
+    }
+}
+
+impl InlineCompletionProvider for FillInTheMiddleCompletionProvider {
+    fn is_enabled(
+        &self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &AppContext,
+    ) -> bool {
+        let buffer = buffer.read(cx);
+        let file = buffer.file();
+        let language = buffer.language_at(cursor_position);
+        all_language_settings(file, cx)
+            .inline_completions_enabled(language.as_ref(), file.map(|file| file.path().as_ref()))
+    }
+
+    fn refresh(
+        &mut self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        debounce: bool,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let settings = AssistantSettings::get_global(cx).inline_completions.clone();
+        let snapshot = buffer.read(cx).snapshot();
+        let cursor = cursor_position.to_point(&snapshot);
+        let prefix_start = Point::new(cursor.row.saturating_sub(settings.prefix_lines), 0);
+        let suffix_end = snapshot.clip_point(
+            Point::new(cursor.row.saturating_add(settings.suffix_lines), u32::MAX),
+            Bias::Left,
+        );
+        let prefix = snapshot
+            .text_for_range(prefix_start..cursor)
+            .collect::<String>();
+        let suffix = snapshot
+            .text_for_range(cursor..suffix_end)
+            .collect::<String>();
+        let request = build_request(&settings, &prefix, &suffix);
+        // Anchor the completion before the cursor, so that typing at the cursor
+        // can be matched against the completion's text.
+        let position = snapshot.anchor_before(cursor);
+
+        let completion_provider = self.completion_provider.clone();
+        self.pending_refresh = cx.spawn(|this, mut cx| {
+            async move {
+                if debounce {
+                    cx.background_executor()
+                        .timer(FILL_IN_THE_MIDDLE_DEBOUNCE_TIMEOUT)
+                        .await;
+                }
+
+                let completion_provider = completion_provider.await;
+                if !completion_provider.has_credentials() {
+                    cx.update(|cx| completion_provider.retrieve_credentials(cx))?
+                        .await;
+                }
+
+                let mut chunks = completion_provider.complete(Box::new(request)).await?;
+                let mut text = String::new();
+                while let Some(chunk) = chunks.next().await {
+                    text.push_str(&chunk?);
+                }
+                for stop in &settings.stop {
+                    if let Some(ix) = text.find(stop.as_str()) {
+                        text.truncate(ix);
+                    }
+                }
+
+                this.update(&mut cx, |this, cx| {
+                    this.completion = Some(FillInTheMiddleCompletion {
+                        buffer_id: buffer.entity_id(),
+                        position,
+                        text,
+                    });
+                    cx.notify();
+                })?;
+
+                anyhow::Ok(())
+            }
+            .log_err()
+        });
+    }
+
+    fn cycle(
+        &mut self,
+        _buffer: Model<Buffer>,
+        _cursor_position: language::Anchor,
+        _direction: Direction,
+        _cx: &mut ModelContext<Self>,
+    ) {
+        // Fill-in-the-middle models produce a single completion, so there's nothing to cycle.
+    }
+
+    fn accept(&mut self, _cx: &mut ModelContext<Self>) {
+        self.completion.take();
+    }
+
+    fn discard(&mut self, cx: &mut ModelContext<Self>) {
+        self.completion.take();
+        self.pending_refresh = Task::ready(None);
+        cx.notify();
+    }
+
+    fn active_completion_text<'a>(
+        &'a self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &'a AppContext,
+    ) -> Option<&'a str> {
+        let completion = self.completion.as_ref()?;
+        if completion.buffer_id != buffer.entity_id() {
+            return None;
+        }
+
+        // Hide the part of the completion that was typed since it was requested.
+        let buffer = buffer.read(cx);
+        let start = completion.position.to_offset(buffer);
+        let cursor = cursor_position.to_offset(buffer);
+        if cursor < start {
+            return None;
+        }
+        let typed_text = buffer.text_for_range(start..cursor).collect::<String>();
+        let text = completion.text.strip_prefix(typed_text.as_str())?;
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+}
+
+fn build_request(settings: &InlineCompletionSettings, prefix: &str, suffix: &str) -> OpenAiRequest {
+    OpenAiRequest {
+        model: settings.model.clone(),
+        messages: vec![RequestMessage {
+            role: Role::User,
+            content: fill_in_the_middle_prompt(&settings.prompt_template, prefix, suffix),
+            ..Default::default()
+        }],
+        stream: true,
+        stop: settings.stop.clone(),
+        temperature: 0.,
+        tools: Vec::new(),
+    }
+}
+
+/// Substitutes `{prefix}` and `{suffix}` in the template, without expanding
+/// placeholders that occur in the substituted text itself.
+fn fill_in_the_middle_prompt(template: &str, prefix: &str, suffix: &str) -> String {
+    let mut prompt = String::with_capacity(template.len() + prefix.len() + suffix.len());
+    let mut rest = template;
+    loop {
+        let next_placeholder = [("{prefix}", prefix), ("{suffix}", suffix)]
+            .into_iter()
+            .filter_map(|(placeholder, value)| Some((rest.find(placeholder)?, placeholder, value)))
+            .min_by_key(|(ix, _, _)| *ix);
+        match next_placeholder {
+            Some((ix, placeholder, value)) => {
+                prompt.push_str(&rest[..ix]);
+                prompt.push_str(value);
+                rest = &rest[ix + placeholder.len()..];
+            }
+            None => {
+                prompt.push_str(rest);
+                return prompt;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai::test::FakeCompletionProvider;
+    use editor::{Editor, MultiBuffer};
+    use gpui::{Context, TestAppContext};
+    use language::BufferId;
+    use project::Project;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_fill_in_the_middle_completions(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            cx.set_global(SettingsStore::test(cx));
+            theme::init(theme::LoadThemes::JustBase, cx);
+            client::init_settings(cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init_settings(cx);
+            AssistantSettings::register(cx);
+        });
+
+        let buffer = cx.new_model(|cx| {
+            Buffer::new(
+                0,
+                BufferId::new(cx.entity_id().as_u64()).unwrap(),
+                "fn add(a: i32, b: i32) -> i32 {\n    \n}\n",
+            )
+        });
+        let multibuffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+        let (editor, cx) = cx.add_window_view(|cx| Editor::for_multibuffer(multibuffer, None, cx));
+
+        let completion_provider = Arc::new(FakeCompletionProvider::new());
+        let provider =
+            cx.new_model(|_| FillInTheMiddleCompletionProvider::new(completion_provider.clone()));
+        editor.update(cx, |editor, cx| {
+            editor.set_inline_completion_provider(Some(provider.clone()), cx);
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(1, 4)..Point::new(1, 4)])
+            });
+            editor.show_inline_completion(&Default::default(), cx);
+        });
+        cx.run_until_parked();
+
+        completion_provider.send_completion("a + b<EOT> junk");
+        completion_provider.finish_completion();
+        cx.run_until_parked();
+        editor.update(cx, |editor, cx| {
+            assert!(editor.has_active_inline_completion(cx));
+            assert_eq!(
+                editor.display_text(cx),
+                "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n"
+            );
+
+            // Typing part of the completion keeps showing the rest of it.
+            editor.handle_input("a ", cx);
+            assert!(editor.has_active_inline_completion(cx));
+            assert_eq!(
+                editor.display_text(cx),
+                "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n"
+            );
+            assert_eq!(
+                editor.text(cx),
+                "fn add(a: i32, b: i32) -> i32 {\n    a \n}\n"
+            );
+
+            // Accepting a word only inserts the next word of the completion.
+            editor.accept_inline_completion_word(&Default::default(), cx);
+            assert_eq!(
+                editor.text(cx),
+                "fn add(a: i32, b: i32) -> i32 {\n    a +\n}\n"
+            );
+            assert!(editor.has_active_inline_completion(cx));
+            assert_eq!(
+                editor.display_text(cx),
+                "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n"
+            );
+
+            // Accepting the rest of the line inserts the remaining text.
+            editor.accept_inline_completion_line(&Default::default(), cx);
+            assert!(!editor.has_active_inline_completion(cx));
+            assert_eq!(
+                editor.text(cx),
+                "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n"
+            );
+
+            editor.show_inline_completion(&Default::default(), cx);
+        });
+        cx.run_until_parked();
+
+        completion_provider.send_completion(" // sum");
+        completion_provider.finish_completion();
+        cx.run_until_parked();
+        editor.update(cx, |editor, cx| {
+            assert!(editor.has_active_inline_completion(cx));
+
+            // Discarding the completion forgets it.
+            editor.cancel(&Default::default(), cx);
+            assert!(!editor.has_active_inline_completion(cx));
+        });
+        provider.read_with(cx, |provider, _| assert!(provider.completion.is_none()));
+    }
+
+    #[test]
+    fn test_fill_in_the_middle_prompt() {
+        assert_eq!(
+            fill_in_the_middle_prompt("<PRE> {prefix} <SUF>{suffix} <MID>", "a {suffix}", "c"),
+            "<PRE> a {suffix} <SUF>c <MID>"
+        );
+        assert_eq!(
+            fill_in_the_middle_prompt("{suffix}|{prefix}", "a", "b"),
+            "b|a"
+        );
+    }
+}
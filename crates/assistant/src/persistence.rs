@@ -0,0 +1,190 @@
+use anyhow::Result;
+use db::{define_connection, query, sqlez_macros::sql};
+
+define_connection!(
+    // Current schema shape using pseudo-rust syntax:
+    // conversations(
+    //   id: String,
+    //   title: String,
+    //   forked_from: Option<String>, // The conversation this one was forked from
+    //   updated_at: i64, // Milliseconds since the Unix epoch
+    //   data: String, // The `SavedConversation`, serialized as JSON
+    // )
+    //
+    // conversation_messages(
+    //   conversation_id: String,
+    //   message_id: usize,
+    //   body: String, // Full-text indexed
+    // )
+    pub static ref ASSISTANT_DB: AssistantDb<()> =
+        &[sql!(
+            CREATE TABLE conversations(
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                forked_from TEXT,
+                updated_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            ) STRICT;
+
+            CREATE VIRTUAL TABLE conversation_messages USING fts5(
+                conversation_id UNINDEXED,
+                message_id UNINDEXED,
+                body
+            );
+        )];
+);
+
+impl AssistantDb {
+    /// Saves a conversation, replacing its previously indexed messages.
+    pub async fn save_conversation(
+        &self,
+        id: String,
+        title: String,
+        forked_from: Option<String>,
+        updated_at: i64,
+        data: String,
+        messages: Vec<(usize, String)>,
+    ) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("save_conversation", || {
+                conn.exec_bound(sql!(
+                    INSERT INTO conversations(id, title, forked_from, updated_at, data)
+                    VALUES (?1, ?2, ?3, ?4, ?5)
+                    ON CONFLICT DO UPDATE SET
+                        title = ?2,
+                        forked_from = ?3,
+                        updated_at = ?4,
+                        data = ?5
+                ))?((id.as_str(), title, forked_from, updated_at, data))?;
+
+                conn.exec_bound(sql!(
+                    DELETE FROM conversation_messages WHERE conversation_id = ?
+                ))?(id.as_str())?;
+
+                let mut insert_message = conn.exec_bound(sql!(
+                    INSERT INTO conversation_messages(conversation_id, message_id, body)
+                    VALUES (?, ?, ?)
+                ))?;
+                for (message_id, body) in messages {
+                    insert_message((id.as_str(), message_id, body))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    // Returns the id, title, id of the conversation it was forked from and the
+    // time of the last update, most recently updated first
+    query! {
+        pub fn conversations() -> Result<Vec<(String, String, Option<String>, i64)>> {
+            SELECT id, title, forked_from, updated_at
+            FROM conversations
+            ORDER BY updated_at DESC
+        }
+    }
+
+    query! {
+        pub fn conversation_data(id: &str) -> Result<Option<String>> {
+            SELECT data FROM conversations WHERE id = ?
+        }
+    }
+
+    query! {
+        pub fn has_conversation(id: &str) -> Result<bool> {
+            SELECT EXISTS(SELECT 1 FROM conversations WHERE id = ?)
+        }
+    }
+
+    query! {
+        pub async fn delete_conversation(id: String) -> Result<()> {
+            DELETE FROM conversations WHERE id = ?1;
+            DELETE FROM conversation_messages WHERE conversation_id = ?1;
+        }
+    }
+
+    // Returns the conversation id, message id and body of the messages that
+    // match a full-text query, best matches first
+    query! {
+        pub fn search_messages(query: &str, limit: usize) -> Result<Vec<(String, usize, String)>> {
+            SELECT conversation_id, message_id, body
+            FROM conversation_messages
+            WHERE conversation_messages MATCH ?1
+            ORDER BY rank
+            LIMIT ?2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_conversation_search() {
+        let db = AssistantDb(db::open_test_db("test_conversation_search").await);
+
+        db.save_conversation(
+            "a".into(),
+            "Parsing".into(),
+            None,
+            1,
+            "{}".into(),
+            vec![
+                (0, "How do I parse a TOML file?".into()),
+                (1, "Use the toml crate's from_str function.".into()),
+            ],
+        )
+        .await
+        .unwrap();
+        db.save_conversation(
+            "b".into(),
+            "Parsing (fork)".into(),
+            Some("a".into()),
+            2,
+            "{}".into(),
+            vec![(0, "How do I parse JSON?".into())],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.conversations().unwrap(),
+            [
+                ("b".into(), "Parsing (fork)".into(), Some("a".into()), 2),
+                ("a".into(), "Parsing".into(), None, 1),
+            ]
+        );
+        let mut matches = db.search_messages("\"toml\"", 10).unwrap();
+        matches.sort();
+        assert_eq!(
+            matches,
+            [
+                ("a".into(), 0, "How do I parse a TOML file?".into()),
+                (
+                    "a".into(),
+                    1,
+                    "Use the toml crate's from_str function.".into()
+                ),
+            ]
+        );
+
+        // Saving a conversation again replaces its indexed messages.
+        db.save_conversation(
+            "a".into(),
+            "Parsing".into(),
+            None,
+            3,
+            "{}".into(),
+            vec![(0, "How do I parse YAML?".into())],
+        )
+        .await
+        .unwrap();
+        assert_eq!(db.search_messages("\"toml\"", 10).unwrap(), []);
+        assert_eq!(db.search_messages("\"parse\"", 10).unwrap().len(), 2);
+
+        db.delete_conversation("b".into()).await.unwrap();
+        assert!(!db.has_conversation("b").unwrap());
+        assert_eq!(db.search_messages("\"json\"", 10).unwrap(), []);
+    }
+}
@@ -0,0 +1,439 @@
+use anyhow::Result;
+use editor::{Editor, ToOffset};
+use fs::Fs;
+use futures::StreamExt;
+use gpui::{AppContext, Model, ModelContext, SharedString, Subscription, Task, View};
+use project::Project;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use util::{
+    paths::{LOCAL_PROMPTS_RELATIVE_PATH, LOCAL_RULES_RELATIVE_PATH, PROMPTS_DIR},
+    ResultExt,
+};
+
+/// A reusable system prompt, loaded from a Markdown file in the prompts
+/// directory of the config dir or of a project.
+#[derive(Debug, PartialEq)]
+pub struct Prompt {
+    pub name: SharedString,
+    pub source: PromptSource,
+    pub template: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PromptSource {
+    User,
+    /// The prompt belongs to the worktree with the given root.
+    Project(Arc<Path>),
+}
+
+/// The values substituted for `{language}`, `{file_path}` and `{selection}`
+/// when rendering a prompt.
+#[derive(Clone, Debug, Default)]
+pub struct PromptVariables {
+    pub language: Option<String>,
+    pub file_path: Option<String>,
+    pub selection: Option<String>,
+}
+
+impl PromptVariables {
+    pub fn for_editor(editor: &View<Editor>, cx: &AppContext) -> Self {
+        let editor = editor.read(cx);
+        let buffer = editor.buffer().read(cx);
+        let snapshot = buffer.snapshot(cx);
+        let selection = editor.selections.newest_anchor();
+        let range = selection.start.to_offset(&snapshot)..selection.end.to_offset(&snapshot);
+        let language = snapshot
+            .language_at(range.start)
+            .filter(|language| !Arc::ptr_eq(language, &language::PLAIN_TEXT))
+            .map(|language| language.name().to_string());
+        let file_path = buffer
+            .as_singleton()
+            .and_then(|buffer| buffer.read(cx).file().map(|file| file.full_path(cx)))
+            .map(|path| path.to_string_lossy().into_owned());
+        let selection =
+            (!range.is_empty()).then(|| snapshot.text_for_range(range).collect::<String>());
+        Self {
+            language,
+            file_path,
+            selection,
+        }
+    }
+}
+
+impl Prompt {
+    /// Renders the prompt, substituting its variables. Unknown values are
+    /// replaced with an empty string.
+    pub fn render(&self, variables: &PromptVariables, project_rules: Option<&str>) -> String {
+        fill_template(
+            &self.template,
+            &[
+                ("language", variables.language.as_deref().unwrap_or("")),
+                ("file_path", variables.file_path.as_deref().unwrap_or("")),
+                ("selection", variables.selection.as_deref().unwrap_or("")),
+                ("project_rules", project_rules.unwrap_or("")),
+            ],
+        )
+    }
+
+    fn uses_project_rules(&self) -> bool {
+        self.template.contains("{project_rules}")
+    }
+}
+
+/// The prompts available in a project, along with the project's rules, which
+/// are included in every request made on its behalf.
+pub struct PromptLibrary {
+    fs: Arc<dyn Fs>,
+    project: Model<Project>,
+    user_prompts: Vec<Arc<Prompt>>,
+    project_prompts: Vec<Arc<Prompt>>,
+    project_rules: Option<String>,
+    pending_project_reload: Task<()>,
+    _watch_user_prompts: Task<()>,
+    _subscription: Subscription,
+}
+
+impl PromptLibrary {
+    pub fn new(fs: Arc<dyn Fs>, project: Model<Project>, cx: &mut ModelContext<Self>) -> Self {
+        const PROMPTS_WATCH_DURATION: Duration = Duration::from_millis(100);
+        let _watch_user_prompts = cx.spawn({
+            let fs = fs.clone();
+            |this, mut cx| async move {
+                fs.create_dir(&PROMPTS_DIR).await.log_err();
+                let mut events = fs.watch(&PROMPTS_DIR, PROMPTS_WATCH_DURATION).await;
+                loop {
+                    let prompts = load_prompts(fs.as_ref(), &PROMPTS_DIR, PromptSource::User)
+                        .await
+                        .log_err()
+                        .unwrap_or_default();
+                    if this
+                        .update(&mut cx, |this, cx| {
+                            this.user_prompts = prompts;
+                            cx.notify();
+                        })
+                        .is_err()
+                    {
+                        break;
+                    }
+                    if events.next().await.is_none() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let _subscription = cx.subscribe(&project, |this, _, event, cx| match event {
+            project::Event::WorktreeAdded | project::Event::WorktreeRemoved(_) => {
+                this.reload_project(cx)
+            }
+            project::Event::WorktreeUpdatedEntries(_, entries) => {
+                if entries.iter().any(|(path, _, _)| path.starts_with(".zed")) {
+                    this.reload_project(cx);
+                }
+            }
+            _ => {}
+        });
+
+        let mut this = Self {
+            fs,
+            project,
+            user_prompts: Vec::new(),
+            project_prompts: Vec::new(),
+            project_rules: None,
+            pending_project_reload: Task::ready(()),
+            _watch_user_prompts,
+            _subscription,
+        };
+        this.reload_project(cx);
+        this
+    }
+
+    /// The available prompts, sorted by name. Project prompts take precedence
+    /// over the user's prompts with the same name.
+    pub fn prompts(&self) -> Vec<Arc<Prompt>> {
+        let mut prompts = self.project_prompts.clone();
+        for prompt in &self.user_prompts {
+            if !prompts.iter().any(|existing| existing.name == prompt.name) {
+                prompts.push(prompt.clone());
+            }
+        }
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        prompts
+    }
+
+    pub fn prompt(&self, name: &str) -> Option<Arc<Prompt>> {
+        self.project_prompts
+            .iter()
+            .chain(&self.user_prompts)
+            .find(|prompt| prompt.name.as_ref() == name)
+            .cloned()
+    }
+
+    pub fn project_rules(&self) -> Option<&str> {
+        self.project_rules.as_deref()
+    }
+
+    /// The prompt after the given one, wrapping around to no prompt at all.
+    pub fn next_prompt(&self, current: Option<&str>) -> Option<SharedString> {
+        let prompts = self.prompts();
+        let next_ix = match current {
+            Some(current) => prompts
+                .iter()
+                .position(|prompt| prompt.name.as_ref() == current)
+                .map_or(0, |ix| ix + 1),
+            None => 0,
+        };
+        prompts.get(next_ix).map(|prompt| prompt.name.clone())
+    }
+
+    /// Renders the prompt with the given name, if it exists.
+    pub fn render(&self, prompt: Option<&str>, variables: &PromptVariables) -> Option<String> {
+        let prompt = self.prompt(prompt?)?;
+        Some(prompt.render(variables, self.project_rules()))
+    }
+
+    /// The project's rules, unless the given prompt already includes them.
+    pub fn rules_for(&self, prompt: Option<&str>) -> Option<String> {
+        let rules = self.project_rules()?;
+        let prompt = prompt.and_then(|name| self.prompt(name));
+        if prompt.map_or(false, |prompt| prompt.uses_project_rules()) {
+            None
+        } else {
+            Some(rules.to_string())
+        }
+    }
+
+    /// The system message for a request that uses the given prompt: the
+    /// rendered prompt followed by the project's rules.
+    pub fn system_message(
+        &self,
+        prompt: Option<&str>,
+        variables: &PromptVariables,
+    ) -> Option<String> {
+        let message = self
+            .render(prompt, variables)
+            .into_iter()
+            .chain(self.rules_for(prompt))
+            .filter(|part| !part.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        (!message.is_empty()).then_some(message)
+    }
+
+    fn reload_project(&mut self, cx: &mut ModelContext<Self>) {
+        let project = self.project.read(cx);
+        let roots = if project.is_local() {
+            project
+                .visible_worktrees(cx)
+                .map(|worktree| worktree.read(cx).abs_path())
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let fs = self.fs.clone();
+        self.pending_project_reload = cx.spawn(|this, mut cx| async move {
+            let mut prompts = Vec::new();
+            let mut rules = Vec::new();
+            for root in roots {
+                let prompts_dir = root.join(*LOCAL_PROMPTS_RELATIVE_PATH);
+                let source = PromptSource::Project(root.clone());
+                if let Some(project_prompts) = load_prompts(fs.as_ref(), &prompts_dir, source)
+                    .await
+                    .log_err()
+                {
+                    prompts.extend(project_prompts);
+                }
+                if let Ok(project_rules) = fs.load(&root.join(*LOCAL_RULES_RELATIVE_PATH)).await {
+                    if !project_rules.trim().is_empty() {
+                        rules.push(project_rules.trim().to_string());
+                    }
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.project_prompts = prompts;
+                this.project_rules = (!rules.is_empty()).then(|| rules.join("\n\n"));
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+}
+
+/// Loads the Markdown files in the given directory as prompts named after the
+/// files. A missing directory has no prompts.
+async fn load_prompts(fs: &dyn Fs, dir: &Path, source: PromptSource) -> Result<Vec<Arc<Prompt>>> {
+    if fs.metadata(dir).await?.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = fs.read_dir(dir).await?;
+    let mut prompt_paths = Vec::<PathBuf>::new();
+    while let Some(path) = paths.next().await {
+        let path = path?;
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "md")
+        {
+            prompt_paths.push(path);
+        }
+    }
+    prompt_paths.sort();
+
+    let mut prompts = Vec::new();
+    for path in prompt_paths {
+        let Some(name) = path.file_stem() else {
+            continue;
+        };
+        let name = SharedString::from(name.to_string_lossy().into_owned());
+        if let Some(template) = fs.load(&path).await.log_err() {
+            prompts.push(Arc::new(Prompt {
+                name,
+                source: source.clone(),
+                template,
+            }));
+        }
+    }
+    Ok(prompts)
+}
+
+/// Substitutes each `{name}` in the template with its value, without expanding
+/// placeholders that occur in the substituted values themselves.
+pub fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let placeholders = values
+        .iter()
+        .map(|(name, value)| (format!("{{{name}}}"), *value))
+        .collect::<Vec<_>>();
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    loop {
+        let next_placeholder = placeholders
+            .iter()
+            .filter_map(|(placeholder, value)| Some((rest.find(placeholder)?, placeholder, value)))
+            .min_by_key(|(ix, _, _)| *ix);
+        match next_placeholder {
+            Some((ix, placeholder, value)) => {
+                result.push_str(&rest[..ix]);
+                result.push_str(value);
+                rest = &rest[ix + placeholder.len()..];
+            }
+            None => {
+                result.push_str(rest);
+                return result;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::{Context, TestAppContext};
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_prompt_library(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            PROMPTS_DIR.as_path(),
+            json!({
+                "reviewer.md": "Review this {language} code.",
+                "writer.md": "Write prose.",
+                "notes.txt": "Not a prompt.",
+            }),
+        )
+        .await;
+        fs.insert_tree(
+            "/project",
+            json!({
+                ".zed": {
+                    "rules.md": "Always use anyhow.\n",
+                    "prompts": {
+                        "reviewer.md": "Review {file_path} ({language}).\n{project_rules}",
+                    },
+                },
+                "src": { "main.rs": "" },
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/project".as_ref()], cx).await;
+        let library = cx.new_model(|cx| PromptLibrary::new(fs.clone(), project, cx));
+        cx.run_until_parked();
+
+        library.read_with(cx, |library, _| {
+            let prompts = library.prompts();
+            assert_eq!(
+                prompts
+                    .iter()
+                    .map(|prompt| (prompt.name.as_ref(), prompt.source.clone()))
+                    .collect::<Vec<_>>(),
+                [
+                    (
+                        "reviewer",
+                        PromptSource::Project(Path::new("/project").into())
+                    ),
+                    ("writer", PromptSource::User),
+                ]
+            );
+            assert_eq!(library.project_rules(), Some("Always use anyhow."));
+            assert_eq!(library.next_prompt(None).as_deref(), Some("reviewer"));
+            assert_eq!(
+                library.next_prompt(Some("reviewer")).as_deref(),
+                Some("writer")
+            );
+            assert_eq!(library.next_prompt(Some("writer")), None);
+
+            let variables = PromptVariables {
+                language: Some("Rust".into()),
+                file_path: Some("src/main.rs".into()),
+                selection: None,
+            };
+            assert_eq!(
+                library
+                    .system_message(Some("reviewer"), &variables)
+                    .as_deref(),
+                Some("Review src/main.rs (Rust).\nAlways use anyhow.")
+            );
+            assert_eq!(
+                library
+                    .system_message(Some("writer"), &variables)
+                    .as_deref(),
+                Some("Write prose.\n\nAlways use anyhow.")
+            );
+            assert_eq!(
+                library.system_message(None, &variables).as_deref(),
+                Some("Always use anyhow.")
+            );
+        });
+
+        // Editing the project's rules is picked up by the library.
+        fs.atomic_write("/project/.zed/rules.md".into(), "Prefer iterators.".into())
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        library.read_with(cx, |library, _| {
+            assert_eq!(library.project_rules(), Some("Prefer iterators."));
+        });
+    }
+
+    #[test]
+    fn test_fill_template() {
+        assert_eq!(
+            fill_template("{a} and {b}, not {c}", &[("a", "{b}"), ("b", "two")]),
+            "{b} and two, not {c}"
+        );
+    }
+}
@@ -4,7 +4,6 @@ use ai::prompts::file_context::FileContext;
 use ai::prompts::generate::GenerateInlineContent;
 use ai::prompts::preamble::EngineerPreamble;
 use ai::prompts::repository_context::{PromptCodeSnippet, RepositoryContext};
-use ai::providers::open_ai::OpenAiLanguageModel;
 use language::{BufferSnapshot, OffsetRangeExt, ToOffset};
 use std::cmp::{self, Reverse};
 use std::ops::Range;
@@ -121,17 +120,32 @@ fn summarize(buffer: &BufferSnapshot, selected_range: Range<impl ToOffset>) -> S
     summary
 }
 
+/// A preamble rendered from a prompt in the prompt library, which replaces the
+/// default one.
+struct LibraryPreamble(String);
+
+impl PromptTemplate for LibraryPreamble {
+    fn generate(
+        &self,
+        args: &PromptArguments,
+        _max_token_length: Option<usize>,
+    ) -> anyhow::Result<(String, usize)> {
+        let token_count = args.model.count_tokens(&self.0)?;
+        anyhow::Ok((self.0.clone(), token_count))
+    }
+}
+
 pub fn generate_content_prompt(
     user_prompt: String,
+    preamble: Option<String>,
     language_name: Option<&str>,
     buffer: BufferSnapshot,
     range: Range<usize>,
     search_results: Vec<PromptCodeSnippet>,
-    model: &str,
+    model: Arc<dyn LanguageModel>,
     project_name: Option<String>,
 ) -> anyhow::Result<String> {
     // Using new Prompt Templates
-    let openai_model: Arc<dyn LanguageModel> = Arc::new(OpenAiLanguageModel::load(model));
     let lang_name = if let Some(language_name) = language_name {
         Some(language_name.to_string())
     } else {
@@ -139,7 +153,7 @@ pub fn generate_content_prompt(
     };
 
     let args = PromptArguments {
-        model: openai_model,
+        model,
         language_name: lang_name.clone(),
         project_name,
         snippets: search_results.clone(),
@@ -149,8 +163,12 @@ pub fn generate_content_prompt(
         user_prompt: Some(user_prompt.clone()),
     };
 
+    let preamble: Box<dyn PromptTemplate> = match preamble {
+        Some(preamble) => Box::new(LibraryPreamble(preamble)),
+        None => Box::new(EngineerPreamble {}),
+    };
     let templates: Vec<(PromptPriority, Box<dyn PromptTemplate>)> = vec![
-        (PromptPriority::Mandatory, Box::new(EngineerPreamble {})),
+        (PromptPriority::Mandatory, preamble),
         (
             PromptPriority::Ordered { order: 1 },
             Box::new(RepositoryContext {}),
@@ -0,0 +1,484 @@
+use crate::{
+    AcceptAllProposedEdits, AcceptProposedHunk, RejectAllProposedEdits, RejectProposedHunk,
+};
+use collections::HashSet;
+use editor::{
+    display_map::{BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    Editor, EditorEvent,
+};
+use gpui::{
+    AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, IntoElement, Model, Render,
+    StyledText, Subscription, Task, View, VisualContext,
+};
+use language::{Buffer, BufferId, Capability, Point, ToOffset};
+use multi_buffer::MultiBuffer;
+use project::Project;
+use std::{ops::Range, sync::Arc};
+use ui::{prelude::*, Tooltip};
+use workspace::item::{Item, ItemEvent};
+
+/// How many unchanged lines are shown around each edit.
+const CONTEXT_LINE_COUNT: u32 = 3;
+
+/// The new contents the assistant proposed for a buffer, along with the
+/// ranges of the new text that were edited.
+pub(crate) struct ProposedEdit {
+    pub original: Model<Buffer>,
+    pub new_text: String,
+    pub edited_ranges: Vec<Range<usize>>,
+}
+
+struct ProposedBuffer {
+    original: Model<Buffer>,
+    /// A copy of the original buffer with the edits applied, whose diff base
+    /// is kept in sync with the original buffer's text.
+    proposed: Model<Buffer>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ProposedHunk {
+    buffer_ix: usize,
+    /// The range of the proposed text.
+    range: Range<usize>,
+    /// The range of the original text that the proposed text replaces.
+    original_range: Range<usize>,
+}
+
+/// Shows the edits proposed by the assistant across several files as a diff,
+/// so that each hunk can be accepted into the original buffer or rejected.
+pub struct ProposedEditsView {
+    editor: View<Editor>,
+    buffers: Vec<ProposedBuffer>,
+    hunk_blocks: HashSet<BlockId>,
+    recalculating_diffs: bool,
+    pending_diffs: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ProposedEditsView {
+    pub(crate) fn new(
+        edits: Vec<ProposedEdit>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let multibuffer = cx.new_model(|_| {
+            MultiBuffer::new(0, Capability::ReadWrite).with_title("Proposed Edits".into())
+        });
+        let mut buffers = Vec::new();
+        let mut subscriptions = Vec::new();
+        for edit in edits {
+            let original = edit.original.read(cx);
+            let language = original.language().cloned();
+            let diff_base = original.text();
+            let proposed = cx.new_model(|cx| {
+                let mut buffer = Buffer::new(
+                    0,
+                    BufferId::new(cx.entity_id().as_u64()).unwrap(),
+                    edit.new_text,
+                );
+                buffer.set_language(language, cx);
+                buffer.set_diff_base(Some(diff_base), cx);
+                buffer
+            });
+            multibuffer.update(cx, |multibuffer, cx| {
+                multibuffer.push_excerpts_with_context_lines(
+                    proposed.clone(),
+                    edit.edited_ranges,
+                    CONTEXT_LINE_COUNT,
+                    cx,
+                );
+            });
+            subscriptions.push(cx.subscribe(&edit.original, Self::handle_original_buffer_event));
+            subscriptions.push(cx.subscribe(&proposed, Self::handle_proposed_buffer_event));
+            buffers.push(ProposedBuffer {
+                original: edit.original,
+                proposed,
+            });
+        }
+
+        let editor = cx.new_view(|cx| Editor::for_multibuffer(multibuffer, Some(project), cx));
+        subscriptions.push(cx.subscribe(&editor, |_, _, event: &EditorEvent, cx| {
+            cx.emit(event.clone())
+        }));
+
+        let mut this = Self {
+            editor,
+            buffers,
+            hunk_blocks: HashSet::default(),
+            recalculating_diffs: false,
+            pending_diffs: Task::ready(()),
+            _subscriptions: subscriptions,
+        };
+        this.recalculate_diffs(cx);
+        this
+    }
+
+    fn handle_original_buffer_event(
+        &mut self,
+        original: Model<Buffer>,
+        event: &language::Event,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let language::Event::Edited = event {
+            if let Some(buffer) = self
+                .buffers
+                .iter()
+                .find(|buffer| buffer.original == original)
+            {
+                let text = original.read(cx).text();
+                buffer.proposed.update(cx, |proposed, cx| {
+                    proposed.set_diff_base(Some(text), cx);
+                });
+                self.recalculate_diffs(cx);
+            }
+        }
+    }
+
+    fn handle_proposed_buffer_event(
+        &mut self,
+        _: Model<Buffer>,
+        event: &language::Event,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let language::Event::Edited = event {
+            self.recalculate_diffs(cx);
+        }
+    }
+
+    fn recalculate_diffs(&mut self, cx: &mut ViewContext<Self>) {
+        let recalculations = self
+            .buffers
+            .iter()
+            .filter_map(|buffer| {
+                buffer
+                    .proposed
+                    .update(cx, |proposed, cx| proposed.git_diff_recalc(cx))
+            })
+            .collect::<Vec<_>>();
+        self.recalculating_diffs = true;
+        self.pending_diffs = cx.spawn(|this, mut cx| async move {
+            futures::future::join_all(recalculations).await;
+            this.update(&mut cx, |this, cx| {
+                this.recalculating_diffs = false;
+                this.update_hunk_blocks(cx);
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    fn hunks(&self, cx: &AppContext) -> Vec<ProposedHunk> {
+        let mut hunks = Vec::new();
+        for (buffer_ix, buffer) in self.buffers.iter().enumerate() {
+            let proposed = buffer.proposed.read(cx);
+            let max_point = proposed.max_point();
+            for hunk in proposed.snapshot().git_diff_hunks_in_row_range(0..u32::MAX) {
+                let start = Point::new(hunk.buffer_range.start, 0).min(max_point);
+                let end = Point::new(hunk.buffer_range.end, 0).min(max_point);
+                hunks.push(ProposedHunk {
+                    buffer_ix,
+                    range: start.to_offset(proposed)..end.to_offset(proposed),
+                    original_range: hunk.diff_base_byte_range,
+                });
+            }
+        }
+        hunks
+    }
+
+    fn hunk_at(&self, buffer_ix: usize, offset: usize, cx: &AppContext) -> Option<ProposedHunk> {
+        if self.recalculating_diffs {
+            return None;
+        }
+        self.hunks(cx).into_iter().find(|hunk| {
+            hunk.buffer_ix == buffer_ix && hunk.range.start <= offset && offset <= hunk.range.end
+        })
+    }
+
+    /// Returns the hunk containing the newest cursor.
+    fn hunk_at_cursor(&self, cx: &AppContext) -> Option<ProposedHunk> {
+        let head = self.editor.read(cx).selections.newest_anchor().head();
+        let buffer_id = head.buffer_id?;
+        let buffer_ix = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.proposed.read(cx).remote_id() == buffer_id)?;
+        let offset = head
+            .text_anchor
+            .to_offset(&self.buffers[buffer_ix].proposed.read(cx).snapshot());
+        self.hunk_at(buffer_ix, offset, cx)
+    }
+
+    fn accept_hunks(&mut self, hunks: Vec<ProposedHunk>, cx: &mut ViewContext<Self>) {
+        for (buffer_ix, buffer) in self.buffers.iter().enumerate() {
+            let proposed = buffer.proposed.read(cx);
+            let edits = hunks
+                .iter()
+                .filter(|hunk| hunk.buffer_ix == buffer_ix)
+                .map(|hunk| {
+                    let text = proposed
+                        .text_for_range(hunk.range.clone())
+                        .collect::<String>();
+                    (hunk.original_range.clone(), text)
+                })
+                .collect::<Vec<_>>();
+            if !edits.is_empty() {
+                buffer
+                    .original
+                    .update(cx, |original, cx| original.edit(edits, None, cx));
+            }
+        }
+    }
+
+    fn reject_hunks(&mut self, hunks: Vec<ProposedHunk>, cx: &mut ViewContext<Self>) {
+        for (buffer_ix, buffer) in self.buffers.iter().enumerate() {
+            let original = buffer.original.read(cx);
+            let edits = hunks
+                .iter()
+                .filter(|hunk| hunk.buffer_ix == buffer_ix)
+                .map(|hunk| {
+                    let text = original
+                        .text_for_range(hunk.original_range.clone())
+                        .collect::<String>();
+                    (hunk.range.clone(), text)
+                })
+                .collect::<Vec<_>>();
+            if !edits.is_empty() {
+                buffer
+                    .proposed
+                    .update(cx, |proposed, cx| proposed.edit(edits, None, cx));
+            }
+        }
+    }
+
+    fn accept_hunk(&mut self, _: &AcceptProposedHunk, cx: &mut ViewContext<Self>) {
+        if let Some(hunk) = self.hunk_at_cursor(cx) {
+            self.accept_hunks(vec![hunk], cx);
+        }
+    }
+
+    fn reject_hunk(&mut self, _: &RejectProposedHunk, cx: &mut ViewContext<Self>) {
+        if let Some(hunk) = self.hunk_at_cursor(cx) {
+            self.reject_hunks(vec![hunk], cx);
+        }
+    }
+
+    fn accept_all(&mut self, _: &AcceptAllProposedEdits, cx: &mut ViewContext<Self>) {
+        if !self.recalculating_diffs {
+            let hunks = self.hunks(cx);
+            self.accept_hunks(hunks, cx);
+        }
+    }
+
+    fn reject_all(&mut self, _: &RejectAllProposedEdits, cx: &mut ViewContext<Self>) {
+        if !self.recalculating_diffs {
+            let hunks = self.hunks(cx);
+            self.reject_hunks(hunks, cx);
+        }
+    }
+
+    /// Shows the removed lines above each hunk, along with buttons to accept
+    /// or reject it.
+    fn update_hunk_blocks(&mut self, cx: &mut ViewContext<Self>) {
+        let this = cx.view().downgrade();
+        let hunks = self.hunks(cx);
+        let buffers = self
+            .buffers
+            .iter()
+            .map(|buffer| (buffer.original.read(cx).snapshot(), buffer.proposed.clone()))
+            .collect::<Vec<_>>();
+        self.editor.update(cx, |editor, cx| {
+            let multibuffer = editor.buffer().read(cx);
+            let snapshot = multibuffer.snapshot(cx);
+            let mut blocks = Vec::new();
+            for (hunk_ix, hunk) in hunks.into_iter().enumerate() {
+                let (original, proposed_buffer) = &buffers[hunk.buffer_ix];
+                let proposed = proposed_buffer.read(cx);
+                let position = proposed.anchor_before(hunk.range.start);
+                let Some(excerpt_id) = multibuffer
+                    .excerpts_for_buffer(proposed_buffer, cx)
+                    .into_iter()
+                    .find(|(_, range)| {
+                        range.context.start.to_offset(proposed) <= hunk.range.start
+                            && hunk.range.start <= range.context.end.to_offset(proposed)
+                    })
+                    .map(|(excerpt_id, _)| excerpt_id)
+                else {
+                    continue;
+                };
+
+                let removed_text = original
+                    .text_for_range(hunk.original_range.clone())
+                    .collect::<String>();
+                let removed_lines = removed_text
+                    .lines()
+                    .map(|line| SharedString::from(line.to_string()))
+                    .collect::<Vec<_>>();
+                let buffer_ix = hunk.buffer_ix;
+                let hunk_start = hunk.range.start;
+                blocks.push(BlockProperties {
+                    position: snapshot.anchor_in_excerpt(excerpt_id, position),
+                    height: (removed_lines.len() + 1).min(u8::MAX as usize) as u8,
+                    style: BlockStyle::Flex,
+                    render: Arc::new({
+                        let this = this.clone();
+                        move |cx: &mut BlockContext| {
+                            let text_style = cx.editor_style.text.clone();
+                            let mut removed_background = cx.editor_style.status.deleted_background;
+                            removed_background.fade_out(0.8);
+                            v_flex()
+                                .id(("proposed_hunk", hunk_ix))
+                                .pl(cx.gutter_width)
+                                .w(cx.max_width + cx.gutter_width)
+                                .child(
+                                    h_flex()
+                                        .h(cx.line_height)
+                                        .gap_1()
+                                        .child(
+                                            Button::new(("accept_hunk", hunk_ix), "Accept")
+                                                .label_size(LabelSize::Small)
+                                                .tooltip(|cx| {
+                                                    Tooltip::for_action(
+                                                        "Apply this change",
+                                                        &AcceptProposedHunk,
+                                                        cx,
+                                                    )
+                                                })
+                                                .on_click({
+                                                    let this = this.clone();
+                                                    move |_, cx| {
+                                                        this.update(cx, |this, cx| {
+                                                            if let Some(hunk) = this
+                                                                .hunk_at(buffer_ix, hunk_start, cx)
+                                                            {
+                                                                this.accept_hunks(vec![hunk], cx);
+                                                            }
+                                                        })
+                                                        .ok();
+                                                    }
+                                                }),
+                                        )
+                                        .child(
+                                            Button::new(("reject_hunk", hunk_ix), "Reject")
+                                                .label_size(LabelSize::Small)
+                                                .tooltip(|cx| {
+                                                    Tooltip::for_action(
+                                                        "Discard this change",
+                                                        &RejectProposedHunk,
+                                                        cx,
+                                                    )
+                                                })
+                                                .on_click({
+                                                    let this = this.clone();
+                                                    move |_, cx| {
+                                                        this.update(cx, |this, cx| {
+                                                            if let Some(hunk) = this
+                                                                .hunk_at(buffer_ix, hunk_start, cx)
+                                                            {
+                                                                this.reject_hunks(vec![hunk], cx);
+                                                            }
+                                                        })
+                                                        .ok();
+                                                    }
+                                                }),
+                                        ),
+                                )
+                                .children(removed_lines.iter().map(|line| {
+                                    div().h(cx.line_height).bg(removed_background).child(
+                                        StyledText::new(line.clone())
+                                            .with_highlights(&text_style, Vec::new()),
+                                    )
+                                }))
+                                .into_any_element()
+                        }
+                    }),
+                    disposition: BlockDisposition::Above,
+                });
+            }
+
+            let old_blocks = std::mem::take(&mut self.hunk_blocks);
+            editor.remove_blocks(old_blocks, None, cx);
+            self.hunk_blocks = editor.insert_blocks(blocks, None, cx).into_iter().collect();
+        });
+    }
+}
+
+impl EventEmitter<EditorEvent> for ProposedEditsView {}
+
+impl FocusableView for ProposedEditsView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl Render for ProposedEditsView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("ProposedEdits")
+            .size_full()
+            .on_action(cx.listener(Self::accept_hunk))
+            .on_action(cx.listener(Self::reject_hunk))
+            .on_action(cx.listener(Self::accept_all))
+            .on_action(cx.listener(Self::reject_all))
+            .child(
+                h_flex()
+                    .p_2()
+                    .gap_2()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(
+                        Label::new(format!(
+                            "Changes to {} file{} proposed by the assistant",
+                            self.buffers.len(),
+                            if self.buffers.len() == 1 { "" } else { "s" }
+                        ))
+                        .color(Color::Muted),
+                    )
+                    .child(div().flex_1())
+                    .child(
+                        Button::new("accept_all", "Accept All")
+                            .disabled(self.recalculating_diffs)
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.accept_all(&AcceptAllProposedEdits, cx)
+                            })),
+                    )
+                    .child(
+                        Button::new("reject_all", "Reject All")
+                            .disabled(self.recalculating_diffs)
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.reject_all(&RejectAllProposedEdits, cx)
+                            })),
+                    ),
+            )
+            .child(div().flex_1().child(self.editor.clone()))
+    }
+}
+
+impl Item for ProposedEditsView {
+    type Event = EditorEvent;
+
+    fn to_item_events(event: &EditorEvent, f: impl FnMut(ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.editor.update(cx, |editor, cx| editor.deactivated(cx));
+    }
+
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some("Proposed Edits".into())
+    }
+
+    fn tab_content(&self, _detail: Option<usize>, selected: bool, _: &WindowContext) -> AnyElement {
+        Label::new("Proposed Edits")
+            .color(if selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+}
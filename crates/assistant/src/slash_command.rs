@@ -0,0 +1,291 @@
+mod code_command;
+mod diagnostics_command;
+mod file_command;
+mod search_command;
+mod symbol_command;
+mod tab_command;
+mod terminal_command;
+
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::Result;
+use collections::HashMap;
+use editor::{CompletionProvider, Editor};
+use fuzzy::StringMatchCandidate;
+use gpui::{AppContext, Global, Model, Task, ViewContext, WeakView, WindowContext};
+use language::{Buffer, CodeLabel, Completion, LanguageServerId, Point, ToOffset, ToPoint};
+use parking_lot::RwLock;
+use std::sync::{atomic::AtomicBool, Arc};
+use workspace::Workspace;
+
+pub(crate) use file_command::project_path_for_str;
+pub(crate) use search_command::matching_lines;
+
+/// A command that can be typed on its own line in a conversation, such as
+/// `/file src/main.rs`, to attach context to the conversation.
+pub trait SlashCommand: 'static + Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn requires_argument(&self) -> bool;
+    fn complete_argument(
+        &self,
+        query: String,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>>;
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>>;
+}
+
+#[derive(Default)]
+pub struct SlashCommandRegistry {
+    commands: HashMap<&'static str, Arc<dyn SlashCommand>>,
+}
+
+impl Global for SlashCommandRegistry {}
+
+impl SlashCommandRegistry {
+    pub fn global(cx: &AppContext) -> &Self {
+        cx.global::<Self>()
+    }
+
+    pub fn register_command(&mut self, command: impl SlashCommand) {
+        self.commands.insert(command.name(), Arc::new(command));
+    }
+
+    pub fn command(&self, name: &str) -> Option<Arc<dyn SlashCommand>> {
+        self.commands.get(name).cloned()
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Arc<dyn SlashCommand>> {
+        self.commands.values()
+    }
+}
+
+pub fn init(cx: &mut AppContext) {
+    let mut registry = SlashCommandRegistry::default();
+    registry.register_command(file_command::FileSlashCommand);
+    registry.register_command(symbol_command::SymbolSlashCommand);
+    registry.register_command(diagnostics_command::DiagnosticsSlashCommand);
+    registry.register_command(search_command::SearchSlashCommand);
+    registry.register_command(code_command::CodeSlashCommand);
+    registry.register_command(tab_command::TabSlashCommand);
+    registry.register_command(terminal_command::TerminalSlashCommand);
+    cx.set_global(registry);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParsedSlashCommand<'a> {
+    pub name: &'a str,
+    pub argument: Option<&'a str>,
+}
+
+/// Parses a line of the form `/name argument`. Returns `None` if the line
+/// isn't shaped like a slash command, regardless of whether the command exists.
+pub fn parse_slash_command(line: &str) -> Option<ParsedSlashCommand> {
+    let line = line.strip_prefix('/')?;
+    let (name, argument) = match line.find(char::is_whitespace) {
+        Some(ix) => (&line[..ix], line[ix..].trim()),
+        None => (line, ""),
+    };
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+    {
+        return None;
+    }
+
+    Some(ParsedSlashCommand {
+        name,
+        argument: if argument.is_empty() {
+            None
+        } else {
+            Some(argument)
+        },
+    })
+}
+
+/// Completes command names and their arguments in the conversation editor.
+pub struct SlashCommandCompletionProvider {
+    workspace: WeakView<Workspace>,
+}
+
+impl SlashCommandCompletionProvider {
+    pub fn new(workspace: WeakView<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    fn complete_command_name(
+        &self,
+        query: &str,
+        range: std::ops::Range<language::Anchor>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<Completion>>> {
+        let commands = SlashCommandRegistry::global(cx)
+            .commands()
+            .cloned()
+            .collect::<Vec<_>>();
+        let candidates = commands
+            .iter()
+            .enumerate()
+            .map(|(ix, command)| StringMatchCandidate {
+                id: ix,
+                string: command.name().to_string(),
+                char_bag: command.name().chars().collect(),
+            })
+            .collect::<Vec<_>>();
+        let query = query.to_string();
+        let executor = cx.background_executor().clone();
+        cx.background_executor().spawn(async move {
+            let matches = fuzzy::match_strings(
+                &candidates,
+                &query,
+                false,
+                candidates.len(),
+                &AtomicBool::default(),
+                executor,
+            )
+            .await;
+
+            Ok(matches
+                .into_iter()
+                .map(|mat| {
+                    let command = &commands[mat.candidate_id];
+                    let mut new_text = command.name().to_string();
+                    if command.requires_argument() {
+                        new_text.push(' ');
+                    }
+                    Completion {
+                        old_range: range.clone(),
+                        new_text,
+                        label: CodeLabel {
+                            text: format!("{} {}", command.name(), command.description()),
+                            runs: Vec::new(),
+                            filter_range: 0..command.name().len(),
+                        },
+                        documentation: None,
+                        server_id: LanguageServerId(0),
+                        lsp_completion: Default::default(),
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn complete_command_argument(
+        &self,
+        command: Arc<dyn SlashCommand>,
+        query: &str,
+        range: std::ops::Range<language::Anchor>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<Completion>>> {
+        let arguments = command.complete_argument(query.to_string(), self.workspace.clone(), cx);
+        cx.background_executor().spawn(async move {
+            Ok(arguments
+                .await?
+                .into_iter()
+                .map(|argument| Completion {
+                    old_range: range.clone(),
+                    label: CodeLabel::plain(argument.clone(), None),
+                    new_text: argument,
+                    documentation: None,
+                    server_id: LanguageServerId(0),
+                    lsp_completion: Default::default(),
+                })
+                .collect())
+        })
+    }
+}
+
+impl CompletionProvider for SlashCommandCompletionProvider {
+    fn completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: language::Anchor,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Vec<Completion>>> {
+        let buffer = buffer.read(cx);
+        let position = buffer_position.to_offset(buffer);
+        let line_start = Point::new(buffer_position.to_point(buffer).row, 0).to_offset(buffer);
+        let line = buffer
+            .text_for_range(line_start..position)
+            .collect::<String>();
+        let Some(command) = parse_slash_command(&line) else {
+            return Task::ready(Ok(Vec::new()));
+        };
+
+        let name_start = line_start + 1;
+        if name_start + command.name.len() == position {
+            let range = buffer.anchor_after(name_start)..buffer_position;
+            let query = command.name.to_string();
+            self.complete_command_name(&query, range, cx)
+        } else {
+            let query = line[1 + command.name.len()..].trim_start().to_string();
+            let range = buffer.anchor_after(position - query.len())..buffer_position;
+            let name = command.name.to_string();
+            match SlashCommandRegistry::global(cx).command(&name) {
+                Some(slash_command) => {
+                    self.complete_command_argument(slash_command, &query, range, cx)
+                }
+                None => Task::ready(Ok(Vec::new())),
+            }
+        }
+    }
+
+    fn resolve_completions(
+        &self,
+        _completion_indices: Vec<usize>,
+        _completions: Arc<RwLock<Box<[Completion]>>>,
+        _cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<bool>> {
+        Task::ready(Ok(false))
+    }
+
+    fn apply_additional_edits_for_completion(
+        &self,
+        _buffer: Model<Buffer>,
+        _completion: Completion,
+        _push_to_history: bool,
+        _cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Option<language::Transaction>>> {
+        Task::ready(Ok(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slash_command() {
+        assert_eq!(
+            parse_slash_command("/file src/main.rs"),
+            Some(ParsedSlashCommand {
+                name: "file",
+                argument: Some("src/main.rs"),
+            })
+        );
+        assert_eq!(
+            parse_slash_command("/diagnostics  "),
+            Some(ParsedSlashCommand {
+                name: "diagnostics",
+                argument: None,
+            })
+        );
+        assert_eq!(
+            parse_slash_command("/search  fn main "),
+            Some(ParsedSlashCommand {
+                name: "search",
+                argument: Some("fn main"),
+            })
+        );
+        assert_eq!(parse_slash_command("/"), None);
+        assert_eq!(parse_slash_command("/usr/bin"), None);
+        assert_eq!(parse_slash_command(" /file"), None);
+        assert_eq!(parse_slash_command("file"), None);
+    }
+}
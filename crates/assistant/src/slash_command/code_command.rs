@@ -0,0 +1,101 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Result};
+use gpui::{Task, WeakView, WindowContext};
+use language::{Point, ToPoint};
+use semantic_index::SemanticIndex;
+use workspace::Workspace;
+
+const RESULT_COUNT: usize = 10;
+
+pub(crate) struct CodeSlashCommand;
+
+impl SlashCommand for CodeSlashCommand {
+    fn name(&self) -> &'static str {
+        "code"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert the code that best matches a description or symbol names"
+    }
+
+    fn requires_argument(&self) -> bool {
+        true
+    }
+
+    fn complete_argument(
+        &self,
+        _query: String,
+        _workspace: WeakView<Workspace>,
+        _cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        Task::ready(Ok(Vec::new()))
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let Some(argument) = argument else {
+            return Task::ready(Err(anyhow!("missing search query")));
+        };
+        let semantic_index = SemanticIndex::enabled(cx)
+            .then(|| SemanticIndex::global(cx))
+            .flatten();
+        let Some(semantic_index) = semantic_index else {
+            return Task::ready(Err(anyhow!("semantic search is not enabled")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let results = semantic_index.update(cx, |semantic_index, cx| {
+            semantic_index.hybrid_search_project(
+                project,
+                argument.to_string(),
+                RESULT_COUNT,
+                Vec::new(),
+                Vec::new(),
+                cx,
+            )
+        });
+
+        let title = format!("Code matching `{argument}`");
+        cx.spawn(|cx| async move {
+            let mut sections = Vec::new();
+            for (ix, result) in results.await?.into_iter().enumerate() {
+                let section = result.buffer.read_with(&cx, |buffer, cx| {
+                    let start = result.range.start.to_point(buffer).row;
+                    let end = result.range.end.to_point(buffer).row;
+                    let path = buffer
+                        .file()
+                        .map(|file| file.full_path(cx).to_string_lossy().to_string())
+                        .unwrap_or_else(|| "untitled".into());
+                    PromptContextSection::new(
+                        format!("#{} `{path}`, lines {}-{}", ix + 1, start + 1, end + 1),
+                        buffer
+                            .text_for_range(
+                                Point::new(start, 0)..Point::new(end, buffer.line_len(end)),
+                            )
+                            .collect::<String>(),
+                    )
+                    .with_language_name(
+                        buffer
+                            .language()
+                            .map(|language| language.name().to_string()),
+                    )
+                })?;
+                sections.push(section.to_prompt());
+            }
+
+            let text = if sections.is_empty() {
+                "No results.".to_string()
+            } else {
+                sections.join("\n\n")
+            };
+            Ok(PromptContextSection::new(title, text))
+        })
+    }
+}
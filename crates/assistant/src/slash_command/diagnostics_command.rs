@@ -0,0 +1,135 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Result};
+use collections::HashSet;
+use gpui::{AppContext, Task, WeakView, WindowContext};
+use language::Point;
+use lsp::DiagnosticSeverity;
+use project::{Project, ProjectPath};
+use std::fmt::Write;
+use workspace::Workspace;
+
+pub(crate) struct DiagnosticsSlashCommand;
+
+impl DiagnosticsSlashCommand {
+    /// Returns the paths that have errors or warnings, along with the label
+    /// they're displayed with.
+    fn paths_with_diagnostics(project: &Project, cx: &AppContext) -> Vec<(ProjectPath, String)> {
+        let include_root_name = project.visible_worktrees(cx).count() > 1;
+        let mut seen_paths = HashSet::default();
+        project
+            .diagnostic_summaries(false, cx)
+            .filter(|(_, _, summary)| summary.error_count + summary.warning_count > 0)
+            .filter(|(path, _, _)| seen_paths.insert(path.clone()))
+            .filter_map(|(path, _, _)| {
+                let worktree = project.worktree_for_id(path.worktree_id, cx)?;
+                let mut label = path.path.to_string_lossy().to_string();
+                if include_root_name {
+                    label = format!("{}/{label}", worktree.read(cx).root_name());
+                }
+                Some((path, label))
+            })
+            .collect()
+    }
+}
+
+impl SlashCommand for DiagnosticsSlashCommand {
+    fn name(&self) -> &'static str {
+        "diagnostics"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert errors and warnings"
+    }
+
+    fn requires_argument(&self) -> bool {
+        false
+    }
+
+    fn complete_argument(
+        &self,
+        query: String,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let project = workspace.read(cx).project().read(cx);
+        Task::ready(Ok(Self::paths_with_diagnostics(project, cx)
+            .into_iter()
+            .map(|(_, label)| label)
+            .filter(|label| label.contains(query.as_str()))
+            .collect()))
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let buffers = Self::paths_with_diagnostics(project.read(cx), cx)
+            .into_iter()
+            .filter(|(_, label)| argument.map_or(true, |argument| label.starts_with(argument)))
+            .map(|(path, label)| {
+                let buffer = project.update(cx, |project, cx| project.open_buffer(path, cx));
+                (label, buffer)
+            })
+            .collect::<Vec<_>>();
+        let title = match argument {
+            Some(argument) => format!("Errors and warnings in `{argument}`"),
+            None => "Errors and warnings in the project".to_string(),
+        };
+
+        cx.spawn(|cx| async move {
+            let mut text = String::new();
+            for (label, buffer) in buffers {
+                let buffer = buffer.await?;
+                buffer.read_with(&cx, |buffer, _| {
+                    let snapshot = buffer.snapshot();
+                    for entry in snapshot.diagnostics_in_range::<_, Point>(0..snapshot.len(), false)
+                    {
+                        let diagnostic = &entry.diagnostic;
+                        if !diagnostic.is_primary
+                            || diagnostic.severity > DiagnosticSeverity::WARNING
+                        {
+                            continue;
+                        }
+
+                        let severity = if diagnostic.severity == DiagnosticSeverity::ERROR {
+                            "error"
+                        } else {
+                            "warning"
+                        };
+                        let start = entry.range.start;
+                        let line = snapshot
+                            .text_for_range(
+                                Point::new(start.row, 0)
+                                    ..Point::new(start.row, snapshot.line_len(start.row)),
+                            )
+                            .collect::<String>();
+                        writeln!(
+                            text,
+                            "{label}:{}:{}: {severity}: {}\n    {}",
+                            start.row + 1,
+                            start.column + 1,
+                            diagnostic.message,
+                            line.trim()
+                        )
+                        .unwrap();
+                    }
+                })?;
+            }
+
+            if text.is_empty() {
+                text = "No errors or warnings.".into();
+            }
+            Ok(PromptContextSection::new(title, text))
+        })
+    }
+}
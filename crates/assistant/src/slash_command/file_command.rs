@@ -0,0 +1,132 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Result};
+use gpui::{AppContext, Task, WeakView, WindowContext};
+use project::{PathMatchCandidateSet, Project, ProjectPath};
+use std::{
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+};
+use workspace::Workspace;
+
+pub(crate) struct FileSlashCommand;
+
+impl SlashCommand for FileSlashCommand {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert a file"
+    }
+
+    fn requires_argument(&self) -> bool {
+        true
+    }
+
+    fn complete_argument(
+        &self,
+        query: String,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let worktrees = workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .visible_worktrees(cx)
+            .collect::<Vec<_>>();
+        let include_root_name = worktrees.len() > 1;
+        let candidate_sets = worktrees
+            .into_iter()
+            .map(|worktree| {
+                let worktree = worktree.read(cx);
+                PathMatchCandidateSet {
+                    snapshot: worktree.snapshot(),
+                    include_ignored: worktree
+                        .root_entry()
+                        .map_or(false, |entry| entry.is_ignored),
+                    include_root_name,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let executor = cx.background_executor().clone();
+        cx.background_executor().spawn(async move {
+            let matches = fuzzy::match_path_sets(
+                candidate_sets.as_slice(),
+                &query,
+                None,
+                false,
+                100,
+                &AtomicBool::default(),
+                executor,
+            )
+            .await;
+            Ok(matches
+                .into_iter()
+                .map(|mat| format!("{}{}", mat.path_prefix, mat.path.to_string_lossy()))
+                .collect())
+        })
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let Some(argument) = argument else {
+            return Task::ready(Err(anyhow!("missing path")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let Some(project_path) = project_path_for_str(project.read(cx), argument, cx) else {
+            return Task::ready(Err(anyhow!("no such file: {argument}")));
+        };
+
+        let buffer = project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+        let title = format!("File `{argument}`");
+        cx.spawn(|cx| async move {
+            let buffer = buffer.await?;
+            buffer.read_with(&cx, |buffer, _| {
+                PromptContextSection::new(title, buffer.text()).with_language_name(
+                    buffer
+                        .language()
+                        .map(|language| language.name().to_string()),
+                )
+            })
+        })
+    }
+}
+
+/// Resolves a path as displayed by the file finder, which includes the name of
+/// the worktree's root when the project has more than one worktree.
+pub(crate) fn project_path_for_str(
+    project: &Project,
+    path: &str,
+    cx: &AppContext,
+) -> Option<ProjectPath> {
+    let path = Path::new(path);
+    project.visible_worktrees(cx).find_map(|worktree| {
+        let worktree = worktree.read(cx);
+        let root_relative_path = path.strip_prefix(worktree.root_name()).ok();
+        [Some(path), root_relative_path]
+            .into_iter()
+            .flatten()
+            .find(|path| {
+                worktree
+                    .entry_for_path(path)
+                    .map_or(false, |entry| entry.is_file())
+            })
+            .map(|path| ProjectPath {
+                worktree_id: worktree.id(),
+                path: Arc::from(path),
+            })
+    })
+}
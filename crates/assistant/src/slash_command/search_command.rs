@@ -0,0 +1,107 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use gpui::{Model, Task, WeakView, WindowContext};
+use language::{Point, ToPoint};
+use project::{search::SearchQuery, Project};
+use std::fmt::Write;
+use workspace::Workspace;
+
+/// The maximum number of matching lines to include, so that searching for a
+/// common word doesn't exhaust the model's context window.
+const MAX_MATCHING_LINES: usize = 100;
+
+pub(crate) struct SearchSlashCommand;
+
+impl SlashCommand for SearchSlashCommand {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert project search results"
+    }
+
+    fn requires_argument(&self) -> bool {
+        true
+    }
+
+    fn complete_argument(
+        &self,
+        _query: String,
+        _workspace: WeakView<Workspace>,
+        _cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        Task::ready(Ok(Vec::new()))
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let Some(argument) = argument else {
+            return Task::ready(Err(anyhow!("missing search query")));
+        };
+        let query = match SearchQuery::text(argument, false, false, false, Vec::new(), Vec::new()) {
+            Ok(query) => query,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let project = workspace.read(cx).project().clone();
+        let text = matching_lines(project, query, cx);
+        let title = format!("Search results for `{argument}`");
+        cx.spawn(|_| async move { Ok(PromptContextSection::new(title, text.await?)) })
+    }
+}
+
+/// Lists the lines that match the query, as `path:row: line`.
+pub(crate) fn matching_lines(
+    project: Model<Project>,
+    query: SearchQuery,
+    cx: &mut WindowContext,
+) -> Task<Result<String>> {
+    let mut matches = project.update(cx, |project, cx| project.search(query, cx));
+    cx.spawn(|cx| async move {
+        let mut text = String::new();
+        let mut matching_lines = 0;
+        while let Some((buffer, ranges)) = matches.next().await {
+            buffer.read_with(&cx, |buffer, cx| {
+                let path = buffer
+                    .file()
+                    .map(|file| file.full_path(cx).to_string_lossy().to_string())
+                    .unwrap_or_else(|| "untitled".into());
+                let mut last_row = None;
+                for range in ranges {
+                    let row = range.start.to_point(buffer).row;
+                    if last_row == Some(row) {
+                        continue;
+                    }
+                    last_row = Some(row);
+
+                    let line = buffer
+                        .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+                        .collect::<String>();
+                    writeln!(text, "{path}:{}: {}", row + 1, line.trim()).unwrap();
+                    matching_lines += 1;
+                    if matching_lines == MAX_MATCHING_LINES {
+                        break;
+                    }
+                }
+            })?;
+            if matching_lines == MAX_MATCHING_LINES {
+                writeln!(text, "(more results were omitted)").unwrap();
+                break;
+            }
+        }
+
+        if text.is_empty() {
+            text = "No results.".into();
+        }
+        Ok(text)
+    })
+}
@@ -0,0 +1,119 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashSet;
+use gpui::{Task, WeakView, WindowContext};
+use language::{Bias, Point, ToOffset, ToPoint};
+use workspace::Workspace;
+
+pub(crate) struct SymbolSlashCommand;
+
+impl SlashCommand for SymbolSlashCommand {
+    fn name(&self) -> &'static str {
+        "symbol"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert a symbol's definition"
+    }
+
+    fn requires_argument(&self) -> bool {
+        true
+    }
+
+    fn complete_argument(
+        &self,
+        query: String,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let symbols = project.update(cx, |project, cx| project.symbols(&query, cx));
+        cx.background_executor().spawn(async move {
+            let mut seen_names = HashSet::default();
+            Ok(symbols
+                .await?
+                .into_iter()
+                .map(|symbol| symbol.name)
+                .filter(|name| seen_names.insert(name.clone()))
+                .collect())
+        })
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let Some(name) = argument.map(str::to_string) else {
+            return Task::ready(Err(anyhow!("missing symbol name")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let symbols = project.update(cx, |project, cx| project.symbols(&name, cx));
+        cx.spawn(|mut cx| async move {
+            let symbols = symbols.await?;
+            let symbol = symbols
+                .iter()
+                .find(|symbol| symbol.name == name)
+                .or_else(|| symbols.first())
+                .with_context(|| format!("no such symbol: {name}"))?;
+            let buffer = project
+                .update(&mut cx, |project, cx| {
+                    project.open_buffer_for_symbol(symbol, cx)
+                })?
+                .await?;
+
+            buffer.read_with(&cx, |buffer, _| {
+                let snapshot = buffer.snapshot();
+                let symbol_start = snapshot
+                    .clip_point_utf16(symbol.range.start, Bias::Left)
+                    .to_point(&snapshot);
+                let symbol_end = snapshot
+                    .clip_point_utf16(symbol.range.end, Bias::Right)
+                    .to_point(&snapshot);
+
+                // Language servers often report only the range of the symbol's
+                // name, so prefer the innermost outline item that contains it.
+                let range = snapshot
+                    .outline(None)
+                    .and_then(|outline| {
+                        outline
+                            .items
+                            .into_iter()
+                            .map(|item| {
+                                item.range.start.to_point(&snapshot)
+                                    ..item.range.end.to_point(&snapshot)
+                            })
+                            .filter(|range| range.start <= symbol_start && range.end >= symbol_end)
+                            .min_by_key(|range| {
+                                range.end.to_offset(&snapshot) - range.start.to_offset(&snapshot)
+                            })
+                    })
+                    .unwrap_or(symbol_start..symbol_end);
+                let range = Point::new(range.start.row, 0)
+                    ..Point::new(range.end.row, snapshot.line_len(range.end.row));
+
+                let path = buffer
+                    .file()
+                    .map(|file| file.path().to_string_lossy().to_string())
+                    .unwrap_or_else(|| "untitled".into());
+                PromptContextSection::new(
+                    format!("Symbol `{}` in `{path}`", symbol.name),
+                    snapshot.text_for_range(range).collect::<String>(),
+                )
+                .with_language_name(
+                    buffer
+                        .language()
+                        .map(|language| language.name().to_string()),
+                )
+            })
+        })
+    }
+}
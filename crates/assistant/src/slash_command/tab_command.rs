@@ -0,0 +1,93 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Result};
+use collections::HashSet;
+use editor::Editor;
+use gpui::{AppContext, Model, Task, View, WeakView, WindowContext};
+use language::Buffer;
+use workspace::Workspace;
+
+pub(crate) struct TabSlashCommand;
+
+impl TabSlashCommand {
+    fn tab_buffer(editor: &View<Editor>, cx: &AppContext) -> Option<(Model<Buffer>, String)> {
+        let buffer = editor.read(cx).buffer().read(cx).as_singleton()?;
+        let label = buffer
+            .read(cx)
+            .file()
+            .map(|file| file.path().to_string_lossy().to_string())
+            .unwrap_or_else(|| "untitled".into());
+        Some((buffer, label))
+    }
+}
+
+impl SlashCommand for TabSlashCommand {
+    fn name(&self) -> &'static str {
+        "tab"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert an open tab, or the active one"
+    }
+
+    fn requires_argument(&self) -> bool {
+        false
+    }
+
+    fn complete_argument(
+        &self,
+        query: String,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let mut seen_labels = HashSet::default();
+        Task::ready(Ok(workspace
+            .read(cx)
+            .items_of_type::<Editor>(cx)
+            .filter_map(|editor| Self::tab_buffer(&editor, cx))
+            .map(|(_, label)| label)
+            .filter(|label| label.contains(query.as_str()) && seen_labels.insert(label.clone()))
+            .collect()))
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let workspace = workspace.read(cx);
+        let tab = match argument {
+            Some(argument) => workspace
+                .items_of_type::<Editor>(cx)
+                .filter_map(|editor| Self::tab_buffer(&editor, cx))
+                .find(|(_, label)| label == argument),
+            None => workspace
+                .active_item_as::<Editor>(cx)
+                .and_then(|editor| Self::tab_buffer(&editor, cx)),
+        };
+        let Some((buffer, label)) = tab else {
+            return Task::ready(Err(match argument {
+                Some(argument) => anyhow!("no open tab for {argument}"),
+                None => anyhow!("the active tab isn't a file"),
+            }));
+        };
+
+        let buffer = buffer.read(cx);
+        Task::ready(Ok(PromptContextSection::new(
+            format!("Tab `{label}`"),
+            buffer.text(),
+        )
+        .with_language_name(
+            buffer
+                .language()
+                .map(|language| language.name().to_string()),
+        )))
+    }
+}
@@ -0,0 +1,81 @@
+use super::SlashCommand;
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Context as _, Result};
+use gpui::{Task, WeakView, WindowContext};
+use terminal_view::{terminal_panel::TerminalPanel, TerminalView};
+use workspace::Workspace;
+
+const DEFAULT_LINE_COUNT: usize = 50;
+
+pub(crate) struct TerminalSlashCommand;
+
+impl SlashCommand for TerminalSlashCommand {
+    fn name(&self) -> &'static str {
+        "terminal"
+    }
+
+    fn description(&self) -> &'static str {
+        "insert the last lines of terminal output"
+    }
+
+    fn requires_argument(&self) -> bool {
+        false
+    }
+
+    fn complete_argument(
+        &self,
+        _query: String,
+        _workspace: WeakView<Workspace>,
+        _cx: &mut WindowContext,
+    ) -> Task<Result<Vec<String>>> {
+        Task::ready(Ok(Vec::new()))
+    }
+
+    fn run(
+        &self,
+        argument: Option<&str>,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<PromptContextSection>> {
+        Task::ready(terminal_output(argument, workspace, cx))
+    }
+}
+
+fn terminal_output(
+    argument: Option<&str>,
+    workspace: WeakView<Workspace>,
+    cx: &mut WindowContext,
+) -> Result<PromptContextSection> {
+    let workspace = workspace.upgrade().context("workspace was dropped")?;
+    let line_count = match argument {
+        Some(argument) => argument
+            .parse::<usize>()
+            .map_err(|_| anyhow!("expected a number of lines, got {argument}"))?,
+        None => DEFAULT_LINE_COUNT,
+    };
+
+    // Prefer a terminal in the center pane, falling back to the terminal panel.
+    let workspace = workspace.read(cx);
+    let terminal_view = workspace
+        .active_item_as::<TerminalView>(cx)
+        .or_else(|| {
+            workspace
+                .panel::<TerminalPanel>(cx)?
+                .read(cx)
+                .pane()
+                .read(cx)
+                .active_item()?
+                .downcast::<TerminalView>()
+        })
+        .context("no terminal is open")?;
+
+    let text = terminal_view
+        .read(cx)
+        .terminal()
+        .read(cx)
+        .last_lines(line_count);
+    Ok(PromptContextSection::new(
+        format!("Last {line_count} lines of terminal output"),
+        text,
+    ))
+}
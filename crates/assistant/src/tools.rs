@@ -0,0 +1,108 @@
+mod list_entries_tool;
+mod propose_edits_tool;
+mod read_file_tool;
+mod semantic_search_tool;
+mod symbols_tool;
+mod text_search_tool;
+
+use ai::completion::ToolDefinition;
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use gpui::{AppContext, Global, Task, WeakView, WindowContext};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use workspace::Workspace;
+
+/// A function that the model can call during a conversation, such as
+/// `read_file`, to look around the project or to act on the user's behalf.
+pub trait AssistantTool: 'static + Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    /// A JSON schema describing the tool's arguments.
+    fn parameters(&self) -> serde_json::Value;
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>>;
+}
+
+#[derive(Default)]
+pub struct AssistantToolRegistry {
+    tools: HashMap<&'static str, Arc<dyn AssistantTool>>,
+}
+
+impl Global for AssistantToolRegistry {}
+
+impl AssistantToolRegistry {
+    pub fn global(cx: &AppContext) -> &Self {
+        cx.global::<Self>()
+    }
+
+    pub fn register_tool(&mut self, tool: impl AssistantTool) {
+        self.tools.insert(tool.name(), Arc::new(tool));
+    }
+
+    pub fn tool(&self, name: &str) -> Option<Arc<dyn AssistantTool>> {
+        self.tools.get(name).cloned()
+    }
+
+    /// The definitions sent along with completion requests, ordered by name.
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions = self
+            .tools
+            .values()
+            .map(|tool| ToolDefinition {
+                name: tool.name().into(),
+                description: tool.description().into(),
+                parameters: tool.parameters(),
+            })
+            .collect::<Vec<_>>();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+}
+
+pub fn init(cx: &mut AppContext) {
+    let mut registry = AssistantToolRegistry::default();
+    registry.register_tool(read_file_tool::ReadFileTool);
+    registry.register_tool(list_entries_tool::ListEntriesTool);
+    registry.register_tool(text_search_tool::TextSearchTool);
+    registry.register_tool(semantic_search_tool::SemanticSearchTool);
+    registry.register_tool(symbols_tool::SymbolsTool);
+    registry.register_tool(propose_edits_tool::ProposeEditsTool);
+    cx.set_global(registry);
+}
+
+fn parse_arguments<T: DeserializeOwned>(arguments: &str) -> Result<T> {
+    serde_json::from_str(arguments).with_context(|| format!("invalid arguments: {arguments}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    fn test_tool_definitions(cx: &mut AppContext) {
+        init(cx);
+        let definitions = AssistantToolRegistry::global(cx).definitions();
+        assert_eq!(
+            definitions
+                .iter()
+                .map(|definition| definition.name.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "list_entries",
+                "propose_edits",
+                "read_file",
+                "search_symbols",
+                "search_text",
+                "semantic_search"
+            ]
+        );
+        for definition in definitions {
+            assert_eq!(definition.parameters["type"], "object");
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::{parse_arguments, AssistantTool};
+use anyhow::{anyhow, Result};
+use gpui::{AppContext, Task, WeakView, WindowContext};
+use project::Project;
+use serde::Deserialize;
+use serde_json::json;
+use std::{fmt::Write, path::Path};
+use workspace::Workspace;
+
+pub(crate) struct ListEntriesTool;
+
+#[derive(Deserialize)]
+struct ListEntriesArguments {
+    #[serde(default)]
+    path: String,
+}
+
+impl AssistantTool for ListEntriesTool {
+    fn name(&self) -> &'static str {
+        "list_entries"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the files and directories in a directory of the project. Directories end with a slash."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path of the directory, relative to the project root. Omit it to list the project root."
+                }
+            }
+        })
+    }
+
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let result = parse_arguments::<ListEntriesArguments>(arguments).and_then(|arguments| {
+            let workspace = workspace
+                .upgrade()
+                .ok_or_else(|| anyhow!("workspace was dropped"))?;
+            let project = workspace.read(cx).project().read(cx);
+            list_entries(project, &arguments.path, cx)
+        });
+        Task::ready(result)
+    }
+}
+
+fn list_entries(project: &Project, path: &str, cx: &AppContext) -> Result<String> {
+    let path = Path::new(path.trim_matches('/'));
+    let worktrees = project.visible_worktrees(cx).collect::<Vec<_>>();
+
+    // With several worktrees, the root of the project lists their names.
+    if worktrees.len() > 1 && path.as_os_str().is_empty() {
+        let mut text = String::new();
+        for worktree in worktrees {
+            writeln!(text, "{}/", worktree.read(cx).root_name()).unwrap();
+        }
+        return Ok(text);
+    }
+
+    for worktree in worktrees {
+        let worktree = worktree.read(cx);
+        let root_relative_path = path.strip_prefix(worktree.root_name()).ok();
+        let Some((directory, entry)) = [Some(path), root_relative_path]
+            .into_iter()
+            .flatten()
+            .find_map(|path| Some((path, worktree.entry_for_path(path)?)))
+        else {
+            continue;
+        };
+        if !entry.is_dir() {
+            return Err(anyhow!("{} is not a directory", path.display()));
+        }
+
+        let mut text = String::new();
+        for entry in worktree.entries(false) {
+            if entry.path.parent() != Some(directory) {
+                continue;
+            }
+            let name = entry.path.strip_prefix(directory)?.to_string_lossy();
+            if entry.is_dir() {
+                writeln!(text, "{name}/").unwrap();
+            } else {
+                writeln!(text, "{name}").unwrap();
+            }
+        }
+        if text.is_empty() {
+            text = "The directory is empty.".into();
+        }
+        return Ok(text);
+    }
+
+    Err(anyhow!("no such directory: {}", path.display()))
+}
@@ -0,0 +1,214 @@
+use super::{parse_arguments, AssistantTool};
+use crate::{
+    proposed_edits::{ProposedEdit, ProposedEditsView},
+    slash_command::project_path_for_str,
+};
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use gpui::{Task, VisualContext, WeakView, WindowContext};
+use serde::Deserialize;
+use serde_json::json;
+use std::ops::Range;
+use workspace::Workspace;
+
+pub(crate) struct ProposeEditsTool;
+
+#[derive(Deserialize)]
+struct ProposeEditsArguments {
+    edits: Vec<TextReplacement>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+struct TextReplacement {
+    path: String,
+    old_text: String,
+    new_text: String,
+}
+
+impl AssistantTool for ProposeEditsTool {
+    fn name(&self) -> &'static str {
+        "propose_edits"
+    }
+
+    fn description(&self) -> &'static str {
+        "Propose edits to existing files in the project. Each edit replaces text that occurs exactly once in the file. The user reviews the edits and decides which ones to apply."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "edits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "The path of the file, relative to the project root."
+                            },
+                            "old_text": {
+                                "type": "string",
+                                "description": "The text to replace. It must occur exactly once in the file, so include enough surrounding lines to make it unique."
+                            },
+                            "new_text": {
+                                "type": "string",
+                                "description": "The text to replace it with."
+                            }
+                        },
+                        "required": ["path", "old_text", "new_text"]
+                    }
+                }
+            },
+            "required": ["edits"]
+        })
+    }
+
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let arguments = match parse_arguments::<ProposeEditsArguments>(arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let project = workspace.read(cx).project().clone();
+
+        let mut replacements_by_path = HashMap::<String, Vec<TextReplacement>>::default();
+        let mut paths = Vec::new();
+        for edit in arguments.edits {
+            if !replacements_by_path.contains_key(&edit.path) {
+                paths.push(edit.path.clone());
+            }
+            replacements_by_path
+                .entry(edit.path.clone())
+                .or_default()
+                .push(edit);
+        }
+        let mut buffers = Vec::new();
+        for path in paths {
+            let Some(project_path) = project_path_for_str(project.read(cx), &path, cx) else {
+                return Task::ready(Err(anyhow!("no such file: {path}")));
+            };
+            let buffer = project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+            let replacements = replacements_by_path.remove(&path).unwrap_or_default();
+            buffers.push((path, buffer, replacements));
+        }
+
+        cx.spawn(|mut cx| async move {
+            let mut edits = Vec::new();
+            for (path, buffer, replacements) in buffers {
+                let buffer = buffer.await?;
+                let text = buffer.read_with(&cx, |buffer, _| buffer.text())?;
+                let (new_text, edited_ranges) = apply_replacements(&text, &replacements)
+                    .map_err(|error| anyhow!("{path}: {error}"))?;
+                edits.push(ProposedEdit {
+                    original: buffer,
+                    new_text,
+                    edited_ranges,
+                });
+            }
+
+            let file_count = edits.len();
+            workspace.update(&mut cx, |workspace, cx| {
+                let view = cx.new_view(|cx| ProposedEditsView::new(edits, project, cx));
+                workspace.add_item(Box::new(view), cx);
+            })?;
+            Ok(format!(
+                "Proposed edits to {file_count} file(s). The user will review them and apply the ones they accept."
+            ))
+        })
+    }
+}
+
+/// Applies the replacements to the text, returning the new text along with
+/// the ranges of it that were replaced.
+fn apply_replacements(
+    text: &str,
+    replacements: &[TextReplacement],
+) -> Result<(String, Vec<Range<usize>>)> {
+    let mut edits = Vec::new();
+    for replacement in replacements {
+        if replacement.old_text.is_empty() {
+            return Err(anyhow!("old_text must not be empty"));
+        }
+        let mut matches = text.match_indices(&replacement.old_text);
+        let Some((start, _)) = matches.next() else {
+            return Err(anyhow!("could not find {:?}", replacement.old_text));
+        };
+        if matches.next().is_some() {
+            return Err(anyhow!(
+                "{:?} occurs more than once, include more surrounding text",
+                replacement.old_text
+            ));
+        }
+        edits.push((
+            start..start + replacement.old_text.len(),
+            replacement.new_text.as_str(),
+        ));
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    for pair in edits.windows(2) {
+        if pair[0].0.end > pair[1].0.start {
+            return Err(anyhow!("edits overlap"));
+        }
+    }
+
+    let mut new_text = String::new();
+    let mut edited_ranges = Vec::new();
+    let mut last_end = 0;
+    for (range, replacement) in edits {
+        new_text.push_str(&text[last_end..range.start]);
+        let start = new_text.len();
+        new_text.push_str(replacement);
+        edited_ranges.push(start..new_text.len());
+        last_end = range.end;
+    }
+    new_text.push_str(&text[last_end..]);
+    Ok((new_text, edited_ranges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacement(old_text: &str, new_text: &str) -> TextReplacement {
+        TextReplacement {
+            path: "a.rs".into(),
+            old_text: old_text.into(),
+            new_text: new_text.into(),
+        }
+    }
+
+    #[test]
+    fn test_apply_replacements() {
+        let text = "fn one() {}\nfn two() {}\nfn three() {}\n";
+        let (new_text, edited_ranges) = apply_replacements(
+            text,
+            &[
+                replacement("fn three() {}", "fn three() -> u32 { 3 }"),
+                replacement("fn one() {}\n", ""),
+            ],
+        )
+        .unwrap();
+        assert_eq!(new_text, "fn two() {}\nfn three() -> u32 { 3 }\n");
+        assert_eq!(edited_ranges, [0..0, 12..35]);
+
+        assert!(apply_replacements(text, &[replacement("fn four", "")]).is_err());
+        assert!(apply_replacements(text, &[replacement("fn", "")]).is_err());
+        assert!(apply_replacements(
+            text,
+            &[
+                replacement("one() {}\nfn two", "x"),
+                replacement("two() {}", "y")
+            ]
+        )
+        .is_err());
+    }
+}
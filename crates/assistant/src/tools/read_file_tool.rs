@@ -0,0 +1,87 @@
+use super::{parse_arguments, AssistantTool};
+use crate::slash_command::project_path_for_str;
+use anyhow::{anyhow, Result};
+use gpui::{Task, WeakView, WindowContext};
+use language::Point;
+use serde::Deserialize;
+use serde_json::json;
+use workspace::Workspace;
+
+pub(crate) struct ReadFileTool;
+
+#[derive(Deserialize)]
+struct ReadFileArguments {
+    path: String,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+}
+
+impl AssistantTool for ReadFileTool {
+    fn name(&self) -> &'static str {
+        "read_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read the contents of a file in the project, optionally limited to a range of lines."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path of the file, relative to the project root."
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "The first line to read, starting at 1."
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "The last line to read, inclusive."
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let arguments = match parse_arguments::<ReadFileArguments>(arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let Some(project_path) = project_path_for_str(project.read(cx), &arguments.path, cx) else {
+            return Task::ready(Err(anyhow!("no such file: {}", arguments.path)));
+        };
+        let buffer = project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+
+        cx.spawn(|cx| async move {
+            let buffer = buffer.await?;
+            buffer.read_with(&cx, |buffer, _| {
+                let max_row = buffer.max_point().row;
+                let start_row = arguments.start_line.unwrap_or(1).saturating_sub(1);
+                let end_row = arguments
+                    .end_line
+                    .map_or(max_row, |line| line.saturating_sub(1).min(max_row));
+                if start_row > end_row {
+                    return Err(anyhow!("{} has only {} lines", arguments.path, max_row + 1));
+                }
+                Ok(buffer
+                    .text_for_range(
+                        Point::new(start_row, 0)..Point::new(end_row, buffer.line_len(end_row)),
+                    )
+                    .collect())
+            })?
+        })
+    }
+}
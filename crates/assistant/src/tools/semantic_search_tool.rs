@@ -0,0 +1,107 @@
+use super::{parse_arguments, AssistantTool};
+use ai::prompts::context_section::PromptContextSection;
+use anyhow::{anyhow, Result};
+use gpui::{Task, WeakView, WindowContext};
+use language::{Point, ToPoint};
+use semantic_index::SemanticIndex;
+use serde::Deserialize;
+use serde_json::json;
+use workspace::Workspace;
+
+const RESULT_COUNT: usize = 10;
+
+pub(crate) struct SemanticSearchTool;
+
+#[derive(Deserialize)]
+struct SemanticSearchArguments {
+    query: String,
+}
+
+impl AssistantTool for SemanticSearchTool {
+    fn name(&self) -> &'static str {
+        "semantic_search"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search the project for code related to a natural language description, returning the most relevant snippets."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "A description of the code to look for."
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let arguments = match parse_arguments::<SemanticSearchArguments>(arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let semantic_index = SemanticIndex::enabled(cx)
+            .then(|| SemanticIndex::global(cx))
+            .flatten();
+        let Some(semantic_index) = semantic_index else {
+            return Task::ready(Err(anyhow!("semantic search is not enabled")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let results = semantic_index.update(cx, |semantic_index, cx| {
+            semantic_index.search_project(
+                project,
+                arguments.query,
+                RESULT_COUNT,
+                Vec::new(),
+                Vec::new(),
+                cx,
+            )
+        });
+
+        cx.spawn(|cx| async move {
+            let mut sections = Vec::new();
+            for result in results.await? {
+                let section = result.buffer.read_with(&cx, |buffer, cx| {
+                    let start = result.range.start.to_point(buffer).row;
+                    let end = result.range.end.to_point(buffer).row;
+                    let path = buffer
+                        .file()
+                        .map(|file| file.full_path(cx).to_string_lossy().to_string())
+                        .unwrap_or_else(|| "untitled".into());
+                    PromptContextSection::new(
+                        format!("`{path}`, lines {}-{}", start + 1, end + 1),
+                        buffer
+                            .text_for_range(
+                                Point::new(start, 0)..Point::new(end, buffer.line_len(end)),
+                            )
+                            .collect::<String>(),
+                    )
+                    .with_language_name(
+                        buffer
+                            .language()
+                            .map(|language| language.name().to_string()),
+                    )
+                })?;
+                sections.push(section.to_prompt());
+            }
+
+            if sections.is_empty() {
+                Ok("No results.".into())
+            } else {
+                Ok(sections.join("\n\n"))
+            }
+        })
+    }
+}
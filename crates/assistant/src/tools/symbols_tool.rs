@@ -0,0 +1,90 @@
+use super::{parse_arguments, AssistantTool};
+use anyhow::{anyhow, Result};
+use gpui::{Task, WeakView, WindowContext};
+use serde::Deserialize;
+use serde_json::json;
+use std::fmt::Write;
+use workspace::Workspace;
+
+/// The maximum number of symbols to list, since language servers can return
+/// thousands of fuzzy matches.
+const MAX_SYMBOLS: usize = 50;
+
+pub(crate) struct SymbolsTool;
+
+#[derive(Deserialize)]
+struct SymbolsArguments {
+    query: String,
+}
+
+impl AssistantTool for SymbolsTool {
+    fn name(&self) -> &'static str {
+        "search_symbols"
+    }
+
+    fn description(&self) -> &'static str {
+        "Ask the project's language servers for symbols, such as functions and types, whose names match a query. Returns each symbol's kind and location."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "The name, or part of the name, of the symbol."
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let arguments = match parse_arguments::<SymbolsArguments>(arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let project = workspace.read(cx).project().clone();
+        let symbols = project.update(cx, |project, cx| project.symbols(&arguments.query, cx));
+
+        cx.spawn(|cx| async move {
+            let symbols = symbols.await?;
+            project.read_with(&cx, |project, cx| {
+                let include_root_name = project.visible_worktrees(cx).count() > 1;
+                let mut text = String::new();
+                for symbol in symbols.iter().take(MAX_SYMBOLS) {
+                    let mut path = symbol.path.path.to_string_lossy().to_string();
+                    if include_root_name {
+                        if let Some(worktree) = project.worktree_for_id(symbol.path.worktree_id, cx)
+                        {
+                            path = format!("{}/{path}", worktree.read(cx).root_name());
+                        }
+                    }
+                    writeln!(
+                        text,
+                        "{} ({:?}) {path}:{}",
+                        symbol.name,
+                        symbol.kind,
+                        symbol.range.start.0.row + 1
+                    )
+                    .unwrap();
+                }
+                if symbols.len() > MAX_SYMBOLS {
+                    writeln!(text, "(more symbols were omitted)").unwrap();
+                }
+                if text.is_empty() {
+                    text = "No symbols found.".into();
+                }
+                text
+            })
+        })
+    }
+}
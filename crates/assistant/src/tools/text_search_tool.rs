@@ -0,0 +1,90 @@
+use super::{parse_arguments, AssistantTool};
+use crate::slash_command::matching_lines;
+use anyhow::{anyhow, Result};
+use gpui::{Task, WeakView, WindowContext};
+use project::search::SearchQuery;
+use serde::Deserialize;
+use serde_json::json;
+use workspace::Workspace;
+
+pub(crate) struct TextSearchTool;
+
+#[derive(Deserialize)]
+struct TextSearchArguments {
+    query: String,
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    case_sensitive: bool,
+}
+
+impl AssistantTool for TextSearchTool {
+    fn name(&self) -> &'static str {
+        "search_text"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search the project's files for text or a regular expression, returning the matching lines."
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "The text or regular expression to search for."
+                },
+                "regex": {
+                    "type": "boolean",
+                    "description": "Whether the query is a regular expression."
+                },
+                "case_sensitive": {
+                    "type": "boolean",
+                    "description": "Whether the search is case-sensitive."
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn run(
+        &self,
+        arguments: &str,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let arguments = match parse_arguments::<TextSearchArguments>(arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+        let query = if arguments.regex {
+            SearchQuery::regex(
+                arguments.query,
+                false,
+                arguments.case_sensitive,
+                false,
+                Vec::new(),
+                Vec::new(),
+            )
+        } else {
+            SearchQuery::text(
+                arguments.query,
+                false,
+                arguments.case_sensitive,
+                false,
+                Vec::new(),
+                Vec::new(),
+            )
+        };
+        let query = match query {
+            Ok(query) => query,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let project = workspace.read(cx).project().clone();
+        matching_lines(project, query, cx)
+    }
+}
@@ -1,4 +1,5 @@
 pub mod call_settings;
+pub mod media_backend;
 pub mod participant;
 pub mod room;
 
@@ -13,10 +14,10 @@ use gpui::{
     Task, WeakModel,
 };
 use postage::watch;
-use project::Project;
+use project::{Project, ProjectPath};
 use room::Event;
 use settings::Settings;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 pub use participant::ParticipantLocation;
 pub use room::Room;
@@ -399,15 +400,52 @@ impl ActiveCall {
         &mut self,
         project: Model<Project>,
         cx: &mut ModelContext<Self>,
+    ) -> Task<Result<u64>> {
+        let scope = CallSettings::get_global(cx).share_scope();
+        self.share_project_with_scope(project, scope, cx)
+    }
+
+    /// Shares the project with the room, limiting what guests can do to the given scope.
+    pub fn share_project_with_scope(
+        &mut self,
+        project: Model<Project>,
+        scope: proto::ProjectShareScope,
+        cx: &mut ModelContext<Self>,
     ) -> Task<Result<u64>> {
         if let Some((room, _)) = self.room.as_ref() {
             self.report_call_event("share project", cx);
-            room.update(cx, |room, cx| room.share_project(project, cx))
+            room.update(cx, |room, cx| room.share_project(project, scope, cx))
         } else {
             Task::ready(Err(anyhow!("no active call")))
         }
     }
 
+    /// Creates an invite to a shared project, which room participants can use
+    /// to join it until it expires. Returns the invite's token.
+    pub fn create_project_invite(
+        &mut self,
+        project: Model<Project>,
+        read_only: bool,
+        expires_in: Duration,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<String>> {
+        let Some(project_id) = project.read(cx).remote_id() else {
+            return Task::ready(Err(anyhow!("project is not shared")));
+        };
+        if !project.read(cx).is_local() {
+            return Task::ready(Err(anyhow!("only the host can invite guests")));
+        }
+
+        self.report_call_event("create project invite", cx);
+        let request = self.client.request(proto::CreateProjectInvite {
+            project_id,
+            read_only,
+            expires_in_seconds: expires_in.as_secs(),
+        });
+        cx.background_executor()
+            .spawn(async move { Ok(request.await?.token) })
+    }
+
     pub fn unshare_project(
         &mut self,
         project: Model<Project>,
@@ -439,6 +477,22 @@ impl ActiveCall {
         Task::ready(Ok(()))
     }
 
+    /// Updates the file that collaborators see the user has open, provided
+    /// the given project is the user's current location.
+    pub fn set_active_path(
+        &mut self,
+        project: &Model<Project>,
+        path: Option<ProjectPath>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        if self.location.as_ref() == Some(&project.downgrade()) {
+            if let Some((room, _)) = self.room.as_ref() {
+                return room.update(cx, |room, cx| room.set_active_path(path, cx));
+            }
+        }
+        Task::ready(Ok(()))
+    }
+
     fn set_room(
         &mut self,
         room: Option<Model<Room>>,
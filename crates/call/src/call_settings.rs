@@ -1,4 +1,5 @@
 use anyhow::Result;
+use client::proto;
 use gpui::AppContext;
 use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
@@ -7,6 +8,8 @@ use settings::Settings;
 #[derive(Deserialize, Debug)]
 pub struct CallSettings {
     pub mute_on_join: bool,
+    pub share_excluded_paths: Vec<String>,
+    pub microphone: Option<String>,
 }
 
 /// Configuration of voice calls in Zed.
@@ -16,6 +19,28 @@ pub struct CallSettingsContent {
     ///
     /// Default: false
     pub mute_on_join: Option<bool>,
+
+    /// Globs of paths, relative to each worktree's root, that are never shared
+    /// with guests when sharing a project.
+    ///
+    /// Default: []
+    pub share_excluded_paths: Option<Vec<String>>,
+
+    /// The name of the microphone to capture audio from during calls, or
+    /// null to use the system's default microphone.
+    ///
+    /// Default: null
+    pub microphone: Option<String>,
+}
+
+impl CallSettings {
+    /// The scope that projects are shared with, unless another one is given.
+    pub fn share_scope(&self) -> proto::ProjectShareScope {
+        proto::ProjectShareScope {
+            excluded_paths: self.share_excluded_paths.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 impl Settings for CallSettings {
@@ -0,0 +1,421 @@
+use anyhow::{anyhow, Result};
+use futures::{future::LocalBoxFuture, stream::LocalBoxStream, FutureExt, StreamExt};
+use gpui::{AppContext, Global, ImageSource};
+use live_kit_client::{ConnectionState, LocalAudioTrack, LocalVideoTrack, RoomUpdate};
+use postage::stream::Stream as _;
+use std::{any::Any, fmt, sync::Arc};
+
+/// Identifies a track within a [`MediaRoom`].
+pub type TrackSid = String;
+
+/// The transport that carries the audio and screen shares of a call.
+///
+/// LiveKit is used by default. Self-hosted servers can use a different SFU, or
+/// connect the participants of a call directly, by registering another backend
+/// with [`set_media_backend`]. The connection info that the collab server
+/// hands out when joining a call is passed to [`MediaRoom::connect`] as is.
+pub trait MediaBackend: 'static {
+    /// Creates a room that isn't connected yet.
+    fn create_room(&self) -> Arc<dyn MediaRoom>;
+
+    /// Returns the names of the microphones that audio can be captured from.
+    fn audio_input_devices(&self) -> Result<Vec<String>>;
+
+    /// Captures audio from the microphone with the given name, or from the
+    /// system's default microphone when no name is given.
+    fn set_audio_input_device(&self, name: Option<&str>) -> Result<()>;
+}
+
+/// A call's connection to a [`MediaBackend`].
+///
+/// Participants are identified by the string form of their user id.
+pub trait MediaRoom {
+    fn as_any(&self) -> &dyn Any;
+    fn connection_state(&self) -> MediaConnectionState;
+    /// Yields the connection state every time it changes.
+    fn connection_state_changes(&self) -> LocalBoxStream<'static, MediaConnectionState>;
+    fn connect(&self, url: &str, token: &str) -> LocalBoxFuture<'static, Result<()>>;
+    /// Publishes audio from the selected microphone.
+    fn publish_microphone_track(
+        &self,
+    ) -> LocalBoxFuture<'static, Result<Box<dyn LocalTrackPublication>>>;
+    /// Publishes a video of the main display.
+    fn publish_screen_track(
+        &self,
+    ) -> LocalBoxFuture<'static, Result<Box<dyn LocalTrackPublication>>>;
+    fn remote_audio_tracks(&self, publisher_id: &str) -> Vec<Arc<dyn RemoteAudioTrack>>;
+    fn remote_audio_track_publications(
+        &self,
+        publisher_id: &str,
+    ) -> Vec<Arc<dyn RemoteTrackPublication>>;
+    fn remote_video_tracks(&self, publisher_id: &str) -> Vec<Arc<dyn RemoteVideoTrack>>;
+    fn updates(&self) -> LocalBoxStream<'static, MediaRoomUpdate>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MediaConnectionState {
+    Disconnected,
+    Connected,
+}
+
+/// A change to the tracks or speakers of a [`MediaRoom`].
+#[derive(Clone)]
+pub enum MediaRoomUpdate {
+    ActiveSpeakersChanged {
+        speakers: Vec<String>,
+    },
+    RemoteAudioTrackMuteChanged {
+        track_id: TrackSid,
+        muted: bool,
+    },
+    SubscribedToRemoteVideoTrack(Arc<dyn RemoteVideoTrack>),
+    SubscribedToRemoteAudioTrack(Arc<dyn RemoteAudioTrack>, Arc<dyn RemoteTrackPublication>),
+    UnsubscribedFromRemoteVideoTrack {
+        publisher_id: String,
+        track_id: TrackSid,
+    },
+    UnsubscribedFromRemoteAudioTrack {
+        publisher_id: String,
+        track_id: TrackSid,
+    },
+    LocalAudioTrackPublished {
+        track_id: TrackSid,
+    },
+    LocalAudioTrackUnpublished {
+        track_id: TrackSid,
+    },
+    LocalVideoTrackPublished {
+        track_id: TrackSid,
+    },
+    LocalVideoTrackUnpublished {
+        track_id: TrackSid,
+    },
+}
+
+/// A track that the local participant published to a [`MediaRoom`].
+pub trait LocalTrackPublication {
+    fn sid(&self) -> TrackSid;
+    fn is_muted(&self) -> bool;
+    fn set_mute(&self, muted: bool) -> LocalBoxFuture<'static, Result<()>>;
+    /// Stops sending the track to the other participants.
+    fn unpublish(self: Box<Self>);
+}
+
+/// The publication of another participant's track, which decides whether the
+/// track is received.
+pub trait RemoteTrackPublication {
+    fn sid(&self) -> TrackSid;
+    fn is_muted(&self) -> bool;
+    fn set_enabled(&self, enabled: bool) -> LocalBoxFuture<'static, Result<()>>;
+}
+
+pub trait RemoteAudioTrack {
+    fn sid(&self) -> &str;
+    fn publisher_id(&self) -> &str;
+    fn start(&self);
+    fn stop(&self);
+    #[cfg(any(test, feature = "test-support"))]
+    fn is_playing(&self) -> bool;
+}
+
+pub trait RemoteVideoTrack {
+    fn sid(&self) -> &str;
+    fn publisher_id(&self) -> &str;
+    fn frames(&self) -> LocalBoxStream<'static, Arc<dyn VideoFrame>>;
+}
+
+impl fmt::Debug for dyn RemoteAudioTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteAudioTrack")
+            .field("sid", &self.sid())
+            .field("publisher_id", &self.publisher_id())
+            .finish()
+    }
+}
+
+impl fmt::Debug for dyn RemoteVideoTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteVideoTrack")
+            .field("sid", &self.sid())
+            .field("publisher_id", &self.publisher_id())
+            .finish()
+    }
+}
+
+/// A frame of a [`RemoteVideoTrack`].
+pub trait VideoFrame {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn image(&self) -> ImageSource;
+}
+
+struct GlobalMediaBackend(Arc<dyn MediaBackend>);
+
+impl Global for GlobalMediaBackend {}
+
+/// Returns the backend that calls connect to, which is LiveKit unless another
+/// one was registered.
+pub fn media_backend(cx: &AppContext) -> Arc<dyn MediaBackend> {
+    cx.try_global::<GlobalMediaBackend>().map_or_else(
+        || Arc::new(LiveKitBackend) as _,
+        |backend| backend.0.clone(),
+    )
+}
+
+/// Makes calls that are joined from now on use the given backend.
+pub fn set_media_backend(backend: Arc<dyn MediaBackend>, cx: &mut AppContext) {
+    cx.set_global(GlobalMediaBackend(backend));
+}
+
+/// Carries calls over a LiveKit server.
+pub struct LiveKitBackend;
+
+impl MediaBackend for LiveKitBackend {
+    fn create_room(&self) -> Arc<dyn MediaRoom> {
+        Arc::new(LiveKitMediaRoom(live_kit_client::Room::new()))
+    }
+
+    fn audio_input_devices(&self) -> Result<Vec<String>> {
+        live_kit_client::audio_input_devices()
+    }
+
+    fn set_audio_input_device(&self, name: Option<&str>) -> Result<()> {
+        live_kit_client::set_audio_input_device(name)
+    }
+}
+
+pub(crate) struct LiveKitMediaRoom(pub(crate) Arc<live_kit_client::Room>);
+
+impl MediaRoom for LiveKitMediaRoom {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn connection_state(&self) -> MediaConnectionState {
+        MediaConnectionState::from(&*self.0.status().borrow())
+    }
+
+    fn connection_state_changes(&self) -> LocalBoxStream<'static, MediaConnectionState> {
+        let mut status = self.0.status();
+        // Skip the current state.
+        let _ = status.try_recv();
+        StreamExt::map(status, |state| MediaConnectionState::from(&state)).boxed_local()
+    }
+
+    fn connect(&self, url: &str, token: &str) -> LocalBoxFuture<'static, Result<()>> {
+        self.0.connect(url, token).boxed_local()
+    }
+
+    fn publish_microphone_track(
+        &self,
+    ) -> LocalBoxFuture<'static, Result<Box<dyn LocalTrackPublication>>> {
+        let room = self.0.clone();
+        async move {
+            let publication = room.publish_audio_track(LocalAudioTrack::create()).await?;
+            Ok(Box::new(LiveKitPublication { room, publication })
+                as Box<dyn LocalTrackPublication>)
+        }
+        .boxed_local()
+    }
+
+    fn publish_screen_track(
+        &self,
+    ) -> LocalBoxFuture<'static, Result<Box<dyn LocalTrackPublication>>> {
+        let room = self.0.clone();
+        async move {
+            let displays = room.display_sources().await?;
+            let display = displays
+                .first()
+                .ok_or_else(|| anyhow!("no display found"))?;
+            let track = LocalVideoTrack::screen_share_for_display(display);
+            let publication = room.publish_video_track(track).await?;
+            Ok(Box::new(LiveKitPublication { room, publication })
+                as Box<dyn LocalTrackPublication>)
+        }
+        .boxed_local()
+    }
+
+    fn remote_audio_tracks(&self, publisher_id: &str) -> Vec<Arc<dyn RemoteAudioTrack>> {
+        self.0
+            .remote_audio_tracks(publisher_id)
+            .into_iter()
+            .map(|track| track as Arc<dyn RemoteAudioTrack>)
+            .collect()
+    }
+
+    fn remote_audio_track_publications(
+        &self,
+        publisher_id: &str,
+    ) -> Vec<Arc<dyn RemoteTrackPublication>> {
+        self.0
+            .remote_audio_track_publications(publisher_id)
+            .into_iter()
+            .map(|publication| publication as Arc<dyn RemoteTrackPublication>)
+            .collect()
+    }
+
+    fn remote_video_tracks(&self, publisher_id: &str) -> Vec<Arc<dyn RemoteVideoTrack>> {
+        self.0
+            .remote_video_tracks(publisher_id)
+            .into_iter()
+            .map(|track| track as Arc<dyn RemoteVideoTrack>)
+            .collect()
+    }
+
+    fn updates(&self) -> LocalBoxStream<'static, MediaRoomUpdate> {
+        self.0.updates().map(MediaRoomUpdate::from).boxed_local()
+    }
+}
+
+impl From<&ConnectionState> for MediaConnectionState {
+    fn from(state: &ConnectionState) -> Self {
+        match state {
+            ConnectionState::Disconnected => Self::Disconnected,
+            ConnectionState::Connected { .. } => Self::Connected,
+        }
+    }
+}
+
+impl From<RoomUpdate> for MediaRoomUpdate {
+    fn from(update: RoomUpdate) -> Self {
+        match update {
+            RoomUpdate::ActiveSpeakersChanged { speakers } => {
+                Self::ActiveSpeakersChanged { speakers }
+            }
+            RoomUpdate::RemoteAudioTrackMuteChanged { track_id, muted } => {
+                Self::RemoteAudioTrackMuteChanged { track_id, muted }
+            }
+            RoomUpdate::SubscribedToRemoteVideoTrack(track) => {
+                Self::SubscribedToRemoteVideoTrack(track)
+            }
+            RoomUpdate::SubscribedToRemoteAudioTrack(track, publication) => {
+                Self::SubscribedToRemoteAudioTrack(track, publication)
+            }
+            RoomUpdate::UnsubscribedFromRemoteVideoTrack {
+                publisher_id,
+                track_id,
+            } => Self::UnsubscribedFromRemoteVideoTrack {
+                publisher_id,
+                track_id,
+            },
+            RoomUpdate::UnsubscribedFromRemoteAudioTrack {
+                publisher_id,
+                track_id,
+            } => Self::UnsubscribedFromRemoteAudioTrack {
+                publisher_id,
+                track_id,
+            },
+            RoomUpdate::LocalAudioTrackPublished { publication } => {
+                Self::LocalAudioTrackPublished {
+                    track_id: publication.sid(),
+                }
+            }
+            RoomUpdate::LocalAudioTrackUnpublished { publication } => {
+                Self::LocalAudioTrackUnpublished {
+                    track_id: publication.sid(),
+                }
+            }
+            RoomUpdate::LocalVideoTrackPublished { publication } => {
+                Self::LocalVideoTrackPublished {
+                    track_id: publication.sid(),
+                }
+            }
+            RoomUpdate::LocalVideoTrackUnpublished { publication } => {
+                Self::LocalVideoTrackUnpublished {
+                    track_id: publication.sid(),
+                }
+            }
+        }
+    }
+}
+
+struct LiveKitPublication {
+    room: Arc<live_kit_client::Room>,
+    publication: live_kit_client::LocalTrackPublication,
+}
+
+impl LocalTrackPublication for LiveKitPublication {
+    fn sid(&self) -> TrackSid {
+        self.publication.sid()
+    }
+
+    fn is_muted(&self) -> bool {
+        self.publication.is_muted()
+    }
+
+    fn set_mute(&self, muted: bool) -> LocalBoxFuture<'static, Result<()>> {
+        self.publication.set_mute(muted).boxed_local()
+    }
+
+    fn unpublish(self: Box<Self>) {
+        let LiveKitPublication { room, publication } = *self;
+        room.unpublish_track(publication)
+    }
+}
+
+impl RemoteTrackPublication for live_kit_client::RemoteTrackPublication {
+    fn sid(&self) -> TrackSid {
+        live_kit_client::RemoteTrackPublication::sid(self)
+    }
+
+    fn is_muted(&self) -> bool {
+        live_kit_client::RemoteTrackPublication::is_muted(self)
+    }
+
+    fn set_enabled(&self, enabled: bool) -> LocalBoxFuture<'static, Result<()>> {
+        live_kit_client::RemoteTrackPublication::set_enabled(self, enabled).boxed_local()
+    }
+}
+
+impl RemoteAudioTrack for live_kit_client::RemoteAudioTrack {
+    fn sid(&self) -> &str {
+        live_kit_client::RemoteAudioTrack::sid(self)
+    }
+
+    fn publisher_id(&self) -> &str {
+        live_kit_client::RemoteAudioTrack::publisher_id(self)
+    }
+
+    fn start(&self) {
+        live_kit_client::RemoteAudioTrack::start(self)
+    }
+
+    fn stop(&self) {
+        live_kit_client::RemoteAudioTrack::stop(self)
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    fn is_playing(&self) -> bool {
+        live_kit_client::RemoteAudioTrack::is_playing(self)
+    }
+}
+
+impl RemoteVideoTrack for live_kit_client::RemoteVideoTrack {
+    fn sid(&self) -> &str {
+        live_kit_client::RemoteVideoTrack::sid(self)
+    }
+
+    fn publisher_id(&self) -> &str {
+        live_kit_client::RemoteVideoTrack::publisher_id(self)
+    }
+
+    fn frames(&self) -> LocalBoxStream<'static, Arc<dyn VideoFrame>> {
+        live_kit_client::RemoteVideoTrack::frames(self)
+            .map(|frame| Arc::new(frame) as Arc<dyn VideoFrame>)
+            .boxed_local()
+    }
+}
+
+impl VideoFrame for live_kit_client::Frame {
+    fn width(&self) -> usize {
+        live_kit_client::Frame::width(self)
+    }
+
+    fn height(&self) -> usize {
+        live_kit_client::Frame::height(self)
+    }
+
+    fn image(&self) -> ImageSource {
+        live_kit_client::Frame::image(self).into()
+    }
+}
@@ -1,12 +1,11 @@
+pub use crate::media_backend::{RemoteAudioTrack, RemoteVideoTrack, TrackSid, VideoFrame};
 use anyhow::{anyhow, Result};
 use client::ParticipantIndex;
 use client::{proto, User};
 use collections::HashMap;
 use gpui::WeakModel;
-pub use live_kit_client::Frame;
-pub use live_kit_client::{RemoteAudioTrack, RemoteVideoTrack};
-use project::Project;
-use std::sync::Arc;
+use project::{Project, ProjectPath, WorktreeId};
+use std::{path::Path, sync::Arc};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ParticipantLocation {
@@ -32,10 +31,24 @@ impl ParticipantLocation {
     }
 }
 
+/// Returns the file a participant has open in their shared project, if any.
+pub fn active_path_from_proto(
+    location: Option<&proto::ParticipantLocation>,
+) -> Option<ProjectPath> {
+    match location?.variant.as_ref()? {
+        proto::participant_location::Variant::SharedProject(project) => Some(ProjectPath {
+            worktree_id: WorktreeId::from_proto(project.worktree_id?),
+            path: Arc::from(Path::new(project.path.as_ref()?)),
+        }),
+        _ => None,
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct LocalParticipant {
     pub projects: Vec<proto::ParticipantProject>,
     pub active_project: Option<WeakModel<Project>>,
+    pub active_path: Option<ProjectPath>,
     pub role: proto::ChannelRole,
 }
 
@@ -46,10 +59,12 @@ pub struct RemoteParticipant {
     pub role: proto::ChannelRole,
     pub projects: Vec<proto::ParticipantProject>,
     pub location: ParticipantLocation,
+    /// The file this participant has open, when their location is a shared project.
+    pub active_path: Option<ProjectPath>,
     pub participant_index: ParticipantIndex,
     pub muted: bool,
     pub speaking: bool,
     pub in_call: bool,
-    pub video_tracks: HashMap<live_kit_client::Sid, Arc<RemoteVideoTrack>>,
-    pub audio_tracks: HashMap<live_kit_client::Sid, Arc<RemoteAudioTrack>>,
+    pub video_tracks: HashMap<TrackSid, Arc<dyn RemoteVideoTrack>>,
+    pub audio_tracks: HashMap<TrackSid, Arc<dyn RemoteAudioTrack>>,
 }
@@ -1,6 +1,9 @@
 use crate::{
     call_settings::CallSettings,
-    participant::{LocalParticipant, ParticipantLocation, RemoteParticipant},
+    media_backend::{
+        media_backend, LocalTrackPublication, MediaConnectionState, MediaRoom, MediaRoomUpdate,
+    },
+    participant::{self, LocalParticipant, ParticipantLocation, RemoteParticipant},
 };
 use anyhow::{anyhow, Result};
 use audio::{Audio, Sound};
@@ -15,9 +18,8 @@ use gpui::{
     AppContext, AsyncAppContext, Context, EventEmitter, Model, ModelContext, Task, WeakModel,
 };
 use language::LanguageRegistry;
-use live_kit_client::{LocalAudioTrack, LocalTrackPublication, LocalVideoTrack, RoomUpdate};
 use postage::{sink::Sink, stream::Stream, watch};
-use project::Project;
+use project::{Project, ProjectPath};
 use settings::Settings as _;
 use std::{future::Future, mem, sync::Arc, time::Duration};
 use util::{post_inc, ResultExt, TryFutureExt};
@@ -95,14 +97,9 @@ impl Room {
 
     #[cfg(any(test, feature = "test-support"))]
     pub fn is_connected(&self) -> bool {
-        if let Some(live_kit) = self.live_kit.as_ref() {
-            matches!(
-                *live_kit.room.status().borrow(),
-                live_kit_client::ConnectionState::Connected { .. }
-            )
-        } else {
-            false
-        }
+        self.live_kit.as_ref().map_or(false, |live_kit| {
+            live_kit.room.connection_state() == MediaConnectionState::Connected
+        })
     }
 
     fn new(
@@ -183,7 +180,8 @@ impl Room {
             let initial_project_id = if let Some(initial_project) = initial_project {
                 let initial_project_id = room
                     .update(&mut cx, |room, cx| {
-                        room.share_project(initial_project.clone(), cx)
+                        let scope = CallSettings::get_global(cx).share_scope();
+                        room.share_project(initial_project.clone(), scope, cx)
                     })?
                     .await?;
                 Some(initial_project_id)
@@ -777,6 +775,8 @@ impl Room {
 
                         let role = participant.role();
                         let in_call = participant.in_call;
+                        let active_path =
+                            participant::active_path_from_proto(participant.location.as_ref());
                         let location = ParticipantLocation::from_proto(participant.location)
                             .unwrap_or(ParticipantLocation::External);
                         if let Some(remote_participant) =
@@ -786,6 +786,7 @@ impl Room {
                             remote_participant.projects = participant.projects;
                             remote_participant.participant_index = participant_index;
                             if location != remote_participant.location
+                                || active_path != remote_participant.active_path
                                 || role != remote_participant.role
                                 || in_call != remote_participant.in_call
                             {
@@ -793,6 +794,7 @@ impl Room {
                                     Audio::play_sound(Sound::Joined, cx);
                                 }
                                 remote_participant.location = location;
+                                remote_participant.active_path = active_path;
                                 remote_participant.role = role;
                                 remote_participant.in_call = participant.in_call;
 
@@ -809,6 +811,7 @@ impl Room {
                                     peer_id,
                                     projects: participant.projects,
                                     location,
+                                    active_path,
                                     role,
                                     muted: true,
                                     speaking: false,
@@ -833,7 +836,7 @@ impl Room {
 
                                 for track in video_tracks {
                                     this.live_kit_room_updated(
-                                        RoomUpdate::SubscribedToRemoteVideoTrack(track),
+                                        MediaRoomUpdate::SubscribedToRemoteVideoTrack(track),
                                         cx,
                                     )
                                     .log_err();
@@ -843,7 +846,7 @@ impl Room {
                                     audio_tracks.iter().zip(publications.iter())
                                 {
                                     this.live_kit_room_updated(
-                                        RoomUpdate::SubscribedToRemoteAudioTrack(
+                                        MediaRoomUpdate::SubscribedToRemoteAudioTrack(
                                             track.clone(),
                                             publication.clone(),
                                         ),
@@ -936,11 +939,11 @@ impl Room {
 
     fn live_kit_room_updated(
         &mut self,
-        update: RoomUpdate,
+        update: MediaRoomUpdate,
         cx: &mut ModelContext<Self>,
     ) -> Result<()> {
         match update {
-            RoomUpdate::SubscribedToRemoteVideoTrack(track) => {
+            MediaRoomUpdate::SubscribedToRemoteVideoTrack(track) => {
                 let user_id = track.publisher_id().parse()?;
                 let track_id = track.sid().to_string();
                 let participant = self
@@ -953,7 +956,7 @@ impl Room {
                 });
             }
 
-            RoomUpdate::UnsubscribedFromRemoteVideoTrack {
+            MediaRoomUpdate::UnsubscribedFromRemoteVideoTrack {
                 publisher_id,
                 track_id,
             } => {
@@ -968,7 +971,7 @@ impl Room {
                 });
             }
 
-            RoomUpdate::ActiveSpeakersChanged { speakers } => {
+            MediaRoomUpdate::ActiveSpeakersChanged { speakers } => {
                 let mut speaker_ids = speakers
                     .into_iter()
                     .filter_map(|speaker_sid| speaker_sid.parse().ok())
@@ -992,7 +995,7 @@ impl Room {
                 }
             }
 
-            RoomUpdate::RemoteAudioTrackMuteChanged { track_id, muted } => {
+            MediaRoomUpdate::RemoteAudioTrackMuteChanged { track_id, muted } => {
                 let mut found = false;
                 for participant in &mut self.remote_participants.values_mut() {
                     for track in participant.audio_tracks.values() {
@@ -1008,7 +1011,7 @@ impl Room {
                 }
             }
 
-            RoomUpdate::SubscribedToRemoteAudioTrack(track, publication) => {
+            MediaRoomUpdate::SubscribedToRemoteAudioTrack(track, publication) => {
                 let user_id = track.publisher_id().parse()?;
                 let track_id = track.sid().to_string();
                 let participant = self
@@ -1023,7 +1026,7 @@ impl Room {
                 });
             }
 
-            RoomUpdate::UnsubscribedFromRemoteAudioTrack {
+            MediaRoomUpdate::UnsubscribedFromRemoteAudioTrack {
                 publisher_id,
                 track_id,
             } => {
@@ -1038,26 +1041,26 @@ impl Room {
                 });
             }
 
-            RoomUpdate::LocalAudioTrackUnpublished { publication } => {
-                log::info!("unpublished audio track {}", publication.sid());
+            MediaRoomUpdate::LocalAudioTrackUnpublished { track_id } => {
+                log::info!("unpublished audio track {}", track_id);
                 if let Some(room) = &mut self.live_kit {
                     room.microphone_track = LocalTrack::None;
                 }
             }
 
-            RoomUpdate::LocalVideoTrackUnpublished { publication } => {
-                log::info!("unpublished video track {}", publication.sid());
+            MediaRoomUpdate::LocalVideoTrackUnpublished { track_id } => {
+                log::info!("unpublished video track {}", track_id);
                 if let Some(room) = &mut self.live_kit {
                     room.screen_track = LocalTrack::None;
                 }
             }
 
-            RoomUpdate::LocalAudioTrackPublished { publication } => {
-                log::info!("published audio track {}", publication.sid());
+            MediaRoomUpdate::LocalAudioTrackPublished { track_id } => {
+                log::info!("published audio track {}", track_id);
             }
 
-            RoomUpdate::LocalVideoTrackPublished { publication } => {
-                log::info!("published video track {}", publication.sid());
+            MediaRoomUpdate::LocalVideoTrackPublished { track_id } => {
+                log::info!("published video track {}", track_id);
             }
         }
 
@@ -1124,6 +1127,18 @@ impl Room {
         language_registry: Arc<LanguageRegistry>,
         fs: Arc<dyn Fs>,
         cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Project>>> {
+        self.join_project_with_invite(id, None, language_registry, fs, cx)
+    }
+
+    /// Joins a project in this room, using an invite created by its host.
+    pub fn join_project_with_invite(
+        &mut self,
+        id: u64,
+        invite_token: Option<String>,
+        language_registry: Arc<LanguageRegistry>,
+        fs: Arc<dyn Fs>,
+        cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Project>>> {
         let client = self.client.clone();
         let user_store = self.user_store.clone();
@@ -1137,6 +1152,7 @@ impl Room {
                 language_registry,
                 fs,
                 role,
+                invite_token,
                 cx.clone(),
             )
             .await?;
@@ -1158,21 +1174,24 @@ impl Room {
     pub(crate) fn share_project(
         &mut self,
         project: Model<Project>,
+        scope: proto::ProjectShareScope,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<u64>> {
         if let Some(project_id) = project.read(cx).remote_id() {
             return Task::ready(Ok(project_id));
         }
 
+        let excluded_paths = scope.excluded_paths.clone();
         let request = self.client.request(proto::ShareProject {
             room_id: self.id(),
             worktrees: project.read(cx).worktree_metadata_protos(cx),
+            scope: Some(scope),
         });
         cx.spawn(|this, mut cx| async move {
             let response = request.await?;
 
             project.update(&mut cx, |project, cx| {
-                project.shared(response.project_id, cx)
+                project.shared(response.project_id, &excluded_paths, cx)
             })??;
 
             // If the user's location is in this project, it changes from UnsharedProject to SharedProject.
@@ -1222,10 +1241,20 @@ impl Room {
         let client = self.client.clone();
         let room_id = self.id;
         let location = if let Some(project) = project {
-            self.local_participant.active_project = Some(project.downgrade());
+            let project_changed =
+                self.local_participant.active_project.as_ref() != Some(&project.downgrade());
+            if project_changed {
+                self.local_participant.active_project = Some(project.downgrade());
+                self.local_participant.active_path = None;
+            }
             if let Some(project_id) = project.read(cx).remote_id() {
+                let active_path = self.local_participant.active_path.as_ref();
                 proto::participant_location::Variant::SharedProject(
-                    proto::participant_location::SharedProject { id: project_id },
+                    proto::participant_location::SharedProject {
+                        id: project_id,
+                        worktree_id: active_path.map(|path| path.worktree_id.to_proto()),
+                        path: active_path.map(|path| path.path.to_string_lossy().to_string()),
+                    },
                 )
             } else {
                 proto::participant_location::Variant::UnsharedProject(
@@ -1234,6 +1263,7 @@ impl Room {
             }
         } else {
             self.local_participant.active_project = None;
+            self.local_participant.active_path = None;
             proto::participant_location::Variant::External(proto::participant_location::External {})
         };
 
@@ -1251,6 +1281,41 @@ impl Room {
         })
     }
 
+    /// Updates the file the local participant has open in their active project.
+    pub(crate) fn set_active_path(
+        &mut self,
+        path: Option<ProjectPath>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        if self.local_participant.active_path == path {
+            return Task::ready(Ok(()));
+        }
+        let Some(project) = self
+            .local_participant
+            .active_project
+            .as_ref()
+            .and_then(|project| project.upgrade())
+        else {
+            return Task::ready(Ok(()));
+        };
+        self.local_participant.active_path = path;
+        self.set_location(Some(&project), cx)
+    }
+
+    /// Returns the participants who have the given file open in a shared project.
+    pub fn remote_participants_at_path<'a>(
+        &'a self,
+        project_id: u64,
+        path: &'a ProjectPath,
+    ) -> impl Iterator<Item = &'a RemoteParticipant> + 'a {
+        self.remote_participants
+            .values()
+            .filter(move |participant| {
+                participant.location == ParticipantLocation::SharedProject { project_id }
+                    && participant.active_path.as_ref() == Some(path)
+            })
+    }
+
     pub fn is_screen_sharing(&self) -> bool {
         self.live_kit.as_ref().map_or(false, |live_kit| {
             !matches!(live_kit.screen_track, LocalTrack::None)
@@ -1288,6 +1353,11 @@ impl Room {
             return Task::ready(Err(anyhow!("room is offline")));
         }
 
+        let microphone = CallSettings::get_global(cx).microphone.clone();
+        media_backend(cx)
+            .set_audio_input_device(microphone.as_deref())
+            .log_err();
+
         let publish_id = if let Some(live_kit) = self.live_kit.as_mut() {
             let publish_id = post_inc(&mut live_kit.next_publish_id);
             live_kit.microphone_track = LocalTrack::Pending { publish_id };
@@ -1299,13 +1369,12 @@ impl Room {
 
         cx.spawn(move |this, mut cx| async move {
             let publish_track = async {
-                let track = LocalAudioTrack::create();
                 this.upgrade()
                     .ok_or_else(|| anyhow!("room was dropped"))?
                     .update(&mut cx, |this, _| {
                         this.live_kit
                             .as_ref()
-                            .map(|live_kit| live_kit.room.publish_audio_track(track))
+                            .map(|live_kit| live_kit.room.publish_microphone_track())
                     })?
                     .ok_or_else(|| anyhow!("live-kit was not initialized"))?
                     .await
@@ -1331,7 +1400,7 @@ impl Room {
                     match publication {
                         Ok(publication) => {
                             if canceled {
-                                live_kit.room.unpublish_track(publication);
+                                publication.unpublish();
                                 live_kit.microphone_track = LocalTrack::None;
                             } else {
                                 live_kit.microphone_track = LocalTrack::Published {
@@ -1362,28 +1431,23 @@ impl Room {
             return Task::ready(Err(anyhow!("screen was already shared")));
         }
 
-        let (displays, publish_id) = if let Some(live_kit) = self.live_kit.as_mut() {
+        let publish_id = if let Some(live_kit) = self.live_kit.as_mut() {
             let publish_id = post_inc(&mut live_kit.next_publish_id);
             live_kit.screen_track = LocalTrack::Pending { publish_id };
             cx.notify();
-            (live_kit.room.display_sources(), publish_id)
+            publish_id
         } else {
             return Task::ready(Err(anyhow!("live-kit was not initialized")));
         };
 
         cx.spawn(move |this, mut cx| async move {
             let publish_track = async {
-                let displays = displays.await?;
-                let display = displays
-                    .first()
-                    .ok_or_else(|| anyhow!("no display found"))?;
-                let track = LocalVideoTrack::screen_share_for_display(display);
                 this.upgrade()
                     .ok_or_else(|| anyhow!("room was dropped"))?
                     .update(&mut cx, |this, _| {
                         this.live_kit
                             .as_ref()
-                            .map(|live_kit| live_kit.room.publish_video_track(track))
+                            .map(|live_kit| live_kit.room.publish_screen_track())
                     })?
                     .ok_or_else(|| anyhow!("live-kit was not initialized"))?
                     .await
@@ -1410,7 +1474,7 @@ impl Room {
                     match publication {
                         Ok(publication) => {
                             if canceled {
-                                live_kit.room.unpublish_track(publication);
+                                publication.unpublish();
                             } else {
                                 live_kit.screen_track = LocalTrack::Published {
                                     track_publication: publication,
@@ -1437,21 +1501,66 @@ impl Room {
     }
 
     pub fn toggle_mute(&mut self, cx: &mut ModelContext<Self>) {
+        if let Some(live_kit) = self.live_kit.as_mut() {
+            live_kit.push_to_talk = false;
+        }
         let muted = !self.is_muted();
         if let Some(task) = self.set_mute(muted, cx) {
             task.detach_and_log_err(cx);
         }
     }
 
+    pub fn is_pushing_to_talk(&self) -> bool {
+        self.live_kit
+            .as_ref()
+            .map_or(false, |live_kit| live_kit.push_to_talk)
+    }
+
+    /// Unmutes the microphone until [`Room::end_push_to_talk`] is called.
+    /// Does nothing if the microphone isn't muted.
+    pub fn start_push_to_talk(&mut self, cx: &mut ModelContext<Self>) {
+        if self.read_only() || !self.is_muted() {
+            return;
+        }
+        let Some(live_kit) = self.live_kit.as_mut() else {
+            return;
+        };
+        if live_kit.push_to_talk {
+            return;
+        }
+
+        live_kit.push_to_talk = true;
+        if let Some(task) = self.set_mute(false, cx) {
+            task.detach_and_log_err(cx);
+        }
+    }
+
+    pub fn end_push_to_talk(&mut self, cx: &mut ModelContext<Self>) {
+        let Some(live_kit) = self.live_kit.as_mut() else {
+            return;
+        };
+        if !mem::take(&mut live_kit.push_to_talk) {
+            return;
+        }
+
+        // A microphone that is still being published is dropped once its
+        // publication completes.
+        if let LocalTrack::Pending { .. } = live_kit.microphone_track {
+            live_kit.microphone_track = LocalTrack::None;
+            Audio::play_sound(Sound::Mute, cx);
+            cx.notify();
+        } else if let Some(task) = self.set_mute(true, cx) {
+            task.detach_and_log_err(cx);
+        }
+    }
+
     pub fn join_call(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
         if self.live_kit.is_some() {
             return Task::ready(Ok(()));
         }
 
-        let room = live_kit_client::Room::new();
-        let mut status = room.status();
-        // Consume the initial status of the room.
-        let _ = status.try_recv();
+        let room = media_backend(cx).create_room();
+        let mut status = room.connection_state_changes();
         let _maintain_room = cx.spawn(|this, mut cx| async move {
             while let Some(status) = status.next().await {
                 let this = if let Some(this) = this.upgrade() {
@@ -1460,7 +1569,7 @@ impl Room {
                     break;
                 };
 
-                if status == live_kit_client::ConnectionState::Disconnected {
+                if status == MediaConnectionState::Disconnected {
                     this.update(&mut cx, |this, cx| this.leave(cx).log_err())
                         .ok();
                     break;
@@ -1493,6 +1602,7 @@ impl Room {
             microphone_track: LocalTrack::None,
             next_publish_id: 0,
             speaking: false,
+            push_to_talk: false,
             _maintain_room,
             _handle_updates,
         });
@@ -1592,7 +1702,7 @@ impl Room {
             LocalTrack::Published {
                 track_publication, ..
             } => {
-                live_kit.room.unpublish_track(track_publication);
+                track_publication.unpublish();
                 cx.notify();
 
                 Audio::play_sound(Sound::StopScreenshare, cx);
@@ -1637,15 +1747,20 @@ impl Room {
             .as_ref()
             .unwrap()
             .room
+            .as_any()
+            .downcast_ref::<crate::media_backend::LiveKitMediaRoom>()
+            .expect("display sources can only be set on LiveKit rooms")
+            .0
             .set_display_sources(sources);
     }
 }
 
 struct LiveKitRoom {
-    room: Arc<live_kit_client::Room>,
+    room: Arc<dyn MediaRoom>,
     screen_track: LocalTrack,
     microphone_track: LocalTrack,
     speaking: bool,
+    push_to_talk: bool,
     next_publish_id: usize,
     _maintain_room: Task<()>,
     _handle_updates: Task<()>,
@@ -1657,7 +1772,7 @@ impl LiveKitRoom {
             track_publication, ..
         } = mem::replace(&mut self.microphone_track, LocalTrack::None)
         {
-            self.room.unpublish_track(track_publication);
+            track_publication.unpublish();
             cx.notify();
         }
 
@@ -1665,7 +1780,7 @@ impl LiveKitRoom {
             track_publication, ..
         } = mem::replace(&mut self.screen_track, LocalTrack::None)
         {
-            self.room.unpublish_track(track_publication);
+            track_publication.unpublish();
             cx.notify();
         }
     }
@@ -1677,7 +1792,7 @@ enum LocalTrack {
         publish_id: usize,
     },
     Published {
-        track_publication: LocalTrackPublication,
+        track_publication: Box<dyn LocalTrackPublication>,
     },
 }
 
@@ -6,10 +6,13 @@ use client::{Client, UserStore};
 use gpui::{AppContext, Model};
 use std::sync::Arc;
 
-pub use channel_buffer::{ChannelBuffer, ChannelBufferEvent, ACKNOWLEDGE_DEBOUNCE_INTERVAL};
+pub use channel_buffer::{
+    ChannelBuffer, ChannelBufferEvent, NotesSnapshot, ACKNOWLEDGE_DEBOUNCE_INTERVAL,
+    SAVE_NOTES_DEBOUNCE_INTERVAL,
+};
 pub use channel_chat::{
-    mentions_to_proto, ChannelChat, ChannelChatEvent, ChannelMessage, ChannelMessageId,
-    MessageParams,
+    mentions_to_proto, ChannelChat, ChannelChatEvent, ChannelMessage, ChannelMessageEdit,
+    ChannelMessageId, ChatAttachment, ChatReaction, MessageParams,
 };
 pub use channel_store::{Channel, ChannelEvent, ChannelId, ChannelMembership, ChannelStore};
 
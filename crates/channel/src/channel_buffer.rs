@@ -1,18 +1,27 @@
+mod persistence;
+
 use crate::{Channel, ChannelId, ChannelStore};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use client::{Client, Collaborator, UserStore, ZED_ALWAYS_ACTIVE};
 use collections::HashMap;
-use gpui::{AppContext, AsyncAppContext, Context, EventEmitter, Model, ModelContext, Task};
+use futures::Future;
+use gpui::{
+    AppContext, AsyncAppContext, Context, EventEmitter, Model, ModelContext, Subscription, Task,
+};
 use language::proto::serialize_version;
+use persistence::{SavedChannelNotes, CHANNEL_NOTES_DB};
 use rpc::{
     proto::{self, PeerId},
     TypedEnvelope,
 };
-use std::{sync::Arc, time::Duration};
+use similar::{ChangeTag, TextDiff};
+use std::{mem, ops::Range, sync::Arc, time::Duration};
 use text::BufferId;
+use time::OffsetDateTime;
 use util::ResultExt;
 
 pub const ACKNOWLEDGE_DEBOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+pub const SAVE_NOTES_DEBOUNCE_INTERVAL: Duration = Duration::from_secs(1);
 
 pub(crate) fn init(client: &Arc<Client>) {
     client.add_model_message_handler(ChannelBuffer::handle_update_channel_buffer);
@@ -22,23 +31,41 @@ pub(crate) fn init(client: &Arc<Client>) {
 pub struct ChannelBuffer {
     pub channel_id: ChannelId,
     connected: bool,
+    /// Whether the buffer lost its connection but can still be edited, with the
+    /// edits being merged into the notes once the client reconnects.
+    offline: bool,
     collaborators: HashMap<PeerId, Collaborator>,
     user_store: Model<UserStore>,
     channel_store: Model<ChannelStore>,
     buffer: Model<language::Buffer>,
     buffer_epoch: u64,
+    /// The operations that the server is known to have received.
+    synced_version: clock::Global,
     client: Arc<Client>,
     subscription: Option<client::Subscription>,
+    _buffer_subscription: Subscription,
     acknowledge_task: Option<Task<Result<()>>>,
+    save_task: Option<Task<()>>,
 }
 
 pub enum ChannelBufferEvent {
     CollaboratorsChanged,
     Disconnected,
+    Offline,
+    Reconnected,
+    /// The buffer was replaced by a new one after rejoining the notes.
+    BufferReplaced,
     BufferEdited,
     ChannelChanged,
 }
 
+/// A snapshot of a channel's notes, taken when everyone stopped editing them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotesSnapshot {
+    pub epoch: u64,
+    pub timestamp: Option<OffsetDateTime>,
+}
+
 impl EventEmitter<ChannelBufferEvent> for ChannelBuffer {}
 
 impl ChannelBuffer {
@@ -49,47 +76,112 @@ impl ChannelBuffer {
         channel_store: Model<ChannelStore>,
         mut cx: AsyncAppContext,
     ) -> Result<Model<Self>> {
-        let response = client
+        let (response, saved_version) = match client
             .request(proto::JoinChannelBuffer {
                 channel_id: channel.id,
             })
-            .await?;
-        let buffer_id = BufferId::new(response.buffer_id)?;
-        let base_text = response.base_text;
-        let operations = response
-            .operations
-            .into_iter()
-            .map(language::proto::deserialize_operation)
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let buffer = cx.new_model(|cx| {
-            let capability = channel_store.read(cx).channel_capability(channel.id);
-            language::Buffer::remote(buffer_id, response.replica_id as u16, capability, base_text)
-        })?;
-        buffer.update(&mut cx, |buffer, cx| buffer.apply_ops(operations, cx))??;
+            .await
+        {
+            Ok(response) => (response, None),
+            Err(error) => {
+                // Without a connection, fall back to the notes that were saved locally.
+                if client.status().borrow().is_connected() {
+                    return Err(error);
+                }
+                match load_saved_notes(&client, channel.id) {
+                    Some(notes) => (notes.state, Some(notes.synced_version)),
+                    None => return Err(error),
+                }
+            }
+        };
+        let offline = saved_version.is_some();
 
-        let subscription = client.subscribe_to_entity(channel.id)?;
+        let buffer = build_buffer(channel.id, &response, &channel_store, &mut cx)?;
+        let synced_version = match saved_version {
+            Some(version) => version,
+            None => buffer.read_with(&cx, |buffer, _| buffer.version())?,
+        };
+        let subscription = if offline {
+            None
+        } else {
+            Some(client.subscribe_to_entity(channel.id)?)
+        };
 
         anyhow::Ok(cx.new_model(|cx| {
-            cx.subscribe(&buffer, Self::on_buffer_update).detach();
             cx.on_release(Self::release).detach();
+            let subscription = subscription
+                .map(|subscription| subscription.set_model(&cx.handle(), &mut cx.to_async()));
             let mut this = Self {
+                _buffer_subscription: cx.subscribe(&buffer, Self::on_buffer_update),
                 buffer,
                 buffer_epoch: response.epoch,
+                synced_version,
                 client,
-                connected: true,
+                connected: !offline,
+                offline,
                 collaborators: Default::default(),
                 acknowledge_task: None,
+                save_task: None,
                 channel_id: channel.id,
-                subscription: Some(subscription.set_model(&cx.handle(), &mut cx.to_async())),
+                subscription,
                 user_store,
                 channel_store,
             };
             this.replace_collaborators(response.collaborators, cx);
+            if !offline {
+                this.save_notes(cx);
+            }
             this
         })?)
     }
 
+    /// Joins the notes again after the server has forgotten about this client,
+    /// replaying any edits that were made while offline on top of the latest text.
+    pub(crate) fn rejoin(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let client = self.client.clone();
+        let channel_id = self.channel_id;
+        let channel_store = self.channel_store.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let response = client
+                .request(proto::JoinChannelBuffer { channel_id })
+                .await?;
+            let buffer = build_buffer(channel_id, &response, &channel_store, &mut cx)?;
+            this.update(&mut cx, |this, _| this.subscription.take())?;
+            let subscription = client.subscribe_to_entity(channel_id)?;
+
+            this.update(&mut cx, |this, cx| {
+                let merged_text = rebase_offline_edits(
+                    this.buffer.read(cx),
+                    &this.synced_version,
+                    &buffer.read(cx).text(),
+                )?;
+
+                let old_buffer = mem::replace(&mut this.buffer, buffer.clone());
+                if let Some(language) = old_buffer.read(cx).language().cloned() {
+                    buffer.update(cx, |buffer, cx| buffer.set_language(Some(language), cx));
+                }
+                this._buffer_subscription = cx.subscribe(&buffer, Self::on_buffer_update);
+                this.subscription = Some(subscription.set_model(&cx.handle(), &mut cx.to_async()));
+                this.buffer_epoch = response.epoch;
+                this.synced_version = buffer.read(cx).version();
+                this.connected = true;
+                this.offline = false;
+                this.collaborators.clear();
+                this.replace_collaborators(response.collaborators, cx);
+
+                // Editing the new buffer sends the offline edits to the other collaborators.
+                let edits = diff_edits(&buffer.read(cx).text(), &merged_text);
+                if !edits.is_empty() {
+                    buffer.update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+                }
+                this.save_notes(cx);
+                cx.emit(ChannelBufferEvent::BufferReplaced);
+                cx.notify();
+                anyhow::Ok(())
+            })?
+        })
+    }
+
     fn release(&mut self, _: &mut AppContext) {
         if self.connected {
             if let Some(task) = self.acknowledge_task.take() {
@@ -150,6 +242,11 @@ impl ChannelBuffer {
 
         this.update(&mut cx, |this, cx| {
             cx.notify();
+            for operation in &ops {
+                if let language::Operation::Buffer(operation) = operation {
+                    this.synced_version.observe(operation.timestamp());
+                }
+            }
             this.buffer
                 .update(cx, |buffer, cx| buffer.apply_ops(ops, cx))
         })??;
@@ -188,15 +285,22 @@ impl ChannelBuffer {
                         _ => {}
                     }
                 }
-                let operation = language::proto::serialize_operation(operation);
-                self.client
+                if !self.connected {
+                    return;
+                }
+                let sent = self
+                    .client
                     .send(proto::UpdateChannelBuffer {
                         channel_id: self.channel_id,
-                        operations: vec![operation],
+                        operations: vec![language::proto::serialize_operation(operation)],
                     })
                     .log_err();
+                if let (Some(()), language::Operation::Buffer(operation)) = (sent, operation) {
+                    self.synced_version.observe(operation.timestamp());
+                }
             }
             language::Event::Edited => {
+                self.save_notes(cx);
                 cx.emit(ChannelBufferEvent::BufferEdited);
             }
             _ => {}
@@ -225,6 +329,104 @@ impl ChannelBuffer {
         }));
     }
 
+    /// Saves the notes locally once they stop changing, so that they can be
+    /// edited without a connection.
+    fn save_notes(&mut self, cx: &mut ModelContext<Self>) {
+        let Some((server_url, user_id)) = notes_key(&self.client) else {
+            return;
+        };
+        self.save_task = Some(cx.spawn(move |this, mut cx| async move {
+            cx.background_executor()
+                .timer(SAVE_NOTES_DEBOUNCE_INTERVAL)
+                .await;
+            let Some(notes) = this
+                .update(&mut cx, |this, cx| this.saved_notes(cx))
+                .log_err()
+            else {
+                return;
+            };
+            CHANNEL_NOTES_DB
+                .save_channel_notes(server_url, user_id, notes.await)
+                .await
+                .log_err();
+        }));
+    }
+
+    fn saved_notes(&self, cx: &mut AppContext) -> impl Future<Output = SavedChannelNotes> {
+        let channel_id = self.channel_id;
+        let epoch = self.buffer_epoch;
+        let synced_version = self.synced_version.clone();
+        let buffer = self.buffer.read(cx);
+        let buffer_id = buffer.remote_id().into();
+        let replica_id = buffer.replica_id() as u32;
+        let base_text = buffer.base_text().to_string();
+        let operations = self
+            .buffer
+            .update(cx, |buffer, cx| buffer.serialize_ops(None, cx));
+        async move {
+            SavedChannelNotes {
+                channel_id,
+                state: proto::JoinChannelBufferResponse {
+                    buffer_id,
+                    replica_id,
+                    base_text,
+                    operations: operations.await,
+                    collaborators: Vec::new(),
+                    epoch,
+                },
+                synced_version,
+            }
+        }
+    }
+
+    pub(crate) fn forget_saved_notes(&mut self, cx: &mut ModelContext<Self>) {
+        self.save_task.take();
+        if let Some((server_url, user_id)) = notes_key(&self.client) {
+            let channel_id = self.channel_id;
+            cx.background_executor()
+                .spawn(async move {
+                    CHANNEL_NOTES_DB
+                        .delete_channel_notes(server_url, user_id, channel_id)
+                        .await
+                        .log_err();
+                })
+                .detach();
+        }
+    }
+
+    /// Lists the snapshots that were taken of the notes, oldest first.
+    pub fn snapshots(&self, cx: &AppContext) -> Task<Result<Vec<NotesSnapshot>>> {
+        let client = self.client.clone();
+        let channel_id = self.channel_id;
+        cx.spawn(move |_| async move {
+            let response = client
+                .request(proto::GetChannelBufferSnapshots { channel_id })
+                .await?;
+            Ok(response
+                .snapshots
+                .into_iter()
+                .map(|snapshot| NotesSnapshot {
+                    epoch: snapshot.epoch,
+                    timestamp: snapshot.timestamp.and_then(|timestamp| {
+                        OffsetDateTime::from_unix_timestamp(timestamp as i64).ok()
+                    }),
+                })
+                .collect())
+        })
+    }
+
+    /// Retrieves the text of the notes as of the given snapshot.
+    pub fn snapshot_text(&self, epoch: u64, cx: &AppContext) -> Task<Result<String>> {
+        let client = self.client.clone();
+        let channel_id = self.channel_id;
+        cx.spawn(move |_| async move {
+            let response = client
+                .request(proto::GetChannelBufferSnapshot { channel_id, epoch })
+                .await?;
+            Ok(response.text)
+        })
+    }
+
     pub fn epoch(&self) -> u64 {
         self.buffer_epoch
     }
@@ -246,14 +448,39 @@ impl ChannelBuffer {
 
     pub(crate) fn disconnect(&mut self, cx: &mut ModelContext<Self>) {
         log::info!("channel buffer {} disconnected", self.channel_id);
-        if self.connected {
+        if self.connected || self.offline {
             self.connected = false;
+            self.offline = false;
             self.subscription.take();
             cx.emit(ChannelBufferEvent::Disconnected);
             cx.notify()
         }
     }
 
+    /// Keeps the buffer editable after the connection was lost for good, so that
+    /// its edits can be merged into the notes once the client reconnects.
+    pub(crate) fn go_offline(&mut self, cx: &mut ModelContext<Self>) {
+        if self.connected {
+            log::info!("channel buffer {} is offline", self.channel_id);
+            self.connected = false;
+            self.offline = true;
+            self.replace_collaborators(Vec::new(), cx);
+            cx.emit(ChannelBufferEvent::Offline);
+            cx.notify()
+        }
+    }
+
+    /// Called once the server has the same operations as this buffer after a reconnect.
+    pub(crate) fn rejoined(&mut self, cx: &mut ModelContext<Self>) {
+        self.synced_version = self.buffer.read(cx).version();
+        if self.offline {
+            self.connected = true;
+            self.offline = false;
+            cx.emit(ChannelBufferEvent::Reconnected);
+            cx.notify()
+        }
+    }
+
     pub(crate) fn channel_changed(&mut self, cx: &mut ModelContext<Self>) {
         cx.emit(ChannelBufferEvent::ChannelChanged);
         cx.notify()
@@ -263,7 +490,167 @@ impl ChannelBuffer {
         self.connected
     }
 
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
     pub fn replica_id(&self, cx: &AppContext) -> u16 {
         self.buffer.read(cx).replica_id()
     }
 }
+
+fn notes_key(client: &Client) -> Option<(String, u64)> {
+    Some((client.http_client().zed_url(""), client.user_id()?))
+}
+
+fn load_saved_notes(client: &Client, channel_id: ChannelId) -> Option<SavedChannelNotes> {
+    let (server_url, user_id) = notes_key(client)?;
+    CHANNEL_NOTES_DB
+        .load_channel_notes(&server_url, user_id, channel_id)
+        .log_err()
+        .flatten()
+}
+
+fn build_buffer(
+    channel_id: ChannelId,
+    state: &proto::JoinChannelBufferResponse,
+    channel_store: &Model<ChannelStore>,
+    cx: &mut AsyncAppContext,
+) -> Result<Model<language::Buffer>> {
+    let buffer_id = BufferId::new(state.buffer_id)?;
+    let operations = state
+        .operations
+        .iter()
+        .cloned()
+        .map(language::proto::deserialize_operation)
+        .collect::<Result<Vec<_>, _>>()?;
+    let buffer = cx.new_model(|cx| {
+        let capability = channel_store.read(cx).channel_capability(channel_id);
+        language::Buffer::remote(
+            buffer_id,
+            state.replica_id as u16,
+            capability,
+            state.base_text.clone(),
+        )
+    })?;
+    buffer.update(cx, |buffer, cx| buffer.apply_ops(operations, cx))??;
+    Ok(buffer)
+}
+
+/// Merges the edits that were made to a buffer while it was offline into the
+/// text that the server has now. The edits that the server already had are
+/// turned back into a single edit that's concurrent with the unsynced ones, so
+/// that the CRDT can merge the two.
+fn rebase_offline_edits(
+    buffer: &text::Buffer,
+    synced_version: &clock::Global,
+    server_text: &str,
+) -> Result<String> {
+    let server_replica_id = synced_version
+        .iter()
+        .chain(buffer.version().iter())
+        .map(|timestamp| timestamp.replica_id)
+        .chain([buffer.replica_id()])
+        .max()
+        .unwrap_or_default()
+        + 1;
+    let base_text = buffer.base_text().to_string();
+
+    let mut synced_buffer =
+        text::Buffer::new(server_replica_id, buffer.remote_id(), base_text.clone());
+    synced_buffer.apply_ops(
+        buffer
+            .operations()
+            .values()
+            .filter(|operation| synced_version.observed(operation.timestamp()))
+            .cloned(),
+    )?;
+    let server_edits = diff_edits(&synced_buffer.text(), server_text);
+
+    let mut merged_buffer = text::Buffer::new(server_replica_id + 1, buffer.remote_id(), base_text);
+    merged_buffer.apply_ops(buffer.operations().values().cloned())?;
+    if !server_edits.is_empty() {
+        merged_buffer.apply_ops([synced_buffer.edit(server_edits)])?;
+    }
+    if merged_buffer.deferred_ops_len() > 0 {
+        Err(anyhow!("missing operations for channel notes"))?;
+    }
+    Ok(merged_buffer.text())
+}
+
+/// Computes the character-level edits that turn `old_text` into `new_text`.
+fn diff_edits(old_text: &str, new_text: &str) -> Vec<(Range<usize>, String)> {
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut old_offset = 0;
+    for change in TextDiff::from_chars(old_text, new_text).iter_all_changes() {
+        let value = change.value();
+        match change.tag() {
+            ChangeTag::Equal => old_offset += value.len(),
+            ChangeTag::Delete => {
+                let end = old_offset + value.len();
+                match edits.last_mut() {
+                    Some((range, _)) if range.end == old_offset => range.end = end,
+                    _ => edits.push((old_offset..end, String::new())),
+                }
+                old_offset = end;
+            }
+            ChangeTag::Insert => match edits.last_mut() {
+                Some((range, text)) if range.end == old_offset => text.push_str(value),
+                _ => edits.push((old_offset..old_offset, value.to_string())),
+            },
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_offline_edits() {
+        let buffer_id = BufferId::new(1).unwrap();
+        let mut host = text::Buffer::new(0, buffer_id, "one\ntwo\nthree\n".into());
+        let mut guest = text::Buffer::new(1, buffer_id, "one\ntwo\nthree\n".into());
+
+        // Both replicas see the first edit before the guest goes offline.
+        let operation = host.edit([(0..3, "ONE")]);
+        guest.apply_ops([operation]).unwrap();
+        let synced_version = guest.version().clone();
+
+        // While the guest is offline, both replicas keep editing.
+        host.edit([(8..13, "THREE")]);
+        guest.edit([(4..7, "TWO")]);
+        guest.edit([(14..14, "four\n")]);
+
+        assert_eq!(
+            rebase_offline_edits(&guest, &synced_version, &host.text()).unwrap(),
+            "ONE\nTWO\nTHREE\nfour\n"
+        );
+
+        // Without offline edits, the server's text wins.
+        let mut reader = text::Buffer::new(2, buffer_id, "one\ntwo\nthree\n".into());
+        reader
+            .apply_ops(guest.operations().values().cloned())
+            .unwrap();
+        let synced_version = reader.version().clone();
+        assert_eq!(
+            rebase_offline_edits(&reader, &synced_version, "replaced").unwrap(),
+            "replaced"
+        );
+    }
+
+    #[test]
+    fn test_diff_edits() {
+        let old_text = "the quick brown fox";
+        let new_text = "the slow brown dog!";
+        let edits = diff_edits(old_text, new_text);
+        let mut text = old_text.to_string();
+        for (range, new) in edits.iter().rev() {
+            text.replace_range(range.clone(), new);
+        }
+        assert_eq!(text, new_text);
+        assert_eq!(diff_edits("same", "same"), []);
+        assert_eq!(diff_edits("", "añadir"), [(0..0, "añadir".to_string())]);
+    }
+}
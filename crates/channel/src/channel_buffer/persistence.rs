@@ -0,0 +1,164 @@
+use crate::ChannelId;
+use anyhow::Result;
+use db::{define_connection, query, sqlez_macros::sql};
+use prost::Message as _;
+use rpc::proto;
+
+define_connection!(
+    // Current schema shape using pseudo-rust syntax:
+    // channel_notes(
+    //   server_url: String,
+    //   user_id: u64,
+    //   channel_id: u64,
+    //   state: Vec<u8>, // A `proto::JoinChannelBufferResponse` describing the local replica
+    //   synced_version: Vec<u8>, // A `proto::ChannelBufferVersion` the server is known to have
+    // )
+    pub static ref CHANNEL_NOTES_DB: ChannelNotesDb<()> =
+        &[sql!(
+            CREATE TABLE channel_notes(
+                server_url TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                channel_id INTEGER NOT NULL,
+                state BLOB NOT NULL,
+                synced_version BLOB NOT NULL,
+                PRIMARY KEY(server_url, user_id, channel_id)
+            ) STRICT;
+        )];
+);
+
+/// A channel's notes as they were last seen locally, including any edits that
+/// haven't reached the server yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedChannelNotes {
+    pub channel_id: ChannelId,
+    /// The base text of the epoch, along with every operation applied on top of it.
+    /// The replica id is the one this client was assigned when it joined.
+    pub state: proto::JoinChannelBufferResponse,
+    /// The operations that the server has acknowledged.
+    pub synced_version: clock::Global,
+}
+
+impl ChannelNotesDb {
+    pub async fn save_channel_notes(
+        &self,
+        server_url: String,
+        user_id: u64,
+        notes: SavedChannelNotes,
+    ) -> Result<()> {
+        let synced_version = proto::ChannelBufferVersion {
+            channel_id: notes.channel_id,
+            epoch: notes.state.epoch,
+            version: language::proto::serialize_version(&notes.synced_version),
+        };
+        self.write(move |conn| {
+            conn.exec_bound(sql!(
+                INSERT INTO channel_notes(server_url, user_id, channel_id, state, synced_version)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT DO UPDATE SET
+                    state = ?4,
+                    synced_version = ?5
+            ))?((
+                server_url,
+                user_id,
+                notes.channel_id,
+                notes.state.encode_to_vec(),
+                synced_version.encode_to_vec(),
+            ))
+        })
+        .await
+    }
+
+    pub fn load_channel_notes(
+        &self,
+        server_url: &str,
+        user_id: u64,
+        channel_id: ChannelId,
+    ) -> Result<Option<SavedChannelNotes>> {
+        let Some((state, synced_version)) = self.channel_notes(server_url, user_id, channel_id)?
+        else {
+            return Ok(None);
+        };
+        let state = proto::JoinChannelBufferResponse::decode(state.as_slice())?;
+        let synced_version = proto::ChannelBufferVersion::decode(synced_version.as_slice())?;
+        Ok(Some(SavedChannelNotes {
+            channel_id,
+            state,
+            synced_version: language::proto::deserialize_version(&synced_version.version),
+        }))
+    }
+
+    query! {
+        fn channel_notes(server_url: &str, user_id: u64, channel_id: u64) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+            SELECT state, synced_version
+            FROM channel_notes
+            WHERE server_url = ?1 AND user_id = ?2 AND channel_id = ?3
+        }
+    }
+
+    query! {
+        pub async fn delete_channel_notes(server_url: String, user_id: u64, channel_id: u64) -> Result<()> {
+            DELETE FROM channel_notes
+            WHERE server_url = ?1 AND user_id = ?2 AND channel_id = ?3
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_saving_channel_notes() {
+        let db = ChannelNotesDb(db::open_test_db("test_saving_channel_notes").await);
+        let server_url = "https://zed.dev".to_string();
+
+        let mut buffer = text::Buffer::new(1, text::BufferId::new(7).unwrap(), "one".into());
+        let operation = buffer.edit([(3..3, " two")]);
+        let mut synced_version = clock::Global::new();
+        synced_version.observe(operation.timestamp());
+        let notes = SavedChannelNotes {
+            channel_id: 5,
+            state: proto::JoinChannelBufferResponse {
+                buffer_id: 7,
+                replica_id: 1,
+                base_text: "one".into(),
+                operations: vec![language::proto::serialize_operation(
+                    &language::Operation::Buffer(operation),
+                )],
+                collaborators: Vec::new(),
+                epoch: 2,
+            },
+            synced_version,
+        };
+
+        db.save_channel_notes(server_url.clone(), 1, notes.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.load_channel_notes(&server_url, 1, 5).unwrap(),
+            Some(notes.clone())
+        );
+        assert_eq!(db.load_channel_notes(&server_url, 2, 5).unwrap(), None);
+        assert_eq!(
+            db.load_channel_notes("http://localhost", 1, 5).unwrap(),
+            None
+        );
+
+        // Saving the notes again replaces them.
+        let mut updated_notes = notes;
+        updated_notes.state.epoch = 3;
+        updated_notes.state.operations.clear();
+        db.save_channel_notes(server_url.clone(), 1, updated_notes.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            db.load_channel_notes(&server_url, 1, 5).unwrap(),
+            Some(updated_notes)
+        );
+
+        db.delete_channel_notes(server_url.clone(), 1, 5)
+            .await
+            .unwrap();
+        assert_eq!(db.load_channel_notes(&server_url, 1, 5).unwrap(), None);
+    }
+}
@@ -13,6 +13,7 @@ use gpui::{
 use rand::prelude::*;
 use std::{
     ops::{ControlFlow, Range},
+    path::Path,
     sync::Arc,
 };
 use sum_tree::{Bias, SumTree};
@@ -40,6 +41,7 @@ pub struct MessageParams {
     pub text: String,
     pub mentions: Vec<(Range<usize>, UserId)>,
     pub reply_to_message_id: Option<u64>,
+    pub attachments: Vec<ChatAttachment>,
 }
 
 #[derive(Clone, Debug)]
@@ -51,6 +53,37 @@ pub struct ChannelMessage {
     pub nonce: u128,
     pub mentions: Vec<(Range<usize>, UserId)>,
     pub reply_to_message_id: Option<u64>,
+    pub edited_at: Option<OffsetDateTime>,
+    pub reactions: Vec<ChatReaction>,
+    pub attachments: Vec<ChatAttachment>,
+    /// The number of messages that reply to this one.
+    pub reply_count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChatReaction {
+    pub emoji: String,
+    pub user_ids: Vec<UserId>,
+}
+
+/// A snippet of code from a file in a shared project.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChatAttachment {
+    /// The project that the file belongs to, if it is still shared.
+    pub project_id: Option<u64>,
+    pub worktree_id: u64,
+    pub path: Arc<Path>,
+    /// The zero-based rows that the snippet spans, inclusive.
+    pub rows: Range<u32>,
+    pub language: Option<String>,
+    pub text: String,
+}
+
+/// A previous version of an edited message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelMessageEdit {
+    pub body: String,
+    pub timestamp: OffsetDateTime,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -87,12 +120,17 @@ pub enum ChannelChatEvent {
         channel_id: ChannelId,
         message_id: u64,
     },
+    UpdateMessage {
+        message_id: ChannelMessageId,
+        message_ix: usize,
+    },
 }
 
 impl EventEmitter<ChannelChatEvent> for ChannelChat {}
 pub fn init(client: &Arc<Client>) {
     client.add_model_message_handler(ChannelChat::handle_message_sent);
     client.add_model_message_handler(ChannelChat::handle_message_removed);
+    client.add_model_message_handler(ChannelChat::handle_message_updated);
 }
 
 impl ChannelChat {
@@ -187,6 +225,10 @@ impl ChannelChat {
                     mentions: message.mentions.clone(),
                     nonce,
                     reply_to_message_id: message.reply_to_message_id,
+                    edited_at: None,
+                    reactions: Vec::new(),
+                    attachments: message.attachments.clone(),
+                    reply_count: 0,
                 },
                 &(),
             ),
@@ -205,6 +247,11 @@ impl ChannelChat {
                 nonce: Some(nonce.into()),
                 mentions: mentions_to_proto(&message.mentions),
                 reply_to_message_id: message.reply_to_message_id,
+                attachments: message
+                    .attachments
+                    .iter()
+                    .map(ChatAttachment::to_proto)
+                    .collect(),
             });
             let response = request.await?;
             drop(outgoing_message_guard);
@@ -212,12 +259,107 @@ impl ChannelChat {
             let id = response.id;
             let message = ChannelMessage::from_proto(response, &user_store, &mut cx).await?;
             this.update(&mut cx, |this, cx| {
+                this.reply_added(message.reply_to_message_id, cx);
                 this.insert_messages(SumTree::from_item(message, &()), cx);
             })?;
             Ok(id)
         }))
     }
 
+    /// Replaces the body of one of the current user's messages.
+    pub fn update_message(
+        &mut self,
+        id: u64,
+        message: MessageParams,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<Task<Result<()>>> {
+        if message.text.trim().is_empty() {
+            Err(anyhow!("message body can't be empty"))?;
+        }
+
+        let request = self.rpc.request(proto::UpdateChannelMessage {
+            channel_id: self.channel_id,
+            message_id: id,
+            body: message.text,
+            mentions: mentions_to_proto(&message.mentions),
+        });
+        Ok(self.handle_updated_message(request, cx))
+    }
+
+    /// Adds the given reaction to a message, or removes it if the current user
+    /// has already reacted with it.
+    pub fn toggle_reaction(
+        &mut self,
+        id: u64,
+        emoji: impl Into<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let request = self.rpc.request(proto::ToggleChannelMessageReaction {
+            channel_id: self.channel_id,
+            message_id: id,
+            emoji: emoji.into(),
+        });
+        self.handle_updated_message(request, cx)
+    }
+
+    fn handle_updated_message(
+        &mut self,
+        request: impl 'static + std::future::Future<Output = Result<proto::SendChannelMessageResponse>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let user_store = self.user_store.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let message = request
+                .await?
+                .message
+                .ok_or_else(|| anyhow!("invalid message"))?;
+            let message = ChannelMessage::from_proto(message, &user_store, &mut cx).await?;
+            this.update(&mut cx, |this, cx| this.message_updated(message, cx))
+        })
+    }
+
+    /// Fetches the previous versions of an edited message, oldest first.
+    pub fn message_edits(
+        &self,
+        id: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<ChannelMessageEdit>>> {
+        let request = self.rpc.request(proto::GetChannelMessageEdits {
+            channel_id: self.channel_id,
+            message_id: id,
+        });
+        cx.spawn(move |_, _| async move {
+            request
+                .await?
+                .edits
+                .into_iter()
+                .map(|edit| {
+                    Ok(ChannelMessageEdit {
+                        body: edit.body,
+                        timestamp: OffsetDateTime::from_unix_timestamp(edit.timestamp as i64)?,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Fetches the given message along with all of the messages that reply to it.
+    pub fn load_thread(
+        &self,
+        id: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<ChannelMessage>>> {
+        let request = self.rpc.request(proto::GetChannelMessageThread {
+            channel_id: self.channel_id,
+            message_id: id,
+        });
+        let user_store = self.user_store.clone();
+        cx.spawn(move |_, mut cx| async move {
+            let response = request.await?;
+            ChannelMessage::from_proto_vec(response.messages, &user_store, &mut cx).await
+        })
+    }
+
     pub fn remove_message(&mut self, id: u64, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
         let response = self.rpc.request(proto::RemoveChannelMessage {
             channel_id: self.channel_id,
@@ -423,6 +565,11 @@ impl ChannelChat {
                         mentions: mentions_to_proto(&pending_message.mentions),
                         nonce: Some(pending_message.nonce.into()),
                         reply_to_message_id: pending_message.reply_to_message_id,
+                        attachments: pending_message
+                            .attachments
+                            .iter()
+                            .map(ChatAttachment::to_proto)
+                            .collect(),
                     });
                     let response = request.await?;
                     let message = ChannelMessage::from_proto(
@@ -432,6 +579,7 @@ impl ChannelChat {
                     )
                     .await?;
                     this.update(&mut cx, |this, cx| {
+                        this.reply_added(message.reply_to_message_id, cx);
                         this.insert_messages(SumTree::from_item(message, &()), cx);
                     })?;
                 }
@@ -495,6 +643,7 @@ impl ChannelChat {
 
         let message = ChannelMessage::from_proto(message, &user_store, &mut cx).await?;
         this.update(&mut cx, |this, cx| {
+            this.reply_added(message.reply_to_message_id, cx);
             this.insert_messages(SumTree::from_item(message, &()), cx);
             cx.emit(ChannelChatEvent::NewMessage {
                 channel_id: this.channel_id,
@@ -517,6 +666,21 @@ impl ChannelChat {
         Ok(())
     }
 
+    async fn handle_message_updated(
+        this: Model<Self>,
+        message: TypedEnvelope<proto::ChannelMessageUpdate>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        let user_store = this.update(&mut cx, |this, _| this.user_store.clone())?;
+        let message = message
+            .payload
+            .message
+            .ok_or_else(|| anyhow!("empty message"))?;
+        let message = ChannelMessage::from_proto(message, &user_store, &mut cx).await?;
+        this.update(&mut cx, |this, cx| this.message_updated(message, cx))
+    }
+
     fn insert_messages(&mut self, messages: SumTree<ChannelMessage>, cx: &mut ModelContext<Self>) {
         if let Some((first_message, last_message)) = messages.first().zip(messages.last()) {
             let nonces = messages
@@ -576,6 +740,45 @@ impl ChannelChat {
         }
     }
 
+    fn message_updated(&mut self, message: ChannelMessage, cx: &mut ModelContext<Self>) {
+        self.update_message_in_place(message.id, cx, |existing| *existing = message);
+    }
+
+    fn reply_added(&mut self, reply_to_message_id: Option<u64>, cx: &mut ModelContext<Self>) {
+        if let Some(reply_to_message_id) = reply_to_message_id {
+            self.update_message_in_place(
+                ChannelMessageId::Saved(reply_to_message_id),
+                cx,
+                |message| message.reply_count += 1,
+            );
+        }
+    }
+
+    fn update_message_in_place(
+        &mut self,
+        id: ChannelMessageId,
+        cx: &mut ModelContext<Self>,
+        update: impl FnOnce(&mut ChannelMessage),
+    ) {
+        let mut cursor = self.messages.cursor::<(ChannelMessageId, Count)>();
+        let mut messages = cursor.slice(&id, Bias::Left, &());
+        let message_ix = cursor.start().1 .0;
+        let Some(mut message) = cursor.item().filter(|item| item.id == id).cloned() else {
+            return;
+        };
+        update(&mut message);
+        cursor.next(&());
+        messages.push(message, &());
+        messages.append(cursor.suffix(&()), &());
+        drop(cursor);
+        self.messages = messages;
+        cx.emit(ChannelChatEvent::UpdateMessage {
+            message_id: id,
+            message_ix,
+        });
+        cx.notify();
+    }
+
     fn message_removed(&mut self, id: u64, cx: &mut ModelContext<Self>) {
         let mut cursor = self.messages.cursor::<ChannelMessageId>();
         let mut messages = cursor.slice(&ChannelMessageId::Saved(id), Bias::Left, &());
@@ -635,6 +838,24 @@ impl ChannelMessage {
                 .ok_or_else(|| anyhow!("nonce is required"))?
                 .into(),
             reply_to_message_id: message.reply_to_message_id,
+            edited_at: message
+                .edited_at
+                .map(|edited_at| OffsetDateTime::from_unix_timestamp(edited_at as i64))
+                .transpose()?,
+            reactions: message
+                .reactions
+                .into_iter()
+                .map(|reaction| ChatReaction {
+                    emoji: reaction.emoji,
+                    user_ids: reaction.user_ids,
+                })
+                .collect(),
+            attachments: message
+                .attachments
+                .into_iter()
+                .map(ChatAttachment::from_proto)
+                .collect(),
+            reply_count: message.reply_count as usize,
         })
     }
 
@@ -680,6 +901,31 @@ pub fn mentions_to_proto(mentions: &[(Range<usize>, UserId)]) -> Vec<proto::Chat
         .collect()
 }
 
+impl ChatAttachment {
+    pub fn to_proto(&self) -> proto::ChatAttachment {
+        proto::ChatAttachment {
+            project_id: self.project_id,
+            worktree_id: self.worktree_id,
+            path: self.path.to_string_lossy().to_string(),
+            start_row: self.rows.start,
+            end_row: self.rows.end,
+            language: self.language.clone(),
+            text: self.text.clone(),
+        }
+    }
+
+    pub fn from_proto(attachment: proto::ChatAttachment) -> Self {
+        Self {
+            project_id: attachment.project_id,
+            worktree_id: attachment.worktree_id,
+            path: Path::new(&attachment.path).into(),
+            rows: attachment.start_row..attachment.end_row,
+            language: attachment.language,
+            text: attachment.text,
+        }
+    }
+}
+
 impl sum_tree::Item for ChannelMessage {
     type Summary = ChannelMessageSummary;
 
@@ -725,6 +971,7 @@ impl<'a> From<&'a str> for MessageParams {
             text: value.into(),
             mentions: Vec::new(),
             reply_to_message_id: None,
+            attachments: Vec::new(),
         }
     }
 }
@@ -892,6 +892,7 @@ impl ChannelStore {
             let mut response = response.await?;
 
             this.update(&mut cx, |this, cx| {
+                let mut rejoins = Vec::new();
                 this.opened_buffers.retain(|_, buffer| match buffer {
                     OpenedModelHandle::Open(channel_buffer) => {
                         let Some(channel_buffer) = channel_buffer.upgrade() else {
@@ -930,6 +931,7 @@ impl ChannelStore {
                                     .log_err();
 
                                 if let Some(operations) = operations {
+                                    channel_buffer.rejoined(cx);
                                     let client = this.client.clone();
                                     cx.background_executor()
                                         .spawn(async move {
@@ -950,12 +952,33 @@ impl ChannelStore {
                                 }
                             }
 
-                            channel_buffer.disconnect(cx);
-                            false
+                            // The server no longer knows about this buffer, so join it
+                            // again from scratch, keeping any edits made in the meantime.
+                            rejoins.push((channel_id, channel_buffer.rejoin(cx)));
+                            true
                         })
                     }
                     OpenedModelHandle::Loading(_) => true,
                 });
+
+                for (channel_id, rejoin) in rejoins {
+                    cx.spawn(|this, mut cx| async move {
+                        if let Err(error) = rejoin.await {
+                            log::error!("failed to rejoin channel buffer {channel_id}: {error:?}");
+                            this.update(&mut cx, |this, cx| {
+                                if let Some(OpenedModelHandle::Open(buffer)) =
+                                    this.opened_buffers.remove(&channel_id)
+                                {
+                                    if let Some(buffer) = buffer.upgrade() {
+                                        buffer.update(cx, ChannelBuffer::disconnect);
+                                    }
+                                }
+                            })
+                            .ok();
+                        }
+                    })
+                    .detach();
+                }
             })
             .ok();
             anyhow::Ok(())
@@ -965,6 +988,9 @@ impl ChannelStore {
     fn handle_disconnect(&mut self, wait_for_reconnect: bool, cx: &mut ModelContext<Self>) {
         cx.notify();
 
+        if !wait_for_reconnect {
+            self.disconnect_channel_buffers_task.take();
+        }
         self.disconnect_channel_buffers_task.get_or_insert_with(|| {
             cx.spawn(move |this, mut cx| async move {
                 if wait_for_reconnect {
@@ -973,6 +999,19 @@ impl ChannelStore {
 
                 if let Some(this) = this.upgrade() {
                     this.update(&mut cx, |this, cx| {
+                        // Buffers stay editable while the client tries to reconnect, and
+                        // are merged back into the notes when it succeeds.
+                        if wait_for_reconnect {
+                            for buffer in this.opened_buffers.values() {
+                                if let OpenedModelHandle::Open(buffer) = buffer {
+                                    if let Some(buffer) = buffer.upgrade() {
+                                        buffer.update(cx, ChannelBuffer::go_offline);
+                                    }
+                                }
+                            }
+                            return;
+                        }
+
                         for (_, buffer) in this.opened_buffers.drain() {
                             if let OpenedModelHandle::Open(buffer) = buffer {
                                 if let Some(buffer) = buffer.upgrade() {
@@ -1040,7 +1079,10 @@ impl ChannelStore {
                         self.opened_buffers.remove(&channel_id)
                     {
                         if let Some(buffer) = buffer.upgrade() {
-                            buffer.update(cx, ChannelBuffer::disconnect);
+                            buffer.update(cx, |buffer, cx| {
+                                buffer.disconnect(cx);
+                                buffer.forget_saved_notes(cx);
+                            });
                         }
                     }
                 }
@@ -185,6 +185,10 @@ async fn test_channel_messages(cx: &mut TestAppContext) {
                     mentions: vec![],
                     nonce: Some(1.into()),
                     reply_to_message_id: None,
+                    edited_at: None,
+                    reactions: vec![],
+                    attachments: vec![],
+                    reply_count: 0,
                 },
                 proto::ChannelMessage {
                     id: 11,
@@ -194,6 +198,10 @@ async fn test_channel_messages(cx: &mut TestAppContext) {
                     mentions: vec![],
                     nonce: Some(2.into()),
                     reply_to_message_id: None,
+                    edited_at: None,
+                    reactions: vec![],
+                    attachments: vec![],
+                    reply_count: 0,
                 },
             ],
             done: false,
@@ -242,6 +250,10 @@ async fn test_channel_messages(cx: &mut TestAppContext) {
             mentions: vec![],
             nonce: Some(3.into()),
             reply_to_message_id: None,
+            edited_at: None,
+            reactions: vec![],
+            attachments: vec![],
+            reply_count: 0,
         }),
     });
 
@@ -296,6 +308,10 @@ async fn test_channel_messages(cx: &mut TestAppContext) {
                     nonce: Some(4.into()),
                     mentions: vec![],
                     reply_to_message_id: None,
+                    edited_at: None,
+                    reactions: vec![],
+                    attachments: vec![],
+                    reply_count: 0,
                 },
                 proto::ChannelMessage {
                     id: 9,
@@ -305,6 +321,10 @@ async fn test_channel_messages(cx: &mut TestAppContext) {
                     nonce: Some(5.into()),
                     mentions: vec![],
                     reply_to_message_id: None,
+                    edited_at: None,
+                    reactions: vec![],
+                    attachments: vec![],
+                    reply_count: 0,
                 },
             ],
         },
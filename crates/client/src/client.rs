@@ -249,6 +249,7 @@ struct ClientState {
     entity_id_extractors: HashMap<TypeId, fn(&dyn AnyTypedEnvelope) -> u64>,
     _reconnect_task: Option<Task<()>>,
     reconnect_interval: Duration,
+    direct_connection: bool,
     entities_by_type_and_remote_id: HashMap<(TypeId, u64), WeakSubscriber>,
     models_by_message_type: HashMap<TypeId, AnyWeakModel>,
     entity_types_by_message_type: HashMap<TypeId, TypeId>,
@@ -287,6 +288,7 @@ impl Default for ClientState {
             entity_id_extractors: Default::default(),
             _reconnect_task: None,
             reconnect_interval: Duration::from_secs(5),
+            direct_connection: false,
             models_by_message_type: Default::default(),
             entities_by_type_and_remote_id: Default::default(),
             entity_types_by_message_type: Default::default(),
@@ -519,7 +521,7 @@ impl Client {
             Status::Connected { .. } => {
                 state._reconnect_task = None;
             }
-            Status::ConnectionLost => {
+            Status::ConnectionLost if !state.direct_connection => {
                 let this = self.clone();
                 let reconnect_interval = state.reconnect_interval;
                 state._reconnect_task = Some(cx.spawn(move |cx| async move {
@@ -844,6 +846,33 @@ impl Client {
         }
     }
 
+    /// Connects over a transport that was established outside of the client,
+    /// such as the stdio of a remote server. The other end is expected to
+    /// greet us with a `Hello`, just like collab does. These connections skip
+    /// authentication and aren't re-established once lost.
+    pub async fn connect_with(
+        self: &Arc<Self>,
+        conn: Connection,
+        cx: &AsyncAppContext,
+    ) -> Result<()> {
+        self.state.write().direct_connection = true;
+        self.set_status(Status::Connecting, cx);
+
+        let timeout = cx.background_executor().timer(CONNECTION_TIMEOUT);
+        futures::select_biased! {
+            result = self.set_connection(conn, cx).fuse() => {
+                if result.is_err() {
+                    self.set_status(Status::ConnectionError, cx);
+                }
+                result
+            }
+            _ = timeout.fuse() => {
+                self.set_status(Status::ConnectionError, cx);
+                Err(anyhow!("timed out waiting on hello message from peer"))
+            }
+        }
+    }
+
     async fn set_connection(
         self: &Arc<Self>,
         conn: Connection,
@@ -1067,6 +1096,10 @@ impl Client {
         cx: &AsyncAppContext,
     ) -> Task<Result<Credentials>> {
         let http = self.http.clone();
+        let release_channel = cx
+            .update(|cx| ReleaseChannel::try_global(cx))
+            .ok()
+            .flatten();
         cx.spawn(|cx| async move {
             let background = cx.background_executor().clone();
 
@@ -1098,6 +1131,13 @@ impl Client {
                             .await;
                     }
 
+                    // Self-hosted collab servers can offer their own identity providers, in which
+                    // case we sign in with those rather than through zed.dev.
+                    let sign_in_urls = Self::get_collab_sign_in_urls(http.clone(), release_channel)
+                        .await
+                        .log_err()
+                        .flatten();
+
                     // Start an HTTP server to receive the redirect from Zed's sign-in page.
                     let server =
                         tiny_http::Server::http("127.0.0.1:0").expect("failed to find open port");
@@ -1105,10 +1145,19 @@ impl Client {
 
                     // Open the Zed sign-in page in the user's browser, with query parameters that indicate
                     // that the user is signing in from a Zed app running on the same device.
-                    let mut url = http.zed_url(&format!(
-                        "/native_app_signin?native_app_port={}&native_app_public_key={}",
+                    let query = format!(
+                        "native_app_port={}&native_app_public_key={}",
                         port, public_key_string
-                    ));
+                    );
+                    let (mut url, post_auth_url) = match sign_in_urls {
+                        Some((sign_in_url, succeeded_url)) => {
+                            (format!("{}?{}", sign_in_url, query), succeeded_url)
+                        }
+                        None => (
+                            http.zed_url(&format!("/native_app_signin?{}", query)),
+                            http.zed_url("/native_app_signin_succeeded"),
+                        ),
+                    };
 
                     if let Some(impersonate_login) = IMPERSONATE_LOGIN.as_ref() {
                         log::info!("impersonating user @{}", impersonate_login);
@@ -1139,8 +1188,6 @@ impl Client {
                                         }
                                     }
 
-                                    let post_auth_url =
-                                        http.zed_url("/native_app_signin_succeeded");
                                     req.respond(
                                         tiny_http::Response::empty(302).with_header(
                                             tiny_http::Header::from_bytes(
@@ -1181,6 +1228,40 @@ impl Client {
         })
     }
 
+    /// Asks the collab server how to sign in to it. Returns the URL of its sign-in page
+    /// and the page to show once signing in succeeds, or `None` if the server doesn't
+    /// have any identity providers of its own.
+    async fn get_collab_sign_in_urls(
+        http: Arc<ZedHttpClient>,
+        release_channel: Option<ReleaseChannel>,
+    ) -> Result<Option<(String, String)>> {
+        #[derive(Deserialize)]
+        struct AuthMetadata {
+            sign_in_path: String,
+            sign_in_succeeded_path: String,
+            providers: Vec<serde_json::Value>,
+        }
+
+        let mut url = Self::get_rpc_url(http.clone(), release_channel).await?;
+        url.set_path("/auth/metadata");
+        url.set_query(None);
+        let mut response = http.get(url.as_str(), Default::default(), false).await?;
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+        let metadata: AuthMetadata = serde_json::from_str(&body)?;
+        if metadata.providers.is_empty() {
+            return Ok(None);
+        }
+
+        url.set_path(&metadata.sign_in_path);
+        let sign_in_url = url.to_string();
+        url.set_path(&metadata.sign_in_succeeded_path);
+        Ok(Some((sign_in_url, url.to_string())))
+    }
+
     async fn authenticate_as_admin(
         http: Arc<ZedHttpClient>,
         login: String,
@@ -49,7 +49,10 @@ CREATE TABLE "projects" (
     "host_user_id" INTEGER REFERENCES users (id) NOT NULL,
     "host_connection_id" INTEGER,
     "host_connection_server_id" INTEGER REFERENCES servers (id) ON DELETE CASCADE,
-    "unregistered" BOOLEAN NOT NULL DEFAULT FALSE
+    "unregistered" BOOLEAN NOT NULL DEFAULT FALSE,
+    "read_only" BOOLEAN NOT NULL DEFAULT FALSE,
+    "invite_only" BOOLEAN NOT NULL DEFAULT FALSE,
+    "excluded_paths" VARCHAR
 );
 CREATE INDEX "index_projects_on_host_connection_server_id" ON "projects" ("host_connection_server_id");
 CREATE INDEX "index_projects_on_host_connection_id_and_host_connection_server_id" ON "projects" ("host_connection_id", "host_connection_server_id");
@@ -141,7 +144,8 @@ CREATE TABLE "project_collaborators" (
     "connection_server_id" INTEGER NOT NULL REFERENCES servers (id) ON DELETE CASCADE,
     "user_id" INTEGER NOT NULL,
     "replica_id" INTEGER NOT NULL,
-    "is_host" BOOLEAN NOT NULL
+    "is_host" BOOLEAN NOT NULL,
+    "read_only" BOOLEAN NOT NULL DEFAULT FALSE
 );
 CREATE INDEX "index_project_collaborators_on_project_id" ON "project_collaborators" ("project_id");
 CREATE UNIQUE INDEX "index_project_collaborators_on_project_id_and_replica_id" ON "project_collaborators" ("project_id", "replica_id");
@@ -158,6 +162,8 @@ CREATE TABLE "room_participants" (
     "answering_connection_lost" BOOLEAN NOT NULL,
     "location_kind" INTEGER,
     "location_project_id" INTEGER,
+    "location_worktree_id" INTEGER,
+    "location_path" VARCHAR,
     "initial_project_id" INTEGER,
     "calling_user_id" INTEGER NOT NULL REFERENCES users (id),
     "calling_connection_id" INTEGER NOT NULL,
@@ -219,7 +225,8 @@ CREATE TABLE IF NOT EXISTS "channel_messages" (
     "body" TEXT NOT NULL,
     "sent_at" TIMESTAMP,
     "nonce" BLOB NOT NULL,
-    "reply_to_message_id" INTEGER DEFAULT NULL
+    "reply_to_message_id" INTEGER DEFAULT NULL,
+    "edited_at" TIMESTAMP DEFAULT NULL
 );
 CREATE INDEX "index_channel_messages_on_channel_id" ON "channel_messages" ("channel_id");
 CREATE UNIQUE INDEX "index_channel_messages_on_sender_id_nonce" ON "channel_messages" ("sender_id", "nonce");
@@ -232,6 +239,34 @@ CREATE TABLE "channel_message_mentions" (
     PRIMARY KEY(message_id, start_offset)
 );
 
+CREATE TABLE "channel_message_edits" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "message_id" INTEGER NOT NULL REFERENCES channel_messages (id) ON DELETE CASCADE,
+    "body" TEXT NOT NULL,
+    "sent_at" TIMESTAMP NOT NULL
+);
+CREATE INDEX "index_channel_message_edits_on_message_id" ON "channel_message_edits" ("message_id");
+
+CREATE TABLE "channel_message_reactions" (
+    "message_id" INTEGER NOT NULL REFERENCES channel_messages (id) ON DELETE CASCADE,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "emoji" VARCHAR NOT NULL,
+    PRIMARY KEY(message_id, user_id, emoji)
+);
+
+CREATE TABLE "channel_message_attachments" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "message_id" INTEGER NOT NULL REFERENCES channel_messages (id) ON DELETE CASCADE,
+    "project_id" INTEGER REFERENCES projects (id) ON DELETE SET NULL,
+    "worktree_id" INTEGER NOT NULL,
+    "path" VARCHAR NOT NULL,
+    "start_row" INTEGER NOT NULL,
+    "end_row" INTEGER NOT NULL,
+    "language" VARCHAR,
+    "text" TEXT NOT NULL
+);
+CREATE INDEX "index_channel_message_attachments_on_message_id" ON "channel_message_attachments" ("message_id");
+
 CREATE TABLE "channel_members" (
     "id" INTEGER PRIMARY KEY AUTOINCREMENT,
     "channel_id" INTEGER NOT NULL REFERENCES channels (id) ON DELETE CASCADE,
@@ -266,6 +301,7 @@ CREATE TABLE "buffer_snapshots" (
     "epoch" INTEGER NOT NULL,
     "text" TEXT NOT NULL,
     "operation_serialization_version" INTEGER NOT NULL,
+    "created_at" TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
     PRIMARY KEY(buffer_id, epoch)
 );
 
@@ -353,3 +389,74 @@ CREATE TABLE contributors (
     signed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
     PRIMARY KEY (user_id)
 );
+
+CREATE TABLE "comment_threads" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "channel_id" INTEGER REFERENCES channels (id) ON DELETE CASCADE,
+    "host_user_id" INTEGER REFERENCES users (id) ON DELETE CASCADE,
+    "worktree_abs_path" VARCHAR,
+    "path" VARCHAR,
+    "epoch" INTEGER NOT NULL DEFAULT 0,
+    "start_anchor" BLOB NOT NULL,
+    "end_anchor" BLOB NOT NULL,
+    "resolved" BOOLEAN NOT NULL DEFAULT FALSE,
+    "author_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX "index_comment_threads_on_channel_id" ON "comment_threads" ("channel_id");
+CREATE INDEX "index_comment_threads_on_host_user_id_and_worktree_abs_path_and_path" ON "comment_threads" ("host_user_id", "worktree_abs_path", "path");
+
+CREATE TABLE "comments" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "thread_id" INTEGER NOT NULL REFERENCES comment_threads (id) ON DELETE CASCADE,
+    "author_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "body" TEXT NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX "index_comments_on_thread_id" ON "comments" ("thread_id");
+
+CREATE TABLE "comment_mentions" (
+    "comment_id" INTEGER NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+    "start_offset" INTEGER NOT NULL,
+    "end_offset" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY(comment_id, start_offset)
+);
+
+CREATE TABLE "user_identities" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "provider" VARCHAR NOT NULL,
+    "subject" VARCHAR NOT NULL,
+    "password_hash" VARCHAR,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX "index_user_identities_on_provider_and_subject" ON "user_identities" ("provider", "subject");
+CREATE INDEX "index_user_identities_on_user_id" ON "user_identities" ("user_id");
+
+CREATE TABLE "local_account_invites" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "code" VARCHAR NOT NULL,
+    "inviter_id" INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    "email_address" VARCHAR,
+    "admin" BOOLEAN NOT NULL DEFAULT FALSE,
+    "accepted_user_id" INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX "index_local_account_invites_on_code" ON "local_account_invites" ("code");
+
+CREATE TABLE "project_invites" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "project_id" INTEGER NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    "token" VARCHAR NOT NULL,
+    "read_only" BOOLEAN NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX "index_project_invites_on_token" ON "project_invites" ("token");
+CREATE INDEX "index_project_invites_on_project_id" ON "project_invites" ("project_id");
@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS "comment_threads" (
+    "id" SERIAL PRIMARY KEY,
+    "channel_id" INTEGER REFERENCES channels (id) ON DELETE CASCADE,
+    "host_user_id" INTEGER REFERENCES users (id) ON DELETE CASCADE,
+    "worktree_abs_path" VARCHAR,
+    "path" VARCHAR,
+    "epoch" INTEGER NOT NULL DEFAULT 0,
+    "start_anchor" BYTEA NOT NULL,
+    "end_anchor" BYTEA NOT NULL,
+    "resolved" BOOLEAN NOT NULL DEFAULT FALSE,
+    "author_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "created_at" TIMESTAMP NOT NULL DEFAULT now()
+);
+CREATE INDEX "index_comment_threads_on_channel_id" ON "comment_threads" ("channel_id");
+CREATE INDEX "index_comment_threads_on_host_user_id_and_worktree_abs_path_and_path" ON "comment_threads" ("host_user_id", "worktree_abs_path", "path");
+
+CREATE TABLE IF NOT EXISTS "comments" (
+    "id" SERIAL PRIMARY KEY,
+    "thread_id" INTEGER NOT NULL REFERENCES comment_threads (id) ON DELETE CASCADE,
+    "author_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "body" TEXT NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT now()
+);
+CREATE INDEX "index_comments_on_thread_id" ON "comments" ("thread_id");
+
+CREATE TABLE IF NOT EXISTS "comment_mentions" (
+    "comment_id" INTEGER NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+    "start_offset" INTEGER NOT NULL,
+    "end_offset" INTEGER NOT NULL,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY(comment_id, start_offset)
+);
@@ -0,0 +1,31 @@
+ALTER TABLE channel_messages ADD edited_at TIMESTAMP WITHOUT TIME ZONE DEFAULT NULL;
+
+CREATE TABLE IF NOT EXISTS "channel_message_edits" (
+    "id" SERIAL PRIMARY KEY,
+    "message_id" INTEGER NOT NULL REFERENCES channel_messages (id) ON DELETE CASCADE,
+    "body" TEXT NOT NULL,
+    "sent_at" TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+
+CREATE INDEX "index_channel_message_edits_on_message_id" ON "channel_message_edits" ("message_id");
+
+CREATE TABLE IF NOT EXISTS "channel_message_reactions" (
+    "message_id" INTEGER NOT NULL REFERENCES channel_messages (id) ON DELETE CASCADE,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "emoji" VARCHAR NOT NULL,
+    PRIMARY KEY (message_id, user_id, emoji)
+);
+
+CREATE TABLE IF NOT EXISTS "channel_message_attachments" (
+    "id" SERIAL PRIMARY KEY,
+    "message_id" INTEGER NOT NULL REFERENCES channel_messages (id) ON DELETE CASCADE,
+    "project_id" INTEGER REFERENCES projects (id) ON DELETE SET NULL,
+    "worktree_id" BIGINT NOT NULL,
+    "path" VARCHAR NOT NULL,
+    "start_row" INTEGER NOT NULL,
+    "end_row" INTEGER NOT NULL,
+    "language" VARCHAR,
+    "text" TEXT NOT NULL
+);
+
+CREATE INDEX "index_channel_message_attachments_on_message_id" ON "channel_message_attachments" ("message_id");
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS "user_identities" (
+    "id" SERIAL PRIMARY KEY,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "provider" VARCHAR NOT NULL,
+    "subject" VARCHAR NOT NULL,
+    "password_hash" VARCHAR,
+    "created_at" TIMESTAMP NOT NULL DEFAULT now()
+);
+CREATE UNIQUE INDEX "index_user_identities_on_provider_and_subject" ON "user_identities" ("provider", "subject");
+CREATE INDEX "index_user_identities_on_user_id" ON "user_identities" ("user_id");
+
+CREATE TABLE IF NOT EXISTS "local_account_invites" (
+    "id" SERIAL PRIMARY KEY,
+    "code" VARCHAR NOT NULL,
+    "inviter_id" INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    "email_address" VARCHAR,
+    "admin" BOOLEAN NOT NULL DEFAULT FALSE,
+    "accepted_user_id" INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT now()
+);
+CREATE UNIQUE INDEX "index_local_account_invites_on_code" ON "local_account_invites" ("code");
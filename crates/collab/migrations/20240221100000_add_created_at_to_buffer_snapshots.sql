@@ -0,0 +1,2 @@
+ALTER TABLE "buffer_snapshots" ADD COLUMN "created_at" TIMESTAMP;
+ALTER TABLE "buffer_snapshots" ALTER COLUMN "created_at" SET DEFAULT now();
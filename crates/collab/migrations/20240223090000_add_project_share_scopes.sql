@@ -0,0 +1,16 @@
+ALTER TABLE "projects" ADD COLUMN "read_only" BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE "projects" ADD COLUMN "invite_only" BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE "projects" ADD COLUMN "excluded_paths" VARCHAR;
+
+ALTER TABLE "project_collaborators" ADD COLUMN "read_only" BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS "project_invites" (
+    "id" SERIAL PRIMARY KEY,
+    "project_id" INTEGER NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    "token" VARCHAR NOT NULL,
+    "read_only" BOOLEAN NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT now()
+);
+CREATE UNIQUE INDEX "index_project_invites_on_token" ON "project_invites" ("token");
+CREATE INDEX "index_project_invites_on_project_id" ON "project_invites" ("project_id");
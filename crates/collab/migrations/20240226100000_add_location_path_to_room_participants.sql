@@ -0,0 +1,2 @@
+ALTER TABLE "room_participants" ADD COLUMN "location_worktree_id" INT8;
+ALTER TABLE "room_participants" ADD COLUMN "location_path" VARCHAR;
@@ -1,6 +1,6 @@
 use crate::{
     auth,
-    db::{ContributorSelector, User, UserId},
+    db::{ContributorSelector, FeatureFlag, FlagId, ProjectSummary, RoomSummary, User, UserId},
     rpc, AppState, Error, Result,
 };
 use anyhow::anyhow;
@@ -10,7 +10,7 @@ use axum::{
     http::{self, Request, StatusCode},
     middleware::{self, Next},
     response::IntoResponse,
-    routing::{get, post},
+    routing::{get, post, put},
     Extension, Json, Router,
 };
 use axum_extra::response::ErasedJson;
@@ -23,11 +23,30 @@ use tracing::instrument;
 pub fn routes(rpc_server: Arc<rpc::Server>, state: Arc<AppState>) -> Router<Body> {
     Router::new()
         .route("/user", get(get_authenticated_user))
-        .route("/users/:id/access_tokens", post(create_access_token))
+        .route(
+            "/users/:id/access_tokens",
+            post(create_access_token).delete(revoke_access_tokens),
+        )
+        .route(
+            "/users/:id/access_tokens/rotate",
+            post(rotate_access_tokens),
+        )
+        .route("/users/:id/disconnect", post(disconnect_user))
+        .route(
+            "/users/:id/feature_flags/:flag",
+            put(add_user_feature_flag).delete(remove_user_feature_flag),
+        )
+        .route(
+            "/feature_flags",
+            get(get_feature_flags).post(create_feature_flag),
+        )
+        .route("/rooms", get(get_rooms))
+        .route("/projects", get(get_projects))
         .route("/panic", post(trace_panic))
         .route("/rpc_server_snapshot", get(get_rpc_server_snapshot))
         .route("/contributors", get(get_contributors).post(add_contributor))
         .route("/contributor", get(check_is_contributor))
+        .route("/local_account_invites", post(create_local_account_invite))
         .layer(
             ServiceBuilder::new()
                 .layer(Extension(state))
@@ -246,3 +265,204 @@ async fn create_access_token(
         encrypted_access_token,
     }))
 }
+
+#[derive(Debug, Deserialize)]
+struct CreateLocalAccountInviteParams {
+    inviter_github_login: Option<String>,
+    email_address: Option<String>,
+    #[serde(default)]
+    admin: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateLocalAccountInviteResponse {
+    code: String,
+    url: String,
+}
+
+async fn create_local_account_invite(
+    Extension(app): Extension<Arc<AppState>>,
+    Json(params): Json<CreateLocalAccountInviteParams>,
+) -> Result<Json<CreateLocalAccountInviteResponse>> {
+    if !app.config.local_accounts_enabled() {
+        return Err(Error::Http(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "local accounts are not enabled".to_string(),
+        ));
+    }
+
+    let inviter_id = if let Some(login) = params.inviter_github_login {
+        let inviter = app
+            .db
+            .get_user_by_github_login(&login)
+            .await?
+            .ok_or_else(|| anyhow!("user {login} does not exist"))?;
+        Some(inviter.id)
+    } else {
+        None
+    };
+
+    let code = app
+        .db
+        .create_local_account_invite(inviter_id, params.email_address.as_deref(), params.admin)
+        .await?;
+    let url = format!(
+        "{}/auth/local/invites/{}",
+        app.config
+            .public_url
+            .as_deref()
+            .unwrap_or("")
+            .trim_end_matches('/'),
+        code
+    );
+    Ok(Json(CreateLocalAccountInviteResponse { code, url }))
+}
+
+#[derive(Debug, Serialize)]
+struct RevokeAccessTokensResponse {
+    revoked_access_token_count: u64,
+    disconnected_connection_count: usize,
+}
+
+/// Revokes all of the user's access tokens and disconnects them, so that they
+/// need to sign in again to get a new token.
+async fn revoke_access_tokens(
+    Path(user_id): Path<UserId>,
+    Extension(app): Extension<Arc<AppState>>,
+    Extension(rpc_server): Extension<Arc<rpc::Server>>,
+) -> Result<Json<RevokeAccessTokensResponse>> {
+    let revoked_access_token_count = app.db.delete_access_tokens_for_user(user_id).await?;
+    let disconnected_connection_count = rpc_server.disconnect_user(user_id);
+    Ok(Json(RevokeAccessTokensResponse {
+        revoked_access_token_count,
+        disconnected_connection_count,
+    }))
+}
+
+#[derive(Deserialize)]
+struct RotateAccessTokensQueryParams {
+    public_key: String,
+}
+
+#[derive(Serialize)]
+struct RotateAccessTokensResponse {
+    user_id: UserId,
+    encrypted_access_token: String,
+    revoked_access_token_count: u64,
+    disconnected_connection_count: usize,
+}
+
+/// Replaces all of the user's access tokens with a single new one, and
+/// disconnects the clients that were using the old tokens.
+async fn rotate_access_tokens(
+    Path(user_id): Path<UserId>,
+    Query(params): Query<RotateAccessTokensQueryParams>,
+    Extension(app): Extension<Arc<AppState>>,
+    Extension(rpc_server): Extension<Arc<rpc::Server>>,
+) -> Result<Json<RotateAccessTokensResponse>> {
+    app.db
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| anyhow!("user not found"))?;
+    // Check the key before revoking anything, so that a bad request can't
+    // leave the user without a token.
+    ::rpc::auth::PublicKey::try_from(params.public_key.clone())?;
+
+    let revoked_access_token_count = app.db.delete_access_tokens_for_user(user_id).await?;
+    let disconnected_connection_count = rpc_server.disconnect_user(user_id);
+    let access_token = auth::create_access_token(app.db.as_ref(), user_id, None).await?;
+    let encrypted_access_token = auth::encrypt_access_token(&access_token, params.public_key)?;
+
+    Ok(Json(RotateAccessTokensResponse {
+        user_id,
+        encrypted_access_token,
+        revoked_access_token_count,
+        disconnected_connection_count,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct DisconnectUserResponse {
+    disconnected_connection_count: usize,
+}
+
+async fn disconnect_user(
+    Path(user_id): Path<UserId>,
+    Extension(rpc_server): Extension<Arc<rpc::Server>>,
+) -> Result<Json<DisconnectUserResponse>> {
+    Ok(Json(DisconnectUserResponse {
+        disconnected_connection_count: rpc_server.disconnect_user(user_id),
+    }))
+}
+
+async fn get_rooms(Extension(app): Extension<Arc<AppState>>) -> Result<Json<Vec<RoomSummary>>> {
+    Ok(Json(app.db.get_room_summaries().await?))
+}
+
+async fn get_projects(
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Json<Vec<ProjectSummary>>> {
+    Ok(Json(app.db.get_project_summaries().await?))
+}
+
+async fn get_feature_flags(
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Json<Vec<FeatureFlag>>> {
+    Ok(Json(app.db.get_feature_flags().await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateFeatureFlagParams {
+    flag: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateFeatureFlagResponse {
+    id: FlagId,
+}
+
+async fn create_feature_flag(
+    Extension(app): Extension<Arc<AppState>>,
+    Json(params): Json<CreateFeatureFlagParams>,
+) -> Result<Json<CreateFeatureFlagResponse>> {
+    if app.db.get_flag_by_name(&params.flag).await?.is_some() {
+        return Err(Error::Http(
+            StatusCode::CONFLICT,
+            format!("feature flag {} already exists", params.flag),
+        ));
+    }
+
+    let id = app.db.create_user_flag(&params.flag).await?;
+    Ok(Json(CreateFeatureFlagResponse { id }))
+}
+
+async fn find_feature_flag(app: &AppState, flag: &str) -> Result<FlagId> {
+    app.db.get_flag_by_name(flag).await?.ok_or_else(|| {
+        Error::Http(
+            StatusCode::NOT_FOUND,
+            format!("feature flag {flag} does not exist"),
+        )
+    })
+}
+
+/// Enables a feature flag for the user. The user's clients pick up the change
+/// the next time they connect.
+async fn add_user_feature_flag(
+    Path((user_id, flag)): Path<(UserId, String)>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<()> {
+    let flag_id = find_feature_flag(&app, &flag).await?;
+    if !app.db.get_user_flags(user_id).await?.contains(&flag) {
+        app.db.add_user_flag(user_id, flag_id).await?;
+    }
+    Ok(())
+}
+
+async fn remove_user_feature_flag(
+    Path((user_id, flag)): Path<(UserId, String)>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<()> {
+    let flag_id = find_feature_flag(&app, &flag).await?;
+    app.db.remove_user_flag(user_id, flag_id).await?;
+    Ok(())
+}
@@ -1,12 +1,19 @@
+mod local;
+mod oidc;
+
 use crate::{
     db::{self, AccessTokenId, Database, UserId},
     AppState, Error, Result,
 };
 use anyhow::{anyhow, Context};
 use axum::{
+    body::Body,
+    extract::Query,
     http::{self, Request, StatusCode},
     middleware::Next,
-    response::IntoResponse,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::get,
+    Extension, Json, Router,
 };
 use lazy_static::lazy_static;
 use prometheus::{exponential_buckets, register_histogram, Histogram};
@@ -18,6 +25,9 @@ use scrypt::{
 use serde::{Deserialize, Serialize};
 use std::{sync::Arc, time::Instant};
 
+pub use local::{hash_password, verify_password, SignInThrottle};
+pub use oidc::{code_challenge, HttpOidcClient, OidcClient, OidcIdentity, OidcProvider};
+
 lazy_static! {
     static ref METRIC_ACCESS_TOKEN_HASHING_TIME: Histogram = register_histogram!(
         "access_token_hashing_time",
@@ -30,6 +40,155 @@ lazy_static! {
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Impersonator(pub Option<db::User>);
 
+/// Routes that let users of the native app sign in with the identity providers
+/// configured on this server, rather than through zed.dev.
+pub fn routes(state: Arc<AppState>) -> Router<Body> {
+    Router::new()
+        .route("/auth/metadata", get(get_auth_metadata))
+        .route("/auth/signin", get(sign_in))
+        .route("/auth/signin_succeeded", get(sign_in_succeeded))
+        .merge(oidc::routes())
+        .merge(local::routes())
+        .layer(Extension(state))
+}
+
+/// Describes how the native app can sign in to this server. When no providers
+/// are configured, the app falls back to signing in through zed.dev.
+#[derive(Debug, Serialize)]
+struct AuthMetadata {
+    sign_in_path: &'static str,
+    sign_in_succeeded_path: &'static str,
+    providers: Vec<AuthProvider>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuthProvider {
+    kind: &'static str,
+    name: String,
+    sign_in_path: &'static str,
+}
+
+fn auth_providers(app: &AppState) -> Vec<AuthProvider> {
+    let mut providers = Vec::new();
+    if let Some(provider) = app.oidc.as_ref() {
+        providers.push(AuthProvider {
+            kind: "oidc",
+            name: provider.display_name().to_string(),
+            sign_in_path: oidc::SIGN_IN_PATH,
+        });
+    }
+    if app.config.local_accounts_enabled() {
+        providers.push(AuthProvider {
+            kind: "local",
+            name: "Username and password".to_string(),
+            sign_in_path: local::SIGN_IN_PATH,
+        });
+    }
+    providers
+}
+
+async fn get_auth_metadata(Extension(app): Extension<Arc<AppState>>) -> Json<AuthMetadata> {
+    Json(AuthMetadata {
+        sign_in_path: "/auth/signin",
+        sign_in_succeeded_path: "/auth/signin_succeeded",
+        providers: auth_providers(&app),
+    })
+}
+
+/// The parameters with which the native app starts signing in. Once the user has
+/// been authenticated, their access token is encrypted with the app's public key
+/// and sent to a server that the app is running on the given local port.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NativeAppSignInParams {
+    pub native_app_port: u16,
+    pub native_app_public_key: String,
+}
+
+impl NativeAppSignInParams {
+    fn query(&self) -> String {
+        url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("native_app_port", &self.native_app_port.to_string())
+            .append_pair("native_app_public_key", &self.native_app_public_key)
+            .finish()
+    }
+}
+
+/// Sends the user to the only configured provider, or lets them pick one.
+async fn sign_in(
+    Query(params): Query<NativeAppSignInParams>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Response> {
+    let providers = auth_providers(&app);
+    match providers.as_slice() {
+        [] => Err(Error::Http(
+            StatusCode::NOT_FOUND,
+            "no identity providers are configured".to_string(),
+        )),
+        [provider] => Ok(
+            Redirect::to(&format!("{}?{}", provider.sign_in_path, params.query())).into_response(),
+        ),
+        providers => {
+            let mut body = String::from("<h1>Sign in</h1><ul>");
+            for provider in providers {
+                body.push_str(&format!(
+                    "<li><a href=\"{}?{}\">{}</a></li>",
+                    provider.sign_in_path,
+                    html_escape(&params.query()),
+                    html_escape(&provider.name)
+                ));
+            }
+            body.push_str("</ul>");
+            Ok(render_page("Sign in", &body).into_response())
+        }
+    }
+}
+
+async fn sign_in_succeeded() -> Html<String> {
+    render_page(
+        "Signed in",
+        "<h1>You're signed in</h1><p>You can close this window and return to Zed.</p>",
+    )
+}
+
+/// Issues an access token for the given user and hands it to the native app that
+/// started signing in.
+async fn redirect_to_native_app(
+    app: &AppState,
+    user_id: UserId,
+    params: &NativeAppSignInParams,
+) -> Result<Redirect> {
+    let access_token = create_access_token(app.db.as_ref(), user_id, None).await?;
+    let encrypted_access_token =
+        encrypt_access_token(&access_token, params.native_app_public_key.clone())?;
+    Ok(Redirect::to(&format!(
+        "http://127.0.0.1:{}?user_id={}&access_token={}",
+        params.native_app_port, user_id, encrypted_access_token
+    )))
+}
+
+fn render_page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title></head><body>{}</body></html>",
+        html_escape(title),
+        body
+    ))
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
 /// Validates the authorization header. This has two mechanisms, one for the ADMIN_TOKEN
 /// and one for the access tokens that we issue.
 pub async fn validate_header<B>(mut req: Request<B>, next: Next<B>) -> impl IntoResponse {
@@ -124,8 +283,7 @@ pub async fn create_access_token(
 ) -> Result<String> {
     const VERSION: usize = 1;
     let access_token = rpc::auth::random_token();
-    let access_token_hash =
-        hash_access_token(&access_token).context("failed to hash access token")?;
+    let access_token_hash = hash_secret(&access_token).context("failed to hash access token")?;
     let id = db
         .create_access_token(
             user_id,
@@ -141,9 +299,10 @@ pub async fn create_access_token(
     })?)
 }
 
-fn hash_access_token(token: &str) -> Result<String> {
-    // Avoid slow hashing in debug mode.
-    let params = if cfg!(debug_assertions) {
+/// Hashes an access token or password so that it can be stored.
+fn hash_secret(secret: &str) -> Result<String> {
+    // Avoid slow hashing in tests. Hashes made anywhere else may be stored.
+    let params = if cfg!(test) {
         scrypt::Params::new(1, 1, 1).unwrap()
     } else {
         scrypt::Params::new(14, 8, 1).unwrap()
@@ -151,7 +310,7 @@ fn hash_access_token(token: &str) -> Result<String> {
 
     Ok(Scrypt
         .hash_password(
-            token.as_bytes(),
+            secret.as_bytes(),
             None,
             params,
             &SaltString::generate(thread_rng()),
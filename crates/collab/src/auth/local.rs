@@ -0,0 +1,347 @@
+use super::{hash_secret, html_escape, redirect_to_native_app, render_page, NativeAppSignInParams};
+use crate::{AppState, Error, Result};
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Form, Path, Query},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Extension, Router,
+};
+use collections::HashMap;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use scrypt::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Scrypt,
+};
+use serde::Deserialize;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub(super) const SIGN_IN_PATH: &str = "/auth/local/signin";
+const MIN_PASSWORD_LEN: usize = 8;
+const MAX_USERNAME_LEN: usize = 39;
+const MAX_FAILED_SIGN_INS_PER_USERNAME: usize = 5;
+const MAX_FAILED_SIGN_INS_PER_ADDRESS: usize = 20;
+const FAILED_SIGN_IN_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+lazy_static! {
+    /// Checked in place of a stored hash when nobody has the given username, so
+    /// that response times don't reveal which usernames have accounts.
+    static ref DUMMY_PASSWORD_HASH: String = hash_password("not a real password").unwrap();
+}
+
+pub(super) fn routes() -> Router<Body> {
+    Router::new()
+        .route(SIGN_IN_PATH, get(render_sign_in).post(sign_in))
+        .route(
+            "/auth/local/invites/:code",
+            get(render_accept_invite).post(accept_invite),
+        )
+}
+
+/// Hashes a local account's password so that it can be stored.
+pub fn hash_password(password: &str) -> Result<String> {
+    hash_secret(password)
+}
+
+/// Checks a password against the hash that was stored for a local account.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash).map_or(false, |hash| {
+        Scrypt.verify_password(password.as_bytes(), &hash).is_ok()
+    })
+}
+
+/// Runs scrypt on Tokio's blocking thread pool, since hashing a password takes
+/// long enough to stall the other requests served by an async worker thread.
+async fn run_scrypt<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T> {
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => Ok(runtime
+            .spawn_blocking(f)
+            .await
+            .map_err(anyhow::Error::new)?),
+        // Tests serve requests outside of a Tokio runtime.
+        Err(_) => Ok(f()),
+    }
+}
+
+/// Limits the failed sign-in attempts from each client address, so that passwords
+/// can't be guessed by brute force. Attempts are never limited by username alone,
+/// since that would let anyone lock a user out of their account.
+#[derive(Default)]
+pub struct SignInThrottle {
+    failures: Mutex<HashMap<ThrottleKey, Vec<Instant>>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ThrottleKey {
+    Address(IpAddr),
+    AddressAndUsername(IpAddr, String),
+}
+
+impl ThrottleKey {
+    fn max_failures(&self) -> usize {
+        match self {
+            ThrottleKey::Address(_) => MAX_FAILED_SIGN_INS_PER_ADDRESS,
+            ThrottleKey::AddressAndUsername(..) => MAX_FAILED_SIGN_INS_PER_USERNAME,
+        }
+    }
+}
+
+impl SignInThrottle {
+    fn is_throttled(&self, keys: &[ThrottleKey]) -> bool {
+        let now = Instant::now();
+        let failures = self.failures.lock();
+        keys.iter().any(|key| {
+            failures.get(key).map_or(false, |attempts| {
+                attempts
+                    .iter()
+                    .filter(|attempt| now.duration_since(**attempt) < FAILED_SIGN_IN_WINDOW)
+                    .count()
+                    >= key.max_failures()
+            })
+        })
+    }
+
+    fn record_failure(&self, keys: &[ThrottleKey]) {
+        let now = Instant::now();
+        let mut failures = self.failures.lock();
+        failures.retain(|_, attempts| {
+            attempts.retain(|attempt| now.duration_since(*attempt) < FAILED_SIGN_IN_WINDOW);
+            !attempts.is_empty()
+        });
+        for key in keys {
+            failures.entry(key.clone()).or_default().push(now);
+        }
+    }
+
+    fn clear(&self, key: &ThrottleKey) {
+        self.failures.lock().remove(key);
+    }
+}
+
+fn is_valid_username(username: &str) -> bool {
+    !username.is_empty()
+        && username.len() <= MAX_USERNAME_LEN
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn ensure_enabled(app: &AppState) -> Result<()> {
+    if app.config.local_accounts_enabled() {
+        Ok(())
+    } else {
+        Err(Error::Http(
+            StatusCode::NOT_FOUND,
+            "local accounts are not enabled".to_string(),
+        ))
+    }
+}
+
+fn sign_in_form(params: &NativeAppSignInParams, error: Option<&str>) -> Html<String> {
+    let mut body = String::from("<h1>Sign in</h1>");
+    if let Some(error) = error {
+        body.push_str(&format!("<p><strong>{}</strong></p>", html_escape(error)));
+    }
+    body.push_str(&format!(
+        "<form method=\"post\" action=\"{SIGN_IN_PATH}\">\
+            <input type=\"hidden\" name=\"native_app_port\" value=\"{}\">\
+            <input type=\"hidden\" name=\"native_app_public_key\" value=\"{}\">\
+            <p><label>Username <input name=\"username\" autocomplete=\"username\" required></label></p>\
+            <p><label>Password <input name=\"password\" type=\"password\" autocomplete=\"current-password\" required></label></p>\
+            <p><button type=\"submit\">Sign in</button></p>\
+        </form>",
+        params.native_app_port,
+        html_escape(&params.native_app_public_key),
+    ));
+    render_page("Sign in", &body)
+}
+
+async fn render_sign_in(
+    Query(params): Query<NativeAppSignInParams>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Html<String>> {
+    ensure_enabled(&app)?;
+    Ok(sign_in_form(&params, None))
+}
+
+#[derive(Debug, Deserialize)]
+struct SignInForm {
+    username: String,
+    password: String,
+    native_app_port: u16,
+    native_app_public_key: String,
+}
+
+async fn sign_in(
+    Extension(app): Extension<Arc<AppState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Form(form): Form<SignInForm>,
+) -> Result<Response> {
+    ensure_enabled(&app)?;
+    let native_app = NativeAppSignInParams {
+        native_app_port: form.native_app_port,
+        native_app_public_key: form.native_app_public_key,
+    };
+
+    let is_valid_username = is_valid_username(&form.username);
+    let mut throttle_keys = Vec::new();
+    let mut username_key = None;
+    if let Some(ConnectInfo(address)) = connect_info {
+        throttle_keys.push(ThrottleKey::Address(address.ip()));
+        if is_valid_username {
+            username_key = Some(ThrottleKey::AddressAndUsername(
+                address.ip(),
+                form.username.clone(),
+            ));
+            throttle_keys.extend(username_key.clone());
+        }
+    }
+    if app.sign_in_throttle.is_throttled(&throttle_keys) {
+        return Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            sign_in_form(
+                &native_app,
+                Some("Too many failed sign-in attempts. Try again later."),
+            ),
+        )
+            .into_response());
+    }
+
+    let account = if is_valid_username {
+        app.db.get_local_account(&form.username).await?
+    } else {
+        None
+    };
+    let (user, password_hash) = match account {
+        Some((user, password_hash)) => (Some(user), password_hash),
+        None => (None, DUMMY_PASSWORD_HASH.clone()),
+    };
+    let password = form.password;
+    let password_matches = run_scrypt(move || verify_password(&password, &password_hash)).await?;
+    if let Some(user) = user.filter(|_| password_matches) {
+        if let Some(username_key) = &username_key {
+            app.sign_in_throttle.clear(username_key);
+        }
+        return Ok(redirect_to_native_app(&app, user.id, &native_app)
+            .await?
+            .into_response());
+    }
+
+    app.sign_in_throttle.record_failure(&throttle_keys);
+    Ok((
+        StatusCode::UNAUTHORIZED,
+        sign_in_form(&native_app, Some("Invalid username or password.")),
+    )
+        .into_response())
+}
+
+fn accept_invite_form(code: &str, error: Option<&str>) -> Html<String> {
+    let mut body = String::from("<h1>Create your account</h1>");
+    if let Some(error) = error {
+        body.push_str(&format!("<p><strong>{}</strong></p>", html_escape(error)));
+    }
+    body.push_str(&format!(
+        "<form method=\"post\" action=\"/auth/local/invites/{}\">\
+            <p><label>Username <input name=\"username\" autocomplete=\"username\" required></label></p>\
+            <p><label>Password <input name=\"password\" type=\"password\" autocomplete=\"new-password\" required></label></p>\
+            <p><label>Confirm password <input name=\"password_confirmation\" type=\"password\" autocomplete=\"new-password\" required></label></p>\
+            <p><button type=\"submit\">Create account</button></p>\
+        </form>",
+        html_escape(code),
+    ));
+    render_page("Create your account", &body)
+}
+
+async fn render_accept_invite(
+    Path(code): Path<String>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Html<String>> {
+    ensure_enabled(&app)?;
+    if app
+        .db
+        .get_pending_local_account_invite(&code)
+        .await?
+        .is_none()
+    {
+        return Err(Error::Http(
+            StatusCode::NOT_FOUND,
+            "this invite is invalid or has already been used".to_string(),
+        ));
+    }
+    Ok(accept_invite_form(&code, None))
+}
+
+#[derive(Debug, Deserialize)]
+struct AcceptInviteForm {
+    username: String,
+    password: String,
+    password_confirmation: String,
+}
+
+async fn accept_invite(
+    Path(code): Path<String>,
+    Extension(app): Extension<Arc<AppState>>,
+    Form(form): Form<AcceptInviteForm>,
+) -> Result<Response> {
+    ensure_enabled(&app)?;
+    if app
+        .db
+        .get_pending_local_account_invite(&code)
+        .await?
+        .is_none()
+    {
+        return Err(Error::Http(
+            StatusCode::NOT_FOUND,
+            "this invite is invalid or has already been used".to_string(),
+        ));
+    }
+
+    let error = if !is_valid_username(&form.username) {
+        Some(format!(
+            "Usernames must be at most {MAX_USERNAME_LEN} letters, digits, dashes or underscores."
+        ))
+    } else if form.password.len() < MIN_PASSWORD_LEN {
+        Some(format!(
+            "Passwords must be at least {MIN_PASSWORD_LEN} characters long."
+        ))
+    } else if form.password != form.password_confirmation {
+        Some("Passwords don't match.".to_string())
+    } else if app
+        .db
+        .get_user_by_github_login(&form.username)
+        .await?
+        .is_some()
+    {
+        Some("That username is already taken.".to_string())
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        return Ok((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            accept_invite_form(&code, Some(&error)),
+        )
+            .into_response());
+    }
+
+    let password = form.password.clone();
+    let password_hash = run_scrypt(move || hash_password(&password)).await??;
+    let user = app
+        .db
+        .accept_local_account_invite(&code, &form.username, &password_hash)
+        .await?;
+    Ok(render_page(
+        "Account created",
+        &format!(
+            "<h1>Your account has been created</h1><p>Sign in from Zed as <strong>{}</strong>.</p>",
+            html_escape(&user.github_login)
+        ),
+    )
+    .into_response())
+}
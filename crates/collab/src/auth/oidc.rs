@@ -0,0 +1,354 @@
+use super::{redirect_to_native_app, NativeAppSignInParams};
+use crate::{AppState, Error, Result};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use axum::{
+    body::Body, extract::Query, http::StatusCode, response::Redirect, routing::get, Extension,
+    Router,
+};
+use collections::HashMap;
+use parking_lot::Mutex;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub(super) const SIGN_IN_PATH: &str = "/auth/oidc/signin";
+const CALLBACK_PATH: &str = "/auth/oidc/callback";
+
+/// How long the user has to finish signing in at the identity provider.
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+pub(super) fn routes() -> Router<Body> {
+    Router::new()
+        .route(SIGN_IN_PATH, get(sign_in))
+        .route(CALLBACK_PATH, get(handle_callback))
+}
+
+/// The requests we make to an OpenID Connect provider.
+#[async_trait]
+pub trait OidcClient: Send + Sync {
+    /// The provider's issuer identifier, which scopes the subject identifiers it assigns.
+    fn issuer(&self) -> &str;
+
+    /// Returns the URL at which the user signs in with the provider. Once they do, the
+    /// provider redirects them to `redirect_uri` with an authorization code.
+    fn authorization_url(&self, redirect_uri: &str, state: &str, code_challenge: &str) -> String;
+
+    /// Redeems an authorization code for the identity of the user who signed in.
+    async fn exchange_code(
+        &self,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str,
+    ) -> anyhow::Result<OidcIdentity>;
+}
+
+/// The claims about a user that we receive from the provider's userinfo endpoint.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct OidcIdentity {
+    #[serde(rename = "sub")]
+    pub subject: String,
+    pub preferred_username: Option<String>,
+    pub email: Option<String>,
+}
+
+impl OidcIdentity {
+    /// The login to give the user when they sign in for the first time.
+    fn login(&self) -> String {
+        self.preferred_username
+            .as_deref()
+            .or_else(|| self.email.as_deref()?.split('@').next())
+            .filter(|login| !login.is_empty())
+            .unwrap_or("user")
+            .to_string()
+    }
+}
+
+/// An OpenID Connect provider that users can sign in with, along with the sign-ins
+/// that are waiting for the provider to redirect the user back to us.
+pub struct OidcProvider {
+    client: Arc<dyn OidcClient>,
+    display_name: String,
+    pending_sign_ins: Mutex<HashMap<String, PendingSignIn>>,
+}
+
+struct PendingSignIn {
+    code_verifier: String,
+    native_app: NativeAppSignInParams,
+    started_at: Instant,
+}
+
+impl OidcProvider {
+    pub fn new(client: Arc<dyn OidcClient>, display_name: String) -> Self {
+        Self {
+            client,
+            display_name,
+            pending_sign_ins: Default::default(),
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    fn start_sign_in(
+        &self,
+        state: String,
+        code_verifier: String,
+        native_app: NativeAppSignInParams,
+    ) {
+        let mut pending_sign_ins = self.pending_sign_ins.lock();
+        pending_sign_ins.retain(|_, sign_in| sign_in.started_at.elapsed() < SIGN_IN_TIMEOUT);
+        pending_sign_ins.insert(
+            state,
+            PendingSignIn {
+                code_verifier,
+                native_app,
+                started_at: Instant::now(),
+            },
+        );
+    }
+
+    fn finish_sign_in(&self, state: &str) -> Option<PendingSignIn> {
+        self.pending_sign_ins
+            .lock()
+            .remove(state)
+            .filter(|sign_in| sign_in.started_at.elapsed() < SIGN_IN_TIMEOUT)
+    }
+}
+
+/// Computes the PKCE code challenge for a code verifier, using the S256 method.
+pub fn code_challenge(code_verifier: &str) -> String {
+    base64::encode_config(
+        Sha256::digest(code_verifier.as_bytes()),
+        base64::URL_SAFE_NO_PAD,
+    )
+}
+
+fn redirect_uri(app: &AppState) -> Result<String> {
+    let public_url = app.config.public_url.as_deref().ok_or_else(|| {
+        anyhow!("PUBLIC_URL must be configured to sign in with an identity provider")
+    })?;
+    Ok(format!(
+        "{}{}",
+        public_url.trim_end_matches('/'),
+        CALLBACK_PATH
+    ))
+}
+
+fn provider(app: &AppState) -> Result<&Arc<OidcProvider>> {
+    app.oidc.as_ref().ok_or_else(|| {
+        Error::Http(
+            StatusCode::NOT_FOUND,
+            "no OpenID Connect provider is configured".to_string(),
+        )
+    })
+}
+
+async fn sign_in(
+    Query(params): Query<NativeAppSignInParams>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Redirect> {
+    let provider = provider(&app)?;
+    let redirect_uri = redirect_uri(&app)?;
+    let state = nanoid::nanoid!(32);
+    let code_verifier = nanoid::nanoid!(64);
+    let authorization_url =
+        provider
+            .client
+            .authorization_url(&redirect_uri, &state, &code_challenge(&code_verifier));
+    provider.start_sign_in(state, code_verifier, params);
+    Ok(Redirect::to(&authorization_url))
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackParams {
+    state: String,
+    code: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+async fn handle_callback(
+    Query(params): Query<CallbackParams>,
+    Extension(app): Extension<Arc<AppState>>,
+) -> Result<Redirect> {
+    let provider = provider(&app)?;
+    let sign_in = provider.finish_sign_in(&params.state).ok_or_else(|| {
+        Error::Http(
+            StatusCode::BAD_REQUEST,
+            "sign-in is unknown or has expired".to_string(),
+        )
+    })?;
+    if let Some(error) = params.error {
+        return Err(Error::Http(
+            StatusCode::UNAUTHORIZED,
+            format!(
+                "sign-in failed: {}",
+                params.error_description.unwrap_or(error)
+            ),
+        ));
+    }
+    let code = params.code.ok_or_else(|| {
+        Error::Http(
+            StatusCode::BAD_REQUEST,
+            "missing authorization code".to_string(),
+        )
+    })?;
+
+    let identity = provider
+        .client
+        .exchange_code(&redirect_uri(&app)?, &code, &sign_in.code_verifier)
+        .await
+        .map_err(|error| {
+            Error::Http(
+                StatusCode::UNAUTHORIZED,
+                format!("failed to redeem authorization code: {error}"),
+            )
+        })?;
+    let user = app
+        .db
+        .get_or_create_user_by_identity(
+            provider.client.issuer(),
+            &identity.subject,
+            &identity.login(),
+            identity.email.as_deref(),
+        )
+        .await?;
+    redirect_to_native_app(&app, user.id, &sign_in.native_app).await
+}
+
+/// An [`OidcClient`] that talks to a real provider, using the endpoints from its
+/// discovery document.
+pub struct HttpOidcClient {
+    http: reqwest::Client,
+    issuer: String,
+    client_id: String,
+    client_secret: Option<String>,
+    authorization_endpoint: Url,
+    token_endpoint: Url,
+    userinfo_endpoint: Url,
+}
+
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+impl HttpOidcClient {
+    /// Fetches the provider's endpoints from its discovery document.
+    pub async fn discover(
+        issuer: String,
+        client_id: String,
+        client_secret: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let http = reqwest::ClientBuilder::new()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = http
+            .get(&discovery_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("invalid discovery document at {discovery_url}"))?;
+        if metadata.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+            Err(anyhow!(
+                "discovery document is for issuer {}, not {}",
+                metadata.issuer,
+                issuer
+            ))?;
+        }
+
+        Ok(Self {
+            http,
+            issuer: metadata.issuer,
+            client_id,
+            client_secret,
+            authorization_endpoint: Url::parse(&metadata.authorization_endpoint)
+                .context("invalid authorization endpoint")?,
+            token_endpoint: Url::parse(&metadata.token_endpoint)
+                .context("invalid token endpoint")?,
+            userinfo_endpoint: Url::parse(&metadata.userinfo_endpoint)
+                .context("invalid userinfo endpoint")?,
+        })
+    }
+}
+
+#[async_trait]
+impl OidcClient for HttpOidcClient {
+    fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    fn authorization_url(&self, redirect_uri: &str, state: &str, code_challenge: &str) -> String {
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", "openid profile email")
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        url.into()
+    }
+
+    async fn exchange_code(
+        &self,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str,
+    ) -> anyhow::Result<OidcIdentity> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", self.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(client_secret) = self.client_secret.as_deref() {
+            form.push(("client_secret", client_secret));
+        }
+        let token: TokenResponse = self
+            .http
+            .post(self.token_endpoint.clone())
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()
+            .context("token request failed")?
+            .json()
+            .await?;
+
+        // The userinfo endpoint is requested directly from the provider over TLS,
+        // so we can trust its claims without verifying the ID token's signature.
+        Ok(self
+            .http
+            .get(self.userinfo_endpoint.clone())
+            .bearer_auth(token.access_token)
+            .send()
+            .await?
+            .error_for_status()
+            .context("userinfo request failed")?
+            .json()
+            .await?)
+    }
+}
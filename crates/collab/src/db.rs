@@ -45,6 +45,7 @@ use tokio::sync::{Mutex, OwnedMutexGuard};
 
 pub use ids::*;
 pub use queries::contributors::ContributorSelector;
+pub use queries::identities::LOCAL_IDENTITY_PROVIDER;
 pub use sea_orm::ConnectOptions;
 pub use tables::user::Model as User;
 
@@ -86,6 +87,19 @@ impl Database {
         self.rooms.clear();
     }
 
+    /// Returns the size of the connection pool, if the database backend reports it.
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        if self.pool.get_database_backend() != sea_orm::DatabaseBackend::Postgres {
+            return None;
+        }
+
+        let pool = self.pool.get_postgres_connection_pool();
+        Some(PoolStats {
+            connections: pool.size(),
+            idle_connections: pool.num_idle() as u32,
+        })
+    }
+
     /// Runs the database migrations.
     pub async fn migrate(
         &self,
@@ -458,6 +472,30 @@ pub struct CreatedChannelMessage {
     pub notifications: NotificationBatch,
 }
 
+pub struct UpdatedChannelMessage {
+    pub message: proto::ChannelMessage,
+    pub participant_connection_ids: Vec<ConnectionId>,
+    pub notifications: NotificationBatch,
+}
+
+/// The buffer that a set of comment threads is anchored in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CommentLocation {
+    ChannelBuffer(ChannelId),
+    ProjectPath {
+        project_id: ProjectId,
+        worktree_id: u64,
+        path: String,
+    },
+}
+
+/// The result of creating, replying to, or resolving a comment thread.
+pub struct UpdatedCommentThread {
+    pub thread: proto::CommentThread,
+    pub connection_ids: Vec<ConnectionId>,
+    pub notifications: NotificationBatch,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, FromQueryResult, Serialize, Deserialize)]
 pub struct Invite {
     pub email_address: String,
@@ -672,6 +710,8 @@ pub struct Project {
     pub collaborators: Vec<ProjectCollaborator>,
     pub worktrees: BTreeMap<u64, Worktree>,
     pub language_servers: Vec<proto::LanguageServer>,
+    /// Whether the guest that joined the project can only read it.
+    pub read_only: bool,
 }
 
 pub struct ProjectCollaborator {
@@ -717,3 +757,37 @@ pub struct WorktreeSettingsFile {
     pub path: String,
     pub content: String,
 }
+
+#[derive(Clone, Copy, Debug)]
+pub struct PoolStats {
+    pub connections: u32,
+    pub idle_connections: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RoomSummary {
+    pub id: RoomId,
+    pub channel_id: Option<ChannelId>,
+    pub live_kit_room: String,
+    /// Users who have joined the room.
+    pub participant_user_ids: Vec<UserId>,
+    /// Users who have been called, but haven't answered yet.
+    pub pending_participant_user_ids: Vec<UserId>,
+    pub project_ids: Vec<ProjectId>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ProjectSummary {
+    pub id: ProjectId,
+    pub room_id: RoomId,
+    pub host_user_id: UserId,
+    pub guest_user_ids: Vec<UserId>,
+    pub worktree_root_names: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FeatureFlag {
+    pub id: FlagId,
+    pub flag: String,
+    pub user_ids: Vec<UserId>,
+}
@@ -72,17 +72,22 @@ id_type!(AccessTokenId);
 id_type!(ChannelChatParticipantId);
 id_type!(ChannelId);
 id_type!(ChannelMemberId);
+id_type!(CommentId);
+id_type!(CommentThreadId);
 id_type!(MessageId);
 id_type!(ContactId);
 id_type!(FollowerId);
+id_type!(LocalAccountInviteId);
 id_type!(RoomId);
 id_type!(RoomParticipantId);
 id_type!(ProjectId);
 id_type!(ProjectCollaboratorId);
+id_type!(ProjectInviteId);
 id_type!(ReplicaId);
 id_type!(ServerId);
 id_type!(SignupId);
 id_type!(UserId);
+id_type!(UserIdentityId);
 id_type!(ChannelBufferCollaboratorId);
 id_type!(FlagId);
 id_type!(NotificationId);
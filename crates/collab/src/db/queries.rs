@@ -3,8 +3,10 @@ use super::*;
 pub mod access_tokens;
 pub mod buffers;
 pub mod channels;
+pub mod comments;
 pub mod contacts;
 pub mod contributors;
+pub mod identities;
 pub mod messages;
 pub mod notifications;
 pub mod projects;
@@ -55,4 +55,17 @@ impl Database {
         })
         .await
     }
+
+    /// Deletes all of the given user's access tokens, including the ones they
+    /// use to impersonate other users. Returns the number of deleted tokens.
+    pub async fn delete_access_tokens_for_user(&self, user_id: UserId) -> Result<u64> {
+        self.transaction(|tx| async move {
+            let result = access_token::Entity::delete_many()
+                .filter(access_token::Column::UserId.eq(user_id))
+                .exec(&*tx)
+                .await?;
+            Ok(result.rows_affected)
+        })
+        .await
+    }
 }
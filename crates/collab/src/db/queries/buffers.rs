@@ -1,6 +1,7 @@
 use super::*;
 use prost::Message;
 use text::{EditOperation, UndoOperation};
+use time::{OffsetDateTime, PrimitiveDateTime};
 
 pub struct LeftChannelBuffer {
     pub channel_id: ChannelId,
@@ -46,6 +47,7 @@ impl Database {
                     operation_serialization_version: ActiveValue::Set(
                         storage::SERIALIZATION_VERSION,
                     ),
+                    created_at: ActiveValue::Set(Some(now_timestamp())),
                 }
                 .insert(&*tx)
                 .await?;
@@ -592,6 +594,60 @@ impl Database {
         Ok(())
     }
 
+    /// Returns the snapshots that were taken of a channel's notes, oldest first.
+    pub async fn get_channel_buffer_snapshots(
+        &self,
+        channel_id: ChannelId,
+        user_id: UserId,
+    ) -> Result<Vec<proto::ChannelBufferSnapshot>> {
+        self.transaction(|tx| async move {
+            let channel = self.get_channel_internal(channel_id, &*tx).await?;
+            self.check_user_is_channel_participant(&channel, user_id, &tx)
+                .await?;
+
+            let buffer = self.get_channel_buffer(channel_id, &tx).await?;
+            let snapshots = buffer_snapshot::Entity::find()
+                .filter(buffer_snapshot::Column::BufferId.eq(buffer.id))
+                .order_by_asc(buffer_snapshot::Column::Epoch)
+                .all(&*tx)
+                .await?;
+            Ok(snapshots
+                .into_iter()
+                .map(|snapshot| proto::ChannelBufferSnapshot {
+                    epoch: snapshot.epoch as u64,
+                    timestamp: snapshot
+                        .created_at
+                        .map(|created_at| created_at.assume_utc().unix_timestamp() as u64),
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Returns the text of a channel's notes as of the given snapshot.
+    pub async fn get_channel_buffer_snapshot_text(
+        &self,
+        channel_id: ChannelId,
+        epoch: i32,
+        user_id: UserId,
+    ) -> Result<String> {
+        self.transaction(|tx| async move {
+            let channel = self.get_channel_internal(channel_id, &*tx).await?;
+            self.check_user_is_channel_participant(&channel, user_id, &tx)
+                .await?;
+
+            let buffer = self.get_channel_buffer(channel_id, &tx).await?;
+            let snapshot = buffer_snapshot::Entity::find()
+                .filter(buffer_snapshot::Column::BufferId.eq(buffer.id))
+                .filter(buffer_snapshot::Column::Epoch.eq(epoch))
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such snapshot"))?;
+            Ok(snapshot.text)
+        })
+        .await
+    }
+
     async fn get_buffer_operation_serialization_version(
         &self,
         buffer_id: BufferId,
@@ -700,11 +756,15 @@ impl Database {
         let base_text = text_buffer.text();
         let epoch = buffer.epoch + 1;
 
+        self.reanchor_channel_comment_threads(channel_id, &text_buffer, epoch, tx)
+            .await?;
+
         buffer_snapshot::Model {
             buffer_id: buffer.id,
             epoch,
             text: base_text,
             operation_serialization_version: storage::SERIALIZATION_VERSION,
+            created_at: Some(now_timestamp()),
         }
         .into_active_model()
         .insert(tx)
@@ -1035,6 +1095,11 @@ enum QueryOperationSerializationVersion {
     OperationSerializationVersion,
 }
 
+fn now_timestamp() -> PrimitiveDateTime {
+    let now = OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
 mod storage {
     #![allow(non_snake_case)]
     use prost::Message;
@@ -0,0 +1,580 @@
+use super::*;
+use prost::Message as _;
+use rpc::Notification;
+use time::OffsetDateTime;
+
+/// The buffer and audience that a comment thread belongs to.
+struct CommentThreadTarget {
+    channel_id: Option<ChannelId>,
+    project_path: Option<CommentProjectPath>,
+    epoch: i32,
+    connection_ids: Vec<ConnectionId>,
+    member_ids: HashSet<UserId>,
+}
+
+/// The file that a project comment thread belongs to, both as it appears in the
+/// project that it was accessed through and as it is stored.
+struct CommentProjectPath {
+    project_id: ProjectId,
+    worktree_id: u64,
+    host_user_id: UserId,
+    worktree_abs_path: String,
+    path: String,
+}
+
+impl Database {
+    /// Returns all of the comment threads anchored in the given buffer.
+    pub async fn get_comment_threads(
+        &self,
+        location: CommentLocation,
+        user_id: UserId,
+        connection: ConnectionId,
+    ) -> Result<Vec<proto::CommentThread>> {
+        self.transaction(|tx| async move {
+            let target = self
+                .comment_thread_target(location, user_id, connection, &*tx)
+                .await?;
+            let threads = comment_thread::Entity::find()
+                .filter(Self::comment_thread_condition(&target))
+                .order_by_asc(comment_thread::Column::Id)
+                .all(&*tx)
+                .await?;
+            self.load_comment_threads(threads, &target, &*tx).await
+        })
+        .await
+    }
+
+    /// Creates a new comment thread over the given range, starting with a single comment.
+    pub async fn create_comment_thread(
+        &self,
+        location: CommentLocation,
+        user_id: UserId,
+        connection: ConnectionId,
+        start: proto::Anchor,
+        end: proto::Anchor,
+        body: &str,
+        mentions: &[proto::ChatMention],
+        timestamp: OffsetDateTime,
+    ) -> Result<UpdatedCommentThread> {
+        self.transaction(|tx| async move {
+            let target = self
+                .comment_thread_target(location, user_id, connection, &*tx)
+                .await?;
+            let timestamp = Self::comment_timestamp(timestamp);
+
+            let thread = comment_thread::ActiveModel {
+                id: ActiveValue::NotSet,
+                channel_id: ActiveValue::Set(target.channel_id),
+                host_user_id: ActiveValue::Set(
+                    target.project_path.as_ref().map(|path| path.host_user_id),
+                ),
+                worktree_abs_path: ActiveValue::Set(
+                    target
+                        .project_path
+                        .as_ref()
+                        .map(|path| path.worktree_abs_path.clone()),
+                ),
+                path: ActiveValue::Set(target.project_path.as_ref().map(|path| path.path.clone())),
+                epoch: ActiveValue::Set(target.epoch),
+                start_anchor: ActiveValue::Set(anchor_to_storage(start)),
+                end_anchor: ActiveValue::Set(anchor_to_storage(end)),
+                resolved: ActiveValue::Set(false),
+                author_id: ActiveValue::Set(user_id),
+                created_at: ActiveValue::Set(timestamp),
+            }
+            .insert(&*tx)
+            .await?;
+
+            let notifications = self
+                .insert_comment(&thread, &target, user_id, body, mentions, timestamp, &*tx)
+                .await?;
+            self.updated_comment_thread(thread, target, notifications, &*tx)
+                .await
+        })
+        .await
+    }
+
+    /// Adds a reply to an existing comment thread.
+    pub async fn create_comment(
+        &self,
+        thread_id: CommentThreadId,
+        user_id: UserId,
+        connection: ConnectionId,
+        body: &str,
+        mentions: &[proto::ChatMention],
+        timestamp: OffsetDateTime,
+    ) -> Result<UpdatedCommentThread> {
+        self.transaction(|tx| async move {
+            let (thread, target) = self
+                .get_comment_thread_with_target(thread_id, user_id, connection, &*tx)
+                .await?;
+            let timestamp = Self::comment_timestamp(timestamp);
+            let notifications = self
+                .insert_comment(&thread, &target, user_id, body, mentions, timestamp, &*tx)
+                .await?;
+            self.updated_comment_thread(thread, target, notifications, &*tx)
+                .await
+        })
+        .await
+    }
+
+    /// Marks a comment thread as resolved, or reopens it.
+    pub async fn set_comment_thread_resolved(
+        &self,
+        thread_id: CommentThreadId,
+        user_id: UserId,
+        connection: ConnectionId,
+        resolved: bool,
+    ) -> Result<UpdatedCommentThread> {
+        self.transaction(|tx| async move {
+            let (thread, target) = self
+                .get_comment_thread_with_target(thread_id, user_id, connection, &*tx)
+                .await?;
+            let thread = comment_thread::ActiveModel {
+                id: ActiveValue::Unchanged(thread.id),
+                resolved: ActiveValue::Set(resolved),
+                ..Default::default()
+            }
+            .update(&*tx)
+            .await?;
+            self.updated_comment_thread(thread, target, Vec::new(), &*tx)
+                .await
+        })
+        .await
+    }
+
+    /// Moves the anchors of a channel buffer's comment threads into the buffer's next epoch.
+    ///
+    /// The anchors are resolved against the buffer's final state, and then re-created
+    /// against a fresh buffer containing that same text, which is what collaborators
+    /// will be given as the base text of the new epoch.
+    pub(crate) async fn reanchor_channel_comment_threads(
+        &self,
+        channel_id: ChannelId,
+        old_buffer: &text::Buffer,
+        new_epoch: i32,
+        tx: &DatabaseTransaction,
+    ) -> Result<()> {
+        let threads = comment_thread::Entity::find()
+            .filter(comment_thread::Column::ChannelId.eq(channel_id))
+            .all(tx)
+            .await?;
+        if threads.is_empty() {
+            return Ok(());
+        }
+
+        let new_buffer = text::Buffer::new(0, old_buffer.remote_id(), old_buffer.text());
+        let reanchor = |bytes: &[u8]| {
+            let offset = anchor_from_storage(bytes, old_buffer.remote_id())
+                .filter(|anchor| old_buffer.can_resolve(anchor))
+                .map(|anchor| (old_buffer.summary_for_anchor::<usize>(&anchor), anchor.bias));
+            let (offset, bias) = offset.unwrap_or((0, text::Bias::Left));
+            anchor_to_storage(serialize_text_anchor(&new_buffer.anchor_at(offset, bias)))
+        };
+
+        for thread in threads {
+            comment_thread::ActiveModel {
+                id: ActiveValue::Unchanged(thread.id),
+                epoch: ActiveValue::Set(new_epoch),
+                start_anchor: ActiveValue::Set(reanchor(&thread.start_anchor)),
+                end_anchor: ActiveValue::Set(reanchor(&thread.end_anchor)),
+                ..Default::default()
+            }
+            .update(tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn comment_thread_target(
+        &self,
+        location: CommentLocation,
+        user_id: UserId,
+        connection: ConnectionId,
+        tx: &DatabaseTransaction,
+    ) -> Result<CommentThreadTarget> {
+        match location {
+            CommentLocation::ChannelBuffer(channel_id) => {
+                let channel = self.get_channel_internal(channel_id, tx).await?;
+                self.check_user_is_channel_participant(&channel, user_id, tx)
+                    .await?;
+                let buffer = self.get_channel_buffer(channel_id, tx).await?;
+
+                let connection_ids = channel_buffer_collaborator::Entity::find()
+                    .filter(channel_buffer_collaborator::Column::ChannelId.eq(channel_id))
+                    .all(tx)
+                    .await?
+                    .into_iter()
+                    .map(|collaborator| collaborator.connection())
+                    .collect();
+                let member_ids = self
+                    .get_channel_participants(&channel, tx)
+                    .await?
+                    .into_iter()
+                    .collect();
+
+                Ok(CommentThreadTarget {
+                    channel_id: Some(channel_id),
+                    project_path: None,
+                    epoch: buffer.epoch,
+                    connection_ids,
+                    member_ids,
+                })
+            }
+            CommentLocation::ProjectPath {
+                project_id,
+                worktree_id,
+                path,
+            } => {
+                let project = project::Entity::find_by_id(project_id)
+                    .one(tx)
+                    .await?
+                    .ok_or_else(|| anyhow!("no such project"))?;
+                let collaborators = project_collaborator::Entity::find()
+                    .filter(project_collaborator::Column::ProjectId.eq(project_id))
+                    .all(tx)
+                    .await?;
+                if !collaborators
+                    .iter()
+                    .any(|collaborator| collaborator.connection() == connection)
+                {
+                    Err(anyhow!("no such project"))?;
+                }
+                if project
+                    .excluded_paths()?
+                    .iter()
+                    .any(|excluded_path| excluded_path.is_match(&path))
+                {
+                    Err(anyhow!("no such path"))?;
+                }
+                let worktree = worktree::Entity::find()
+                    .filter(
+                        Condition::all()
+                            .add(worktree::Column::ProjectId.eq(project_id))
+                            .add(worktree::Column::Id.eq(worktree_id as i64)),
+                    )
+                    .one(tx)
+                    .await?
+                    .ok_or_else(|| anyhow!("no such worktree"))?;
+
+                Ok(CommentThreadTarget {
+                    channel_id: None,
+                    project_path: Some(CommentProjectPath {
+                        project_id,
+                        worktree_id,
+                        host_user_id: project.host_user_id,
+                        worktree_abs_path: worktree.abs_path,
+                        path,
+                    }),
+                    epoch: 0,
+                    connection_ids: collaborators
+                        .iter()
+                        .map(|collaborator| collaborator.connection())
+                        .collect(),
+                    member_ids: collaborators
+                        .iter()
+                        .map(|collaborator| collaborator.user_id)
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    async fn get_comment_thread_with_target(
+        &self,
+        thread_id: CommentThreadId,
+        user_id: UserId,
+        connection: ConnectionId,
+        tx: &DatabaseTransaction,
+    ) -> Result<(comment_thread::Model, CommentThreadTarget)> {
+        let thread = comment_thread::Entity::find_by_id(thread_id)
+            .one(tx)
+            .await?
+            .ok_or_else(|| anyhow!("no such comment thread"))?;
+        let location = if let Some(channel_id) = thread.channel_id {
+            CommentLocation::ChannelBuffer(channel_id)
+        } else if let (Some(host_user_id), Some(worktree_abs_path), Some(path)) = (
+            thread.host_user_id,
+            thread.worktree_abs_path.as_deref(),
+            thread.path.as_deref(),
+        ) {
+            // Find the worktree through which this connection can see the thread's file.
+            let project_ids = project_collaborator::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(project_collaborator::Column::ConnectionId.eq(connection.id as i32))
+                        .add(
+                            project_collaborator::Column::ConnectionServerId
+                                .eq(connection.owner_id as i32),
+                        ),
+                )
+                .all(tx)
+                .await?
+                .into_iter()
+                .map(|collaborator| collaborator.project_id);
+            let worktree = worktree::Entity::find()
+                .inner_join(project::Entity)
+                .filter(
+                    Condition::all()
+                        .add(worktree::Column::ProjectId.is_in(project_ids))
+                        .add(worktree::Column::AbsPath.eq(worktree_abs_path))
+                        .add(project::Column::HostUserId.eq(host_user_id)),
+                )
+                .one(tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such comment thread"))?;
+            CommentLocation::ProjectPath {
+                project_id: worktree.project_id,
+                worktree_id: worktree.id as u64,
+                path: path.to_string(),
+            }
+        } else {
+            Err(anyhow!("comment thread has no location"))?
+        };
+        let target = self
+            .comment_thread_target(location, user_id, connection, tx)
+            .await?;
+        Ok((thread, target))
+    }
+
+    fn comment_thread_condition(target: &CommentThreadTarget) -> Condition {
+        match (target.channel_id, &target.project_path) {
+            (Some(channel_id), _) => {
+                Condition::all().add(comment_thread::Column::ChannelId.eq(channel_id))
+            }
+            (None, Some(project_path)) => Condition::all()
+                .add(comment_thread::Column::HostUserId.eq(project_path.host_user_id))
+                .add(
+                    comment_thread::Column::WorktreeAbsPath
+                        .eq(project_path.worktree_abs_path.as_str()),
+                )
+                .add(comment_thread::Column::Path.eq(project_path.path.as_str())),
+            (None, None) => Condition::any(),
+        }
+    }
+
+    fn comment_timestamp(timestamp: OffsetDateTime) -> time::PrimitiveDateTime {
+        let timestamp = timestamp.to_offset(time::UtcOffset::UTC);
+        time::PrimitiveDateTime::new(timestamp.date(), timestamp.time())
+    }
+
+    async fn insert_comment(
+        &self,
+        thread: &comment_thread::Model,
+        target: &CommentThreadTarget,
+        user_id: UserId,
+        body: &str,
+        mentions: &[proto::ChatMention],
+        timestamp: time::PrimitiveDateTime,
+        tx: &DatabaseTransaction,
+    ) -> Result<NotificationBatch> {
+        let comment = comment::ActiveModel {
+            id: ActiveValue::NotSet,
+            thread_id: ActiveValue::Set(thread.id),
+            author_id: ActiveValue::Set(user_id),
+            body: ActiveValue::Set(body.to_string()),
+            created_at: ActiveValue::Set(timestamp),
+        }
+        .insert(tx)
+        .await?;
+
+        // Mentions are keyed by their start offset, so keep the first one at each offset.
+        let mut mention_starts = HashSet::default();
+        let mut mention_rows = Vec::new();
+        let mut mentioned_user_ids = HashSet::default();
+        for mention in mentions {
+            let Some(range) = mention.range.as_ref() else {
+                continue;
+            };
+            if range.start > range.end {
+                Err(anyhow!("invalid mention range"))?;
+            }
+            if !body.is_char_boundary(range.start as usize)
+                || !body.is_char_boundary(range.end as usize)
+                || !mention_starts.insert(range.start)
+            {
+                continue;
+            }
+            let mentioned_user_id = UserId::from_proto(mention.user_id);
+            mention_rows.push(comment_mention::ActiveModel {
+                comment_id: ActiveValue::Set(comment.id),
+                start_offset: ActiveValue::Set(range.start as i32),
+                end_offset: ActiveValue::Set(range.end as i32),
+                user_id: ActiveValue::Set(mentioned_user_id),
+            });
+
+            // Only notify mentioned users who are able to see the thread.
+            if mentioned_user_id != user_id && target.member_ids.contains(&mentioned_user_id) {
+                mentioned_user_ids.insert(mentioned_user_id);
+            }
+        }
+        if !mention_rows.is_empty() {
+            comment_mention::Entity::insert_many(mention_rows)
+                .exec(tx)
+                .await?;
+        }
+
+        let mut notifications = Vec::new();
+        for mentioned_user_id in mentioned_user_ids {
+            notifications.extend(
+                self.create_notification(
+                    mentioned_user_id,
+                    Notification::CommentMention {
+                        comment_id: comment.id.to_proto(),
+                        sender_id: user_id.to_proto(),
+                        thread_id: thread.id.to_proto(),
+                        channel_id: target.channel_id.map(|id| id.to_proto()),
+                        project_id: target
+                            .project_path
+                            .as_ref()
+                            .map(|path| path.project_id.to_proto()),
+                    },
+                    false,
+                    tx,
+                )
+                .await?,
+            );
+        }
+
+        Ok(notifications)
+    }
+
+    async fn updated_comment_thread(
+        &self,
+        thread: comment_thread::Model,
+        target: CommentThreadTarget,
+        notifications: NotificationBatch,
+        tx: &DatabaseTransaction,
+    ) -> Result<UpdatedCommentThread> {
+        let thread = self
+            .load_comment_threads(vec![thread], &target, tx)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("failed to load comment thread"))?;
+        Ok(UpdatedCommentThread {
+            thread,
+            connection_ids: target.connection_ids,
+            notifications,
+        })
+    }
+
+    async fn load_comment_threads(
+        &self,
+        rows: Vec<comment_thread::Model>,
+        target: &CommentThreadTarget,
+        tx: &DatabaseTransaction,
+    ) -> Result<Vec<proto::CommentThread>> {
+        let mut threads = rows
+            .into_iter()
+            .map(|row| {
+                Ok(proto::CommentThread {
+                    id: row.id.to_proto(),
+                    channel_id: row.channel_id.map(|id| id.to_proto()),
+                    project_id: target
+                        .project_path
+                        .as_ref()
+                        .map(|path| path.project_id.to_proto()),
+                    worktree_id: target.project_path.as_ref().map(|path| path.worktree_id),
+                    path: target.project_path.as_ref().map(|path| path.path.clone()),
+                    start: Some(proto::Anchor::decode(row.start_anchor.as_slice())?),
+                    end: Some(proto::Anchor::decode(row.end_anchor.as_slice())?),
+                    resolved: row.resolved,
+                    author_id: row.author_id.to_proto(),
+                    comments: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>, prost::DecodeError>>()
+            .map_err(|error| anyhow!("invalid comment anchor: {}", error))?;
+        let thread_ixs_by_id = threads
+            .iter()
+            .enumerate()
+            .map(|(ix, thread)| (thread.id, ix))
+            .collect::<HashMap<_, _>>();
+
+        let comments = comment::Entity::find()
+            .filter(comment::Column::ThreadId.is_in(threads.iter().map(|thread| thread.id)))
+            .order_by_asc(comment::Column::Id)
+            .all(tx)
+            .await?;
+        let mut mentions_by_comment_id = HashMap::<CommentId, Vec<proto::ChatMention>>::default();
+        let mut mentions = comment_mention::Entity::find()
+            .filter(comment_mention::Column::CommentId.is_in(comments.iter().map(|c| c.id)))
+            .order_by_asc(comment_mention::Column::StartOffset)
+            .stream(tx)
+            .await?;
+        while let Some(mention) = mentions.next().await {
+            let mention = mention?;
+            mentions_by_comment_id
+                .entry(mention.comment_id)
+                .or_default()
+                .push(proto::ChatMention {
+                    range: Some(proto::Range {
+                        start: mention.start_offset as u64,
+                        end: mention.end_offset as u64,
+                    }),
+                    user_id: mention.user_id.to_proto(),
+                });
+        }
+        drop(mentions);
+
+        for comment in comments {
+            if let Some(ix) = thread_ixs_by_id.get(&comment.thread_id.to_proto()) {
+                threads[*ix].comments.push(proto::Comment {
+                    id: comment.id.to_proto(),
+                    author_id: comment.author_id.to_proto(),
+                    body: comment.body,
+                    timestamp: comment.created_at.assume_utc().unix_timestamp() as u64,
+                    mentions: mentions_by_comment_id
+                        .remove(&comment.id)
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        Ok(threads)
+    }
+}
+
+/// Encodes an anchor for storage. The buffer id is dropped, since it is
+/// specific to each replica of the buffer.
+fn anchor_to_storage(anchor: proto::Anchor) -> Vec<u8> {
+    proto::Anchor {
+        buffer_id: None,
+        ..anchor
+    }
+    .encode_to_vec()
+}
+
+fn anchor_from_storage(bytes: &[u8], buffer_id: text::BufferId) -> Option<text::Anchor> {
+    let anchor = proto::Anchor::decode(bytes).ok()?;
+    let mut anchor = text::Anchor {
+        timestamp: clock::Lamport {
+            replica_id: anchor.replica_id as text::ReplicaId,
+            value: anchor.timestamp,
+        },
+        offset: anchor.offset as usize,
+        bias: match proto::Bias::from_i32(anchor.bias)? {
+            proto::Bias::Left => text::Bias::Left,
+            proto::Bias::Right => text::Bias::Right,
+        },
+        buffer_id: None,
+    };
+    if anchor != text::Anchor::MIN && anchor != text::Anchor::MAX {
+        anchor.buffer_id = Some(buffer_id);
+    }
+    Some(anchor)
+}
+
+fn serialize_text_anchor(anchor: &text::Anchor) -> proto::Anchor {
+    proto::Anchor {
+        replica_id: anchor.timestamp.replica_id as u32,
+        timestamp: anchor.timestamp.value,
+        offset: anchor.offset as u64,
+        bias: match anchor.bias {
+            text::Bias::Left => proto::Bias::Left as i32,
+            text::Bias::Right => proto::Bias::Right as i32,
+        },
+        buffer_id: None,
+    }
+}
@@ -0,0 +1,202 @@
+use super::*;
+
+/// The provider of accounts that sign in with a username and password.
+pub const LOCAL_IDENTITY_PROVIDER: &str = "local";
+
+impl Database {
+    /// Returns the user that signs in with the given account at an identity provider,
+    /// creating one if this is the first time that the account has been used.
+    pub async fn get_or_create_user_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+        login: &str,
+        email_address: Option<&str>,
+    ) -> Result<User> {
+        self.transaction(|tx| async move {
+            if let Some(identity) = user_identity::Entity::find()
+                .filter(user_identity::Column::Provider.eq(provider))
+                .filter(user_identity::Column::Subject.eq(subject))
+                .one(&*tx)
+                .await?
+            {
+                return Ok(user::Entity::find_by_id(identity.user_id)
+                    .one(&*tx)
+                    .await?
+                    .ok_or_else(|| anyhow!("no such user {}", identity.user_id))?);
+            }
+
+            let user = self
+                .create_user_with_unique_login(login, email_address, false, &*tx)
+                .await?;
+            user_identity::Entity::insert(user_identity::ActiveModel {
+                user_id: ActiveValue::set(user.id),
+                provider: ActiveValue::set(provider.into()),
+                subject: ActiveValue::set(subject.into()),
+                password_hash: ActiveValue::set(None),
+                ..Default::default()
+            })
+            .exec_without_returning(&*tx)
+            .await?;
+            Ok(user)
+        })
+        .await
+    }
+
+    /// Creates a user whose login is based on the given one. Logins must be unique,
+    /// so a numeric suffix is added when the login is already in use.
+    async fn create_user_with_unique_login(
+        &self,
+        login: &str,
+        email_address: Option<&str>,
+        admin: bool,
+        tx: &DatabaseTransaction,
+    ) -> Result<User> {
+        let mut candidate = login.to_string();
+        let mut suffix = 1;
+        while user::Entity::find()
+            .filter(user::Column::GithubLogin.eq(candidate.as_str()))
+            .one(tx)
+            .await?
+            .is_some()
+        {
+            suffix += 1;
+            candidate = format!("{login}{suffix}");
+        }
+
+        Ok(user::Entity::insert(user::ActiveModel {
+            github_login: ActiveValue::set(candidate),
+            github_user_id: ActiveValue::set(None),
+            email_address: ActiveValue::set(email_address.map(|email| email.into())),
+            admin: ActiveValue::set(admin),
+            invite_count: ActiveValue::set(0),
+            metrics_id: ActiveValue::set(Uuid::new_v4()),
+            ..Default::default()
+        })
+        .exec_with_returning(tx)
+        .await?)
+    }
+
+    /// Creates a single-use code that allows someone to create a local account.
+    pub async fn create_local_account_invite(
+        &self,
+        inviter_id: Option<UserId>,
+        email_address: Option<&str>,
+        admin: bool,
+    ) -> Result<String> {
+        self.transaction(|tx| async move {
+            let invite = local_account_invite::Entity::insert(local_account_invite::ActiveModel {
+                code: ActiveValue::set(nanoid::nanoid!(32)),
+                inviter_id: ActiveValue::set(inviter_id),
+                email_address: ActiveValue::set(email_address.map(|email| email.into())),
+                admin: ActiveValue::set(admin),
+                accepted_user_id: ActiveValue::set(None),
+                ..Default::default()
+            })
+            .exec_with_returning(&*tx)
+            .await?;
+            Ok(invite.code)
+        })
+        .await
+    }
+
+    /// Returns the invite with the given code, if it hasn't been used yet.
+    pub async fn get_pending_local_account_invite(
+        &self,
+        code: &str,
+    ) -> Result<Option<local_account_invite::Model>> {
+        self.transaction(|tx| async move {
+            Ok(local_account_invite::Entity::find()
+                .filter(local_account_invite::Column::Code.eq(code))
+                .filter(local_account_invite::Column::AcceptedUserId.is_null())
+                .one(&*tx)
+                .await?)
+        })
+        .await
+    }
+
+    /// Uses an invite code to create a local account with the given username
+    /// and password hash.
+    pub async fn accept_local_account_invite(
+        &self,
+        code: &str,
+        username: &str,
+        password_hash: &str,
+    ) -> Result<User> {
+        self.transaction(|tx| async move {
+            let invite = local_account_invite::Entity::find()
+                .filter(local_account_invite::Column::Code.eq(code))
+                .filter(local_account_invite::Column::AcceptedUserId.is_null())
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("invalid invite code"))?;
+
+            let username_taken = user::Entity::find()
+                .filter(user::Column::GithubLogin.eq(username))
+                .one(&*tx)
+                .await?
+                .is_some()
+                || user_identity::Entity::find()
+                    .filter(user_identity::Column::Provider.eq(LOCAL_IDENTITY_PROVIDER))
+                    .filter(user_identity::Column::Subject.eq(username))
+                    .one(&*tx)
+                    .await?
+                    .is_some();
+            if username_taken {
+                Err(anyhow!("username {} is already taken", username))?;
+            }
+
+            let user = self
+                .create_user_with_unique_login(
+                    username,
+                    invite.email_address.as_deref(),
+                    invite.admin,
+                    &*tx,
+                )
+                .await?;
+            user_identity::Entity::insert(user_identity::ActiveModel {
+                user_id: ActiveValue::set(user.id),
+                provider: ActiveValue::set(LOCAL_IDENTITY_PROVIDER.into()),
+                subject: ActiveValue::set(username.into()),
+                password_hash: ActiveValue::set(Some(password_hash.into())),
+                ..Default::default()
+            })
+            .exec_without_returning(&*tx)
+            .await?;
+
+            local_account_invite::ActiveModel {
+                id: ActiveValue::Unchanged(invite.id),
+                accepted_user_id: ActiveValue::set(Some(user.id)),
+                ..Default::default()
+            }
+            .update(&*tx)
+            .await?;
+
+            Ok(user)
+        })
+        .await
+    }
+
+    /// Returns the user with the given local account, along with the account's password hash.
+    pub async fn get_local_account(&self, username: &str) -> Result<Option<(User, String)>> {
+        self.transaction(|tx| async move {
+            let Some(identity) = user_identity::Entity::find()
+                .filter(user_identity::Column::Provider.eq(LOCAL_IDENTITY_PROVIDER))
+                .filter(user_identity::Column::Subject.eq(username))
+                .one(&*tx)
+                .await?
+            else {
+                return Ok(None);
+            };
+            let Some(password_hash) = identity.password_hash else {
+                return Ok(None);
+            };
+            let user = user::Entity::find_by_id(identity.user_id)
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such user {}", identity.user_id))?;
+            Ok(Some((user, password_hash)))
+        })
+        .await
+    }
+}
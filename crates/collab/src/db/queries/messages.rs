@@ -162,6 +162,12 @@ impl Database {
                         lower_half: nonce.1,
                     }),
                     reply_to_message_id: row.reply_to_message_id.map(|id| id.to_proto()),
+                    edited_at: row
+                        .edited_at
+                        .map(|edited_at| edited_at.assume_utc().unix_timestamp() as u64),
+                    reactions: Vec::new(),
+                    attachments: Vec::new(),
+                    reply_count: 0,
                 }
             })
             .collect::<Vec<_>>();
@@ -195,10 +201,385 @@ impl Database {
                 }
             }
         }
+        drop(mentions);
+
+        let message_ixs = messages
+            .iter()
+            .enumerate()
+            .map(|(ix, message)| (message.id, ix))
+            .collect::<HashMap<_, _>>();
+        let message_ids = || message_ixs.keys().map(|id| MessageId::from_proto(*id));
+
+        let reactions = channel_message_reaction::Entity::find()
+            .filter(channel_message_reaction::Column::MessageId.is_in(message_ids()))
+            .order_by_asc(channel_message_reaction::Column::Emoji)
+            .order_by_asc(channel_message_reaction::Column::UserId)
+            .all(&*tx)
+            .await?;
+        for reaction in reactions {
+            let Some(ix) = message_ixs.get(&reaction.message_id.to_proto()) else {
+                continue;
+            };
+            let reactions = &mut messages[*ix].reactions;
+            let user_id = reaction.user_id.to_proto();
+            if let Some(existing) = reactions.iter_mut().find(|r| r.emoji == reaction.emoji) {
+                existing.user_ids.push(user_id);
+            } else {
+                reactions.push(proto::ChatReaction {
+                    emoji: reaction.emoji,
+                    user_ids: vec![user_id],
+                });
+            }
+        }
+
+        let attachments = channel_message_attachment::Entity::find()
+            .filter(channel_message_attachment::Column::MessageId.is_in(message_ids()))
+            .order_by_asc(channel_message_attachment::Column::Id)
+            .all(&*tx)
+            .await?;
+        for attachment in attachments {
+            if let Some(ix) = message_ixs.get(&attachment.message_id.to_proto()) {
+                messages[*ix].attachments.push(proto::ChatAttachment {
+                    project_id: attachment.project_id.map(|id| id.to_proto()),
+                    worktree_id: attachment.worktree_id as u64,
+                    path: attachment.path,
+                    start_row: attachment.start_row as u32,
+                    end_row: attachment.end_row as u32,
+                    language: attachment.language,
+                    text: attachment.text,
+                });
+            }
+        }
+
+        #[derive(Debug, FromQueryResult)]
+        struct ReplyCount {
+            reply_to_message_id: MessageId,
+            count: i64,
+        }
+
+        let reply_counts = channel_message::Entity::find()
+            .select_only()
+            .column(channel_message::Column::ReplyToMessageId)
+            .column_as(channel_message::Column::Id.count(), "count")
+            .filter(channel_message::Column::ReplyToMessageId.is_in(message_ids()))
+            .group_by(channel_message::Column::ReplyToMessageId)
+            .into_model::<ReplyCount>()
+            .all(&*tx)
+            .await?;
+        for reply_count in reply_counts {
+            if let Some(ix) = message_ixs.get(&reply_count.reply_to_message_id.to_proto()) {
+                messages[*ix].reply_count = reply_count.count as u64;
+            }
+        }
 
         Ok(messages)
     }
 
+    /// Returns the given message, along with all of the messages that reply to it.
+    pub async fn get_channel_message_thread(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+    ) -> Result<Vec<proto::ChannelMessage>> {
+        self.transaction(|tx| async move {
+            let channel = self.get_channel_internal(channel_id, &*tx).await?;
+            self.check_user_is_channel_participant(&channel, user_id, &*tx)
+                .await?;
+
+            let rows = channel_message::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(channel_message::Column::ChannelId.eq(channel_id))
+                        .add(
+                            Condition::any()
+                                .add(channel_message::Column::Id.eq(message_id))
+                                .add(channel_message::Column::ReplyToMessageId.eq(message_id)),
+                        ),
+                )
+                .order_by_desc(channel_message::Column::Id)
+                .all(&*tx)
+                .await?;
+            if rows.last().map_or(true, |row| row.id != message_id) {
+                Err(anyhow!("no such message"))?;
+            }
+
+            self.load_channel_messages(rows, &*tx).await
+        })
+        .await
+    }
+
+    /// Returns the previous versions of an edited message, oldest first.
+    pub async fn get_channel_message_edits(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+    ) -> Result<Vec<proto::ChannelMessageEdit>> {
+        self.transaction(|tx| async move {
+            let channel = self.get_channel_internal(channel_id, &*tx).await?;
+            self.check_user_is_channel_participant(&channel, user_id, &*tx)
+                .await?;
+            self.get_channel_message_internal(channel_id, message_id, &*tx)
+                .await?;
+
+            let edits = channel_message_edit::Entity::find()
+                .filter(channel_message_edit::Column::MessageId.eq(message_id))
+                .order_by_asc(channel_message_edit::Column::Id)
+                .all(&*tx)
+                .await?;
+            Ok(edits
+                .into_iter()
+                .map(|edit| proto::ChannelMessageEdit {
+                    body: edit.body,
+                    timestamp: edit.sent_at.assume_utc().unix_timestamp() as u64,
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Replaces the body of one of the user's own messages, keeping the previous
+    /// body in the message's edit history.
+    pub async fn update_channel_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        body: &str,
+        mentions: &[proto::ChatMention],
+        timestamp: OffsetDateTime,
+    ) -> Result<UpdatedChannelMessage> {
+        self.transaction(|tx| async move {
+            let participant_connection_ids = self
+                .channel_chat_connection_ids(channel_id, user_id, &*tx)
+                .await?;
+            let message = self
+                .get_channel_message_internal(channel_id, message_id, &*tx)
+                .await?;
+            if message.sender_id != user_id {
+                Err(anyhow!("can only edit your own messages"))?;
+            }
+
+            let timestamp = timestamp.to_offset(time::UtcOffset::UTC);
+            let timestamp = time::PrimitiveDateTime::new(timestamp.date(), timestamp.time());
+
+            channel_message_edit::ActiveModel {
+                id: ActiveValue::NotSet,
+                message_id: ActiveValue::Set(message_id),
+                body: ActiveValue::Set(message.body.clone()),
+                sent_at: ActiveValue::Set(message.edited_at.unwrap_or(message.sent_at)),
+            }
+            .insert(&*tx)
+            .await?;
+
+            channel_message::ActiveModel {
+                id: ActiveValue::Unchanged(message_id),
+                body: ActiveValue::Set(body.to_string()),
+                edited_at: ActiveValue::Set(Some(timestamp)),
+                ..Default::default()
+            }
+            .update(&*tx)
+            .await?;
+
+            let previously_mentioned_user_ids = channel_message_mention::Entity::find()
+                .filter(channel_message_mention::Column::MessageId.eq(message_id))
+                .all(&*tx)
+                .await?
+                .into_iter()
+                .map(|mention| mention.user_id)
+                .collect::<HashSet<_>>();
+            channel_message_mention::Entity::delete_many()
+                .filter(channel_message_mention::Column::MessageId.eq(message_id))
+                .exec(&*tx)
+                .await?;
+            let notifications = self
+                .create_channel_message_mentions(
+                    channel_id,
+                    message_id,
+                    user_id,
+                    body,
+                    mentions,
+                    &previously_mentioned_user_ids,
+                    &*tx,
+                )
+                .await?;
+
+            let message = self
+                .load_channel_messages(
+                    vec![
+                        self.get_channel_message_internal(channel_id, message_id, &*tx)
+                            .await?,
+                    ],
+                    &*tx,
+                )
+                .await?
+                .pop()
+                .ok_or_else(|| anyhow!("no such message"))?;
+
+            Ok(UpdatedChannelMessage {
+                message,
+                participant_connection_ids,
+                notifications,
+            })
+        })
+        .await
+    }
+
+    /// Adds the given emoji reaction to a message, or removes it if the user
+    /// has already reacted with it.
+    pub async fn toggle_channel_message_reaction(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        emoji: &str,
+    ) -> Result<UpdatedChannelMessage> {
+        self.transaction(|tx| async move {
+            let participant_connection_ids = self
+                .channel_chat_connection_ids(channel_id, user_id, &*tx)
+                .await?;
+            let message = self
+                .get_channel_message_internal(channel_id, message_id, &*tx)
+                .await?;
+
+            let result = channel_message_reaction::Entity::delete_many()
+                .filter(
+                    Condition::all()
+                        .add(channel_message_reaction::Column::MessageId.eq(message_id))
+                        .add(channel_message_reaction::Column::UserId.eq(user_id))
+                        .add(channel_message_reaction::Column::Emoji.eq(emoji)),
+                )
+                .exec(&*tx)
+                .await?;
+            if result.rows_affected == 0 {
+                channel_message_reaction::ActiveModel {
+                    message_id: ActiveValue::Set(message_id),
+                    user_id: ActiveValue::Set(user_id),
+                    emoji: ActiveValue::Set(emoji.to_string()),
+                }
+                .insert(&*tx)
+                .await?;
+            }
+
+            let message = self
+                .load_channel_messages(vec![message], &*tx)
+                .await?
+                .pop()
+                .ok_or_else(|| anyhow!("no such message"))?;
+
+            Ok(UpdatedChannelMessage {
+                message,
+                participant_connection_ids,
+                notifications: Vec::new(),
+            })
+        })
+        .await
+    }
+
+    async fn get_channel_message_internal(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        tx: &DatabaseTransaction,
+    ) -> Result<channel_message::Model> {
+        Ok(channel_message::Entity::find_by_id(message_id)
+            .filter(channel_message::Column::ChannelId.eq(channel_id))
+            .one(&*tx)
+            .await?
+            .ok_or_else(|| anyhow!("no such message"))?)
+    }
+
+    /// Returns the connections that are participating in the channel's chat,
+    /// failing if the given user isn't one of the participants.
+    async fn channel_chat_connection_ids(
+        &self,
+        channel_id: ChannelId,
+        user_id: UserId,
+        tx: &DatabaseTransaction,
+    ) -> Result<Vec<ConnectionId>> {
+        let mut rows = channel_chat_participant::Entity::find()
+            .filter(channel_chat_participant::Column::ChannelId.eq(channel_id))
+            .stream(&*tx)
+            .await?;
+
+        let mut is_participant = false;
+        let mut participant_connection_ids = Vec::new();
+        while let Some(row) = rows.next().await {
+            let row = row?;
+            if row.user_id == user_id {
+                is_participant = true;
+            }
+            participant_connection_ids.push(row.connection());
+        }
+        drop(rows);
+
+        if !is_participant {
+            Err(anyhow!("not a chat participant"))?;
+        }
+        Ok(participant_connection_ids)
+    }
+
+    /// Stores the mentions in a message's body, and notifies any mentioned users
+    /// that aren't in `already_notified`.
+    async fn create_channel_message_mentions(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        sender_id: UserId,
+        body: &str,
+        mentions: &[proto::ChatMention],
+        already_notified: &HashSet<UserId>,
+        tx: &DatabaseTransaction,
+    ) -> Result<NotificationBatch> {
+        let mentioned_user_ids = mentions.iter().map(|m| m.user_id).collect::<HashSet<_>>();
+
+        let mentions = mentions
+            .iter()
+            .filter_map(|mention| {
+                let range = mention.range.as_ref()?;
+                if !body.is_char_boundary(range.start as usize)
+                    || !body.is_char_boundary(range.end as usize)
+                {
+                    return None;
+                }
+                Some(channel_message_mention::ActiveModel {
+                    message_id: ActiveValue::Set(message_id),
+                    start_offset: ActiveValue::Set(range.start as i32),
+                    end_offset: ActiveValue::Set(range.end as i32),
+                    user_id: ActiveValue::Set(UserId::from_proto(mention.user_id)),
+                })
+            })
+            .collect::<Vec<_>>();
+        if !mentions.is_empty() {
+            channel_message_mention::Entity::insert_many(mentions)
+                .exec(&*tx)
+                .await?;
+        }
+
+        let mut notifications = Vec::new();
+        for mentioned_user in mentioned_user_ids {
+            let mentioned_user = UserId::from_proto(mentioned_user);
+            if already_notified.contains(&mentioned_user) {
+                continue;
+            }
+            notifications.extend(
+                self.create_notification(
+                    mentioned_user,
+                    rpc::Notification::ChannelMessageMention {
+                        message_id: message_id.to_proto(),
+                        sender_id: sender_id.to_proto(),
+                        channel_id: channel_id.to_proto(),
+                    },
+                    false,
+                    &*tx,
+                )
+                .await?,
+            );
+        }
+        Ok(notifications)
+    }
+
     /// Creates a new channel message.
     pub async fn create_channel_message(
         &self,
@@ -209,6 +590,7 @@ impl Database {
         timestamp: OffsetDateTime,
         nonce: u128,
         reply_to_message_id: Option<MessageId>,
+        attachments: &[proto::ChatAttachment],
     ) -> Result<CreatedChannelMessage> {
         self.transaction(|tx| async move {
             let channel = self.get_channel_internal(channel_id, &*tx).await?;
@@ -248,6 +630,7 @@ impl Database {
                 nonce: ActiveValue::Set(Uuid::from_u128(nonce)),
                 id: ActiveValue::NotSet,
                 reply_to_message_id: ActiveValue::Set(reply_to_message_id),
+                edited_at: ActiveValue::Set(None),
             })
             .on_conflict(
                 OnConflict::columns([
@@ -262,56 +645,49 @@ impl Database {
             .await?;
 
             let message_id;
-            let mut notifications = Vec::new();
+            let notifications;
             match result {
                 TryInsertResult::Inserted(result) => {
                     message_id = result.last_insert_id;
-                    let mentioned_user_ids =
-                        mentions.iter().map(|m| m.user_id).collect::<HashSet<_>>();
+                    notifications = self
+                        .create_channel_message_mentions(
+                            channel_id,
+                            message_id,
+                            user_id,
+                            body,
+                            mentions,
+                            &HashSet::default(),
+                            &*tx,
+                        )
+                        .await?;
 
-                    let mentions = mentions
+                    let attachments = attachments
                         .iter()
-                        .filter_map(|mention| {
-                            let range = mention.range.as_ref()?;
-                            if !body.is_char_boundary(range.start as usize)
-                                || !body.is_char_boundary(range.end as usize)
-                            {
-                                return None;
-                            }
-                            Some(channel_message_mention::ActiveModel {
-                                message_id: ActiveValue::Set(message_id),
-                                start_offset: ActiveValue::Set(range.start as i32),
-                                end_offset: ActiveValue::Set(range.end as i32),
-                                user_id: ActiveValue::Set(UserId::from_proto(mention.user_id)),
-                            })
+                        .map(|attachment| channel_message_attachment::ActiveModel {
+                            id: ActiveValue::NotSet,
+                            message_id: ActiveValue::Set(message_id),
+                            project_id: ActiveValue::Set(
+                                attachment.project_id.map(ProjectId::from_proto),
+                            ),
+                            worktree_id: ActiveValue::Set(attachment.worktree_id as i64),
+                            path: ActiveValue::Set(attachment.path.clone()),
+                            start_row: ActiveValue::Set(attachment.start_row as i32),
+                            end_row: ActiveValue::Set(attachment.end_row as i32),
+                            language: ActiveValue::Set(attachment.language.clone()),
+                            text: ActiveValue::Set(attachment.text.clone()),
                         })
                         .collect::<Vec<_>>();
-                    if !mentions.is_empty() {
-                        channel_message_mention::Entity::insert_many(mentions)
+                    if !attachments.is_empty() {
+                        channel_message_attachment::Entity::insert_many(attachments)
                             .exec(&*tx)
                             .await?;
                     }
 
-                    for mentioned_user in mentioned_user_ids {
-                        notifications.extend(
-                            self.create_notification(
-                                UserId::from_proto(mentioned_user),
-                                rpc::Notification::ChannelMessageMention {
-                                    message_id: message_id.to_proto(),
-                                    sender_id: user_id.to_proto(),
-                                    channel_id: channel_id.to_proto(),
-                                },
-                                false,
-                                &*tx,
-                            )
-                            .await?,
-                        );
-                    }
-
                     self.observe_channel_message_internal(channel_id, user_id, message_id, &*tx)
                         .await?;
                 }
                 _ => {
+                    notifications = Vec::new();
                     message_id = channel_message::Entity::find()
                         .filter(channel_message::Column::Nonce.eq(Uuid::from_u128(nonce)))
                         .one(&*tx)
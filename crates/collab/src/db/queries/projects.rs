@@ -1,4 +1,6 @@
 use super::*;
+use time::{OffsetDateTime, PrimitiveDateTime};
+use util::paths::PathMatcher;
 
 impl Database {
     /// Returns the count of all projects, excluding ones marked as admin.
@@ -22,13 +24,73 @@ impl Database {
         .await
     }
 
-    /// Shares a project with the given room.
+    /// Returns every shared project, along with its guests and visible worktrees.
+    pub async fn get_project_summaries(&self) -> Result<Vec<ProjectSummary>> {
+        self.transaction(|tx| async move {
+            let mut summaries = project::Entity::find()
+                .order_by_asc(project::Column::Id)
+                .all(&*tx)
+                .await?
+                .into_iter()
+                .map(|project| {
+                    (
+                        project.id,
+                        ProjectSummary {
+                            id: project.id,
+                            room_id: project.room_id,
+                            host_user_id: project.host_user_id,
+                            guest_user_ids: Vec::new(),
+                            worktree_root_names: Vec::new(),
+                        },
+                    )
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            let collaborators = project_collaborator::Entity::find()
+                .filter(project_collaborator::Column::IsHost.eq(false))
+                .order_by_asc(project_collaborator::Column::Id)
+                .all(&*tx)
+                .await?;
+            for collaborator in collaborators {
+                if let Some(summary) = summaries.get_mut(&collaborator.project_id) {
+                    summary.guest_user_ids.push(collaborator.user_id);
+                }
+            }
+
+            let worktrees = worktree::Entity::find()
+                .filter(worktree::Column::Visible.eq(true))
+                .order_by_asc(worktree::Column::Id)
+                .all(&*tx)
+                .await?;
+            for worktree in worktrees {
+                if let Some(summary) = summaries.get_mut(&worktree.project_id) {
+                    summary.worktree_root_names.push(worktree.root_name);
+                }
+            }
+
+            Ok(summaries.into_values().collect())
+        })
+        .await
+    }
+
+    /// Shares a project with the given room, limiting what guests can do to the given scope.
     pub async fn share_project(
         &self,
         room_id: RoomId,
         connection: ConnectionId,
         worktrees: &[proto::WorktreeMetadata],
+        scope: &proto::ProjectShareScope,
     ) -> Result<RoomGuard<(ProjectId, proto::Room)>> {
+        for excluded_path in &scope.excluded_paths {
+            PathMatcher::new(excluded_path)
+                .map_err(|error| anyhow!("invalid excluded path {excluded_path:?}: {error}"))?;
+        }
+        let excluded_paths = if scope.excluded_paths.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&scope.excluded_paths)?)
+        };
+
         self.room_transaction(room_id, |tx| async move {
             let participant = room_participant::Entity::find()
                 .filter(
@@ -63,6 +125,9 @@ impl Database {
                 host_connection_server_id: ActiveValue::set(Some(ServerId(
                     connection.owner_id as i32,
                 ))),
+                read_only: ActiveValue::set(scope.read_only),
+                invite_only: ActiveValue::set(scope.invite_only),
+                excluded_paths: ActiveValue::set(excluded_paths.clone()),
                 ..Default::default()
             }
             .insert(&*tx)
@@ -91,6 +156,7 @@ impl Database {
                 user_id: ActiveValue::set(participant.user_id),
                 replica_id: ActiveValue::set(ReplicaId(0)),
                 is_host: ActiveValue::set(true),
+                read_only: ActiveValue::set(false),
                 ..Default::default()
             }
             .insert(&*tx)
@@ -195,17 +261,21 @@ impl Database {
         Ok(())
     }
 
+    /// Updates the worktree with the given ID.
+    ///
+    /// Entries matching the project's excluded paths are dropped, and the update
+    /// that guests are allowed to see is returned along with their connections.
     pub async fn update_worktree(
         &self,
         update: &proto::UpdateWorktree,
         connection: ConnectionId,
-    ) -> Result<RoomGuard<Vec<ConnectionId>>> {
+    ) -> Result<RoomGuard<(Vec<ConnectionId>, proto::UpdateWorktree)>> {
         let project_id = ProjectId::from_proto(update.project_id);
         let worktree_id = update.worktree_id as i64;
         let room_id = self.room_id_for_project(project_id).await?;
         self.room_transaction(room_id, |tx| async move {
             // Ensure the update comes from the host.
-            let _project = project::Entity::find_by_id(project_id)
+            let project = project::Entity::find_by_id(project_id)
                 .filter(
                     Condition::all()
                         .add(project::Column::HostConnectionId.eq(connection.id as i32))
@@ -217,6 +287,15 @@ impl Database {
                 .await?
                 .ok_or_else(|| anyhow!("no such project"))?;
 
+            let excluded_paths = project.excluded_paths()?;
+            let mut update = update.clone();
+            update.updated_entries.retain(|entry| {
+                !excluded_paths
+                    .iter()
+                    .any(|excluded_path| excluded_path.is_match(&entry.path))
+            });
+            let update = &update;
+
             // Update metadata.
             worktree::Entity::update(worktree::ActiveModel {
                 id: ActiveValue::set(worktree_id),
@@ -345,7 +424,7 @@ impl Database {
             }
 
             let connection_ids = self.project_guest_connection_ids(project_id, &tx).await?;
-            Ok(connection_ids)
+            Ok((connection_ids, update.clone()))
         })
         .await
     }
@@ -373,6 +452,13 @@ impl Database {
             if project.host_connection()? != connection {
                 return Err(anyhow!("can't update a project hosted by someone else"))?;
             }
+            if project
+                .excluded_paths()?
+                .iter()
+                .any(|excluded_path| excluded_path.is_match(&summary.path))
+            {
+                return Ok(Vec::new());
+            }
 
             // Update summary.
             worktree_diagnostic_summary::Entity::insert(worktree_diagnostic_summary::ActiveModel {
@@ -507,11 +593,17 @@ impl Database {
     }
 
     /// Adds the given connection to the specified project.
+    ///
+    /// If an invite token is given, the invite must not have expired at `now`,
+    /// and it decides whether the guest can edit the project.
     pub async fn join_project(
         &self,
         project_id: ProjectId,
         connection: ConnectionId,
+        invite_token: Option<&str>,
+        now: OffsetDateTime,
     ) -> Result<RoomGuard<(Project, ReplicaId)>> {
+        let now = PrimitiveDateTime::new(now.date(), now.time());
         let room_id = self.room_id_for_project(project_id).await?;
         self.room_transaction(room_id, |tx| async move {
             let participant = room_participant::Entity::find()
@@ -538,6 +630,24 @@ impl Database {
                 return Err(anyhow!("no such project"))?;
             }
 
+            let invite = if let Some(invite_token) = invite_token {
+                Some(
+                    project_invite::Entity::find()
+                        .filter(project_invite::Column::ProjectId.eq(project_id))
+                        .filter(project_invite::Column::Token.eq(invite_token))
+                        .filter(project_invite::Column::ExpiresAt.gt(now))
+                        .one(&*tx)
+                        .await?
+                        .ok_or_else(|| anyhow!("invite is invalid or has expired"))?,
+                )
+            } else {
+                None
+            };
+            if project.invite_only && invite.is_none() {
+                return Err(anyhow!("project can only be joined with an invite"))?;
+            }
+            let read_only = invite.map_or(project.read_only, |invite| invite.read_only);
+
             let mut collaborators = project
                 .find_related(project_collaborator::Entity)
                 .all(&*tx)
@@ -557,6 +667,7 @@ impl Database {
                 user_id: ActiveValue::set(participant.user_id),
                 replica_id: ActiveValue::set(replica_id),
                 is_host: ActiveValue::set(false),
+                read_only: ActiveValue::set(read_only),
                 ..Default::default()
             }
             .insert(&*tx)
@@ -706,6 +817,7 @@ impl Database {
                         name: language_server.name,
                     })
                     .collect(),
+                read_only,
             };
             Ok((project, replica_id as ReplicaId))
         })
@@ -852,6 +964,60 @@ impl Database {
         .map(|guard| guard.into_inner())
     }
 
+    /// Creates an invite that lets room participants join the given project
+    /// until it expires. Only the project's host can create invites.
+    pub async fn create_project_invite(
+        &self,
+        project_id: ProjectId,
+        connection: ConnectionId,
+        read_only: bool,
+        expires_at: OffsetDateTime,
+    ) -> Result<String> {
+        let expires_at = PrimitiveDateTime::new(expires_at.date(), expires_at.time());
+        let room_id = self.room_id_for_project(project_id).await?;
+        self.room_transaction(room_id, |tx| async move {
+            let project = project::Entity::find_by_id(project_id)
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such project"))?;
+            if project.host_connection()? != connection {
+                return Err(anyhow!("only the host can invite guests to a project"))?;
+            }
+
+            let invite = project_invite::Entity::insert(project_invite::ActiveModel {
+                project_id: ActiveValue::set(project_id),
+                token: ActiveValue::set(nanoid::nanoid!(32)),
+                read_only: ActiveValue::set(read_only),
+                expires_at: ActiveValue::set(expires_at),
+                ..Default::default()
+            })
+            .exec_with_returning(&*tx)
+            .await?;
+            Ok(invite.token)
+        })
+        .await
+        .map(|guard| guard.into_inner())
+    }
+
+    /// Returns whether the given worktree path is hidden from the project's guests.
+    pub async fn is_path_excluded_from_project(
+        &self,
+        project_id: ProjectId,
+        path: &str,
+    ) -> Result<bool> {
+        self.transaction(|tx| async move {
+            let project = project::Entity::find_by_id(project_id)
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such project"))?;
+            Ok(project
+                .excluded_paths()?
+                .iter()
+                .any(|excluded_path| excluded_path.is_match(path)))
+        })
+        .await
+    }
+
     /// Returns the host connection for a request to join a shared project.
     pub async fn host_for_mutating_project_request(
         &self,
@@ -874,6 +1040,19 @@ impl Database {
                 Err(anyhow!("not authorized to edit projects"))?;
             }
 
+            let current_collaborator = project_collaborator::Entity::find()
+                .filter(project_collaborator::Column::ProjectId.eq(project_id))
+                .filter(project_collaborator::Column::ConnectionId.eq(connection_id.id as i32))
+                .filter(
+                    project_collaborator::Column::ConnectionServerId
+                        .eq(connection_id.owner_id as i32),
+                )
+                .one(&*tx)
+                .await?;
+            if current_collaborator.map_or(false, |collaborator| collaborator.read_only) {
+                Err(anyhow!("not authorized to edit this project"))?;
+            }
+
             let host = project_collaborator::Entity::find()
                 .filter(
                     project_collaborator::Column::ProjectId
@@ -916,7 +1095,16 @@ impl Database {
             let collaborators = project_collaborator::Entity::find()
                 .filter(project_collaborator::Column::ProjectId.eq(project_id))
                 .all(&*tx)
-                .await?
+                .await?;
+            if requires_write
+                && collaborators.iter().any(|collaborator| {
+                    collaborator.connection() == connection_id && collaborator.read_only
+                })
+            {
+                Err(anyhow!("not authorized to edit this project"))?;
+            }
+
+            let collaborators = collaborators
                 .into_iter()
                 .map(|collaborator| ProjectCollaborator {
                     connection_id: collaborator.connection(),
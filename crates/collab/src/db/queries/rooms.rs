@@ -1,6 +1,60 @@
 use super::*;
 
 impl Database {
+    /// Returns every room, along with its participants and shared projects.
+    pub async fn get_room_summaries(&self) -> Result<Vec<RoomSummary>> {
+        self.transaction(|tx| async move {
+            let mut summaries = room::Entity::find()
+                .order_by_asc(room::Column::Id)
+                .all(&*tx)
+                .await?
+                .into_iter()
+                .map(|room| {
+                    (
+                        room.id,
+                        RoomSummary {
+                            id: room.id,
+                            channel_id: room.channel_id,
+                            live_kit_room: room.live_kit_room,
+                            participant_user_ids: Vec::new(),
+                            pending_participant_user_ids: Vec::new(),
+                            project_ids: Vec::new(),
+                        },
+                    )
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            let participants = room_participant::Entity::find()
+                .order_by_asc(room_participant::Column::Id)
+                .all(&*tx)
+                .await?;
+            for participant in participants {
+                if let Some(summary) = summaries.get_mut(&participant.room_id) {
+                    if participant.answering_connection_id.is_some() {
+                        summary.participant_user_ids.push(participant.user_id);
+                    } else {
+                        summary
+                            .pending_participant_user_ids
+                            .push(participant.user_id);
+                    }
+                }
+            }
+
+            let projects = project::Entity::find()
+                .order_by_asc(project::Column::Id)
+                .all(&*tx)
+                .await?;
+            for project in projects {
+                if let Some(summary) = summaries.get_mut(&project.room_id) {
+                    summary.project_ids.push(project.id);
+                }
+            }
+
+            Ok(summaries.into_values().collect())
+        })
+        .await
+    }
+
     /// Clears all room participants in rooms attached to a stale server.
     pub async fn clear_stale_room_participants(
         &self,
@@ -140,6 +194,8 @@ impl Database {
                 id: ActiveValue::NotSet,
                 location_kind: ActiveValue::NotSet,
                 location_project_id: ActiveValue::NotSet,
+                location_worktree_id: ActiveValue::NotSet,
+                location_path: ActiveValue::NotSet,
                 initial_project_id: ActiveValue::NotSet,
             }
             .insert(&*tx)
@@ -195,6 +251,8 @@ impl Database {
                 answering_connection_server_id: ActiveValue::NotSet,
                 location_kind: ActiveValue::NotSet,
                 location_project_id: ActiveValue::NotSet,
+                location_worktree_id: ActiveValue::NotSet,
+                location_path: ActiveValue::NotSet,
             }
             .insert(&*tx)
             .await?;
@@ -445,6 +503,8 @@ impl Database {
             id: ActiveValue::NotSet,
             location_kind: ActiveValue::NotSet,
             location_project_id: ActiveValue::NotSet,
+            location_worktree_id: ActiveValue::NotSet,
+            location_path: ActiveValue::NotSet,
             initial_project_id: ActiveValue::NotSet,
         }])
         .on_conflict(
@@ -964,14 +1024,35 @@ impl Database {
             let tx = tx;
             let location_kind;
             let location_project_id;
+            let mut location_worktree_id = None;
+            let mut location_path = None;
             match location
                 .variant
                 .as_ref()
                 .ok_or_else(|| anyhow!("invalid location"))?
             {
                 proto::participant_location::Variant::SharedProject(project) => {
+                    let project_id = ProjectId::from_proto(project.id);
                     location_kind = 0;
-                    location_project_id = Some(ProjectId::from_proto(project.id));
+                    location_project_id = Some(project_id);
+                    if let Some((worktree_id, path)) =
+                        project.worktree_id.zip(project.path.as_ref())
+                    {
+                        // Don't reveal paths that were excluded when the project was shared,
+                        // nor any path in a project that only invited participants can join.
+                        let project = project::Entity::find_by_id(project_id).one(&*tx).await?;
+                        let reveal_path = match project {
+                            Some(project) if !project.invite_only => !project
+                                .excluded_paths()?
+                                .iter()
+                                .any(|excluded| excluded.is_match(path)),
+                            _ => false,
+                        };
+                        if reveal_path {
+                            location_worktree_id = Some(worktree_id as i64);
+                            location_path = Some(path.clone());
+                        }
+                    }
                 }
                 proto::participant_location::Variant::UnsharedProject(_) => {
                     location_kind = 1;
@@ -999,6 +1080,8 @@ impl Database {
                 .set(room_participant::ActiveModel {
                     location_kind: ActiveValue::set(Some(location_kind)),
                     location_project_id: ActiveValue::set(location_project_id),
+                    location_worktree_id: ActiveValue::set(location_worktree_id),
+                    location_path: ActiveValue::set(location_path),
                     ..Default::default()
                 })
                 .exec(&*tx)
@@ -1239,6 +1322,10 @@ impl Database {
                         Some(proto::participant_location::Variant::SharedProject(
                             proto::participant_location::SharedProject {
                                 id: project_id.to_proto(),
+                                worktree_id: db_participant
+                                    .location_worktree_id
+                                    .map(|id| id as u64),
+                                path: db_participant.location_path.clone(),
                             },
                         ))
                     }
@@ -292,4 +292,63 @@ impl Database {
         })
         .await
     }
+
+    /// Removes the given user from the feature flag.
+    pub async fn remove_user_flag(&self, user: UserId, flag: FlagId) -> Result<()> {
+        self.transaction(|tx| async move {
+            user_feature::Entity::delete_by_id((user, flag))
+                .exec(&*tx)
+                .await?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Returns the feature flag with the given name.
+    pub async fn get_flag_by_name(&self, flag: &str) -> Result<Option<FlagId>> {
+        self.transaction(|tx| async move {
+            Ok(feature_flag::Entity::find()
+                .filter(feature_flag::Column::Flag.eq(flag))
+                .one(&*tx)
+                .await?
+                .map(|flag| flag.id))
+        })
+        .await
+    }
+
+    /// Returns all feature flags, along with the users they're enabled for.
+    pub async fn get_feature_flags(&self) -> Result<Vec<FeatureFlag>> {
+        self.transaction(|tx| async move {
+            let mut flags = feature_flag::Entity::find()
+                .order_by_asc(feature_flag::Column::Id)
+                .all(&*tx)
+                .await?
+                .into_iter()
+                .map(|flag| {
+                    (
+                        flag.id,
+                        FeatureFlag {
+                            id: flag.id,
+                            flag: flag.flag,
+                            user_ids: Vec::new(),
+                        },
+                    )
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            let user_features = user_feature::Entity::find()
+                .order_by_asc(user_feature::Column::UserId)
+                .all(&*tx)
+                .await?;
+            for user_feature in user_features {
+                if let Some(flag) = flags.get_mut(&user_feature.feature_id) {
+                    flag.user_ids.push(user_feature.user_id);
+                }
+            }
+
+            Ok(flags.into_values().collect())
+        })
+        .await
+    }
 }
@@ -7,24 +7,33 @@ pub mod channel_buffer_collaborator;
 pub mod channel_chat_participant;
 pub mod channel_member;
 pub mod channel_message;
+pub mod channel_message_attachment;
+pub mod channel_message_edit;
 pub mod channel_message_mention;
+pub mod channel_message_reaction;
+pub mod comment;
+pub mod comment_mention;
+pub mod comment_thread;
 pub mod contact;
 pub mod contributor;
 pub mod feature_flag;
 pub mod follower;
 pub mod language_server;
+pub mod local_account_invite;
 pub mod notification;
 pub mod notification_kind;
 pub mod observed_buffer_edits;
 pub mod observed_channel_messages;
 pub mod project;
 pub mod project_collaborator;
+pub mod project_invite;
 pub mod room;
 pub mod room_participant;
 pub mod server;
 pub mod signup;
 pub mod user;
 pub mod user_feature;
+pub mod user_identity;
 pub mod worktree;
 pub mod worktree_diagnostic_summary;
 pub mod worktree_entry;
@@ -13,6 +13,7 @@ pub struct Model {
     pub sent_at: PrimitiveDateTime,
     pub nonce: Uuid,
     pub reply_to_message_id: Option<MessageId>,
+    pub edited_at: Option<PrimitiveDateTime>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
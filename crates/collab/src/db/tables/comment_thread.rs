@@ -0,0 +1,60 @@
+use crate::db::{ChannelId, CommentThreadId, UserId};
+use sea_orm::entity::prelude::*;
+use time::PrimitiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "comment_threads")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: CommentThreadId,
+    pub channel_id: Option<ChannelId>,
+    /// Project threads are keyed by the file's location on the host, rather
+    /// than by the project, so that they outlive any one share of the project.
+    pub host_user_id: Option<UserId>,
+    pub worktree_abs_path: Option<String>,
+    pub path: Option<String>,
+    pub epoch: i32,
+    pub start_anchor: Vec<u8>,
+    pub end_anchor: Vec<u8>,
+    pub resolved: bool,
+    pub author_id: UserId,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::channel::Entity",
+        from = "Column::ChannelId",
+        to = "super::channel::Column::Id"
+    )]
+    Channel,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::HostUserId",
+        to = "super::user::Column::Id"
+    )]
+    HostUser,
+    #[sea_orm(has_many = "super::comment::Entity")]
+    Comments,
+}
+
+impl Related<super::channel::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Channel.def()
+    }
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HostUser.def()
+    }
+}
+
+impl Related<super::comment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comments.def()
+    }
+}
@@ -0,0 +1,28 @@
+use crate::db::{LocalAccountInviteId, UserId};
+use sea_orm::entity::prelude::*;
+
+/// An invitation, created by an admin, to create a local account.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "local_account_invites")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: LocalAccountInviteId,
+    pub code: String,
+    pub inviter_id: Option<UserId>,
+    pub email_address: Option<String>,
+    pub admin: bool,
+    pub accepted_user_id: Option<UserId>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::InviterId",
+        to = "super::user::Column::Id"
+    )]
+    Inviter,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
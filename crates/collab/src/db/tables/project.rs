@@ -2,6 +2,7 @@ use crate::db::{ProjectId, Result, RoomId, ServerId, UserId};
 use anyhow::anyhow;
 use rpc::ConnectionId;
 use sea_orm::entity::prelude::*;
+use util::paths::PathMatcher;
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "projects")]
@@ -12,6 +13,10 @@ pub struct Model {
     pub host_user_id: UserId,
     pub host_connection_id: Option<i32>,
     pub host_connection_server_id: Option<ServerId>,
+    pub read_only: bool,
+    pub invite_only: bool,
+    /// A JSON array of the globs that are excluded from the project's worktrees.
+    pub excluded_paths: Option<String>,
 }
 
 impl Model {
@@ -27,6 +32,20 @@ impl Model {
             id: host_connection_id as u32,
         })
     }
+
+    /// Returns matchers for the worktree paths that aren't shared with guests.
+    pub fn excluded_paths(&self) -> Result<Vec<PathMatcher>> {
+        let Some(excluded_paths) = self.excluded_paths.as_deref() else {
+            return Ok(Vec::new());
+        };
+        serde_json::from_str::<Vec<String>>(excluded_paths)?
+            .iter()
+            .map(|glob| {
+                PathMatcher::new(glob)
+                    .map_err(|error| anyhow!("invalid excluded path {glob:?}: {error}").into())
+            })
+            .collect()
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -49,6 +68,8 @@ pub enum Relation {
     Collaborators,
     #[sea_orm(has_many = "super::language_server::Entity")]
     LanguageServers,
+    #[sea_orm(has_many = "super::project_invite::Entity")]
+    Invites,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -81,4 +102,10 @@ impl Related<super::language_server::Entity> for Entity {
     }
 }
 
+impl Related<super::project_invite::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Invites.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -13,6 +13,7 @@ pub struct Model {
     pub user_id: UserId,
     pub replica_id: ReplicaId,
     pub is_host: bool,
+    pub read_only: bool,
 }
 
 impl Model {
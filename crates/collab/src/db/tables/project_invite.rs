@@ -0,0 +1,33 @@
+use crate::db::{ProjectId, ProjectInviteId};
+use sea_orm::entity::prelude::*;
+
+/// An invitation, created by a project's host, to join the project until it expires.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "project_invites")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: ProjectInviteId,
+    pub project_id: ProjectId,
+    pub token: String,
+    pub read_only: bool,
+    pub expires_at: DateTime,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::project::Entity",
+        from = "Column::ProjectId",
+        to = "super::project::Column::Id"
+    )]
+    Project,
+}
+
+impl Related<super::project::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Project.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
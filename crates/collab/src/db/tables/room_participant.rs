@@ -14,6 +14,8 @@ pub struct Model {
     pub answering_connection_lost: bool,
     pub location_kind: Option<i32>,
     pub location_project_id: Option<ProjectId>,
+    pub location_worktree_id: Option<i64>,
+    pub location_path: Option<String>,
     pub initial_project_id: Option<ProjectId>,
     pub calling_user_id: UserId,
     pub calling_connection_id: i32,
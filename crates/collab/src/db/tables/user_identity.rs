@@ -0,0 +1,37 @@
+use crate::db::{UserId, UserIdentityId};
+use sea_orm::entity::prelude::*;
+
+/// An account at an identity provider that can be used to sign in as a user.
+///
+/// Accounts at OpenID Connect providers are keyed by the issuer and the
+/// provider's subject identifier. Local accounts use the `local` provider,
+/// are keyed by username and store a password hash.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_identities")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: UserIdentityId,
+    pub user_id: UserId,
+    pub provider: String,
+    pub subject: String,
+    pub password_hash: Option<String>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,8 +1,10 @@
 mod buffer_tests;
 mod channel_tests;
+mod comment_tests;
 mod contributor_tests;
 mod db_tests;
 mod feature_flag_tests;
+mod identity_tests;
 mod message_tests;
 
 use super::*;
@@ -163,6 +163,36 @@ async fn test_channel_buffers(db: &Arc<Database>) {
         .unwrap();
     assert_eq!(buffer_response_b.base_text, "hello, cruel world");
     assert_eq!(buffer_response_b.operations, &[]);
+
+    // Each snapshot of the notes is kept, and can be retrieved later.
+    let snapshots = db.get_channel_buffer_snapshots(zed_id, b_id).await.unwrap();
+    assert_eq!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot.epoch)
+            .collect::<Vec<_>>(),
+        &[0, 1]
+    );
+    assert!(snapshots
+        .iter()
+        .all(|snapshot| snapshot.timestamp.is_some()));
+    assert_eq!(
+        db.get_channel_buffer_snapshot_text(zed_id, 0, b_id)
+            .await
+            .unwrap(),
+        ""
+    );
+    assert_eq!(
+        db.get_channel_buffer_snapshot_text(zed_id, 1, a_id)
+            .await
+            .unwrap(),
+        "hello, cruel world"
+    );
+    assert!(db.get_channel_buffer_snapshots(zed_id, c_id).await.is_err());
+    assert!(db
+        .get_channel_buffer_snapshot_text(zed_id, 1, c_id)
+        .await
+        .is_err());
 }
 
 test_both_dbs!(
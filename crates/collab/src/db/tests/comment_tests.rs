@@ -0,0 +1,313 @@
+use super::*;
+use crate::test_both_dbs;
+use language::proto::{self, deserialize_anchor, serialize_anchor};
+use text::{Buffer, ToOffset};
+use time::OffsetDateTime;
+
+test_both_dbs!(
+    test_channel_buffer_comments,
+    test_channel_buffer_comments_postgres,
+    test_channel_buffer_comments_sqlite
+);
+
+async fn test_channel_buffer_comments(db: &Arc<Database>) {
+    let a_id = new_test_user(db, "user_a@example.com").await;
+    let b_id = new_test_user(db, "user_b@example.com").await;
+    let c_id = new_test_user(db, "user_c@example.com").await;
+
+    let owner_id = db.create_server("production").await.unwrap().0 as u32;
+    let connection_a = ConnectionId { owner_id, id: 1 };
+    let connection_b = ConnectionId { owner_id, id: 2 };
+    let connection_c = ConnectionId { owner_id, id: 3 };
+
+    let zed_id = db.create_root_channel("zed", a_id).await.unwrap();
+    db.invite_channel_member(zed_id, b_id, a_id, ChannelRole::Member)
+        .await
+        .unwrap();
+    db.respond_to_channel_invite(zed_id, b_id, true)
+        .await
+        .unwrap();
+
+    db.join_channel_buffer(zed_id, a_id, connection_a)
+        .await
+        .unwrap();
+    let mut buffer_a = Buffer::new(0, text::BufferId::new(1).unwrap(), "".to_string());
+    let operation = buffer_a.edit([(0..0, "hello world")]);
+    db.update_channel_buffer(
+        zed_id,
+        a_id,
+        &[proto::serialize_operation(&language::Operation::Buffer(
+            operation,
+        ))],
+    )
+    .await
+    .unwrap();
+
+    // Mentions only notify users who can see the channel.
+    let body = "@user_b @user_c what about this?";
+    let location = CommentLocation::ChannelBuffer(zed_id);
+    let created = db
+        .create_comment_thread(
+            location.clone(),
+            a_id,
+            connection_a,
+            serialize_anchor(&buffer_a.anchor_before(6)),
+            serialize_anchor(&buffer_a.anchor_after(11)),
+            body,
+            &[mention(0..7, b_id), mention(8..15, c_id)],
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.connection_ids, &[connection_a]);
+    assert_eq!(
+        created
+            .notifications
+            .iter()
+            .map(|(user_id, _)| *user_id)
+            .collect::<Vec<_>>(),
+        &[b_id]
+    );
+    let thread_id = CommentThreadId::from_proto(created.thread.id);
+
+    db.create_comment(
+        thread_id,
+        b_id,
+        connection_b,
+        "looks fine",
+        &[],
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+    let resolved = db
+        .set_comment_thread_resolved(thread_id, b_id, connection_b, true)
+        .await
+        .unwrap();
+    assert!(resolved.thread.resolved);
+    assert_eq!(
+        resolved
+            .thread
+            .comments
+            .iter()
+            .map(|comment| (
+                comment.author_id,
+                comment.body.as_str(),
+                comment.mentions.len()
+            ))
+            .collect::<Vec<_>>(),
+        &[
+            (a_id.to_proto(), body, 2),
+            (b_id.to_proto(), "looks fine", 0)
+        ]
+    );
+
+    // Non-members can't read or reply to the thread.
+    assert!(db
+        .get_comment_threads(location.clone(), c_id, connection_c)
+        .await
+        .is_err());
+    assert!(db
+        .create_comment(
+            thread_id,
+            c_id,
+            connection_c,
+            "hi",
+            &[],
+            OffsetDateTime::now_utc()
+        )
+        .await
+        .is_err());
+
+    // Edit the buffer before the thread, then leave so that the buffer is snapshotted.
+    let operation = buffer_a.edit([(0..0, "oh, ")]);
+    db.update_channel_buffer(
+        zed_id,
+        a_id,
+        &[proto::serialize_operation(&language::Operation::Buffer(
+            operation,
+        ))],
+    )
+    .await
+    .unwrap();
+    db.leave_channel_buffer(zed_id, connection_a).await.unwrap();
+
+    // The thread's anchors are valid in the buffer's new epoch.
+    let response = db
+        .join_channel_buffer(zed_id, b_id, connection_b)
+        .await
+        .unwrap();
+    assert_eq!(response.epoch, 1);
+    assert!(response.operations.is_empty());
+    let buffer_b = Buffer::new(1, text::BufferId::new(1).unwrap(), response.base_text);
+    assert_eq!(buffer_b.text(), "oh, hello world");
+
+    let threads = db
+        .get_comment_threads(location, b_id, connection_b)
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    let range = [threads[0].start.clone(), threads[0].end.clone()].map(|anchor| {
+        let mut anchor = deserialize_anchor(anchor.unwrap()).unwrap();
+        if anchor != text::Anchor::MIN && anchor != text::Anchor::MAX {
+            anchor.buffer_id = Some(buffer_b.remote_id());
+        }
+        anchor.to_offset(&buffer_b)
+    });
+    assert_eq!(&buffer_b.text()[range[0]..range[1]], "world");
+}
+
+test_both_dbs!(
+    test_project_comments,
+    test_project_comments_postgres,
+    test_project_comments_sqlite
+);
+
+async fn test_project_comments(db: &Arc<Database>) {
+    let host_id = new_test_user(db, "host@example.com").await;
+    let guest_id = new_test_user(db, "guest@example.com").await;
+
+    let owner_id = db.create_server("production").await.unwrap().0 as u32;
+    let host_connection = ConnectionId { owner_id, id: 1 };
+    let guest_connection = ConnectionId { owner_id, id: 2 };
+
+    let room_id = RoomId::from_proto(
+        db.create_room(host_id, host_connection, "", "test")
+            .await
+            .unwrap()
+            .id,
+    );
+    db.call(room_id, host_id, host_connection, guest_id, None)
+        .await
+        .unwrap();
+    db.join_room(room_id, guest_id, guest_connection, "test")
+        .await
+        .unwrap();
+
+    let worktree = rpc::proto::WorktreeMetadata {
+        id: 1,
+        root_name: "zed".into(),
+        visible: true,
+        abs_path: "/code/zed".into(),
+    };
+    let (project_id, _) = *db
+        .share_project(
+            room_id,
+            host_connection,
+            &[worktree.clone()],
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+    db.join_project(
+        project_id,
+        guest_connection,
+        None,
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap();
+
+    let buffer = Buffer::new(
+        0,
+        text::BufferId::new(1).unwrap(),
+        "fn main() {}".to_string(),
+    );
+    let location = CommentLocation::ProjectPath {
+        project_id,
+        worktree_id: 1,
+        path: "src/main.rs".into(),
+    };
+    let create_thread = |mentions: Vec<rpc::proto::ChatMention>| {
+        let location = location.clone();
+        let (start, end) = (buffer.anchor_before(3), buffer.anchor_after(7));
+        async move {
+            db.create_comment_thread(
+                location,
+                guest_id,
+                guest_connection,
+                serialize_anchor(&start),
+                serialize_anchor(&end),
+                "@host look",
+                &mentions,
+                OffsetDateTime::now_utc(),
+            )
+            .await
+        }
+    };
+
+    // Mentions with backwards ranges are rejected, and duplicate mentions are ignored.
+    assert!(create_thread(vec![mention(5..0, host_id)]).await.is_err());
+    let created = create_thread(vec![mention(0..5, host_id), mention(0..5, host_id)])
+        .await
+        .unwrap();
+    assert_eq!(created.thread.comments[0].mentions.len(), 1);
+    assert_eq!(created.notifications.len(), 1);
+    assert_eq!(created.thread.path.as_deref(), Some("src/main.rs"));
+    let thread_id = CommentThreadId::from_proto(created.thread.id);
+
+    // Threads outlive the project they were created in, and appear again when
+    // the host shares the same worktree in a new project.
+    db.unshare_project(project_id, host_connection)
+        .await
+        .unwrap();
+    let (project_id, _) = *db
+        .share_project(
+            room_id,
+            host_connection,
+            &[rpc::proto::WorktreeMetadata { id: 2, ..worktree }],
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+    let location = CommentLocation::ProjectPath {
+        project_id,
+        worktree_id: 2,
+        path: "src/main.rs".into(),
+    };
+    let threads = db
+        .get_comment_threads(location, host_id, host_connection)
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].id, thread_id.to_proto());
+    assert_eq!(threads[0].project_id, Some(project_id.to_proto()));
+    assert_eq!(threads[0].worktree_id, Some(2));
+
+    let replied = db
+        .create_comment(
+            thread_id,
+            host_id,
+            host_connection,
+            "looks fine",
+            &[],
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(replied.thread.comments.len(), 2);
+    assert_eq!(replied.connection_ids, &[host_connection]);
+
+    // Guests that haven't joined the new project can't see the thread.
+    assert!(db
+        .create_comment(
+            thread_id,
+            guest_id,
+            guest_connection,
+            "hi",
+            &[],
+            OffsetDateTime::now_utc()
+        )
+        .await
+        .is_err());
+}
+
+fn mention(range: std::ops::Range<u64>, user_id: UserId) -> rpc::proto::ChatMention {
+    rpc::proto::ChatMention {
+        range: Some(rpc::proto::Range {
+            start: range.start,
+            end: range.end,
+        }),
+        user_id: user_id.to_proto(),
+    }
+}
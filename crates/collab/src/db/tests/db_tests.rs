@@ -2,8 +2,9 @@ use super::*;
 use crate::test_both_dbs;
 use gpui::TestAppContext;
 use pretty_assertions::{assert_eq, assert_ne};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tests::TestDb;
+use time::OffsetDateTime;
 
 test_both_dbs!(
     test_get_users,
@@ -278,6 +279,14 @@ async fn test_create_access_tokens(db: &Arc<Database>) {
             hash: "h3".into()
         }
     );
+
+    // Revoking a user's access tokens includes the ones they use to impersonate
+    // other users, but not the ones other users hold.
+    assert_eq!(db.delete_access_tokens_for_user(user_2).await.unwrap(), 2);
+    assert!(db.get_access_token(token_6).await.is_err());
+    assert!(db.get_access_token(token_7).await.is_err());
+    assert!(db.get_access_token(token_3).await.is_ok());
+    assert!(db.get_access_token(token_4).await.is_ok());
 }
 
 test_both_dbs!(
@@ -541,20 +550,35 @@ async fn test_project_count(db: &Arc<Database>) {
     .unwrap();
     assert_eq!(db.project_count_excluding_admins().await.unwrap(), 0);
 
-    db.share_project(room_id, ConnectionId { owner_id, id: 1 }, &[])
-        .await
-        .unwrap();
+    db.share_project(
+        room_id,
+        ConnectionId { owner_id, id: 1 },
+        &[],
+        &Default::default(),
+    )
+    .await
+    .unwrap();
     assert_eq!(db.project_count_excluding_admins().await.unwrap(), 1);
 
-    db.share_project(room_id, ConnectionId { owner_id, id: 1 }, &[])
-        .await
-        .unwrap();
+    db.share_project(
+        room_id,
+        ConnectionId { owner_id, id: 1 },
+        &[],
+        &Default::default(),
+    )
+    .await
+    .unwrap();
     assert_eq!(db.project_count_excluding_admins().await.unwrap(), 2);
 
     // Projects shared by admins aren't counted.
-    db.share_project(room_id, ConnectionId { owner_id, id: 0 }, &[])
-        .await
-        .unwrap();
+    db.share_project(
+        room_id,
+        ConnectionId { owner_id, id: 0 },
+        &[],
+        &Default::default(),
+    )
+    .await
+    .unwrap();
     assert_eq!(db.project_count_excluding_admins().await.unwrap(), 2);
 
     db.leave_room(ConnectionId { owner_id, id: 1 })
@@ -563,6 +587,224 @@ async fn test_project_count(db: &Arc<Database>) {
     assert_eq!(db.project_count_excluding_admins().await.unwrap(), 0);
 }
 
+test_both_dbs!(
+    test_project_share_scopes,
+    test_project_share_scopes_postgres,
+    test_project_share_scopes_sqlite
+);
+
+async fn test_project_share_scopes(db: &Arc<Database>) {
+    let owner_id = db.create_server("test").await.unwrap().0 as u32;
+    let host_connection = ConnectionId { owner_id, id: 0 };
+    let guest_connection = ConnectionId { owner_id, id: 1 };
+
+    let host = db
+        .create_user(
+            "host@example.com",
+            false,
+            NewUserParams {
+                github_login: "host".into(),
+                github_user_id: 0,
+            },
+        )
+        .await
+        .unwrap();
+    let guest = db
+        .create_user(
+            "guest@example.com",
+            false,
+            NewUserParams {
+                github_login: "guest".into(),
+                github_user_id: 1,
+            },
+        )
+        .await
+        .unwrap();
+
+    let room_id = RoomId::from_proto(
+        db.create_room(host.user_id, host_connection, "", "test")
+            .await
+            .unwrap()
+            .id,
+    );
+    db.call(room_id, host.user_id, host_connection, guest.user_id, None)
+        .await
+        .unwrap();
+    db.join_room(room_id, guest.user_id, guest_connection, "test")
+        .await
+        .unwrap();
+
+    let worktree = proto::WorktreeMetadata {
+        id: 1,
+        root_name: "a".into(),
+        visible: true,
+        abs_path: "/a".into(),
+    };
+    let invalid_glob = db
+        .share_project(
+            room_id,
+            host_connection,
+            &[worktree.clone()],
+            &proto::ProjectShareScope {
+                excluded_paths: vec!["a/{b".into()],
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(invalid_glob.is_err());
+
+    // Invite-only projects can't be joined without a valid invite.
+    let (project_id, _) = *db
+        .share_project(
+            room_id,
+            host_connection,
+            &[worktree.clone()],
+            &proto::ProjectShareScope {
+                read_only: true,
+                excluded_paths: vec!["secrets".into(), "**/*.pem".into()],
+                invite_only: true,
+            },
+        )
+        .await
+        .unwrap();
+    let now = OffsetDateTime::now_utc();
+    assert!(db
+        .join_project(project_id, guest_connection, None, now)
+        .await
+        .is_err());
+    assert!(db
+        .create_project_invite(
+            project_id,
+            guest_connection,
+            false,
+            now + Duration::from_secs(60)
+        )
+        .await
+        .is_err());
+    let token = db
+        .create_project_invite(
+            project_id,
+            host_connection,
+            false,
+            now + Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+    assert!(db
+        .join_project(project_id, guest_connection, Some("invalid"), now)
+        .await
+        .is_err());
+    assert!(db
+        .join_project(
+            project_id,
+            guest_connection,
+            Some(&token),
+            now + Duration::from_secs(120)
+        )
+        .await
+        .is_err());
+
+    // Entries matching the excluded paths are never stored or sent to guests.
+    let (_, update) = &*db
+        .update_worktree(
+            &proto::UpdateWorktree {
+                project_id: project_id.to_proto(),
+                worktree_id: worktree.id,
+                abs_path: worktree.abs_path.clone(),
+                root_name: worktree.root_name.clone(),
+                updated_entries: [
+                    "secrets",
+                    "secrets/key.txt",
+                    "src",
+                    "src/cert.pem",
+                    "src/main.rs",
+                ]
+                .into_iter()
+                .enumerate()
+                .map(|(id, path)| proto::Entry {
+                    id: id as u64 + 1,
+                    is_dir: !path.contains('.'),
+                    path: path.into(),
+                    ..Default::default()
+                })
+                .collect(),
+                scan_id: 1,
+                is_last_update: true,
+                ..Default::default()
+            },
+            host_connection,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        update
+            .updated_entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>(),
+        ["src", "src/main.rs"]
+    );
+    assert!(db
+        .is_path_excluded_from_project(project_id, "secrets/key.txt")
+        .await
+        .unwrap());
+    assert!(!db
+        .is_path_excluded_from_project(project_id, "src/main.rs")
+        .await
+        .unwrap());
+
+    // The invite lets the guest edit the project, even though it was shared read-only.
+    let (project, _) = &*db
+        .join_project(project_id, guest_connection, Some(&token), now)
+        .await
+        .unwrap();
+    assert!(!project.read_only);
+    assert_eq!(
+        project.worktrees[&worktree.id]
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>(),
+        ["src", "src/main.rs"]
+    );
+    db.host_for_mutating_project_request(project_id, guest_connection)
+        .await
+        .unwrap();
+
+    // Guests that join read-only projects without an invite can't edit them.
+    let (project_id, _) = *db
+        .share_project(
+            room_id,
+            host_connection,
+            &[worktree.clone()],
+            &proto::ProjectShareScope {
+                read_only: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let (project, _) = &*db
+        .join_project(project_id, guest_connection, None, now)
+        .await
+        .unwrap();
+    assert!(project.read_only);
+    db.host_for_read_only_project_request(project_id, guest_connection)
+        .await
+        .unwrap();
+    assert!(db
+        .host_for_mutating_project_request(project_id, guest_connection)
+        .await
+        .is_err());
+    assert!(db
+        .project_collaborators_for_buffer_update(project_id, guest_connection, true)
+        .await
+        .is_err());
+    db.project_collaborators_for_buffer_update(project_id, guest_connection, false)
+        .await
+        .unwrap();
+}
+
 #[test]
 fn test_fuzzy_like_string() {
     assert_eq!(Database::fuzzy_like_string("abcd"), "%a%b%c%d%");
@@ -1,5 +1,5 @@
 use crate::{
-    db::{Database, NewUserParams},
+    db::{Database, FeatureFlag, NewUserParams},
     test_both_dbs,
 };
 use std::sync::Arc;
@@ -56,3 +56,53 @@ async fn test_get_user_flags(db: &Arc<Database>) {
     user_2_flags.sort();
     assert_eq!(user_2_flags, &[CHANNELS_ALPHA]);
 }
+
+test_both_dbs!(
+    test_manage_feature_flags,
+    test_manage_feature_flags_postgres,
+    test_manage_feature_flags_sqlite
+);
+
+async fn test_manage_feature_flags(db: &Arc<Database>) {
+    let user_1 = db
+        .create_user(
+            "user1@example.com",
+            false,
+            NewUserParams {
+                github_login: "user1".into(),
+                github_user_id: 1,
+            },
+        )
+        .await
+        .unwrap()
+        .user_id;
+
+    let channels_flag = db.create_user_flag("channels-alpha").await.unwrap();
+    let search_flag = db.create_user_flag("new-search").await.unwrap();
+    assert_eq!(
+        db.get_flag_by_name("new-search").await.unwrap(),
+        Some(search_flag)
+    );
+    assert_eq!(db.get_flag_by_name("unknown").await.unwrap(), None);
+
+    db.add_user_flag(user_1, channels_flag).await.unwrap();
+    db.add_user_flag(user_1, search_flag).await.unwrap();
+    db.remove_user_flag(user_1, channels_flag).await.unwrap();
+    assert_eq!(db.get_user_flags(user_1).await.unwrap(), &["new-search"]);
+
+    assert_eq!(
+        db.get_feature_flags().await.unwrap(),
+        &[
+            FeatureFlag {
+                id: channels_flag,
+                flag: "channels-alpha".into(),
+                user_ids: vec![],
+            },
+            FeatureFlag {
+                id: search_flag,
+                flag: "new-search".into(),
+                user_ids: vec![user_1],
+            },
+        ]
+    );
+}
@@ -0,0 +1,102 @@
+use super::*;
+use crate::test_both_dbs;
+
+test_both_dbs!(
+    test_user_identities,
+    test_user_identities_postgres,
+    test_user_identities_sqlite
+);
+
+async fn test_user_identities(db: &Arc<Database>) {
+    let existing_user_id = new_test_user(db, "alice@example.com").await;
+
+    // Signing in with an unknown account creates a user, whose login is
+    // disambiguated from existing ones.
+    let alice = db
+        .get_or_create_user_by_identity(
+            "https://idp.example.com",
+            "subject-1",
+            "alice",
+            Some("alice@corp.example.com"),
+        )
+        .await
+        .unwrap();
+    assert_ne!(alice.id, existing_user_id);
+    assert_eq!(alice.github_login, "alice2");
+    assert_eq!(
+        alice.email_address.as_deref(),
+        Some("alice@corp.example.com")
+    );
+    assert!(!alice.admin);
+
+    // Signing in again with the same account returns the same user.
+    let alice_again = db
+        .get_or_create_user_by_identity("https://idp.example.com", "subject-1", "alicia", None)
+        .await
+        .unwrap();
+    assert_eq!(alice_again.id, alice.id);
+    assert_eq!(alice_again.github_login, "alice2");
+
+    // Subjects are scoped to their provider.
+    let other = db
+        .get_or_create_user_by_identity("https://other.example.com", "subject-1", "bob", None)
+        .await
+        .unwrap();
+    assert_ne!(other.id, alice.id);
+}
+
+test_both_dbs!(
+    test_local_account_invites,
+    test_local_account_invites_postgres,
+    test_local_account_invites_sqlite
+);
+
+async fn test_local_account_invites(db: &Arc<Database>) {
+    let admin_id = new_test_user(db, "admin@example.com").await;
+
+    let code = db
+        .create_local_account_invite(Some(admin_id), Some("carol@example.com"), true)
+        .await
+        .unwrap();
+    let invite = db
+        .get_pending_local_account_invite(&code)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(invite.inviter_id, Some(admin_id));
+    assert!(db
+        .get_pending_local_account_invite("not-a-code")
+        .await
+        .unwrap()
+        .is_none());
+    assert!(db.get_local_account("carol").await.unwrap().is_none());
+
+    // Usernames can't collide with existing logins.
+    assert!(db
+        .accept_local_account_invite(&code, "admin", "hash")
+        .await
+        .is_err());
+
+    let carol = db
+        .accept_local_account_invite(&code, "carol", "hash")
+        .await
+        .unwrap();
+    assert_eq!(carol.github_login, "carol");
+    assert_eq!(carol.email_address.as_deref(), Some("carol@example.com"));
+    assert!(carol.admin);
+
+    let (user, password_hash) = db.get_local_account("carol").await.unwrap().unwrap();
+    assert_eq!(user.id, carol.id);
+    assert_eq!(password_hash, "hash");
+
+    // Invites can only be used once.
+    assert!(db
+        .get_pending_local_account_invite(&code)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(db
+        .accept_local_account_invite(&code, "carol2", "hash")
+        .await
+        .is_err());
+}
@@ -33,6 +33,7 @@ async fn test_channel_message_retrieval(db: &Arc<Database>) {
                 OffsetDateTime::now_utc(),
                 i,
                 None,
+                &[],
             )
             .await
             .unwrap()
@@ -108,6 +109,7 @@ async fn test_channel_message_nonces(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             100,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -121,6 +123,7 @@ async fn test_channel_message_nonces(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             200,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -134,6 +137,7 @@ async fn test_channel_message_nonces(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             100,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -147,6 +151,7 @@ async fn test_channel_message_nonces(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             200,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -163,6 +168,7 @@ async fn test_channel_message_nonces(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             100,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -245,6 +251,7 @@ async fn test_unseen_channel_messages(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             1,
             None,
+            &[],
         )
         .await
         .unwrap();
@@ -258,6 +265,7 @@ async fn test_unseen_channel_messages(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             2,
             None,
+            &[],
         )
         .await
         .unwrap();
@@ -271,6 +279,7 @@ async fn test_unseen_channel_messages(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             3,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -289,6 +298,7 @@ async fn test_unseen_channel_messages(db: &Arc<Database>) {
             OffsetDateTime::now_utc(),
             4,
             None,
+            &[],
         )
         .await
         .unwrap()
@@ -356,6 +366,7 @@ async fn test_channel_message_mentions(db: &Arc<Database>) {
         OffsetDateTime::now_utc(),
         1,
         None,
+        &[],
     )
     .await
     .unwrap();
@@ -367,6 +378,7 @@ async fn test_channel_message_mentions(db: &Arc<Database>) {
         OffsetDateTime::now_utc(),
         2,
         None,
+        &[],
     )
     .await
     .unwrap();
@@ -378,6 +390,7 @@ async fn test_channel_message_mentions(db: &Arc<Database>) {
         OffsetDateTime::now_utc(),
         3,
         None,
+        &[],
     )
     .await
     .unwrap();
@@ -389,6 +402,7 @@ async fn test_channel_message_mentions(db: &Arc<Database>) {
         OffsetDateTime::now_utc(),
         4,
         None,
+        &[],
     )
     .await
     .unwrap();
@@ -419,3 +433,141 @@ async fn test_channel_message_mentions(db: &Arc<Database>) {
         ]
     );
 }
+
+test_both_dbs!(
+    test_channel_message_edits_reactions_and_threads,
+    test_channel_message_edits_reactions_and_threads_postgres,
+    test_channel_message_edits_reactions_and_threads_sqlite
+);
+
+async fn test_channel_message_edits_reactions_and_threads(db: &Arc<Database>) {
+    let user_a = new_test_user(db, "user_a@example.com").await;
+    let user_b = new_test_user(db, "user_b@example.com").await;
+    let channel = db.create_root_channel("channel", user_a).await.unwrap();
+    db.invite_channel_member(channel, user_b, user_a, ChannelRole::Member)
+        .await
+        .unwrap();
+    db.respond_to_channel_invite(channel, user_b, true)
+        .await
+        .unwrap();
+
+    let owner_id = db.create_server("test").await.unwrap().0 as u32;
+    db.join_channel_chat(channel, rpc::ConnectionId { owner_id, id: 0 }, user_a)
+        .await
+        .unwrap();
+    db.join_channel_chat(channel, rpc::ConnectionId { owner_id, id: 1 }, user_b)
+        .await
+        .unwrap();
+
+    let attachment = rpc::proto::ChatAttachment {
+        project_id: None,
+        worktree_id: 1,
+        path: "src/main.rs".into(),
+        start_row: 3,
+        end_row: 5,
+        language: Some("Rust".into()),
+        text: "fn main() {\n}\n".into(),
+    };
+    let root_id = db
+        .create_channel_message(
+            channel,
+            user_a,
+            "look at this",
+            &[],
+            OffsetDateTime::now_utc(),
+            1,
+            None,
+            &[attachment.clone()],
+        )
+        .await
+        .unwrap()
+        .message_id;
+    let reply_id = db
+        .create_channel_message(
+            channel,
+            user_b,
+            "looks good",
+            &[],
+            OffsetDateTime::now_utc(),
+            2,
+            Some(root_id),
+            &[],
+        )
+        .await
+        .unwrap()
+        .message_id;
+
+    // Only the sender can edit a message. Editing notifies newly-mentioned users.
+    db.update_channel_message(
+        channel,
+        root_id,
+        user_b,
+        "hijacked",
+        &[],
+        OffsetDateTime::now_utc(),
+    )
+    .await
+    .unwrap_err();
+    let updated = db
+        .update_channel_message(
+            channel,
+            root_id,
+            user_a,
+            "look at this @user_b",
+            &mentions_to_proto(&[(13..20, user_b.to_proto())]),
+            OffsetDateTime::now_utc(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.message.body, "look at this @user_b");
+    assert!(updated.message.edited_at.is_some());
+    assert_eq!(updated.participant_connection_ids.len(), 2);
+    assert_eq!(updated.notifications.len(), 1);
+    assert_eq!(updated.notifications[0].0, user_b);
+
+    let edits = db
+        .get_channel_message_edits(channel, root_id, user_b)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|edit| edit.body)
+        .collect::<Vec<_>>();
+    assert_eq!(edits, &["look at this"]);
+
+    // Reacting twice with the same emoji removes the reaction.
+    db.toggle_channel_message_reaction(channel, root_id, user_a, "👍")
+        .await
+        .unwrap();
+    db.toggle_channel_message_reaction(channel, root_id, user_b, "👍")
+        .await
+        .unwrap();
+    db.toggle_channel_message_reaction(channel, root_id, user_b, "🎉")
+        .await
+        .unwrap();
+    let updated = db
+        .toggle_channel_message_reaction(channel, root_id, user_b, "🎉")
+        .await
+        .unwrap();
+    assert_eq!(
+        updated.message.reactions,
+        &[rpc::proto::ChatReaction {
+            emoji: "👍".into(),
+            user_ids: vec![user_a.to_proto(), user_b.to_proto()],
+        }]
+    );
+
+    let thread = db
+        .get_channel_message_thread(channel, root_id, user_b)
+        .await
+        .unwrap();
+    assert_eq!(
+        thread
+            .iter()
+            .map(|message| MessageId::from_proto(message.id))
+            .collect::<Vec<_>>(),
+        &[root_id, reply_id]
+    );
+    assert_eq!(thread[0].reply_count, 1);
+    assert_eq!(thread[0].attachments, &[attachment]);
+    assert_eq!(thread[1].reply_to_message_id, Some(root_id.to_proto()));
+}
@@ -101,12 +101,24 @@ pub struct Config {
     pub rust_log: Option<String>,
     pub log_json: Option<bool>,
     pub zed_environment: Arc<str>,
+    /// The URL at which browsers can reach this server, used when redirecting
+    /// back to it from an identity provider.
+    pub public_url: Option<String>,
+    pub oidc_issuer_url: Option<String>,
+    pub oidc_client_id: Option<String>,
+    pub oidc_client_secret: Option<String>,
+    pub oidc_display_name: Option<String>,
+    pub local_accounts: Option<bool>,
 }
 
 impl Config {
     pub fn is_development(&self) -> bool {
         self.zed_environment == "development".into()
     }
+
+    pub fn local_accounts_enabled(&self) -> bool {
+        self.local_accounts.unwrap_or(false)
+    }
 }
 
 #[derive(Default, Deserialize)]
@@ -118,6 +130,8 @@ pub struct MigrateConfig {
 pub struct AppState {
     pub db: Arc<Database>,
     pub live_kit_client: Option<Arc<dyn live_kit_server::api::Client>>,
+    pub oidc: Option<Arc<auth::OidcProvider>>,
+    pub sign_in_throttle: auth::SignInThrottle,
     pub config: Config,
 }
 
@@ -143,9 +157,38 @@ impl AppState {
             None
         };
 
+        let oidc = if let Some((issuer_url, client_id)) = config
+            .oidc_issuer_url
+            .as_ref()
+            .zip(config.oidc_client_id.as_ref())
+        {
+            if config.public_url.is_none() {
+                Err(anyhow::anyhow!(
+                    "PUBLIC_URL must be set when an OIDC provider is configured"
+                ))?;
+            }
+            let client = auth::HttpOidcClient::discover(
+                issuer_url.clone(),
+                client_id.clone(),
+                config.oidc_client_secret.clone(),
+            )
+            .await?;
+            Some(Arc::new(auth::OidcProvider::new(
+                Arc::new(client),
+                config
+                    .oidc_display_name
+                    .clone()
+                    .unwrap_or_else(|| "Single sign-on".to_string()),
+            )))
+        } else {
+            None
+        };
+
         let this = Self {
             db: Arc::new(db),
             live_kit_client,
+            oidc,
+            sign_in_throttle: Default::default(),
             config,
         };
         Ok(Arc::new(this))
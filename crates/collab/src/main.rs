@@ -51,6 +51,7 @@ async fn main() -> Result<()> {
             rpc_server.start().await?;
 
             let app = collab::api::routes(rpc_server.clone(), state.clone())
+                .merge(collab::auth::routes(state.clone()))
                 .merge(collab::rpc::routes(rpc_server.clone()))
                 .merge(
                     Router::new()
@@ -3,9 +3,10 @@ mod connection_pool;
 use crate::{
     auth::{self, Impersonator},
     db::{
-        self, BufferId, ChannelId, ChannelRole, ChannelsForUser, CreatedChannelMessage, Database,
-        InviteMemberResult, MembershipUpdated, MessageId, NotificationId, ProjectId,
-        RemoveChannelMemberResult, RespondToChannelInvite, RoomId, ServerId, User, UserId,
+        self, BufferId, ChannelId, ChannelRole, ChannelsForUser, CommentLocation, CommentThreadId,
+        CreatedChannelMessage, Database, InviteMemberResult, MembershipUpdated, MessageId,
+        NotificationId, ProjectId, RemoveChannelMemberResult, RespondToChannelInvite, RoomId,
+        ServerId, UpdatedChannelMessage, UpdatedCommentThread, User, UserId,
     },
     executor::Executor,
     AppState, Error, Result,
@@ -36,7 +37,10 @@ use futures::{
     FutureExt, SinkExt, StreamExt, TryStreamExt,
 };
 use lazy_static::lazy_static;
-use prometheus::{register_int_gauge, IntGauge};
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, HistogramVec,
+    IntCounterVec, IntGauge,
+};
 use rpc::{
     proto::{
         self, Ack, AnyTypedEnvelope, EntityMessage, EnvelopedMessage, LiveKitConnectionInfo,
@@ -71,7 +75,11 @@ pub const CLEANUP_TIMEOUT: Duration = Duration::from_secs(10);
 
 const MESSAGE_COUNT_PER_PAGE: usize = 100;
 const MAX_MESSAGE_LEN: usize = 1024;
+const MAX_ATTACHMENTS_PER_MESSAGE: usize = 4;
+const MAX_ATTACHMENT_LEN: usize = 8 * 1024;
+const MAX_REACTION_LEN: usize = 32;
 const NOTIFICATION_COUNT_PER_PAGE: usize = 50;
+const MAX_PROJECT_INVITE_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
 lazy_static! {
     static ref METRIC_CONNECTIONS: IntGauge =
@@ -81,6 +89,30 @@ lazy_static! {
         "number of open projects with one or more guests"
     )
     .unwrap();
+    static ref METRIC_CONNECTED_USERS: IntGauge = register_int_gauge!(
+        "connected_users",
+        "number of users with one or more connections"
+    )
+    .unwrap();
+    static ref METRIC_MESSAGE_DURATION: HistogramVec = register_histogram_vec!(
+        "rpc_message_duration_seconds",
+        "time spent handling rpc messages, by message type",
+        &["message_type"]
+    )
+    .unwrap();
+    static ref METRIC_MESSAGE_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "rpc_message_errors",
+        "number of rpc messages whose handler failed, by message type",
+        &["message_type"]
+    )
+    .unwrap();
+    static ref METRIC_DB_POOL_CONNECTIONS: IntGauge =
+        register_int_gauge!("db_pool_connections", "number of open database connections").unwrap();
+    static ref METRIC_DB_POOL_IDLE_CONNECTIONS: IntGauge = register_int_gauge!(
+        "db_pool_idle_connections",
+        "number of idle database connections"
+    )
+    .unwrap();
 }
 
 type MessageHandler =
@@ -110,6 +142,9 @@ struct Session {
     peer: Arc<Peer>,
     connection_pool: Arc<parking_lot::Mutex<ConnectionPool>>,
     live_kit_client: Option<Arc<dyn live_kit_server::api::Client>>,
+    /// Buffers at excluded paths that this host tried to send to guests,
+    /// whose remaining chunks are dropped too.
+    excluded_buffers: Arc<parking_lot::Mutex<HashSet<(ProjectId, u64)>>>,
     _executor: Executor,
 }
 
@@ -223,6 +258,7 @@ impl Server {
             .add_request_handler(share_project)
             .add_message_handler(unshare_project)
             .add_request_handler(join_project)
+            .add_request_handler(create_project_invite)
             .add_message_handler(leave_project)
             .add_request_handler(update_project)
             .add_request_handler(update_worktree)
@@ -241,7 +277,7 @@ impl Server {
             .add_request_handler(forward_read_only_project_request::<proto::OpenBufferById>)
             .add_request_handler(forward_read_only_project_request::<proto::SynchronizeBuffers>)
             .add_request_handler(forward_read_only_project_request::<proto::InlayHints>)
-            .add_request_handler(forward_read_only_project_request::<proto::OpenBufferByPath>)
+            .add_request_handler(open_buffer_by_path)
             .add_request_handler(forward_mutating_project_request::<proto::GetCompletions>)
             .add_request_handler(
                 forward_mutating_project_request::<proto::ApplyCompletionAdditionalEdits>,
@@ -259,9 +295,15 @@ impl Server {
             .add_request_handler(forward_mutating_project_request::<proto::RenameProjectEntry>)
             .add_request_handler(forward_mutating_project_request::<proto::CopyProjectEntry>)
             .add_request_handler(forward_mutating_project_request::<proto::DeleteProjectEntry>)
+            .add_request_handler(forward_mutating_project_request::<proto::TrashProjectEntry>)
+            .add_request_handler(forward_mutating_project_request::<proto::RestoreProjectEntry>)
             .add_request_handler(forward_mutating_project_request::<proto::ExpandProjectEntry>)
             .add_request_handler(forward_mutating_project_request::<proto::OnTypeFormatting>)
             .add_request_handler(forward_mutating_project_request::<proto::SaveBuffer>)
+            .add_request_handler(forward_read_only_project_request::<proto::OpenTerminal>)
+            .add_request_handler(forward_mutating_project_request::<proto::ResizeTerminal>)
+            .add_request_handler(forward_read_only_project_request::<proto::CloseTerminal>)
+            .add_request_handler(forward_mutating_project_request::<proto::TerminalInput>)
             .add_message_handler(create_buffer_for_peer)
             .add_request_handler(update_buffer)
             .add_message_handler(broadcast_project_message_from_host::<proto::RefreshInlayHints>)
@@ -269,6 +311,8 @@ impl Server {
             .add_message_handler(broadcast_project_message_from_host::<proto::BufferReloaded>)
             .add_message_handler(broadcast_project_message_from_host::<proto::BufferSaved>)
             .add_message_handler(broadcast_project_message_from_host::<proto::UpdateDiffBase>)
+            .add_message_handler(broadcast_project_message_from_host::<proto::UpdateTerminal>)
+            .add_message_handler(broadcast_project_message_from_host::<proto::RemoveTerminal>)
             .add_request_handler(get_users)
             .add_request_handler(fuzzy_search_users)
             .add_request_handler(request_contact)
@@ -285,6 +329,8 @@ impl Server {
             .add_request_handler(leave_channel_buffer)
             .add_message_handler(update_channel_buffer)
             .add_request_handler(rejoin_channel_buffers)
+            .add_request_handler(get_channel_buffer_snapshots)
+            .add_request_handler(get_channel_buffer_snapshot)
             .add_request_handler(get_channel_members)
             .add_request_handler(respond_to_channel_invite)
             .add_request_handler(join_channel)
@@ -297,6 +343,14 @@ impl Server {
             .add_request_handler(remove_channel_message)
             .add_request_handler(get_channel_messages)
             .add_request_handler(get_channel_messages_by_id)
+            .add_request_handler(update_channel_message)
+            .add_request_handler(toggle_channel_message_reaction)
+            .add_request_handler(get_channel_message_edits)
+            .add_request_handler(get_channel_message_thread)
+            .add_request_handler(get_comment_threads)
+            .add_request_handler(create_comment_thread)
+            .add_request_handler(create_comment)
+            .add_request_handler(set_comment_thread_resolved)
             .add_request_handler(get_notifications)
             .add_request_handler(mark_notification_as_read)
             .add_request_handler(move_channel)
@@ -500,13 +554,21 @@ impl Server {
                         "message received"
                     );
                 });
+                let payload_type = envelope.payload_type_name();
                 let start_time = Instant::now();
                 let future = (handler)(*envelope, session);
                 async move {
                     let result = future.await;
-                    let duration_ms = start_time.elapsed().as_micros() as f64 / 1000.0;
+                    let duration = start_time.elapsed();
+                    let duration_ms = duration.as_micros() as f64 / 1000.0;
+                    METRIC_MESSAGE_DURATION
+                        .with_label_values(&[payload_type])
+                        .observe(duration.as_secs_f64());
                     match result {
                         Err(error) => {
+                            METRIC_MESSAGE_ERRORS
+                                .with_label_values(&[payload_type])
+                                .inc();
                             tracing::error!(%error, ?duration_ms, "error handling message")
                         }
                         Ok(()) => tracing::info!(?duration_ms, "finished handling message"),
@@ -640,6 +702,7 @@ impl Server {
                 peer: this.peer.clone(),
                 connection_pool: this.connection_pool.clone(),
                 live_kit_client: this.app_state.live_kit_client.clone(),
+                excluded_buffers: Default::default(),
                 _executor: executor.clone()
             };
             update_user_contacts(user_id, &session).await?;
@@ -765,6 +828,20 @@ impl Server {
         Ok(())
     }
 
+    /// Closes all of the user's connections, returning how many were closed. Their
+    /// clients will try to reconnect, re-authenticating as they do.
+    pub fn disconnect_user(&self, user_id: UserId) -> usize {
+        let connection_ids = self
+            .connection_pool
+            .lock()
+            .user_connection_ids(user_id)
+            .collect::<Vec<_>>();
+        for connection_id in &connection_ids {
+            self.peer.disconnect(*connection_id);
+        }
+        connection_ids.len()
+    }
+
     pub async fn snapshot<'a>(self: &'a Arc<Self>) -> ServerSnapshot<'a> {
         ServerSnapshot {
             connection_pool: ConnectionPoolGuard {
@@ -932,14 +1009,18 @@ pub async fn handle_websocket_request(
 }
 
 pub async fn handle_metrics(Extension(server): Extension<Arc<Server>>) -> Result<String> {
-    let connections = server
-        .connection_pool
-        .lock()
-        .connections()
-        .filter(|connection| !connection.admin)
-        .count();
+    let (connections, connected_users) = {
+        let pool = server.connection_pool.lock();
+        (pool.connection_count(), pool.connected_user_count())
+    };
 
     METRIC_CONNECTIONS.set(connections as _);
+    METRIC_CONNECTED_USERS.set(connected_users as _);
+
+    if let Some(pool_stats) = server.app_state.db.pool_stats() {
+        METRIC_DB_POOL_CONNECTIONS.set(pool_stats.connections as _);
+        METRIC_DB_POOL_IDLE_CONNECTIONS.set(pool_stats.idle_connections as _);
+    }
 
     let shared_projects = server.app_state.db.project_count_excluding_admins().await?;
     METRIC_SHARED_PROJECTS.set(shared_projects as _);
@@ -1548,6 +1629,7 @@ async fn share_project(
             RoomId::from_proto(request.room_id),
             session.connection_id,
             &request.worktrees,
+            &request.scope.unwrap_or_default(),
         )
         .await?;
     response.send(proto::ShareProjectResponse {
@@ -1592,7 +1674,12 @@ async fn join_project(
     let (project, replica_id) = &mut *session
         .db()
         .await
-        .join_project(project_id, session.connection_id)
+        .join_project(
+            project_id,
+            session.connection_id,
+            request.invite_token.as_deref(),
+            OffsetDateTime::now_utc(),
+        )
         .await?;
 
     let collaborators = project
@@ -1636,6 +1723,7 @@ async fn join_project(
         replica_id: replica_id.0 as u32,
         collaborators: collaborators.clone(),
         language_servers: project.language_servers.clone(),
+        read_only: project.read_only,
     })?;
 
     for (worktree_id, worktree) in mem::take(&mut project.worktrees) {
@@ -1704,6 +1792,41 @@ async fn join_project(
     Ok(())
 }
 
+/// Create an invite that lets room participants join a project until it expires.
+async fn create_project_invite(
+    request: proto::CreateProjectInvite,
+    response: Response<proto::CreateProjectInvite>,
+    session: Session,
+) -> Result<()> {
+    let project_id = ProjectId::from_proto(request.project_id);
+    if request.expires_in_seconds == 0 {
+        return Err(anyhow!("project invites must not expire immediately"))?;
+    }
+    let expires_in =
+        Duration::from_secs(request.expires_in_seconds).min(MAX_PROJECT_INVITE_DURATION);
+    let expires_at = OffsetDateTime::now_utc()
+        .checked_add(expires_in.try_into()?)
+        .ok_or_else(|| anyhow!("invalid project invite expiration"))?;
+    let token = session
+        .db()
+        .await
+        .create_project_invite(
+            project_id,
+            session.connection_id,
+            request.read_only,
+            expires_at,
+        )
+        .await?;
+    response.send(proto::CreateProjectInviteResponse {
+        token,
+        expires_at: Some(proto::Timestamp {
+            seconds: expires_at.unix_timestamp() as u64,
+            nanos: expires_at.nanosecond(),
+        }),
+    })?;
+    Ok(())
+}
+
 /// Leave someone elses shared project.
 async fn leave_project(request: proto::LeaveProject, session: Session) -> Result<()> {
     let sender_id = session.connection_id;
@@ -1760,7 +1883,7 @@ async fn update_worktree(
     response: Response<proto::UpdateWorktree>,
     session: Session,
 ) -> Result<()> {
-    let guest_connection_ids = session
+    let (guest_connection_ids, update) = &*session
         .db()
         .await
         .update_worktree(&request, session.connection_id)
@@ -1772,7 +1895,7 @@ async fn update_worktree(
         |connection_id| {
             session
                 .peer
-                .forward_send(session.connection_id, connection_id, request.clone())
+                .forward_send(session.connection_id, connection_id, update.clone())
         },
     );
     response.send(proto::Ack {})?;
@@ -1897,6 +2020,25 @@ where
     Ok(())
 }
 
+/// Forward a request to open a buffer to the host, unless the buffer's path
+/// is excluded from the project.
+async fn open_buffer_by_path(
+    request: proto::OpenBufferByPath,
+    response: Response<proto::OpenBufferByPath>,
+    session: Session,
+) -> Result<()> {
+    let project_id = ProjectId::from_proto(request.project_id);
+    if session
+        .db()
+        .await
+        .is_path_excluded_from_project(project_id, &request.path)
+        .await?
+    {
+        Err(ErrorCode::UnsharedItem.anyhow())?;
+    }
+    forward_read_only_project_request(request, response, session).await
+}
+
 /// forward a project request to the host. These requests are disallowed
 /// for guests.
 async fn forward_mutating_project_request<T>(
@@ -1921,19 +2063,46 @@ where
     Ok(())
 }
 
-/// Notify other participants that a new buffer has been created
+/// Notify other participants that a new buffer has been created, unless the
+/// buffer's path is excluded from the project.
 async fn create_buffer_for_peer(
     request: proto::CreateBufferForPeer,
     session: Session,
 ) -> Result<()> {
-    session
-        .db()
-        .await
-        .check_user_is_project_host(
-            ProjectId::from_proto(request.project_id),
-            session.connection_id,
-        )
-        .await?;
+    let project_id = ProjectId::from_proto(request.project_id);
+    {
+        // Messages from the same connection take the database lock in order,
+        // so a buffer's state is always checked before its chunks.
+        let db = session.db().await;
+        db.check_user_is_project_host(project_id, session.connection_id)
+            .await?;
+        match &request.variant {
+            Some(proto::create_buffer_for_peer::Variant::State(state)) => {
+                if let Some(file) = &state.file {
+                    if db
+                        .is_path_excluded_from_project(project_id, &file.path)
+                        .await?
+                    {
+                        session
+                            .excluded_buffers
+                            .lock()
+                            .insert((project_id, state.id));
+                        return Ok(());
+                    }
+                }
+            }
+            Some(proto::create_buffer_for_peer::Variant::Chunk(chunk)) => {
+                if session
+                    .excluded_buffers
+                    .lock()
+                    .contains(&(project_id, chunk.buffer_id))
+                {
+                    return Ok(());
+                }
+            }
+            None => {}
+        }
+    }
     let peer_id = request.peer_id.ok_or_else(|| anyhow!("invalid peer id"))?;
     session
         .peer
@@ -3044,6 +3213,40 @@ async fn leave_channel_buffer(
     Ok(())
 }
 
+/// List the snapshots that were taken of the channel notes
+async fn get_channel_buffer_snapshots(
+    request: proto::GetChannelBufferSnapshots,
+    response: Response<proto::GetChannelBufferSnapshots>,
+    session: Session,
+) -> Result<()> {
+    let snapshots = session
+        .db()
+        .await
+        .get_channel_buffer_snapshots(ChannelId::from_proto(request.channel_id), session.user_id)
+        .await?;
+    response.send(proto::GetChannelBufferSnapshotsResponse { snapshots })?;
+    Ok(())
+}
+
+/// Retrieve the text of the channel notes as of a snapshot
+async fn get_channel_buffer_snapshot(
+    request: proto::GetChannelBufferSnapshot,
+    response: Response<proto::GetChannelBufferSnapshot>,
+    session: Session,
+) -> Result<()> {
+    let text = session
+        .db()
+        .await
+        .get_channel_buffer_snapshot_text(
+            ChannelId::from_proto(request.channel_id),
+            request.epoch as i32,
+            session.user_id,
+        )
+        .await?;
+    response.send(proto::GetChannelBufferSnapshotResponse { text })?;
+    Ok(())
+}
+
 fn channel_buffer_updated<T: EnvelopedMessage>(
     sender_id: ConnectionId,
     collaborators: impl IntoIterator<Item = ConnectionId>,
@@ -3095,6 +3298,17 @@ async fn send_channel_message(
 
     // TODO: adjust mentions if body is trimmed
 
+    if request.attachments.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+        return Err(anyhow!("too many attachments"))?;
+    }
+    if request
+        .attachments
+        .iter()
+        .any(|attachment| attachment.text.len() > MAX_ATTACHMENT_LEN)
+    {
+        return Err(anyhow!("attachment is too long"))?;
+    }
+
     let timestamp = OffsetDateTime::now_utc();
     let nonce = request
         .nonce
@@ -3120,6 +3334,7 @@ async fn send_channel_message(
                 Some(reply_to_message_id) => Some(MessageId::from_proto(reply_to_message_id)),
                 None => None,
             },
+            &request.attachments,
         )
         .await?;
     let message = proto::ChannelMessage {
@@ -3130,6 +3345,10 @@ async fn send_channel_message(
         timestamp: timestamp.unix_timestamp() as u64,
         nonce: Some(nonce),
         reply_to_message_id: request.reply_to_message_id,
+        edited_at: None,
+        reactions: Vec::new(),
+        attachments: request.attachments,
+        reply_count: 0,
     };
     broadcast(
         Some(session.connection_id),
@@ -3310,6 +3529,295 @@ async fn get_channel_messages_by_id(
     Ok(())
 }
 
+/// Edit the body of a channel message
+async fn update_channel_message(
+    request: proto::UpdateChannelMessage,
+    response: Response<proto::UpdateChannelMessage>,
+    session: Session,
+) -> Result<()> {
+    // Mention offsets refer to the untrimmed body, so it is stored as-is.
+    if request.body.len() > MAX_MESSAGE_LEN {
+        return Err(anyhow!("message is too long"))?;
+    }
+    if request.body.trim().is_empty() {
+        return Err(anyhow!("message can't be blank"))?;
+    }
+
+    let channel_id = ChannelId::from_proto(request.channel_id);
+    let updated = session
+        .db()
+        .await
+        .update_channel_message(
+            channel_id,
+            MessageId::from_proto(request.message_id),
+            session.user_id,
+            &request.body,
+            &request.mentions,
+            OffsetDateTime::now_utc(),
+        )
+        .await?;
+    broadcast_channel_message_update(channel_id, updated, response, &session).await
+}
+
+/// Add or remove an emoji reaction on a channel message
+async fn toggle_channel_message_reaction(
+    request: proto::ToggleChannelMessageReaction,
+    response: Response<proto::ToggleChannelMessageReaction>,
+    session: Session,
+) -> Result<()> {
+    if request.emoji.is_empty() || request.emoji.len() > MAX_REACTION_LEN {
+        return Err(anyhow!("invalid reaction"))?;
+    }
+
+    let channel_id = ChannelId::from_proto(request.channel_id);
+    let updated = session
+        .db()
+        .await
+        .toggle_channel_message_reaction(
+            channel_id,
+            MessageId::from_proto(request.message_id),
+            session.user_id,
+            &request.emoji,
+        )
+        .await?;
+    broadcast_channel_message_update(channel_id, updated, response, &session).await
+}
+
+/// Retrieve the previous versions of an edited channel message
+async fn get_channel_message_edits(
+    request: proto::GetChannelMessageEdits,
+    response: Response<proto::GetChannelMessageEdits>,
+    session: Session,
+) -> Result<()> {
+    let edits = session
+        .db()
+        .await
+        .get_channel_message_edits(
+            ChannelId::from_proto(request.channel_id),
+            MessageId::from_proto(request.message_id),
+            session.user_id,
+        )
+        .await?;
+    response.send(proto::GetChannelMessageEditsResponse { edits })?;
+    Ok(())
+}
+
+/// Retrieve a channel message along with all of its replies
+async fn get_channel_message_thread(
+    request: proto::GetChannelMessageThread,
+    response: Response<proto::GetChannelMessageThread>,
+    session: Session,
+) -> Result<()> {
+    let messages = session
+        .db()
+        .await
+        .get_channel_message_thread(
+            ChannelId::from_proto(request.channel_id),
+            MessageId::from_proto(request.message_id),
+            session.user_id,
+        )
+        .await?;
+    response.send(proto::GetChannelMessagesResponse {
+        done: true,
+        messages,
+    })?;
+    Ok(())
+}
+
+async fn broadcast_channel_message_update<
+    T: RequestMessage<Response = proto::SendChannelMessageResponse>,
+>(
+    channel_id: ChannelId,
+    updated: UpdatedChannelMessage,
+    response: Response<T>,
+    session: &Session,
+) -> Result<()> {
+    let UpdatedChannelMessage {
+        message,
+        participant_connection_ids,
+        notifications,
+    } = updated;
+    broadcast(
+        Some(session.connection_id),
+        participant_connection_ids,
+        |connection| {
+            session.peer.send(
+                connection,
+                proto::ChannelMessageUpdate {
+                    channel_id: channel_id.to_proto(),
+                    message: Some(message.clone()),
+                },
+            )
+        },
+    );
+    response.send(proto::SendChannelMessageResponse {
+        message: Some(message),
+    })?;
+    send_notifications(
+        &*session.connection_pool().await,
+        &session.peer,
+        notifications,
+    );
+    Ok(())
+}
+
+/// Retrieve the comment threads anchored in a channel or project buffer
+async fn get_comment_threads(
+    request: proto::GetCommentThreads,
+    response: Response<proto::GetCommentThreads>,
+    session: Session,
+) -> Result<()> {
+    let location = comment_location(
+        request.channel_id,
+        request.project_id,
+        request.worktree_id,
+        request.path,
+    )?;
+    let threads = session
+        .db()
+        .await
+        .get_comment_threads(location, session.user_id, session.connection_id)
+        .await?;
+    response.send(proto::GetCommentThreadsResponse { threads })?;
+    Ok(())
+}
+
+/// Start a new comment thread on a range of a buffer
+async fn create_comment_thread(
+    request: proto::CreateCommentThread,
+    response: Response<proto::CreateCommentThread>,
+    session: Session,
+) -> Result<()> {
+    let body = validate_comment_body(&request.body)?;
+    let location = comment_location(
+        request.channel_id,
+        request.project_id,
+        request.worktree_id,
+        request.path,
+    )?;
+    let start = request
+        .start
+        .ok_or_else(|| anyhow!("missing start anchor"))?;
+    let end = request.end.ok_or_else(|| anyhow!("missing end anchor"))?;
+    let updated = session
+        .db()
+        .await
+        .create_comment_thread(
+            location,
+            session.user_id,
+            session.connection_id,
+            start,
+            end,
+            body,
+            &request.mentions,
+            OffsetDateTime::now_utc(),
+        )
+        .await?;
+    broadcast_comment_thread(updated, response, &session).await
+}
+
+/// Reply to an existing comment thread
+async fn create_comment(
+    request: proto::CreateComment,
+    response: Response<proto::CreateComment>,
+    session: Session,
+) -> Result<()> {
+    let body = validate_comment_body(&request.body)?;
+    let updated = session
+        .db()
+        .await
+        .create_comment(
+            CommentThreadId::from_proto(request.thread_id),
+            session.user_id,
+            session.connection_id,
+            body,
+            &request.mentions,
+            OffsetDateTime::now_utc(),
+        )
+        .await?;
+    broadcast_comment_thread(updated, response, &session).await
+}
+
+/// Resolve or reopen a comment thread
+async fn set_comment_thread_resolved(
+    request: proto::SetCommentThreadResolved,
+    response: Response<proto::SetCommentThreadResolved>,
+    session: Session,
+) -> Result<()> {
+    let updated = session
+        .db()
+        .await
+        .set_comment_thread_resolved(
+            CommentThreadId::from_proto(request.thread_id),
+            session.user_id,
+            session.connection_id,
+            request.resolved,
+        )
+        .await?;
+    broadcast_comment_thread(updated, response, &session).await
+}
+
+fn comment_location(
+    channel_id: Option<u64>,
+    project_id: Option<u64>,
+    worktree_id: Option<u64>,
+    path: Option<String>,
+) -> Result<CommentLocation> {
+    match (channel_id, project_id, worktree_id.zip(path)) {
+        (Some(channel_id), None, None) => Ok(CommentLocation::ChannelBuffer(
+            ChannelId::from_proto(channel_id),
+        )),
+        (None, Some(project_id), Some((worktree_id, path))) => Ok(CommentLocation::ProjectPath {
+            project_id: ProjectId::from_proto(project_id),
+            worktree_id,
+            path,
+        }),
+        _ => Err(anyhow!(
+            "comments must belong to either a channel or a project"
+        ))?,
+    }
+}
+
+fn validate_comment_body(body: &str) -> Result<&str> {
+    // Mention offsets refer to the untrimmed body, so it is stored as-is.
+    if body.len() > MAX_MESSAGE_LEN {
+        return Err(anyhow!("comment is too long"))?;
+    }
+    if body.trim().is_empty() {
+        return Err(anyhow!("comment can't be blank"))?;
+    }
+    Ok(body)
+}
+
+async fn broadcast_comment_thread<T: RequestMessage<Response = proto::CommentThreadResponse>>(
+    updated: UpdatedCommentThread,
+    response: Response<T>,
+    session: &Session,
+) -> Result<()> {
+    let UpdatedCommentThread {
+        thread,
+        connection_ids,
+        notifications,
+    } = updated;
+    broadcast(Some(session.connection_id), connection_ids, |connection| {
+        session.peer.send(
+            connection,
+            proto::UpdateCommentThread {
+                thread: Some(thread.clone()),
+            },
+        )
+    });
+    response.send(proto::CommentThreadResponse {
+        thread: Some(thread),
+    })?;
+    send_notifications(
+        &*session.connection_pool().await,
+        &session.peer,
+        notifications,
+    );
+    Ok(())
+}
+
 /// Retrieve the current users notifications
 async fn get_notifications(
     request: proto::GetNotifications,
@@ -57,6 +57,28 @@ impl ConnectionPool {
         self.connections.values()
     }
 
+    /// The number of connections, not counting those of admins.
+    pub fn connection_count(&self) -> usize {
+        self.connections
+            .values()
+            .filter(|connection| !connection.admin)
+            .count()
+    }
+
+    /// The number of users with one or more connections, not counting admins.
+    pub fn connected_user_count(&self) -> usize {
+        self.connected_users
+            .values()
+            .filter(|user| {
+                user.connection_ids.iter().any(|connection_id| {
+                    self.connections
+                        .get(connection_id)
+                        .map_or(false, |connection| !connection.admin)
+                })
+            })
+            .count()
+    }
+
     pub fn user_connection_ids(&self, user_id: UserId) -> impl Iterator<Item = ConnectionId> + '_ {
         self.connected_users
             .get(&user_id)
@@ -1,6 +1,7 @@
 use call::Room;
 use gpui::{Model, TestAppContext};
 
+mod auth_tests;
 mod channel_buffer_tests;
 mod channel_guest_tests;
 mod channel_message_tests;
@@ -9,6 +10,7 @@ mod editor_tests;
 mod following_tests;
 mod integration_tests;
 mod notification_tests;
+mod project_share_scope_tests;
 mod random_channel_buffer_tests;
 mod random_project_collaboration_tests;
 mod randomized_test_helpers;
@@ -0,0 +1,436 @@
+use crate::{
+    auth::{self, OidcClient, OidcIdentity, OidcProvider},
+    db::{TestDb, UserId},
+    tests::TestServer,
+    AppState, Config,
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Request, StatusCode},
+    response::Response,
+};
+use gpui::TestAppContext;
+use parking_lot::Mutex;
+use reqwest::Url;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tower::Service;
+
+const ISSUER: &str = "https://idp.example.com";
+const PUBLIC_URL: &str = "https://collab.example.com";
+const NATIVE_APP_PORT: u16 = 4567;
+
+#[gpui::test]
+async fn test_oidc_sign_in(cx: &mut TestAppContext) {
+    let test_db = TestDb::sqlite(cx.executor());
+    let provider = Arc::new(FakeOidcProvider::default());
+    let mut config = TestServer::test_config();
+    config.public_url = Some(PUBLIC_URL.into());
+    let app = build_app_state(&test_db, config, Some(provider.clone()));
+
+    let metadata = json_body(request(&app, get("/auth/metadata")).await).await;
+    assert_eq!(metadata["providers"][0]["kind"], "oidc");
+    assert_eq!(metadata["providers"][0]["name"], "Example SSO");
+
+    // With a single provider, the sign-in page goes straight to it.
+    let (public_key, private_key) = rpc::auth::keypair().unwrap();
+    let query = native_app_query(String::try_from(public_key).unwrap());
+    let response = request(&app, get(&format!("/auth/signin?{query}"))).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), format!("/auth/oidc/signin?{query}"));
+
+    let alice = OidcIdentity {
+        subject: "alice-subject".into(),
+        preferred_username: Some("alice".into()),
+        email: Some("alice@example.com".into()),
+    };
+    let callback = sign_in_with_provider(&app, &provider, &query, alice.clone()).await;
+    let response = request(&app, get(&callback)).await;
+    let (user_id, access_token) = native_app_credentials(&response, &private_key);
+    assert!(
+        auth::verify_access_token(&access_token, user_id, test_db.db())
+            .await
+            .unwrap()
+            .is_valid
+    );
+    let user = test_db.db().get_user_by_id(user_id).await.unwrap().unwrap();
+    assert_eq!(user.github_login, "alice");
+    assert_eq!(user.email_address.as_deref(), Some("alice@example.com"));
+
+    // Each sign-in can only be completed once.
+    let response = request(&app, get(&callback)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Signing in again with the same account signs in as the same user.
+    let callback = sign_in_with_provider(&app, &provider, &query, alice).await;
+    let response = request(&app, get(&callback)).await;
+    assert_eq!(native_app_credentials(&response, &private_key).0, user_id);
+
+    // Errors reported by the provider are surfaced to the user.
+    let response = request(&app, get(&format!("/auth/oidc/signin?{query}"))).await;
+    let state = query_params(&location(&response))["state"].clone();
+    let response = request(
+        &app,
+        get(&format!(
+            "/auth/oidc/callback?state={state}&error=access_denied"
+        )),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Local accounts aren't available unless they're enabled.
+    let response = request(&app, get(&format!("/auth/local/signin?{query}"))).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[gpui::test]
+async fn test_local_account_sign_in(cx: &mut TestAppContext) {
+    let test_db = TestDb::sqlite(cx.executor());
+    let mut config = TestServer::test_config();
+    config.local_accounts = Some(true);
+    let app = build_app_state(&test_db, config, None);
+
+    let metadata = json_body(request(&app, get("/auth/metadata")).await).await;
+    assert_eq!(metadata["providers"][0]["kind"], "local");
+
+    let code = test_db
+        .db()
+        .create_local_account_invite(None, Some("bob@example.com"), false)
+        .await
+        .unwrap();
+    let invite_path = format!("/auth/local/invites/{code}");
+    let response = request(&app, get(&invite_path)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = request(
+        &app,
+        post_form(
+            &invite_path,
+            &[
+                ("username", "bob"),
+                ("password", "correct horse"),
+                ("password_confirmation", "battery staple"),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = request(
+        &app,
+        post_form(
+            &invite_path,
+            &[
+                ("username", "bob"),
+                ("password", "correct horse"),
+                ("password_confirmation", "correct horse"),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Invites can't be reused.
+    let response = request(&app, get(&invite_path)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let (public_key, private_key) = rpc::auth::keypair().unwrap();
+    let public_key = String::try_from(public_key).unwrap();
+    let port = NATIVE_APP_PORT.to_string();
+    let response = request(
+        &app,
+        post_form(
+            "/auth/local/signin",
+            &[
+                ("username", "bob"),
+                ("password", "wrong password"),
+                ("native_app_port", &port),
+                ("native_app_public_key", &public_key),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = request(
+        &app,
+        post_form(
+            "/auth/local/signin",
+            &[
+                ("username", "bob"),
+                ("password", "correct horse"),
+                ("native_app_port", &port),
+                ("native_app_public_key", &public_key),
+            ],
+        ),
+    )
+    .await;
+    let (user_id, access_token) = native_app_credentials(&response, &private_key);
+    assert!(
+        auth::verify_access_token(&access_token, user_id, test_db.db())
+            .await
+            .unwrap()
+            .is_valid
+    );
+    let user = test_db.db().get_user_by_id(user_id).await.unwrap().unwrap();
+    assert_eq!(user.github_login, "bob");
+    assert_eq!(user.email_address.as_deref(), Some("bob@example.com"));
+
+    // Unknown usernames are rejected the same way as wrong passwords.
+    let response = request(
+        &app,
+        post_form(
+            "/auth/local/signin",
+            &[
+                ("username", "nobody"),
+                ("password", "correct horse"),
+                ("native_app_port", &port),
+                ("native_app_public_key", &public_key),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // After too many failed attempts, even the right password is turned away.
+    for _ in 0..5 {
+        let response = request(
+            &app,
+            post_form(
+                "/auth/local/signin",
+                &[
+                    ("username", "bob"),
+                    ("password", "wrong password"),
+                    ("native_app_port", &port),
+                    ("native_app_public_key", &public_key),
+                ],
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = request(
+        &app,
+        post_form(
+            "/auth/local/signin",
+            &[
+                ("username", "bob"),
+                ("password", "correct horse"),
+                ("native_app_port", &port),
+                ("native_app_public_key", &public_key),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Failures from one address don't lock the user out everywhere else.
+    let response = request(
+        &app,
+        from_address(
+            post_form(
+                "/auth/local/signin",
+                &[
+                    ("username", "bob"),
+                    ("password", "correct horse"),
+                    ("native_app_port", &port),
+                    ("native_app_public_key", &public_key),
+                ],
+            ),
+            [10, 0, 0, 2],
+        ),
+    )
+    .await;
+    let (other_user_id, _) = native_app_credentials(&response, &private_key);
+    assert_eq!(other_user_id, user_id);
+}
+
+/// An identity provider that lets tests decide who signs in, and that checks
+/// the PKCE code verifier when authorization codes are redeemed.
+#[derive(Default)]
+struct FakeOidcProvider {
+    codes: Mutex<HashMap<String, IssuedCode>>,
+}
+
+struct IssuedCode {
+    redirect_uri: String,
+    code_challenge: String,
+    identity: OidcIdentity,
+}
+
+impl FakeOidcProvider {
+    /// Signs the given identity in at the authorization URL, returning the URL
+    /// that the provider redirects the browser back to.
+    fn authorize(&self, authorization_url: &str, identity: OidcIdentity) -> String {
+        let params = query_params(authorization_url);
+        assert!(authorization_url.starts_with(&format!("{ISSUER}/authorize?")));
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        let mut codes = self.codes.lock();
+        let code = format!("code-{}", codes.len());
+        codes.insert(
+            code.clone(),
+            IssuedCode {
+                redirect_uri: params["redirect_uri"].clone(),
+                code_challenge: params["code_challenge"].clone(),
+                identity,
+            },
+        );
+
+        let mut redirect_uri = Url::parse(&params["redirect_uri"]).unwrap();
+        redirect_uri
+            .query_pairs_mut()
+            .append_pair("code", &code)
+            .append_pair("state", &params["state"]);
+        redirect_uri.into()
+    }
+}
+
+#[async_trait]
+impl OidcClient for FakeOidcProvider {
+    fn issuer(&self) -> &str {
+        ISSUER
+    }
+
+    fn authorization_url(&self, redirect_uri: &str, state: &str, code_challenge: &str) -> String {
+        Url::parse_with_params(
+            &format!("{ISSUER}/authorize"),
+            &[
+                ("redirect_uri", redirect_uri),
+                ("state", state),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .unwrap()
+        .into()
+    }
+
+    async fn exchange_code(
+        &self,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str,
+    ) -> anyhow::Result<OidcIdentity> {
+        let issued = self
+            .codes
+            .lock()
+            .remove(code)
+            .ok_or_else(|| anyhow!("invalid authorization code"))?;
+        if issued.redirect_uri != redirect_uri {
+            Err(anyhow!("redirect uri mismatch"))?;
+        }
+        if auth::code_challenge(code_verifier) != issued.code_challenge {
+            Err(anyhow!("invalid code verifier"))?;
+        }
+        Ok(issued.identity)
+    }
+}
+
+fn build_app_state(
+    test_db: &TestDb,
+    config: Config,
+    oidc: Option<Arc<FakeOidcProvider>>,
+) -> Arc<AppState> {
+    Arc::new(AppState {
+        db: test_db.db().clone(),
+        live_kit_client: None,
+        oidc: oidc.map(|client| Arc::new(OidcProvider::new(client, "Example SSO".into()))),
+        sign_in_throttle: Default::default(),
+        config,
+    })
+}
+
+/// Starts signing in, has the provider authenticate the given identity, and returns
+/// the path of the callback that the provider redirects the browser to.
+async fn sign_in_with_provider(
+    app: &Arc<AppState>,
+    provider: &FakeOidcProvider,
+    query: &str,
+    identity: OidcIdentity,
+) -> String {
+    let response = request(app, get(&format!("/auth/oidc/signin?{query}"))).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let callback = Url::parse(&provider.authorize(&location(&response), identity)).unwrap();
+    assert_eq!(
+        callback.as_str().split('?').next(),
+        Some(format!("{PUBLIC_URL}/auth/oidc/callback").as_str())
+    );
+    format!("{}?{}", callback.path(), callback.query().unwrap())
+}
+
+async fn request(app: &Arc<AppState>, request: Request<Body>) -> Response {
+    let mut routes = auth::routes(app.clone());
+    futures::future::poll_fn(|cx| routes.poll_ready(cx))
+        .await
+        .unwrap();
+    routes.call(request).await.unwrap()
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+fn post_form(uri: &str, fields: &[(&str, &str)]) -> Request<Body> {
+    let body = Url::parse_with_params("http://localhost", fields)
+        .unwrap()
+        .query()
+        .unwrap()
+        .to_string();
+    let request = Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(body))
+        .unwrap();
+    from_address(request, [10, 0, 0, 1])
+}
+
+fn from_address(mut request: Request<Body>, address: [u8; 4]) -> Request<Body> {
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from((address, 0))));
+    request
+}
+
+async fn json_body(response: Response) -> serde_json::Value {
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn location(response: &Response) -> String {
+    response.headers()[header::LOCATION]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+fn query_params(url: &str) -> HashMap<String, String> {
+    Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+fn native_app_query(public_key: String) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("native_app_port", &NATIVE_APP_PORT.to_string())
+        .append_pair("native_app_public_key", &public_key)
+        .finish()
+}
+
+/// Returns the credentials that the server handed to the native app's local server.
+fn native_app_credentials(
+    response: &Response,
+    private_key: &rpc::auth::PrivateKey,
+) -> (UserId, String) {
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let url = location(response);
+    assert!(url.starts_with(&format!("http://127.0.0.1:{NATIVE_APP_PORT}")));
+    let params = query_params(&url);
+    let user_id = UserId(params["user_id"].parse().unwrap());
+    let access_token = private_key.decrypt_string(&params["access_token"]).unwrap();
+    (user_id, access_token)
+}
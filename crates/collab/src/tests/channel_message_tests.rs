@@ -44,6 +44,7 @@ async fn test_basic_channel_messages(
                     text: "hi @user_c!".into(),
                     mentions: vec![(3..10, client_c.id())],
                     reply_to_message_id: None,
+                    attachments: Vec::new(),
                 },
                 cx,
             )
@@ -446,6 +447,7 @@ async fn test_chat_replies(cx_a: &mut TestAppContext, cx_b: &mut TestAppContext)
                     text: "reply".into(),
                     reply_to_message_id: Some(msg_id),
                     mentions: Vec::new(),
+                    attachments: Vec::new(),
                 },
                 cx,
             )
@@ -466,3 +468,115 @@ async fn test_chat_replies(cx_a: &mut TestAppContext, cx_b: &mut TestAppContext)
         )
     });
 }
+
+#[gpui::test]
+async fn test_chat_edits_reactions_and_threads(
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(cx_a.executor()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+
+    let channel_id = server
+        .make_channel(
+            "the-channel",
+            None,
+            (&client_a, cx_a),
+            &mut [(&client_b, cx_b)],
+        )
+        .await;
+
+    let channel_chat_a = client_a
+        .channel_store()
+        .update(cx_a, |store, cx| store.open_channel_chat(channel_id, cx))
+        .await
+        .unwrap();
+    let channel_chat_b = client_b
+        .channel_store()
+        .update(cx_b, |store, cx| store.open_channel_chat(channel_id, cx))
+        .await
+        .unwrap();
+
+    let msg_id = channel_chat_a
+        .update(cx_a, |c, cx| c.send_message("one".into(), cx).unwrap())
+        .await
+        .unwrap();
+    cx_a.run_until_parked();
+
+    // Client B replies, and both clients see the reply count change.
+    channel_chat_b
+        .update(cx_b, |c, cx| {
+            c.send_message(
+                MessageParams {
+                    text: "two".into(),
+                    reply_to_message_id: Some(msg_id),
+                    mentions: Vec::new(),
+                    attachments: Vec::new(),
+                },
+                cx,
+            )
+            .unwrap()
+        })
+        .await
+        .unwrap();
+    cx_a.run_until_parked();
+    channel_chat_a.update(cx_a, |c, _| {
+        assert_eq!(c.find_loaded_message(msg_id).unwrap().reply_count, 1);
+    });
+    channel_chat_b.update(cx_b, |c, _| {
+        assert_eq!(c.find_loaded_message(msg_id).unwrap().reply_count, 1);
+    });
+
+    // Client B can't edit client A's message.
+    channel_chat_b
+        .update(cx_b, |c, cx| {
+            c.update_message(msg_id, "hijacked".into(), cx).unwrap()
+        })
+        .await
+        .unwrap_err();
+
+    channel_chat_a
+        .update(cx_a, |c, cx| {
+            c.update_message(msg_id, "one, edited".into(), cx).unwrap()
+        })
+        .await
+        .unwrap();
+    channel_chat_b
+        .update(cx_b, |c, cx| c.toggle_reaction(msg_id, "🎉", cx))
+        .await
+        .unwrap();
+    cx_a.run_until_parked();
+
+    for (chat, cx) in [(&channel_chat_a, &mut *cx_a), (&channel_chat_b, &mut *cx_b)] {
+        chat.update(cx, |c, _| {
+            let message = c.find_loaded_message(msg_id).unwrap();
+            assert_eq!(message.body, "one, edited");
+            assert!(message.edited_at.is_some());
+            assert_eq!(message.reactions.len(), 1);
+            assert_eq!(message.reactions[0].emoji, "🎉");
+            assert_eq!(message.reactions[0].user_ids, vec![client_b.id()]);
+        });
+    }
+
+    let edits = channel_chat_b
+        .update(cx_b, |c, cx| c.message_edits(msg_id, cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        edits.into_iter().map(|edit| edit.body).collect::<Vec<_>>(),
+        &["one"]
+    );
+
+    let thread = channel_chat_b
+        .update(cx_b, |c, cx| c.load_thread(msg_id, cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        thread
+            .iter()
+            .map(|message| message.body.as_str())
+            .collect::<Vec<_>>(),
+        &["one, edited", "two"]
+    );
+}
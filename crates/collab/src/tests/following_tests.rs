@@ -11,7 +11,7 @@ use gpui::{
 };
 use language::Capability;
 use live_kit_client::MacOSDisplay;
-use project::project_settings::ProjectSettings;
+use project::{project_settings::ProjectSettings, ProjectPath};
 use rpc::proto::PeerId;
 use serde_json::json;
 use settings::SettingsStore;
@@ -1759,6 +1759,121 @@ async fn test_following_into_excluded_file(
     });
 }
 
+#[gpui::test]
+async fn test_jumping_to_participant_location_without_following(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+    let active_call_b = cx_b.read(ActiveCall::global);
+    let peer_id_a = client_a.peer_id().unwrap();
+    let peer_id_b = client_b.peer_id().unwrap();
+
+    client_a
+        .fs()
+        .insert_tree("/a", json!({ "1.txt": "one", "2.txt": "two" }))
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/a", cx_a).await;
+    active_call_a
+        .update(cx_a, |call, cx| call.set_location(Some(&project_a), cx))
+        .await
+        .unwrap();
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_remote_project(project_id, cx_b).await;
+    active_call_b
+        .update(cx_b, |call, cx| call.set_location(Some(&project_b), cx))
+        .await
+        .unwrap();
+
+    let (workspace_a, cx_a) = client_a.build_workspace(&project_a, cx_a);
+    let (workspace_b, cx_b) = client_b.build_workspace(&project_b, cx_b);
+
+    // The file that client A has open is replicated to the other participants.
+    workspace_a
+        .update(cx_a, |workspace, cx| {
+            workspace.open_path((worktree_id, "1.txt"), None, true, cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    let path_1 = ProjectPath::from((worktree_id, "1.txt"));
+    active_call_b.read_with(cx_b, |call, cx| {
+        let room = call.room().unwrap().read(cx);
+        let participant = room.remote_participant_for_peer_id(peer_id_a).unwrap();
+        assert_eq!(participant.active_path, Some(path_1.clone()));
+        assert_eq!(
+            room.remote_participants_at_path(project_id, &path_1)
+                .map(|participant| participant.peer_id)
+                .collect::<Vec<_>>(),
+            [peer_id_a]
+        );
+    });
+
+    // Client B jumps to client A's file without following them.
+    workspace_b.update(cx_b, |workspace, cx| {
+        workspace.open_participant_location(peer_id_a, cx)
+    });
+    executor.run_until_parked();
+    workspace_b.update(cx_b, |workspace, cx| {
+        assert_eq!(
+            workspace.active_item(cx).unwrap().project_path(cx),
+            Some(path_1.clone())
+        );
+        assert_eq!(workspace.leader_for_pane(workspace.active_pane()), None);
+    });
+    active_call_a.read_with(cx_a, |call, cx| {
+        let room = call.room().unwrap().read(cx);
+        let participant = room.remote_participant_for_peer_id(peer_id_b).unwrap();
+        assert_eq!(participant.active_path, Some(path_1.clone()));
+    });
+
+    // When client A opens another file, client B stays where they are.
+    workspace_a
+        .update(cx_a, |workspace, cx| {
+            workspace.open_path((worktree_id, "2.txt"), None, true, cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    active_call_b.read_with(cx_b, |call, cx| {
+        let room = call.room().unwrap().read(cx);
+        let participant = room.remote_participant_for_peer_id(peer_id_a).unwrap();
+        assert_eq!(
+            participant.active_path,
+            Some(ProjectPath::from((worktree_id, "2.txt")))
+        );
+    });
+    workspace_b.update(cx_b, |workspace, cx| {
+        assert_eq!(
+            workspace.active_item(cx).unwrap().project_path(cx),
+            Some(path_1.clone())
+        );
+    });
+
+    // Paths are cleared when the participant leaves the project.
+    active_call_a
+        .update(cx_a, |call, cx| call.set_location(None, cx))
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    active_call_b.read_with(cx_b, |call, cx| {
+        let room = call.room().unwrap().read(cx);
+        let participant = room.remote_participant_for_peer_id(peer_id_a).unwrap();
+        assert_eq!(participant.location, ParticipantLocation::External);
+        assert_eq!(participant.active_path, None);
+    });
+}
+
 fn visible_push_notifications(
     cx: &mut TestAppContext,
 ) -> Vec<gpui::View<ProjectSharedNotification>> {
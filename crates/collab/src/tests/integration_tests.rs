@@ -2,14 +2,18 @@ use crate::{
     rpc::{CLEANUP_TIMEOUT, RECONNECT_TIMEOUT},
     tests::{channel_id, room_participants, RoomParticipants, TestClient, TestServer},
 };
-use call::{room, ActiveCall, ParticipantLocation, Room};
+use call::{
+    call_settings::CallSettings,
+    media_backend::{set_media_backend, LiveKitBackend, MediaBackend, MediaRoom},
+    room, ActiveCall, ParticipantLocation, Room,
+};
 use client::{User, RECEIVE_TIMEOUT};
 use collections::{HashMap, HashSet};
 use fs::{repository::GitFileStatus, FakeFs, Fs as _, RemoveOptions};
 use futures::StreamExt as _;
 use gpui::{
-    px, size, AppContext, BackgroundExecutor, Model, Modifiers, MouseButton, MouseDownEvent,
-    TestAppContext,
+    px, size, AppContext, BackgroundExecutor, KeyBinding, KeyUpEvent, Keystroke, Model, Modifiers,
+    MouseButton, MouseDownEvent, TestAppContext,
 };
 use language::{
     language_settings::{AllLanguageSettings, Formatter},
@@ -32,7 +36,7 @@ use std::{
     rc::Rc,
     sync::{
         atomic::{AtomicBool, Ordering::SeqCst},
-        Arc,
+        Arc, Mutex,
     },
     time::Duration,
 };
@@ -2020,6 +2024,159 @@ async fn test_mute(
     }
 }
 
+#[gpui::test]
+async fn test_push_to_talk_with_custom_media_backend(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .make_contacts(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+
+    let media_backend = Arc::new(TestMediaBackend::default());
+    cx_a.update(|cx| {
+        set_media_backend(media_backend.clone(), cx);
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<CallSettings>(cx, |settings| {
+                settings.mute_on_join = Some(true);
+                settings.microphone = Some("USB Microphone".into());
+            });
+        });
+    });
+
+    let active_call_a = cx_a.read(ActiveCall::global);
+    let active_call_b = cx_b.read(ActiveCall::global);
+
+    // User A calls user B, B answers. A joins muted.
+    active_call_a
+        .update(cx_a, |call, cx| {
+            call.invite(client_b.user_id().unwrap(), None, cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    active_call_b
+        .update(cx_b, |call, cx| call.accept_incoming(cx))
+        .await
+        .unwrap();
+    executor.run_until_parked();
+
+    let room_a = active_call_a.read_with(cx_a, |call, _| call.room().unwrap().clone());
+    let room_b = active_call_b.read_with(cx_b, |call, _| call.room().unwrap().clone());
+    assert_eq!(*media_backend.rooms_created.lock().unwrap(), 1);
+    assert_eq!(*media_backend.selected_device.lock().unwrap(), None);
+    room_a.read_with(cx_a, |room, _| assert!(room.is_muted()));
+
+    // Pushing to talk publishes audio from the configured microphone.
+    room_a.update(cx_a, |room, cx| room.start_push_to_talk(cx));
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| {
+        assert!(!room.is_muted());
+        assert!(room.is_pushing_to_talk());
+    });
+    assert_eq!(
+        media_backend.selected_device.lock().unwrap().as_deref(),
+        Some("USB Microphone")
+    );
+    room_b.read_with(cx_b, |room, _| {
+        let participant = &room.remote_participants()[&client_a.user_id().unwrap()];
+        assert!(!participant.muted);
+        assert_eq!(participant.audio_tracks.len(), 1);
+    });
+
+    // Pushing again while talking does nothing.
+    room_a.update(cx_a, |room, cx| room.start_push_to_talk(cx));
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| assert!(!room.is_muted()));
+
+    // Releasing mutes the microphone again.
+    room_a.update(cx_a, |room, cx| room.end_push_to_talk(cx));
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| {
+        assert!(room.is_muted());
+        assert!(!room.is_pushing_to_talk());
+    });
+    room_b.read_with(cx_b, |room, _| {
+        assert!(room.remote_participants()[&client_a.user_id().unwrap()].muted);
+    });
+
+    // Releasing without pushing first leaves an unmuted microphone alone.
+    room_a.update(cx_a, |room, cx| room.toggle_mute(cx));
+    executor.run_until_parked();
+    room_a.update(cx_a, |room, cx| room.end_push_to_talk(cx));
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| assert!(!room.is_muted()));
+
+    // In a workspace, only releasing the key bound to push-to-talk mutes the
+    // microphone again.
+    room_a.update(cx_a, |room, cx| room.toggle_mute(cx));
+    executor.run_until_parked();
+    cx_a.update(|cx| cx.bind_keys([KeyBinding::new("f12", collab_ui::PushToTalk, None)]));
+    let project_a = client_a.build_empty_local_project(cx_a);
+    let (workspace_a, cx_a) = client_a.build_workspace(&project_a, cx_a);
+    workspace_a.update(cx_a, |_, cx| cx.focus_self());
+    cx_a.simulate_keystrokes("f12");
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| assert!(room.is_pushing_to_talk()));
+
+    cx_a.simulate_event(KeyUpEvent {
+        keystroke: Keystroke::parse("a").unwrap(),
+    });
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| {
+        assert!(!room.is_muted());
+        assert!(room.is_pushing_to_talk());
+    });
+
+    cx_a.simulate_event(KeyUpEvent {
+        keystroke: Keystroke::parse("f12").unwrap(),
+    });
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| {
+        assert!(room.is_muted());
+        assert!(!room.is_pushing_to_talk());
+    });
+
+    // Losing focus also ends push-to-talk.
+    cx_a.simulate_keystrokes("f12");
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| assert!(room.is_pushing_to_talk()));
+    cx_a.deactivate_window();
+    executor.run_until_parked();
+    room_a.read_with(cx_a, |room, _| {
+        assert!(room.is_muted());
+        assert!(!room.is_pushing_to_talk());
+    });
+
+    /// Routes calls through the fake LiveKit server while recording how the
+    /// backend is used.
+    #[derive(Default)]
+    struct TestMediaBackend {
+        rooms_created: Mutex<usize>,
+        selected_device: Mutex<Option<String>>,
+    }
+
+    impl MediaBackend for TestMediaBackend {
+        fn create_room(&self) -> Arc<dyn MediaRoom> {
+            *self.rooms_created.lock().unwrap() += 1;
+            LiveKitBackend.create_room()
+        }
+
+        fn audio_input_devices(&self) -> anyhow::Result<Vec<String>> {
+            Ok(vec!["Built-in Microphone".into(), "USB Microphone".into()])
+        }
+
+        fn set_audio_input_device(&self, name: Option<&str>) -> anyhow::Result<()> {
+            *self.selected_device.lock().unwrap() = name.map(Into::into);
+            Ok(())
+        }
+    }
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_room_location(
     executor: BackgroundExecutor,
@@ -3703,6 +3860,7 @@ async fn test_leaving_project(
             client_b.language_registry().clone(),
             FakeFs::new(cx.background_executor().clone()),
             ChannelRole::Member,
+            None,
             cx,
         )
     })
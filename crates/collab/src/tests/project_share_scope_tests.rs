@@ -0,0 +1,624 @@
+use crate::tests::{TestClient, TestServer};
+use call::ActiveCall;
+use fs::Fs as _;
+use futures::StreamExt as _;
+use gpui::{BackgroundExecutor, TestAppContext};
+use language::{tree_sitter_rust, Language, LanguageConfig, LanguageMatcher};
+use project::{search::SearchQuery, ProjectPath};
+use rpc::proto;
+use serde_json::json;
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use text::BufferId;
+
+#[gpui::test]
+async fn test_read_only_project_share(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/dir", json!({ "a.txt": "a-contents" }))
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| {
+            let scope = proto::ProjectShareScope {
+                read_only: true,
+                ..Default::default()
+            };
+            call.share_project_with_scope(project_a.clone(), scope, cx)
+        })
+        .await
+        .unwrap();
+
+    // Client B is a member of the room, but the project was shared read-only.
+    let project_b = client_b.build_remote_project(project_id, cx_b).await;
+    assert!(project_b.read_with(cx_b, |project, _| project.is_read_only()));
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, "a.txt"), cx)
+        })
+        .await
+        .unwrap();
+    assert!(buffer_b.read_with(cx_b, |buffer, _| buffer.read_only()));
+    let buffer_id: u64 = buffer_b.read_with(cx_b, |buffer, _| buffer.remote_id().into());
+
+    // The server rejects mutations, even from a client that ignores its capability.
+    let client = client_b.client();
+    assert!(client
+        .request(proto::UpdateBuffer {
+            project_id,
+            buffer_id,
+            operations: vec![proto::Operation {
+                variant: Some(proto::operation::Variant::Edit(Default::default())),
+            }],
+        })
+        .await
+        .is_err());
+    assert!(client
+        .request(proto::SaveBuffer {
+            project_id,
+            buffer_id,
+            version: Vec::new(),
+        })
+        .await
+        .is_err());
+    assert!(client
+        .request(proto::FormatBuffers {
+            project_id,
+            trigger: 0,
+            buffer_ids: vec![buffer_id],
+        })
+        .await
+        .is_err());
+    assert!(client
+        .request(proto::CreateProjectEntry {
+            project_id,
+            worktree_id: worktree_id.to_proto(),
+            path: "b.txt".into(),
+            is_directory: false,
+        })
+        .await
+        .is_err());
+    let entry_id = project_a.read_with(cx_a, |project, cx| {
+        project
+            .entry_for_path(&(worktree_id, "a.txt").into(), cx)
+            .unwrap()
+            .id
+    });
+    assert!(client
+        .request(proto::RenameProjectEntry {
+            project_id,
+            entry_id: entry_id.to_proto(),
+            new_path: "c.txt".into(),
+        })
+        .await
+        .is_err());
+    assert!(client
+        .request(proto::DeleteProjectEntry {
+            project_id,
+            entry_id: entry_id.to_proto(),
+        })
+        .await
+        .is_err());
+
+    // Selections can still be shared.
+    client
+        .request(proto::UpdateBuffer {
+            project_id,
+            buffer_id,
+            operations: vec![proto::Operation {
+                variant: Some(proto::operation::Variant::UpdateSelections(
+                    Default::default(),
+                )),
+            }],
+        })
+        .await
+        .unwrap();
+
+    executor.run_until_parked();
+    assert_eq!(
+        client_a.fs().load(Path::new("/dir/a.txt")).await.unwrap(),
+        "a-contents"
+    );
+    assert!(!client_a.fs().is_file(Path::new("/dir/b.txt")).await);
+}
+
+#[gpui::test]
+async fn test_project_share_excluded_paths(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree(
+            "/dir",
+            json!({
+                ".env": "TOKEN=1",
+                "a.txt": "TOKEN",
+                "secrets": {
+                    "key.txt": "TOKEN",
+                },
+                "src": {
+                    "cert.pem": "TOKEN",
+                    "main.rs": "fn main() {}",
+                },
+            }),
+        )
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| {
+            let scope = proto::ProjectShareScope {
+                excluded_paths: vec![".env".into(), "secrets".into(), "**/*.pem".into()],
+                ..Default::default()
+            };
+            call.share_project_with_scope(project_a.clone(), scope, cx)
+        })
+        .await
+        .unwrap();
+    let project_b = client_b.build_remote_project(project_id, cx_b).await;
+    executor.run_until_parked();
+
+    let guest_paths = |cx_b: &mut TestAppContext| {
+        project_b.read_with(cx_b, |project, cx| {
+            project
+                .worktrees()
+                .next()
+                .unwrap()
+                .read(cx)
+                .snapshot()
+                .paths()
+                .map(|path| path.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(guest_paths(cx_b), ["a.txt", "src", "src/main.rs"]);
+    assert!(!project_b.read_with(cx_b, |project, _| project.is_read_only()));
+
+    // Entries created after the project was shared are excluded too.
+    client_a
+        .fs()
+        .insert_file("/dir/secrets/other.txt", "TOKEN".into())
+        .await;
+    client_a
+        .fs()
+        .insert_file("/dir/src/lib.rs", "".into())
+        .await;
+    executor.run_until_parked();
+    assert_eq!(
+        guest_paths(cx_b),
+        ["a.txt", "src", "src/lib.rs", "src/main.rs"]
+    );
+
+    // Excluded files can't be opened or searched by guests.
+    assert!(project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, "secrets/key.txt"), cx)
+        })
+        .await
+        .is_err());
+    let buffer_a = project_a
+        .update(cx_a, |project, cx| {
+            project.open_buffer((worktree_id, "src/cert.pem"), cx)
+        })
+        .await
+        .unwrap();
+    let buffer_id = buffer_a.read_with(cx_a, |buffer, _| buffer.remote_id());
+    assert!(project_b
+        .update(cx_b, |project, cx| project.open_buffer_by_id(buffer_id, cx))
+        .await
+        .is_err());
+
+    let mut search_results = project_b.update(cx_b, |project, cx| {
+        project.search(
+            SearchQuery::text("TOKEN", false, true, false, Vec::new(), Vec::new()).unwrap(),
+            cx,
+        )
+    });
+    let mut paths = Vec::new();
+    while let Some((buffer, _)) = search_results.next().await {
+        paths.push(buffer.read_with(cx_b, |buffer, cx| buffer.file().unwrap().full_path(cx)));
+    }
+    assert_eq!(paths, [Path::new("dir/a.txt")]);
+
+    // Guests aren't told when the host has an excluded file open.
+    let peer_id_a = client_a.peer_id().unwrap();
+    let active_path_a = |cx_b: &mut TestAppContext| {
+        cx_b.read(ActiveCall::global).read_with(cx_b, |call, cx| {
+            let room = call.room().unwrap().read(cx);
+            room.remote_participant_for_peer_id(peer_id_a)
+                .unwrap()
+                .active_path
+                .clone()
+        })
+    };
+    active_call_a
+        .update(cx_a, |call, cx| call.set_location(Some(&project_a), cx))
+        .await
+        .unwrap();
+    active_call_a
+        .update(cx_a, |call, cx| {
+            call.set_active_path(&project_a, Some((worktree_id, "a.txt").into()), cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(
+        active_path_a(cx_b),
+        Some(ProjectPath::from((worktree_id, "a.txt")))
+    );
+    active_call_a
+        .update(cx_a, |call, cx| {
+            call.set_active_path(
+                &project_a,
+                Some((worktree_id, "secrets/key.txt").into()),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(active_path_a(cx_b), None);
+}
+
+#[gpui::test]
+async fn test_project_invites(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+    cx_c: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    let client_c = server.create_client(cx_c, "user_c").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b), (&client_c, cx_c)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/dir", json!({ "a.txt": "a-contents" }))
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| {
+            let scope = proto::ProjectShareScope {
+                read_only: true,
+                invite_only: true,
+                ..Default::default()
+            };
+            call.share_project_with_scope(project_a.clone(), scope, cx)
+        })
+        .await
+        .unwrap();
+
+    let join_project =
+        |client: &TestClient, invite_token: Option<String>, cx: &mut TestAppContext| {
+            let active_call = cx.read(ActiveCall::global);
+            let room = active_call.read_with(cx, |call, _| call.room().unwrap().clone());
+            let app_state = client.app_state.clone();
+            room.update(cx, |room, cx| {
+                room.join_project_with_invite(
+                    project_id,
+                    invite_token,
+                    app_state.languages.clone(),
+                    app_state.fs.clone(),
+                    cx,
+                )
+            })
+        };
+
+    // Without an invite, room participants can't join the project, nor see
+    // which of its files the host has open.
+    assert!(join_project(&client_b, None, cx_b).await.is_err());
+    active_call_a
+        .update(cx_a, |call, cx| call.set_location(Some(&project_a), cx))
+        .await
+        .unwrap();
+    active_call_a
+        .update(cx_a, |call, cx| {
+            call.set_active_path(&project_a, Some((worktree_id, "a.txt").into()), cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    let peer_id_a = client_a.peer_id().unwrap();
+    cx_b.read(ActiveCall::global).read_with(cx_b, |call, cx| {
+        let room = call.room().unwrap().read(cx);
+        let participant_a = room.remote_participant_for_peer_id(peer_id_a).unwrap();
+        assert_eq!(participant_a.active_path, None);
+    });
+
+    // Only the host can create invites.
+    assert!(client_b
+        .client()
+        .request(proto::CreateProjectInvite {
+            project_id,
+            read_only: false,
+            expires_in_seconds: 60,
+        })
+        .await
+        .is_err());
+
+    // Invites must expire in the future, but not too far in the future.
+    assert!(active_call_a
+        .update(cx_a, |call, cx| {
+            call.create_project_invite(project_a.clone(), false, Duration::ZERO, cx)
+        })
+        .await
+        .is_err());
+    let response = client_a
+        .client()
+        .request(proto::CreateProjectInvite {
+            project_id,
+            read_only: false,
+            expires_in_seconds: u64::MAX,
+        })
+        .await
+        .unwrap();
+    let max_expires_at = SystemTime::now() + Duration::from_secs(7 * 24 * 60 * 60);
+    assert!(
+        SystemTime::from(response.expires_at.unwrap()) <= max_expires_at + Duration::from_secs(1)
+    );
+
+    // An invite decides whether the guest can edit the project.
+    let token = active_call_a
+        .update(cx_a, |call, cx| {
+            call.create_project_invite(project_a.clone(), false, Duration::from_secs(60), cx)
+        })
+        .await
+        .unwrap();
+    let project_b = join_project(&client_b, Some(token), cx_b).await.unwrap();
+    assert!(!project_b.read_with(cx_b, |project, _| project.is_read_only()));
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, "a.txt"), cx)
+        })
+        .await
+        .unwrap();
+    buffer_b.update(cx_b, |buffer, cx| buffer.edit([(0..0, "b-")], None, cx));
+    project_b
+        .update(cx_b, |project, cx| {
+            project.save_buffer(buffer_b.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        client_a.fs().load(Path::new("/dir/a.txt")).await.unwrap(),
+        "b-a-contents"
+    );
+
+    let read_only_token = active_call_a
+        .update(cx_a, |call, cx| {
+            call.create_project_invite(project_a.clone(), true, Duration::from_secs(60), cx)
+        })
+        .await
+        .unwrap();
+    let project_c = join_project(&client_c, Some(read_only_token), cx_c)
+        .await
+        .unwrap();
+    assert!(project_c.read_with(cx_c, |project, _| project.is_read_only()));
+    assert!(project_c
+        .update(cx_c, |project, cx| {
+            project.create_entry((worktree_id, "c.txt"), false, cx)
+        })
+        .await
+        .is_err());
+}
+
+#[gpui::test]
+async fn test_excluded_paths_in_buffers_and_locations(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    let mut language = Language::new(
+        LanguageConfig {
+            name: "Rust".into(),
+            matcher: LanguageMatcher {
+                path_suffixes: vec!["rs".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    );
+    let mut fake_language_servers = language.set_fake_lsp_adapter(Default::default()).await;
+    client_a.language_registry().add(Arc::new(language));
+
+    client_a
+        .fs()
+        .insert_tree(
+            "/dir",
+            json!({
+                "main.rs": "const ONE: usize = key::KEY;",
+                "secrets": {
+                    "key.rs": "const KEY: usize = 1;",
+                },
+            }),
+        )
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| {
+            let scope = proto::ProjectShareScope {
+                excluded_paths: vec!["secrets".into()],
+                ..Default::default()
+            };
+            call.share_project_with_scope(project_a.clone(), scope, cx)
+        })
+        .await
+        .unwrap();
+    let project_b = client_b.build_remote_project(project_id, cx_b).await;
+
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, "main.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let fake_language_server = fake_language_servers.next().await.unwrap();
+
+    // Locations in excluded files are dropped from definitions and references.
+    fake_language_server.handle_request::<lsp::request::GotoDefinition, _, _>(|_, _| async move {
+        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/secrets/key.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+            ),
+        )))
+    });
+    let definitions = project_b
+        .update(cx_b, |project, cx| project.definition(&buffer_b, 25, cx))
+        .await
+        .unwrap();
+    assert!(definitions.is_empty());
+
+    fake_language_server.handle_request::<lsp::request::References, _, _>(|_, _| async move {
+        Ok(Some(vec![
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/main.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 24), lsp::Position::new(0, 27)),
+            ),
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/secrets/key.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+            ),
+        ]))
+    });
+    let references = project_b
+        .update(cx_b, |project, cx| project.references(&buffer_b, 25, cx))
+        .await
+        .unwrap();
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].buffer, buffer_b);
+
+    // So are workspace symbols defined in excluded files.
+    fake_language_server.handle_request::<lsp::WorkspaceSymbolRequest, _, _>(|_, _| async move {
+        #[allow(deprecated)]
+        let symbol = |name: &str, path: &str| lsp::SymbolInformation {
+            name: name.into(),
+            location: lsp::Location::new(
+                lsp::Url::from_file_path(path).unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+            ),
+            kind: lsp::SymbolKind::CONSTANT,
+            tags: None,
+            container_name: None,
+            deprecated: None,
+        };
+        Ok(Some(lsp::WorkspaceSymbolResponse::Flat(vec![
+            symbol("ONE", "/dir/main.rs"),
+            symbol("KEY", "/dir/secrets/key.rs"),
+        ])))
+    });
+    let symbols = project_b
+        .update(cx_b, |project, cx| project.symbols("", cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        symbols
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>(),
+        ["ONE"]
+    );
+
+    // The host doesn't report excluded buffers when guests synchronize.
+    let excluded_buffer_a = project_a
+        .update(cx_a, |project, cx| {
+            project.open_buffer((worktree_id, "secrets/key.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let excluded_buffer_id: u64 =
+        excluded_buffer_a.read_with(cx_a, |buffer, _| buffer.remote_id().into());
+    let response = client_b
+        .client()
+        .request(proto::SynchronizeBuffers {
+            project_id,
+            buffers: vec![proto::BufferVersion {
+                id: excluded_buffer_id,
+                version: Vec::new(),
+            }],
+        })
+        .await
+        .unwrap();
+    assert!(response.buffers.is_empty());
+
+    // The server drops excluded buffers even if the host sends them.
+    let guest_peer_id = client_b.peer_id().unwrap();
+    let send_buffer = |buffer_id: u64, path: &str| {
+        for variant in [
+            proto::create_buffer_for_peer::Variant::State(proto::BufferState {
+                id: buffer_id,
+                file: Some(proto::File {
+                    worktree_id: worktree_id.to_proto(),
+                    entry_id: None,
+                    path: path.into(),
+                    mtime: Some(proto::Timestamp::default()),
+                    is_deleted: false,
+                }),
+                ..Default::default()
+            }),
+            proto::create_buffer_for_peer::Variant::Chunk(proto::BufferChunk {
+                buffer_id,
+                operations: Vec::new(),
+                is_last: true,
+            }),
+        ] {
+            client_a
+                .client()
+                .send(proto::CreateBufferForPeer {
+                    project_id,
+                    peer_id: Some(guest_peer_id),
+                    variant: Some(variant),
+                })
+                .unwrap();
+        }
+    };
+    send_buffer(1000, "secrets/other.rs");
+    send_buffer(1001, "other.rs");
+    executor.run_until_parked();
+    project_b.read_with(cx_b, |project, _| {
+        assert!(project
+            .buffer_for_id(BufferId::new(1000).unwrap())
+            .is_none());
+        assert!(project
+            .buffer_for_id(BufferId::new(1001).unwrap())
+            .is_some());
+    });
+}
@@ -270,7 +270,8 @@ impl TestServer {
             workspace::init(app_state.clone(), cx);
             call::init(client.clone(), user_store.clone(), cx);
             channel::init(&client, user_store.clone(), cx);
-            notifications::init(client.clone(), user_store, cx);
+            notifications::init(client.clone(), user_store.clone(), cx);
+            comments::init(client.clone(), user_store, cx);
             collab_ui::init(&app_state, cx);
             file_finder::init(cx);
             menu::init();
@@ -480,21 +481,33 @@ impl TestServer {
         Arc::new(AppState {
             db: test_db.db().clone(),
             live_kit_client: Some(Arc::new(fake_server.create_api_client())),
-            config: Config {
-                http_port: 0,
-                database_url: "".into(),
-                database_max_connections: 0,
-                api_token: "".into(),
-                invite_link_prefix: "".into(),
-                live_kit_server: None,
-                live_kit_key: None,
-                live_kit_secret: None,
-                rust_log: None,
-                log_json: None,
-                zed_environment: "test".into(),
-            },
+            oidc: None,
+            sign_in_throttle: Default::default(),
+            config: Self::test_config(),
         })
     }
+
+    pub fn test_config() -> Config {
+        Config {
+            http_port: 0,
+            database_url: "".into(),
+            database_max_connections: 0,
+            api_token: "".into(),
+            invite_link_prefix: "".into(),
+            live_kit_server: None,
+            live_kit_key: None,
+            live_kit_secret: None,
+            rust_log: None,
+            log_json: None,
+            zed_environment: "test".into(),
+            public_url: None,
+            oidc_issuer_url: None,
+            oidc_client_id: None,
+            oidc_client_secret: None,
+            oidc_display_name: None,
+            local_accounts: None,
+        }
+    }
 }
 
 impl Deref for TestServer {
@@ -1,3 +1,6 @@
+mod notes_history;
+
+use crate::comment_threads::{AddComment, CommentSource, EditorComments};
 use anyhow::Result;
 use call::report_call_event_for_channel;
 use channel::{Channel, ChannelBuffer, ChannelBufferEvent, ChannelId, ChannelStore};
@@ -11,10 +14,11 @@ use editor::{
     EditorEvent,
 };
 use gpui::{
-    actions, AnyElement, AnyView, AppContext, ClipboardItem, Entity as _, EventEmitter,
+    actions, Action, AnyElement, AnyView, AppContext, ClipboardItem, Entity as _, EventEmitter,
     FocusableView, IntoElement as _, Model, Pixels, Point, Render, Subscription, Task, View,
     ViewContext, VisualContext as _, WeakView, WindowContext,
 };
+use notes_history::NotesHistory;
 use project::Project;
 use std::{
     any::{Any, TypeId},
@@ -29,7 +33,7 @@ use workspace::{
     ItemNavHistory, Pane, SaveIntent, Toast, ViewId, Workspace, WorkspaceId,
 };
 
-actions!(collab, [CopyLink]);
+actions!(collab, [CopyLink, ShowNotesHistory]);
 
 pub fn init(cx: &mut AppContext) {
     register_followable_item::<ChannelView>(cx)
@@ -42,6 +46,7 @@ pub struct ChannelView {
     channel_store: Model<ChannelStore>,
     channel_buffer: Model<ChannelBuffer>,
     remote_id: Option<ViewId>,
+    comments: Option<View<EditorComments>>,
     _editor_event_subscription: Subscription,
     _reparse_subscription: Option<Subscription>,
 }
@@ -160,13 +165,48 @@ impl ChannelView {
         channel_buffer: Model<ChannelBuffer>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
+        let (editor, comments) = Self::build_editor(&project, &channel_buffer, cx);
+        let _editor_event_subscription =
+            cx.subscribe(&editor, |_, _, e: &EditorEvent, cx| cx.emit(e.clone()));
+
+        cx.subscribe(&channel_buffer, Self::handle_channel_buffer_event)
+            .detach();
+
+        Self {
+            editor,
+            workspace,
+            project,
+            channel_store,
+            channel_buffer,
+            remote_id: None,
+            comments,
+            _editor_event_subscription,
+            _reparse_subscription: None,
+        }
+    }
+
+    fn build_editor(
+        project: &Model<Project>,
+        channel_buffer: &Model<ChannelBuffer>,
+        cx: &mut ViewContext<Self>,
+    ) -> (View<Editor>, Option<View<EditorComments>>) {
         let buffer = channel_buffer.read(cx).buffer();
         let this = cx.view().downgrade();
+        let channel_id = channel_buffer.read(cx).channel_id;
+        let language_registry = project.read(cx).languages().clone();
+        let mut comments = None;
         let editor = cx.new_view(|cx| {
             let mut editor = Editor::for_buffer(buffer, None, cx);
             editor.set_collaboration_hub(Box::new(ChannelBufferCollaborationHub(
                 channel_buffer.clone(),
             )));
+            comments = EditorComments::attach(
+                &mut editor,
+                CommentSource::Channel(channel_id),
+                language_registry,
+                cx,
+            );
+            let has_comments = comments.is_some();
             editor.set_custom_context_menu(move |_, position, cx| {
                 let this = this.clone();
                 Some(ui::ContextMenu::build(cx, move |menu, _| {
@@ -176,26 +216,46 @@ impl ChannelView {
                         })
                         .ok();
                     })
+                    .when(has_comments, |menu| {
+                        menu.action("Add comment", AddComment.boxed_clone())
+                    })
+                    .action("Show history", ShowNotesHistory.boxed_clone())
                 }))
             });
             editor
         });
-        let _editor_event_subscription =
-            cx.subscribe(&editor, |_, _, e: &EditorEvent, cx| cx.emit(e.clone()));
+        (editor, comments)
+    }
 
-        cx.subscribe(&channel_buffer, Self::handle_channel_buffer_event)
-            .detach();
+    /// Shows the new buffer after the channel buffer rejoined the notes from scratch.
+    fn replace_editor(&mut self, cx: &mut ViewContext<Self>) {
+        let language_registry = self.project.read(cx).languages().clone();
+        self.channel_buffer.update(cx, |channel_buffer, cx| {
+            channel_buffer.buffer().update(cx, |buffer, _| {
+                buffer.set_language_registry(language_registry);
+            })
+        });
 
-        Self {
-            editor,
-            workspace,
-            project,
-            channel_store,
-            channel_buffer,
-            remote_id: None,
-            _editor_event_subscription,
-            _reparse_subscription: None,
+        let was_focused = self.editor.focus_handle(cx).contains_focused(cx);
+        let (editor, comments) = Self::build_editor(&self.project, &self.channel_buffer, cx);
+        self._editor_event_subscription =
+            cx.subscribe(&editor, |_, _, e: &EditorEvent, cx| cx.emit(e.clone()));
+        self.editor = editor;
+        self.comments = comments;
+        if was_focused {
+            self.editor.focus_handle(cx).focus(cx);
         }
+        cx.emit(EditorEvent::TitleChanged);
+        cx.notify();
+    }
+
+    fn show_history(&mut self, _: &ShowNotesHistory, cx: &mut ViewContext<Self>) {
+        let channel_buffer = self.channel_buffer.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.toggle_modal(cx, |cx| NotesHistory::new(channel_buffer, cx));
+            })
+            .ok();
     }
 
     fn focus_position_from_link(
@@ -280,6 +340,12 @@ impl ChannelView {
         self.channel_buffer.read(cx).channel(cx)
     }
 
+    pub fn reveal_comment_thread(&mut self, thread_id: u64, cx: &mut ViewContext<Self>) {
+        if let Some(comments) = self.comments.as_ref() {
+            comments.update(cx, |comments, cx| comments.reveal_thread(thread_id, cx));
+        }
+    }
+
     fn handle_channel_buffer_event(
         &mut self,
         _: Model<ChannelBuffer>,
@@ -291,6 +357,13 @@ impl ChannelView {
                 editor.set_read_only(true);
                 cx.notify();
             }),
+            ChannelBufferEvent::Offline | ChannelBufferEvent::Reconnected => {
+                self.editor.update(cx, |_, cx| {
+                    cx.emit(editor::EditorEvent::TitleChanged);
+                    cx.notify()
+                });
+            }
+            ChannelBufferEvent::BufferReplaced => self.replace_editor(cx),
             ChannelBufferEvent::ChannelChanged => {
                 self.editor.update(cx, |_, cx| {
                     cx.emit(editor::EditorEvent::TitleChanged);
@@ -339,6 +412,7 @@ impl Render for ChannelView {
         div()
             .size_full()
             .on_action(cx.listener(Self::copy_link))
+            .on_action(cx.listener(Self::show_history))
             .child(self.editor.clone())
     }
 }
@@ -369,12 +443,16 @@ impl Item for ChannelView {
 
     fn tab_content(&self, _: Option<usize>, selected: bool, cx: &WindowContext) -> AnyElement {
         let label = if let Some(channel) = self.channel(cx) {
+            let channel_buffer = self.channel_buffer.read(cx);
             match (
-                self.channel_buffer.read(cx).buffer().read(cx).read_only(),
-                self.channel_buffer.read(cx).is_connected(),
+                channel_buffer.buffer().read(cx).read_only(),
+                channel_buffer.is_connected(),
             ) {
                 (false, true) => format!("#{}", channel.name),
                 (true, true) => format!("#{} (read-only)", channel.name),
+                (_, false) if channel_buffer.is_offline() => {
+                    format!("#{} (offline)", channel.name)
+                }
                 (_, false) => format!("#{} (disconnected)", channel.name),
             }
         } else {
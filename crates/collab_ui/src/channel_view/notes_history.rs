@@ -0,0 +1,244 @@
+use crate::chat_panel::format_timestamp;
+use channel::{ChannelBuffer, NotesSnapshot};
+use collections::HashMap;
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, Render, ViewContext,
+};
+use similar::{ChangeTag, TextDiff};
+use time::{OffsetDateTime, UtcOffset};
+use ui::prelude::*;
+use util::ResultExt as _;
+use workspace::ModalView;
+
+/// A version of the notes that can be compared with another one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NotesVersion {
+    Snapshot(u64),
+    Current,
+}
+
+struct DiffLine {
+    tag: ChangeTag,
+    text: SharedString,
+}
+
+/// Lists the snapshots of a channel's notes, and shows how the notes changed
+/// between any two of them.
+pub struct NotesHistory {
+    channel_buffer: Model<ChannelBuffer>,
+    snapshots: Option<Vec<NotesSnapshot>>,
+    snapshot_texts: HashMap<u64, String>,
+    from: Option<NotesVersion>,
+    to: NotesVersion,
+    diff: Vec<DiffLine>,
+    error: Option<SharedString>,
+    focus_handle: FocusHandle,
+    local_timezone: UtcOffset,
+}
+
+impl NotesHistory {
+    pub fn new(channel_buffer: Model<ChannelBuffer>, cx: &mut ViewContext<Self>) -> Self {
+        let snapshots = channel_buffer.read(cx).snapshots(cx);
+        cx.spawn(|this, mut cx| async move {
+            let snapshots = snapshots.await;
+            this.update(&mut cx, |this, cx| match snapshots {
+                Ok(snapshots) => {
+                    let latest_epoch = snapshots.last().map(|snapshot| snapshot.epoch);
+                    this.snapshots = Some(snapshots);
+                    if let Some(epoch) = latest_epoch {
+                        this.select(NotesVersion::Snapshot(epoch), true, cx);
+                    }
+                    cx.notify();
+                }
+                Err(error) => {
+                    log::error!("failed to load notes history: {error:?}");
+                    this.error = Some("Couldn't load the history of these notes.".into());
+                    cx.notify();
+                }
+            })
+            .log_err();
+        })
+        .detach();
+
+        Self {
+            channel_buffer,
+            snapshots: None,
+            snapshot_texts: HashMap::default(),
+            from: None,
+            to: NotesVersion::Current,
+            diff: Vec::new(),
+            error: None,
+            focus_handle: cx.focus_handle(),
+            local_timezone: cx.local_timezone(),
+        }
+    }
+
+    fn select(&mut self, version: NotesVersion, from: bool, cx: &mut ViewContext<Self>) {
+        if from {
+            self.from = Some(version);
+        } else {
+            self.to = version;
+        }
+
+        match version {
+            NotesVersion::Snapshot(epoch) if !self.snapshot_texts.contains_key(&epoch) => {
+                let text = self.channel_buffer.read(cx).snapshot_text(epoch, cx);
+                cx.spawn(|this, mut cx| async move {
+                    let text = text.await?;
+                    this.update(&mut cx, |this, cx| {
+                        this.snapshot_texts.insert(epoch, text);
+                        this.update_diff(cx);
+                    })
+                })
+                .detach_and_log_err(cx);
+            }
+            _ => self.update_diff(cx),
+        }
+    }
+
+    fn text(&self, version: NotesVersion, cx: &AppContext) -> Option<String> {
+        match version {
+            NotesVersion::Snapshot(epoch) => self.snapshot_texts.get(&epoch).cloned(),
+            NotesVersion::Current => Some(self.channel_buffer.read(cx).buffer().read(cx).text()),
+        }
+    }
+
+    fn update_diff(&mut self, cx: &mut ViewContext<Self>) {
+        self.diff.clear();
+        let old_text = self.from.and_then(|from| self.text(from, cx));
+        let new_text = self.text(self.to, cx);
+        if let Some((old_text, new_text)) = old_text.zip(new_text) {
+            self.diff = TextDiff::from_lines(&old_text, &new_text)
+                .iter_all_changes()
+                .map(|change| DiffLine {
+                    tag: change.tag(),
+                    text: change.value().trim_end_matches('\n').to_string().into(),
+                })
+                .collect();
+        }
+        cx.notify();
+    }
+
+    fn dismiss(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_version(
+        &self,
+        version: NotesVersion,
+        label: String,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let id = match version {
+            NotesVersion::Snapshot(epoch) => epoch as usize,
+            NotesVersion::Current => usize::MAX,
+        };
+        h_flex()
+            .w_full()
+            .justify_between()
+            .child(Label::new(label).size(LabelSize::Small))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new(("from", id), "From")
+                            .label_size(LabelSize::Small)
+                            .selected(self.from == Some(version))
+                            .on_click(
+                                cx.listener(move |this, _, cx| this.select(version, true, cx)),
+                            ),
+                    )
+                    .child(
+                        Button::new(("to", id), "To")
+                            .label_size(LabelSize::Small)
+                            .selected(self.to == version)
+                            .on_click(
+                                cx.listener(move |this, _, cx| this.select(version, false, cx)),
+                            ),
+                    ),
+            )
+    }
+}
+
+impl EventEmitter<DismissEvent> for NotesHistory {}
+impl ModalView for NotesHistory {}
+
+impl FocusableView for NotesHistory {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for NotesHistory {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let now = OffsetDateTime::now_utc();
+        let versions = self
+            .snapshots
+            .iter()
+            .flatten()
+            .rev()
+            .map(|snapshot| {
+                let label = match snapshot.timestamp {
+                    Some(timestamp) => format!(
+                        "Version {} · {}",
+                        snapshot.epoch,
+                        format_timestamp(now, timestamp, self.local_timezone)
+                    ),
+                    None => format!("Version {}", snapshot.epoch),
+                };
+                (NotesVersion::Snapshot(snapshot.epoch), label)
+            })
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .key_context("NotesHistory")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::dismiss))
+            .elevation_3(cx)
+            .w(rems(40.))
+            .p_2()
+            .gap_2()
+            .child(Label::new("Notes History"))
+            .child(
+                v_flex()
+                    .gap_1()
+                    .child(self.render_version(NotesVersion::Current, "Current".into(), cx))
+                    .children(
+                        versions
+                            .into_iter()
+                            .map(|(version, label)| self.render_version(version, label, cx)),
+                    )
+                    .when(self.snapshots.is_none() && self.error.is_none(), |this| {
+                        this.child(
+                            Label::new("Loading…")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .children(
+                        self.error.clone().map(|error| {
+                            Label::new(error).size(LabelSize::Small).color(Color::Error)
+                        }),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .id("notes-history-diff")
+                    .max_h(rems(24.))
+                    .overflow_y_scroll()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .pt_1()
+                    .children(self.diff.iter().map(|line| {
+                        let (prefix, color) = match line.tag {
+                            ChangeTag::Equal => ("  ", Color::Muted),
+                            ChangeTag::Delete => ("- ", Color::Deleted),
+                            ChangeTag::Insert => ("+ ", Color::Created),
+                        };
+                        Label::new(format!("{prefix}{}", line.text))
+                            .size(LabelSize::Small)
+                            .color(color)
+                    })),
+            )
+    }
+}
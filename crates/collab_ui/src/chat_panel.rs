@@ -1,25 +1,29 @@
 use crate::{collab_panel, ChatPanelSettings};
 use anyhow::Result;
 use call::{room, ActiveCall};
-use channel::{ChannelChat, ChannelChatEvent, ChannelMessage, ChannelMessageId, ChannelStore};
+use channel::{
+    ChannelChat, ChannelChatEvent, ChannelMessage, ChannelMessageEdit, ChannelMessageId,
+    ChannelStore, ChatAttachment,
+};
 use client::Client;
 use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
-use editor::Editor;
+use editor::{scroll::Autoscroll, Editor};
 use gpui::{
-    actions, div, list, prelude::*, px, Action, AppContext, AsyncWindowContext, CursorStyle,
-    DismissEvent, ElementId, EventEmitter, FocusHandle, FocusableView, FontStyle, FontWeight,
-    HighlightStyle, ListOffset, ListScrollEvent, ListState, Model, Render, StyledText,
+    actions, div, list, prelude::*, px, Action, AnyElement, AppContext, AsyncWindowContext,
+    CursorStyle, DismissEvent, ElementId, EventEmitter, FocusHandle, FocusableView, FontStyle,
+    FontWeight, HighlightStyle, ListOffset, ListScrollEvent, ListState, Model, Render, StyledText,
     Subscription, Task, View, ViewContext, VisualContext, WeakView,
 };
-use language::LanguageRegistry;
+use language::{LanguageRegistry, Point};
 use menu::Confirm;
-use message_editor::MessageEditor;
-use project::Fs;
+pub use message_editor::MessageEditor;
+use project::{Fs, ProjectPath, WorktreeId};
 use rich_text::RichText;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
-use std::{sync::Arc, time::Duration};
+use std::{mem, sync::Arc, time::Duration};
+use theme::ThemeSettings;
 use time::{OffsetDateTime, UtcOffset};
 use ui::{
     popover_menu, prelude::*, Avatar, Button, ContextMenu, IconButton, IconName, KeyBinding, Label,
@@ -28,19 +32,24 @@ use ui::{
 use util::{ResultExt, TryFutureExt};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
-    Workspace,
+    Toast, Workspace,
 };
 
 mod message_editor;
 
 const MESSAGE_LOADING_THRESHOLD: usize = 50;
 const CHAT_PANEL_KEY: &'static str = "ChatPanel";
+const QUICK_REACTIONS: [&str; 5] = ["👍", "❤️", "😄", "🎉", "👀"];
+const MAX_ATTACHMENT_ROWS: u32 = 40;
+const MAX_ATTACHMENT_LEN: usize = 8 * 1024;
+const ATTACHMENT_TOAST_ID: usize = 0x4a3f;
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(|workspace: &mut Workspace, _| {
         workspace.register_action(|workspace, _: &ToggleFocus, cx| {
             workspace.toggle_panel_focus::<ChatPanel>(cx);
         });
+        workspace.register_action(ChatPanel::attach_selection);
     })
     .detach();
 }
@@ -63,6 +72,16 @@ pub struct ChatPanel {
     focus_handle: FocusHandle,
     open_context_menu: Option<(u64, Subscription)>,
     highlighted_message: Option<(u64, Task<()>)>,
+    workspace: WeakView<Workspace>,
+    pending_attachments: Vec<ChatAttachment>,
+    open_thread: Option<OpenThread>,
+    edit_histories: HashMap<u64, Vec<ChannelMessageEdit>>,
+}
+
+/// A message whose replies are being shown in place of the channel's messages.
+struct OpenThread {
+    root_id: u64,
+    messages: Vec<ChannelMessage>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -70,7 +89,16 @@ struct SerializedChatPanel {
     width: Option<Pixels>,
 }
 
-actions!(chat_panel, [ToggleFocus, CloseReplyPreview]);
+actions!(
+    chat_panel,
+    [
+        ToggleFocus,
+        CloseReplyPreview,
+        CancelEdit,
+        CloseThread,
+        AttachSelection
+    ]
+);
 
 impl ChatPanel {
     pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
@@ -78,6 +106,7 @@ impl ChatPanel {
         let client = workspace.app_state().client.clone();
         let channel_store = ChannelStore::global(cx);
         let languages = workspace.app_state().languages.clone();
+        let workspace_handle = cx.view().downgrade();
 
         let input_editor = cx.new_view(|cx| {
             MessageEditor::new(
@@ -126,6 +155,10 @@ impl ChatPanel {
                 focus_handle: cx.focus_handle(),
                 open_context_menu: None,
                 highlighted_message: None,
+                workspace: workspace_handle,
+                pending_attachments: Vec::new(),
+                open_thread: None,
+                edit_histories: Default::default(),
             };
 
             if let Some(channel_id) = ActiveCall::global(cx)
@@ -232,6 +265,9 @@ impl ChatPanel {
             let channel_id = chat.read(cx).channel_id;
             {
                 self.markdown_data.clear();
+                self.edit_histories.clear();
+                self.open_thread = None;
+                self.pending_attachments.clear();
                 let chat = chat.read(cx);
                 self.message_list.reset(chat.message_count());
 
@@ -239,6 +275,7 @@ impl ChatPanel {
                 self.message_editor.update(cx, |editor, cx| {
                     editor.set_channel(channel_id, channel_name, cx);
                     editor.clear_reply_to_message_id();
+                    editor.clear_edit_message_id(cx);
                 });
             };
             let subscription = cx.subscribe(&chat, Self::channel_did_change);
@@ -274,10 +311,46 @@ impl ChatPanel {
                     })
                 }
             }
+            ChannelChatEvent::UpdateMessage {
+                message_id,
+                message_ix,
+            } => {
+                self.markdown_data.remove(message_id);
+                if let ChannelMessageId::Saved(id) = message_id {
+                    self.edit_histories.remove(id);
+                }
+                self.message_list.splice(*message_ix..*message_ix + 1, 1);
+            }
         }
+        self.sync_open_thread(cx);
         cx.notify();
     }
 
+    /// Copies any of the chat's loaded messages that belong to the open thread into it.
+    fn sync_open_thread(&mut self, cx: &mut ViewContext<Self>) {
+        let Some((chat, _)) = self.active_chat.as_ref() else {
+            return;
+        };
+        let Some(thread) = self.open_thread.as_mut() else {
+            return;
+        };
+        for message in chat.read(cx).messages().iter() {
+            let ChannelMessageId::Saved(id) = message.id else {
+                continue;
+            };
+            if id != thread.root_id && message.reply_to_message_id != Some(thread.root_id) {
+                continue;
+            }
+            match thread
+                .messages
+                .binary_search_by_key(&message.id, |message| message.id)
+            {
+                Ok(ix) => thread.messages[ix] = message.clone(),
+                Err(ix) => thread.messages.insert(ix, message.clone()),
+            }
+        }
+    }
+
     fn acknowledge_last_message(&mut self, cx: &mut ViewContext<Self>) {
         if self.active && self.is_scrolled_to_bottom {
             if let Some((chat, _)) = &self.active_chat {
@@ -412,10 +485,23 @@ impl ChatPanel {
                 (this_message, is_continuation_from_previous, is_admin)
             });
 
+        self.render_channel_message(message, is_continuation_from_previous, is_admin, false, cx)
+    }
+
+    fn render_channel_message(
+        &mut self,
+        message: ChannelMessage,
+        is_continuation_from_previous: bool,
+        is_admin: bool,
+        in_thread: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let active_chat = &self.active_chat.as_ref().unwrap().0;
         let _is_pending = message.is_pending();
 
         let belongs_to_user = Some(message.sender.id) == self.client.user_id();
         let can_delete_message = belongs_to_user || is_admin;
+        let can_edit_message = belongs_to_user;
 
         let element_id: ElementId = match message.id {
             ChannelMessageId::Saved(id) => ("saved-message", id).into(),
@@ -433,8 +519,10 @@ impl ChatPanel {
             ChannelMessageId::Pending(_) => None,
         };
 
+        // Within a thread, every message replies to the thread's first message.
         let reply_to_message = message
             .reply_to_message_id
+            .filter(|_| !in_thread)
             .map(|id| active_chat.read(cx).find_loaded_message(id))
             .flatten()
             .cloned();
@@ -487,7 +575,9 @@ impl ChatPanel {
                     )
                 })
                 .when(
-                    message.reply_to_message_id.is_some() && reply_to_message.is_none(),
+                    !in_thread
+                        && message.reply_to_message_id.is_some()
+                        && reply_to_message.is_none(),
                     |this| {
                         const MESSAGE_DELETED: &str = "Message has been deleted";
 
@@ -557,7 +647,9 @@ impl ChatPanel {
                                                     Some(Self::render_message_menu(
                                                         &this,
                                                         message_id,
+                                                        can_edit_message,
                                                         can_delete_message,
+                                                        in_thread,
                                                         cx,
                                                     ))
                                                 }),
@@ -565,10 +657,175 @@ impl ChatPanel {
                                     }),
                             ),
                     )
+                })
+                .when_some(message_id, |el, message_id| {
+                    el.children(self.render_attachments(message_id, &message.attachments, cx))
+                        .children(self.render_edit_history(message_id, cx))
+                        .children(self.render_message_footer(message_id, &message, in_thread, cx))
                 }),
         )
     }
 
+    fn render_attachments(
+        &self,
+        message_id: u64,
+        attachments: &[ChatAttachment],
+        cx: &mut ViewContext<Self>,
+    ) -> Option<AnyElement> {
+        if attachments.is_empty() {
+            return None;
+        }
+
+        let buffer_font = ThemeSettings::get_global(cx).buffer_font.family.clone();
+        Some(
+            v_flex()
+                .id(("attachments", message_id))
+                .gap_1()
+                .children(attachments.iter().enumerate().map(|(ix, attachment)| {
+                    let mut location = format!(
+                        "{}:{}",
+                        attachment.path.display(),
+                        attachment.rows.start + 1
+                    );
+                    if attachment.rows.end > attachment.rows.start {
+                        location.push_str(&format!("-{}", attachment.rows.end + 1));
+                    }
+                    let attachment = attachment.clone();
+                    v_flex()
+                        .mt_1()
+                        .border_1()
+                        .border_color(cx.theme().colors().border)
+                        .rounded_md()
+                        .overflow_hidden()
+                        .child(
+                            h_flex()
+                                .id(ix)
+                                .px_1()
+                                .gap_1()
+                                .bg(cx.theme().colors().element_background)
+                                .cursor(CursorStyle::PointingHand)
+                                .tooltip(|cx| Tooltip::text("Open file", cx))
+                                .child(Icon::new(IconName::File).size(IconSize::Small))
+                                .child(Label::new(location).size(LabelSize::Small))
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    this.open_attachment(&attachment, cx)
+                                })),
+                        )
+                        .child(
+                            div()
+                                .px_1()
+                                .max_h_32()
+                                .overflow_hidden()
+                                .font(buffer_font.clone())
+                                .text_ui_xs()
+                                .child(SharedString::from(attachment.text.clone())),
+                        )
+                }))
+                .into_any_element(),
+        )
+    }
+
+    fn render_edit_history(
+        &self,
+        message_id: u64,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<AnyElement> {
+        let edits = self.edit_histories.get(&message_id)?;
+        let now = OffsetDateTime::now_utc();
+        Some(
+            v_flex()
+                .mt_1()
+                .pl_1()
+                .border_l_2()
+                .border_color(cx.theme().colors().border)
+                .text_ui_xs()
+                .children(edits.iter().rev().map(|edit| {
+                    v_flex()
+                        .child(
+                            Label::new(format_timestamp(now, edit.timestamp, self.local_timezone))
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(Label::new(edit.body.clone()).color(Color::Muted))
+                }))
+                .into_any_element(),
+        )
+    }
+
+    fn render_message_footer(
+        &self,
+        message_id: u64,
+        message: &ChannelMessage,
+        in_thread: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<AnyElement> {
+        let show_replies = !in_thread && message.reply_count > 0;
+        if message.reactions.is_empty() && !show_replies && message.edited_at.is_none() {
+            return None;
+        }
+
+        let current_user_id = self.client.user_id();
+        Some(
+            h_flex()
+                .id(("message-footer", message_id))
+                .mt_0p5()
+                .gap_1()
+                .flex_wrap()
+                .children(message.reactions.iter().map(|reaction| {
+                    let emoji = reaction.emoji.clone();
+                    let reacted = current_user_id
+                        .map_or(false, |user_id| reaction.user_ids.contains(&user_id));
+                    Button::new(
+                        SharedString::from(format!("reaction-{}", emoji)),
+                        format!("{} {}", emoji, reaction.user_ids.len()),
+                    )
+                    .label_size(LabelSize::Small)
+                    .style(ButtonStyle::Filled)
+                    .selected(reacted)
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.toggle_reaction(message_id, emoji.clone(), cx)
+                    }))
+                }))
+                .when(show_replies, |el| {
+                    let label = if message.reply_count == 1 {
+                        "1 reply".to_string()
+                    } else {
+                        format!("{} replies", message.reply_count)
+                    };
+                    el.child(
+                        Button::new("replies", label)
+                            .label_size(LabelSize::Small)
+                            .color(Color::Accent)
+                            .on_click(
+                                cx.listener(move |this, _, cx| this.open_thread(message_id, cx)),
+                            ),
+                    )
+                })
+                .when(message.edited_at.is_some(), |el| {
+                    let is_expanded = self.edit_histories.contains_key(&message_id);
+                    el.child(
+                        Button::new("edited", "(edited)")
+                            .label_size(LabelSize::XSmall)
+                            .color(Color::Muted)
+                            .tooltip(move |cx| {
+                                Tooltip::text(
+                                    if is_expanded {
+                                        "Hide previous versions"
+                                    } else {
+                                        "Show previous versions"
+                                    },
+                                    cx,
+                                )
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.toggle_edit_history(message_id, cx)
+                            })),
+                    )
+                })
+                .into_any_element(),
+        )
+    }
+
     fn has_open_menu(&self, message_id: Option<u64>) -> bool {
         match self.open_context_menu.as_ref() {
             Some((id, _)) => Some(*id) == message_id,
@@ -579,28 +836,62 @@ impl ChatPanel {
     fn render_message_menu(
         this: &View<Self>,
         message_id: u64,
+        can_edit_message: bool,
         can_delete_message: bool,
+        in_thread: bool,
         cx: &mut WindowContext,
     ) -> View<ContextMenu> {
         let menu = {
             ContextMenu::build(cx, move |menu, cx| {
-                menu.entry(
-                    "Reply to message",
-                    None,
-                    cx.handler_for(&this, move |this, cx| {
-                        this.message_editor.update(cx, |editor, cx| {
-                            editor.set_reply_to_message_id(message_id);
-                            editor.focus_handle(cx).focus(cx);
-                        })
-                    }),
-                )
-                .when(can_delete_message, move |menu| {
-                    menu.entry(
-                        "Delete message",
-                        None,
-                        cx.handler_for(&this, move |this, cx| this.remove_message(message_id, cx)),
-                    )
-                })
+                let menu = menu
+                    .when(!in_thread, |menu| {
+                        menu.entry(
+                            "Reply to message",
+                            None,
+                            cx.handler_for(&this, move |this, cx| {
+                                this.message_editor.update(cx, |editor, cx| {
+                                    editor.clear_edit_message_id(cx);
+                                    editor.set_reply_to_message_id(message_id);
+                                    editor.focus_handle(cx).focus(cx);
+                                })
+                            }),
+                        )
+                        .entry(
+                            "Reply in thread",
+                            None,
+                            cx.handler_for(&this, move |this, cx| this.open_thread(message_id, cx)),
+                        )
+                    })
+                    .when(can_edit_message, move |menu| {
+                        menu.entry(
+                            "Edit message",
+                            None,
+                            cx.handler_for(&this, move |this, cx| {
+                                this.start_editing(message_id, cx)
+                            }),
+                        )
+                    })
+                    .separator();
+                QUICK_REACTIONS
+                    .iter()
+                    .fold(menu, |menu, emoji| {
+                        menu.entry(
+                            format!("React with {}", emoji),
+                            None,
+                            cx.handler_for(&this, move |this, cx| {
+                                this.toggle_reaction(message_id, emoji.to_string(), cx)
+                            }),
+                        )
+                    })
+                    .when(can_delete_message, move |menu| {
+                        menu.entry(
+                            "Delete message",
+                            None,
+                            cx.handler_for(&this, move |this, cx| {
+                                this.remove_message(message_id, cx)
+                            }),
+                        )
+                    })
             })
         };
         this.update(cx, |this, cx| {
@@ -631,9 +922,26 @@ impl ChatPanel {
 
     fn send(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
         if let Some((chat, _)) = self.active_chat.as_ref() {
-            let message = self
-                .message_editor
-                .update(cx, |editor, cx| editor.take_message(cx));
+            let (edit_message_id, mut message) = self.message_editor.update(cx, |editor, cx| {
+                (editor.edit_message_id(), editor.take_message(cx))
+            });
+
+            if let Some(edit_message_id) = edit_message_id {
+                if let Some(task) = chat
+                    .update(cx, |chat, cx| {
+                        chat.update_message(edit_message_id, message, cx)
+                    })
+                    .log_err()
+                {
+                    task.detach_and_log_err(cx);
+                }
+                return;
+            }
+
+            if message.reply_to_message_id.is_none() {
+                message.reply_to_message_id = self.open_thread.as_ref().map(|t| t.root_id);
+            }
+            message.attachments = mem::take(&mut self.pending_attachments);
 
             if let Some(task) = chat
                 .update(cx, |chat, cx| chat.send_message(message, cx))
@@ -644,6 +952,272 @@ impl ChatPanel {
         }
     }
 
+    fn start_editing(&mut self, message_id: u64, cx: &mut ViewContext<Self>) {
+        let Some(message) = self
+            .active_chat
+            .as_ref()
+            .and_then(|(chat, _)| chat.read(cx).find_loaded_message(message_id))
+            .cloned()
+        else {
+            return;
+        };
+        self.message_editor.update(cx, |editor, cx| {
+            editor.set_edit_message(message_id, &message.body, cx);
+            editor.focus_handle(cx).focus(cx);
+        });
+    }
+
+    fn cancel_edit(&mut self, _: &CancelEdit, cx: &mut ViewContext<Self>) {
+        self.message_editor
+            .update(cx, |editor, cx| editor.clear_edit_message_id(cx));
+    }
+
+    fn toggle_reaction(&mut self, message_id: u64, emoji: String, cx: &mut ViewContext<Self>) {
+        if let Some((chat, _)) = self.active_chat.as_ref() {
+            chat.update(cx, |chat, cx| {
+                chat.toggle_reaction(message_id, emoji, cx)
+                    .detach_and_log_err(cx)
+            })
+        }
+    }
+
+    fn toggle_edit_history(&mut self, message_id: u64, cx: &mut ViewContext<Self>) {
+        if self.edit_histories.remove(&message_id).is_some() {
+            cx.notify();
+            return;
+        }
+        let Some((chat, _)) = self.active_chat.as_ref() else {
+            return;
+        };
+        let edits = chat.update(cx, |chat, cx| chat.message_edits(message_id, cx));
+        cx.spawn(|this, mut cx| async move {
+            let edits = edits.await?;
+            this.update(&mut cx, |this, cx| {
+                this.edit_histories.insert(message_id, edits);
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn open_thread(&mut self, message_id: u64, cx: &mut ViewContext<Self>) {
+        let Some((chat, _)) = self.active_chat.as_ref() else {
+            return;
+        };
+        let messages = chat.update(cx, |chat, cx| chat.load_thread(message_id, cx));
+        cx.spawn(|this, mut cx| async move {
+            let messages = messages.await?;
+            this.update(&mut cx, |this, cx| {
+                this.open_thread = Some(OpenThread {
+                    root_id: message_id,
+                    messages,
+                });
+                this.sync_open_thread(cx);
+                this.message_editor.update(cx, |editor, cx| {
+                    editor.clear_reply_to_message_id();
+                    editor.focus_handle(cx).focus(cx);
+                });
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn close_thread(&mut self, _: &CloseThread, cx: &mut ViewContext<Self>) {
+        if self.open_thread.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    fn add_attachment(&mut self, attachment: ChatAttachment, cx: &mut ViewContext<Self>) {
+        self.pending_attachments.push(attachment);
+        self.message_editor
+            .update(cx, |editor, cx| editor.focus_handle(cx).focus(cx));
+        cx.notify();
+    }
+
+    fn remove_pending_attachment(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if ix < self.pending_attachments.len() {
+            self.pending_attachments.remove(ix);
+            cx.notify();
+        }
+    }
+
+    /// Attaches the lines selected in the active editor to the message being composed.
+    fn attach_selection(
+        workspace: &mut Workspace,
+        _: &AttachSelection,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let project_id = workspace.project().read(cx).remote_id();
+        let attachment = editor.update(cx, |editor, cx| {
+            let buffer = editor.buffer().read(cx).as_singleton()?;
+            let selection = editor.selections.newest::<Point>(cx);
+            let buffer = buffer.read(cx);
+            let file = buffer.file()?;
+
+            let start_row = selection.start.row;
+            let mut end_row = selection.end.row;
+            if end_row > start_row && selection.end.column == 0 {
+                end_row -= 1;
+            }
+            end_row = end_row.min(start_row + MAX_ATTACHMENT_ROWS - 1);
+            let end = Point::new(end_row, buffer.line_len(end_row));
+
+            let mut text = buffer
+                .text_for_range(Point::new(start_row, 0)..end)
+                .collect::<String>();
+            if text.len() > MAX_ATTACHMENT_LEN {
+                let mut len = MAX_ATTACHMENT_LEN;
+                while !text.is_char_boundary(len) {
+                    len -= 1;
+                }
+                text.truncate(len);
+            }
+
+            Some(ChatAttachment {
+                project_id,
+                worktree_id: file.worktree_id() as u64,
+                path: file.path().clone(),
+                rows: start_row..end_row,
+                language: buffer
+                    .language()
+                    .map(|language| language.name().to_string()),
+                text,
+            })
+        });
+        let Some(attachment) = attachment else {
+            return;
+        };
+        if let Some(panel) = workspace.focus_panel::<ChatPanel>(cx) {
+            panel.update(cx, |panel, cx| {
+                if panel.active_chat.is_some() {
+                    panel.add_attachment(attachment, cx);
+                }
+            });
+        }
+    }
+
+    fn open_attachment(&mut self, attachment: &ChatAttachment, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let project_id = workspace.read(cx).project().read(cx).remote_id();
+        if attachment.project_id.is_none() || attachment.project_id != project_id {
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(
+                        ATTACHMENT_TOAST_ID,
+                        "Join the project this snippet was shared from to open it",
+                    ),
+                    cx,
+                )
+            });
+            return;
+        }
+
+        let row = attachment.rows.start;
+        let open_task = workspace.update(cx, |workspace, cx| {
+            workspace.open_path(
+                ProjectPath {
+                    worktree_id: WorktreeId::from_usize(attachment.worktree_id as usize),
+                    path: attachment.path.clone(),
+                },
+                None,
+                true,
+                cx,
+            )
+        });
+        cx.spawn(|_, mut cx| async move {
+            let item = open_task.await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(&mut cx, |editor, cx| {
+                    let point = Point::new(row, 0);
+                    editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                        selections.select_ranges([point..point])
+                    });
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_thread(&mut self, cx: &mut ViewContext<Self>) -> AnyElement {
+        let Some(thread) = self.open_thread.as_ref() else {
+            return div().into_any_element();
+        };
+        let messages = thread.messages.clone();
+        let is_admin = self.active_chat.as_ref().map_or(false, |(chat, _)| {
+            self.channel_store
+                .read(cx)
+                .is_channel_admin(chat.read(cx).channel_id)
+        });
+
+        let mut previous: Option<&ChannelMessage> = None;
+        let mut children = Vec::with_capacity(messages.len());
+        for message in &messages {
+            let is_continuation = previous.map_or(false, |previous| {
+                previous.sender.id == message.sender.id
+                    && message.timestamp - previous.timestamp < Duration::from_secs(5 * 60)
+            });
+            children.push(
+                self.render_channel_message(message.clone(), is_continuation, is_admin, true, cx)
+                    .into_any_element(),
+            );
+            previous = Some(message);
+        }
+
+        v_flex()
+            .id("chat-thread")
+            .full()
+            .overflow_y_scroll()
+            .children(children)
+            .into_any_element()
+    }
+
+    fn render_pending_attachments(&self, cx: &mut ViewContext<Self>) -> Option<AnyElement> {
+        if self.pending_attachments.is_empty() {
+            return None;
+        }
+        Some(
+            v_flex()
+                .px_2()
+                .pt_1()
+                .gap_0p5()
+                .children(
+                    self.pending_attachments
+                        .iter()
+                        .enumerate()
+                        .map(|(ix, attachment)| {
+                            h_flex()
+                                .gap_1()
+                                .child(Icon::new(IconName::File).size(IconSize::Small))
+                                .child(
+                                    Label::new(format!(
+                                        "{}:{}",
+                                        attachment.path.display(),
+                                        attachment.rows.start + 1
+                                    ))
+                                    .size(LabelSize::Small),
+                                )
+                                .child(
+                                    IconButton::new(("remove-attachment", ix), IconName::Close)
+                                        .shape(ui::IconButtonShape::Square)
+                                        .tooltip(|cx| Tooltip::text("Remove attachment", cx))
+                                        .on_click(cx.listener(move |this, _, cx| {
+                                            this.remove_pending_attachment(ix, cx)
+                                        })),
+                                )
+                        }),
+                )
+                .into_any_element(),
+        )
+    }
+
     fn remove_message(&mut self, id: u64, cx: &mut ViewContext<Self>) {
         if let Some((chat, _)) = self.active_chat.as_ref() {
             chat.update(cx, |chat, cx| chat.remove_message(id, cx).detach())
@@ -729,12 +1303,15 @@ impl ChatPanel {
 impl Render for ChatPanel {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let reply_to_message_id = self.message_editor.read(cx).reply_to_message_id();
+        let is_editing = self.message_editor.read(cx).edit_message_id().is_some();
+        let has_open_thread = self.open_thread.is_some();
 
         v_flex()
             .key_context("ChatPanel")
             .track_focus(&self.focus_handle)
             .full()
             .on_action(cx.listener(Self::send))
+            .on_action(cx.listener(Self::close_thread))
             .child(
                 h_flex().z_index(1).child(
                     TabBar::new("chat_header").child(
@@ -742,6 +1319,19 @@ impl Render for ChatPanel {
                             .w_full()
                             .h(rems(ui::Tab::CONTAINER_HEIGHT_IN_REMS))
                             .px_2()
+                            .gap_1()
+                            .when(has_open_thread, |el| {
+                                el.child(
+                                    IconButton::new("close-thread", IconName::ArrowLeft)
+                                        .shape(ui::IconButtonShape::Square)
+                                        .tooltip(|cx| {
+                                            Tooltip::for_action("Back to channel", &CloseThread, cx)
+                                        })
+                                        .on_click(cx.listener(|this, _, cx| {
+                                            this.close_thread(&CloseThread, cx)
+                                        })),
+                                )
+                            })
                             .child(Label::new(
                                 self.active_chat
                                     .as_ref()
@@ -749,12 +1339,17 @@ impl Render for ChatPanel {
                                         Some(format!("#{}", c.0.read(cx).channel(cx)?.name))
                                     })
                                     .unwrap_or("Chat".to_string()),
-                            )),
+                            ))
+                            .when(has_open_thread, |el| {
+                                el.child(Label::new("Thread").color(Color::Muted))
+                            }),
                     ),
                 ),
             )
             .child(div().flex_grow().px_2().map(|this| {
-                if self.active_chat.is_some() {
+                if self.active_chat.is_some() && has_open_thread {
+                    this.child(self.render_thread(cx))
+                } else if self.active_chat.is_some() {
                     this.child(list(self.message_list.clone()).full())
                 } else {
                     this.child(
@@ -830,11 +1425,39 @@ impl Render for ChatPanel {
                     )
                 })
             })
+            .when(is_editing, |el| {
+                el.child(
+                    h_flex()
+                        .w_full()
+                        .py_1()
+                        .px_2()
+                        .justify_between()
+                        .bg(cx.theme().colors().background)
+                        .child(
+                            Label::new("Editing message")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            IconButton::new("cancel-edit", IconName::Close)
+                                .shape(ui::IconButtonShape::Square)
+                                .tooltip(|cx| Tooltip::for_action("Cancel edit", &CancelEdit, cx))
+                                .on_click(
+                                    cx.listener(|this, _, cx| this.cancel_edit(&CancelEdit, cx)),
+                                ),
+                        ),
+                )
+            })
+            .children(
+                self.render_pending_attachments(cx)
+                    .filter(|_| self.active_chat.is_some()),
+            )
             .children(
                 Some(
                     h_flex()
                         .key_context("MessageEditor")
                         .on_action(cx.listener(ChatPanel::close_reply_preview))
+                        .on_action(cx.listener(ChatPanel::cancel_edit))
                         .when(
                             !self.is_scrolled_to_bottom && reply_to_message_id.is_none(),
                             |el| el.border_t_1().border_color(cx.theme().colors().border),
@@ -917,7 +1540,7 @@ impl Panel for ChatPanel {
 
 impl EventEmitter<PanelEvent> for ChatPanel {}
 
-fn format_timestamp(
+pub(crate) fn format_timestamp(
     reference: OffsetDateTime,
     timestamp: OffsetDateTime,
     timezone: UtcOffset,
@@ -983,6 +1606,10 @@ mod tests {
             nonce: 5,
             mentions: vec![(ranges[0].clone(), 101), (ranges[1].clone(), 102)],
             reply_to_message_id: None,
+            edited_at: None,
+            reactions: Vec::new(),
+            attachments: Vec::new(),
+            reply_count: 0,
         };
 
         let message = ChatPanel::render_markdown_with_mentions(&language_registry, 102, &message);
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use channel::{ChannelId, ChannelMembership, ChannelStore, MessageParams};
-use client::UserId;
+use client::{User, UserId};
 use collections::{HashMap, HashSet};
 use editor::{AnchorRangeExt, CompletionProvider, Editor, EditorElement, EditorStyle};
 use fuzzy::StringMatchCandidate;
@@ -35,6 +35,7 @@ pub struct MessageEditor {
     mentions_task: Option<Task<()>>,
     channel_id: Option<ChannelId>,
     reply_to_message_id: Option<u64>,
+    edit_message_id: Option<u64>,
 }
 
 struct MessageEditorCompletionProvider(WeakView<MessageEditor>);
@@ -114,6 +115,7 @@ impl MessageEditor {
             mentions: Vec::new(),
             mentions_task: None,
             reply_to_message_id: None,
+            edit_message_id: None,
         }
     }
 
@@ -129,6 +131,28 @@ impl MessageEditor {
         self.reply_to_message_id = None;
     }
 
+    pub fn edit_message_id(&self) -> Option<u64> {
+        self.edit_message_id
+    }
+
+    /// Fills the editor with an existing message's body, so that submitting
+    /// the editor replaces that message rather than sending a new one.
+    pub fn set_edit_message(&mut self, message_id: u64, body: &str, cx: &mut ViewContext<Self>) {
+        self.edit_message_id = Some(message_id);
+        self.reply_to_message_id = None;
+        self.editor.update(cx, |editor, cx| {
+            editor.set_text(body, cx);
+            editor.move_to_end(&editor::actions::MoveToEnd, cx);
+        });
+    }
+
+    pub fn clear_edit_message_id(&mut self, cx: &mut ViewContext<Self>) {
+        if self.edit_message_id.take().is_some() {
+            self.editor.update(cx, |editor, cx| editor.clear(cx));
+            self.mentions.clear();
+        }
+    }
+
     pub fn set_channel(
         &mut self,
         channel_id: u64,
@@ -169,6 +193,20 @@ impl MessageEditor {
         );
     }
 
+    /// Sets the users that can be mentioned when this editor isn't associated with a channel.
+    pub fn set_mentionable_users(
+        &mut self,
+        users: impl IntoIterator<Item = Arc<User>>,
+        _: &mut ViewContext<Self>,
+    ) {
+        self.channel_members.clear();
+        self.channel_members.extend(
+            users
+                .into_iter()
+                .map(|user| (user.github_login.clone(), user.id)),
+        );
+    }
+
     pub fn take_message(&mut self, cx: &mut ViewContext<Self>) -> MessageParams {
         self.editor.update(cx, |editor, cx| {
             let highlights = editor.text_highlights::<Self>(cx);
@@ -187,11 +225,13 @@ impl MessageEditor {
             editor.clear(cx);
             self.mentions.clear();
             let reply_to_message_id = std::mem::take(&mut self.reply_to_message_id);
+            self.edit_message_id = None;
 
             MessageParams {
                 text,
                 mentions,
                 reply_to_message_id,
+                attachments: Vec::new(),
             }
         })
     }
@@ -444,7 +484,8 @@ mod tests {
                 MessageParams {
                     text,
                     mentions: vec![(ranges[0].clone(), 101), (ranges[1].clone(), 102)],
-                    reply_to_message_id: None
+                    reply_to_message_id: None,
+                    attachments: Vec::new(),
                 }
             );
         });
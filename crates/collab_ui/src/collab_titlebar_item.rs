@@ -1,17 +1,23 @@
 use crate::face_pile::FacePile;
 use auto_update::AutoUpdateStatus;
-use call::{ActiveCall, ParticipantLocation, Room};
+use call::{
+    call_settings::CallSettings, media_backend::media_backend, ActiveCall, ParticipantLocation,
+    Room,
+};
 use client::{proto::PeerId, Client, User, UserStore};
+use collections::BTreeMap;
 use gpui::{
-    actions, canvas, div, point, px, Action, AnyElement, AppContext, Element, Hsla,
+    actions, canvas, div, point, px, Action, AnyElement, AppContext, ClipboardItem, Element, Hsla,
     InteractiveElement, IntoElement, Model, ParentElement, Path, Render,
     StatefulInteractiveElement, Styled, Subscription, View, ViewContext, VisualContext, WeakView,
     WindowBounds,
 };
-use project::{Project, RepositoryEntry};
+use project::{Project, ProjectPath, RepositoryEntry};
 use recent_projects::RecentProjects;
+use release_channel::RELEASE_CHANNEL;
 use rpc::proto;
-use std::sync::Arc;
+use settings::Settings as _;
+use std::{iter, sync::Arc, time::Duration};
 use theme::ActiveTheme;
 use ui::{
     h_flex, popover_menu, prelude::*, Avatar, AvatarAudioStatusIndicator, Button, ButtonLike,
@@ -19,16 +25,25 @@ use ui::{
 };
 use util::ResultExt;
 use vcs_menu::{build_branch_list, BranchList, OpenRecent as ToggleVcsMenu};
-use workspace::{notifications::NotifyResultExt, titlebar_height, Workspace};
+use workspace::{
+    notifications::{DetachAndPromptErr, NotifyResultExt},
+    titlebar_height, Toast, Workspace,
+};
 
 const MAX_PROJECT_NAME_LENGTH: usize = 40;
 const MAX_BRANCH_NAME_LENGTH: usize = 40;
 
+/// How long invite links to a shared project can be used for.
+const PROJECT_INVITE_DURATION: Duration = Duration::from_secs(60 * 60);
+
 actions!(
     collab,
     [
         ShareProject,
+        ShareProjectReadOnly,
         UnshareProject,
+        CopyProjectInviteLink,
+        CopyReadOnlyProjectInviteLink,
         ToggleUserMenu,
         ToggleProjectMenu,
         SwitchBranch
@@ -38,11 +53,68 @@ actions!(
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(|workspace: &mut Workspace, cx| {
         let titlebar_item = cx.new_view(|cx| CollabTitlebarItem::new(workspace, cx));
-        workspace.set_titlebar_item(titlebar_item.into(), cx)
+        workspace.set_titlebar_item(titlebar_item.into(), cx);
+        workspace
+            .register_action(share_project_read_only)
+            .register_action(|workspace, _: &CopyProjectInviteLink, cx| {
+                copy_project_invite_link(workspace, false, cx)
+            })
+            .register_action(|workspace, _: &CopyReadOnlyProjectInviteLink, cx| {
+                copy_project_invite_link(workspace, true, cx)
+            })
+            .register_action(|workspace, _: &crate::PushToTalk, cx| {
+                workspace.start_push_to_talk(cx)
+            });
     })
     .detach();
 }
 
+fn share_project_read_only(
+    workspace: &mut Workspace,
+    _: &ShareProjectReadOnly,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    ActiveCall::global(cx)
+        .update(cx, |call, cx| {
+            let scope = proto::ProjectShareScope {
+                read_only: true,
+                ..CallSettings::get_global(cx).share_scope()
+            };
+            call.share_project_with_scope(project, scope, cx)
+        })
+        .detach_and_prompt_err("Failed to share project", cx, |_, _| None);
+}
+
+fn copy_project_invite_link(
+    workspace: &mut Workspace,
+    read_only: bool,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let Some(project_id) = project.read(cx).remote_id() else {
+        return;
+    };
+    let create_invite = ActiveCall::global(cx).update(cx, |call, cx| {
+        call.create_project_invite(project, read_only, PROJECT_INVITE_DURATION, cx)
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        let token = create_invite.await?;
+        let link = format!(
+            "{}project/{project_id}/invite/{token}",
+            RELEASE_CHANNEL.link_prefix()
+        );
+        workspace.update(&mut cx, |workspace, cx| {
+            cx.write_to_clipboard(ClipboardItem::new(link));
+            workspace.show_toast(
+                Toast::new(0, "Invite link copied to clipboard, it expires in an hour"),
+                cx,
+            );
+        })
+    })
+    .detach_and_prompt_err("Failed to create invite link", cx, |_, _| None);
+}
+
 pub struct CollabTitlebarItem {
     project: Model<Project>,
     user_store: Model<UserStore>,
@@ -197,108 +269,129 @@ impl Render for CollabTitlebarItem {
                         let is_screen_sharing = room.is_screen_sharing();
                         let read_only = room.read_only();
 
-                        this.when(is_local && !read_only, |this| {
-                            this.child(
-                                Button::new(
-                                    "toggle_sharing",
-                                    if is_shared { "Unshare" } else { "Share" },
-                                )
-                                .tooltip(move |cx| {
-                                    Tooltip::text(
-                                        if is_shared {
-                                            "Stop sharing project with call participants"
-                                        } else {
-                                            "Share project with call participants"
-                                        },
-                                        cx,
-                                    )
-                                })
-                                .style(ButtonStyle::Subtle)
-                                .selected_style(ButtonStyle::Tinted(TintColor::Accent))
-                                .selected(is_shared)
-                                .label_size(LabelSize::Small)
-                                .on_click(cx.listener(
-                                    move |this, _, cx| {
-                                        if is_shared {
-                                            this.unshare_project(&Default::default(), cx);
-                                        } else {
-                                            this.share_project(&Default::default(), cx);
-                                        }
-                                    },
-                                )),
-                            )
-                        })
-                        .when(is_connected_to_livekit, |el| {
-                            el.child(
-                                div()
-                                    .child(
-                                        IconButton::new("leave-call", ui::IconName::Exit)
-                                            .style(ButtonStyle::Subtle)
-                                            .tooltip(|cx| Tooltip::text("Leave call", cx))
-                                            .icon_size(IconSize::Small)
-                                            .on_click(move |_, cx| {
-                                                ActiveCall::global(cx).update(cx, |call, cx| {
-                                                    if let Some(room) = call.room() {
-                                                        room.update(cx, |room, cx| {
-                                                            room.leave_call(cx)
-                                                        })
-                                                    }
-                                                })
-                                            }),
+                        this.children(self.render_file_presence(room, cx))
+                            .when(is_local && !read_only, |this| {
+                                this.child(
+                                    Button::new(
+                                        "toggle_sharing",
+                                        if is_shared { "Unshare" } else { "Share" },
                                     )
-                                    .pl_2(),
-                            )
-                        })
-                        .when(!read_only && is_connected_to_livekit, |this| {
-                            this.child(
-                                IconButton::new(
-                                    "mute-microphone",
-                                    if is_muted {
-                                        ui::IconName::MicMute
-                                    } else {
-                                        ui::IconName::Mic
-                                    },
+                                    .tooltip(move |cx| {
+                                        Tooltip::text(
+                                            if is_shared {
+                                                "Stop sharing project with call participants"
+                                            } else {
+                                                "Share project with call participants"
+                                            },
+                                            cx,
+                                        )
+                                    })
+                                    .style(ButtonStyle::Subtle)
+                                    .selected_style(ButtonStyle::Tinted(TintColor::Accent))
+                                    .selected(is_shared)
+                                    .label_size(LabelSize::Small)
+                                    .on_click(cx.listener(
+                                        move |this, _, cx| {
+                                            if is_shared {
+                                                this.unshare_project(&Default::default(), cx);
+                                            } else {
+                                                this.share_project(&Default::default(), cx);
+                                            }
+                                        },
+                                    )),
                                 )
-                                .tooltip(move |cx| {
-                                    Tooltip::text(
+                            })
+                            .when(is_shared, |this| {
+                                this.child(
+                                    IconButton::new("copy-project-invite-link", IconName::Link)
+                                        .style(ButtonStyle::Subtle)
+                                        .icon_size(IconSize::Small)
+                                        .tooltip(|cx| {
+                                            Tooltip::for_action(
+                                                "Copy invite link",
+                                                &CopyProjectInviteLink,
+                                                cx,
+                                            )
+                                        })
+                                        .on_click(|_, cx| {
+                                            cx.dispatch_action(CopyProjectInviteLink.boxed_clone())
+                                        }),
+                                )
+                            })
+                            .when(is_connected_to_livekit, |el| {
+                                el.child(
+                                    div()
+                                        .child(
+                                            IconButton::new("leave-call", ui::IconName::Exit)
+                                                .style(ButtonStyle::Subtle)
+                                                .tooltip(|cx| Tooltip::text("Leave call", cx))
+                                                .icon_size(IconSize::Small)
+                                                .on_click(move |_, cx| {
+                                                    ActiveCall::global(cx).update(cx, |call, cx| {
+                                                        if let Some(room) = call.room() {
+                                                            room.update(cx, |room, cx| {
+                                                                room.leave_call(cx)
+                                                            })
+                                                        }
+                                                    })
+                                                }),
+                                        )
+                                        .pl_2(),
+                                )
+                            })
+                            .when(!read_only && is_connected_to_livekit, |this| {
+                                this.child(
+                                    IconButton::new(
+                                        "mute-microphone",
                                         if is_muted {
-                                            "Unmute microphone"
+                                            ui::IconName::MicMute
                                         } else {
-                                            "Mute microphone"
+                                            ui::IconName::Mic
                                         },
-                                        cx,
                                     )
-                                })
-                                .style(ButtonStyle::Subtle)
-                                .icon_size(IconSize::Small)
-                                .selected(is_muted)
-                                .selected_style(ButtonStyle::Tinted(TintColor::Negative))
-                                .on_click(move |_, cx| crate::toggle_mute(&Default::default(), cx)),
-                            )
-                        })
-                        .when(!read_only && is_connected_to_livekit, |this| {
-                            this.child(
-                                IconButton::new("screen-share", ui::IconName::Screen)
-                                    .style(ButtonStyle::Subtle)
-                                    .icon_size(IconSize::Small)
-                                    .selected(is_screen_sharing)
-                                    .selected_style(ButtonStyle::Tinted(TintColor::Accent))
                                     .tooltip(move |cx| {
                                         Tooltip::text(
-                                            if is_screen_sharing {
-                                                "Stop Sharing Screen"
+                                            if is_muted {
+                                                "Unmute microphone"
                                             } else {
-                                                "Share Screen"
+                                                "Mute microphone"
                                             },
                                             cx,
                                         )
                                     })
+                                    .style(ButtonStyle::Subtle)
+                                    .icon_size(IconSize::Small)
+                                    .selected(is_muted)
+                                    .selected_style(ButtonStyle::Tinted(TintColor::Negative))
                                     .on_click(move |_, cx| {
-                                        crate::toggle_screen_sharing(&Default::default(), cx)
+                                        crate::toggle_mute(&Default::default(), cx)
                                     }),
-                            )
-                        })
-                        .child(div().pr_2())
+                                )
+                                .child(self.render_microphone_menu())
+                            })
+                            .when(!read_only && is_connected_to_livekit, |this| {
+                                this.child(
+                                    IconButton::new("screen-share", ui::IconName::Screen)
+                                        .style(ButtonStyle::Subtle)
+                                        .icon_size(IconSize::Small)
+                                        .selected(is_screen_sharing)
+                                        .selected_style(ButtonStyle::Tinted(TintColor::Accent))
+                                        .tooltip(move |cx| {
+                                            Tooltip::text(
+                                                if is_screen_sharing {
+                                                    "Stop Sharing Screen"
+                                                } else {
+                                                    "Share Screen"
+                                                },
+                                                cx,
+                                            )
+                                        })
+                                        .on_click(move |_, cx| {
+                                            crate::toggle_screen_sharing(&Default::default(), cx)
+                                        }),
+                                )
+                            })
+                            .child(div().pr_2())
                     })
                     .map(|el| {
                         let status = self.client.status();
@@ -366,6 +459,72 @@ impl CollabTitlebarItem {
     // resolve if you are in a room -> render_project_owner
     // render_project_owner -> resolve if you are in a room -> Option<foo>
 
+    /// Lists the files that collaborators have open in this project, so that
+    /// the user can jump to them without following anyone.
+    fn render_file_presence(
+        &self,
+        room: &Room,
+        cx: &ViewContext<Self>,
+    ) -> Option<impl IntoElement> {
+        let project_id = self.project.read(cx).remote_id()?;
+        let mut files = BTreeMap::<ProjectPath, Vec<(SharedString, PeerId)>>::default();
+        for participant in room.remote_participants().values() {
+            if participant.location != (ParticipantLocation::SharedProject { project_id }) {
+                continue;
+            }
+            if let Some(path) = participant.active_path.clone() {
+                files.entry(path).or_default().push((
+                    participant.user.github_login.clone().into(),
+                    participant.peer_id,
+                ));
+            }
+        }
+        if files.is_empty() {
+            return None;
+        }
+
+        let workspace = self.workspace.clone();
+        Some(
+            popover_menu("file-presence")
+                .menu(move |cx| {
+                    let files = files.clone();
+                    let workspace = workspace.clone();
+                    ContextMenu::build(cx, move |mut menu, _| {
+                        menu = menu.header("Open Files");
+                        for (path, participants) in files {
+                            let logins = participants
+                                .iter()
+                                .map(|(login, _)| login.as_ref())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let peer_id = participants[0].1;
+                            let workspace = workspace.clone();
+                            menu = menu.entry(
+                                format!("{} · {logins}", path.path.to_string_lossy()),
+                                None,
+                                move |cx| {
+                                    workspace
+                                        .update(cx, |workspace, cx| {
+                                            workspace.open_participant_location(peer_id, cx)
+                                        })
+                                        .ok();
+                                },
+                            );
+                        }
+                        menu
+                    })
+                    .into()
+                })
+                .trigger(
+                    IconButton::new("file-presence", IconName::File)
+                        .style(ButtonStyle::Subtle)
+                        .icon_size(IconSize::Small)
+                        .tooltip(|cx| Tooltip::text("Files open by collaborators", cx)),
+                )
+                .anchor(gpui::AnchorCorner::TopRight),
+        )
+    }
+
     pub fn render_project_host(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
         let host = self.project.read(cx).host()?;
         let host_user = self.user_store.read(cx).get_cached_user(host.user_id)?;
@@ -673,6 +832,43 @@ impl CollabTitlebarItem {
             })
     }
 
+    fn render_microphone_menu(&self) -> impl IntoElement {
+        let workspace = self.workspace.clone();
+        popover_menu("microphone-menu")
+            .menu(move |cx| {
+                let fs = workspace.upgrade()?.read(cx).app_state().fs.clone();
+                let selected = CallSettings::get_global(cx).microphone.clone();
+                let devices = media_backend(cx)
+                    .audio_input_devices()
+                    .log_err()
+                    .unwrap_or_default();
+                ContextMenu::build(cx, move |mut menu, _| {
+                    menu = menu.header("Microphone");
+                    for name in iter::once(None).chain(devices.into_iter().map(Some)) {
+                        let label = name.as_deref().unwrap_or("System Default");
+                        let label = if name == selected {
+                            format!("✓ {label}")
+                        } else {
+                            label.to_string()
+                        };
+                        let fs = fs.clone();
+                        menu = menu.entry(label, None, move |cx| {
+                            crate::select_microphone(name.clone(), fs.clone(), cx)
+                        });
+                    }
+                    menu
+                })
+                .into()
+            })
+            .trigger(
+                IconButton::new("microphone-menu", IconName::ChevronDown)
+                    .style(ButtonStyle::Subtle)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::text("Select microphone", cx)),
+            )
+            .anchor(gpui::AnchorCorner::TopRight)
+    }
+
     pub fn render_user_menu_button(&mut self, cx: &mut ViewContext<Self>) -> impl Element {
         if let Some(user) = self.user_store.read(cx).current_user() {
             popover_menu("user-menu")
@@ -2,6 +2,7 @@ pub mod channel_view;
 pub mod chat_panel;
 pub mod collab_panel;
 mod collab_titlebar_item;
+pub mod comment_threads;
 mod face_pile;
 pub mod notification_panel;
 pub mod notifications;
@@ -9,7 +10,10 @@ mod panel_settings;
 
 use std::{rc::Rc, sync::Arc};
 
-use call::{report_call_event_for_room, ActiveCall};
+use call::{
+    call_settings::CallSettings, media_backend::media_backend, report_call_event_for_room,
+    ActiveCall,
+};
 pub use collab_panel::CollabPanel;
 pub use collab_titlebar_item::CollabTitlebarItem;
 use gpui::{
@@ -19,10 +23,15 @@ use gpui::{
 pub use panel_settings::{
     ChatPanelSettings, CollaborationPanelSettings, NotificationPanelSettings,
 };
+use project::Fs;
 use settings::Settings;
+use util::ResultExt;
 use workspace::{notifications::DetachAndPromptErr, AppState};
 
-actions!(collab, [ToggleScreenSharing, ToggleMute, LeaveCall]);
+actions!(
+    collab,
+    [ToggleScreenSharing, ToggleMute, PushToTalk, LeaveCall]
+);
 
 pub fn init(app_state: &Arc<AppState>, cx: &mut AppContext) {
     CollaborationPanelSettings::register(cx);
@@ -33,6 +42,7 @@ pub fn init(app_state: &Arc<AppState>, cx: &mut AppContext) {
     collab_titlebar_item::init(cx);
     collab_panel::init(cx);
     channel_view::init(cx);
+    comment_threads::init(cx);
     chat_panel::init(cx);
     notification_panel::init(cx);
     notifications::init(&app_state, cx);
@@ -82,6 +92,17 @@ pub fn toggle_mute(_: &ToggleMute, cx: &mut AppContext) {
     }
 }
 
+/// Captures call audio from the microphone with the given name, or from the
+/// system's default microphone, and remembers the choice in the settings.
+pub fn select_microphone(name: Option<String>, fs: Arc<dyn Fs>, cx: &mut AppContext) {
+    media_backend(cx)
+        .set_audio_input_device(name.as_deref())
+        .log_err();
+    settings::update_settings_file::<CallSettings>(fs, cx, move |settings| {
+        settings.microphone = name;
+    });
+}
+
 fn notification_window_options(
     screen: Rc<dyn PlatformDisplay>,
     window_size: Size<Pixels>,
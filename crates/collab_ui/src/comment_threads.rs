@@ -0,0 +1,716 @@
+use crate::chat_panel::{format_timestamp, MessageEditor};
+use channel::{ChannelId, ChannelStore, MessageParams};
+use collections::{HashMap, HashSet};
+use comments::{CommentLocation, CommentStore, CommentStoreEvent, CommentThread};
+use editor::{
+    display_map::{BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    scroll::Autoscroll,
+    Editor, EditorMode, RenderBlock,
+};
+use gpui::{
+    actions, AppContext, EventEmitter, FocusHandle, FocusableView, Model, Render, Subscription,
+    View, ViewContext, WeakView,
+};
+use language::{Anchor, Buffer, LanguageRegistry, Point, ToOffset};
+use menu::Confirm;
+use project::Project;
+use rich_text::RichText;
+use std::{mem, ops::Range, sync::Arc};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{prelude::*, Avatar, Button, Label};
+use util::ResultExt;
+
+actions!(collab, [AddComment]);
+
+/// The rows taken up by a thread's header, reply editor and buttons.
+const THREAD_CHROME_HEIGHT: usize = 4;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|editor: &mut Editor, cx: &mut ViewContext<Editor>| {
+        if editor.mode() != EditorMode::Full {
+            return;
+        }
+        let Some(project) = editor.project().cloned() else {
+            return;
+        };
+        let language_registry = project.read(cx).languages().clone();
+        EditorComments::attach(
+            editor,
+            CommentSource::Project(project),
+            language_registry,
+            cx,
+        );
+    })
+    .detach();
+}
+
+/// Where the buffer displayed in an editor comes from, which determines the
+/// [`CommentLocation`] of its threads and who can be mentioned in them.
+#[derive(Clone)]
+pub enum CommentSource {
+    Channel(ChannelId),
+    Project(Model<Project>),
+}
+
+/// Displays the comment threads anchored in an editor's buffer as blocks below
+/// the lines that they refer to.
+pub struct EditorComments {
+    editor: WeakView<Editor>,
+    buffer: Model<Buffer>,
+    source: CommentSource,
+    location: Option<CommentLocation>,
+    comment_store: Model<CommentStore>,
+    language_registry: Arc<LanguageRegistry>,
+    blocks: HashMap<u64, ThreadBlock>,
+    draft: Option<DraftBlock>,
+    pending_reveal: Option<u64>,
+    _subscriptions: Vec<Subscription>,
+}
+
+struct ThreadBlock {
+    block_id: BlockId,
+    height: u8,
+    view: View<CommentThreadView>,
+    _subscription: Subscription,
+}
+
+struct DraftBlock {
+    block_id: BlockId,
+    range: Range<Anchor>,
+    _view: View<CommentThreadView>,
+    _subscription: Subscription,
+}
+
+impl EditorComments {
+    /// Shows comment threads in the given editor, and lets the user start new
+    /// ones with the [`AddComment`] action.
+    pub fn attach(
+        editor: &mut Editor,
+        source: CommentSource,
+        language_registry: Arc<LanguageRegistry>,
+        cx: &mut ViewContext<Editor>,
+    ) -> Option<View<Self>> {
+        let comment_store = CommentStore::try_global(cx)?;
+        let buffer = editor.buffer().read(cx).as_singleton()?;
+        let editor_handle = cx.view().downgrade();
+        let comments = cx.new_view(|cx| {
+            Self::new(
+                editor_handle,
+                buffer,
+                source,
+                comment_store,
+                language_registry,
+                cx,
+            )
+        });
+
+        // The editor's action listener keeps the comments alive for as long as the editor.
+        editor.register_action({
+            let comments = comments.clone();
+            move |_: &AddComment, cx| comments.update(cx, |comments, cx| comments.add_comment(cx))
+        });
+        Some(comments)
+    }
+
+    fn new(
+        editor: WeakView<Editor>,
+        buffer: Model<Buffer>,
+        source: CommentSource,
+        comment_store: Model<CommentStore>,
+        language_registry: Arc<LanguageRegistry>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let mut subscriptions = vec![cx.subscribe(&comment_store, Self::handle_store_event)];
+        if let CommentSource::Project(project) = &source {
+            subscriptions.push(cx.subscribe(project, |this, _, event, cx| {
+                if let project::Event::RemoteIdChanged(_) = event {
+                    this.update_location(cx);
+                }
+            }));
+            subscriptions.push(cx.subscribe(&buffer, |this, _, event, cx| {
+                if let language::Event::FileHandleChanged = event {
+                    this.update_location(cx);
+                }
+            }));
+        }
+
+        let mut this = Self {
+            editor,
+            buffer,
+            source,
+            location: None,
+            comment_store,
+            language_registry,
+            blocks: HashMap::default(),
+            draft: None,
+            pending_reveal: None,
+            _subscriptions: subscriptions,
+        };
+        this.location = this.location_for_source(cx);
+        if let Some(location) = this.location.clone() {
+            this.comment_store
+                .update(cx, |store, cx| store.load_threads(location, cx))
+                .detach_and_log_err(cx);
+        }
+        this
+    }
+
+    /// Scrolls to the given thread, once it has been loaded.
+    pub fn reveal_thread(&mut self, thread_id: u64, cx: &mut ViewContext<Self>) {
+        let Some(location) = &self.location else {
+            return;
+        };
+        let Some(thread) = self
+            .comment_store
+            .read(cx)
+            .threads(location)
+            .iter()
+            .find(|thread| thread.id == thread_id)
+            .cloned()
+        else {
+            self.pending_reveal = Some(thread_id);
+            return;
+        };
+        self.pending_reveal = None;
+
+        let start = self.resolve_range(&thread.range, cx).start;
+        self.editor
+            .update(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let Some((excerpt_id, _, _)) = snapshot.as_singleton() else {
+                    return;
+                };
+                let start = snapshot.anchor_in_excerpt(*excerpt_id, start);
+                editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                    selections.select_anchor_ranges([start..start])
+                });
+            })
+            .ok();
+    }
+
+    fn location_for_source(&self, cx: &AppContext) -> Option<CommentLocation> {
+        match &self.source {
+            CommentSource::Channel(channel_id) => Some(CommentLocation::ChannelBuffer(*channel_id)),
+            CommentSource::Project(project) => {
+                let project_id = project.read(cx).remote_id()?;
+                let file = project::File::from_dyn(self.buffer.read(cx).file())?;
+                Some(CommentLocation::ProjectPath {
+                    project_id,
+                    worktree_id: file.worktree_id(cx).to_proto(),
+                    path: file.path().clone(),
+                })
+            }
+        }
+    }
+
+    fn update_location(&mut self, cx: &mut ViewContext<Self>) {
+        let location = self.location_for_source(cx);
+        if location == self.location {
+            return;
+        }
+
+        self.location = location.clone();
+        self.remove_draft(cx);
+        self.sync_threads(cx);
+        if let Some(location) = location {
+            self.comment_store
+                .update(cx, |store, cx| store.load_threads(location, cx))
+                .detach_and_log_err(cx);
+        }
+    }
+
+    fn handle_store_event(
+        &mut self,
+        _: Model<CommentStore>,
+        event: &CommentStoreEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match event {
+            CommentStoreEvent::ThreadsUpdated(location) => {
+                if Some(location) == self.location.as_ref() {
+                    self.sync_threads(cx);
+                    if let Some(thread_id) = self.pending_reveal {
+                        self.reveal_thread(thread_id, cx);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Thread anchors don't carry a buffer id, so give them this replica's id
+    /// before resolving them.
+    fn resolve_range(&self, range: &Range<Anchor>, cx: &AppContext) -> Range<Anchor> {
+        let buffer_id = self.buffer.read(cx).remote_id();
+        let resolve = |mut anchor: Anchor| {
+            if anchor != Anchor::MIN && anchor != Anchor::MAX {
+                anchor.buffer_id = Some(buffer_id);
+            }
+            anchor
+        };
+        resolve(range.start)..resolve(range.end)
+    }
+
+    fn sync_threads(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        let threads = self.location.as_ref().map_or(Vec::new(), |location| {
+            self.comment_store.read(cx).threads(location).to_vec()
+        });
+        let threads = threads
+            .into_iter()
+            .filter_map(|mut thread| {
+                thread.range = self.resolve_range(&thread.range, cx);
+                let buffer = self.buffer.read(cx);
+                (buffer.can_resolve(&thread.range.start) && buffer.can_resolve(&thread.range.end))
+                    .then_some(thread)
+            })
+            .collect::<Vec<_>>();
+
+        let mut old_blocks = mem::take(&mut self.blocks);
+        let mut blocks_to_remove = HashSet::default();
+        let mut blocks_to_insert = Vec::new();
+        for thread in &threads {
+            if let Some(block) = old_blocks.remove(&thread.id) {
+                block
+                    .view
+                    .update(cx, |view, cx| view.set_thread(Some(thread.clone()), cx));
+                if block.view.read(cx).height() == block.height {
+                    self.blocks.insert(thread.id, block);
+                    continue;
+                }
+                blocks_to_remove.insert(block.block_id);
+                blocks_to_insert.push((thread.id, thread.range.end, block.view));
+            } else {
+                let view = cx.new_view(|cx| {
+                    CommentThreadView::new(
+                        Some(thread.clone()),
+                        &self.source,
+                        self.language_registry.clone(),
+                        cx,
+                    )
+                });
+                blocks_to_insert.push((thread.id, thread.range.end, view));
+            }
+        }
+        blocks_to_remove.extend(old_blocks.into_values().map(|block| block.block_id));
+
+        let new_blocks = editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let Some((excerpt_id, _, _)) = snapshot.as_singleton() else {
+                return Vec::new();
+            };
+            let excerpt_id = *excerpt_id;
+
+            editor.remove_blocks(blocks_to_remove, None, cx);
+            let block_ids = editor.insert_blocks(
+                blocks_to_insert
+                    .iter()
+                    .map(|(_, position, view)| BlockProperties {
+                        position: snapshot.anchor_in_excerpt(excerpt_id, *position),
+                        height: view.read(cx).height(),
+                        style: BlockStyle::Flex,
+                        render: render_thread_block(view.clone()),
+                        disposition: BlockDisposition::Below,
+                    })
+                    .collect::<Vec<_>>(),
+                None,
+                cx,
+            );
+
+            let unresolved_ranges = threads
+                .iter()
+                .filter(|thread| !thread.resolved)
+                .map(|thread| {
+                    snapshot.anchor_in_excerpt(excerpt_id, thread.range.start)
+                        ..snapshot.anchor_in_excerpt(excerpt_id, thread.range.end)
+                })
+                .collect();
+            editor.highlight_background::<Self>(
+                unresolved_ranges,
+                |theme| theme.editor_document_highlight_read_background,
+                cx,
+            );
+
+            block_ids
+        });
+
+        for ((thread_id, _, view), block_id) in blocks_to_insert.into_iter().zip(new_blocks) {
+            let _subscription = cx.subscribe(&view, Self::handle_thread_event);
+            self.blocks.insert(
+                thread_id,
+                ThreadBlock {
+                    block_id,
+                    height: view.read(cx).height(),
+                    view,
+                    _subscription,
+                },
+            );
+        }
+    }
+
+    fn add_comment(&mut self, cx: &mut ViewContext<Self>) {
+        if self.location.is_none() {
+            return;
+        }
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        self.remove_draft(cx);
+
+        let selection = editor.read(cx).selections.newest_anchor().clone();
+        let buffer = self.buffer.read(cx);
+        let mut start = selection.start.text_anchor.to_offset(buffer);
+        let mut end = selection.end.text_anchor.to_offset(buffer);
+        // An empty selection comments on the whole line containing the cursor.
+        if start == end {
+            let row = buffer.offset_to_point(start).row;
+            start = Point::new(row, 0).to_offset(buffer);
+            end = Point::new(row, buffer.line_len(row)).to_offset(buffer);
+        }
+        let range = buffer.anchor_before(start)..buffer.anchor_after(end);
+
+        let view = cx.new_view(|cx| {
+            CommentThreadView::new(None, &self.source, self.language_registry.clone(), cx)
+        });
+        let block_id = editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let excerpt_id = *snapshot.as_singleton()?.0;
+            editor
+                .insert_blocks(
+                    [BlockProperties {
+                        position: snapshot.anchor_in_excerpt(excerpt_id, range.end),
+                        height: view.read(cx).height(),
+                        style: BlockStyle::Flex,
+                        render: render_thread_block(view.clone()),
+                        disposition: BlockDisposition::Below,
+                    }],
+                    Some(Autoscroll::fit()),
+                    cx,
+                )
+                .pop()
+        });
+        let Some(block_id) = block_id else {
+            return;
+        };
+
+        cx.focus_view(&view);
+        self.draft = Some(DraftBlock {
+            block_id,
+            range,
+            _subscription: cx.subscribe(&view, Self::handle_thread_event),
+            _view: view,
+        });
+    }
+
+    fn remove_draft(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(draft) = self.draft.take() {
+            self.editor
+                .update(cx, |editor, cx| {
+                    editor.remove_blocks(HashSet::from_iter([draft.block_id]), None, cx);
+                    editor.focus(cx);
+                })
+                .ok();
+        }
+    }
+
+    fn handle_thread_event(
+        &mut self,
+        view: View<CommentThreadView>,
+        event: &CommentThreadEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let thread_id = view.read(cx).thread_id();
+        match event {
+            CommentThreadEvent::Submitted(message) => {
+                let MessageParams { text, mentions, .. } = message;
+                if let Some(thread_id) = thread_id {
+                    self.comment_store
+                        .update(cx, |store, cx| {
+                            store.reply(thread_id, text.clone(), mentions.clone(), cx)
+                        })
+                        .detach_and_log_err(cx);
+                } else if let Some((location, draft)) =
+                    self.location.clone().zip(self.draft.as_ref())
+                {
+                    let create_thread = self.comment_store.update(cx, |store, cx| {
+                        store.create_thread(
+                            location,
+                            draft.range.clone(),
+                            text.clone(),
+                            mentions.clone(),
+                            cx,
+                        )
+                    });
+                    cx.spawn(|this, mut cx| async move {
+                        create_thread.await?;
+                        this.update(&mut cx, |this, cx| this.remove_draft(cx))
+                    })
+                    .detach_and_log_err(cx);
+                }
+            }
+            CommentThreadEvent::Cancelled => self.remove_draft(cx),
+            CommentThreadEvent::SetResolved(resolved) => {
+                if let Some(thread_id) = thread_id {
+                    self.comment_store
+                        .update(cx, |store, cx| store.set_resolved(thread_id, *resolved, cx))
+                        .detach_and_log_err(cx);
+                }
+            }
+        }
+    }
+}
+
+impl Render for EditorComments {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        // The threads are rendered by the editor's blocks.
+        div()
+    }
+}
+
+fn render_thread_block(view: View<CommentThreadView>) -> RenderBlock {
+    Arc::new(move |cx: &mut BlockContext| {
+        div()
+            .pl(cx.gutter_width + cx.gutter_padding)
+            .pr_4()
+            .child(view.clone())
+            .into_any_element()
+    })
+}
+
+pub enum CommentThreadEvent {
+    Submitted(MessageParams),
+    Cancelled,
+    SetResolved(bool),
+}
+
+/// A single comment thread, or a draft of a new one, along with an editor for replying to it.
+pub struct CommentThreadView {
+    thread: Option<CommentThread>,
+    rendered_comments: Vec<RichText>,
+    message_editor: View<MessageEditor>,
+    language_registry: Arc<LanguageRegistry>,
+    current_user_id: Option<u64>,
+    local_timezone: UtcOffset,
+}
+
+impl EventEmitter<CommentThreadEvent> for CommentThreadView {}
+
+impl CommentThreadView {
+    fn new(
+        thread: Option<CommentThread>,
+        source: &CommentSource,
+        language_registry: Arc<LanguageRegistry>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let channel_store = ChannelStore::global(cx);
+        let current_user_id = channel_store.read(cx).client().user_id();
+        let message_editor = cx.new_view(|cx| {
+            let editor = cx.new_view(|cx| Editor::auto_height(4, cx));
+            let mut message_editor =
+                MessageEditor::new(language_registry.clone(), channel_store, editor, cx);
+            match source {
+                CommentSource::Channel(channel_id) => {
+                    message_editor.set_channel(*channel_id, None, cx);
+                }
+                CommentSource::Project(project) => {
+                    let project = project.read(cx);
+                    let user_store = project.user_store();
+                    let user_store = user_store.read(cx);
+                    let users = project
+                        .collaborators()
+                        .values()
+                        .filter_map(|collaborator| user_store.get_cached_user(collaborator.user_id))
+                        .collect::<Vec<_>>();
+                    message_editor.set_mentionable_users(users, cx);
+                }
+            }
+            let placeholder = if thread.is_some() {
+                "Reply…"
+            } else {
+                "Add a comment…"
+            };
+            message_editor.editor.update(cx, |editor, cx| {
+                editor.set_placeholder_text(placeholder, cx)
+            });
+            message_editor
+        });
+
+        let mut this = Self {
+            thread: None,
+            rendered_comments: Vec::new(),
+            message_editor,
+            language_registry,
+            current_user_id,
+            local_timezone: cx.local_timezone(),
+        };
+        this.set_thread(thread, cx);
+        this
+    }
+
+    fn thread_id(&self) -> Option<u64> {
+        self.thread.as_ref().map(|thread| thread.id)
+    }
+
+    fn set_thread(&mut self, thread: Option<CommentThread>, cx: &mut ViewContext<Self>) {
+        self.rendered_comments = thread
+            .iter()
+            .flat_map(|thread| &thread.comments)
+            .map(|comment| {
+                let mentions = comment
+                    .mentions
+                    .iter()
+                    .map(|(range, user_id)| rich_text::Mention {
+                        range: range.clone(),
+                        is_self_mention: Some(*user_id) == self.current_user_id,
+                    })
+                    .collect::<Vec<_>>();
+                rich_text::render_rich_text(
+                    comment.body.clone(),
+                    &mentions,
+                    &self.language_registry,
+                    None,
+                )
+            })
+            .collect();
+        self.thread = thread;
+        cx.notify();
+    }
+
+    /// The number of editor rows needed to display this thread.
+    fn height(&self) -> u8 {
+        let Some(thread) = self.thread.as_ref() else {
+            return THREAD_CHROME_HEIGHT as u8;
+        };
+        if thread.resolved {
+            return 2;
+        }
+        let comments_height = thread
+            .comments
+            .iter()
+            .map(|comment| 1 + comment.body.lines().count().max(1))
+            .sum::<usize>();
+        (THREAD_CHROME_HEIGHT + comments_height).min(u8::MAX as usize) as u8
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        let message = self
+            .message_editor
+            .update(cx, |message_editor, cx| message_editor.take_message(cx));
+        if !message.text.trim().is_empty() {
+            cx.emit(CommentThreadEvent::Submitted(message));
+        }
+    }
+
+    fn render_comments(&self, cx: &mut ViewContext<Self>) -> Vec<AnyElement> {
+        let Some(thread) = self.thread.as_ref() else {
+            return Vec::new();
+        };
+        let now = OffsetDateTime::now_utc();
+        thread
+            .comments
+            .iter()
+            .zip(&self.rendered_comments)
+            .map(|(comment, body)| {
+                v_flex()
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .text_ui_sm()
+                            .child(Avatar::new(comment.author.avatar_uri.clone()).size(rems(1.)))
+                            .child(Label::new(comment.author.github_login.clone()))
+                            .child(
+                                Label::new(format_timestamp(
+                                    now,
+                                    comment.timestamp,
+                                    self.local_timezone,
+                                ))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .pl(cx.rem_size() + px(4.0))
+                            .child(body.element(("comment", comment.id as usize).into(), cx)),
+                    )
+                    .into_any_element()
+            })
+            .collect()
+    }
+}
+
+impl Render for CommentThreadView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let is_draft = self.thread.is_none();
+        let (resolved, comment_count) = self.thread.as_ref().map_or((false, 0), |thread| {
+            (thread.resolved, thread.comments.len())
+        });
+        let title = if is_draft {
+            "New comment".to_string()
+        } else if resolved {
+            format!("Resolved · {} comments", comment_count)
+        } else if comment_count == 1 {
+            "1 comment".to_string()
+        } else {
+            format!("{} comments", comment_count)
+        };
+
+        v_flex()
+            .key_context("CommentThread")
+            .on_action(cx.listener(Self::confirm))
+            .w_full()
+            .max_w(rems(40.))
+            .my_1()
+            .p_2()
+            .gap_1()
+            .bg(cx.theme().colors().elevated_surface_background)
+            .border_1()
+            .border_color(cx.theme().colors().border)
+            .rounded_md()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(Label::new(title).size(LabelSize::Small).color(Color::Muted))
+                    .when(!is_draft, |this| {
+                        this.child(
+                            Button::new("resolve", if resolved { "Reopen" } else { "Resolve" })
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener(move |_, _, cx| {
+                                    cx.emit(CommentThreadEvent::SetResolved(!resolved))
+                                })),
+                        )
+                    }),
+            )
+            .when(!resolved, |this| {
+                this.children(self.render_comments(cx))
+                    .child(self.message_editor.clone())
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("submit", if is_draft { "Comment" } else { "Reply" })
+                                    .label_size(LabelSize::Small)
+                                    .on_click(
+                                        cx.listener(|this, _, cx| this.confirm(&Confirm, cx)),
+                                    ),
+                            )
+                            .when(is_draft, |this| {
+                                this.child(
+                                    Button::new("cancel", "Cancel")
+                                        .label_size(LabelSize::Small)
+                                        .on_click(cx.listener(|_, _, cx| {
+                                            cx.emit(CommentThreadEvent::Cancelled)
+                                        })),
+                                )
+                            }),
+                    )
+            })
+    }
+}
+
+impl FocusableView for CommentThreadView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.message_editor.read(cx).focus_handle(cx)
+    }
+}
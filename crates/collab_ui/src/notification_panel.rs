@@ -1,4 +1,4 @@
-use crate::{chat_panel::ChatPanel, NotificationPanelSettings};
+use crate::{channel_view::ChannelView, chat_panel::ChatPanel, NotificationPanelSettings};
 use anyhow::Result;
 use channel::ChannelStore;
 use client::{Client, Notification, User, UserStore};
@@ -384,6 +384,32 @@ impl NotificationPanel {
                     can_navigate: true,
                 })
             }
+            Notification::CommentMention {
+                sender_id,
+                channel_id,
+                ..
+            } => {
+                let sender = user_store.get_cached_user(sender_id)?;
+                let text = if let Some(channel_id) = channel_id {
+                    let channel = channel_store.channel_for_id(channel_id)?;
+                    format!(
+                        "{} mentioned you in a comment on #{} notes",
+                        sender.github_login, channel.name
+                    )
+                } else {
+                    format!(
+                        "{} mentioned you in a comment on a shared project",
+                        sender.github_login
+                    )
+                };
+                Some(NotificationPresenter {
+                    icon: "icons/conversations.svg",
+                    text,
+                    needs_response: false,
+                    actor: Some(sender),
+                    can_navigate: channel_id.is_some(),
+                })
+            }
         }
     }
 
@@ -397,7 +423,8 @@ impl NotificationPanel {
             Notification::ContactRequestAccepted { .. } => true,
             Notification::ContactRequest { .. }
             | Notification::ChannelInvitation { .. }
-            | Notification::ChannelMessageMention { .. } => false,
+            | Notification::ChannelMessageMention { .. }
+            | Notification::CommentMention { .. } => false,
         };
 
         if should_mark_as_read {
@@ -420,25 +447,45 @@ impl NotificationPanel {
     }
 
     fn did_click_notification(&mut self, notification: &Notification, cx: &mut ViewContext<Self>) {
-        if let Notification::ChannelMessageMention {
-            message_id,
-            channel_id,
-            ..
-        } = notification.clone()
-        {
-            if let Some(workspace) = self.workspace.upgrade() {
-                cx.window_context().defer(move |cx| {
-                    workspace.update(cx, |workspace, cx| {
-                        if let Some(panel) = workspace.focus_panel::<ChatPanel>(cx) {
-                            panel.update(cx, |panel, cx| {
-                                panel
-                                    .select_channel(channel_id, Some(message_id), cx)
-                                    .detach_and_log_err(cx);
-                            });
-                        }
+        match notification.clone() {
+            Notification::ChannelMessageMention {
+                message_id,
+                channel_id,
+                ..
+            } => {
+                if let Some(workspace) = self.workspace.upgrade() {
+                    cx.window_context().defer(move |cx| {
+                        workspace.update(cx, |workspace, cx| {
+                            if let Some(panel) = workspace.focus_panel::<ChatPanel>(cx) {
+                                panel.update(cx, |panel, cx| {
+                                    panel
+                                        .select_channel(channel_id, Some(message_id), cx)
+                                        .detach_and_log_err(cx);
+                                });
+                            }
+                        });
                     });
-                });
+                }
+            }
+            Notification::CommentMention {
+                thread_id,
+                channel_id: Some(channel_id),
+                ..
+            } => {
+                if let Some(workspace) = self.workspace.upgrade() {
+                    cx.window_context().defer(move |cx| {
+                        let open = ChannelView::open(channel_id, None, workspace, cx);
+                        cx.spawn(|mut cx| async move {
+                            let channel_view = open.await?;
+                            channel_view.update(&mut cx, |channel_view, cx| {
+                                channel_view.reveal_comment_thread(thread_id, cx)
+                            })
+                        })
+                        .detach_and_log_err(cx);
+                    });
+                }
             }
+            _ => {}
         }
     }
 
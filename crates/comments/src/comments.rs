@@ -0,0 +1,346 @@
+use anyhow::{anyhow, Result};
+use channel::{mentions_to_proto, ChannelId};
+use client::{
+    proto,
+    user::{User, UserStore},
+    Client, Subscription, TypedEnvelope, UserId,
+};
+use collections::{HashMap, HashSet};
+use gpui::{
+    AppContext, AsyncAppContext, Context as _, EventEmitter, Global, Model, ModelContext, Task,
+};
+use language::proto::{deserialize_anchor, serialize_anchor};
+use std::{ops::Range, path::Path, sync::Arc};
+use text::Anchor;
+use time::OffsetDateTime;
+
+pub fn init(client: Arc<Client>, user_store: Model<UserStore>, cx: &mut AppContext) {
+    let comment_store = cx.new_model(|cx| CommentStore::new(client, user_store, cx));
+    cx.set_global(GlobalCommentStore(comment_store));
+}
+
+struct GlobalCommentStore(Model<CommentStore>);
+
+impl Global for GlobalCommentStore {}
+
+/// Keeps track of the comment threads that are anchored in the buffers open in this window.
+pub struct CommentStore {
+    client: Arc<Client>,
+    user_store: Model<UserStore>,
+    threads: HashMap<CommentLocation, Vec<CommentThread>>,
+    _subscription: Subscription,
+}
+
+/// The buffer that a comment thread is anchored in. Project buffers are
+/// identified by their path, so that their threads outlive the buffer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CommentLocation {
+    ChannelBuffer(ChannelId),
+    ProjectPath {
+        project_id: u64,
+        worktree_id: u64,
+        path: Arc<Path>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct CommentThread {
+    pub id: u64,
+    pub location: CommentLocation,
+    /// The range that the thread is attached to. These anchors don't carry a
+    /// buffer id, since they are shared by every replica of the buffer.
+    pub range: Range<Anchor>,
+    pub resolved: bool,
+    pub author_id: UserId,
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Comment {
+    pub id: u64,
+    pub author: Arc<User>,
+    pub body: String,
+    pub timestamp: OffsetDateTime,
+    pub mentions: Vec<(Range<usize>, UserId)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommentStoreEvent {
+    ThreadsUpdated(CommentLocation),
+}
+
+impl EventEmitter<CommentStoreEvent> for CommentStore {}
+
+impl CommentStore {
+    pub fn global(cx: &AppContext) -> Model<Self> {
+        cx.global::<GlobalCommentStore>().0.clone()
+    }
+
+    pub fn try_global(cx: &AppContext) -> Option<Model<Self>> {
+        cx.try_global::<GlobalCommentStore>()
+            .map(|store| store.0.clone())
+    }
+
+    pub fn new(
+        client: Arc<Client>,
+        user_store: Model<UserStore>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        Self {
+            _subscription: client
+                .add_message_handler(cx.weak_model(), Self::handle_update_comment_thread),
+            threads: HashMap::default(),
+            user_store,
+            client,
+        }
+    }
+
+    pub fn threads(&self, location: &CommentLocation) -> &[CommentThread] {
+        self.threads.get(location).map_or(&[], |threads| threads)
+    }
+
+    pub fn thread(&self, thread_id: u64) -> Option<&CommentThread> {
+        self.threads
+            .values()
+            .flatten()
+            .find(|thread| thread.id == thread_id)
+    }
+
+    /// Fetches every comment thread anchored in the given buffer from the server.
+    pub fn load_threads(
+        &mut self,
+        location: CommentLocation,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let (channel_id, project_id, worktree_id, path) = location.to_proto();
+        let request = self.client.request(proto::GetCommentThreads {
+            channel_id,
+            project_id,
+            worktree_id,
+            path,
+        });
+        let user_store = self.user_store.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let response = request.await?;
+            let threads =
+                CommentThread::from_proto_vec(response.threads, &user_store, &mut cx).await?;
+            this.update(&mut cx, |this, cx| {
+                this.threads.insert(location.clone(), threads);
+                cx.emit(CommentStoreEvent::ThreadsUpdated(location));
+            })
+        })
+    }
+
+    /// Starts a new thread on the given range, returning the new thread's id.
+    pub fn create_thread(
+        &mut self,
+        location: CommentLocation,
+        range: Range<Anchor>,
+        body: String,
+        mentions: Vec<(Range<usize>, UserId)>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<u64>> {
+        let (channel_id, project_id, worktree_id, path) = location.to_proto();
+        let request = self.client.request(proto::CreateCommentThread {
+            channel_id,
+            project_id,
+            worktree_id,
+            path,
+            start: Some(serialize_anchor(&range.start)),
+            end: Some(serialize_anchor(&range.end)),
+            body,
+            mentions: mentions_to_proto(&mentions),
+        });
+        self.handle_thread_response(request, cx)
+    }
+
+    pub fn reply(
+        &mut self,
+        thread_id: u64,
+        body: String,
+        mentions: Vec<(Range<usize>, UserId)>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<u64>> {
+        let request = self.client.request(proto::CreateComment {
+            thread_id,
+            body,
+            mentions: mentions_to_proto(&mentions),
+        });
+        self.handle_thread_response(request, cx)
+    }
+
+    pub fn set_resolved(
+        &mut self,
+        thread_id: u64,
+        resolved: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<u64>> {
+        let request = self.client.request(proto::SetCommentThreadResolved {
+            thread_id,
+            resolved,
+        });
+        self.handle_thread_response(request, cx)
+    }
+
+    fn handle_thread_response(
+        &mut self,
+        request: impl 'static + std::future::Future<Output = Result<proto::CommentThreadResponse>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<u64>> {
+        let user_store = self.user_store.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let thread = request
+                .await?
+                .thread
+                .ok_or_else(|| anyhow!("missing comment thread"))?;
+            let thread = CommentThread::from_proto(thread, &user_store, &mut cx).await?;
+            let thread_id = thread.id;
+            this.update(&mut cx, |this, cx| this.insert_thread(thread, cx))?;
+            Ok(thread_id)
+        })
+    }
+
+    async fn handle_update_comment_thread(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::UpdateCommentThread>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        let user_store = this.update(&mut cx, |this, _| this.user_store.clone())?;
+        let thread = envelope
+            .payload
+            .thread
+            .ok_or_else(|| anyhow!("missing comment thread"))?;
+        let thread = CommentThread::from_proto(thread, &user_store, &mut cx).await?;
+        this.update(&mut cx, |this, cx| this.insert_thread(thread, cx))
+    }
+
+    fn insert_thread(&mut self, thread: CommentThread, cx: &mut ModelContext<Self>) {
+        let location = thread.location.clone();
+        let threads = self.threads.entry(location.clone()).or_default();
+        match threads.binary_search_by_key(&thread.id, |thread| thread.id) {
+            Ok(ix) => threads[ix] = thread,
+            Err(ix) => threads.insert(ix, thread),
+        }
+        cx.emit(CommentStoreEvent::ThreadsUpdated(location));
+    }
+}
+
+impl CommentLocation {
+    fn to_proto(&self) -> (Option<u64>, Option<u64>, Option<u64>, Option<String>) {
+        match self {
+            CommentLocation::ChannelBuffer(channel_id) => (Some(*channel_id), None, None, None),
+            CommentLocation::ProjectPath {
+                project_id,
+                worktree_id,
+                path,
+            } => (
+                None,
+                Some(*project_id),
+                Some(*worktree_id),
+                Some(path.to_string_lossy().to_string()),
+            ),
+        }
+    }
+
+    fn from_proto(thread: &proto::CommentThread) -> Result<Self> {
+        match (thread.channel_id, thread.project_id, thread.worktree_id) {
+            (Some(channel_id), _, _) => Ok(CommentLocation::ChannelBuffer(channel_id)),
+            (None, Some(project_id), Some(worktree_id)) => Ok(CommentLocation::ProjectPath {
+                project_id,
+                worktree_id,
+                path: Path::new(
+                    thread
+                        .path
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("comment thread has no path"))?,
+                )
+                .into(),
+            }),
+            _ => Err(anyhow!("comment thread has no location")),
+        }
+    }
+}
+
+impl CommentThread {
+    async fn from_proto(
+        thread: proto::CommentThread,
+        user_store: &Model<UserStore>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self::from_proto_vec(vec![thread], user_store, cx)
+            .await?
+            .pop()
+            .unwrap())
+    }
+
+    async fn from_proto_vec(
+        threads: Vec<proto::CommentThread>,
+        user_store: &Model<UserStore>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Vec<Self>> {
+        let unique_user_ids = threads
+            .iter()
+            .flat_map(|thread| &thread.comments)
+            .map(|comment| comment.author_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let users = user_store
+            .update(cx, |user_store, cx| {
+                user_store.get_users(unique_user_ids, cx)
+            })?
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect::<HashMap<_, _>>();
+
+        threads
+            .into_iter()
+            .map(|thread| {
+                let location = CommentLocation::from_proto(&thread)?;
+                let start = thread.start.and_then(deserialize_anchor);
+                let end = thread.end.and_then(deserialize_anchor);
+                let (Some(start), Some(end)) = (start, end) else {
+                    return Err(anyhow!("invalid comment thread range"));
+                };
+                let comments = thread
+                    .comments
+                    .into_iter()
+                    .map(|comment| {
+                        Ok(Comment {
+                            id: comment.id,
+                            author: users
+                                .get(&comment.author_id)
+                                .cloned()
+                                .ok_or_else(|| anyhow!("missing comment author"))?,
+                            body: comment.body,
+                            timestamp: OffsetDateTime::from_unix_timestamp(
+                                comment.timestamp as i64,
+                            )?,
+                            mentions: comment
+                                .mentions
+                                .into_iter()
+                                .filter_map(|mention| {
+                                    let range = mention.range?;
+                                    Some((
+                                        range.start as usize..range.end as usize,
+                                        mention.user_id,
+                                    ))
+                                })
+                                .collect(),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(CommentThread {
+                    id: thread.id,
+                    location,
+                    range: start..end,
+                    resolved: thread.resolved,
+                    author_id: thread.author_id,
+                    comments,
+                })
+            })
+            .collect()
+    }
+}
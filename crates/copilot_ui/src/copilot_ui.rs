@@ -1,5 +1,7 @@
 mod copilot_button;
+mod copilot_completion_provider;
 mod sign_in;
 
 pub use copilot_button::*;
+pub use copilot_completion_provider::*;
 pub use sign_in::*;
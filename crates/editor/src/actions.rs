@@ -119,6 +119,9 @@ impl_actions!(
 gpui::actions!(
     editor,
     [
+        AcceptInlineCompletion,
+        AcceptInlineCompletionLine,
+        AcceptInlineCompletionWord,
         AddSelectionAbove,
         AddSelectionBelow,
         Backspace,
@@ -192,6 +195,7 @@ gpui::actions!(
         Newline,
         NewlineAbove,
         NewlineBelow,
+        NextInlineCompletion,
         NextScreen,
         OpenExcerpts,
         OpenPermalinkToLine,
@@ -199,6 +203,7 @@ gpui::actions!(
         PageDown,
         PageUp,
         Paste,
+        PreviousInlineCompletion,
         Redo,
         RedoSelection,
         Rename,
@@ -227,6 +232,7 @@ gpui::actions!(
         SelectUp,
         ShowCharacterPalette,
         ShowCompletions,
+        ShowInlineCompletion,
         ShuffleLines,
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
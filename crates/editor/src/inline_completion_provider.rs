@@ -0,0 +1,134 @@
+use crate::Direction;
+use gpui::{AppContext, Model, ModelContext};
+use language::Buffer;
+
+/// A source of inline completions, which the editor renders as ghost text
+/// after the newest cursor.
+///
+/// Providers are models so that they can notify the editor whenever a new
+/// completion becomes available.
+pub trait InlineCompletionProvider: 'static + Sized {
+    /// Returns whether completions should be requested at the given position.
+    fn is_enabled(
+        &self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &AppContext,
+    ) -> bool;
+    /// Requests new completions for the given position, replacing any existing ones.
+    fn refresh(
+        &mut self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        debounce: bool,
+        cx: &mut ModelContext<Self>,
+    );
+    /// Switches to the next or previous completion for the given position,
+    /// fetching alternatives if the provider supports them.
+    fn cycle(
+        &mut self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        direction: Direction,
+        cx: &mut ModelContext<Self>,
+    );
+    /// Called after the editor inserted the whole active completion.
+    fn accept(&mut self, cx: &mut ModelContext<Self>);
+    /// Called when the active completion was dismissed without being accepted.
+    fn discard(&mut self, cx: &mut ModelContext<Self>);
+    /// Returns the text that should be inserted at the cursor if the active
+    /// completion is accepted, or `None` if it doesn't apply to this position.
+    fn active_completion_text<'a>(
+        &'a self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &'a AppContext,
+    ) -> Option<&'a str>;
+}
+
+pub(crate) trait InlineCompletionProviderHandle {
+    fn is_enabled(
+        &self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &AppContext,
+    ) -> bool;
+    fn refresh(
+        &self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        debounce: bool,
+        cx: &mut AppContext,
+    );
+    fn cycle(
+        &self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        direction: Direction,
+        cx: &mut AppContext,
+    );
+    fn accept(&self, cx: &mut AppContext);
+    fn discard(&self, cx: &mut AppContext);
+    fn active_completion_text<'a>(
+        &'a self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &'a AppContext,
+    ) -> Option<&'a str>;
+}
+
+impl<T> InlineCompletionProviderHandle for Model<T>
+where
+    T: InlineCompletionProvider,
+{
+    fn is_enabled(
+        &self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &AppContext,
+    ) -> bool {
+        self.read(cx).is_enabled(buffer, cursor_position, cx)
+    }
+
+    fn refresh(
+        &self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        debounce: bool,
+        cx: &mut AppContext,
+    ) {
+        self.update(cx, |this, cx| {
+            this.refresh(buffer, cursor_position, debounce, cx)
+        })
+    }
+
+    fn cycle(
+        &self,
+        buffer: Model<Buffer>,
+        cursor_position: language::Anchor,
+        direction: Direction,
+        cx: &mut AppContext,
+    ) {
+        self.update(cx, |this, cx| {
+            this.cycle(buffer, cursor_position, direction, cx)
+        })
+    }
+
+    fn accept(&self, cx: &mut AppContext) {
+        self.update(cx, |this, cx| this.accept(cx))
+    }
+
+    fn discard(&self, cx: &mut AppContext) {
+        self.update(cx, |this, cx| this.discard(cx))
+    }
+
+    fn active_completion_text<'a>(
+        &'a self,
+        buffer: &Model<Buffer>,
+        cursor_position: language::Anchor,
+        cx: &'a AppContext,
+    ) -> Option<&'a str> {
+        self.read(cx)
+            .active_completion_text(buffer, cursor_position, cx)
+    }
+}
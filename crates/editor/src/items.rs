@@ -8,18 +8,18 @@ use collections::HashSet;
 use futures::future::try_join_all;
 use gpui::{
     div, point, AnyElement, AppContext, AsyncWindowContext, Context, Entity, EntityId,
-    EventEmitter, IntoElement, Model, ParentElement, Pixels, Render, SharedString, Styled,
-    Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
+    EventEmitter, IntoElement, Model, ParentElement, Pixels, PromptLevel, Render, SharedString,
+    Styled, Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
 };
 use language::{
-    proto::serialize_anchor as serialize_text_anchor, Bias, Buffer, CharKind, OffsetRangeExt,
-    Point, SelectionGoal,
+    proto::{serialize_anchor as serialize_text_anchor, serialize_fingerprint},
+    Bias, Buffer, CharKind, OffsetRangeExt, Point, SelectionGoal,
 };
 use project::repository::GitFileStatus;
 use project::{search::SearchQuery, FormatTrigger, Item as _, Project, ProjectPath};
 use rpc::proto::{self, update_view, PeerId};
 use settings::Settings;
-use workspace::item::ItemSettings;
+use workspace::{item::ItemSettings, WorkspaceSettings};
 
 use std::fmt::Write;
 use std::{
@@ -29,6 +29,7 @@ use std::{
     ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use text::{BufferId, Selection};
 use theme::Theme;
@@ -45,6 +46,7 @@ use workspace::{
 };
 
 pub const MAX_TAB_TITLE_LEN: usize = 24;
+const SERIALIZE_UNSAVED_CHANGES_INTERVAL: Duration = Duration::from_secs(1);
 
 impl FollowableItem for Editor {
     fn remote_id(&self) -> Option<ViewId> {
@@ -864,16 +866,28 @@ impl Item for Editor {
 
         if let Some(buffer) = self.buffer().read(cx).as_singleton() {
             serialize(buffer.clone(), workspace_id, item_id, cx);
+            self.schedule_unsaved_changes_serialization(cx);
 
             cx.subscribe(&buffer, |this, buffer, event, cx| {
                 if let Some((_, workspace_id)) = this.workspace.as_ref() {
-                    if let language::Event::FileHandleChanged = event {
-                        serialize(
-                            buffer,
-                            *workspace_id,
-                            cx.view().item_id().as_u64() as ItemId,
-                            cx,
-                        );
+                    match event {
+                        language::Event::FileHandleChanged => {
+                            serialize(
+                                buffer,
+                                *workspace_id,
+                                cx.view().item_id().as_u64() as ItemId,
+                                cx,
+                            );
+                            this.schedule_unsaved_changes_serialization(cx);
+                        }
+                        language::Event::Edited
+                        | language::Event::DirtyChanged
+                        | language::Event::Saved
+                        | language::Event::Reloaded
+                        | language::Event::LanguageChanged => {
+                            this.schedule_unsaved_changes_serialization(cx);
+                        }
+                        _ => {}
                     }
                 }
             })
@@ -881,6 +895,38 @@ impl Item for Editor {
         }
     }
 
+    fn serialize_unsaved_changes(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Task<Result<()>>> {
+        let (_, workspace_id) = self.workspace.as_ref()?;
+        let buffer = self.buffer().read(cx).as_singleton()?;
+        let buffer = buffer.read(cx);
+        // Buffers of remote projects can't be reopened after a restart.
+        if buffer.file().map_or(false, |file| !file.is_local()) {
+            return None;
+        }
+
+        let item_id = cx.view().item_id().as_u64() as ItemId;
+        let contents = (buffer.is_dirty()
+            && WorkspaceSettings::get_global(cx).restore_unsaved_buffers)
+            .then(|| buffer.text());
+        let language = buffer
+            .language()
+            .map(|language| language.name().to_string());
+        // Remember what the file contained, so that changes made to it on disk
+        // in the meantime aren't silently overwritten when restoring.
+        let saved_fingerprint = buffer
+            .file()
+            .filter(|_| contents.is_some())
+            .map(|_| serialize_fingerprint(buffer.saved_version_fingerprint()));
+        let workspace_id = *workspace_id;
+        Some(cx.background_executor().spawn(async move {
+            DB.save_unsaved_contents(item_id, workspace_id, contents, language, saved_fingerprint)
+                .await
+        }))
+    }
+
     fn serialized_item_kind() -> Option<&'static str> {
         Some("Editor")
     }
@@ -926,41 +972,118 @@ impl Item for Editor {
         item_id: ItemId,
         cx: &mut ViewContext<Pane>,
     ) -> Task<Result<View<Self>>> {
-        let project_item: Result<_> = project.update(cx, |project, cx| {
-            // Look up the path with this key associated, create a self with that path
-            let path = DB
-                .get_path(item_id, workspace_id)?
-                .context("No path stored for this editor")?;
-
-            let (worktree, path) = project
-                .find_local_worktree(&path, cx)
-                .with_context(|| format!("No worktree for path: {path:?}"))?;
-            let project_path = ProjectPath {
-                worktree_id: worktree.read(cx).id(),
-                path: path.into(),
+        // Look up the path and unsaved contents with this key associated, create a self with them
+        let serialized_editor = DB
+            .get_serialized_editor(item_id, workspace_id)
+            .and_then(|editor| editor.context("No editor stored for this item"));
+        let (path, contents, language, saved_fingerprint) = match serialized_editor {
+            Ok(serialized_editor) => serialized_editor,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let restore_unsaved_buffers = WorkspaceSettings::get_global(cx).restore_unsaved_buffers;
+        let contents = contents.filter(|_| restore_unsaved_buffers);
+
+        cx.spawn(|pane, mut cx| async move {
+            let buffer = if let Some(path) = path {
+                let (worktree, relative_path) = project
+                    .update(&mut cx, |project, cx| {
+                        project.find_or_create_local_worktree(&path, false, cx)
+                    })?
+                    .await
+                    .with_context(|| format!("No worktree for path: {path:?}"))?;
+                let project_path = ProjectPath {
+                    worktree_id: worktree.update(&mut cx, |worktree, _| worktree.id())?,
+                    path: relative_path.into(),
+                };
+                let (_, project_item) = project
+                    .update(&mut cx, |project, cx| project.open_path(project_path, cx))?
+                    .await?;
+                project_item
+                    .downcast::<Buffer>()
+                    .map_err(|_| anyhow!("Project item at stored path was not a buffer"))?
+            } else if contents.is_some() {
+                let language = match language {
+                    Some(language) => project
+                        .update(&mut cx, |project, _| {
+                            project.languages().language_for_name(&language)
+                        })?
+                        .await
+                        .log_err(),
+                    None => None,
+                };
+                project.update(&mut cx, |project, cx| {
+                    project.create_buffer("", language, cx)
+                })??
+            } else {
+                return Err(anyhow!("No path stored for this editor"));
             };
 
-            Ok(project.open_path(project_path, cx))
-        });
+            // Apply the unsaved contents as a single edit on top of the saved text,
+            // so that they can be undone to get back to what's on disk.
+            if let Some(contents) = contents {
+                let changed_path = buffer.update(&mut cx, |buffer, cx| {
+                    let fingerprint = serialize_fingerprint(buffer.saved_version_fingerprint());
+                    let file = buffer.file()?;
+                    (saved_fingerprint? != fingerprint).then(|| file.full_path(cx))
+                })?;
+                let restore = match changed_path {
+                    Some(changed_path) => {
+                        let message = format!(
+                            "{} changed on disk after its unsaved changes were stored.",
+                            changed_path.display()
+                        );
+                        let answer = pane.update(&mut cx, |_, cx| {
+                            cx.prompt(
+                                PromptLevel::Warning,
+                                &message,
+                                Some("Saving the restored changes overwrites the file."),
+                                &["Restore Unsaved Changes", "Keep Disk Version"],
+                            )
+                        })?;
+                        answer.await.ok() == Some(0)
+                    }
+                    None => true,
+                };
+                if restore {
+                    buffer.update(&mut cx, |buffer, cx| {
+                        if buffer.text() != contents {
+                            buffer.set_text(contents, cx);
+                        }
+                    })?;
+                }
+            }
 
-        project_item
-            .map(|project_item| {
-                cx.spawn(|pane, mut cx| async move {
-                    let (_, project_item) = project_item.await?;
-                    let buffer = project_item
-                        .downcast::<Buffer>()
-                        .map_err(|_| anyhow!("Project item at stored path was not a buffer"))?;
-                    Ok(pane.update(&mut cx, |_, cx| {
-                        cx.new_view(|cx| {
-                            let mut editor = Editor::for_buffer(buffer, Some(project), cx);
-
-                            editor.read_scroll_position_from_db(item_id, workspace_id, cx);
-                            editor
-                        })
-                    })?)
+            Ok(pane.update(&mut cx, |_, cx| {
+                cx.new_view(|cx| {
+                    let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+
+                    editor.read_scroll_position_from_db(item_id, workspace_id, cx);
+                    editor
                 })
+            })?)
+        })
+    }
+}
+
+impl Editor {
+    /// Periodically persists the buffer's unsaved contents, so that they survive a crash.
+    fn schedule_unsaved_changes_serialization(&mut self, cx: &mut ViewContext<Self>) {
+        if self.serialize_unsaved_changes_task.is_some() {
+            return;
+        }
+
+        self.serialize_unsaved_changes_task = Some(cx.spawn(|this, mut cx| async move {
+            cx.background_executor()
+                .timer(SERIALIZE_UNSAVED_CHANGES_INTERVAL)
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.serialize_unsaved_changes_task = None;
+                if let Some(serialization) = this.serialize_unsaved_changes(cx) {
+                    serialization.detach_and_log_err(cx);
+                }
             })
-            .unwrap_or_else(|error| Task::ready(Err(error)))
+            .ok();
+        }));
     }
 }
 
@@ -10,10 +10,13 @@ define_connection!(
     // editors(
     //   item_id: usize,
     //   workspace_id: usize,
-    //   path: PathBuf,
+    //   path: Option<PathBuf>, // None for untitled buffers
     //   scroll_top_row: usize,
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
+    //   contents: Option<String>, // Unsaved contents of the buffer, None if it's clean
+    //   language: Option<String>, // Name of the buffer's language
+    //   saved_fingerprint: Option<String>, // Fingerprint of the file's text when the contents were stored
     // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
@@ -31,13 +34,53 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        // Allow untitled editors and store unsaved contents
+        sql! (
+            CREATE TABLE editors_2(
+                item_id INTEGER NOT NULL,
+                workspace_id INTEGER NOT NULL,
+                path BLOB,
+                scroll_top_row INTEGER NOT NULL DEFAULT 0,
+                scroll_horizontal_offset REAL NOT NULL DEFAULT 0,
+                scroll_vertical_offset REAL NOT NULL DEFAULT 0,
+                contents TEXT,
+                language TEXT,
+                PRIMARY KEY(item_id, workspace_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+            INSERT INTO editors_2(
+                item_id,
+                workspace_id,
+                path,
+                scroll_top_row,
+                scroll_horizontal_offset,
+                scroll_vertical_offset
+            )
+            SELECT
+                item_id,
+                workspace_id,
+                path,
+                scroll_top_row,
+                scroll_horizontal_offset,
+                scroll_vertical_offset
+            FROM editors;
+            DROP TABLE editors;
+            ALTER TABLE editors_2 RENAME TO editors;
+        ),
+        // Detect files that changed on disk since their unsaved contents were stored
+        sql! (
+            ALTER TABLE editors ADD COLUMN saved_fingerprint TEXT;
         )];
 );
 
 impl EditorDb {
+    // Returns the path, unsaved contents, language name and fingerprint of the file's text
     query! {
-        pub fn get_path(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<PathBuf>> {
-            SELECT path FROM editors
+        pub fn get_serialized_editor(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(Option<PathBuf>, Option<String>, Option<String>, Option<String>)>> {
+            SELECT path, contents, language, saved_fingerprint FROM editors
             WHERE item_id = ? AND workspace_id = ?
         }
     }
@@ -55,6 +98,25 @@ impl EditorDb {
         }
     }
 
+    query! {
+        pub async fn save_unsaved_contents(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            contents: Option<String>,
+            language: Option<String>,
+            saved_fingerprint: Option<String>
+        ) -> Result<()> {
+            INSERT INTO editors
+                (item_id, workspace_id, contents, language, saved_fingerprint)
+            VALUES
+                (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT DO UPDATE SET
+                contents = ?3,
+                language = ?4,
+                saved_fingerprint = ?5
+        }
+    }
+
     // Returns the scroll top row, and offset
     query! {
         pub fn get_scroll_position(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(u32, f32, f32)>> {
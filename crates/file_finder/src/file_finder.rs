@@ -5,15 +5,18 @@ use collections::{HashMap, HashSet};
 use editor::{scroll::Autoscroll, Bias, Editor};
 use fuzzy::{CharBag, PathMatch, PathMatchCandidate};
 use gpui::{
-    actions, rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
-    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+    actions, rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView,
+    InteractiveElement, Model, ParentElement, Render, Styled, Task, View, ViewContext,
+    VisualContext, WeakView,
 };
 use itertools::Itertools;
 use picker::{Picker, PickerDelegate};
-use project::{PathMatchCandidateSet, Project, ProjectPath, WorktreeId};
+use postage::stream::Stream as _;
+use project::{EntryKind, PathMatchCandidateSet, Project, ProjectPath, Snapshot, WorktreeId};
 use std::{
     cmp,
     path::{Path, PathBuf},
+    slice,
     sync::{
         atomic::{self, AtomicBool},
         Arc,
@@ -22,9 +25,19 @@ use std::{
 use text::Point;
 use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
 use util::{paths::PathLikeWithPosition, post_inc, ResultExt};
-use workspace::{ModalView, Workspace};
-
-actions!(file_finder, [Toggle]);
+use workspace::{ModalView, SplitDirection, Workspace};
+
+actions!(
+    file_finder,
+    [
+        Toggle,
+        TogglePreview,
+        SplitUp,
+        SplitDown,
+        SplitLeft,
+        SplitRight
+    ]
+);
 
 impl ModalView for FileFinder {}
 
@@ -96,6 +109,20 @@ impl FileFinder {
             picker: cx.new_view(|cx| Picker::new(delegate, cx)),
         }
     }
+
+    fn toggle_preview(&mut self, _: &TogglePreview, cx: &mut ViewContext<Self>) {
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.preview_enabled = !picker.delegate.preview_enabled;
+            picker.delegate.update_preview(cx);
+        });
+        cx.notify();
+    }
+
+    fn open_in_split(&mut self, direction: SplitDirection, cx: &mut ViewContext<Self>) {
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.open_selected(Some(direction), cx)
+        });
+    }
 }
 
 impl EventEmitter<DismissEvent> for FileFinder {}
@@ -107,8 +134,43 @@ impl FocusableView for FileFinder {
 }
 
 impl Render for FileFinder {
-    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
-        v_flex().w(rems(34.)).child(self.picker.clone())
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let preview_editor = self
+            .picker
+            .read(cx)
+            .delegate
+            .preview
+            .as_ref()
+            .map(|preview| preview.editor.clone());
+
+        h_flex()
+            .key_context("FileFinder")
+            .on_action(cx.listener(Self::toggle_preview))
+            .on_action(
+                cx.listener(|this, _: &SplitUp, cx| this.open_in_split(SplitDirection::Up, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SplitDown, cx| this.open_in_split(SplitDirection::Down, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SplitLeft, cx| this.open_in_split(SplitDirection::Left, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SplitRight, cx| {
+                    this.open_in_split(SplitDirection::Right, cx)
+                }),
+            )
+            .items_start()
+            .gap_2()
+            .child(v_flex().w(rems(34.)).child(self.picker.clone()))
+            .children(preview_editor.map(|editor| {
+                v_flex()
+                    .w(rems(48.))
+                    .h(rems(32.))
+                    .overflow_hidden()
+                    .elevation_3(cx)
+                    .child(editor)
+            }))
     }
 }
 
@@ -125,6 +187,18 @@ pub struct FileFinderDelegate {
     selected_index: usize,
     cancel_flag: Arc<AtomicBool>,
     history_items: Vec<FoundPath>,
+    preview_enabled: bool,
+    preview: Option<FilePreview>,
+    preview_task: Task<()>,
+    /// Loads the ignored directories for a query with the `!ignored` filter,
+    /// then searches again.
+    ignored_entries_scan: Option<Task<()>>,
+}
+
+/// A read-only editor showing the file of the highlighted match.
+struct FilePreview {
+    path: ProjectPath,
+    editor: View<Editor>,
 }
 
 /// Use a custom ordering for file finder: the regular one
@@ -193,12 +267,17 @@ impl Matches {
         &mut self,
         history_items: &Vec<FoundPath>,
         currently_opened: Option<&FoundPath>,
+        worktree_root_names: &HashMap<WorktreeId, String>,
         query: &PathLikeWithPosition<FileSearchQuery>,
         new_search_matches: impl Iterator<Item = ProjectPanelOrdMatch>,
         extend_old_matches: bool,
     ) {
-        let matching_history_paths =
-            matching_history_item_paths(history_items, currently_opened, query);
+        let matching_history_paths = matching_history_item_paths(
+            history_items,
+            currently_opened,
+            worktree_root_names,
+            query,
+        );
         let new_search_matches = new_search_matches
             .filter(|path_match| !matching_history_paths.contains_key(&path_match.0.path));
 
@@ -256,11 +335,22 @@ impl Matches {
 fn matching_history_item_paths(
     history_items: &Vec<FoundPath>,
     currently_opened: Option<&FoundPath>,
+    worktree_root_names: &HashMap<WorktreeId, String>,
     query: &PathLikeWithPosition<FileSearchQuery>,
 ) -> HashMap<Arc<Path>, ProjectPanelOrdMatch> {
     let history_items_by_worktrees = history_items
         .iter()
         .chain(currently_opened)
+        .filter(|found_path| {
+            // Filter the same paths as `FilteredCandidateSet` does for search matches.
+            let root_name = worktree_root_names
+                .get(&found_path.project.worktree_id)
+                .map_or("", String::as_str);
+            query
+                .path_like
+                .filters
+                .matches(&Path::new(root_name).join(&found_path.project.path))
+        })
         .filter_map(|found_path| {
             let candidate = PathMatchCandidate {
                 path: &found_path.project.path,
@@ -345,6 +435,140 @@ pub enum Event {
 struct FileSearchQuery {
     raw_query: String,
     file_query_end: Option<usize>,
+    filters: QueryFilters,
+}
+
+/// Modifiers that can be mixed into the query as separate words:
+/// `!ignored` also searches ignored and excluded files, `*.rs` keeps files with the
+/// given extension and `dir/` keeps files located inside of a `dir` directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct QueryFilters {
+    include_ignored: bool,
+    extensions: Vec<String>,
+    directories: Vec<PathBuf>,
+}
+
+impl QueryFilters {
+    /// Splits the modifiers out of the query, returning them with the rest of the query.
+    fn parse(query: &str) -> (Self, String) {
+        let mut filters = Self::default();
+        let mut rest = String::new();
+        for word in query.split_whitespace() {
+            if word == "!ignored" {
+                filters.include_ignored = true;
+            } else if let Some(extension) = word
+                .strip_prefix("*.")
+                .filter(|extension| !extension.is_empty() && !extension.contains(['/', '*']))
+            {
+                filters
+                    .extensions
+                    .push(format!(".{}", extension.to_lowercase()));
+            } else if word.len() > 1 && word.ends_with('/') && !word.starts_with('/') {
+                filters
+                    .directories
+                    .push(PathBuf::from(word.trim_end_matches('/')));
+            } else {
+                rest.push_str(word);
+            }
+        }
+        (filters, rest)
+    }
+
+    fn narrows_results(&self) -> bool {
+        !self.extensions.is_empty() || !self.directories.is_empty()
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let extension_matches = self.extensions.is_empty()
+            || path.file_name().map_or(false, |file_name| {
+                let file_name = file_name.to_string_lossy().to_lowercase();
+                self.extensions
+                    .iter()
+                    .any(|extension| file_name.ends_with(extension.as_str()))
+            });
+        let directory_matches = self.directories.is_empty()
+            || path.parent().map_or(false, |parent| {
+                let parent = parent.components().collect::<Vec<_>>();
+                self.directories.iter().any(|directory| {
+                    let directory = directory.components().collect::<Vec<_>>();
+                    parent
+                        .windows(directory.len())
+                        .any(|window| window == directory.as_slice())
+                })
+            });
+        extension_matches && directory_matches
+    }
+}
+
+/// The files of a worktree that pass the query filters.
+struct FilteredCandidateSet {
+    worktree_id: usize,
+    prefix: Arc<str>,
+    files: Vec<(Arc<Path>, CharBag)>,
+}
+
+impl FilteredCandidateSet {
+    fn new(
+        snapshot: &Snapshot,
+        include_ignored: bool,
+        include_root_name: bool,
+        filters: &QueryFilters,
+    ) -> Self {
+        let prefix = if snapshot.root_entry().map_or(false, |e| e.is_file()) {
+            snapshot.root_name().into()
+        } else if include_root_name {
+            format!("{}/", snapshot.root_name()).into()
+        } else {
+            "".into()
+        };
+        let root_name = Path::new(snapshot.root_name());
+        let files = snapshot
+            .files(include_ignored, 0)
+            .filter(|entry| filters.matches(&root_name.join(&entry.path)))
+            .filter_map(|entry| match entry.kind {
+                EntryKind::File(char_bag) => Some((entry.path.clone(), char_bag)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            worktree_id: snapshot.id().to_usize(),
+            prefix,
+            files,
+        }
+    }
+}
+
+impl<'a> fuzzy::PathMatchCandidateSet<'a> for FilteredCandidateSet {
+    type Candidates = FilteredCandidates<'a>;
+
+    fn id(&self) -> usize {
+        self.worktree_id
+    }
+
+    fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    fn prefix(&self) -> Arc<str> {
+        self.prefix.clone()
+    }
+
+    fn candidates(&'a self, start: usize) -> Self::Candidates {
+        FilteredCandidates(self.files[start.min(self.files.len())..].iter())
+    }
+}
+
+struct FilteredCandidates<'a>(slice::Iter<'a, (Arc<Path>, CharBag)>);
+
+impl<'a> Iterator for FilteredCandidates<'a> {
+    type Item = PathMatchCandidate<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(path, char_bag)| PathMatchCandidate {
+            path,
+            char_bag: *char_bag,
+        })
+    }
 }
 
 impl FileSearchQuery {
@@ -386,6 +610,10 @@ impl FileFinderDelegate {
             selected_index: 0,
             cancel_flag: Arc::new(AtomicBool::new(false)),
             history_items,
+            preview_enabled: false,
+            preview: None,
+            preview_task: Task::ready(()),
+            ignored_entries_scan: None,
         }
     }
 
@@ -404,17 +632,43 @@ impl FileFinderDelegate {
             .visible_worktrees(cx)
             .collect::<Vec<_>>();
         let include_root_name = worktrees.len() > 1;
-        let candidate_sets = worktrees
+        let filters = query.path_like.filters.clone();
+        if filters.include_ignored && self.ignored_entries_scan.is_none() {
+            // Ignored directories are only scanned on demand, load them so that their files can be matched.
+            let scans = worktrees
+                .iter()
+                .filter_map(|worktree| worktree.read(cx).as_local())
+                .flat_map(|worktree| {
+                    worktree
+                        .entries(true)
+                        .filter(|entry| entry.kind.is_unloaded())
+                        .map(|entry| worktree.add_path_prefix_to_scan(entry.path.clone()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            if !scans.is_empty() {
+                self.ignored_entries_scan = Some(cx.spawn(|picker, mut cx| async move {
+                    for mut scan in scans {
+                        scan.recv().await;
+                    }
+                    picker
+                        .update(&mut cx, |picker, cx| {
+                            picker.delegate.ignored_entries_scan = None;
+                            picker.refresh(cx);
+                        })
+                        .ok();
+                }));
+            }
+        }
+        let snapshots = worktrees
             .into_iter()
             .map(|worktree| {
                 let worktree = worktree.read(cx);
-                PathMatchCandidateSet {
-                    snapshot: worktree.snapshot(),
-                    include_ignored: worktree
+                let include_ignored = filters.include_ignored
+                    || worktree
                         .root_entry()
-                        .map_or(false, |entry| entry.is_ignored),
-                    include_root_name,
-                }
+                        .map_or(false, |entry| entry.is_ignored);
+                (worktree.snapshot(), include_ignored)
             })
             .collect::<Vec<_>>();
 
@@ -423,18 +677,72 @@ impl FileFinderDelegate {
         self.cancel_flag = Arc::new(AtomicBool::new(false));
         let cancel_flag = self.cancel_flag.clone();
         cx.spawn(|picker, mut cx| async move {
-            let matches = fuzzy::match_path_sets(
-                candidate_sets.as_slice(),
-                query.path_like.path_query(),
-                relative_to,
-                false,
-                100,
-                &cancel_flag,
-                cx.background_executor().clone(),
-            )
-            .await
-            .into_iter()
-            .map(ProjectPanelOrdMatch);
+            let path_query = query.path_like.path_query();
+            let matches = if filters.narrows_results() {
+                let candidate_sets = cx
+                    .background_executor()
+                    .spawn(async move {
+                        snapshots
+                            .iter()
+                            .map(|(snapshot, include_ignored)| {
+                                FilteredCandidateSet::new(
+                                    snapshot,
+                                    *include_ignored,
+                                    include_root_name,
+                                    &filters,
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .await;
+                if path_query.is_empty() {
+                    candidate_sets
+                        .iter()
+                        .flat_map(|candidate_set| {
+                            candidate_set.files.iter().map(|(path, _)| PathMatch {
+                                score: 1.0,
+                                positions: Vec::new(),
+                                worktree_id: candidate_set.worktree_id,
+                                path: path.clone(),
+                                path_prefix: candidate_set.prefix.clone(),
+                                distance_to_relative_ancestor: usize::MAX,
+                            })
+                        })
+                        .take(100)
+                        .collect()
+                } else {
+                    fuzzy::match_path_sets(
+                        candidate_sets.as_slice(),
+                        path_query,
+                        relative_to,
+                        false,
+                        100,
+                        &cancel_flag,
+                        cx.background_executor().clone(),
+                    )
+                    .await
+                }
+            } else {
+                let candidate_sets = snapshots
+                    .into_iter()
+                    .map(|(snapshot, include_ignored)| PathMatchCandidateSet {
+                        snapshot,
+                        include_ignored,
+                        include_root_name,
+                    })
+                    .collect::<Vec<_>>();
+                fuzzy::match_path_sets(
+                    candidate_sets.as_slice(),
+                    path_query,
+                    relative_to,
+                    false,
+                    100,
+                    &cancel_flag,
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            let matches = matches.into_iter().map(ProjectPanelOrdMatch);
             let did_cancel = cancel_flag.load(atomic::Ordering::Relaxed);
             picker
                 .update(&mut cx, |picker, cx| {
@@ -457,14 +765,26 @@ impl FileFinderDelegate {
         if search_id >= self.latest_search_id {
             self.latest_search_id = search_id;
             let extend_old_matches = self.latest_search_did_cancel
-                && Some(query.path_like.path_query())
-                    == self
-                        .latest_search_query
-                        .as_ref()
-                        .map(|query| query.path_like.path_query());
+                && self
+                    .latest_search_query
+                    .as_ref()
+                    .map_or(false, |latest_query| {
+                        latest_query.path_like.path_query() == query.path_like.path_query()
+                            && latest_query.path_like.filters == query.path_like.filters
+                    });
+            let worktree_root_names = self
+                .project
+                .read(cx)
+                .worktrees()
+                .map(|worktree| {
+                    let worktree = worktree.read(cx);
+                    (worktree.id(), worktree.root_name().to_string())
+                })
+                .collect();
             self.matches.push_new_matches(
                 &self.history_items,
                 self.currently_opened_path.as_ref(),
+                &worktree_root_names,
                 &query,
                 matches.into_iter(),
                 extend_old_matches,
@@ -472,6 +792,7 @@ impl FileFinderDelegate {
             self.latest_search_query = Some(query);
             self.latest_search_did_cancel = did_cancel;
             self.selected_index = self.calculate_selected_index();
+            self.update_preview(cx);
             cx.notify();
         }
     }
@@ -658,93 +979,100 @@ impl FileFinderDelegate {
         }
         0
     }
-}
-
-impl PickerDelegate for FileFinderDelegate {
-    type ListItem = ListItem;
-
-    fn placeholder_text(&self) -> Arc<str> {
-        "Search project files...".into()
-    }
-
-    fn match_count(&self) -> usize {
-        self.matches.len()
-    }
 
-    fn selected_index(&self) -> usize {
-        self.selected_index
+    fn project_path_for_match(&self, path_match: Match, cx: &AppContext) -> Option<ProjectPath> {
+        match path_match {
+            Match::History(found_path, _) => self
+                .project
+                .read(cx)
+                .worktree_for_id(found_path.project.worktree_id, cx)
+                .map(|_| found_path.project.clone()),
+            Match::Search(path_match) => Some(ProjectPath {
+                worktree_id: WorktreeId::from_usize(path_match.0.worktree_id),
+                path: path_match.0.path.clone(),
+            }),
+        }
     }
 
-    fn set_selected_index(&mut self, ix: usize, cx: &mut ViewContext<Picker<Self>>) {
-        self.selected_index = ix;
-        cx.notify();
+    /// Row and column of the query's `path:row:column` suffix, converted to zero-based values.
+    fn query_position(&self) -> Option<(u32, u32)> {
+        let query = self.latest_search_query.as_ref()?;
+        let row = query.row?.saturating_sub(1);
+        let column = query.column.unwrap_or(0).saturating_sub(1);
+        Some((row, column))
     }
 
-    fn separators_after_indices(&self) -> Vec<usize> {
-        let history_items = self.matches.history.len();
-        if history_items == 0 || self.matches.search.is_empty() {
-            Vec::new()
+    fn update_preview(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        let project_path = if self.preview_enabled {
+            self.matches
+                .get(self.selected_index)
+                .and_then(|path_match| self.project_path_for_match(path_match, cx))
         } else {
-            vec![history_items - 1]
+            None
+        };
+        let Some(project_path) = project_path else {
+            self.preview = None;
+            self.preview_task = Task::ready(());
+            self.notify_file_finder(cx);
+            return;
+        };
+
+        if let Some(preview) = &self.preview {
+            if preview.path == project_path {
+                if let Some((row, column)) = self.query_position() {
+                    preview
+                        .editor
+                        .update(cx, |editor, cx| select_position(editor, row, column, cx));
+                }
+                return;
+            }
         }
-    }
 
-    fn update_matches(
-        &mut self,
-        raw_query: String,
-        cx: &mut ViewContext<Picker<Self>>,
-    ) -> Task<()> {
-        let raw_query = raw_query.replace(" ", "");
-        let raw_query = raw_query.trim();
-        if raw_query.is_empty() {
-            let project = self.project.read(cx);
-            self.latest_search_id = post_inc(&mut self.search_count);
-            self.matches = Matches {
-                history: Vec::new(),
-                search: Vec::new(),
+        let open_buffer = self.project.update(cx, |project, cx| {
+            project.open_buffer(project_path.clone(), cx)
+        });
+        self.preview_task = cx.spawn(|picker, mut cx| async move {
+            let Some(buffer) = open_buffer.await.log_err() else {
+                return;
             };
-            self.matches.set_new_history(
-                self.currently_opened_path.as_ref(),
-                None,
-                self.history_items.iter().filter(|history_item| {
-                    project
-                        .worktree_for_id(history_item.project.worktree_id, cx)
-                        .is_some()
-                        || (project.is_local() && history_item.absolute.is_some())
-                }),
-            );
-
-            self.selected_index = self.calculate_selected_index();
-            cx.notify();
-            Task::ready(())
-        } else {
-            let query = PathLikeWithPosition::parse_str(raw_query, |path_like_str| {
-                Ok::<_, std::convert::Infallible>(FileSearchQuery {
-                    raw_query: raw_query.to_owned(),
-                    file_query_end: if path_like_str == raw_query {
-                        None
-                    } else {
-                        Some(path_like_str.len())
-                    },
+            picker
+                .update(&mut cx, |picker, cx| {
+                    let delegate = &mut picker.delegate;
+                    let project = delegate.project.clone();
+                    let position = delegate.query_position();
+                    let editor = cx.new_view(|cx| {
+                        let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+                        editor.set_read_only(true);
+                        if let Some((row, column)) = position {
+                            select_position(&mut editor, row, column, cx);
+                        }
+                        editor
+                    });
+                    delegate.preview = Some(FilePreview {
+                        path: project_path,
+                        editor,
+                    });
+                    delegate.notify_file_finder(cx);
                 })
-            })
-            .expect("infallible");
+                .log_err();
+        });
+    }
 
-            if Path::new(query.path_like.path_query()).is_absolute() {
-                self.lookup_absolute_path(query, cx)
-            } else {
-                self.spawn_search(query, cx)
-            }
-        }
+    fn notify_file_finder(&self, cx: &mut ViewContext<Picker<Self>>) {
+        self.file_finder.update(cx, |_, cx| cx.notify()).ok();
     }
 
-    fn confirm(&mut self, secondary: bool, cx: &mut ViewContext<Picker<FileFinderDelegate>>) {
+    fn open_selected(
+        &mut self,
+        split: Option<SplitDirection>,
+        cx: &mut ViewContext<Picker<FileFinderDelegate>>,
+    ) {
         if let Some(m) = self.matches.get(self.selected_index()) {
             if let Some(workspace) = self.workspace.upgrade() {
                 let open_task = workspace.update(cx, move |workspace, cx| {
                     let split_or_open = |workspace: &mut Workspace, project_path, cx| {
-                        if secondary {
-                            workspace.split_path(project_path, cx)
+                        if let Some(direction) = split {
+                            workspace.split_path_in_direction(project_path, direction, cx)
                         } else {
                             workspace.open_path(project_path, None, true, cx)
                         }
@@ -769,10 +1097,11 @@ impl PickerDelegate for FileFinderDelegate {
                             } else {
                                 match history_match.absolute.as_ref() {
                                     Some(abs_path) => {
-                                        if secondary {
-                                            workspace.split_abs_path(
+                                        if let Some(direction) = split {
+                                            workspace.split_abs_path_in_direction(
                                                 abs_path.to_path_buf(),
                                                 false,
+                                                direction,
                                                 cx,
                                             )
                                         } else {
@@ -805,33 +1134,17 @@ impl PickerDelegate for FileFinderDelegate {
                     }
                 });
 
-                let row = self
-                    .latest_search_query
-                    .as_ref()
-                    .and_then(|query| query.row)
-                    .map(|row| row.saturating_sub(1));
-                let col = self
-                    .latest_search_query
-                    .as_ref()
-                    .and_then(|query| query.column)
-                    .unwrap_or(0)
-                    .saturating_sub(1);
+                let position = self.query_position();
                 let finder = self.file_finder.clone();
 
                 cx.spawn(|_, mut cx| async move {
                     let item = open_task.await.log_err()?;
-                    if let Some(row) = row {
+                    if let Some((row, column)) = position {
                         if let Some(active_editor) = item.downcast::<Editor>() {
                             active_editor
                                 .downgrade()
                                 .update(&mut cx, |editor, cx| {
-                                    let snapshot = editor.snapshot(cx).display_snapshot;
-                                    let point = snapshot
-                                        .buffer_snapshot
-                                        .clip_point(Point::new(row, col), Bias::Left);
-                                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
-                                        s.select_ranges([point..point])
-                                    });
+                                    select_position(editor, row, column, cx)
                                 })
                                 .log_err();
                         }
@@ -844,6 +1157,102 @@ impl PickerDelegate for FileFinderDelegate {
             }
         }
     }
+}
+
+fn select_position(editor: &mut Editor, row: u32, column: u32, cx: &mut ViewContext<Editor>) {
+    let snapshot = editor.snapshot(cx).display_snapshot;
+    let point = snapshot
+        .buffer_snapshot
+        .clip_point(Point::new(row, column), Bias::Left);
+    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+        s.select_ranges([point..point])
+    });
+}
+
+impl PickerDelegate for FileFinderDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self) -> Arc<str> {
+        "Search project files...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, cx: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+        self.update_preview(cx);
+        cx.notify();
+    }
+
+    fn separators_after_indices(&self) -> Vec<usize> {
+        let history_items = self.matches.history.len();
+        if history_items == 0 || self.matches.search.is_empty() {
+            Vec::new()
+        } else {
+            vec![history_items - 1]
+        }
+    }
+
+    fn update_matches(
+        &mut self,
+        raw_query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Task<()> {
+        let (filters, raw_query) = QueryFilters::parse(&raw_query);
+        let raw_query = raw_query.trim();
+        if raw_query.is_empty() && !filters.narrows_results() {
+            let project = self.project.read(cx);
+            self.latest_search_id = post_inc(&mut self.search_count);
+            self.matches = Matches {
+                history: Vec::new(),
+                search: Vec::new(),
+            };
+            self.matches.set_new_history(
+                self.currently_opened_path.as_ref(),
+                None,
+                self.history_items.iter().filter(|history_item| {
+                    project
+                        .worktree_for_id(history_item.project.worktree_id, cx)
+                        .is_some()
+                        || (project.is_local() && history_item.absolute.is_some())
+                }),
+            );
+
+            self.selected_index = self.calculate_selected_index();
+            self.update_preview(cx);
+            cx.notify();
+            Task::ready(())
+        } else {
+            let query = PathLikeWithPosition::parse_str(raw_query, |path_like_str| {
+                Ok::<_, std::convert::Infallible>(FileSearchQuery {
+                    raw_query: raw_query.to_owned(),
+                    file_query_end: if path_like_str == raw_query {
+                        None
+                    } else {
+                        Some(path_like_str.len())
+                    },
+                    filters: filters.clone(),
+                })
+            })
+            .expect("infallible");
+
+            if Path::new(query.path_like.path_query()).is_absolute() {
+                self.lookup_absolute_path(query, cx)
+            } else {
+                self.spawn_search(query, cx)
+            }
+        }
+    }
+
+    fn confirm(&mut self, secondary: bool, cx: &mut ViewContext<Picker<FileFinderDelegate>>) {
+        self.open_selected(secondary.then_some(SplitDirection::Right), cx);
+    }
 
     fn dismissed(&mut self, cx: &mut ViewContext<Picker<FileFinderDelegate>>) {
         self.file_finder
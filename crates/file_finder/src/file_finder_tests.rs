@@ -451,6 +451,88 @@ async fn test_ignored_root(cx: &mut TestAppContext) {
     picker.update(cx, |picker, _| assert_eq!(picker.delegate.matches.len(), 7));
 }
 
+#[gpui::test]
+async fn test_query_filters(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                ".gitignore": "target",
+                "src": {
+                    "main.rs": "",
+                    "lib.rs": "",
+                    "util": {
+                        "mod.rs": "",
+                        "helpers.ts": "",
+                    },
+                },
+                "tests": {
+                    "main_test.rs": "",
+                },
+                "target": {
+                    "main.rs": "",
+                },
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (picker, _, cx) = build_find_picker(project, cx);
+
+    for (query, expected_matches) in [
+        (
+            "*.rs",
+            vec![
+                "src/lib.rs",
+                "src/main.rs",
+                "src/util/mod.rs",
+                "tests/main_test.rs",
+            ],
+        ),
+        ("main src/", vec!["src/main.rs"]),
+        ("util/ *.ts", vec!["src/util/helpers.ts"]),
+        ("main *.ts", vec![]),
+        ("main", vec!["src/main.rs", "tests/main_test.rs"]),
+    ] {
+        picker
+            .update(cx, |picker, cx| {
+                picker.delegate.update_matches(query.to_string(), cx)
+            })
+            .await;
+        picker.update(cx, |picker, _| {
+            let mut matches = collect_search_matches(picker).search_only();
+            matches.sort();
+            assert_eq!(
+                matches,
+                expected_matches
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>(),
+                "Wrong matches for query '{query}'"
+            );
+        });
+    }
+
+    // Ignored directories get loaded when asked for, and searched again once they are.
+    picker.update(cx, |picker, cx| picker.set_query("main !ignored", cx));
+    cx.run_until_parked();
+    picker.update(cx, |picker, _| {
+        let mut matches = collect_search_matches(picker).search_only();
+        matches.sort();
+        assert_eq!(
+            matches,
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("target/main.rs"),
+                PathBuf::from("tests/main_test.rs"),
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_single_file_worktrees(cx: &mut TestAppContext) {
     let app_state = init_test(cx);
@@ -1414,6 +1496,7 @@ fn test_path_like(test_str: &str) -> PathLikeWithPosition<FileSearchQuery> {
             } else {
                 Some(path_like_str.len())
             },
+            filters: QueryFilters::default(),
         })
     })
     .unwrap()
@@ -656,6 +656,16 @@ impl FakeFs {
         });
     }
 
+    pub fn set_staged_diff_for_repo(&self, dot_git: &Path, diff: impl Into<String>) {
+        self.with_git_state(dot_git, false, |state| state.staged_diff = diff.into());
+    }
+
+    pub fn set_commit_subjects_for_repo(&self, dot_git: &Path, subjects: &[&str]) {
+        self.with_git_state(dot_git, false, |state| {
+            state.commit_subjects = subjects.iter().map(|subject| subject.to_string()).collect();
+        });
+    }
+
     pub fn set_status_for_repo_via_working_copy_change(
         &self,
         dot_git: &Path,
@@ -1234,6 +1244,15 @@ pub fn normalize_path(path: &Path) -> PathBuf {
     ret
 }
 
+/// Returns whether an operation failed because it tried to rename a path onto
+/// a different file system.
+pub fn is_cross_device_error(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .and_then(|error| error.raw_os_error())
+        == Some(libc::EXDEV)
+}
+
 pub fn copy_recursive<'a>(
     fs: &'a dyn Fs,
     source: &'a Path,
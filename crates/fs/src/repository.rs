@@ -53,6 +53,14 @@ pub trait GitRepository: Send {
     fn branches(&self) -> Result<Vec<Branch>>;
     fn change_branch(&self, _: &str) -> Result<()>;
     fn create_branch(&self, _: &str) -> Result<()>;
+
+    /// Returns the changes staged in the index relative to the HEAD commit,
+    /// formatted as a unified diff.
+    fn staged_diff(&self) -> Result<String>;
+
+    /// Returns the subject lines of the most recent commits reachable from HEAD,
+    /// newest first.
+    fn recent_commit_subjects(&self, limit: usize) -> Result<Vec<String>>;
 }
 
 impl std::fmt::Debug for dyn GitRepository {
@@ -209,6 +217,45 @@ impl GitRepository for LibGitRepository {
 
         Ok(())
     }
+
+    fn staged_diff(&self) -> Result<String> {
+        // A repository without any commits has an unborn HEAD, in which case
+        // everything in the index is compared against the empty tree.
+        let head_tree = match self.head() {
+            Ok(head) => Some(head.peel_to_tree()?),
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch => None,
+            Err(error) => return Err(error.into()),
+        };
+        let diff = self.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+
+        let mut text = String::new();
+        diff.print(git2::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                text.push(line.origin());
+            }
+            text.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+        Ok(text)
+    }
+
+    fn recent_commit_subjects(&self, limit: usize) -> Result<Vec<String>> {
+        let mut revwalk = self.revwalk()?;
+        match revwalk.push_head() {
+            Ok(()) => {}
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        }
+
+        let mut subjects = Vec::new();
+        for oid in revwalk.take(limit) {
+            let commit = self.find_commit(oid?)?;
+            if let Some(summary) = commit.summary() {
+                subjects.push(summary.to_string());
+            }
+        }
+        Ok(subjects)
+    }
 }
 
 fn matches_index(repo: &LibGitRepository, path: &RepoPath, mtime: SystemTime) -> bool {
@@ -253,6 +300,8 @@ pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
+    pub staged_diff: String,
+    pub commit_subjects: Vec<String>,
 }
 
 impl FakeGitRepository {
@@ -317,6 +366,16 @@ impl GitRepository for FakeGitRepository {
         state.branch_name = Some(name.to_owned());
         Ok(())
     }
+
+    fn staged_diff(&self) -> Result<String> {
+        let state = self.state.lock();
+        Ok(state.staged_diff.clone())
+    }
+
+    fn recent_commit_subjects(&self, limit: usize) -> Result<Vec<String>> {
+        let state = self.state.lock();
+        Ok(state.commit_subjects.iter().take(limit).cloned().collect())
+    }
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
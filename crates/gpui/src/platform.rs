@@ -2,6 +2,7 @@
 #![cfg_attr(target_os = "linux", allow(dead_code))]
 
 mod app_menu;
+mod headless;
 mod keystroke;
 #[cfg(target_os = "linux")]
 mod linux;
@@ -37,6 +38,7 @@ use std::{
 use uuid::Uuid;
 
 pub use app_menu::*;
+pub(crate) use headless::*;
 pub use keystroke::*;
 #[cfg(target_os = "linux")]
 pub(crate) use linux::*;
@@ -56,6 +58,10 @@ pub(crate) fn current_platform() -> Rc<dyn Platform> {
     Rc::new(LinuxPlatform::new())
 }
 
+pub(crate) fn headless_platform() -> Rc<dyn Platform> {
+    Rc::new(HeadlessPlatform::new())
+}
+
 pub(crate) trait Platform: 'static {
     fn background_executor(&self) -> BackgroundExecutor;
     fn foreground_executor(&self) -> ForegroundExecutor;
@@ -0,0 +1,5 @@
+mod dispatcher;
+mod platform;
+
+pub(crate) use dispatcher::*;
+pub(crate) use platform::*;
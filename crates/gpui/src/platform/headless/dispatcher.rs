@@ -0,0 +1,113 @@
+use crate::{PlatformDispatcher, TaskLabel};
+use async_task::Runnable;
+use parking::{Parker, Unparker};
+use parking_lot::Mutex;
+use std::{
+    panic,
+    thread::{self, JoinHandle, ThreadId},
+    time::{Duration, Instant},
+};
+
+/// Dispatches work without a windowing system. The main thread drains a
+/// channel of runnables, background work runs on a thread pool and timers
+/// are driven by a dedicated thread.
+pub(crate) struct HeadlessDispatcher {
+    parker: Mutex<Parker>,
+    main_sender: flume::Sender<Runnable>,
+    background_sender: flume::Sender<Runnable>,
+    timer_sender: flume::Sender<(Instant, Runnable)>,
+    _background_threads: Vec<JoinHandle<()>>,
+    _timer_thread: JoinHandle<()>,
+    main_thread_id: ThreadId,
+}
+
+impl HeadlessDispatcher {
+    pub fn new(main_sender: flume::Sender<Runnable>) -> Self {
+        let (background_sender, background_receiver) = flume::unbounded::<Runnable>();
+        let background_threads = (0..num_cpus::get().max(2))
+            .map(|_| {
+                let receiver = background_receiver.clone();
+                thread::spawn(move || {
+                    for runnable in receiver {
+                        let _ignore_panic = panic::catch_unwind(|| runnable.run());
+                    }
+                })
+            })
+            .collect();
+
+        let (timer_sender, timer_receiver) = flume::unbounded::<(Instant, Runnable)>();
+        let timer_thread = thread::spawn({
+            let background_sender = background_sender.clone();
+            move || {
+                let mut timed_tasks = Vec::<(Instant, Runnable)>::new();
+                loop {
+                    let received = match timed_tasks.last() {
+                        Some((deadline, _)) => timer_receiver.recv_deadline(*deadline),
+                        None => timer_receiver
+                            .recv()
+                            .map_err(|_| flume::RecvTimeoutError::Disconnected),
+                    };
+                    match received {
+                        Ok(timed_task) => {
+                            timed_tasks.push(timed_task);
+                            timed_tasks.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+                        }
+                        Err(flume::RecvTimeoutError::Timeout) => {}
+                        Err(flume::RecvTimeoutError::Disconnected) => break,
+                    }
+
+                    let now = Instant::now();
+                    while timed_tasks
+                        .last()
+                        .map_or(false, |(deadline, _)| *deadline <= now)
+                    {
+                        let (_, runnable) = timed_tasks.pop().unwrap();
+                        background_sender.send(runnable).ok();
+                    }
+                }
+            }
+        });
+
+        Self {
+            parker: Mutex::new(Parker::new()),
+            main_sender,
+            background_sender,
+            timer_sender,
+            _background_threads: background_threads,
+            _timer_thread: timer_thread,
+            main_thread_id: thread::current().id(),
+        }
+    }
+}
+
+impl PlatformDispatcher for HeadlessDispatcher {
+    fn is_main_thread(&self) -> bool {
+        thread::current().id() == self.main_thread_id
+    }
+
+    fn dispatch(&self, runnable: Runnable, _: Option<TaskLabel>) {
+        self.background_sender.send(runnable).ok();
+    }
+
+    fn dispatch_on_main_thread(&self, runnable: Runnable) {
+        self.main_sender.send(runnable).ok();
+    }
+
+    fn dispatch_after(&self, duration: Duration, runnable: Runnable) {
+        self.timer_sender
+            .send((Instant::now() + duration, runnable))
+            .ok();
+    }
+
+    fn tick(&self, _background_only: bool) -> bool {
+        false
+    }
+
+    fn park(&self) {
+        self.parker.lock().park()
+    }
+
+    fn unparker(&self) -> Unparker {
+        self.parker.lock().unparker()
+    }
+}
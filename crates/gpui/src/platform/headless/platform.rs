@@ -0,0 +1,213 @@
+use crate::{
+    Action, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DisplayId,
+    ForegroundExecutor, HeadlessDispatcher, Keymap, Menu, PathPromptOptions, Platform,
+    PlatformDisplay, PlatformInput, PlatformTextSystem, PlatformWindow, SemanticVersion, Task,
+    WindowAppearance, WindowOptions,
+};
+use anyhow::{anyhow, Result};
+use async_task::Runnable;
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+use time::UtcOffset;
+
+/// A platform without windows, displays or input, for running gpui
+/// applications as background processes such as the remote server.
+pub(crate) struct HeadlessPlatform {
+    background_executor: BackgroundExecutor,
+    foreground_executor: ForegroundExecutor,
+    main_receiver: flume::Receiver<Runnable>,
+    text_system: Arc<dyn PlatformTextSystem>,
+    quit_requested: Cell<bool>,
+    quit_callback: Mutex<Option<Box<dyn FnMut()>>>,
+}
+
+impl HeadlessPlatform {
+    pub(crate) fn new() -> Self {
+        let (main_sender, main_receiver) = flume::unbounded::<Runnable>();
+        let dispatcher = Arc::new(HeadlessDispatcher::new(main_sender));
+
+        #[cfg(target_os = "linux")]
+        let text_system = Arc::new(crate::LinuxTextSystem::new());
+        #[cfg(target_os = "macos")]
+        let text_system = Arc::new(crate::MacTextSystem::new());
+
+        Self {
+            background_executor: BackgroundExecutor::new(dispatcher.clone()),
+            foreground_executor: ForegroundExecutor::new(dispatcher),
+            main_receiver,
+            text_system,
+            quit_requested: Cell::new(false),
+            quit_callback: Mutex::new(None),
+        }
+    }
+}
+
+impl Platform for HeadlessPlatform {
+    fn background_executor(&self) -> BackgroundExecutor {
+        self.background_executor.clone()
+    }
+
+    fn foreground_executor(&self) -> ForegroundExecutor {
+        self.foreground_executor.clone()
+    }
+
+    fn text_system(&self) -> Arc<dyn PlatformTextSystem> {
+        self.text_system.clone()
+    }
+
+    fn run(&self, on_finish_launching: Box<dyn FnOnce()>) {
+        on_finish_launching();
+        while !self.quit_requested.get() {
+            match self.main_receiver.recv() {
+                Ok(runnable) => {
+                    runnable.run();
+                }
+                Err(_) => break,
+            }
+        }
+
+        if let Some(callback) = self.quit_callback.lock().as_mut() {
+            callback();
+        }
+    }
+
+    fn quit(&self) {
+        self.quit_requested.set(true);
+    }
+
+    fn restart(&self) {}
+
+    fn activate(&self, _ignoring_other_apps: bool) {}
+
+    fn hide(&self) {}
+
+    fn hide_other_apps(&self) {}
+
+    fn unhide_other_apps(&self) {}
+
+    fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
+        Vec::new()
+    }
+
+    fn display(&self, _id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
+        None
+    }
+
+    fn active_window(&self) -> Option<AnyWindowHandle> {
+        None
+    }
+
+    fn open_window(
+        &self,
+        _handle: AnyWindowHandle,
+        _options: WindowOptions,
+    ) -> Box<dyn PlatformWindow> {
+        panic!("cannot open windows on a headless platform")
+    }
+
+    fn window_appearance(&self) -> WindowAppearance {
+        WindowAppearance::Light
+    }
+
+    fn open_url(&self, _url: &str) {}
+
+    fn on_open_urls(&self, _callback: Box<dyn FnMut(Vec<String>)>) {}
+
+    fn prompt_for_paths(
+        &self,
+        _options: PathPromptOptions,
+    ) -> oneshot::Receiver<Option<Vec<PathBuf>>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(None).ok();
+        rx
+    }
+
+    fn prompt_for_new_path(&self, _directory: &Path) -> oneshot::Receiver<Option<PathBuf>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(None).ok();
+        rx
+    }
+
+    fn reveal_path(&self, _path: &Path) {}
+
+    fn on_become_active(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_resign_active(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_quit(&self, callback: Box<dyn FnMut()>) {
+        *self.quit_callback.lock() = Some(callback);
+    }
+
+    fn on_reopen(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_event(&self, _callback: Box<dyn FnMut(PlatformInput) -> bool>) {}
+
+    fn set_menus(&self, _menus: Vec<Menu>, _keymap: &Keymap) {}
+
+    fn on_app_menu_action(&self, _callback: Box<dyn FnMut(&dyn Action)>) {}
+
+    fn on_will_open_app_menu(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_validate_app_menu_command(&self, _callback: Box<dyn FnMut(&dyn Action) -> bool>) {}
+
+    fn os_name(&self) -> &'static str {
+        std::env::consts::OS
+    }
+
+    fn os_version(&self) -> Result<SemanticVersion> {
+        Err(anyhow!("os_version is unavailable on a headless platform"))
+    }
+
+    fn app_version(&self) -> Result<SemanticVersion> {
+        Err(anyhow!("app_version is unavailable on a headless platform"))
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        Ok(std::env::current_exe()?)
+    }
+
+    fn local_timezone(&self) -> UtcOffset {
+        UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC)
+    }
+
+    fn double_click_interval(&self) -> Duration {
+        Duration::default()
+    }
+
+    fn path_for_auxiliary_executable(&self, name: &str) -> Result<PathBuf> {
+        let mut path = std::env::current_exe()?;
+        path.set_file_name(name);
+        Ok(path)
+    }
+
+    fn set_cursor_style(&self, _style: CursorStyle) {}
+
+    fn should_auto_hide_scrollbars(&self) -> bool {
+        false
+    }
+
+    fn write_to_clipboard(&self, _item: ClipboardItem) {}
+
+    fn read_from_clipboard(&self) -> Option<ClipboardItem> {
+        None
+    }
+
+    fn write_credentials(&self, _url: &str, _username: &str, _password: &[u8]) -> Task<Result<()>> {
+        Task::ready(Err(anyhow!("no keychain on a headless platform")))
+    }
+
+    fn read_credentials(&self, _url: &str) -> Task<Result<Option<(String, Vec<u8>)>>> {
+        Task::ready(Ok(None))
+    }
+
+    fn delete_credentials(&self, _url: &str) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+}
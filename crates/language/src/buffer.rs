@@ -2673,6 +2673,20 @@ impl BufferSnapshot {
             .map(Outline::new)
     }
 
+    /// Returns the outline of the symbols intersecting the given range.
+    ///
+    /// This method allows passing an optional [SyntaxTheme] to
+    /// syntax-highlight the returned symbols.
+    pub fn outline_in_range<T: ToOffset>(
+        &self,
+        range: Range<T>,
+        theme: Option<&SyntaxTheme>,
+    ) -> Option<Outline<Anchor>> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        self.outline_items_containing(range, true, theme)
+            .map(Outline::new)
+    }
+
     /// Returns all the symbols that contain the given position.
     ///
     /// This method allows passing an optional [SyntaxTheme] to
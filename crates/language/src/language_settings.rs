@@ -42,6 +42,8 @@ pub fn all_language_settings<'a>(
 pub struct AllLanguageSettings {
     /// The settings for GitHub Copilot.
     pub copilot: CopilotSettings,
+    /// The provider that supplies inline completions in the editor.
+    pub inline_completion_provider: InlineCompletionProvider,
     defaults: LanguageSettings,
     languages: HashMap<Arc<str>, LanguageSettings>,
 }
@@ -82,8 +84,8 @@ pub struct LanguageSettings {
     pub prettier: HashMap<String, serde_json::Value>,
     /// Whether to use language servers to provide code intelligence.
     pub enable_language_server: bool,
-    /// Controls whether Copilot provides suggestion immediately (true)
-    /// or waits for a `copilot::Toggle` (false).
+    /// Controls whether inline completions are shown immediately (true)
+    /// or wait for an `editor::ShowInlineCompletion` (false).
     pub show_copilot_suggestions: bool,
     /// Whether to show tabs and spaces in the editor.
     pub show_whitespaces: ShowWhitespaceSetting,
@@ -193,8 +195,8 @@ pub struct LanguageSettingsContent {
     /// Default: true
     #[serde(default)]
     pub enable_language_server: Option<bool>,
-    /// Controls whether Copilot provides suggestion immediately (true)
-    /// or waits for a `copilot::Toggle` (false).
+    /// Controls whether inline completions are shown immediately (true)
+    /// or wait for an `editor::ShowInlineCompletion` (false).
     ///
     /// Default: true
     #[serde(default)]
@@ -231,6 +233,21 @@ pub struct CopilotSettingsContent {
 pub struct FeaturesContent {
     /// Whether the GitHub Copilot feature is enabled.
     pub copilot: Option<bool>,
+    /// Which provider to use for inline completions.
+    pub inline_completion_provider: Option<InlineCompletionProvider>,
+}
+
+/// The provider that supplies inline completions.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineCompletionProvider {
+    /// Don't show inline completions.
+    None,
+    /// Use GitHub Copilot to provide inline completions.
+    #[default]
+    Copilot,
+    /// Use the assistant's fill-in-the-middle model to provide inline completions.
+    Assistant,
 }
 
 /// Controls the soft-wrapping behavior in the editor.
@@ -365,10 +382,16 @@ impl AllLanguageSettings {
 
     /// Returns whether GitHub Copilot is enabled for the given language and path.
     pub fn copilot_enabled(&self, language: Option<&Arc<Language>>, path: Option<&Path>) -> bool {
-        if !self.copilot.feature_enabled {
-            return false;
-        }
+        self.copilot.feature_enabled && self.inline_completions_enabled(language, path)
+    }
 
+    /// Returns whether inline completions should be shown for the given language and path,
+    /// regardless of which provider supplies them.
+    pub fn inline_completions_enabled(
+        &self,
+        language: Option<&Arc<Language>>,
+        path: Option<&Path>,
+    ) -> bool {
         if let Some(path) = path {
             if !self.copilot_enabled_for_path(path) {
                 return false;
@@ -437,6 +460,11 @@ impl settings::Settings for AllLanguageSettings {
             .as_ref()
             .and_then(|f| f.copilot)
             .ok_or_else(Self::missing_default)?;
+        let mut inline_completion_provider = default_value
+            .features
+            .as_ref()
+            .and_then(|f| f.inline_completion_provider)
+            .ok_or_else(Self::missing_default)?;
         let mut copilot_globs = default_value
             .copilot
             .as_ref()
@@ -447,6 +475,13 @@ impl settings::Settings for AllLanguageSettings {
             if let Some(copilot) = user_settings.features.as_ref().and_then(|f| f.copilot) {
                 copilot_enabled = copilot;
             }
+            if let Some(provider) = user_settings
+                .features
+                .as_ref()
+                .and_then(|f| f.inline_completion_provider)
+            {
+                inline_completion_provider = provider;
+            }
             if let Some(globs) = user_settings
                 .copilot
                 .as_ref()
@@ -481,6 +516,7 @@ impl settings::Settings for AllLanguageSettings {
                     .filter_map(|g| Some(globset::Glob::new(g).ok()?.compile_matcher()))
                     .collect(),
             },
+            inline_completion_provider,
             defaults,
             languages,
         })
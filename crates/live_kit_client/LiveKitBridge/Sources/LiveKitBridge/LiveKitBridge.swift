@@ -254,6 +254,26 @@ public func LKLocalAudioTrackCreateTrack() -> UnsafeMutableRawPointer {
 }
 
 
+@_cdecl("LKAudioInputDevices")
+public func LKAudioInputDevices() -> CFArray {
+    return Room.audioDeviceModule.inputDevices.map { $0.name as CFString } as CFArray
+}
+
+@_cdecl("LKSetAudioInputDevice")
+public func LKSetAudioInputDevice(name: CFString?) -> Bool {
+    let audioDeviceModule = Room.audioDeviceModule
+    guard let name = name as String? else {
+        audioDeviceModule.inputDevice = RTCIODevice.defaultDevice(with: .input)
+        return true
+    }
+
+    guard let device = audioDeviceModule.inputDevices.first(where: { $0.name == name }) else {
+        return false
+    }
+    audioDeviceModule.inputDevice = device
+    return true
+}
+
 @_cdecl("LKCreateScreenShareTrackForDisplay")
 public func LKCreateScreenShareTrackForDisplay(display: UnsafeMutableRawPointer) -> UnsafeMutableRawPointer {
     let display = Unmanaged<MacOSDisplay>.fromOpaque(display).takeUnretainedValue()
@@ -147,6 +147,8 @@ extern "C" {
     );
     fn LKCreateScreenShareTrackForDisplay(display: swift::MacOSDisplay) -> swift::LocalVideoTrack;
     fn LKLocalAudioTrackCreateTrack() -> swift::LocalAudioTrack;
+    fn LKAudioInputDevices() -> CFArrayRef;
+    fn LKSetAudioInputDevice(name: CFStringRef) -> bool;
 
     fn LKLocalTrackPublicationSetMute(
         publication: swift::LocalTrackPublication,
@@ -689,6 +691,36 @@ impl Drop for RoomDelegate {
     }
 }
 
+/// Returns the names of the microphones that audio can be captured from.
+pub fn audio_input_devices() -> Result<Vec<String>> {
+    unsafe {
+        let devices = LKAudioInputDevices();
+        if devices.is_null() {
+            return Err(anyhow!("failed to list audio input devices"));
+        }
+        Ok(CFArray::wrap_under_get_rule(devices)
+            .into_iter()
+            .map(|name: core_foundation::base::ItemRef<'_, *const c_void>| {
+                CFString::wrap_under_get_rule(*name as CFStringRef).to_string()
+            })
+            .collect())
+    }
+}
+
+/// Captures audio from the microphone with the given name, or from the
+/// system's default microphone when no name is given.
+pub fn set_audio_input_device(name: Option<&str>) -> Result<()> {
+    let native_name = name.map(CFString::new);
+    let native_name_ref = native_name
+        .as_ref()
+        .map_or(std::ptr::null(), |name| name.as_concrete_TypeRef());
+    if unsafe { LKSetAudioInputDevice(native_name_ref) } {
+        Ok(())
+    } else {
+        Err(anyhow!("no audio input device named {:?}", name))
+    }
+}
+
 pub struct LocalAudioTrack(swift::LocalAudioTrack);
 
 impl LocalAudioTrack {
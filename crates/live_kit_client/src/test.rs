@@ -680,6 +680,19 @@ impl Drop for Room {
     }
 }
 
+/// Returns the names of the microphones that audio can be captured from. The
+/// fake implementation has none.
+pub fn audio_input_devices() -> Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+pub fn set_audio_input_device(name: Option<&str>) -> Result<()> {
+    match name {
+        Some(name) => Err(anyhow!("no audio input device named {name:?}")),
+        None => Ok(()),
+    }
+}
+
 #[derive(Clone)]
 pub struct LocalTrackPublication {
     sid: String,
@@ -3268,6 +3268,34 @@ impl MultiBufferSnapshot {
         ))
     }
 
+    /// Returns the outline of each excerpt, in the order the excerpts appear.
+    /// Excerpts whose buffer has no outline are omitted.
+    pub fn excerpt_outlines(
+        &self,
+        theme: Option<&SyntaxTheme>,
+    ) -> Vec<(ExcerptId, Outline<Anchor>)> {
+        self.excerpts
+            .iter()
+            .filter_map(|excerpt| {
+                let range = excerpt.range.context.to_offset(&excerpt.buffer);
+                let outline = excerpt.buffer.outline_in_range(range, theme)?;
+                let items = outline
+                    .items
+                    .into_iter()
+                    .map(|item| OutlineItem {
+                        depth: item.depth,
+                        range: self.anchor_in_excerpt(excerpt.id, item.range.start)
+                            ..self.anchor_in_excerpt(excerpt.id, item.range.end),
+                        text: item.text,
+                        highlight_ranges: item.highlight_ranges,
+                        name_ranges: item.name_ranges,
+                    })
+                    .collect();
+                Some((excerpt.id, Outline::new(items)))
+            })
+            .collect()
+    }
+
     pub fn symbols_containing<T: ToOffset>(
         &self,
         offset: T,
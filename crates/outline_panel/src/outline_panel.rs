@@ -0,0 +1,1189 @@
+mod outline_panel_settings;
+
+use std::{cmp, ops::Range, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use collections::{HashMap, HashSet};
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Anchor, Editor, EditorEvent, ExcerptId, MultiBufferSnapshot};
+use fuzzy::StringMatch;
+use gpui::{
+    actions, div, px, relative, uniform_list, Action, AppContext, AsyncWindowContext, ClickEvent,
+    Div, EventEmitter, FocusHandle, FocusableView, FontStyle, FontWeight, HighlightStyle,
+    InteractiveElement, KeyContext, ParentElement, Pixels, Render, SharedString, Stateful, Styled,
+    StyledText, Subscription, Task, TextStyle, UniformListScrollHandle, View, ViewContext,
+    VisualContext as _, WeakView, WhiteSpace, WindowContext,
+};
+use language::{Outline, OutlineItem, Point, ToPoint as _};
+use menu::{Cancel, Confirm, SelectNext, SelectPrev};
+use outline_panel_settings::{OutlinePanelDockPosition, OutlinePanelSettings};
+use project::Fs;
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use theme::{color_alpha, ActiveTheme, SyntaxTheme, ThemeSettings};
+use ui::{prelude::*, Label, ListItem};
+use util::ResultExt;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+const OUTLINE_PANEL_KEY: &'static str = "OutlinePanel";
+const UPDATE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+actions!(
+    outline_panel,
+    [
+        ExpandSelectedEntry,
+        CollapseSelectedEntry,
+        ExpandAllEntries,
+        CollapseAllEntries,
+        ToggleFocus,
+    ]
+);
+
+pub fn init_settings(cx: &mut AppContext) {
+    OutlinePanelSettings::register(cx);
+}
+
+pub fn init(cx: &mut AppContext) {
+    init_settings(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<OutlinePanel>(cx);
+        });
+    })
+    .detach();
+}
+
+pub struct OutlinePanel {
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    filter_editor: View<Editor>,
+    list: UniformListScrollHandle,
+    active_editor: Option<ActiveEditor>,
+    groups: Vec<OutlineGroup>,
+    /// Matches of the filter query, one list per group, or `None` when the panel is not filtered.
+    filter_matches: Option<Vec<Vec<StringMatch>>>,
+    visible_entries: Vec<VisibleEntry>,
+    collapsed_entries: HashSet<OutlineEntryId>,
+    selected_entry: Option<OutlineEntryId>,
+    width: Option<Pixels>,
+    pending_serialization: Task<Option<()>>,
+    update_outline_task: Task<()>,
+    filter_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+struct ActiveEditor {
+    editor: View<Editor>,
+    _subscription: Subscription,
+}
+
+/// The symbols of a single excerpt of the active editor.
+struct OutlineGroup {
+    excerpt_id: ExcerptId,
+    /// The path and first line of the excerpt, present when the editor is a multibuffer.
+    title: Option<SharedString>,
+    outline: Arc<Outline<Anchor>>,
+}
+
+/// Identifies an entry across reparses, so that collapsed and selected
+/// entries survive edits to the buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum OutlineEntryId {
+    Excerpt(ExcerptId),
+    Item(Anchor),
+}
+
+#[derive(Clone, Debug)]
+struct VisibleEntry {
+    id: OutlineEntryId,
+    group_ix: usize,
+    item_ix: Option<usize>,
+    depth: usize,
+    has_children: bool,
+    highlight_positions: Vec<usize>,
+}
+
+#[derive(Clone)]
+struct DraggedOutlineItem {
+    group_ix: usize,
+    item_ix: usize,
+    text: SharedString,
+}
+
+struct DraggedOutlineItemView {
+    text: SharedString,
+    width: Pixels,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedOutlinePanel {
+    width: Option<Pixels>,
+}
+
+impl OutlinePanel {
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let workspace_handle = cx.view().clone();
+        let active_editor = workspace
+            .active_item(cx)
+            .and_then(|item| item.act_as::<Editor>(cx));
+        let fs = workspace.app_state().fs.clone();
+
+        cx.new_view(|cx: &mut ViewContext<Self>| {
+            let filter_editor = cx.new_view(|cx| {
+                let mut editor = Editor::single_line(cx);
+                editor.set_placeholder_text("Filter symbols...", cx);
+                editor
+            });
+
+            let subscriptions = vec![
+                cx.subscribe(&workspace_handle, |this, workspace, event, cx| {
+                    if let workspace::Event::ActiveItemChanged = event {
+                        let active_editor = workspace
+                            .read(cx)
+                            .active_item(cx)
+                            .and_then(|item| item.act_as::<Editor>(cx));
+                        this.set_active_editor(active_editor, cx);
+                    }
+                }),
+                cx.subscribe(&filter_editor, |this, _, event, cx| {
+                    if let EditorEvent::BufferEdited = event {
+                        this.update_filter(cx);
+                    }
+                }),
+            ];
+
+            let mut this = Self {
+                fs,
+                focus_handle: cx.focus_handle(),
+                filter_editor,
+                list: UniformListScrollHandle::new(),
+                active_editor: None,
+                groups: Vec::new(),
+                filter_matches: None,
+                visible_entries: Vec::new(),
+                collapsed_entries: HashSet::default(),
+                selected_entry: None,
+                width: None,
+                pending_serialization: Task::ready(None),
+                update_outline_task: Task::ready(()),
+                filter_task: Task::ready(()),
+                _subscriptions: subscriptions,
+            };
+            this.set_active_editor(active_editor, cx);
+            this
+        })
+    }
+
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(OUTLINE_PANEL_KEY) })
+            .await
+            .map_err(|e| anyhow!("Failed to load outline panel: {}", e))
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedOutlinePanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = OutlinePanel::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.width = serialized_panel.width;
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        OUTLINE_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedOutlinePanel { width })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    fn set_active_editor(&mut self, editor: Option<View<Editor>>, cx: &mut ViewContext<Self>) {
+        if self.active_editor.as_ref().map(|active| &active.editor) == editor.as_ref() {
+            return;
+        }
+
+        self.groups.clear();
+        self.filter_matches = None;
+        self.collapsed_entries.clear();
+        self.selected_entry = None;
+        self.active_editor = editor.map(|editor| ActiveEditor {
+            _subscription: cx.subscribe(&editor, Self::handle_editor_event),
+            editor,
+        });
+        self.update_outline(false, cx);
+    }
+
+    fn handle_editor_event(
+        &mut self,
+        _: View<Editor>,
+        event: &EditorEvent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match event {
+            EditorEvent::Reparsed
+            | EditorEvent::ExcerptsAdded { .. }
+            | EditorEvent::ExcerptsRemoved { .. } => self.update_outline(true, cx),
+            EditorEvent::SelectionsChanged { .. } => self.follow_cursor(cx),
+            _ => {}
+        }
+    }
+
+    /// Recomputes the outline of the active editor in the background.
+    fn update_outline(&mut self, debounce: bool, cx: &mut ViewContext<Self>) {
+        if self.active_editor.is_none() {
+            self.update_outline_task = Task::ready(());
+            self.update_visible_entries(cx);
+            return;
+        }
+
+        self.update_outline_task = cx.spawn(|this, mut cx| async move {
+            if debounce {
+                cx.background_executor().timer(UPDATE_DEBOUNCE).await;
+            }
+
+            let Some((snapshot, theme)) = this
+                .update(&mut cx, |this, cx| {
+                    let editor = &this.active_editor.as_ref()?.editor;
+                    let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+                    Some((snapshot, cx.theme().syntax().clone()))
+                })
+                .ok()
+                .flatten()
+            else {
+                return;
+            };
+
+            let groups = cx
+                .background_executor()
+                .spawn(async move { outline_groups(&snapshot, &theme) })
+                .await;
+
+            this.update(&mut cx, |this, cx| {
+                this.groups = groups;
+                this.update_filter(cx);
+                this.follow_cursor(cx);
+            })
+            .ok();
+        });
+    }
+
+    fn update_filter(&mut self, cx: &mut ViewContext<Self>) {
+        let query = self.filter_editor.read(cx).text(cx);
+        if query.trim().is_empty() {
+            self.filter_task = Task::ready(());
+            self.filter_matches = None;
+            self.update_visible_entries(cx);
+            return;
+        }
+
+        let outlines = self
+            .groups
+            .iter()
+            .map(|group| group.outline.clone())
+            .collect::<Vec<_>>();
+        let executor = cx.background_executor().clone();
+        self.filter_task = cx.spawn(|this, mut cx| async move {
+            let mut matches = Vec::with_capacity(outlines.len());
+            for outline in outlines {
+                matches.push(outline.search(&query, executor.clone()).await);
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.filter_matches = Some(matches);
+                this.update_visible_entries(cx);
+                if this.selected_entry.is_none() {
+                    this.selected_entry = this.visible_entries.first().map(|entry| entry.id);
+                }
+            })
+            .ok();
+        });
+    }
+
+    fn update_visible_entries(&mut self, cx: &mut ViewContext<Self>) {
+        self.visible_entries.clear();
+        for (group_ix, group) in self.groups.iter().enumerate() {
+            let depth_offset = if group.title.is_some() { 1 } else { 0 };
+            let group_entry = VisibleEntry {
+                id: OutlineEntryId::Excerpt(group.excerpt_id),
+                group_ix,
+                item_ix: None,
+                depth: 0,
+                has_children: true,
+                highlight_positions: Vec::new(),
+            };
+
+            if let Some(filter_matches) = &self.filter_matches {
+                let Some(matches) = filter_matches.get(group_ix) else {
+                    continue;
+                };
+                if matches.is_empty() {
+                    continue;
+                }
+                if group.title.is_some() {
+                    self.visible_entries.push(group_entry);
+                }
+                for string_match in matches {
+                    let Some(item) = group.outline.items.get(string_match.candidate_id) else {
+                        continue;
+                    };
+                    self.visible_entries.push(VisibleEntry {
+                        id: OutlineEntryId::Item(item.range.start),
+                        group_ix,
+                        item_ix: Some(string_match.candidate_id),
+                        depth: item.depth + depth_offset,
+                        has_children: false,
+                        highlight_positions: string_match.positions.clone(),
+                    });
+                }
+            } else {
+                if group.title.is_some() {
+                    let is_collapsed = self.collapsed_entries.contains(&group_entry.id);
+                    self.visible_entries.push(group_entry);
+                    if is_collapsed {
+                        continue;
+                    }
+                }
+
+                let items = &group.outline.items;
+                let mut collapsed_depth = None;
+                for (item_ix, item) in items.iter().enumerate() {
+                    if let Some(collapsed_depth) = collapsed_depth {
+                        if item.depth > collapsed_depth {
+                            continue;
+                        }
+                    }
+                    collapsed_depth = None;
+
+                    let id = OutlineEntryId::Item(item.range.start);
+                    let has_children = items
+                        .get(item_ix + 1)
+                        .map_or(false, |next_item| next_item.depth > item.depth);
+                    if has_children && self.collapsed_entries.contains(&id) {
+                        collapsed_depth = Some(item.depth);
+                    }
+                    self.visible_entries.push(VisibleEntry {
+                        id,
+                        group_ix,
+                        item_ix: Some(item_ix),
+                        depth: item.depth + depth_offset,
+                        has_children,
+                        highlight_positions: Vec::new(),
+                    });
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// Selects the innermost symbol containing the newest cursor of the active editor,
+    /// expanding its ancestors if they are collapsed.
+    fn follow_cursor(&mut self, cx: &mut ViewContext<Self>) {
+        if !OutlinePanelSettings::get_global(cx).follow_cursor
+            || self.focus_handle.contains_focused(cx)
+        {
+            return;
+        }
+        let Some(active_editor) = &self.active_editor else {
+            return;
+        };
+
+        let editor = active_editor.editor.read(cx);
+        let cursor = editor.selections.newest_anchor().head();
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let group = if snapshot.as_singleton().is_some() {
+            self.groups.first()
+        } else {
+            self.groups
+                .iter()
+                .find(|group| group.excerpt_id == cursor.excerpt_id)
+        };
+        let Some(group) = group else {
+            return;
+        };
+
+        let mut ancestors = Vec::<OutlineEntryId>::new();
+        let mut containing_item = None;
+        for item in &group.outline.items {
+            if item.range.start.cmp(&cursor, &snapshot).is_gt() {
+                break;
+            }
+            if item.range.end.cmp(&cursor, &snapshot).is_lt() {
+                continue;
+            }
+            ancestors.truncate(item.depth);
+            if let Some(parent) = containing_item.replace(OutlineEntryId::Item(item.range.start)) {
+                ancestors.push(parent);
+            }
+        }
+        let Some(containing_item) = containing_item else {
+            return;
+        };
+
+        self.collapsed_entries
+            .remove(&OutlineEntryId::Excerpt(group.excerpt_id));
+        for ancestor in ancestors {
+            self.collapsed_entries.remove(&ancestor);
+        }
+        self.selected_entry = Some(containing_item);
+        self.update_visible_entries(cx);
+        self.autoscroll(cx);
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        let selected_entry = self.selected_entry?;
+        self.visible_entries
+            .iter()
+            .position(|entry| entry.id == selected_entry)
+    }
+
+    fn autoscroll(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(index) = self.selected_index() {
+            self.list.scroll_to_item(index);
+            cx.notify();
+        }
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        let next_index = self.selected_index().map_or(0, |index| {
+            cmp::min(index + 1, self.visible_entries.len().saturating_sub(1))
+        });
+        if let Some(entry) = self.visible_entries.get(next_index) {
+            self.selected_entry = Some(entry.id);
+            self.autoscroll(cx);
+        }
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        let prev_index = self
+            .selected_index()
+            .map_or(0, |index| index.saturating_sub(1));
+        if let Some(entry) = self.visible_entries.get(prev_index) {
+            self.selected_entry = Some(entry.id);
+            self.autoscroll(cx);
+        }
+    }
+
+    fn expand_selected_entry(&mut self, _: &ExpandSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self
+            .selected_index()
+            .map(|index| self.visible_entries[index].clone())
+        else {
+            return;
+        };
+
+        if entry.has_children && self.collapsed_entries.remove(&entry.id) {
+            self.update_visible_entries(cx);
+        } else {
+            self.select_next(&SelectNext, cx);
+        }
+    }
+
+    fn collapse_selected_entry(&mut self, _: &CollapseSelectedEntry, cx: &mut ViewContext<Self>) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+
+        let entry = &self.visible_entries[index];
+        if entry.has_children && self.filter_matches.is_none() {
+            if self.collapsed_entries.insert(entry.id) {
+                self.update_visible_entries(cx);
+                return;
+            }
+        }
+
+        let depth = entry.depth;
+        if let Some(parent) = self.visible_entries[..index]
+            .iter()
+            .rev()
+            .find(|entry| entry.depth < depth)
+        {
+            self.selected_entry = Some(parent.id);
+            self.autoscroll(cx);
+        }
+    }
+
+    fn expand_all_entries(&mut self, _: &ExpandAllEntries, cx: &mut ViewContext<Self>) {
+        self.collapsed_entries.clear();
+        self.update_visible_entries(cx);
+    }
+
+    fn collapse_all_entries(&mut self, _: &CollapseAllEntries, cx: &mut ViewContext<Self>) {
+        for group in &self.groups {
+            if group.title.is_some() {
+                self.collapsed_entries
+                    .insert(OutlineEntryId::Excerpt(group.excerpt_id));
+            }
+            let items = &group.outline.items;
+            for (item_ix, item) in items.iter().enumerate() {
+                if items
+                    .get(item_ix + 1)
+                    .map_or(false, |next_item| next_item.depth > item.depth)
+                {
+                    self.collapsed_entries
+                        .insert(OutlineEntryId::Item(item.range.start));
+                }
+            }
+        }
+        self.update_visible_entries(cx);
+        self.autoscroll(cx);
+    }
+
+    fn toggle_expanded(&mut self, id: OutlineEntryId, cx: &mut ViewContext<Self>) {
+        if !self.collapsed_entries.remove(&id) {
+            self.collapsed_entries.insert(id);
+        }
+        self.update_visible_entries(cx);
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self
+            .selected_index()
+            .map(|index| self.visible_entries[index].clone())
+        else {
+            return;
+        };
+
+        match entry.item_ix {
+            Some(item_ix) => self.reveal_item(entry.group_ix, item_ix, true, cx),
+            None => self.toggle_expanded(entry.id, cx),
+        }
+    }
+
+    fn cancel(&mut self, _: &Cancel, cx: &mut ViewContext<Self>) {
+        if self.filter_matches.is_some() {
+            self.filter_editor
+                .update(cx, |editor, cx| editor.set_text("", cx));
+            cx.focus(&self.focus_handle);
+        } else {
+            cx.propagate();
+        }
+    }
+
+    /// Moves the cursor of the active editor to the start of the given symbol.
+    fn reveal_item(
+        &mut self,
+        group_ix: usize,
+        item_ix: usize,
+        focus_editor: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(editor) = self
+            .active_editor
+            .as_ref()
+            .map(|active| active.editor.clone())
+        else {
+            return;
+        };
+        let Some(item) = self
+            .groups
+            .get(group_ix)
+            .and_then(|group| group.outline.items.get(item_ix))
+        else {
+            return;
+        };
+
+        let position = item.range.start;
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                selections.select_anchor_ranges([position..position])
+            });
+        });
+        if focus_editor {
+            let focus_handle = editor.focus_handle(cx);
+            cx.focus(&focus_handle);
+        }
+    }
+
+    /// Symbols can only be reordered in unfiltered, singleton buffers, where every
+    /// top-level symbol is fully visible in the editor.
+    fn can_reorder(&self) -> bool {
+        self.filter_matches.is_none()
+            && self.groups.len() == 1
+            && self.groups.iter().all(|group| group.title.is_none())
+    }
+
+    /// Moves the lines of a dragged top-level symbol next to the target symbol:
+    /// before it when dragging upwards, after it when dragging downwards.
+    fn move_item(
+        &mut self,
+        dragged: &DraggedOutlineItem,
+        target_item_ix: usize,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if dragged.item_ix == target_item_ix || !self.can_reorder() {
+            return;
+        }
+        let Some(editor) = self
+            .active_editor
+            .as_ref()
+            .map(|active| active.editor.clone())
+        else {
+            return;
+        };
+        let Some(group) = self.groups.get(dragged.group_ix) else {
+            return;
+        };
+
+        let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+        let max_point = snapshot.max_point();
+        let line_start = |row: u32| {
+            if row > max_point.row {
+                max_point
+            } else {
+                Point::new(row, 0)
+            }
+        };
+
+        let source_rows = top_level_item_rows(&group.outline, dragged.item_ix, &snapshot);
+        let target_rows = top_level_item_rows(&group.outline, target_item_ix, &snapshot);
+        let source_range = line_start(source_rows.start)..line_start(source_rows.end);
+        let mut text = snapshot
+            .text_for_range(source_range.clone())
+            .collect::<String>();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        let insertion_point = if dragged.item_ix < target_item_ix {
+            line_start(target_rows.end)
+        } else {
+            line_start(target_rows.start)
+        };
+        if insertion_point == max_point && insertion_point.column > 0 {
+            text.pop();
+            text.insert(0, '\n');
+        }
+
+        let mut edits = vec![
+            (source_range, String::new()),
+            (insertion_point..insertion_point, text),
+        ];
+        edits.sort_by_key(|(range, _)| range.start);
+        editor.update(cx, |editor, cx| {
+            editor.transact(cx, |editor, cx| editor.edit(edits, cx));
+        });
+    }
+
+    fn dispatch_context(&self, cx: &ViewContext<Self>) -> KeyContext {
+        let mut dispatch_context = KeyContext::default();
+        dispatch_context.add("OutlinePanel");
+        dispatch_context.add("menu");
+
+        let identifier = if self.filter_editor.focus_handle(cx).is_focused(cx) {
+            "filtering"
+        } else {
+            "not_filtering"
+        };
+
+        dispatch_context.add(identifier);
+        dispatch_context
+    }
+
+    fn render_entry(
+        &self,
+        index: usize,
+        entry: &VisibleEntry,
+        cx: &mut ViewContext<Self>,
+    ) -> Stateful<Div> {
+        let settings = OutlinePanelSettings::get_global(cx);
+        let group = &self.groups[entry.group_ix];
+        let id = entry.id;
+        let group_ix = entry.group_ix;
+        let item_ix = entry.item_ix;
+        let is_selected = self.selected_entry == Some(id);
+        let is_collapsed = self.collapsed_entries.contains(&id);
+
+        let label = match item_ix {
+            Some(item_ix) => render_item_text(
+                &group.outline.items[item_ix],
+                &entry.highlight_positions,
+                cx,
+            )
+            .into_any_element(),
+            None => Label::new(group.title.clone().unwrap_or_default())
+                .color(Color::Muted)
+                .into_any_element(),
+        };
+
+        let list_item = ListItem::new(index)
+            .indent_level(entry.depth)
+            .indent_step_size(px(settings.indent_size))
+            .selected(is_selected)
+            .when(entry.has_children, |list_item| {
+                list_item
+                    .toggle(!is_collapsed)
+                    .on_toggle(cx.listener(move |this, _, cx| this.toggle_expanded(id, cx)))
+            })
+            .child(label)
+            .on_click(cx.listener(move |this, event: &ClickEvent, cx| {
+                this.selected_entry = Some(id);
+                match item_ix {
+                    Some(item_ix) => {
+                        this.reveal_item(group_ix, item_ix, event.up.click_count > 1, cx)
+                    }
+                    None => this.toggle_expanded(id, cx),
+                }
+                cx.notify();
+            }));
+
+        let element = div().id(index).child(list_item);
+        match item_ix {
+            Some(item_ix) if entry.depth == 0 && self.can_reorder() => {
+                let dragged_item = DraggedOutlineItem {
+                    group_ix,
+                    item_ix,
+                    text: group.outline.items[item_ix].text.clone().into(),
+                };
+                let width = self.width.unwrap_or(px(0.));
+                element
+                    .on_drag(dragged_item, move |dragged_item, cx| {
+                        cx.new_view(|_| DraggedOutlineItemView {
+                            text: dragged_item.text.clone(),
+                            width,
+                        })
+                    })
+                    .drag_over::<DraggedOutlineItem>(|style, _, cx| {
+                        style.bg(cx.theme().colors().drop_target_background)
+                    })
+                    .on_drop(
+                        cx.listener(move |this, dragged_item: &DraggedOutlineItem, cx| {
+                            this.move_item(dragged_item, item_ix, cx);
+                        }),
+                    )
+            }
+            _ => element,
+        }
+    }
+}
+
+/// Computes the outline of every excerpt in the snapshot, skipping excerpts without symbols.
+fn outline_groups(snapshot: &MultiBufferSnapshot, theme: &SyntaxTheme) -> Vec<OutlineGroup> {
+    let mut titles = HashMap::default();
+    if snapshot.as_singleton().is_none() {
+        for (excerpt_id, buffer, range) in snapshot.excerpts() {
+            let path = buffer
+                .file()
+                .map(|file| file.path().to_string_lossy().to_string())
+                .unwrap_or_else(|| "untitled".to_string());
+            let row = range.context.start.to_point(buffer).row + 1;
+            titles.insert(excerpt_id, SharedString::from(format!("{path}:{row}")));
+        }
+    }
+
+    snapshot
+        .excerpt_outlines(Some(theme))
+        .into_iter()
+        .filter(|(_, outline)| !outline.items.is_empty())
+        .map(|(excerpt_id, outline)| OutlineGroup {
+            excerpt_id,
+            title: titles.remove(&excerpt_id),
+            outline: Arc::new(outline),
+        })
+        .collect()
+}
+
+/// Returns the rows spanned by a top-level symbol, including the comments and
+/// attributes directly above it, with an exclusive end.
+fn top_level_item_rows(
+    outline: &Outline<Anchor>,
+    item_ix: usize,
+    snapshot: &MultiBufferSnapshot,
+) -> Range<u32> {
+    let item = &outline.items[item_ix];
+    let min_row = outline.items[..item_ix]
+        .iter()
+        .rev()
+        .find(|item| item.depth == 0)
+        .map_or(0, |previous_item| {
+            previous_item.range.end.to_point(snapshot).row + 1
+        });
+
+    let mut start_row = item.range.start.to_point(snapshot).row;
+    while start_row > min_row && !snapshot.is_line_blank(start_row - 1) {
+        start_row -= 1;
+    }
+    start_row..item.range.end.to_point(snapshot).row + 1
+}
+
+fn render_item_text(
+    item: &OutlineItem<Anchor>,
+    highlight_positions: &[usize],
+    cx: &WindowContext,
+) -> StyledText {
+    let settings = ThemeSettings::get_global(cx);
+    let text_style = TextStyle {
+        color: cx.theme().colors().text,
+        font_family: settings.buffer_font.family.clone(),
+        font_features: settings.buffer_font.features,
+        font_size: settings.ui_font_size.into(),
+        font_weight: FontWeight::NORMAL,
+        font_style: FontStyle::Normal,
+        line_height: relative(1.).into(),
+        background_color: None,
+        underline: None,
+        strikethrough: None,
+        white_space: WhiteSpace::Nowrap,
+    };
+
+    let mut highlight_style = HighlightStyle::default();
+    highlight_style.background_color = Some(color_alpha(cx.theme().colors().text_accent, 0.3));
+
+    let highlights = gpui::combine_highlights(
+        highlight_positions.iter().map(|&position| {
+            let end = position
+                + item.text[position..]
+                    .chars()
+                    .next()
+                    .map_or(0, |c| c.len_utf8());
+            (position..end, highlight_style)
+        }),
+        item.highlight_ranges.iter().cloned(),
+    );
+
+    StyledText::new(item.text.clone()).with_highlights(&text_style, highlights)
+}
+
+impl Render for OutlinePanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let empty_message = if self.active_editor.is_none() {
+            Some("No active editor")
+        } else if self.visible_entries.is_empty() {
+            Some("No symbols")
+        } else {
+            None
+        };
+
+        v_flex()
+            .id("outline-panel")
+            .size_full()
+            .key_context(self.dispatch_context(cx))
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::expand_selected_entry))
+            .on_action(cx.listener(Self::collapse_selected_entry))
+            .on_action(cx.listener(Self::expand_all_entries))
+            .on_action(cx.listener(Self::collapse_all_entries))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::cancel))
+            .track_focus(&self.focus_handle)
+            .child(
+                div()
+                    .p_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(self.filter_editor.clone()),
+            )
+            .child(match empty_message {
+                Some(message) => v_flex()
+                    .id("empty-outline-panel")
+                    .size_full()
+                    .p_4()
+                    .child(Label::new(message).color(Color::Muted))
+                    .into_any_element(),
+                None => uniform_list(
+                    cx.view().clone(),
+                    "entries",
+                    self.visible_entries.len(),
+                    |this, range, cx| {
+                        this.visible_entries[range.clone()]
+                            .iter()
+                            .zip(range)
+                            .map(|(entry, index)| this.render_entry(index, entry, cx))
+                            .collect()
+                    },
+                )
+                .size_full()
+                .track_scroll(self.list.clone())
+                .into_any_element(),
+            })
+    }
+}
+
+impl Render for DraggedOutlineItemView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl Element {
+        let ui_font = ThemeSettings::get_global(cx).ui_font.family.clone();
+        h_flex()
+            .font(ui_font)
+            .bg(cx.theme().colors().background)
+            .w(self.width)
+            .child(ListItem::new("dragged-outline-item").child(Label::new(self.text.clone())))
+    }
+}
+
+impl EventEmitter<PanelEvent> for OutlinePanel {}
+
+impl Panel for OutlinePanel {
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match OutlinePanelSettings::get_global(cx).dock {
+            OutlinePanelDockPosition::Left => DockPosition::Left,
+            OutlinePanelDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<OutlinePanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| {
+                let dock = match position {
+                    DockPosition::Left | DockPosition::Bottom => OutlinePanelDockPosition::Left,
+                    DockPosition::Right => OutlinePanelDockPosition::Right,
+                };
+                settings.dock = Some(dock);
+            },
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| OutlinePanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<ui::IconName> {
+        Some(ui::IconName::Hash)
+    }
+
+    fn icon_tooltip(&self, _cx: &WindowContext) -> Option<&'static str> {
+        Some("Outline Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn persistent_name() -> &'static str {
+        "Outline Panel"
+    }
+}
+
+impl FocusableView for OutlinePanel {
+    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use indoc::indoc;
+    use language::{Language, LanguageConfig, LanguageMatcher};
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_outline_panel_follows_and_reorders(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a.rs": indoc! {"
+                    struct One {
+                        a: usize,
+                    }
+
+                    /// Two.
+                    fn two() {}
+
+                    fn three() {}
+                "},
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        project.update(cx, |project, _| {
+            project.languages().add(Arc::new(rust_lang()))
+        });
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| {
+                let panel = OutlinePanel::new(workspace, cx);
+                workspace.add_panel(panel.clone(), cx);
+                panel
+            })
+            .unwrap();
+
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                let worktree_id = workspace
+                    .project()
+                    .read(cx)
+                    .worktrees()
+                    .next()
+                    .unwrap()
+                    .read(cx)
+                    .id();
+                workspace.open_path((worktree_id, "a.rs"), None, true, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+        wait_for_outline(cx);
+
+        assert_eq!(
+            visible_entries(&panel, cx),
+            &["struct One", "    a", "fn two", "fn three"]
+        );
+
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(1, 6)..Point::new(1, 6)])
+            })
+        });
+        panel.update(cx, |panel, cx| {
+            assert_eq!(panel.selected_index(), Some(1));
+            panel.collapse_selected_entry(&CollapseSelectedEntry, cx);
+            panel.collapse_selected_entry(&CollapseSelectedEntry, cx);
+        });
+        assert_eq!(
+            visible_entries(&panel, cx),
+            &["struct One", "fn two", "fn three"]
+        );
+
+        panel.update(cx, |panel, cx| {
+            panel
+                .filter_editor
+                .update(cx, |editor, cx| editor.set_text("thr", cx));
+        });
+        cx.run_until_parked();
+        assert_eq!(visible_entries(&panel, cx), &["fn three"]);
+
+        panel.update(cx, |panel, cx| {
+            panel
+                .filter_editor
+                .update(cx, |editor, cx| editor.set_text("", cx));
+        });
+        panel.update(cx, |panel, cx| {
+            let dragged = DraggedOutlineItem {
+                group_ix: 0,
+                item_ix: 2,
+                text: "fn two".into(),
+            };
+            panel.move_item(&dragged, 0, cx);
+        });
+        wait_for_outline(cx);
+
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)),
+            indoc! {"
+                /// Two.
+                fn two() {}
+                struct One {
+                    a: usize,
+                }
+
+
+                fn three() {}
+            "}
+        );
+        assert_eq!(
+            visible_entries(&panel, cx),
+            &["fn two", "struct One", "fn three"]
+        );
+    }
+
+    fn wait_for_outline(cx: &mut VisualTestContext) {
+        cx.run_until_parked();
+        cx.executor().advance_clock(UPDATE_DEBOUNCE);
+        cx.run_until_parked();
+    }
+
+    fn visible_entries(panel: &View<OutlinePanel>, cx: &mut VisualTestContext) -> Vec<String> {
+        panel.update(cx, |panel, _| {
+            panel
+                .visible_entries
+                .iter()
+                .map(|entry| {
+                    let group = &panel.groups[entry.group_ix];
+                    let text = match entry.item_ix {
+                        Some(item_ix) => group.outline.items[item_ix].text.clone(),
+                        None => group.title.clone().unwrap_or_default().to_string(),
+                    };
+                    format!("{}{}", "    ".repeat(entry.depth), text)
+                })
+                .collect()
+        })
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            init_settings(cx);
+        });
+    }
+
+    fn rust_lang() -> Language {
+        Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Some(tree_sitter_rust::language()),
+        )
+        .with_outline_query(
+            r#"
+            (struct_item
+                "struct" @context
+                name: (_) @name) @item
+            (field_declaration
+                name: (_) @name) @item
+            (function_item
+                "fn" @context
+                name: (_) @name) @item
+            "#,
+        )
+        .unwrap()
+    }
+}
@@ -0,0 +1,590 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use client::Client;
+use fs::{is_cross_device_error, Fs, RemoveOptions};
+use futures::{Future, StreamExt};
+use gpui::{AsyncAppContext, Model, ModelContext, Task, WeakModel};
+use rpc::{proto, TypedEnvelope};
+use sysinfo::{Pid, PidExt, ProcessRefreshKind, System, SystemExt};
+use util::{
+    paths::{LOCAL_TRASH_RELATIVE_PATH, TRASH_DIR},
+    ResultExt,
+};
+
+use crate::{Entry, Event, Project, ProjectEntryId, ProjectPath, Worktree, WorktreeId};
+
+/// A file system change made through the project, recorded so that it can be undone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileOperation {
+    Created {
+        path: ProjectPath,
+    },
+    Moved {
+        old_path: ProjectPath,
+        new_path: ProjectPath,
+    },
+    Trashed {
+        path: ProjectPath,
+        trash_id: u64,
+    },
+}
+
+#[derive(Default)]
+pub(crate) struct FileOperationHistory {
+    undo_stack: Vec<Vec<FileOperation>>,
+    redo_stack: Vec<Vec<FileOperation>>,
+}
+
+/// An entry that was moved out of its worktree and can be restored later.
+pub(crate) struct TrashedEntry {
+    worktree_id: WorktreeId,
+    path: Arc<Path>,
+    abs_path: PathBuf,
+}
+
+/// Deletes the entries that processes which have exited trashed and didn't
+/// clean up, for example because they crashed. Those entries can't be
+/// restored anymore.
+pub async fn delete_stale_trash(fs: Arc<dyn Fs>) -> Result<()> {
+    delete_stale_trash_in(fs.as_ref(), &TRASH_DIR).await
+}
+
+/// The trash inside a worktree, which is used instead of the app's when the
+/// latter is on another filesystem.
+pub(crate) fn worktree_trash_dir(worktree: &Worktree) -> PathBuf {
+    let abs_path = worktree.abs_path();
+    let is_dir = worktree.root_entry().map_or(true, |entry| entry.is_dir());
+    let root_dir = if is_dir {
+        &*abs_path
+    } else {
+        abs_path.parent().unwrap_or(&abs_path)
+    };
+    root_dir.join(*LOCAL_TRASH_RELATIVE_PATH)
+}
+
+pub(crate) async fn delete_stale_trash_in(fs: &dyn Fs, trash_dir: &Path) -> Result<()> {
+    if !fs.is_dir(trash_dir).await {
+        return Ok(());
+    }
+    let current_pid = std::process::id();
+    let mut system = System::new();
+    let mut trash_dirs = fs.read_dir(trash_dir).await?;
+    while let Some(trash_dir) = trash_dirs.next().await {
+        let trash_dir = trash_dir?;
+        // Trash directories are named after the process that owns them, which
+        // may be another instance that can still restore their entries.
+        let owner_pid = trash_dir
+            .file_name()
+            .and_then(|name| name.to_str()?.split_once('-')?.0.parse::<u32>().ok());
+        let is_stale = owner_pid.map_or(false, |pid| {
+            pid != current_pid
+                && !system.refresh_process_specifics(Pid::from_u32(pid), ProcessRefreshKind::new())
+        });
+        if is_stale {
+            fs.remove_dir(
+                &trash_dir,
+                RemoveOptions {
+                    recursive: true,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+impl Project {
+    /// Records a group of file operations that will be undone together.
+    pub fn push_file_operations(&mut self, operations: Vec<FileOperation>) {
+        if operations.is_empty() {
+            return;
+        }
+        self.file_operation_history.undo_stack.push(operations);
+        self.file_operation_history.redo_stack.clear();
+    }
+
+    pub fn can_undo_file_operations(&self) -> bool {
+        !self.file_operation_history.undo_stack.is_empty()
+    }
+
+    pub fn can_redo_file_operations(&self) -> bool {
+        !self.file_operation_history.redo_stack.is_empty()
+    }
+
+    pub fn undo_file_operations(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let Some(mut operations) = self.file_operation_history.undo_stack.pop() else {
+            return Task::ready(Ok(()));
+        };
+        cx.spawn(|this, mut cx| async move {
+            let mut inverted = Vec::new();
+            let result =
+                Self::revert_file_operations(&this, &mut operations, &mut inverted, &mut cx).await;
+            this.update(&mut cx, |this, _| {
+                let history = &mut this.file_operation_history;
+                if !operations.is_empty() {
+                    history.undo_stack.push(operations);
+                }
+                if !inverted.is_empty() {
+                    history.redo_stack.push(inverted);
+                }
+            })?;
+            result
+        })
+    }
+
+    pub fn redo_file_operations(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let Some(mut operations) = self.file_operation_history.redo_stack.pop() else {
+            return Task::ready(Ok(()));
+        };
+        cx.spawn(|this, mut cx| async move {
+            let mut inverted = Vec::new();
+            let result =
+                Self::revert_file_operations(&this, &mut operations, &mut inverted, &mut cx).await;
+            this.update(&mut cx, |this, _| {
+                let history = &mut this.file_operation_history;
+                if !operations.is_empty() {
+                    history.redo_stack.push(operations);
+                }
+                if !inverted.is_empty() {
+                    history.undo_stack.push(inverted);
+                }
+            })?;
+            result
+        })
+    }
+
+    /// Applies the inverse of each operation, last to first, moving it from
+    /// `operations` to `inverted` once it succeeds. If one fails, it and the
+    /// operations before it are left in `operations`.
+    async fn revert_file_operations(
+        this: &WeakModel<Self>,
+        operations: &mut Vec<FileOperation>,
+        inverted: &mut Vec<FileOperation>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        while let Some(operation) = operations.last() {
+            let inverse = Self::revert_file_operation(this, operation, cx).await?;
+            operations.pop();
+            inverted.push(inverse);
+        }
+        Ok(())
+    }
+
+    /// Applies the inverse of an operation, returning the operation that reverts it.
+    async fn revert_file_operation(
+        this: &WeakModel<Self>,
+        operation: &FileOperation,
+        cx: &mut AsyncAppContext,
+    ) -> Result<FileOperation> {
+        match operation {
+            FileOperation::Created { path } => {
+                let trash = this.update(cx, |this, cx| {
+                    let entry = this
+                        .entry_for_path(path, cx)
+                        .ok_or_else(|| anyhow!("no entry for path {:?}", path.path))?;
+                    this.trash_entry(entry.id, cx)
+                        .ok_or_else(|| anyhow!("invalid entry"))
+                })??;
+                let trash_id = trash.await?;
+                Ok(FileOperation::Trashed {
+                    path: path.clone(),
+                    trash_id,
+                })
+            }
+            FileOperation::Moved { old_path, new_path } => {
+                let move_entry = this.update(cx, |this, cx| {
+                    let entry = this
+                        .entry_for_path(new_path, cx)
+                        .ok_or_else(|| anyhow!("no entry for path {:?}", new_path.path))?;
+                    anyhow::Ok(this.move_entry_to(entry.id, old_path.clone(), cx))
+                })??;
+                move_entry.await?;
+                Ok(FileOperation::Moved {
+                    old_path: new_path.clone(),
+                    new_path: old_path.clone(),
+                })
+            }
+            FileOperation::Trashed { path, trash_id } => {
+                this.update(cx, |this, cx| {
+                    this.restore_entry(path.worktree_id, *trash_id, cx)
+                })?
+                .await?;
+                Ok(FileOperation::Created { path: path.clone() })
+            }
+        }
+    }
+
+    /// Permanently deletes the entries that this project trashed, since they
+    /// can only be restored while the project is open.
+    pub(crate) fn empty_trash(&mut self) -> impl Future<Output = ()> {
+        let fs = self.fs.clone();
+        let trash_dirs = self
+            .trashed_entries
+            .drain()
+            .filter_map(|(_, entry)| entry.abs_path.parent().map(Path::to_path_buf))
+            .collect::<Vec<_>>();
+        async move {
+            for trash_dir in trash_dirs {
+                fs.remove_dir(
+                    &trash_dir,
+                    RemoveOptions {
+                        recursive: true,
+                        ignore_if_not_exists: true,
+                    },
+                )
+                .await
+                .log_err();
+            }
+        }
+    }
+
+    /// Moves an entry out of its worktree into the trash, returning an id that can be
+    /// passed to [`Project::restore_entry`].
+    pub fn trash_entry(
+        &mut self,
+        entry_id: ProjectEntryId,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Task<Result<u64>>> {
+        let worktree = self.worktree_for_entry(entry_id, cx)?;
+
+        cx.emit(Event::DeletedEntry(entry_id));
+
+        if self.is_local() {
+            let trash_id = self.next_trash_id;
+            self.next_trash_id += 1;
+            let worktree_id = worktree.read(cx).id();
+            let file_name = worktree
+                .read(cx)
+                .entry_for_id(entry_id)?
+                .path
+                .file_name()
+                .map(|name| name.to_os_string())
+                .unwrap_or_default();
+            let trash_name = format!("{}-{}", std::process::id(), trash_id);
+            let worktree_trash_dir = worktree_trash_dir(worktree.read(cx));
+            let fs = self.fs.clone();
+            Some(cx.spawn(move |this, mut cx| async move {
+                // Trashing an entry is a rename, so that it's quick and can't
+                // stop half-way. Entries on another filesystem than the app's
+                // trash go to a trash inside their worktree.
+                let mut abs_path = TRASH_DIR.join(&trash_name).join(&file_name);
+                let mut result =
+                    Self::move_entry_out(&worktree, entry_id, abs_path.clone(), &mut cx).await;
+                if matches!(&result, Err(error) if is_cross_device_error(error)) {
+                    fs.create_dir(&worktree_trash_dir).await?;
+                    let gitignore_path = worktree_trash_dir.join(".gitignore");
+                    if !fs.is_file(&gitignore_path).await {
+                        fs.atomic_write(gitignore_path, "*\n".into()).await?;
+                    }
+                    abs_path = worktree_trash_dir.join(&trash_name).join(&file_name);
+                    result =
+                        Self::move_entry_out(&worktree, entry_id, abs_path.clone(), &mut cx).await;
+                }
+                let path = result?;
+                this.update(&mut cx, |this, _| {
+                    this.trashed_entries.insert(
+                        trash_id,
+                        TrashedEntry {
+                            worktree_id,
+                            path,
+                            abs_path,
+                        },
+                    );
+                })?;
+                Ok(trash_id)
+            }))
+        } else {
+            let client = self.client.clone();
+            let project_id = self.remote_id().unwrap();
+            Some(cx.spawn(move |_, mut cx| async move {
+                let response = client
+                    .request(proto::TrashProjectEntry {
+                        project_id,
+                        entry_id: entry_id.to_proto(),
+                    })
+                    .await?;
+                worktree
+                    .update(&mut cx, move |worktree, cx| {
+                        worktree.as_remote_mut().unwrap().delete_entry(
+                            entry_id,
+                            response.worktree_scan_id as usize,
+                            cx,
+                        )
+                    })?
+                    .await?;
+                Ok(response.trash_id)
+            }))
+        }
+    }
+
+    async fn move_entry_out(
+        worktree: &Model<Worktree>,
+        entry_id: ProjectEntryId,
+        abs_path: PathBuf,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Arc<Path>> {
+        worktree
+            .update(cx, |worktree, cx| {
+                worktree
+                    .as_local_mut()
+                    .unwrap()
+                    .move_entry_out(entry_id, abs_path, cx)
+            })?
+            .ok_or_else(|| anyhow!("invalid entry"))?
+            .await
+    }
+
+    /// Moves a previously trashed entry back to where it was before being trashed.
+    pub fn restore_entry(
+        &mut self,
+        worktree_id: WorktreeId,
+        trash_id: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("worktree not found")));
+        };
+
+        if self.is_local() {
+            let Some(trashed_entry) = self.trashed_entries.get(&trash_id) else {
+                return Task::ready(Err(anyhow!("no trashed entry with id {trash_id}")));
+            };
+            if trashed_entry.worktree_id != worktree_id {
+                return Task::ready(Err(anyhow!("entry was trashed from another worktree")));
+            }
+            let abs_path = trashed_entry.abs_path.clone();
+            let path = trashed_entry.path.clone();
+            let restore = worktree.update(cx, |worktree, cx| {
+                worktree
+                    .as_local_mut()
+                    .unwrap()
+                    .move_external_entry(abs_path, path, cx)
+            });
+            cx.spawn(move |this, mut cx| async move {
+                let entry = restore.await?;
+                this.update(&mut cx, |this, _| this.trashed_entries.remove(&trash_id))?;
+                Ok(entry)
+            })
+        } else {
+            let client = self.client.clone();
+            let project_id = self.remote_id().unwrap();
+            cx.spawn(move |_, mut cx| async move {
+                let response = client
+                    .request(proto::RestoreProjectEntry {
+                        project_id,
+                        worktree_id: worktree_id.to_proto(),
+                        trash_id,
+                    })
+                    .await?;
+                match response.entry {
+                    Some(entry) => worktree
+                        .update(&mut cx, |worktree, cx| {
+                            worktree.as_remote_mut().unwrap().insert_entry(
+                                entry,
+                                response.worktree_scan_id as usize,
+                                cx,
+                            )
+                        })?
+                        .await
+                        .map(Some),
+                    None => Ok(None),
+                }
+            })
+        }
+    }
+
+    /// Moves an entry to `new_path`, which may belong to a different worktree.
+    pub fn move_entry_to(
+        &mut self,
+        entry_id: ProjectEntryId,
+        new_path: ProjectPath,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        self.transfer_entry(entry_id, new_path, false, cx)
+    }
+
+    /// Copies an entry to `new_path`, which may belong to a different worktree.
+    pub fn copy_entry_to(
+        &mut self,
+        entry_id: ProjectEntryId,
+        new_path: ProjectPath,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        self.transfer_entry(entry_id, new_path, true, cx)
+    }
+
+    fn transfer_entry(
+        &mut self,
+        entry_id: ProjectEntryId,
+        new_path: ProjectPath,
+        copy: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        let Some(source_worktree) = self.worktree_for_entry(entry_id, cx) else {
+            return Task::ready(Ok(None));
+        };
+        if source_worktree.read(cx).id() == new_path.worktree_id {
+            return if copy {
+                self.copy_entry(entry_id, new_path.path, cx)
+            } else {
+                self.rename_entry(entry_id, new_path.path, cx)
+            };
+        }
+        let Some(target_worktree) = self.worktree_for_id(new_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("worktree not found")));
+        };
+
+        if self.is_local() {
+            let Some(entry_path) = source_worktree
+                .read(cx)
+                .entry_for_id(entry_id)
+                .map(|entry| entry.path.clone())
+            else {
+                return Task::ready(Ok(None));
+            };
+            let abs_source_path = match source_worktree.read(cx).absolutize(&entry_path) {
+                Ok(abs_path) => abs_path,
+                Err(error) => return Task::ready(Err(error)),
+            };
+            let transfer = target_worktree.update(cx, |worktree, cx| {
+                let worktree = worktree.as_local_mut().unwrap();
+                if copy {
+                    worktree.copy_external_entry(abs_source_path, new_path.path, cx)
+                } else {
+                    worktree.move_external_entry(abs_source_path, new_path.path, cx)
+                }
+            });
+            cx.spawn(move |_, mut cx| async move {
+                let entry = transfer.await?;
+                if !copy {
+                    source_worktree
+                        .update(&mut cx, |worktree, _| {
+                            worktree
+                                .as_local_mut()
+                                .unwrap()
+                                .refresh_entries_for_paths(vec![entry_path])
+                        })?
+                        .recv()
+                        .await;
+                }
+                Ok(entry)
+            })
+        } else {
+            let client = self.client.clone();
+            let project_id = self.remote_id().unwrap();
+            let new_worktree_id = Some(new_path.worktree_id.to_proto());
+            let new_path = new_path.path.to_string_lossy().into();
+            cx.spawn(move |_, mut cx| async move {
+                let response = if copy {
+                    client
+                        .request(proto::CopyProjectEntry {
+                            project_id,
+                            entry_id: entry_id.to_proto(),
+                            new_path,
+                            new_worktree_id,
+                        })
+                        .await?
+                } else {
+                    client
+                        .request(proto::RenameProjectEntry {
+                            project_id,
+                            entry_id: entry_id.to_proto(),
+                            new_path,
+                            new_worktree_id,
+                        })
+                        .await?
+                };
+                match response.entry {
+                    Some(entry) => target_worktree
+                        .update(&mut cx, |worktree, cx| {
+                            worktree.as_remote_mut().unwrap().insert_entry(
+                                entry,
+                                response.worktree_scan_id as usize,
+                                cx,
+                            )
+                        })?
+                        .await
+                        .map(Some),
+                    None => Ok(None),
+                }
+            })
+        }
+    }
+
+    /// Handles a rename or copy request whose destination is in a different worktree
+    /// than the entry being transferred.
+    pub(crate) async fn handle_transfer_project_entry(
+        this: Model<Self>,
+        entry_id: ProjectEntryId,
+        new_path: ProjectPath,
+        copy: bool,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ProjectEntryResponse> {
+        let worktree = this.update(&mut cx, |this, cx| {
+            this.worktree_for_id(new_path.worktree_id, cx)
+                .ok_or_else(|| anyhow!("worktree not found"))
+        })??;
+        let worktree_scan_id = worktree.update(&mut cx, |worktree, _| worktree.scan_id())?;
+        let entry = this
+            .update(&mut cx, |this, cx| {
+                this.transfer_entry(entry_id, new_path, copy, cx)
+            })?
+            .await?;
+        Ok(proto::ProjectEntryResponse {
+            entry: entry.as_ref().map(|e| e.into()),
+            worktree_scan_id: worktree_scan_id as u64,
+        })
+    }
+
+    pub(crate) async fn handle_trash_project_entry(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::TrashProjectEntry>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::TrashProjectEntryResponse> {
+        let entry_id = ProjectEntryId::from_proto(envelope.payload.entry_id);
+        let worktree = this.update(&mut cx, |this, cx| {
+            this.worktree_for_entry(entry_id, cx)
+                .ok_or_else(|| anyhow!("worktree not found"))
+        })??;
+        let worktree_scan_id = worktree.update(&mut cx, |worktree, _| worktree.scan_id())?;
+        let trash_id = this
+            .update(&mut cx, |this, cx| {
+                this.trash_entry(entry_id, cx)
+                    .ok_or_else(|| anyhow!("invalid entry"))
+            })??
+            .await?;
+        Ok(proto::TrashProjectEntryResponse {
+            trash_id,
+            worktree_scan_id: worktree_scan_id as u64,
+        })
+    }
+
+    pub(crate) async fn handle_restore_project_entry(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::RestoreProjectEntry>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ProjectEntryResponse> {
+        let worktree_id = WorktreeId::from_proto(envelope.payload.worktree_id);
+        let worktree = this.update(&mut cx, |this, cx| {
+            this.worktree_for_id(worktree_id, cx)
+                .ok_or_else(|| anyhow!("worktree not found"))
+        })??;
+        let worktree_scan_id = worktree.update(&mut cx, |worktree, _| worktree.scan_id())?;
+        let entry = this
+            .update(&mut cx, |this, cx| {
+                this.restore_entry(worktree_id, envelope.payload.trash_id, cx)
+            })?
+            .await?;
+        Ok(proto::ProjectEntryResponse {
+            entry: entry.as_ref().map(|e| e.into()),
+            worktree_scan_id: worktree_scan_id as u64,
+        })
+    }
+}
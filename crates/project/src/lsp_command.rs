@@ -761,17 +761,21 @@ fn location_links_to_proto(
 ) -> Vec<proto::LocationLink> {
     links
         .into_iter()
+        .filter(|definition| !project.is_excluded_from_share(&definition.target.buffer, cx))
         .map(|definition| {
-            let origin = definition.origin.map(|origin| {
-                let buffer_id = project
-                    .create_buffer_for_peer(&origin.buffer, peer_id, cx)
-                    .into();
-                proto::Location {
-                    start: Some(serialize_anchor(&origin.range.start)),
-                    end: Some(serialize_anchor(&origin.range.end)),
-                    buffer_id,
-                }
-            });
+            let origin = definition
+                .origin
+                .filter(|origin| !project.is_excluded_from_share(&origin.buffer, cx))
+                .map(|origin| {
+                    let buffer_id = project
+                        .create_buffer_for_peer(&origin.buffer, peer_id, cx)
+                        .into();
+                    proto::Location {
+                        start: Some(serialize_anchor(&origin.range.start)),
+                        end: Some(serialize_anchor(&origin.range.end)),
+                        buffer_id,
+                    }
+                });
 
             let buffer_id = project
                 .create_buffer_for_peer(&definition.target.buffer, peer_id, cx)
@@ -902,6 +906,7 @@ impl LspCommand for GetReferences {
     ) -> proto::GetReferencesResponse {
         let locations = response
             .into_iter()
+            .filter(|definition| !project.is_excluded_from_share(&definition.buffer, cx))
             .map(|definition| {
                 let buffer_id = project.create_buffer_for_peer(&definition.buffer, peer_id, cx);
                 proto::Location {
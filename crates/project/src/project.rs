@@ -1,4 +1,5 @@
 pub mod debounced_delay;
+mod file_operations;
 mod ignore;
 pub mod lsp_command;
 pub mod lsp_ext_command;
@@ -19,6 +20,7 @@ use clock::ReplicaId;
 use collections::{hash_map, BTreeMap, HashMap, HashSet, VecDeque};
 use copilot::Copilot;
 use debounced_delay::DebouncedDelay;
+use file_operations::{FileOperationHistory, TrashedEntry};
 use futures::{
     channel::mpsc::{self, UnboundedReceiver},
     future::{try_join_all, Shared},
@@ -83,10 +85,15 @@ use std::{
 use terminals::Terminals;
 use text::{Anchor, BufferId};
 use util::{
-    debug_panic, defer, http::HttpClient, merge_json_value_into,
-    paths::LOCAL_SETTINGS_RELATIVE_PATH, post_inc, ResultExt, TryFutureExt as _,
+    debug_panic, defer,
+    http::HttpClient,
+    merge_json_value_into,
+    paths::{PathMatcher, LOCAL_SETTINGS_RELATIVE_PATH},
+    post_inc, ResultExt, TryFutureExt as _,
 };
 
+pub use file_operations::{delete_stale_trash, FileOperation};
+use file_operations::{delete_stale_trash_in, worktree_trash_dir};
 pub use fs::*;
 #[cfg(any(test, feature = "test-support"))]
 pub use prettier::FORMAT_SUFFIX as TEST_PRETTIER_FORMAT_SUFFIX;
@@ -152,6 +159,9 @@ pub struct Project {
     default_prettier: DefaultPrettier,
     prettiers_per_worktree: HashMap<WorktreeId, HashSet<Option<PathBuf>>>,
     prettier_instances: HashMap<PathBuf, PrettierInstance>,
+    trashed_entries: HashMap<u64, TrashedEntry>,
+    next_trash_id: u64,
+    file_operation_history: FileOperationHistory,
 }
 
 pub enum LanguageServerToQuery {
@@ -203,11 +213,15 @@ enum ProjectClientState {
     Shared {
         remote_id: u64,
         updates_tx: mpsc::UnboundedSender<LocalProjectUpdate>,
+        /// Paths that guests can't open, matching the globs the project was shared with.
+        excluded_paths: Vec<PathMatcher>,
         _send_updates: Task<Result<()>>,
     },
     Remote {
         sharing_has_stopped: bool,
         capability: Capability,
+        /// Whether the host shared the project read-only with this guest.
+        read_only_share: bool,
         remote_id: u64,
         replica_id: ReplicaId,
     },
@@ -545,6 +559,8 @@ impl Project {
         client.add_model_request_handler(Self::handle_rename_project_entry);
         client.add_model_request_handler(Self::handle_copy_project_entry);
         client.add_model_request_handler(Self::handle_delete_project_entry);
+        client.add_model_request_handler(Self::handle_trash_project_entry);
+        client.add_model_request_handler(Self::handle_restore_project_entry);
         client.add_model_request_handler(Self::handle_expand_project_entry);
         client.add_model_request_handler(Self::handle_apply_additional_edits_for_completion);
         client.add_model_request_handler(Self::handle_resolve_completion_documentation);
@@ -573,6 +589,12 @@ impl Project {
         client.add_model_request_handler(Self::handle_save_buffer);
         client.add_model_message_handler(Self::handle_update_diff_base);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
+        client.add_model_request_handler(Self::handle_open_terminal);
+        client.add_model_request_handler(Self::handle_terminal_input);
+        client.add_model_request_handler(Self::handle_resize_terminal);
+        client.add_model_request_handler(Self::handle_close_terminal);
+        client.add_model_message_handler(Self::handle_update_terminal);
+        client.add_model_message_handler(Self::handle_remove_terminal);
     }
 
     pub fn local(
@@ -610,6 +632,7 @@ impl Project {
                     cx.observe_global::<SettingsStore>(Self::on_settings_changed),
                     cx.on_release(Self::release),
                     cx.on_app_quit(Self::shutdown_language_servers),
+                    cx.on_app_quit(|this, _| this.empty_trash()),
                 ],
                 _maintain_buffer_languages: Self::maintain_buffer_languages(languages.clone(), cx),
                 _maintain_workspace_config: Self::maintain_workspace_config(cx),
@@ -631,6 +654,9 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    shared: HashMap::default(),
+                    next_shared_id: 0,
+                    replicas: HashMap::default(),
                 },
                 copilot_lsp_subscription,
                 copilot_log_subscription: None,
@@ -639,6 +665,9 @@ impl Project {
                 default_prettier: DefaultPrettier::default(),
                 prettiers_per_worktree: HashMap::default(),
                 prettier_instances: HashMap::default(),
+                trashed_entries: HashMap::default(),
+                next_trash_id: 0,
+                file_operation_history: FileOperationHistory::default(),
             }
         })
     }
@@ -650,6 +679,7 @@ impl Project {
         languages: Arc<LanguageRegistry>,
         fs: Arc<dyn Fs>,
         role: proto::ChannelRole,
+        invite_token: Option<String>,
         mut cx: AsyncAppContext,
     ) -> Result<Model<Self>> {
         client.authenticate_and_connect(true, &cx).await?;
@@ -658,6 +688,7 @@ impl Project {
         let response = client
             .request_envelope(proto::JoinProject {
                 project_id: remote_id,
+                invite_token,
             })
             .await?;
         let this = cx.new_model(|cx| {
@@ -705,6 +736,7 @@ impl Project {
                 client_state: ProjectClientState::Remote {
                     sharing_has_stopped: false,
                     capability: Capability::ReadWrite,
+                    read_only_share: response.payload.read_only,
                     remote_id,
                     replica_id,
                 },
@@ -736,6 +768,9 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    shared: HashMap::default(),
+                    next_shared_id: 0,
+                    replicas: HashMap::default(),
                 },
                 copilot_lsp_subscription,
                 copilot_log_subscription: None,
@@ -744,6 +779,9 @@ impl Project {
                 default_prettier: DefaultPrettier::default(),
                 prettiers_per_worktree: HashMap::default(),
                 prettier_instances: HashMap::default(),
+                trashed_entries: HashMap::default(),
+                next_trash_id: 0,
+                file_operation_history: FileOperationHistory::default(),
             };
             this.set_role(role, cx);
             for worktree in worktrees {
@@ -773,6 +811,7 @@ impl Project {
     }
 
     fn release(&mut self, cx: &mut AppContext) {
+        cx.background_executor().spawn(self.empty_trash()).detach();
         match &self.client_state {
             ProjectClientState::Local => {}
             ProjectClientState::Shared { .. } => {
@@ -1172,6 +1211,7 @@ impl Project {
                         project_id,
                         entry_id: entry_id.to_proto(),
                         new_path: new_path.to_string_lossy().into(),
+                        new_worktree_id: None,
                     })
                     .await?;
                 match response.entry {
@@ -1218,6 +1258,7 @@ impl Project {
                         project_id,
                         entry_id: entry_id.to_proto(),
                         new_path: new_path.to_string_lossy().into(),
+                        new_worktree_id: None,
                     })
                     .await?;
                 match response.entry {
@@ -1307,10 +1348,21 @@ impl Project {
         }
     }
 
-    pub fn shared(&mut self, project_id: u64, cx: &mut ModelContext<Self>) -> Result<()> {
+    /// Starts sharing the project under the given ID. Guests can't open files
+    /// matching `excluded_paths`, which are globs relative to each worktree's root.
+    pub fn shared(
+        &mut self,
+        project_id: u64,
+        excluded_paths: &[String],
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
         if !matches!(self.client_state, ProjectClientState::Local) {
             return Err(anyhow!("project was already shared"));
         }
+        let excluded_paths = excluded_paths
+            .iter()
+            .map(|glob| PathMatcher::new(glob))
+            .collect::<Result<Vec<_>, _>>()?;
         self.client_subscriptions.push(
             self.client
                 .subscribe_to_entity(project_id)?
@@ -1372,6 +1424,7 @@ impl Project {
         self.client_state = ProjectClientState::Shared {
             remote_id: project_id,
             updates_tx,
+            excluded_paths,
             _send_updates: cx.spawn(move |this, mut cx| async move {
                 while let Some(update) = updates_rx.next().await {
                     match update {
@@ -1529,6 +1582,7 @@ impl Project {
             self.collaborators.clear();
             self.shared_buffers.clear();
             self.client_subscriptions.clear();
+            self.unshare_terminals(cx);
 
             for worktree_handle in self.worktrees.iter_mut() {
                 if let WorktreeHandle::Strong(worktree) = worktree_handle {
@@ -1570,13 +1624,19 @@ impl Project {
     }
 
     pub fn set_role(&mut self, role: proto::ChannelRole, cx: &mut ModelContext<Self>) {
-        let new_capability =
-            if role == proto::ChannelRole::Member || role == proto::ChannelRole::Admin {
+        if let ProjectClientState::Remote {
+            capability,
+            read_only_share,
+            ..
+        } = &mut self.client_state
+        {
+            let new_capability = if !*read_only_share
+                && (role == proto::ChannelRole::Member || role == proto::ChannelRole::Admin)
+            {
                 Capability::ReadWrite
             } else {
                 Capability::ReadOnly
             };
-        if let ProjectClientState::Remote { capability, .. } = &mut self.client_state {
             if *capability == new_capability {
                 return;
             }
@@ -1585,6 +1645,7 @@ impl Project {
             for buffer in self.opened_buffers() {
                 buffer.update(cx, |buffer, cx| buffer.set_capability(new_capability, cx));
             }
+            self.set_replica_terminals_read_only(new_capability == Capability::ReadOnly, cx);
         }
     }
 
@@ -1619,6 +1680,8 @@ impl Project {
                 }
             }
 
+            self.set_replica_terminals_read_only(true, cx);
+
             // Wake up all futures currently waiting on a buffer to get opened,
             // to give them a chance to fail now that we've disconnected.
             *self.opened_buffer.0.borrow_mut() = ();
@@ -2295,6 +2358,10 @@ impl Project {
 
         match event {
             BufferEvent::Operation(operation) => {
+                // Guests never see excluded buffers, so don't send them edits.
+                if self.is_excluded_from_share(&buffer, cx) {
+                    return None;
+                }
                 self.buffer_ordered_messages_tx
                     .unbounded_send(BufferOrderedMessage::Operation {
                         buffer_id: buffer.read(cx).remote_id(),
@@ -6375,7 +6442,7 @@ impl Project {
                             client.clone(),
                             path.clone(),
                             visible,
-                            fs,
+                            fs.clone(),
                             next_entry_id,
                             &mut cx,
                         )
@@ -6386,6 +6453,15 @@ impl Project {
                         })?;
 
                         let worktree = worktree?;
+                        let trash_dir =
+                            worktree.update(&mut cx, |worktree, _| worktree_trash_dir(worktree))?;
+                        cx.background_executor()
+                            .spawn(async move {
+                                delete_stale_trash_in(fs.as_ref(), &trash_dir)
+                                    .await
+                                    .log_err();
+                            })
+                            .detach();
                         project
                             .update(&mut cx, |project, cx| project.add_worktree(&worktree, cx))?;
                         Ok(worktree)
@@ -6756,7 +6832,7 @@ impl Project {
 
         let remote_id = self.remote_id();
         let client = self.client.clone();
-        cx.spawn(move |_, mut cx| async move {
+        cx.spawn(move |this, mut cx| async move {
             // Wait for all of the buffers to load.
             let future_buffers = future_buffers.collect::<Vec<_>>().await;
 
@@ -6788,7 +6864,9 @@ impl Project {
                     buffer.set_diff_base(diff_base.clone(), cx);
                     buffer.remote_id().into()
                 })?;
-                if let Some(project_id) = remote_id {
+                let is_excluded =
+                    this.update(&mut cx, |this, cx| this.is_excluded_from_share(&buffer, cx))?;
+                if let Some(project_id) = remote_id.filter(|_| !is_excluded) {
                     client
                         .send(proto::UpdateDiffBase {
                             project_id,
@@ -7102,6 +7180,7 @@ impl Project {
                 }
             }
             this.shared_buffers.remove(&peer_id);
+            this.remove_terminal_viewers(peer_id, cx);
 
             cx.emit(Event::CollaboratorLeft(peer_id));
             cx.notify();
@@ -7198,6 +7277,13 @@ impl Project {
         mut cx: AsyncAppContext,
     ) -> Result<proto::ProjectEntryResponse> {
         let entry_id = ProjectEntryId::from_proto(envelope.payload.entry_id);
+        if let Some(new_worktree_id) = envelope.payload.new_worktree_id {
+            let new_path = ProjectPath {
+                worktree_id: WorktreeId::from_proto(new_worktree_id),
+                path: Path::new(&envelope.payload.new_path).into(),
+            };
+            return Self::handle_transfer_project_entry(this, entry_id, new_path, false, cx).await;
+        }
         let worktree = this.update(&mut cx, |this, cx| {
             this.worktree_for_entry(entry_id, cx)
                 .ok_or_else(|| anyhow!("worktree not found"))
@@ -7225,6 +7311,13 @@ impl Project {
         mut cx: AsyncAppContext,
     ) -> Result<proto::ProjectEntryResponse> {
         let entry_id = ProjectEntryId::from_proto(envelope.payload.entry_id);
+        if let Some(new_worktree_id) = envelope.payload.new_worktree_id {
+            let new_path = ProjectPath {
+                worktree_id: WorktreeId::from_proto(new_worktree_id),
+                path: Path::new(&envelope.payload.new_path).into(),
+            };
+            return Self::handle_transfer_project_entry(this, entry_id, new_path, true, cx).await;
+        }
         let worktree = this.update(&mut cx, |this, cx| {
             this.worktree_for_entry(entry_id, cx)
                 .ok_or_else(|| anyhow!("worktree not found"))
@@ -7656,7 +7749,10 @@ impl Project {
             for buffer in envelope.payload.buffers {
                 let buffer_id = BufferId::new(buffer.id)?;
                 let remote_version = language::proto::deserialize_version(&buffer.version);
-                if let Some(buffer) = this.buffer_for_id(buffer_id) {
+                if let Some(buffer) = this
+                    .buffer_for_id(buffer_id)
+                    .filter(|buffer| !this.is_excluded_from_share(buffer, cx))
+                {
                     this.shared_buffers
                         .entry(guest_id)
                         .or_default()
@@ -8049,8 +8145,12 @@ impl Project {
             })?
             .await?;
 
-        Ok(proto::GetProjectSymbolsResponse {
-            symbols: symbols.iter().map(serialize_symbol).collect(),
+        this.update(&mut cx, |this, _| proto::GetProjectSymbolsResponse {
+            symbols: symbols
+                .iter()
+                .filter(|symbol| !this.is_path_excluded_from_share(&symbol.path.path))
+                .map(serialize_symbol)
+                .collect(),
         })
     }
 
@@ -8067,6 +8167,9 @@ impl Project {
         cx.spawn(move |mut cx| async move {
             let mut locations = Vec::new();
             while let Some((buffer, ranges)) = result.next().await {
+                if this.update(&mut cx, |this, cx| this.is_excluded_from_share(&buffer, cx))? {
+                    continue;
+                }
                 for range in ranges {
                     let start = serialize_anchor(&range.start);
                     let end = serialize_anchor(&range.end);
@@ -8117,7 +8220,7 @@ impl Project {
                 .file()
                 .map(|f| f.is_private())
                 .unwrap_or_default();
-            if is_private {
+            if is_private || this.is_excluded_from_share(&buffer, cx) {
                 Err(anyhow!(ErrorCode::UnsharedItem))
             } else {
                 Ok(proto::OpenBufferForSymbolResponse {
@@ -8127,6 +8230,25 @@ impl Project {
         })?
     }
 
+    /// Returns whether the buffer's path was excluded when sharing the project.
+    pub(crate) fn is_excluded_from_share(&self, buffer: &Model<Buffer>, cx: &AppContext) -> bool {
+        buffer
+            .read(cx)
+            .file()
+            .map_or(false, |file| self.is_path_excluded_from_share(file.path()))
+    }
+
+    /// Returns whether the given worktree-relative path was excluded when
+    /// sharing the project.
+    fn is_path_excluded_from_share(&self, path: &Path) -> bool {
+        let ProjectClientState::Shared { excluded_paths, .. } = &self.client_state else {
+            return false;
+        };
+        excluded_paths
+            .iter()
+            .any(|excluded_path| excluded_path.is_match(path))
+    }
+
     fn symbol_signature(&self, project_path: &ProjectPath) -> [u8; 32] {
         let mut hasher = Sha256::new();
         hasher.update(project_path.worktree_id.to_proto().to_be_bytes());
@@ -8183,7 +8305,7 @@ impl Project {
                 .file()
                 .map(|f| f.is_private())
                 .unwrap_or_default();
-            if is_private {
+            if is_private || this.is_excluded_from_share(&buffer, cx) {
                 Err(anyhow!(ErrorCode::UnsharedItem))
             } else {
                 Ok(proto::OpenBufferResponse {
@@ -8204,6 +8326,9 @@ impl Project {
             transactions: Default::default(),
         };
         for (buffer, transaction) in project_transaction.0 {
+            if self.is_excluded_from_share(&buffer, cx) {
+                continue;
+            }
             serialized_transaction
                 .buffer_ids
                 .push(self.create_buffer_for_peer(&buffer, peer_id, cx).into());
@@ -8259,6 +8384,11 @@ impl Project {
         cx: &mut AppContext,
     ) -> BufferId {
         let buffer_id = buffer.read(cx).remote_id();
+        // Callers are expected to leave excluded buffers out of their responses.
+        if self.is_excluded_from_share(buffer, cx) {
+            log::error!("tried to send excluded buffer {buffer_id} to {peer_id:?}");
+            return buffer_id;
+        }
         if let ProjectClientState::Shared { updates_tx, .. } = &self.client_state {
             updates_tx
                 .unbounded_send(LocalProjectUpdate::CreateBufferForPeer { peer_id, buffer_id })
@@ -31,7 +31,8 @@ pub struct ProjectSettings {
     ///   "**/.DS_Store",
     ///   "**/Thumbs.db",
     ///   "**/.classpath",
-    ///   "**/.settings"
+    ///   "**/.settings",
+    ///   "**/.zed/trash"
     /// ]
     #[serde(default)]
     pub file_scan_exclusions: Option<Vec<String>>,
@@ -4394,6 +4394,52 @@ async fn test_create_entry(cx: &mut gpui::TestAppContext) {
     assert!(result.is_err())
 }
 
+#[gpui::test]
+async fn test_delete_stale_worktree_trash(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let current_trash = format!("{}-0", std::process::id());
+    let exited_trash = format!("{}-0", u32::MAX);
+    let fs = FakeFs::new(cx.executor().clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "",
+            ".zed": {
+                "trash": {
+                    ".gitignore": "*",
+                    current_trash.clone(): { "b.txt": "" },
+                    exited_trash: { "c.txt": "" },
+                },
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+    cx.executor().run_until_parked();
+
+    // Only the trash of processes that have exited is deleted, and the trash
+    // isn't part of the worktree.
+    assert_eq!(
+        fs.files(),
+        [
+            PathBuf::from("/root/.zed/trash/.gitignore"),
+            Path::new("/root/.zed/trash")
+                .join(&current_trash)
+                .join("b.txt"),
+            PathBuf::from("/root/a.txt"),
+        ]
+    );
+    project.read_with(cx, |project, cx| {
+        let worktree = project.worktrees().next().unwrap().read(cx);
+        assert_eq!(
+            worktree.paths().map(AsRef::as_ref).collect::<Vec<_>>(),
+            [Path::new(".zed"), Path::new("a.txt")]
+        );
+    });
+}
+
 async fn search(
     project: &Model<Project>,
     query: SearchQuery,
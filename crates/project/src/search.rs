@@ -37,6 +37,7 @@ pub enum SearchQuery {
     Text {
         search: Arc<AhoCorasick>,
         replacement: Option<String>,
+        preserve_case: bool,
         whole_word: bool,
         case_sensitive: bool,
         include_ignored: bool,
@@ -46,6 +47,7 @@ pub enum SearchQuery {
     Regex {
         regex: Regex,
         replacement: Option<String>,
+        preserve_case: bool,
         multiline: bool,
         whole_word: bool,
         case_sensitive: bool,
@@ -75,6 +77,7 @@ impl SearchQuery {
         Ok(Self::Text {
             search: Arc::new(search),
             replacement: None,
+            preserve_case: false,
             whole_word,
             case_sensitive,
             include_ignored,
@@ -113,6 +116,7 @@ impl SearchQuery {
         Ok(Self::Regex {
             regex,
             replacement: None,
+            preserve_case: false,
             multiline,
             whole_word,
             case_sensitive,
@@ -157,6 +161,22 @@ impl SearchQuery {
             }
         }
     }
+    /// Makes replacements follow the casing of the text they replace, see [`SearchQuery::replacement_for`].
+    pub fn with_preserve_case(mut self, new_preserve_case: bool) -> Self {
+        match self {
+            Self::Text {
+                ref mut preserve_case,
+                ..
+            }
+            | Self::Regex {
+                ref mut preserve_case,
+                ..
+            } => {
+                *preserve_case = new_preserve_case;
+                self
+            }
+        }
+    }
     pub fn to_proto(&self, project_id: u64) -> proto::SearchProject {
         proto::SearchProject {
             project_id,
@@ -223,9 +243,20 @@ impl SearchQuery {
             }
         }
     }
+    /// Returns whether replacements adjust their casing to the text they replace.
+    pub fn preserve_case(&self) -> bool {
+        match self {
+            SearchQuery::Text { preserve_case, .. } | SearchQuery::Regex { preserve_case, .. } => {
+                *preserve_case
+            }
+        }
+    }
     /// Replaces search hits if replacement is set. `text` is assumed to be a string that matches this `SearchQuery` exactly, without any leftovers on either side.
+    ///
+    /// For regex queries, the replacement may reference capture groups as `$1`, `${1}` or `${name}`.
+    /// When case preservation is on, the replacement is upper-cased, lower-cased or capitalized to follow `text`.
     pub fn replacement_for<'a>(&self, text: &'a str) -> Option<Cow<'a, str>> {
-        match self {
+        let replacement = match self {
             SearchQuery::Text { replacement, .. } => replacement.clone().map(Cow::from),
             SearchQuery::Regex {
                 regex, replacement, ..
@@ -236,6 +267,11 @@ impl SearchQuery {
                     None
                 }
             }
+        }?;
+        if self.preserve_case() {
+            Some(preserve_case(text, replacement))
+        } else {
+            Some(replacement)
         }
     }
     pub async fn search(
@@ -401,6 +437,41 @@ impl SearchQuery {
     }
 }
 
+fn preserve_case<'a>(matched: &str, replacement: Cow<'a, str>) -> Cow<'a, str> {
+    let mut cased_chars = matched
+        .chars()
+        .filter(|c| c.is_lowercase() || c.is_uppercase());
+    let Some(first) = cased_chars.next() else {
+        return replacement;
+    };
+    let rest = cased_chars.collect::<Vec<_>>();
+    if rest.is_empty() {
+        if first.is_uppercase() {
+            capitalize(replacement)
+        } else {
+            replacement
+        }
+    } else if first.is_uppercase() && rest.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase().into()
+    } else if first.is_lowercase() && rest.iter().all(|c| c.is_lowercase()) {
+        replacement.to_lowercase().into()
+    } else if first.is_uppercase() {
+        capitalize(replacement)
+    } else {
+        replacement
+    }
+}
+
+fn capitalize(text: Cow<'_, str>) -> Cow<'_, str> {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) if first.is_lowercase() => {
+            first.to_uppercase().chain(chars).collect::<String>().into()
+        }
+        _ => text,
+    }
+}
+
 fn deserialize_path_matches(glob_set: &str) -> anyhow::Result<Vec<PathMatcher>> {
     glob_set
         .split(',')
@@ -438,6 +509,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn regex_replacement_with_capture_groups() {
+        let query = SearchQuery::regex(
+            r"fn (\w+)\((?P<args>.*)\)",
+            false,
+            true,
+            false,
+            Vec::new(),
+            Vec::new(),
+        )
+        .unwrap()
+        .with_replacement("fn ${1}_new(${args})".to_string());
+        assert_eq!(
+            query.replacement_for("fn foo(a: u32)").as_deref(),
+            Some("fn foo_new(a: u32)")
+        );
+
+        let query = SearchQuery::text("foo", false, true, false, Vec::new(), Vec::new())
+            .unwrap()
+            .with_replacement("$1".to_string());
+        assert_eq!(query.replacement_for("foo").as_deref(), Some("$1"));
+    }
+
+    #[test]
+    fn case_preserving_replacement() {
+        let query = SearchQuery::text("foo", false, false, false, Vec::new(), Vec::new())
+            .unwrap()
+            .with_replacement("barBaz".to_string())
+            .with_preserve_case(true);
+        for (matched, expected) in [
+            ("foo", "barbaz"),
+            ("FOO", "BARBAZ"),
+            ("Foo", "BarBaz"),
+            ("fOo", "barBaz"),
+        ] {
+            assert_eq!(
+                query.replacement_for(matched).as_deref(),
+                Some(expected),
+                "unexpected replacement for {matched:?}"
+            );
+        }
+
+        let query = query.with_preserve_case(false);
+        assert_eq!(query.replacement_for("FOO").as_deref(), Some("barBaz"));
+    }
+
     #[test]
     fn path_matcher_creation_for_globs() {
         for invalid_glob in ["dir/[].txt", "dir/[a-z.txt", "dir/{file"] {
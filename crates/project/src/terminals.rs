@@ -1,24 +1,68 @@
-use crate::Project;
-use gpui::{AnyWindowHandle, Context, Entity, Model, ModelContext, WeakModel};
+use crate::{Project, WorktreeId};
+use anyhow::{anyhow, Result};
+use client::{proto, Client, TypedEnvelope};
+use collections::HashMap;
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
+use gpui::{
+    AnyWindowHandle, AppContext, AsyncAppContext, Context, Entity, Model, ModelContext,
+    Subscription, Task, WeakModel,
+};
 use settings::Settings;
-use std::path::{Path, PathBuf};
+use std::{
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use terminal::{
+    alacritty_terminal::index::{Column, Line, Point},
     terminal_settings::{self, TerminalSettings, VenvSettingsContent},
-    Terminal, TerminalBuilder,
+    GridSize, ReplicaRequest, ScreenSnapshot, ScreenUpdate, Terminal, TerminalBuilder,
 };
+use util::{post_inc, ResultExt};
+
+/// How often the screen of a shared terminal is sent to guests, at most.
+const TERMINAL_UPDATE_INTERVAL: Duration = Duration::from_millis(16);
 
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
 
 pub struct Terminals {
     pub(crate) local_handles: Vec<WeakModel<terminal::Terminal>>,
+    /// Local terminals that the host shared with guests, keyed by the id
+    /// guests open them by.
+    pub(crate) shared: HashMap<u64, SharedTerminal>,
+    pub(crate) next_shared_id: u64,
+    /// Replicas of the host's terminals that this guest has opened.
+    pub(crate) replicas: HashMap<u64, ReplicaTerminal>,
+}
+
+pub(crate) struct SharedTerminal {
+    terminal: WeakModel<Terminal>,
+    /// The guests viewing the terminal, and the size of their viewports.
+    viewers: HashMap<proto::PeerId, Option<GridSize>>,
+    last_snapshot: ScreenSnapshot,
+    version: u64,
+    pending_update: Option<Task<()>>,
+    _subscription: Subscription,
+}
+
+pub(crate) struct ReplicaTerminal {
+    terminal: WeakModel<Terminal>,
+    /// The version of the host's screen last applied to the replica, or
+    /// `None` while waiting for the host to send its full screen.
+    version: Option<u64>,
+    pending_updates: Vec<proto::UpdateTerminal>,
 }
 
 impl Project {
     pub fn create_terminal(
         &mut self,
         working_directory: Option<PathBuf>,
-        window: AnyWindowHandle,
+        window: Option<AnyWindowHandle>,
         cx: &mut ModelContext<Self>,
     ) -> anyhow::Result<Model<Terminal>> {
         if self.is_remote() {
@@ -47,6 +91,25 @@ impl Project {
 
                 let id = terminal_handle.entity_id();
                 cx.observe_release(&terminal_handle, move |project, _terminal, cx| {
+                    let shared_id = project
+                        .terminals
+                        .shared
+                        .iter()
+                        .find(|(_, shared)| shared.terminal.entity_id() == id)
+                        .map(|(terminal_id, _)| *terminal_id);
+                    if let Some(terminal_id) = shared_id {
+                        project.terminals.shared.remove(&terminal_id);
+                        if let Some(project_id) = project.remote_id() {
+                            project
+                                .client
+                                .send(proto::RemoveTerminal {
+                                    project_id,
+                                    terminal_id,
+                                })
+                                .log_err();
+                        }
+                    }
+
                     let handles = &mut project.terminals.local_handles;
 
                     if let Some(index) = handles
@@ -77,6 +140,32 @@ impl Project {
         }
     }
 
+    /// Asks the host to start a terminal, in the root of the given worktree
+    /// if there is one, and opens it.
+    ///
+    /// Only remote servers accept this: hosts sharing through collab decide
+    /// for themselves which terminals to share.
+    pub fn create_host_terminal(
+        &mut self,
+        worktree_id: Option<WorktreeId>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        let Some(project_id) = self.remote_id().filter(|_| self.is_remote()) else {
+            return Task::ready(Err(anyhow!("only guests can create terminals on the host")));
+        };
+        let response = self.client.request(proto::CreateTerminal {
+            project_id,
+            worktree_id: worktree_id.map(|worktree_id| worktree_id.to_proto()),
+        });
+        cx.spawn(|this, mut cx| async move {
+            let terminal_id = response.await?.terminal_id;
+            this.update(&mut cx, |this, cx| {
+                this.open_shared_terminal(terminal_id, cx)
+            })?
+            .await
+        })
+    }
+
     pub fn find_activate_script_path(
         &mut self,
         settings: &VenvSettingsContent,
@@ -134,6 +223,558 @@ impl Project {
     pub fn local_terminal_handles(&self) -> &Vec<WeakModel<terminal::Terminal>> {
         &self.terminals.local_handles
     }
+
+    /// The id under which a terminal is shared with collaborators, if it is.
+    ///
+    /// Local terminals are only shared once the host calls
+    /// [`Project::share_terminal`], and get an id of their own that guests
+    /// also use for their replicas.
+    pub fn shared_terminal_id(&self, terminal: &Model<Terminal>) -> Option<u64> {
+        if self.is_local() {
+            self.terminals
+                .shared
+                .iter()
+                .find(|(_, shared)| shared.terminal.entity_id() == terminal.entity_id())
+                .map(|(terminal_id, _)| *terminal_id)
+        } else {
+            self.terminals
+                .replicas
+                .iter()
+                .find(|(_, replica)| replica.terminal.entity_id() == terminal.entity_id())
+                .map(|(terminal_id, _)| *terminal_id)
+        }
+    }
+
+    /// Lets guests open, and type into, a local terminal.
+    pub fn share_terminal(
+        &mut self,
+        terminal: &Model<Terminal>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<u64> {
+        if !self.is_local() {
+            return Err(anyhow!("only the host can share its terminals"));
+        } else if self.remote_id().is_none() {
+            return Err(anyhow!("project is not shared"));
+        } else if !self
+            .terminals
+            .local_handles
+            .iter()
+            .any(|handle| handle.entity_id() == terminal.entity_id())
+        {
+            return Err(anyhow!("terminal does not belong to this project"));
+        }
+        if let Some(terminal_id) = self.shared_terminal_id(terminal) {
+            return Ok(terminal_id);
+        }
+
+        let terminal_id = post_inc(&mut self.terminals.next_shared_id);
+        let subscription = cx.subscribe(terminal, move |this, _, event, cx| match event {
+            terminal::Event::Wakeup
+            | terminal::Event::TitleChanged
+            | terminal::Event::BreadcrumbsChanged => this.schedule_terminal_update(terminal_id, cx),
+            _ => {}
+        });
+        self.terminals.shared.insert(
+            terminal_id,
+            SharedTerminal {
+                terminal: terminal.downgrade(),
+                viewers: HashMap::default(),
+                last_snapshot: ScreenSnapshot::default(),
+                version: 0,
+                pending_update: None,
+                _subscription: subscription,
+            },
+        );
+        cx.notify();
+        Ok(terminal_id)
+    }
+
+    /// Closes a local terminal's replicas on guests, and stops accepting
+    /// their input.
+    pub fn unshare_terminal(&mut self, terminal: &Model<Terminal>, cx: &mut ModelContext<Self>) {
+        let Some(terminal_id) = self.shared_terminal_id(terminal) else {
+            return;
+        };
+        self.terminals.shared.remove(&terminal_id);
+        terminal.update(cx, |terminal, cx| {
+            terminal.set_size_limit(None);
+            cx.notify();
+        });
+        if let Some(project_id) = self.remote_id() {
+            self.client
+                .send(proto::RemoveTerminal {
+                    project_id,
+                    terminal_id,
+                })
+                .log_err();
+        }
+        cx.notify();
+    }
+
+    /// Returns the terminal with the given shared id: a local terminal on the
+    /// host, or a replica of the host's terminal on guests.
+    pub fn terminal_for_id(
+        &mut self,
+        terminal_id: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        if self.is_local() {
+            Task::ready(
+                self.terminals
+                    .shared
+                    .get(&terminal_id)
+                    .and_then(|shared| shared.terminal.upgrade())
+                    .ok_or_else(|| anyhow!("no such terminal {terminal_id}")),
+            )
+        } else {
+            self.open_shared_terminal(terminal_id, cx)
+        }
+    }
+
+    /// Returns a shared terminal that the given guest has opened.
+    fn terminal_opened_by(
+        &mut self,
+        terminal_id: u64,
+        peer_id: proto::PeerId,
+    ) -> Result<&mut SharedTerminal> {
+        let shared = self
+            .terminals
+            .shared
+            .get_mut(&terminal_id)
+            .ok_or_else(|| anyhow!("terminal {terminal_id} is not shared"))?;
+        if !shared.viewers.contains_key(&peer_id) {
+            return Err(anyhow!(
+                "terminal {terminal_id} was not opened by {peer_id:?}"
+            ));
+        }
+        Ok(shared)
+    }
+
+    fn open_shared_terminal(
+        &mut self,
+        terminal_id: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        let Some(project_id) = self.remote_id() else {
+            return Task::ready(Err(anyhow!("project is not shared")));
+        };
+        if let Some(terminal) = self
+            .terminals
+            .replicas
+            .get(&terminal_id)
+            .and_then(|replica| replica.terminal.upgrade())
+        {
+            return Task::ready(Ok(terminal));
+        }
+
+        let (requests_tx, mut requests_rx) = mpsc::unbounded();
+        let (opened_tx, opened_rx) = oneshot::channel();
+        let read_only = self.is_read_only();
+        let terminal = cx.new_model(|_| Terminal::replica(requests_tx, read_only));
+        self.terminals.replicas.insert(
+            terminal_id,
+            ReplicaTerminal {
+                terminal: terminal.downgrade(),
+                version: None,
+                pending_updates: Vec::new(),
+            },
+        );
+
+        let client = self.client.clone();
+        cx.spawn(|this, mut cx| async move {
+            // The host only accepts requests from guests that opened the
+            // terminal, so hold them until it has.
+            let opened = opened_rx.await.is_ok();
+            while let Some(request) = requests_rx.next().await.filter(|_| opened) {
+                let response = match request {
+                    ReplicaRequest::Input(mut input) => {
+                        // Coalesce keystrokes typed while waiting for the host.
+                        while let Ok(Some(ReplicaRequest::Input(more))) = requests_rx.try_next() {
+                            input.extend(more);
+                        }
+                        client
+                            .request(proto::TerminalInput {
+                                project_id,
+                                terminal_id,
+                                input,
+                            })
+                            .await
+                    }
+                    ReplicaRequest::Resize(size) => {
+                        client
+                            .request(proto::ResizeTerminal {
+                                project_id,
+                                terminal_id,
+                                rows: size.rows as u32,
+                                columns: size.columns as u32,
+                            })
+                            .await
+                    }
+                };
+                response.log_err();
+            }
+
+            // The replica was dropped.
+            this.update(&mut cx, |this, _| {
+                if let Some(replica) = this.terminals.replicas.get(&terminal_id) {
+                    if replica.terminal.upgrade().is_none() {
+                        this.terminals.replicas.remove(&terminal_id);
+                    }
+                }
+            })
+            .ok();
+            if opened {
+                client
+                    .request(proto::CloseTerminal {
+                        project_id,
+                        terminal_id,
+                    })
+                    .await
+                    .log_err();
+            }
+        })
+        .detach();
+
+        let response = self.client.request(proto::OpenTerminal {
+            project_id,
+            terminal_id,
+        });
+        cx.spawn(|this, mut cx| async move {
+            let response = response.await?;
+            let screen = response
+                .screen
+                .ok_or_else(|| anyhow!("missing terminal screen"))?;
+            this.update(&mut cx, |this, cx| {
+                terminal.update(cx, |terminal, cx| {
+                    terminal.apply_screen_update(screen_from_proto(screen), cx)
+                })?;
+
+                // Apply any updates the host sent after the screen it responded with.
+                let pending_updates = match this.terminals.replicas.get_mut(&terminal_id) {
+                    Some(replica) => {
+                        replica.version = Some(response.version);
+                        mem::take(&mut replica.pending_updates)
+                    }
+                    None => Vec::new(),
+                };
+                for update in pending_updates {
+                    this.apply_terminal_update(update, cx)?;
+                }
+                opened_tx.send(()).ok();
+                Ok(terminal)
+            })?
+        })
+    }
+
+    fn apply_terminal_update(
+        &mut self,
+        update: proto::UpdateTerminal,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let Some(replica) = self.terminals.replicas.get_mut(&update.terminal_id) else {
+            return Ok(());
+        };
+        let Some(version) = replica.version else {
+            replica.pending_updates.push(update);
+            return Ok(());
+        };
+        let Some(terminal) = replica.terminal.upgrade() else {
+            return Ok(());
+        };
+        if update.version <= version {
+            return Ok(());
+        }
+
+        replica.version = Some(update.version);
+        let screen = update
+            .screen
+            .ok_or_else(|| anyhow!("missing terminal screen"))?;
+        terminal.update(cx, |terminal, cx| {
+            terminal.apply_screen_update(screen_from_proto(screen), cx)
+        })
+    }
+
+    pub(crate) fn set_replica_terminals_read_only(&mut self, read_only: bool, cx: &mut AppContext) {
+        for replica in self.terminals.replicas.values() {
+            if let Some(terminal) = replica.terminal.upgrade() {
+                terminal.update(cx, |terminal, cx| terminal.set_read_only(read_only, cx));
+            }
+        }
+    }
+
+    /// Stops replicating local terminals, and lets them take any size again.
+    pub(crate) fn unshare_terminals(&mut self, cx: &mut AppContext) {
+        for (_, shared) in self.terminals.shared.drain() {
+            if let Some(terminal) = shared.terminal.upgrade() {
+                terminal.update(cx, |terminal, cx| {
+                    terminal.set_size_limit(None);
+                    cx.notify();
+                });
+            }
+        }
+    }
+
+    fn schedule_terminal_update(&mut self, terminal_id: u64, cx: &mut ModelContext<Self>) {
+        let Some(shared) = self.terminals.shared.get_mut(&terminal_id) else {
+            return;
+        };
+        if shared.pending_update.is_none() {
+            shared.pending_update = Some(cx.spawn(|this, mut cx| async move {
+                cx.background_executor()
+                    .timer(TERMINAL_UPDATE_INTERVAL)
+                    .await;
+                this.update(&mut cx, |this, cx| {
+                    this.send_terminal_update(terminal_id, cx)
+                })
+                .ok();
+            }));
+        }
+    }
+
+    /// Sends guests the lines of a shared terminal that changed since they
+    /// were last sent.
+    fn send_terminal_update(&mut self, terminal_id: u64, cx: &mut ModelContext<Self>) {
+        let Some(project_id) = self.remote_id() else {
+            return;
+        };
+        let Some(shared) = self.terminals.shared.get_mut(&terminal_id) else {
+            return;
+        };
+        shared.pending_update.take();
+        let Some(terminal) = shared.terminal.upgrade() else {
+            return;
+        };
+
+        let snapshot = terminal.read(cx).screen_snapshot();
+        if snapshot == shared.last_snapshot {
+            return;
+        }
+        let screen = screen_to_proto(&snapshot, Some(&shared.last_snapshot));
+        shared.last_snapshot = snapshot;
+        shared.version += 1;
+        self.client
+            .send(proto::UpdateTerminal {
+                project_id,
+                terminal_id,
+                version: shared.version,
+                screen: Some(screen),
+            })
+            .log_err();
+    }
+
+    /// Limits a shared terminal to the smallest viewport among its viewers.
+    fn update_terminal_size_limit(&mut self, terminal_id: u64, cx: &mut ModelContext<Self>) {
+        let Some(shared) = self.terminals.shared.get(&terminal_id) else {
+            return;
+        };
+        let Some(terminal) = shared.terminal.upgrade() else {
+            return;
+        };
+        let limit = shared
+            .viewers
+            .values()
+            .flatten()
+            .copied()
+            .reduce(GridSize::min);
+        terminal.update(cx, |terminal, cx| {
+            terminal.set_size_limit(limit);
+            cx.notify();
+        });
+    }
+
+    pub(crate) fn remove_terminal_viewer(
+        &mut self,
+        terminal_id: u64,
+        peer_id: proto::PeerId,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(shared) = self.terminals.shared.get_mut(&terminal_id) else {
+            return;
+        };
+        if shared.viewers.remove(&peer_id).is_some() {
+            self.update_terminal_size_limit(terminal_id, cx);
+        }
+    }
+
+    pub(crate) fn remove_terminal_viewers(
+        &mut self,
+        peer_id: proto::PeerId,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let terminal_ids = self.terminals.shared.keys().copied().collect::<Vec<_>>();
+        for terminal_id in terminal_ids {
+            self.remove_terminal_viewer(terminal_id, peer_id, cx);
+        }
+    }
+
+    pub(crate) async fn handle_open_terminal(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::OpenTerminal>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::OpenTerminalResponse> {
+        let peer_id = envelope.original_sender_id()?;
+        let terminal_id = envelope.payload.terminal_id;
+        this.update(&mut cx, |this, cx| {
+            this.terminals
+                .shared
+                .get_mut(&terminal_id)
+                .ok_or_else(|| anyhow!("terminal {terminal_id} is not shared"))?
+                .viewers
+                .entry(peer_id)
+                .or_default();
+
+            // Bring the other viewers up to date first, so that the updates
+            // sent from now on apply on top of the screen we respond with.
+            this.send_terminal_update(terminal_id, cx);
+            let shared = &this.terminals.shared[&terminal_id];
+            Ok(proto::OpenTerminalResponse {
+                version: shared.version,
+                screen: Some(screen_to_proto(&shared.last_snapshot, None)),
+            })
+        })?
+    }
+
+    pub(crate) async fn handle_terminal_input(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::TerminalInput>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let peer_id = envelope.original_sender_id()?;
+        this.update(&mut cx, |this, cx| {
+            let terminal_id = envelope.payload.terminal_id;
+            let terminal = this
+                .terminal_opened_by(terminal_id, peer_id)?
+                .terminal
+                .upgrade()
+                .ok_or_else(|| anyhow!("no such terminal {terminal_id}"))?;
+            terminal.update(cx, |terminal, _| {
+                terminal.input_bytes(envelope.payload.input)
+            });
+            Ok(proto::Ack {})
+        })?
+    }
+
+    pub(crate) async fn handle_resize_terminal(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResizeTerminal>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let peer_id = envelope.original_sender_id()?;
+        let terminal_id = envelope.payload.terminal_id;
+        let size = GridSize {
+            rows: envelope.payload.rows as usize,
+            columns: envelope.payload.columns as usize,
+        };
+        if size.rows == 0 || size.columns == 0 {
+            return Err(anyhow!("invalid terminal size"));
+        }
+
+        this.update(&mut cx, |this, cx| {
+            this.terminal_opened_by(terminal_id, peer_id)?
+                .viewers
+                .insert(peer_id, Some(size));
+            this.update_terminal_size_limit(terminal_id, cx);
+            Ok(proto::Ack {})
+        })?
+    }
+
+    pub(crate) async fn handle_close_terminal(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::CloseTerminal>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let peer_id = envelope.original_sender_id()?;
+        this.update(&mut cx, |this, cx| {
+            this.remove_terminal_viewer(envelope.payload.terminal_id, peer_id, cx);
+            Ok(proto::Ack {})
+        })?
+    }
+
+    pub(crate) async fn handle_update_terminal(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::UpdateTerminal>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            this.apply_terminal_update(envelope.payload, cx)
+        })?
+    }
+
+    pub(crate) async fn handle_remove_terminal(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::RemoveTerminal>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            if let Some(replica) = this
+                .terminals
+                .replicas
+                .remove(&envelope.payload.terminal_id)
+            {
+                if let Some(terminal) = replica.terminal.upgrade() {
+                    terminal.update(cx, |_, cx| cx.emit(terminal::Event::CloseTerminal));
+                }
+            }
+        })
+    }
+}
+
+/// Encodes a terminal's screen, including only the lines that differ from
+/// `previous` when it's given.
+fn screen_to_proto(
+    snapshot: &ScreenSnapshot,
+    previous: Option<&ScreenSnapshot>,
+) -> proto::TerminalScreen {
+    let previous_lines = previous
+        .filter(|previous| previous.size == snapshot.size)
+        .map(|previous| previous.lines.as_slice())
+        .unwrap_or_default();
+    proto::TerminalScreen {
+        rows: snapshot.size.rows as u32,
+        columns: snapshot.size.columns as u32,
+        lines: snapshot
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(row, line)| previous_lines.get(*row) != Some(*line))
+            .map(|(row, line)| proto::TerminalLine {
+                row: row as u32,
+                cells: line.clone(),
+            })
+            .collect(),
+        cursor_row: snapshot.cursor.line.0.max(0) as u32,
+        cursor_column: snapshot.cursor.column.0 as u32,
+        mode: snapshot.mode,
+        title: snapshot.title.clone(),
+        breadcrumb_text: snapshot.breadcrumb_text.clone(),
+    }
+}
+
+fn screen_from_proto(screen: proto::TerminalScreen) -> ScreenUpdate {
+    ScreenUpdate {
+        size: GridSize {
+            rows: screen.rows as usize,
+            columns: screen.columns as usize,
+        },
+        lines: screen
+            .lines
+            .into_iter()
+            .map(|line| (line.row as usize, line.cells))
+            .collect(),
+        cursor: Point::new(
+            Line(screen.cursor_row as i32),
+            Column(screen.cursor_column as usize),
+        ),
+        mode: screen.mode,
+        title: screen.title,
+        breadcrumb_text: screen.breadcrumb_text,
+    }
 }
 
 // TODO: Add a few tests for adding and removing terminal tabs
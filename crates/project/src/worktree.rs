@@ -8,6 +8,7 @@ use client::{proto, Client};
 use clock::ReplicaId;
 use collections::{HashMap, HashSet, VecDeque};
 use fs::{
+    is_cross_device_error,
     repository::{GitFileStatus, GitRepository, RepoPath},
     Fs,
 };
@@ -79,7 +80,7 @@ pub enum Worktree {
 pub struct LocalWorktree {
     snapshot: LocalSnapshot,
     scan_requests_tx: channel::Sender<ScanRequest>,
-    path_prefixes_to_scan_tx: channel::Sender<Arc<Path>>,
+    path_prefixes_to_scan_tx: channel::Sender<PathPrefixScanRequest>,
     is_scanning: (watch::Sender<bool>, watch::Receiver<bool>),
     _background_scanner_tasks: Vec<Task<()>>,
     share: Option<ShareState>,
@@ -101,6 +102,11 @@ struct ScanRequest {
     done: barrier::Sender,
 }
 
+struct PathPrefixScanRequest {
+    path: Arc<Path>,
+    done: barrier::Sender,
+}
+
 pub struct RemoteWorktree {
     snapshot: Snapshot,
     background_snapshot: Arc<Mutex<Snapshot>>,
@@ -627,7 +633,7 @@ fn start_background_scan_tasks(
     abs_path: &Path,
     snapshot: LocalSnapshot,
     scan_requests_rx: channel::Receiver<ScanRequest>,
-    path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+    path_prefixes_to_scan_rx: channel::Receiver<PathPrefixScanRequest>,
     next_entry_id: Arc<AtomicUsize>,
     fs: Arc<dyn Fs>,
     cx: &mut ModelContext<'_, Worktree>,
@@ -1344,6 +1350,97 @@ impl LocalWorktree {
         })
     }
 
+    /// Copies a file or a directory from outside of this worktree to `new_path` inside of it.
+    pub fn copy_external_entry(
+        &self,
+        abs_source_path: PathBuf,
+        new_path: impl Into<Arc<Path>>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Option<Entry>>> {
+        let new_path = new_path.into();
+        let abs_new_path = self.absolutize(&new_path);
+        let fs = self.fs.clone();
+        let copy = cx.background_executor().spawn(async move {
+            copy_recursive(
+                fs.as_ref(),
+                &abs_source_path,
+                &abs_new_path?,
+                Default::default(),
+            )
+            .await
+        });
+
+        cx.spawn(|this, mut cx| async move {
+            copy.await?;
+            this.update(&mut cx, |this, cx| {
+                this.as_local_mut()
+                    .unwrap()
+                    .refresh_entry(new_path.clone(), None, cx)
+            })?
+            .await
+        })
+    }
+
+    /// Moves a file or a directory from outside of this worktree to `new_path` inside of it.
+    pub fn move_external_entry(
+        &self,
+        abs_source_path: PathBuf,
+        new_path: impl Into<Arc<Path>>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Option<Entry>>> {
+        let new_path = new_path.into();
+        let abs_new_path = self.absolutize(&new_path);
+        let fs = self.fs.clone();
+        let move_entry = cx.background_executor().spawn(async move {
+            move_recursive(fs.as_ref(), &abs_source_path, &abs_new_path?).await
+        });
+
+        cx.spawn(|this, mut cx| async move {
+            move_entry.await?;
+            this.update(&mut cx, |this, cx| {
+                this.as_local_mut()
+                    .unwrap()
+                    .refresh_entry(new_path.clone(), None, cx)
+            })?
+            .await
+        })
+    }
+
+    /// Moves an entry out of this worktree to `abs_target_path`, returning the entry's former path.
+    ///
+    /// Fails rather than copying the entry when the target is on another filesystem.
+    pub fn move_entry_out(
+        &self,
+        entry_id: ProjectEntryId,
+        abs_target_path: PathBuf,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Option<Task<Result<Arc<Path>>>> {
+        let entry = self.entry_for_id(entry_id)?.clone();
+        let abs_path = self.absolutize(&entry.path);
+        let fs = self.fs.clone();
+
+        let move_entry = cx.background_executor().spawn(async move {
+            if let Some(parent) = abs_target_path.parent() {
+                fs.create_dir(parent).await?;
+            }
+            fs.rename(&abs_path?, &abs_target_path, Default::default())
+                .await?;
+            anyhow::Ok(entry.path)
+        });
+
+        Some(cx.spawn(|this, mut cx| async move {
+            let path = move_entry.await?;
+            this.update(&mut cx, |this, _| {
+                this.as_local_mut()
+                    .unwrap()
+                    .refresh_entries_for_paths(vec![path.clone()])
+            })?
+            .recv()
+            .await;
+            Ok(path)
+        }))
+    }
+
     pub fn expand_entry(
         &mut self,
         entry_id: ProjectEntryId,
@@ -1368,8 +1465,17 @@ impl LocalWorktree {
         rx
     }
 
-    pub fn add_path_prefix_to_scan(&self, path_prefix: Arc<Path>) {
-        self.path_prefixes_to_scan_tx.try_send(path_prefix).ok();
+    /// Loads the entries under the given path, even if it's ignored, and keeps
+    /// them loaded. The returned barrier is released once they are.
+    pub fn add_path_prefix_to_scan(&self, path_prefix: Arc<Path>) -> barrier::Receiver {
+        let (tx, rx) = barrier::channel();
+        self.path_prefixes_to_scan_tx
+            .try_send(PathPrefixScanRequest {
+                path: path_prefix,
+                done: tx,
+            })
+            .ok();
+        rx
     }
 
     fn refresh_entry(
@@ -1642,6 +1748,33 @@ impl RemoteWorktree {
     }
 }
 
+/// Renames `source` to `target`, falling back to copying and removing the source
+/// when they are on different file systems.
+async fn move_recursive(fs: &dyn Fs, source: &Path, target: &Path) -> Result<()> {
+    match fs.rename(source, target, Default::default()).await {
+        Err(error) if is_cross_device_error(&error) => {}
+        result => return result,
+    }
+
+    copy_recursive(fs, source, target, Default::default()).await?;
+    let is_dir = fs
+        .metadata(source)
+        .await?
+        .map_or(false, |metadata| metadata.is_dir);
+    if is_dir {
+        fs.remove_dir(
+            source,
+            RemoveOptions {
+                recursive: true,
+                ignore_if_not_exists: false,
+            },
+        )
+        .await
+    } else {
+        fs.remove_file(source, Default::default()).await
+    }
+}
+
 impl Snapshot {
     pub fn id(&self) -> WorktreeId {
         self.id
@@ -3232,7 +3365,7 @@ struct BackgroundScanner {
     status_updates_tx: UnboundedSender<ScanState>,
     executor: BackgroundExecutor,
     scan_requests_rx: channel::Receiver<ScanRequest>,
-    path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+    path_prefixes_to_scan_rx: channel::Receiver<PathPrefixScanRequest>,
     next_entry_id: Arc<AtomicUsize>,
     phase: BackgroundScannerPhase,
 }
@@ -3252,7 +3385,7 @@ impl BackgroundScanner {
         status_updates_tx: UnboundedSender<ScanState>,
         executor: BackgroundExecutor,
         scan_requests_rx: channel::Receiver<ScanRequest>,
-        path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+        path_prefixes_to_scan_rx: channel::Receiver<PathPrefixScanRequest>,
     ) -> Self {
         Self {
             fs,
@@ -3342,8 +3475,10 @@ impl BackgroundScanner {
                     }
                 }
 
-                path_prefix = self.path_prefixes_to_scan_rx.recv().fuse() => {
-                    let Ok(path_prefix) = path_prefix else { break };
+                request = self.path_prefixes_to_scan_rx.recv().fuse() => {
+                    let Ok(PathPrefixScanRequest { path: path_prefix, done }) = request else {
+                        break;
+                    };
                     log::trace!("adding path prefix {:?}", path_prefix);
 
                     let did_scan = self.forcibly_load_paths(&[path_prefix.clone()]).await;
@@ -3358,6 +3493,9 @@ impl BackgroundScanner {
                         if let Some(abs_path) = self.fs.canonicalize(&abs_path).await.log_err() {
                             self.process_events(vec![abs_path]).await;
                         }
+                        if !self.send_status_update(false, Some(done)) {
+                            return;
+                        }
                     }
                 }
 
@@ -1,5 +1,6 @@
 pub mod file_associations;
 mod project_panel_settings;
+use call::ActiveCall;
 use client::{ErrorCode, ErrorExt};
 use settings::Settings;
 
@@ -8,24 +9,31 @@ use editor::{actions::Cancel, scroll::Autoscroll, Editor};
 use file_associations::FileAssociations;
 
 use anyhow::{anyhow, Result};
-use collections::{hash_map, HashMap};
+use collections::{hash_map, HashMap, HashSet};
 use gpui::{
     actions, div, overlay, px, uniform_list, Action, AppContext, AssetSource, AsyncWindowContext,
-    ClipboardItem, DismissEvent, Div, EventEmitter, FocusHandle, FocusableView, InteractiveElement,
-    KeyContext, Model, MouseButton, MouseDownEvent, ParentElement, Pixels, Point, PromptLevel,
-    Render, Stateful, Styled, Subscription, Task, UniformListScrollHandle, View, ViewContext,
-    VisualContext as _, WeakView, WindowContext,
+    ClipboardItem, DismissEvent, Div, EventEmitter, ExternalPaths, FocusHandle, FocusableView,
+    InteractiveElement, KeyContext, Model, MouseButton, MouseDownEvent, ParentElement, Pixels,
+    Point, PromptLevel, Render, Stateful, Styled, Subscription, Task, UniformListScrollHandle,
+    View, ViewContext, VisualContext as _, WeakView, WindowContext,
 };
 use menu::{Confirm, SelectNext, SelectPrev};
 use project::{
-    repository::GitFileStatus, Entry, EntryKind, Fs, Project, ProjectEntryId, ProjectPath,
-    Worktree, WorktreeId,
+    repository::GitFileStatus, Entry, EntryKind, FileOperation, Fs, Project, ProjectEntryId,
+    ProjectPath, Worktree, WorktreeId,
 };
 use project_panel_settings::{ProjectPanelDockPosition, ProjectPanelSettings};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, ffi::OsStr, ops::Range, path::Path, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    ffi::OsStr,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use theme::ThemeSettings;
-use ui::{prelude::*, v_flex, ContextMenu, Icon, KeyBinding, Label, ListItem};
+use ui::{prelude::*, v_flex, Avatar, ContextMenu, Icon, KeyBinding, Label, ListItem};
 use unicase::UniCase;
 use util::{maybe, ResultExt, TryFutureExt};
 use workspace::{
@@ -46,6 +54,8 @@ pub struct ProjectPanel {
     last_worktree_root_id: Option<ProjectEntryId>,
     expanded_dir_ids: HashMap<WorktreeId, Vec<ProjectEntryId>>,
     selection: Option<Selection>,
+    /// Entries marked with ctrl- or shift-clicks, acted upon together with the selection.
+    marked_entries: BTreeSet<Selection>,
     context_menu: Option<(View<ContextMenu>, Point<Pixels>, Subscription)>,
     edit_state: Option<EditState>,
     filename_editor: View<Editor>,
@@ -56,7 +66,7 @@ pub struct ProjectPanel {
     pending_serialization: Task<Option<()>>,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Selection {
     worktree_id: WorktreeId,
     entry_id: ProjectEntryId,
@@ -71,20 +81,32 @@ struct EditState {
     processing_filename: Option<String>,
 }
 
-#[derive(Copy, Clone)]
-pub enum ClipboardEntry {
-    Copied {
-        worktree_id: WorktreeId,
-        entry_id: ProjectEntryId,
-    },
-    Cut {
-        worktree_id: WorktreeId,
-        entry_id: ProjectEntryId,
-    },
+#[derive(Clone)]
+enum ClipboardEntry {
+    Copied(BTreeSet<Selection>),
+    Cut(BTreeSet<Selection>),
+}
+
+/// The payload of a drag started in the project panel.
+#[derive(Clone)]
+struct DraggedSelection {
+    active_selection: Selection,
+    marked_selections: Arc<BTreeSet<Selection>>,
+}
+
+impl DraggedSelection {
+    fn items(&self) -> Box<dyn Iterator<Item = &Selection> + '_> {
+        if self.marked_selections.contains(&self.active_selection) {
+            Box::new(self.marked_selections.iter())
+        } else {
+            Box::new(std::iter::once(&self.active_selection))
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct EntryDetails {
+    worktree_id: WorktreeId,
     filename: String,
     icon: Option<Arc<str>>,
     path: Arc<Path>,
@@ -93,6 +115,7 @@ pub struct EntryDetails {
     is_ignored: bool,
     is_expanded: bool,
     is_selected: bool,
+    is_marked: bool,
     is_editing: bool,
     is_processing: bool,
     is_cut: bool,
@@ -116,6 +139,8 @@ actions!(
         Cut,
         Paste,
         Delete,
+        Undo,
+        Redo,
         Rename,
         Open,
         ToggleFocus,
@@ -160,6 +185,7 @@ struct DraggedProjectEntryView {
     entry_id: ProjectEntryId,
     details: EntryDetails,
     width: Pixels,
+    selection_count: usize,
 }
 
 impl ProjectPanel {
@@ -223,6 +249,10 @@ impl ProjectPanel {
             })
             .detach();
 
+            if let Some(active_call) = ActiveCall::try_global(cx) {
+                cx.observe(&active_call, |_, _, cx| cx.notify()).detach();
+            }
+
             let mut this = Self {
                 project: project.clone(),
                 fs: workspace.app_state().fs.clone(),
@@ -232,6 +262,7 @@ impl ProjectPanel {
                 last_worktree_root_id: Default::default(),
                 expanded_dir_ids: Default::default(),
                 selection: None,
+                marked_entries: BTreeSet::new(),
                 edit_state: None,
                 context_menu: None,
                 filename_editor,
@@ -427,10 +458,8 @@ impl ProjectPanel {
                         .separator()
                         .action("Cut", Box::new(Cut))
                         .action("Copy", Box::new(Copy))
-                        .when_some(self.clipboard_entry, |menu, entry| {
-                            menu.when(entry.worktree_id() == worktree_id, |menu| {
-                                menu.action("Paste", Box::new(Paste))
-                            })
+                        .when(self.clipboard_entry.is_some(), |menu| {
+                            menu.action("Paste", Box::new(Paste))
                         })
                         .separator()
                         .action("Copy Path", Box::new(CopyPath))
@@ -644,6 +673,24 @@ impl ProjectPanel {
 
             if let Some(new_entry) = new_entry? {
                 this.update(&mut cx, |this, cx| {
+                    let new_path = ProjectPath {
+                        worktree_id,
+                        path: new_entry.path.clone(),
+                    };
+                    let operation = if is_new_entry {
+                        FileOperation::Created { path: new_path }
+                    } else {
+                        FileOperation::Moved {
+                            old_path: ProjectPath {
+                                worktree_id,
+                                path: entry.path.clone(),
+                            },
+                            new_path,
+                        }
+                    };
+                    this.project.update(cx, |project, _| {
+                        project.push_file_operations(vec![operation])
+                    });
                     if let Some(selection) = &mut this.selection {
                         if selection.entry_id == edited_entry_id {
                             selection.worktree_id = worktree_id;
@@ -790,33 +837,67 @@ impl ProjectPanel {
 
     fn delete(&mut self, _: &Delete, cx: &mut ViewContext<Self>) {
         maybe!({
-            let Selection { entry_id, .. } = self.selection?;
-            let path = self.project.read(cx).path_for_entry(entry_id, cx)?.path;
-            let file_name = path.file_name()?;
+            let entries = self.disjoint_paths(&self.selections_to_act_on(), cx);
+            let prompt = match entries.as_slice() {
+                [] => return None,
+                [(_, path)] => format!("Move {:?} to trash?", path.path.file_name()?),
+                _ => format!("Move {} items to trash?", entries.len()),
+            };
 
             let answer = cx.prompt(
                 PromptLevel::Info,
-                &format!("Delete {file_name:?}?"),
+                &prompt,
                 None,
-                &["Delete", "Cancel"],
+                &["Move to Trash", "Cancel"],
             );
 
             cx.spawn(|this, mut cx| async move {
                 if answer.await != Ok(0) {
                     return Ok(());
                 }
+                let tasks = this.update(&mut cx, |this, cx| {
+                    this.marked_entries.clear();
+                    this.project.update(cx, |project, cx| {
+                        entries
+                            .into_iter()
+                            .filter_map(|(entry_id, path)| {
+                                Some((project.trash_entry(entry_id, cx)?, path))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })?;
+
+                let mut operations = Vec::new();
+                let mut result = Ok(());
+                for (task, path) in tasks {
+                    match task.await {
+                        Ok(trash_id) => operations.push(FileOperation::Trashed { path, trash_id }),
+                        Err(error) => result = Err(error),
+                    }
+                }
                 this.update(&mut cx, |this, cx| {
                     this.project
-                        .update(cx, |project, cx| project.delete_entry(entry_id, cx))
-                        .ok_or_else(|| anyhow!("no such entry"))
-                })??
-                .await
+                        .update(cx, |project, _| project.push_file_operations(operations))
+                })?;
+                result
             })
             .detach_and_log_err(cx);
             Some(())
         });
     }
 
+    fn undo(&mut self, _: &Undo, cx: &mut ViewContext<Self>) {
+        self.project
+            .update(cx, |project, cx| project.undo_file_operations(cx))
+            .detach_and_log_err(cx);
+    }
+
+    fn redo(&mut self, _: &Redo, cx: &mut ViewContext<Self>) {
+        self.project
+            .update(cx, |project, cx| project.redo_file_operations(cx))
+            .detach_and_log_err(cx);
+    }
+
     fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
         if let Some(selection) = self.selection {
             let (mut worktree_ix, mut entry_ix, _) =
@@ -872,21 +953,17 @@ impl ProjectPanel {
     }
 
     fn cut(&mut self, _: &Cut, cx: &mut ViewContext<Self>) {
-        if let Some((worktree, entry)) = self.selected_entry(cx) {
-            self.clipboard_entry = Some(ClipboardEntry::Cut {
-                worktree_id: worktree.id(),
-                entry_id: entry.id,
-            });
+        let selections = self.selections_to_act_on();
+        if !selections.is_empty() {
+            self.clipboard_entry = Some(ClipboardEntry::Cut(selections));
             cx.notify();
         }
     }
 
     fn copy(&mut self, _: &Copy, cx: &mut ViewContext<Self>) {
-        if let Some((worktree, entry)) = self.selected_entry(cx) {
-            self.clipboard_entry = Some(ClipboardEntry::Copied {
-                worktree_id: worktree.id(),
-                entry_id: entry.id,
-            });
+        let selections = self.selections_to_act_on();
+        if !selections.is_empty() {
+            self.clipboard_entry = Some(ClipboardEntry::Copied(selections));
             cx.notify();
         }
     }
@@ -894,58 +971,51 @@ impl ProjectPanel {
     fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
         maybe!({
             let (worktree, entry) = self.selected_entry(cx)?;
-            let clipboard_entry = self.clipboard_entry?;
-            if clipboard_entry.worktree_id() != worktree.id() {
-                return None;
-            }
-
-            let clipboard_entry_file_name = self
-                .project
-                .read(cx)
-                .path_for_entry(clipboard_entry.entry_id(), cx)?
-                .path
-                .file_name()?
-                .to_os_string();
+            let worktree_id = worktree.id();
+            let clipboard_entry = self.clipboard_entry.as_ref()?;
 
-            let mut new_path = entry.path.to_path_buf();
+            let mut target_dir = entry.path.to_path_buf();
             if entry.is_file() {
-                new_path.pop();
+                target_dir.pop();
             }
 
-            new_path.push(&clipboard_entry_file_name);
-            let extension = new_path.extension().map(|e| e.to_os_string());
-            let file_name_without_extension = Path::new(&clipboard_entry_file_name).file_stem()?;
-            let mut ix = 0;
-            while worktree.entry_for_path(&new_path).is_some() {
-                new_path.pop();
-
-                let mut new_file_name = file_name_without_extension.to_os_string();
-                new_file_name.push(" copy");
-                if ix > 0 {
-                    new_file_name.push(format!(" {}", ix));
-                }
-                if let Some(extension) = extension.as_ref() {
-                    new_file_name.push(".");
-                    new_file_name.push(extension);
-                }
-
-                new_path.push(new_file_name);
-                ix += 1;
+            let mut reserved_paths = HashSet::default();
+            let mut new_paths = Vec::new();
+            for (entry_id, source_path) in self.disjoint_paths(clipboard_entry.items(), cx) {
+                let Some(file_name) = source_path.path.file_name() else {
+                    continue;
+                };
+                let new_path = available_path(worktree, &target_dir, file_name, &reserved_paths)?;
+                reserved_paths.insert(new_path.clone());
+                new_paths.push((entry_id, source_path, new_path));
             }
 
-            if clipboard_entry.is_cut() {
-                self.project
-                    .update(cx, |project, cx| {
-                        project.rename_entry(clipboard_entry.entry_id(), new_path, cx)
-                    })
-                    .detach_and_log_err(cx)
-            } else {
-                self.project
-                    .update(cx, |project, cx| {
-                        project.copy_entry(clipboard_entry.entry_id(), new_path, cx)
+            let is_cut = clipboard_entry.is_cut();
+            let tasks = self.project.update(cx, |project, cx| {
+                new_paths
+                    .into_iter()
+                    .map(|(entry_id, old_path, new_path)| {
+                        let new_path = ProjectPath {
+                            worktree_id,
+                            path: new_path.into(),
+                        };
+                        if is_cut {
+                            (
+                                project.move_entry_to(entry_id, new_path.clone(), cx),
+                                FileOperation::Moved { old_path, new_path },
+                            )
+                        } else {
+                            (
+                                project.copy_entry_to(entry_id, new_path.clone(), cx),
+                                FileOperation::Created { path: new_path },
+                            )
+                        }
                     })
-                    .detach_and_log_err(cx)
-            }
+                    .collect()
+            });
+            self.record_file_operations(tasks, cx)
+                .detach_and_log_err(cx);
+            self.marked_entries.clear();
 
             Some(())
         });
@@ -1004,35 +1074,210 @@ impl ProjectPanel {
         }
     }
 
-    fn move_entry(
+    fn drag_onto(
         &mut self,
-        entry_to_move: ProjectEntryId,
-        destination: ProjectEntryId,
-        destination_is_file: bool,
+        selections: &DraggedSelection,
+        target_entry_id: ProjectEntryId,
+        target_is_file: bool,
         cx: &mut ViewContext<Self>,
     ) {
-        let destination_worktree = self.project.update(cx, |project, cx| {
-            let entry_path = project.path_for_entry(entry_to_move, cx)?;
-            let destination_entry_path = project.path_for_entry(destination, cx)?.path.clone();
+        let is_copy = cx.modifiers().alt;
+        let Some(target) = self.project.read(cx).path_for_entry(target_entry_id, cx) else {
+            return;
+        };
+        let mut target_dir = target.path.to_path_buf();
+        if target_is_file {
+            target_dir.pop();
+        }
 
-            let mut destination_path = destination_entry_path.as_ref();
-            if destination_is_file {
-                destination_path = destination_path.parent()?;
+        let sources = self.disjoint_paths(selections.items(), cx);
+        let tasks = self.project.update(cx, |project, cx| {
+            let mut tasks = Vec::new();
+            for (entry_id, old_path) in sources {
+                let Some(file_name) = old_path.path.file_name() else {
+                    continue;
+                };
+                let new_path = ProjectPath {
+                    worktree_id: target.worktree_id,
+                    path: target_dir.join(file_name).into(),
+                };
+                if is_copy {
+                    tasks.push((
+                        project.copy_entry_to(entry_id, new_path.clone(), cx),
+                        FileOperation::Created { path: new_path },
+                    ));
+                } else if new_path != old_path {
+                    tasks.push((
+                        project.move_entry_to(entry_id, new_path.clone(), cx),
+                        FileOperation::Moved { old_path, new_path },
+                    ));
+                }
             }
+            tasks
+        });
+        self.record_file_operations(tasks, cx)
+            .detach_and_log_err(cx);
+        self.marked_entries.clear();
+        self.expand_entry(target.worktree_id, target_entry_id, cx);
+    }
 
-            let mut new_path = destination_path.to_path_buf();
-            new_path.push(entry_path.path.file_name()?);
-            if new_path != entry_path.path.as_ref() {
-                let task = project.rename_entry(entry_to_move, new_path, cx);
-                cx.foreground_executor().spawn(task).detach_and_log_err(cx);
-            }
+    fn drop_external_files(
+        &mut self,
+        paths: &[PathBuf],
+        target_entry_id: ProjectEntryId,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let project = self.project.read(cx);
+        if !project.is_local() {
+            return;
+        }
+        let Some(worktree) = project.worktree_for_entry(target_entry_id, cx) else {
+            return;
+        };
+        let Some(target_entry) = worktree.read(cx).entry_for_id(target_entry_id) else {
+            return;
+        };
+        let worktree_id = worktree.read(cx).id();
+        let mut target_dir = target_entry.path.to_path_buf();
+        if target_entry.is_file() {
+            target_dir.pop();
+        }
 
-            Some(project.worktree_id_for_entry(destination, cx)?)
+        let mut reserved_paths = HashSet::default();
+        let mut new_paths = Vec::new();
+        for abs_path in paths {
+            let Some(file_name) = abs_path.file_name() else {
+                continue;
+            };
+            let Some(new_path) =
+                available_path(worktree.read(cx), &target_dir, file_name, &reserved_paths)
+            else {
+                continue;
+            };
+            reserved_paths.insert(new_path.clone());
+            new_paths.push((abs_path.clone(), new_path));
+        }
+
+        let tasks = worktree.update(cx, |worktree, cx| {
+            let worktree = worktree.as_local_mut().unwrap();
+            new_paths
+                .into_iter()
+                .map(|(abs_path, new_path)| {
+                    let path = ProjectPath {
+                        worktree_id,
+                        path: new_path.clone().into(),
+                    };
+                    (
+                        worktree.copy_external_entry(abs_path, new_path, cx),
+                        FileOperation::Created { path },
+                    )
+                })
+                .collect()
         });
+        self.record_file_operations(tasks, cx)
+            .detach_and_log_err(cx);
+        self.expand_entry(worktree_id, target_entry_id, cx);
+    }
+
+    /// Waits for the given file operations and adds the successful ones to the
+    /// project's history as a single undoable step.
+    fn record_file_operations(
+        &self,
+        tasks: Vec<(Task<Result<Option<Entry>>>, FileOperation)>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        cx.spawn(|this, mut cx| async move {
+            let mut operations = Vec::new();
+            let mut result = Ok(());
+            for (task, operation) in tasks {
+                match task.await {
+                    Ok(_) => operations.push(operation),
+                    Err(error) => result = Err(error),
+                }
+            }
+            this.update(&mut cx, |this, cx| {
+                this.project
+                    .update(cx, |project, _| project.push_file_operations(operations))
+            })?;
+            result
+        })
+    }
 
-        if let Some(destination_worktree) = destination_worktree {
-            self.expand_entry(destination_worktree, destination, cx);
+    /// The marked entries together with the selected one.
+    fn selections_to_act_on(&self) -> BTreeSet<Selection> {
+        let mut selections = self.marked_entries.clone();
+        selections.extend(self.selection);
+        selections
+    }
+
+    /// Resolves the paths of the given selections, leaving out entries whose
+    /// ancestor directory is selected as well. Worktree roots can't be moved,
+    /// copied or trashed, so they are left out without hiding their children.
+    fn disjoint_paths<'a>(
+        &self,
+        selections: impl IntoIterator<Item = &'a Selection>,
+        cx: &AppContext,
+    ) -> Vec<(ProjectEntryId, ProjectPath)> {
+        let project = self.project.read(cx);
+        let mut paths = Vec::<(ProjectEntryId, ProjectPath)>::new();
+        for selection in selections {
+            let Some(path) = project.path_for_entry(selection.entry_id, cx) else {
+                continue;
+            };
+            if path.path.file_name().is_some() && !paths.iter().any(|(_, other)| *other == path) {
+                paths.push((selection.entry_id, path));
+            }
+        }
+        paths
+            .iter()
+            .filter(|(_, path)| {
+                !paths.iter().any(|(_, other)| {
+                    other.worktree_id == path.worktree_id
+                        && other.path != path.path
+                        && path.path.starts_with(&other.path)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn toggle_mark(&mut self, selection: Selection) {
+        if self.marked_entries.is_empty() {
+            self.marked_entries.extend(self.selection);
         }
+        if !self.marked_entries.remove(&selection) {
+            self.marked_entries.insert(selection);
+        }
+        self.selection = Some(selection);
+    }
+
+    fn mark_range_to(&mut self, selection: Selection) {
+        let Some(anchor) = self.selection else {
+            self.toggle_mark(selection);
+            return;
+        };
+        let (Some((_, _, anchor_ix)), Some((_, _, target_ix))) = (
+            self.index_for_selection(anchor),
+            self.index_for_selection(selection),
+        ) else {
+            return;
+        };
+
+        let range = anchor_ix.min(target_ix)..=anchor_ix.max(target_ix);
+        self.marked_entries.extend(
+            self.visible_entries
+                .iter()
+                .flat_map(|(worktree_id, entries)| {
+                    entries.iter().map(|entry| Selection {
+                        worktree_id: *worktree_id,
+                        entry_id: entry.id,
+                    })
+                })
+                .enumerate()
+                .filter(|(ix, _)| range.contains(ix))
+                .map(|(_, selection)| selection),
+        );
+        self.selection = Some(selection);
     }
 
     fn index_for_selection(&self, selection: Selection) -> Option<(usize, usize, usize)> {
@@ -1294,7 +1539,12 @@ impl ProjectPanel {
                         }
                     };
 
+                    let selection = Selection {
+                        worktree_id: snapshot.id(),
+                        entry_id: entry.id,
+                    };
                     let mut details = EntryDetails {
+                        worktree_id: snapshot.id(),
                         filename: entry
                             .path
                             .file_name()
@@ -1307,14 +1557,14 @@ impl ProjectPanel {
                         kind: entry.kind,
                         is_ignored: entry.is_ignored,
                         is_expanded,
-                        is_selected: self.selection.map_or(false, |e| {
-                            e.worktree_id == snapshot.id() && e.entry_id == entry.id
-                        }),
+                        is_selected: self.selection == Some(selection),
+                        is_marked: self.marked_entries.contains(&selection),
                         is_editing: false,
                         is_processing: false,
                         is_cut: self
                             .clipboard_entry
-                            .map_or(false, |e| e.is_cut() && e.entry_id() == entry.id),
+                            .as_ref()
+                            .map_or(false, |e| e.is_cut() && e.items().contains(&selection)),
                         git_status: status,
                         is_dotenv: entry.is_private,
                     };
@@ -1356,9 +1606,7 @@ impl ProjectPanel {
         let kind = details.kind;
         let settings = ProjectPanelSettings::get_global(cx);
         let show_editor = details.is_editing && !details.is_processing;
-        let is_selected = self
-            .selection
-            .map_or(false, |selection| selection.entry_id == entry_id);
+        let is_selected = details.is_selected || details.is_marked;
         let width = self.width.unwrap_or(px(0.));
 
         let filename_text_color = details
@@ -1380,21 +1628,50 @@ impl ProjectPanel {
         let file_name = details.filename.clone();
         let icon = details.icon.clone();
         let depth = details.depth;
+        let worktree_id = details.worktree_id;
+        let collaborators = if kind.is_file() {
+            self.render_entry_collaborators(
+                &ProjectPath {
+                    worktree_id,
+                    path: details.path.clone(),
+                },
+                cx,
+            )
+        } else {
+            None
+        };
+        let dragged_selection = DraggedSelection {
+            active_selection: Selection {
+                worktree_id,
+                entry_id,
+            },
+            marked_selections: Arc::new(self.marked_entries.clone()),
+        };
         div()
             .id(entry_id.to_proto() as usize)
-            .on_drag(entry_id, move |entry_id, cx| {
+            .on_drag(dragged_selection, move |selection, cx| {
                 cx.new_view(|_| DraggedProjectEntryView {
                     details: details.clone(),
                     width,
-                    entry_id: *entry_id,
+                    entry_id,
+                    selection_count: selection.items().count(),
                 })
             })
-            .drag_over::<ProjectEntryId>(|style, _, cx| {
+            .drag_over::<DraggedSelection>(|style, _, cx| {
+                style.bg(cx.theme().colors().drop_target_background)
+            })
+            .drag_over::<ExternalPaths>(|style, _, cx| {
                 style.bg(cx.theme().colors().drop_target_background)
             })
-            .on_drop(cx.listener(move |this, dragged_id: &ProjectEntryId, cx| {
-                this.move_entry(*dragged_id, entry_id, kind.is_file(), cx);
+            .on_drop(cx.listener(move |this, selections: &DraggedSelection, cx| {
+                this.drag_onto(selections, entry_id, kind.is_file(), cx);
             }))
+            .on_drop(
+                cx.listener(move |this, external_paths: &ExternalPaths, cx| {
+                    this.drop_external_files(external_paths.paths(), entry_id, cx);
+                    cx.stop_propagation();
+                }),
+            )
             .child(
                 ListItem::new(entry_id.to_proto() as usize)
                     .indent_level(depth)
@@ -1413,11 +1690,28 @@ impl ProjectPanel {
                         }
                         .ml_1(),
                     )
+                    .end_slot(collaborators)
                     .on_click(cx.listener(move |this, event: &gpui::ClickEvent, cx| {
                         if event.down.button == MouseButton::Right {
                             return;
                         }
                         if !show_editor {
+                            let modifiers = event.down.modifiers;
+                            if modifiers.shift || modifiers.control {
+                                let selection = Selection {
+                                    worktree_id,
+                                    entry_id,
+                                };
+                                if modifiers.shift {
+                                    this.mark_range_to(selection);
+                                } else {
+                                    this.toggle_mark(selection);
+                                }
+                                cx.focus(&this.focus_handle);
+                                cx.notify();
+                                return;
+                            }
+                            this.marked_entries.clear();
                             if kind.is_dir() {
                                 this.toggle_expanded(entry_id, cx);
                             } else {
@@ -1440,6 +1734,33 @@ impl ProjectPanel {
             )
     }
 
+    /// Renders the avatars of the collaborators who have the given file open.
+    fn render_entry_collaborators(
+        &self,
+        project_path: &ProjectPath,
+        cx: &ViewContext<Self>,
+    ) -> Option<Div> {
+        let project_id = self.project.read(cx).remote_id()?;
+        let room = ActiveCall::try_global(cx)?.read(cx).room()?.read(cx);
+        let avatars = room
+            .remote_participants_at_path(project_id, project_path)
+            .map(|participant| {
+                let player_color = cx
+                    .theme()
+                    .players()
+                    .color_for_participant(participant.participant_index.0);
+                Avatar::new(participant.user.avatar_uri.clone())
+                    .size(rems(0.75))
+                    .border_color(player_color.cursor)
+            })
+            .collect::<Vec<_>>();
+        if avatars.is_empty() {
+            None
+        } else {
+            Some(h_flex().gap_0p5().children(avatars))
+        }
+    }
+
     fn dispatch_context(&self, cx: &ViewContext<Self>) -> KeyContext {
         let mut dispatch_context = KeyContext::default();
         dispatch_context.add("ProjectPanel");
@@ -1481,6 +1802,37 @@ impl ProjectPanel {
     }
 }
 
+/// Returns `dir/file_name`, or a ` copy`-suffixed variant of it when the path is
+/// already taken in the worktree or reserved by another pending operation.
+fn available_path(
+    worktree: &Worktree,
+    dir: &Path,
+    file_name: &OsStr,
+    reserved_paths: &HashSet<PathBuf>,
+) -> Option<PathBuf> {
+    let mut new_path = dir.join(file_name);
+    let extension = new_path.extension().map(|e| e.to_os_string());
+    let file_name_without_extension = Path::new(file_name).file_stem()?;
+    let mut ix = 0;
+    while worktree.entry_for_path(&new_path).is_some() || reserved_paths.contains(&new_path) {
+        new_path.pop();
+
+        let mut new_file_name = file_name_without_extension.to_os_string();
+        new_file_name.push(" copy");
+        if ix > 0 {
+            new_file_name.push(format!(" {}", ix));
+        }
+        if let Some(extension) = extension.as_ref() {
+            new_file_name.push(".");
+            new_file_name.push(extension);
+        }
+
+        new_path.push(new_file_name);
+        ix += 1;
+    }
+    Some(new_path)
+}
+
 impl Render for ProjectPanel {
     fn render(&mut self, cx: &mut gpui::ViewContext<Self>) -> impl IntoElement {
         let has_worktree = self.visible_entries.len() != 0;
@@ -1511,6 +1863,8 @@ impl Render for ProjectPanel {
                         .on_action(cx.listener(Self::cut))
                         .on_action(cx.listener(Self::copy))
                         .on_action(cx.listener(Self::paste))
+                        .on_action(cx.listener(Self::undo))
+                        .on_action(cx.listener(Self::redo))
                 })
                 .when(project.is_local(), |el| {
                     el.on_action(cx.listener(Self::reveal_in_finder))
@@ -1592,7 +1946,13 @@ impl Render for DraggedProjectEntryView {
                     } else {
                         div()
                     })
-                    .child(Label::new(self.details.filename.clone())),
+                    .child(Label::new(self.details.filename.clone()))
+                    .when(self.selection_count > 1, |item| {
+                        item.end_slot(
+                            Label::new(format!("+{}", self.selection_count - 1))
+                                .color(Color::Muted),
+                        )
+                    }),
             )
     }
 }
@@ -1671,21 +2031,12 @@ impl FocusableView for ProjectPanel {
 
 impl ClipboardEntry {
     fn is_cut(&self) -> bool {
-        matches!(self, Self::Cut { .. })
-    }
-
-    fn entry_id(&self) -> ProjectEntryId {
-        match self {
-            ClipboardEntry::Copied { entry_id, .. } | ClipboardEntry::Cut { entry_id, .. } => {
-                *entry_id
-            }
-        }
+        matches!(self, Self::Cut(_))
     }
 
-    fn worktree_id(&self) -> WorktreeId {
+    fn items(&self) -> &BTreeSet<Selection> {
         match self {
-            ClipboardEntry::Copied { worktree_id, .. }
-            | ClipboardEntry::Cut { worktree_id, .. } => *worktree_id,
+            ClipboardEntry::Copied(items) | ClipboardEntry::Cut(items) => items,
         }
     }
 }
@@ -2474,6 +2825,169 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_multi_selection_move_and_undo(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root1",
+            json!({
+                "a.txt": "",
+                "b.txt": "",
+                "c.txt": "",
+            }),
+        )
+        .await;
+        fs.insert_tree("/root2", json!({ "dir": {} })).await;
+
+        let project = Project::test(fs.clone(), ["/root1".as_ref(), "/root2".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+
+        select_path(&panel, "root1/a.txt", cx);
+        let entry_id = find_project_entry(&panel, "root1/b.txt", cx).unwrap();
+        panel.update(cx, |panel, _| {
+            let worktree_id = panel.selection.unwrap().worktree_id;
+            panel.mark_range_to(Selection {
+                worktree_id,
+                entry_id,
+            });
+        });
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root1",
+                "      a.txt  <== marked",
+                "      b.txt  <== selected",
+                "      c.txt",
+                "v root2",
+                "    > dir",
+            ]
+        );
+
+        // Cut entries are pasted into a directory of another worktree.
+        panel.update(cx, |panel, cx| panel.cut(&Cut, cx));
+        select_path(&panel, "root2/dir", cx);
+        panel.update(cx, |panel, cx| panel.paste(&Paste, cx));
+        cx.executor().run_until_parked();
+        toggle_expand_dir(&panel, "root2/dir", cx);
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root1",
+                "      c.txt",
+                "v root2",
+                "    v dir  <== selected",
+                "          a.txt",
+                "          b.txt",
+            ]
+        );
+
+        // The whole paste is reverted in a single step.
+        panel.update(cx, |panel, cx| panel.undo(&Undo, cx));
+        cx.executor().run_until_parked();
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root1",
+                "      a.txt",
+                "      b.txt",
+                "      c.txt",
+                "v root2",
+                "    v dir  <== selected",
+            ]
+        );
+
+        // A selected worktree root is skipped without hiding the entries in it.
+        select_path(&panel, "root1", cx);
+        let entry_id = find_project_entry(&panel, "root1/a.txt", cx).unwrap();
+        panel.update(cx, |panel, cx| {
+            let worktree_id = panel.selection.unwrap().worktree_id;
+            panel.toggle_mark(Selection {
+                worktree_id,
+                entry_id,
+            });
+            panel.copy(&Copy, cx);
+        });
+        select_path(&panel, "root2/dir", cx);
+        panel.update(cx, |panel, cx| panel.paste(&Paste, cx));
+        cx.executor().run_until_parked();
+        assert!(find_project_entry(&panel, "root2/dir/a.txt", cx).is_some());
+        assert!(find_project_entry(&panel, "root1/a.txt", cx).is_some());
+
+        // Deleted entries are moved to the trash and can be restored.
+        select_path(&panel, "root1/c.txt", cx);
+        submit_deletion(&panel, cx);
+        assert!(find_project_entry(&panel, "root1/c.txt", cx).is_none());
+        assert!(!fs.is_file(Path::new("/root1/c.txt")).await);
+
+        panel.update(cx, |panel, cx| panel.undo(&Undo, cx));
+        cx.executor().run_until_parked();
+        assert!(find_project_entry(&panel, "root1/c.txt", cx).is_some());
+        assert!(fs.is_file(Path::new("/root1/c.txt")).await);
+
+        panel.update(cx, |panel, cx| panel.redo(&Redo, cx));
+        cx.executor().run_until_parked();
+        assert!(find_project_entry(&panel, "root1/c.txt", cx).is_none());
+
+        // Operations that couldn't be undone stay on the undo stack.
+        fs.insert_file("/root1/d.txt", "".into()).await;
+        fs.insert_file("/root1/e.txt", "".into()).await;
+        cx.executor().run_until_parked();
+        let worktree_id = project.update(cx, |project, cx| {
+            let worktree_id = project.worktrees().next().unwrap().read(cx).id();
+            project.push_file_operations(vec![
+                FileOperation::Created {
+                    path: (worktree_id, "d.txt").into(),
+                },
+                FileOperation::Created {
+                    path: (worktree_id, "e.txt").into(),
+                },
+            ]);
+            worktree_id
+        });
+        fs.remove_file(Path::new("/root1/d.txt"), Default::default())
+            .await
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(project
+            .update(cx, |project, cx| project.undo_file_operations(cx))
+            .await
+            .is_err());
+        assert!(!fs.is_file(Path::new("/root1/e.txt")).await);
+
+        fs.insert_file("/root1/d.txt", "".into()).await;
+        cx.executor().run_until_parked();
+        project
+            .update(cx, |project, cx| project.undo_file_operations(cx))
+            .await
+            .unwrap();
+        assert!(!fs.is_file(Path::new("/root1/d.txt")).await);
+
+        // Each partial undo is redone separately.
+        project
+            .update(cx, |project, cx| project.redo_file_operations(cx))
+            .await
+            .unwrap();
+        project
+            .update(cx, |project, cx| project.redo_file_operations(cx))
+            .await
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(project.read_with(cx, |project, cx| {
+            project
+                .entry_for_path(&(worktree_id, "d.txt").into(), cx)
+                .is_some()
+                && project
+                    .entry_for_path(&(worktree_id, "e.txt").into(), cx)
+                    .is_some()
+        }));
+    }
+
     #[gpui::test]
     async fn test_copy_paste_directory(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -3537,6 +4051,8 @@ mod tests {
                 };
                 let selected = if details.is_selected {
                     "  <== selected"
+                } else if details.is_marked {
+                    "  <== marked"
                 } else {
                     ""
                 };
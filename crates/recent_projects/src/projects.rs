@@ -1 +1 @@
-gpui::actions!(projects, [OpenRecent]);
+gpui::actions!(projects, [OpenRecent, SwitchSession]);
@@ -1,5 +1,6 @@
 mod highlighted_workspace_location;
 mod projects;
+mod sessions;
 
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
@@ -14,10 +15,11 @@ use ui::{prelude::*, ListItem, ListItemSpacing};
 use util::paths::PathExt;
 use workspace::{ModalView, Workspace, WorkspaceLocation, WORKSPACE_DB};
 
-pub use projects::OpenRecent;
+pub use projects::{OpenRecent, SwitchSession};
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(RecentProjects::register).detach();
+    sessions::init(cx);
 }
 
 pub struct RecentProjects {
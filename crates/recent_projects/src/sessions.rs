@@ -0,0 +1,211 @@
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Subscription, Task, View,
+    ViewContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace, WORKSPACE_DB};
+
+use crate::SwitchSession;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(SessionPicker::register).detach();
+}
+
+pub struct SessionPicker {
+    picker: View<Picker<SessionPickerDelegate>>,
+    _subscription: Subscription,
+}
+
+impl ModalView for SessionPicker {}
+
+impl SessionPicker {
+    fn new(delegate: SessionPickerDelegate, cx: &mut ViewContext<Self>) -> Self {
+        let picker = cx.new_view(|cx| Picker::new(delegate, cx));
+        let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+        Self {
+            picker,
+            _subscription,
+        }
+    }
+
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &SwitchSession, cx| {
+            let weak_workspace = cx.view().downgrade();
+            let sessions = WORKSPACE_DB
+                .sessions()
+                .log_err()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(_, name)| name)
+                .collect();
+            let active_session = workspace::active_session_name(cx);
+            workspace.toggle_modal(cx, |cx| {
+                let delegate = SessionPickerDelegate::new(weak_workspace, sessions, active_session);
+                SessionPicker::new(delegate, cx)
+            });
+        });
+    }
+}
+
+impl EventEmitter<DismissEvent> for SessionPicker {}
+
+impl FocusableView for SessionPicker {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for SessionPicker {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .w(rems(34.))
+            .child(self.picker.clone())
+            .on_mouse_down_out(cx.listener(|this, _, cx| {
+                this.picker.update(cx, |this, cx| {
+                    this.cancel(&Default::default(), cx);
+                })
+            }))
+    }
+}
+
+pub struct SessionPickerDelegate {
+    workspace: WeakView<Workspace>,
+    sessions: Vec<String>,
+    active_session: Option<String>,
+    matches: Vec<StringMatch>,
+    /// Name of the session that will be created when confirming the last entry,
+    /// if the query doesn't match an existing session exactly.
+    new_session: Option<String>,
+    selected_index: usize,
+}
+
+impl SessionPickerDelegate {
+    fn new(
+        workspace: WeakView<Workspace>,
+        sessions: Vec<String>,
+        active_session: Option<String>,
+    ) -> Self {
+        Self {
+            workspace,
+            sessions,
+            active_session,
+            matches: Vec::new(),
+            new_session: None,
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for SessionPickerDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self) -> Arc<str> {
+        "Switch to session or create a new one...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len() + self.new_session.is_some() as usize
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let query = query.trim().to_string();
+        let candidates = self
+            .sessions
+            .iter()
+            .enumerate()
+            .map(|(id, name)| StringMatchCandidate::new(id, name.clone()))
+            .collect::<Vec<_>>();
+        cx.spawn(move |picker, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            picker
+                .update(&mut cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.new_session = (!query.is_empty()
+                        && !delegate.sessions.iter().any(|name| name == &query))
+                    .then_some(query);
+                    delegate.matches = matches;
+                    delegate.selected_index = 0;
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let name = match self.matches.get(self.selected_index) {
+            Some(session_match) => session_match.string.clone(),
+            None => match self.new_session.clone() {
+                Some(name) => name,
+                None => return,
+            },
+        };
+
+        if self.active_session.as_ref() != Some(&name) {
+            if let Some(workspace) = self.workspace.upgrade() {
+                let app_state = workspace.read(cx).app_state().clone();
+                workspace::switch_session(name, &app_state, cx).detach_and_log_err(cx);
+            }
+        }
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, _: &mut ViewContext<Picker<Self>>) {}
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let item = ListItem::new(ix)
+            .inset(true)
+            .spacing(ListItemSpacing::Sparse)
+            .selected(selected);
+        if let Some(session_match) = self.matches.get(ix) {
+            let is_active = self.active_session.as_ref() == Some(&session_match.string);
+            Some(
+                item.child(HighlightedLabel::new(
+                    session_match.string.clone(),
+                    session_match.positions.clone(),
+                ))
+                .when(is_active, |item| {
+                    item.end_slot(Label::new("Active").color(Color::Muted))
+                }),
+            )
+        } else {
+            let name = self.new_session.as_ref()?;
+            Some(item.child(Label::new(format!("Create session \"{name}\""))))
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use crate::REMOTE_PROJECT_ID;
+use anyhow::{anyhow, Result};
+use client::Client;
+use collections::HashMap;
+use futures::StreamExt as _;
+use gpui::{EventEmitter, Model, ModelContext, Task};
+use project::Project;
+use rpc::{
+    proto::{self, AnyTypedEnvelope, EnvelopedMessage, RequestMessage},
+    Connection, ConnectionId, ErrorExt, Peer, TypedEnvelope,
+};
+use std::{any::TypeId, rc::Rc, sync::Arc};
+use terminal::Terminal;
+use util::ResultExt;
+
+type MessageHandler = Rc<
+    dyn Fn(
+        &mut HeadlessProject,
+        Box<dyn AnyTypedEnvelope>,
+        &mut ModelContext<HeadlessProject>,
+    ) -> Result<()>,
+>;
+
+/// Hosts a local project for a single guest connected over the remote
+/// server's stdio.
+///
+/// The project is shared exactly as it would be through collab: its client
+/// is connected to an in-process relay that plays the server's part,
+/// acknowledging the host's updates and forwarding them to the guest, and
+/// forwarding the guest's requests to the host.
+pub struct HeadlessProject {
+    project: Model<Project>,
+    peer: Arc<Peer>,
+    host_connection_id: ConnectionId,
+    guest_connection_id: ConnectionId,
+    host_handlers: HashMap<TypeId, MessageHandler>,
+    guest_handlers: HashMap<TypeId, MessageHandler>,
+    /// Terminals the guest created, keyed by their shared id. They're kept
+    /// alive until the guest closes them.
+    terminals: HashMap<u64, Model<Terminal>>,
+    _tasks: Vec<Task<()>>,
+}
+
+pub enum Event {
+    GuestDisconnected,
+}
+
+impl EventEmitter<Event> for HeadlessProject {}
+
+impl HeadlessProject {
+    pub fn new(
+        project: Model<Project>,
+        client: Arc<Client>,
+        guest_connection: Connection,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let peer = Peer::new(0);
+        let executor = cx.background_executor().clone();
+        let (host_connection, client_connection) = Connection::pair();
+        let (host_connection_id, host_io, mut host_incoming) =
+            peer.add_connection(host_connection, {
+                let executor = executor.clone();
+                move |duration| executor.timer(duration)
+            });
+        let (guest_connection_id, guest_io, mut guest_incoming) =
+            peer.add_connection(guest_connection, {
+                let executor = executor.clone();
+                move |duration| executor.timer(duration)
+            });
+        for connection_id in [host_connection_id, guest_connection_id] {
+            peer.send(
+                connection_id,
+                proto::Hello {
+                    peer_id: Some(connection_id.into()),
+                },
+            )
+            .log_err();
+        }
+
+        let host_io = executor.spawn(host_io);
+        let guest_io = executor.spawn(guest_io);
+        let tasks = vec![
+            cx.spawn(|this, mut cx| async move {
+                while let Some(envelope) = host_incoming.next().await {
+                    if this
+                        .update(&mut cx, |this, cx| this.handle_message(envelope, cx))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }),
+            cx.spawn(|this, mut cx| async move {
+                // Don't relay anything to the host before its client is connected.
+                if client
+                    .connect_with(client_connection, &cx)
+                    .await
+                    .log_err()
+                    .is_none()
+                {
+                    return;
+                }
+                while let Some(envelope) = guest_incoming.next().await {
+                    if this
+                        .update(&mut cx, |this, cx| this.handle_message(envelope, cx))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }),
+            cx.spawn(|this, mut cx| async move {
+                guest_io.await.log_err();
+                this.update(&mut cx, |_, cx| cx.emit(Event::GuestDisconnected))
+                    .ok();
+            }),
+            cx.background_executor().spawn(async move {
+                host_io.await.log_err();
+            }),
+        ];
+
+        let mut this = Self {
+            project,
+            peer,
+            host_connection_id,
+            guest_connection_id,
+            host_handlers: HashMap::default(),
+            guest_handlers: HashMap::default(),
+            terminals: HashMap::default(),
+            _tasks: tasks,
+        };
+
+        this.add_guest_handler(Self::handle_join_project)
+            .add_guest_handler(Self::handle_leave_project)
+            .add_guest_handler(Self::handle_create_terminal)
+            .add_guest_handler(Self::handle_close_terminal)
+            .forward_guest_request::<proto::GetHover>()
+            .forward_guest_request::<proto::GetDefinition>()
+            .forward_guest_request::<proto::GetTypeDefinition>()
+            .forward_guest_request::<proto::GetReferences>()
+            .forward_guest_request::<proto::SearchProject>()
+            .forward_guest_request::<proto::GetDocumentHighlights>()
+            .forward_guest_request::<proto::GetProjectSymbols>()
+            .forward_guest_request::<proto::OpenBufferForSymbol>()
+            .forward_guest_request::<proto::OpenBufferById>()
+            .forward_guest_request::<proto::OpenBufferByPath>()
+            .forward_guest_request::<proto::SynchronizeBuffers>()
+            .forward_guest_request::<proto::InlayHints>()
+            .forward_guest_request::<proto::ResolveInlayHint>()
+            .forward_guest_request::<proto::GetCompletions>()
+            .forward_guest_request::<proto::ApplyCompletionAdditionalEdits>()
+            .forward_guest_request::<proto::ResolveCompletionDocumentation>()
+            .forward_guest_request::<proto::GetCodeActions>()
+            .forward_guest_request::<proto::ApplyCodeAction>()
+            .forward_guest_request::<proto::PrepareRename>()
+            .forward_guest_request::<proto::PerformRename>()
+            .forward_guest_request::<proto::ReloadBuffers>()
+            .forward_guest_request::<proto::FormatBuffers>()
+            .forward_guest_request::<proto::OnTypeFormatting>()
+            .forward_guest_request::<proto::LspExtExpandMacro>()
+            .forward_guest_request::<proto::CreateProjectEntry>()
+            .forward_guest_request::<proto::RenameProjectEntry>()
+            .forward_guest_request::<proto::CopyProjectEntry>()
+            .forward_guest_request::<proto::DeleteProjectEntry>()
+            .forward_guest_request::<proto::TrashProjectEntry>()
+            .forward_guest_request::<proto::RestoreProjectEntry>()
+            .forward_guest_request::<proto::ExpandProjectEntry>()
+            .forward_guest_request::<proto::SaveBuffer>()
+            .forward_guest_request::<proto::UpdateBuffer>()
+            .forward_guest_request::<proto::OpenTerminal>()
+            .forward_guest_request::<proto::TerminalInput>()
+            .forward_guest_request::<proto::ResizeTerminal>()
+            .forward_host_request::<proto::UpdateProject>()
+            .forward_host_request::<proto::UpdateWorktree>()
+            .forward_host_request::<proto::UpdateBuffer>()
+            .forward_host_message::<proto::UnshareProject>()
+            .forward_host_message::<proto::StartLanguageServer>()
+            .forward_host_message::<proto::UpdateLanguageServer>()
+            .forward_host_message::<proto::UpdateDiagnosticSummary>()
+            .forward_host_message::<proto::UpdateWorktreeSettings>()
+            .forward_host_message::<proto::CreateBufferForPeer>()
+            .forward_host_message::<proto::RefreshInlayHints>()
+            .forward_host_message::<proto::UpdateBufferFile>()
+            .forward_host_message::<proto::BufferReloaded>()
+            .forward_host_message::<proto::BufferSaved>()
+            .forward_host_message::<proto::UpdateDiffBase>()
+            .forward_host_message::<proto::UpdateTerminal>()
+            .forward_host_message::<proto::RemoveTerminal>();
+        this
+    }
+
+    pub fn project(&self) -> &Model<Project> {
+        &self.project
+    }
+
+    fn handle_message(&mut self, envelope: Box<dyn AnyTypedEnvelope>, cx: &mut ModelContext<Self>) {
+        let handlers = if envelope.sender_id() == self.host_connection_id {
+            &self.host_handlers
+        } else {
+            &self.guest_handlers
+        };
+        let handler = handlers.get(&envelope.payload_type_id()).cloned();
+        if let Some(handler) = handler {
+            handler(self, envelope, cx).log_err();
+        } else {
+            log::warn!(
+                "unhandled message {} from remote server {}",
+                envelope.payload_type_name(),
+                if envelope.sender_id() == self.host_connection_id {
+                    "host"
+                } else {
+                    "guest"
+                }
+            );
+            self.peer.respond_with_unhandled_message(envelope).log_err();
+        }
+    }
+
+    fn handle_join_project(
+        &mut self,
+        envelope: TypedEnvelope<proto::JoinProject>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let receipt = envelope.receipt();
+        if envelope.payload.project_id != REMOTE_PROJECT_ID {
+            let error = anyhow!("no such project: {}", envelope.payload.project_id);
+            return self.peer.respond_with_error(receipt, error.to_proto());
+        }
+
+        let worktrees = self.project.read(cx).worktree_metadata_protos(cx);
+        self.peer.respond(
+            receipt,
+            proto::JoinProjectResponse {
+                replica_id: 1,
+                worktrees,
+                collaborators: Vec::new(),
+                // Sharing the project reports the running language servers.
+                language_servers: Vec::new(),
+            },
+        )?;
+        self.project
+            .update(cx, |project, cx| project.shared(REMOTE_PROJECT_ID, &[], cx))
+    }
+
+    fn handle_leave_project(
+        &mut self,
+        _: TypedEnvelope<proto::LeaveProject>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        cx.emit(Event::GuestDisconnected);
+        Ok(())
+    }
+
+    /// Starts a terminal on the host and shares it, so that the guest can
+    /// open it like any other shared terminal.
+    fn handle_create_terminal(
+        &mut self,
+        envelope: TypedEnvelope<proto::CreateTerminal>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let receipt = envelope.receipt();
+        let result = self.project.update(cx, |project, cx| {
+            if envelope.payload.project_id != REMOTE_PROJECT_ID {
+                return Err(anyhow!("no such project: {}", envelope.payload.project_id));
+            }
+            let working_directory = match envelope.payload.worktree_id {
+                Some(worktree_id) => {
+                    let worktree = project
+                        .worktrees()
+                        .find(|worktree| worktree.read(cx).id().to_proto() == worktree_id)
+                        .ok_or_else(|| anyhow!("no such worktree: {worktree_id}"))?;
+                    Some(worktree.read(cx).abs_path().to_path_buf())
+                }
+                None => None,
+            };
+            let terminal = project.create_terminal(working_directory, None, cx)?;
+            let terminal_id = project.share_terminal(&terminal, cx)?;
+            Ok((terminal_id, terminal))
+        });
+        match result {
+            Ok((terminal_id, terminal)) => {
+                self.terminals.insert(terminal_id, terminal);
+                self.peer
+                    .respond(receipt, proto::CreateTerminalResponse { terminal_id })
+            }
+            Err(error) => self.peer.respond_with_error(receipt, error.to_proto()),
+        }
+    }
+
+    fn handle_close_terminal(
+        &mut self,
+        envelope: TypedEnvelope<proto::CloseTerminal>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        // Only the guest uses the terminals it created, so releasing them
+        // exits their shell.
+        self.terminals.remove(&envelope.payload.terminal_id);
+        self.forward_to_host(envelope, cx);
+        Ok(())
+    }
+
+    fn forward_guest_request<M: RequestMessage>(&mut self) -> &mut Self {
+        self.add_guest_handler(|this, envelope: TypedEnvelope<M>, cx| {
+            this.forward_to_host(envelope, cx);
+            Ok(())
+        })
+    }
+
+    fn forward_to_host<M: RequestMessage>(
+        &mut self,
+        envelope: TypedEnvelope<M>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let receipt = envelope.receipt();
+        let peer = self.peer.clone();
+        let response = peer.forward_request(
+            envelope.sender_id,
+            self.host_connection_id,
+            envelope.payload,
+        );
+        cx.foreground_executor()
+            .spawn(async move {
+                match response.await {
+                    Ok(response) => peer.respond(receipt, response),
+                    Err(error) => peer.respond_with_error(receipt, error.to_proto()),
+                }
+                .log_err();
+            })
+            .detach();
+    }
+
+    fn forward_host_request<M: RequestMessage<Response = proto::Ack>>(&mut self) -> &mut Self {
+        self.add_host_handler(|this, envelope: TypedEnvelope<M>, _| {
+            let receipt = envelope.receipt();
+            this.peer.forward_send(
+                envelope.sender_id,
+                this.guest_connection_id,
+                envelope.payload,
+            )?;
+            this.peer.respond(receipt, proto::Ack {})
+        })
+    }
+
+    fn forward_host_message<M: EnvelopedMessage>(&mut self) -> &mut Self {
+        self.add_host_handler(|this, envelope: TypedEnvelope<M>, _| {
+            this.peer.forward_send(
+                envelope.sender_id,
+                this.guest_connection_id,
+                envelope.payload,
+            )
+        })
+    }
+
+    fn add_host_handler<M, F>(&mut self, handler: F) -> &mut Self
+    where
+        M: EnvelopedMessage,
+        F: 'static + Fn(&mut Self, TypedEnvelope<M>, &mut ModelContext<Self>) -> Result<()>,
+    {
+        self.host_handlers
+            .insert(TypeId::of::<M>(), Self::erase_handler(handler));
+        self
+    }
+
+    fn add_guest_handler<M, F>(&mut self, handler: F) -> &mut Self
+    where
+        M: EnvelopedMessage,
+        F: 'static + Fn(&mut Self, TypedEnvelope<M>, &mut ModelContext<Self>) -> Result<()>,
+    {
+        self.guest_handlers
+            .insert(TypeId::of::<M>(), Self::erase_handler(handler));
+        self
+    }
+
+    fn erase_handler<M, F>(handler: F) -> MessageHandler
+    where
+        M: EnvelopedMessage,
+        F: 'static + Fn(&mut Self, TypedEnvelope<M>, &mut ModelContext<Self>) -> Result<()>,
+    {
+        Rc::new(move |this, envelope, cx| {
+            let envelope = envelope
+                .into_any()
+                .downcast::<TypedEnvelope<M>>()
+                .map_err(|_| anyhow!("received a message of an unexpected type"))?;
+            handler(this, *envelope, cx)
+        })
+    }
+}
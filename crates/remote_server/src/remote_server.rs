@@ -0,0 +1,116 @@
+mod headless_project;
+
+#[cfg(test)]
+mod remote_server_tests;
+
+pub use headless_project::*;
+
+use anyhow::{anyhow, Context as _, Result};
+use client::{Client, UserStore};
+use fs::Fs;
+use gpui::{AsyncAppContext, Model};
+use language::LanguageRegistry;
+use project::Project;
+use rpc::{proto, Connection};
+use smol::process::{Child, Command, Stdio};
+use std::{path::Path, sync::Arc};
+
+/// The id under which a remote server shares its project. Every server
+/// process hosts exactly one project, so it never changes.
+pub const REMOTE_PROJECT_ID: u64 = 1;
+
+/// The name of the server binary, which must be on the remote `PATH`.
+pub const REMOTE_SERVER_BINARY: &str = "zed-remote-server";
+
+/// Starts a remote server on `host` over SSH, serving the given paths. The
+/// host has the form `[user@]host[:port]`.
+pub fn spawn_over_ssh(host: &str, paths: &[&Path]) -> Result<(Connection, Child)> {
+    let (destination, port) = parse_ssh_host(host)?;
+    let mut remote_command = REMOTE_SERVER_BINARY.to_string();
+    for path in paths {
+        remote_command.push(' ');
+        remote_command.push_str(&shell_quote(&path.to_string_lossy()));
+    }
+
+    let mut command = Command::new("ssh");
+    command.arg("-T");
+    if let Some(port) = port {
+        command.arg("-p").arg(port.to_string());
+    }
+    command.arg("--").arg(destination).arg(remote_command);
+    spawn(command)
+}
+
+/// Splits a `[user@]host[:port]` string into an ssh destination and a port,
+/// rejecting anything that ssh could interpret as an option.
+fn parse_ssh_host(host: &str) -> Result<(&str, Option<u16>)> {
+    let (destination, port) = match host.rsplit_once(':') {
+        Some((destination, port)) => (
+            destination,
+            Some(
+                port.parse::<u16>()
+                    .map_err(|_| anyhow!("invalid ssh port in {host:?}"))?,
+            ),
+        ),
+        None => (host, None),
+    };
+    let (user, hostname) = match destination.split_once('@') {
+        Some((user, hostname)) => (Some(user), hostname),
+        None => (None, destination),
+    };
+
+    let is_valid_part = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('-')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    };
+    if !user.map_or(true, is_valid_part) || !is_valid_part(hostname) {
+        return Err(anyhow!("invalid ssh host {host:?}"));
+    }
+    Ok((destination, port))
+}
+
+/// Starts a remote server process and connects to it over its stdio.
+pub fn spawn(mut command: Command) -> Result<(Connection, Child)> {
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true);
+    let mut child = command.spawn().context("failed to spawn remote server")?;
+    let stdin = child.stdin.take().context("remote server has no stdin")?;
+    let stdout = child.stdout.take().context("remote server has no stdout")?;
+    Ok((Connection::from_byte_streams(stdout, stdin), child))
+}
+
+/// Joins the project hosted by a remote server, just like guests join
+/// projects that are shared through collab. The `client` must be dedicated
+/// to this connection rather than the one connected to collab.
+pub async fn open_remote_project(
+    client: Arc<Client>,
+    connection: Connection,
+    languages: Arc<LanguageRegistry>,
+    fs: Arc<dyn Fs>,
+    mut cx: AsyncAppContext,
+) -> Result<Model<Project>> {
+    cx.update(|cx| Project::init(&client, cx))?;
+    client.connect_with(connection, &cx).await?;
+    let user_store = cx.new_model(|cx| UserStore::new(client.clone(), cx))?;
+    Project::remote(
+        REMOTE_PROJECT_ID,
+        client,
+        user_store,
+        languages,
+        fs,
+        proto::ChannelRole::Member,
+        None,
+        cx,
+    )
+    .await
+}
+
+fn shell_quote(argument: &str) -> String {
+    format!("'{}'", argument.replace('\'', "'\\''"))
+}
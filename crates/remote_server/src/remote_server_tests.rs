@@ -0,0 +1,193 @@
+use crate::{open_remote_project, parse_ssh_host, HeadlessProject};
+use client::{Client, UserStore};
+use fs::{FakeFs, Fs};
+use gpui::{Context, Model, TestAppContext};
+use language::LanguageRegistry;
+use node_runtime::FakeNodeRuntime;
+use project::{Project, WorktreeId};
+use rpc::Connection;
+use serde_json::json;
+use settings::SettingsStore;
+use std::{path::Path, sync::Arc};
+use util::http::FakeHttpClient;
+
+#[test]
+fn test_parse_ssh_host() {
+    assert_eq!(
+        parse_ssh_host("example.com").unwrap(),
+        ("example.com", None)
+    );
+    assert_eq!(
+        parse_ssh_host("me@example.com:2222").unwrap(),
+        ("me@example.com", Some(2222))
+    );
+    assert!(parse_ssh_host("-oProxyCommand=touch /tmp/owned").is_err());
+    assert!(parse_ssh_host("me@-oProxyCommand=x").is_err());
+    assert!(parse_ssh_host("-me@example.com").is_err());
+    assert!(parse_ssh_host("example.com:port").is_err());
+    assert!(parse_ssh_host("").is_err());
+}
+
+#[gpui::test]
+async fn test_remote_project(cx: &mut TestAppContext, guest_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/code",
+        json!({
+            "project1": {
+                "a.txt": "hello",
+                "b.txt": "world",
+            },
+        }),
+    )
+    .await;
+    let (_, _headless_project, guest_project) =
+        connect_to_remote_project(fs.clone(), "/code/project1", cx, guest_cx).await;
+
+    let worktree_id = guest_project.read_with(guest_cx, |project, cx| {
+        assert_eq!(project.replica_id(), 1);
+        let worktree = project.worktrees().next().unwrap().read(cx);
+        assert_eq!(worktree.root_name(), "project1");
+        assert_eq!(
+            worktree.paths().map(AsRef::as_ref).collect::<Vec<_>>(),
+            [Path::new("a.txt"), Path::new("b.txt")]
+        );
+        worktree.id()
+    });
+
+    let buffer = guest_project
+        .update(guest_cx, |project, cx| {
+            project.open_buffer((worktree_id, "a.txt"), cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(guest_cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "hello");
+        buffer.edit([(5..5, " there")], None, cx);
+    });
+    guest_project
+        .update(guest_cx, |project, cx| project.save_buffer(buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load(Path::new("/code/project1/a.txt")).await.unwrap(),
+        "hello there"
+    );
+
+    // Files created on the host show up in the guest's worktree.
+    fs.insert_file("/code/project1/c.txt", "new".into()).await;
+    cx.executor().run_until_parked();
+    guest_project.read_with(guest_cx, |project, cx| {
+        let worktree = project.worktrees().next().unwrap().read(cx);
+        assert_eq!(
+            worktree.paths().map(AsRef::as_ref).collect::<Vec<_>>(),
+            [Path::new("a.txt"), Path::new("b.txt"), Path::new("c.txt")]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_remote_terminal(cx: &mut TestAppContext, guest_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/code", json!({ "project1": { "a.txt": "hello" } }))
+        .await;
+    let (project, _headless_project, guest_project) =
+        connect_to_remote_project(fs, "/code/project1", cx, guest_cx).await;
+
+    let error = guest_project
+        .update(guest_cx, |project, cx| {
+            project.create_host_terminal(Some(WorktreeId::from_usize(100)), cx)
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("no such worktree"), "{error}");
+    project.read_with(cx, |project, _| {
+        assert!(project.local_terminal_handles().is_empty());
+    });
+
+    // The guest can start a terminal on the host, which is shared with it.
+    let terminal = guest_project
+        .update(guest_cx, |project, cx| {
+            project.create_host_terminal(None, cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    guest_project.read_with(guest_cx, |project, _| {
+        assert_eq!(project.shared_terminal_id(&terminal), Some(0));
+    });
+    project.read_with(cx, |project, _| {
+        let handles = project.local_terminal_handles();
+        assert_eq!(handles.len(), 1);
+        let host_terminal = handles[0].upgrade().unwrap();
+        assert_eq!(project.shared_terminal_id(&host_terminal), Some(0));
+    });
+
+    // Closing the guest's terminal releases the one on the host.
+    guest_cx.update(|_| drop(terminal));
+    cx.executor().run_until_parked();
+    project.read_with(cx, |project, _| {
+        assert!(project.local_terminal_handles().is_empty());
+    });
+}
+
+/// Serves a local project with the given worktree through a headless
+/// project, and joins it as a guest.
+async fn connect_to_remote_project(
+    fs: Arc<FakeFs>,
+    worktree_path: &str,
+    cx: &mut TestAppContext,
+    guest_cx: &mut TestAppContext,
+) -> (Model<Project>, Model<HeadlessProject>, Model<Project>) {
+    init_test(cx);
+    init_test(guest_cx);
+
+    let host_client = cx.update(|cx| Client::new(FakeHttpClient::with_404_response(), cx));
+    cx.update(|cx| Project::init(&host_client, cx));
+    let user_store = cx.new_model(|cx| UserStore::new(host_client.clone(), cx));
+    let project = cx.update(|cx| {
+        Project::local(
+            host_client.clone(),
+            FakeNodeRuntime::new(),
+            user_store,
+            Arc::new(LanguageRegistry::test()),
+            fs,
+            cx,
+        )
+    });
+    project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree(worktree_path, true, cx)
+        })
+        .await
+        .unwrap();
+
+    let (server_connection, client_connection, _) = Connection::in_memory(cx.executor());
+    let headless_project = cx
+        .new_model(|cx| HeadlessProject::new(project.clone(), host_client, server_connection, cx));
+
+    let guest_client = guest_cx.update(|cx| Client::new(FakeHttpClient::with_404_response(), cx));
+    let guest_project = open_remote_project(
+        guest_client,
+        client_connection,
+        Arc::new(LanguageRegistry::test()),
+        FakeFs::new(guest_cx.executor()),
+        guest_cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+
+    (project, headless_project, guest_project)
+}
+
+fn init_test(cx: &mut TestAppContext) {
+    cx.update(|cx| {
+        let settings_store = SettingsStore::test(cx);
+        cx.set_global(settings_store);
+        release_channel::init("0.0.0", cx);
+        language::init(cx);
+        Project::init_settings(cx);
+        terminal::init(cx);
+    });
+}
@@ -1,5 +1,24 @@
+use anyhow::anyhow;
 use async_tungstenite::tungstenite::Message as WebSocketMessage;
-use futures::{SinkExt as _, StreamExt as _};
+use futures::{
+    channel::mpsc, AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, SinkExt as _,
+    StreamExt as _,
+};
+use std::io;
+
+const BINARY_FRAME: u8 = 0;
+const PING_FRAME: u8 = 1;
+const PONG_FRAME: u8 = 2;
+const CLOSE_FRAME: u8 = 3;
+
+/// Sent by each side of a byte stream connection before its first frame, so
+/// that output from anything other than a compatible peer, such as a login
+/// shell's banner, is rejected instead of being parsed as frames.
+const BYTE_STREAM_MAGIC: [u8; 4] = *b"ZEDR";
+
+/// Frames larger than this are rejected rather than allocated, since a
+/// corrupted length would otherwise exhaust memory.
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
 
 pub struct Connection {
     pub(crate) tx:
@@ -28,6 +47,62 @@ impl Connection {
         }
     }
 
+    /// Returns both ends of a connection that lives within a single process.
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, b_rx) = mpsc::unbounded::<WebSocketMessage>();
+        let (b_tx, a_rx) = mpsc::unbounded::<WebSocketMessage>();
+        (
+            Self {
+                tx: Box::new(a_tx.sink_map_err(|error| anyhow!(error))),
+                rx: Box::new(a_rx.map(Ok)),
+            },
+            Self {
+                tx: Box::new(b_tx.sink_map_err(|error| anyhow!(error))),
+                rx: Box::new(b_rx.map(Ok)),
+            },
+        )
+    }
+
+    /// Frames messages over a pair of byte streams, such as the stdio of a
+    /// process spawned over SSH, rather than over a websocket. Each direction
+    /// starts with a handshake that identifies the protocol and its version.
+    pub fn from_byte_streams<R, W>(reader: R, writer: W) -> Self
+    where
+        R: 'static + Send + Unpin + AsyncRead,
+        W: 'static + Send + Unpin + AsyncWrite,
+    {
+        let tx = futures::sink::unfold(
+            (writer, false),
+            |(mut writer, mut sent_handshake), message| async move {
+                if !sent_handshake {
+                    write_handshake(&mut writer).await?;
+                    sent_handshake = true;
+                }
+                write_frame(&mut writer, message).await?;
+                Ok::<_, anyhow::Error>((writer, sent_handshake))
+            },
+        );
+        let rx = futures::stream::unfold(Some((reader, false)), |state| async move {
+            let (mut reader, received_handshake) = state?;
+            if !received_handshake {
+                match read_handshake(&mut reader).await {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(error) => return Some((Err(error), None)),
+                }
+            }
+            match read_frame(&mut reader).await {
+                Ok(Some(message)) => Some((Ok(message), Some((reader, true)))),
+                Ok(None) => None,
+                Err(error) => Some((Err(error), None)),
+            }
+        });
+        Self {
+            tx: Box::new(Box::pin(tx)),
+            rx: Box::new(Box::pin(rx)),
+        }
+    }
+
     pub async fn send(&mut self, message: WebSocketMessage) -> Result<(), anyhow::Error> {
         self.tx.send(message).await
     }
@@ -58,8 +133,6 @@ impl Connection {
             Box<dyn Send + Unpin + futures::Sink<WebSocketMessage, Error = anyhow::Error>>,
             Box<dyn Send + Unpin + futures::Stream<Item = Result<WebSocketMessage, anyhow::Error>>>,
         ) {
-            use anyhow::anyhow;
-            use futures::channel::mpsc;
             use std::io::{Error, ErrorKind};
 
             let (tx, rx) = mpsc::unbounded::<WebSocketMessage>();
@@ -106,3 +179,160 @@ impl Connection {
         }
     }
 }
+
+async fn write_handshake(writer: &mut (impl AsyncWrite + Unpin)) -> anyhow::Result<()> {
+    writer.write_all(&BYTE_STREAM_MAGIC).await?;
+    writer
+        .write_all(&crate::PROTOCOL_VERSION.to_le_bytes())
+        .await?;
+    Ok(())
+}
+
+/// Reads the peer's handshake, returning false if the stream ended first.
+async fn read_handshake(reader: &mut (impl AsyncRead + Unpin)) -> anyhow::Result<bool> {
+    let mut handshake = [0; 8];
+    match reader.read_exact(&mut handshake).await {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(error) => return Err(error.into()),
+    }
+    if handshake[..4] != BYTE_STREAM_MAGIC {
+        return Err(anyhow!("peer is not speaking the rpc protocol"));
+    }
+    let version = u32::from_le_bytes([handshake[4], handshake[5], handshake[6], handshake[7]]);
+    if version != crate::PROTOCOL_VERSION {
+        return Err(anyhow!(
+            "peer speaks protocol version {version}, but {} is required",
+            crate::PROTOCOL_VERSION
+        ));
+    }
+    Ok(true)
+}
+
+async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: WebSocketMessage,
+) -> anyhow::Result<()> {
+    let (kind, payload) = match message {
+        WebSocketMessage::Binary(payload) => (BINARY_FRAME, payload),
+        WebSocketMessage::Ping(payload) => (PING_FRAME, payload),
+        WebSocketMessage::Pong(payload) => (PONG_FRAME, payload),
+        WebSocketMessage::Close(_) => (CLOSE_FRAME, Vec::new()),
+        WebSocketMessage::Text(_) => return Err(anyhow!("text messages are not supported")),
+    };
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(anyhow!(
+            "frame of {} bytes exceeds the maximum of {MAX_FRAME_LEN}",
+            payload.len()
+        ));
+    }
+    let len = u32::try_from(payload.len())?;
+    writer.write_all(&[kind]).await?;
+    writer.write_all(&len.to_le_bytes()).await?;
+    writer.write_all(&payload).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+async fn read_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> anyhow::Result<Option<WebSocketMessage>> {
+    let mut header = [0; 5];
+    match reader.read_exact(&mut header).await {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(anyhow!(
+            "frame of {len} bytes exceeds the maximum of {MAX_FRAME_LEN}"
+        ));
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).await?;
+    match header[0] {
+        BINARY_FRAME => Ok(Some(WebSocketMessage::Binary(payload))),
+        PING_FRAME => Ok(Some(WebSocketMessage::Ping(payload))),
+        PONG_FRAME => Ok(Some(WebSocketMessage::Pong(payload))),
+        CLOSE_FRAME => Ok(None),
+        kind => Err(anyhow!("invalid frame kind {kind}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_stream_framing() {
+        smol::block_on(async {
+            let (a, b) = smol::net::unix::UnixStream::pair().unwrap();
+            let mut a = Connection::from_byte_streams(a.clone(), a);
+            let mut b = Connection::from_byte_streams(b.clone(), b);
+
+            a.send(WebSocketMessage::Binary(vec![1, 2, 3]))
+                .await
+                .unwrap();
+            a.send(WebSocketMessage::Ping(Vec::new())).await.unwrap();
+            a.send(WebSocketMessage::Binary(Vec::new())).await.unwrap();
+            assert_eq!(
+                b.rx.next().await.unwrap().unwrap(),
+                WebSocketMessage::Binary(vec![1, 2, 3])
+            );
+            assert_eq!(
+                b.rx.next().await.unwrap().unwrap(),
+                WebSocketMessage::Ping(Vec::new())
+            );
+            assert_eq!(
+                b.rx.next().await.unwrap().unwrap(),
+                WebSocketMessage::Binary(Vec::new())
+            );
+
+            b.send(WebSocketMessage::Pong(Vec::new())).await.unwrap();
+            b.send(WebSocketMessage::Close(None)).await.unwrap();
+            assert_eq!(
+                a.rx.next().await.unwrap().unwrap(),
+                WebSocketMessage::Pong(Vec::new())
+            );
+            assert!(a.rx.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_byte_stream_handshake_and_frame_limit() {
+        smol::block_on(async {
+            // Output that doesn't start with the handshake is rejected.
+            let (mut a, b) = smol::net::unix::UnixStream::pair().unwrap();
+            let mut b = Connection::from_byte_streams(b.clone(), b);
+            a.write_all(b"Welcome to the server!\n").await.unwrap();
+            assert!(b.rx.next().await.unwrap().is_err());
+            assert!(b.rx.next().await.is_none());
+
+            // So is a peer speaking a different protocol version.
+            let (mut a, b) = smol::net::unix::UnixStream::pair().unwrap();
+            let mut b = Connection::from_byte_streams(b.clone(), b);
+            a.write_all(&BYTE_STREAM_MAGIC).await.unwrap();
+            a.write_all(&(crate::PROTOCOL_VERSION + 1).to_le_bytes())
+                .await
+                .unwrap();
+            assert!(b.rx.next().await.unwrap().is_err());
+
+            // Oversized frames are rejected before their payload is read.
+            let (mut a, b) = smol::net::unix::UnixStream::pair().unwrap();
+            let mut b = Connection::from_byte_streams(b.clone(), b);
+            write_handshake(&mut a).await.unwrap();
+            a.write_all(&[BINARY_FRAME]).await.unwrap();
+            a.write_all(&u32::MAX.to_le_bytes()).await.unwrap();
+            assert!(b.rx.next().await.unwrap().is_err());
+
+            let mut writer = Vec::new();
+            assert!(write_frame(
+                &mut writer,
+                WebSocketMessage::Binary(vec![0; MAX_FRAME_LEN + 1])
+            )
+            .await
+            .is_err());
+        });
+    }
+}
@@ -38,6 +38,14 @@ pub enum Notification {
         sender_id: u64,
         channel_id: u64,
     },
+    CommentMention {
+        #[serde(rename = "entity_id")]
+        comment_id: u64,
+        sender_id: u64,
+        thread_id: u64,
+        channel_id: Option<u64>,
+        project_id: Option<u64>,
+    },
 }
 
 impl Notification {
@@ -96,6 +104,13 @@ mod tests {
                 channel_id: 30,
                 message_id: 1,
             },
+            Notification::CommentMention {
+                comment_id: 7,
+                sender_id: 200,
+                thread_id: 3,
+                channel_id: Some(30),
+                project_id: None,
+            },
         ] {
             let message = notification.to_proto();
             let deserialized = Notification::from_proto(&message).unwrap();
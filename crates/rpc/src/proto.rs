@@ -289,6 +289,37 @@ messages!(
     (LspExtExpandMacro, Background),
     (LspExtExpandMacroResponse, Background),
     (SetRoomParticipantRole, Foreground),
+    (TrashProjectEntry, Foreground),
+    (TrashProjectEntryResponse, Foreground),
+    (RestoreProjectEntry, Foreground),
+    (CreateTerminal, Foreground),
+    (CreateTerminalResponse, Foreground),
+    (OpenTerminal, Foreground),
+    (OpenTerminalResponse, Foreground),
+    (UpdateTerminal, Foreground),
+    (RemoveTerminal, Foreground),
+    (TerminalInput, Foreground),
+    (ResizeTerminal, Foreground),
+    (CloseTerminal, Foreground),
+    (GetCommentThreads, Foreground),
+    (GetCommentThreadsResponse, Foreground),
+    (CreateCommentThread, Foreground),
+    (CreateComment, Foreground),
+    (SetCommentThreadResolved, Foreground),
+    (CommentThreadResponse, Foreground),
+    (UpdateCommentThread, Foreground),
+    (UpdateChannelMessage, Foreground),
+    (ChannelMessageUpdate, Foreground),
+    (ToggleChannelMessageReaction, Foreground),
+    (GetChannelMessageEdits, Background),
+    (GetChannelMessageEditsResponse, Background),
+    (GetChannelMessageThread, Background),
+    (GetChannelBufferSnapshots, Background),
+    (GetChannelBufferSnapshotsResponse, Background),
+    (GetChannelBufferSnapshot, Background),
+    (GetChannelBufferSnapshotResponse, Background),
+    (CreateProjectInvite, Foreground),
+    (CreateProjectInviteResponse, Foreground),
 );
 
 request_messages!(
@@ -377,6 +408,24 @@ request_messages!(
     (UpdateWorktree, Ack),
     (LspExtExpandMacro, LspExtExpandMacroResponse),
     (SetRoomParticipantRole, Ack),
+    (TrashProjectEntry, TrashProjectEntryResponse),
+    (RestoreProjectEntry, ProjectEntryResponse),
+    (CreateTerminal, CreateTerminalResponse),
+    (OpenTerminal, OpenTerminalResponse),
+    (TerminalInput, Ack),
+    (ResizeTerminal, Ack),
+    (CloseTerminal, Ack),
+    (GetCommentThreads, GetCommentThreadsResponse),
+    (CreateCommentThread, CommentThreadResponse),
+    (CreateComment, CommentThreadResponse),
+    (SetCommentThreadResolved, CommentThreadResponse),
+    (UpdateChannelMessage, SendChannelMessageResponse),
+    (ToggleChannelMessageReaction, SendChannelMessageResponse),
+    (GetChannelMessageEdits, GetChannelMessageEditsResponse),
+    (GetChannelMessageThread, GetChannelMessagesResponse),
+    (GetChannelBufferSnapshots, GetChannelBufferSnapshotsResponse),
+    (GetChannelBufferSnapshot, GetChannelBufferSnapshotResponse),
+    (CreateProjectInvite, CreateProjectInviteResponse),
 );
 
 entity_messages!(
@@ -430,11 +479,21 @@ entity_messages!(
     UpdateWorktree,
     UpdateWorktreeSettings,
     LspExtExpandMacro,
+    TrashProjectEntry,
+    RestoreProjectEntry,
+    CreateTerminal,
+    OpenTerminal,
+    UpdateTerminal,
+    RemoveTerminal,
+    TerminalInput,
+    ResizeTerminal,
+    CloseTerminal,
 );
 
 entity_messages!(
     {channel_id, Channel},
     ChannelMessageSent,
+    ChannelMessageUpdate,
     RemoveChannelMessage,
     UpdateChannelBuffer,
     UpdateChannelBufferCollaborators,
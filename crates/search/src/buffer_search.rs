@@ -668,9 +668,8 @@ impl BufferSearchBar {
         option.as_button(is_active, action)
     }
     pub fn activate_search_mode(&mut self, mode: SearchMode, cx: &mut ViewContext<Self>) {
-        assert_ne!(
-            mode,
-            SearchMode::Semantic,
+        assert!(
+            !mode.uses_semantic_index(),
             "Semantic search is not supported in buffer search"
         );
         if mode == self.current_mode {
@@ -1,6 +1,6 @@
 use gpui::{Action, SharedString};
 
-use crate::{ActivateRegexMode, ActivateSemanticMode, ActivateTextMode};
+use crate::{ActivateHybridMode, ActivateRegexMode, ActivateSemanticMode, ActivateTextMode};
 
 // TODO: Update the default search mode to get from config
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
@@ -8,6 +8,8 @@ pub enum SearchMode {
     #[default]
     Text,
     Semantic,
+    /// Combines lexical and semantic relevance with symbol-name matches.
+    Hybrid,
     Regex,
 }
 
@@ -16,16 +18,23 @@ impl SearchMode {
         match self {
             SearchMode::Text => "Text",
             SearchMode::Semantic => "Semantic",
+            SearchMode::Hybrid => "Hybrid",
             SearchMode::Regex => "Regex",
         }
     }
     pub(crate) fn tooltip(&self) -> SharedString {
         format!("Activate {} Mode", self.label()).into()
     }
+    /// Whether the mode searches the semantic index, and so needs the project
+    /// to be indexed.
+    pub(crate) fn uses_semantic_index(&self) -> bool {
+        matches!(self, SearchMode::Semantic | SearchMode::Hybrid)
+    }
     pub(crate) fn action(&self) -> Box<dyn Action> {
         match self {
             SearchMode::Text => ActivateTextMode.boxed_clone(),
             SearchMode::Semantic => ActivateSemanticMode.boxed_clone(),
+            SearchMode::Hybrid => ActivateHybridMode.boxed_clone(),
             SearchMode::Regex => ActivateRegexMode.boxed_clone(),
         }
     }
@@ -41,6 +50,7 @@ pub(crate) fn next_mode(mode: &SearchMode, semantic_enabled: bool) -> SearchMode
                 SearchMode::Text
             }
         }
-        SearchMode::Semantic => SearchMode::Text,
+        SearchMode::Semantic => SearchMode::Hybrid,
+        SearchMode::Hybrid => SearchMode::Text,
     }
 }
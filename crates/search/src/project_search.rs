@@ -1,22 +1,25 @@
 use crate::{
-    history::SearchHistory, mode::SearchMode, ActivateRegexMode, ActivateSemanticMode,
-    ActivateTextMode, CycleMode, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll, ReplaceNext,
-    SearchOptions, SelectNextMatch, SelectPrevMatch, ToggleCaseSensitive, ToggleIncludeIgnored,
-    ToggleReplace, ToggleWholeWord,
+    history::SearchHistory, mode::SearchMode, ActivateHybridMode, ActivateRegexMode,
+    ActivateSemanticMode, ActivateTextMode, CycleMode, NextHistoryQuery, PreviousHistoryQuery,
+    ReplaceAll, ReplaceNext, SearchOptions, SelectNextMatch, SelectPrevMatch, ToggleCaseSensitive,
+    ToggleIncludeIgnored, TogglePreserveCase, ToggleReplace, ToggleWholeWord,
 };
 use anyhow::{Context as _, Result};
 use collections::HashMap;
 use editor::{
-    actions::SelectAll, items::active_match_index, scroll::Autoscroll, Anchor, Editor, EditorEvent,
-    MultiBuffer, MAX_TAB_TITLE_LEN,
+    actions::SelectAll,
+    display_map::{BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    items::active_match_index,
+    scroll::Autoscroll,
+    Anchor, Editor, EditorEvent, MultiBuffer, MultiBufferSnapshot, ToOffset, MAX_TAB_TITLE_LEN,
 };
 use editor::{EditorElement, EditorStyle};
 use gpui::{
     actions, div, Action, AnyElement, AnyView, AppContext, Context as _, Element, EntityId,
-    EventEmitter, FocusHandle, FocusableView, FontStyle, FontWeight, Global, Hsla,
+    EventEmitter, FocusHandle, FocusableView, FontStyle, FontWeight, Global, HighlightStyle, Hsla,
     InteractiveElement, IntoElement, KeyContext, Model, ModelContext, ParentElement, PromptLevel,
-    Render, SharedString, Styled, Subscription, Task, TextStyle, View, ViewContext, VisualContext,
-    WeakModel, WeakView, WhiteSpace, WindowContext,
+    Render, SharedString, StrikethroughStyle, Styled, Subscription, Task, TextStyle, View,
+    ViewContext, VisualContext, WeakModel, WeakView, WhiteSpace, WindowContext,
 };
 use menu::Confirm;
 use project::{
@@ -33,6 +36,7 @@ use std::{
     mem,
     ops::{Not, Range},
     path::PathBuf,
+    sync::Arc,
     time::{Duration, Instant},
 };
 use theme::ThemeSettings;
@@ -52,9 +56,19 @@ use workspace::{
 
 actions!(
     project_search,
-    [SearchInNew, ToggleFocus, NextField, ToggleFilters]
+    [
+        SearchInNew,
+        ToggleFocus,
+        NextField,
+        ToggleFilters,
+        ToggleMatchExclusion,
+        ToggleFileExclusion
+    ]
 );
 
+enum ReplacementPreview {}
+enum ExcludedMatch {}
+
 #[derive(Default)]
 struct ActiveSettings(HashMap<WeakModel<Project>, ProjectSearchSettings>);
 
@@ -75,6 +89,16 @@ pub fn init(cx: &mut AppContext) {
         register_workspace_action(workspace, move |search_bar, action: &ToggleReplace, cx| {
             search_bar.toggle_replace(action, cx)
         });
+        register_workspace_action(workspace, move |search_bar, _: &TogglePreserveCase, cx| {
+            search_bar.toggle_preserve_case(cx)
+        });
+        register_workspace_action(
+            workspace,
+            move |search_bar, _: &ToggleMatchExclusion, cx| search_bar.toggle_match_exclusion(cx),
+        );
+        register_workspace_action(workspace, move |search_bar, _: &ToggleFileExclusion, cx| {
+            search_bar.toggle_file_exclusion(cx)
+        });
         register_workspace_action(workspace, move |search_bar, _: &ActivateRegexMode, cx| {
             search_bar.activate_search_mode(SearchMode::Regex, cx)
         });
@@ -87,6 +111,9 @@ pub fn init(cx: &mut AppContext) {
                 search_bar.activate_search_mode(SearchMode::Semantic, cx)
             },
         );
+        register_workspace_action(workspace, move |search_bar, _: &ActivateHybridMode, cx| {
+            search_bar.activate_search_mode(SearchMode::Hybrid, cx)
+        });
         register_workspace_action(workspace, move |search_bar, action: &CycleMode, cx| {
             search_bar.cycle_mode(action, cx)
         });
@@ -134,6 +161,9 @@ struct ProjectSearch {
     excerpts: Model<MultiBuffer>,
     pending_search: Option<Task<Option<()>>>,
     match_ranges: Vec<Range<Anchor>>,
+    /// Why each match of a hybrid search was ranked where it was, in the same
+    /// order as `match_ranges`. Empty for other searches.
+    match_explanations: Vec<SharedString>,
     active_query: Option<SearchQuery>,
     search_id: usize,
     search_history: SearchHistory,
@@ -153,11 +183,17 @@ pub struct ProjectSearchView {
     query_editor: View<Editor>,
     replacement_editor: View<Editor>,
     results_editor: View<Editor>,
+    /// The blocks above each match that explain its hybrid search ranking.
+    explanation_blocks: HashSet<BlockId>,
     semantic_state: Option<SemanticState>,
     semantic_permissioned: Option<bool>,
     search_options: SearchOptions,
     panels_with_errors: HashSet<InputPanel>,
     active_match_index: Option<usize>,
+    /// Indices of the current search's matches that replacements should skip.
+    excluded_matches: HashSet<usize>,
+    /// Indices of the current search's matches that were already replaced.
+    replaced_matches: HashMap<usize, ReplacedMatch>,
     search_id: usize,
     query_editor_was_focused: bool,
     included_files_editor: View<Editor>,
@@ -174,6 +210,32 @@ struct SemanticState {
     _subscription: Subscription,
 }
 
+/// A match that was replaced, along with the text that replaced it.
+struct ReplacedMatch {
+    original_text: String,
+    replacement_text: String,
+    /// Spans the inserted replacement text, so it collapses once the
+    /// replacement is undone.
+    replacement_range: Range<Anchor>,
+}
+
+impl ReplacedMatch {
+    fn is_reverted(&self, match_range: &Range<Anchor>, snapshot: &MultiBufferSnapshot) -> bool {
+        if self.replacement_text.is_empty() {
+            // Deletions leave nothing to anchor to, so look for the original text instead.
+            snapshot
+                .text_for_range(match_range.clone())
+                .collect::<String>()
+                == self.original_text
+        } else {
+            snapshot
+                .text_for_range(self.replacement_range.clone())
+                .collect::<String>()
+                != self.replacement_text
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ProjectSearchSettings {
     search_options: SearchOptions,
@@ -196,6 +258,7 @@ impl ProjectSearch {
             excerpts: cx.new_model(|_| MultiBuffer::new(replica_id, capability)),
             pending_search: Default::default(),
             match_ranges: Default::default(),
+            match_explanations: Default::default(),
             active_query: None,
             search_id: 0,
             search_history: SearchHistory::default(),
@@ -211,6 +274,7 @@ impl ProjectSearch {
                 .update(cx, |excerpts, cx| cx.new_model(|cx| excerpts.clone(cx))),
             pending_search: Default::default(),
             match_ranges: self.match_ranges.clone(),
+            match_explanations: self.match_explanations.clone(),
             active_query: self.active_query.clone(),
             search_id: self.search_id,
             search_history: self.search_history.clone(),
@@ -226,11 +290,13 @@ impl ProjectSearch {
         self.search_history.add(query.as_str().to_string());
         self.active_query = Some(query);
         self.match_ranges.clear();
+        self.match_explanations.clear();
         self.pending_search = Some(cx.spawn(|this, mut cx| async move {
             let mut matches = search;
             let this = this.upgrade()?;
             this.update(&mut cx, |this, cx| {
                 this.match_ranges.clear();
+                this.match_explanations.clear();
                 this.excerpts.update(cx, |this, cx| this.clear(cx));
                 this.no_results = Some(true);
             })
@@ -279,6 +345,7 @@ impl ProjectSearch {
         });
         self.search_id += 1;
         self.match_ranges.clear();
+        self.match_explanations.clear();
         self.search_history.add(inputs.as_str().to_string());
         self.no_results = None;
         self.pending_search = Some(cx.spawn(|this, mut cx| async move {
@@ -325,6 +392,76 @@ impl ProjectSearch {
         }));
         cx.notify();
     }
+
+    fn hybrid_search(&mut self, inputs: &SearchInputs, cx: &mut ModelContext<Self>) {
+        let search = SemanticIndex::global(cx).map(|index| {
+            index.update(cx, |semantic_index, cx| {
+                semantic_index.hybrid_search_project(
+                    self.project.clone(),
+                    inputs.as_str().to_owned(),
+                    10,
+                    inputs.files_to_include().to_vec(),
+                    inputs.files_to_exclude().to_vec(),
+                    cx,
+                )
+            })
+        });
+        self.search_id += 1;
+        self.match_ranges.clear();
+        self.match_explanations.clear();
+        self.search_history.add(inputs.as_str().to_string());
+        self.no_results = None;
+        self.pending_search = Some(cx.spawn(|this, mut cx| async move {
+            let results = search?.await.log_err()?;
+
+            this.update(&mut cx, |this, cx| {
+                this.no_results = Some(true);
+                this.excerpts.update(cx, |excerpts, cx| {
+                    excerpts.clear(cx);
+                });
+            })
+            .ok()?;
+            for (ix, result) in results.into_iter().enumerate() {
+                let explanation = SharedString::from(format!(
+                    "#{} · score {:.4} · {}",
+                    ix + 1,
+                    result.score,
+                    result.explanation
+                ));
+                let ranges = vec![result.range.start..result.range.start];
+                let mut match_ranges = this
+                    .update(&mut cx, |this, cx| {
+                        this.no_results = Some(false);
+                        this.excerpts.update(cx, |excerpts, cx| {
+                            excerpts.stream_excerpts_with_context_lines(
+                                result.buffer,
+                                ranges,
+                                3,
+                                cx,
+                            )
+                        })
+                    })
+                    .ok()?;
+                while let Some(match_range) = match_ranges.next().await {
+                    this.update(&mut cx, |this, cx| {
+                        this.match_ranges.push(match_range);
+                        this.match_explanations.push(explanation.clone());
+                        cx.notify();
+                    })
+                    .ok()?;
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.pending_search.take();
+                cx.notify();
+            })
+            .ok()?;
+
+            None
+        }));
+        cx.notify();
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -713,6 +850,7 @@ impl ProjectSearchView {
             model.pending_search = None;
             model.no_results = None;
             model.match_ranges.clear();
+            model.match_explanations.clear();
 
             model.excerpts.update(cx, |excerpts, cx| {
                 excerpts.clear(cx);
@@ -731,7 +869,7 @@ impl ProjectSearchView {
         self.active_match_index = None;
 
         match mode {
-            SearchMode::Semantic => {
+            SearchMode::Semantic | SearchMode::Hybrid => {
                 let has_permission = self.semantic_permissioned(cx);
                 self.active_match_index = None;
                 cx.spawn(|this, mut cx| async move {
@@ -766,7 +904,7 @@ impl ProjectSearchView {
                         } else {
                             this.update(&mut cx, |this, cx| {
                                 this.semantic_permissioned = Some(false);
-                                debug_assert_ne!(previous_mode, SearchMode::Semantic, "Tried to re-enable semantic search mode after user modal was rejected");
+                                debug_assert!(!previous_mode.uses_semantic_index(), "Tried to re-enable semantic search mode after user modal was rejected");
                                 this.activate_search_mode(previous_mode, cx);
                             })?;
                             return anyhow::Ok(());
@@ -797,43 +935,232 @@ impl ProjectSearchView {
         cx.notify();
     }
     fn replace_next(&mut self, _: &ReplaceNext, cx: &mut ViewContext<Self>) {
+        let Some(active_index) = self.active_match_index else {
+            return;
+        };
+        let Some(query) = self.replacement_query(cx) else {
+            return;
+        };
         let model = self.model.read(cx);
-        if let Some(query) = model.active_query.as_ref() {
-            if model.match_ranges.is_empty() {
-                return;
-            }
-            if let Some(active_index) = self.active_match_index {
-                let query = query.clone().with_replacement(self.replacement(cx));
-                self.results_editor.replace(
-                    &(Box::new(model.match_ranges[active_index].clone()) as _),
-                    &query,
-                    cx,
-                );
-                self.select_match(Direction::Next, cx)
-            }
+        if model.match_ranges.is_empty() {
+            return;
+        }
+        if !self.excluded_matches.contains(&active_index)
+            && !self.replaced_matches.contains_key(&active_index)
+        {
+            let range = model.match_ranges[active_index].clone();
+            self.replace_matches(vec![(active_index, range)], &query, cx);
         }
+        self.select_match(Direction::Next, cx)
     }
     pub fn replacement(&self, cx: &AppContext) -> String {
         self.replacement_editor.read(cx).text(cx)
     }
+    fn replacement_query(&self, cx: &AppContext) -> Option<SearchQuery> {
+        let query = self.model.read(cx).active_query.as_ref()?;
+        Some(
+            query
+                .clone()
+                .with_replacement(self.replacement(cx))
+                .with_preserve_case(self.search_options.contains(SearchOptions::PRESERVE_CASE)),
+        )
+    }
+    /// Returns the matches that are neither excluded nor replaced yet.
+    fn pending_matches(&self, cx: &AppContext) -> Vec<(usize, Range<Anchor>)> {
+        self.model
+            .read(cx)
+            .match_ranges
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|(ix, _)| {
+                !self.excluded_matches.contains(ix) && !self.replaced_matches.contains_key(ix)
+            })
+            .collect()
+    }
     fn replace_all(&mut self, _: &ReplaceAll, cx: &mut ViewContext<Self>) {
-        let model = self.model.read(cx);
-        if let Some(query) = model.active_query.as_ref() {
-            if model.match_ranges.is_empty() {
-                return;
+        if self.active_match_index.is_none() {
+            return;
+        }
+        let Some(query) = self.replacement_query(cx) else {
+            return;
+        };
+        let pending_matches = self.pending_matches(cx);
+        if pending_matches.is_empty() {
+            return;
+        }
+
+        self.replace_matches(pending_matches, &query, cx);
+    }
+
+    /// Replaces the given matches, in order, in a single transaction so that
+    /// one undo reverts all of them.
+    fn replace_matches(
+        &mut self,
+        matches: Vec<(usize, Range<Anchor>)>,
+        query: &SearchQuery,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let replaced_matches = self.results_editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let mut replacements = Vec::new();
+            let mut edits = Vec::new();
+            for (ix, range) in matches {
+                let original_text = snapshot.text_for_range(range.clone()).collect::<String>();
+                if let Some(replacement) = query.replacement_for(&original_text) {
+                    let start = range.start.to_offset(&snapshot);
+                    let replacement_text = replacement.into_owned();
+                    edits.push((range, Arc::<str>::from(replacement_text.as_str())));
+                    replacements.push((ix, start, original_text, replacement_text));
+                }
             }
-            if self.active_match_index.is_some() {
-                let query = query.clone().with_replacement(self.replacement(cx));
-                let matches = model
-                    .match_ranges
+            editor.transact(cx, |editor, cx| editor.edit(edits, cx));
+
+            // Anchor each replacement to the text it inserted, shifting its
+            // start by the length changes of the replacements before it.
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let mut delta = 0_isize;
+            replacements
+                .into_iter()
+                .map(|(ix, start, original_text, replacement_text)| {
+                    let start = (start as isize + delta) as usize;
+                    let end = start + replacement_text.len();
+                    delta += replacement_text.len() as isize - original_text.len() as isize;
+                    let replaced_match = ReplacedMatch {
+                        original_text,
+                        replacement_range: snapshot.anchor_after(start)
+                            ..snapshot.anchor_before(end),
+                        replacement_text,
+                    };
+                    (ix, replaced_match)
+                })
+                .collect::<Vec<_>>()
+        });
+        self.replaced_matches.extend(replaced_matches);
+        self.update_match_highlights(cx);
+    }
+
+    /// Forgets the replacements whose inserted text is gone from the results,
+    /// e.g. after an undo, so that those matches can be replaced again.
+    fn forget_reverted_replacements(&mut self, cx: &mut ViewContext<Self>) -> bool {
+        if self.replaced_matches.is_empty() {
+            return false;
+        }
+        let snapshot = self.results_editor.read(cx).buffer().read(cx).snapshot(cx);
+        let match_ranges = &self.model.read(cx).match_ranges;
+        let replaced_count = self.replaced_matches.len();
+        self.replaced_matches.retain(|ix, replaced_match| {
+            match_ranges
+                .get(*ix)
+                .map_or(false, |range| !replaced_match.is_reverted(range, &snapshot))
+        });
+        self.replaced_matches.len() != replaced_count
+    }
+
+    fn toggle_match_exclusion(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(active_index) = self.active_match_index else {
+            return;
+        };
+        if !self.excluded_matches.remove(&active_index) {
+            self.excluded_matches.insert(active_index);
+        }
+        self.update_match_highlights(cx);
+        cx.notify();
+    }
+
+    fn toggle_file_exclusion(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(active_index) = self.active_match_index else {
+            return;
+        };
+        let match_ranges = &self.model.read(cx).match_ranges;
+        let Some(buffer_id) = match_ranges
+            .get(active_index)
+            .map(|range| range.start.buffer_id)
+        else {
+            return;
+        };
+        let file_matches = match_ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| range.start.buffer_id == buffer_id)
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        let exclude = file_matches
+            .iter()
+            .any(|ix| !self.excluded_matches.contains(ix));
+        for ix in file_matches {
+            if exclude {
+                self.excluded_matches.insert(ix);
+            } else {
+                self.excluded_matches.remove(&ix);
+            }
+        }
+        self.update_match_highlights(cx);
+        cx.notify();
+    }
+
+    fn update_match_highlights(&mut self, cx: &mut ViewContext<Self>) {
+        let excluded_ranges = self
+            .model
+            .read(cx)
+            .match_ranges
+            .iter()
+            .enumerate()
+            .filter(|(ix, _)| self.excluded_matches.contains(ix))
+            .map(|(_, range)| range.clone())
+            .collect::<Vec<_>>();
+        let pending_ranges = self
+            .pending_matches(cx)
+            .into_iter()
+            .map(|(_, range)| range)
+            .collect::<Vec<_>>();
+        let replacement_query = if self.replace_enabled {
+            self.replacement_query(cx)
+        } else {
+            None
+        };
+
+        self.results_editor.update(cx, |editor, cx| {
+            editor.highlight_background::<Self>(
+                pending_ranges.clone(),
+                |theme| theme.search_match_background,
+                cx,
+            );
+            editor.highlight_text::<ExcludedMatch>(
+                excluded_ranges,
+                HighlightStyle {
+                    fade_out: Some(0.6),
+                    ..Default::default()
+                },
+                cx,
+            );
+            if let Some(query) = replacement_query {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let previews = pending_ranges
                     .iter()
-                    .map(|item| Box::new(item.clone()) as _)
+                    .filter_map(|range| {
+                        let text = snapshot.text_for_range(range.clone()).collect::<String>();
+                        let replacement = query.replacement_for(&text)?;
+                        Some((range.end, replacement.into_owned()))
+                    })
                     .collect::<Vec<_>>();
-                for item in matches {
-                    self.results_editor.replace(&item, &query, cx);
-                }
+                editor.highlight_text::<ReplacementPreview>(
+                    pending_ranges,
+                    HighlightStyle {
+                        strikethrough: Some(StrikethroughStyle {
+                            thickness: px(1.),
+                            color: None,
+                        }),
+                        ..Default::default()
+                    },
+                    cx,
+                );
+                editor.set_replacement_previews(previews, cx);
+            } else {
+                editor.clear_highlights::<ReplacementPreview>(cx);
+                editor.set_replacement_previews(Vec::new(), cx);
             }
-        }
+        });
     }
 
     fn new(
@@ -890,6 +1217,14 @@ impl ProjectSearchView {
             }
             editor
         });
+        subscriptions.push(cx.subscribe(
+            &replacement_editor,
+            |this, _, event: &EditorEvent, cx| {
+                if matches!(event, EditorEvent::BufferEdited) {
+                    this.update_match_highlights(cx);
+                }
+            },
+        ));
         let results_editor = cx.new_view(|cx| {
             let mut editor = Editor::for_multibuffer(excerpts, Some(project.clone()), cx);
             editor.set_searchable(false);
@@ -902,6 +1237,12 @@ impl ProjectSearchView {
                 if matches!(event, editor::EditorEvent::SelectionsChanged { .. }) {
                     this.update_match_index(cx);
                 }
+                if matches!(event, EditorEvent::BufferEdited) {
+                    let replacements_reverted = this.forget_reverted_replacements(cx);
+                    if replacements_reverted || this.replace_enabled {
+                        this.update_match_highlights(cx);
+                    }
+                }
                 // Reraise editor events for workspace item activation purposes
                 cx.emit(ViewEvent::EditorEvent(event.clone()));
             }),
@@ -952,11 +1293,14 @@ impl ProjectSearchView {
             model,
             query_editor,
             results_editor,
+            explanation_blocks: HashSet::default(),
             semantic_state: None,
             semantic_permissioned: None,
             search_options: options,
             panels_with_errors: HashSet::default(),
             active_match_index: None,
+            excluded_matches: HashSet::default(),
+            replaced_matches: HashMap::default(),
             query_editor_was_focused: false,
             included_files_editor,
             excluded_files_editor,
@@ -1118,6 +1462,14 @@ impl ProjectSearchView {
                     }
                 }
             }
+            SearchMode::Hybrid => {
+                if self.semantic_state.is_some() {
+                    if let Some(query) = self.build_search_query(cx) {
+                        self.model
+                            .update(cx, |model, cx| model.hybrid_search(query.as_inner(), cx));
+                    }
+                }
+            }
 
             _ => {
                 if let Some(query) = self.build_search_query(cx) {
@@ -1285,13 +1637,17 @@ impl ProjectSearchView {
         let match_ranges = self.model.read(cx).match_ranges.clone();
         if match_ranges.is_empty() {
             self.active_match_index = None;
+            self.excluded_matches.clear();
+            self.replaced_matches.clear();
         } else {
             self.active_match_index = Some(0);
             self.update_match_index(cx);
             let prev_search_id = mem::replace(&mut self.search_id, self.model.read(cx).search_id);
             let is_new_search = self.search_id != prev_search_id;
-            self.results_editor.update(cx, |editor, cx| {
-                if is_new_search {
+            if is_new_search {
+                self.excluded_matches.clear();
+                self.replaced_matches.clear();
+                self.results_editor.update(cx, |editor, cx| {
                     let range_to_select = match_ranges
                         .first()
                         .clone()
@@ -1299,22 +1655,56 @@ impl ProjectSearchView {
                     editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                         s.select_ranges(range_to_select)
                     });
-                }
-                editor.highlight_background::<Self>(
-                    match_ranges,
-                    |theme| theme.search_match_background,
-                    cx,
-                );
-            });
+                });
+            }
+            self.update_match_highlights(cx);
             if is_new_search && self.query_editor.focus_handle(cx).is_focused(cx) {
                 self.focus_results_editor(cx);
             }
         }
+        self.update_explanation_blocks(cx);
 
         cx.emit(ViewEvent::UpdateTab);
         cx.notify();
     }
 
+    fn update_explanation_blocks(&mut self, cx: &mut ViewContext<Self>) {
+        let model = self.model.read(cx);
+        if self.explanation_blocks.is_empty() && model.match_explanations.is_empty() {
+            return;
+        }
+
+        let blocks = model
+            .match_ranges
+            .iter()
+            .zip(&model.match_explanations)
+            .map(|(range, explanation)| {
+                let explanation = explanation.clone();
+                BlockProperties {
+                    position: range.start,
+                    height: 1,
+                    style: BlockStyle::Flex,
+                    render: Arc::new(move |cx: &mut BlockContext| {
+                        h_flex()
+                            .pl(cx.gutter_width)
+                            .child(
+                                Label::new(explanation.clone())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                            .into_any_element()
+                    }),
+                    disposition: BlockDisposition::Above,
+                }
+            })
+            .collect::<Vec<_>>();
+        let old_blocks = mem::take(&mut self.explanation_blocks);
+        self.results_editor.update(cx, |editor, cx| {
+            editor.remove_blocks(old_blocks, None, cx);
+            self.explanation_blocks = editor.insert_blocks(blocks, None, cx).into_iter().collect();
+        });
+    }
+
     fn update_match_index(&mut self, cx: &mut ViewContext<Self>) {
         let results_editor = self.results_editor.read(cx);
         let new_index = active_match_index(
@@ -1335,7 +1725,8 @@ impl ProjectSearchView {
     fn landing_text_minor(&self) -> SharedString {
         match self.current_mode {
             SearchMode::Text | SearchMode::Regex => "Include/exclude specific paths with the filter option. Matching exact word and/or casing is available too.".into(),
-            SearchMode::Semantic => "\nSimply explain the code you are looking to find. ex. 'prompt user for permissions to index their project'".into()
+            SearchMode::Semantic => "\nSimply explain the code you are looking to find. ex. 'prompt user for permissions to index their project'".into(),
+            SearchMode::Hybrid => "\nDescribe the code or name the symbols you are looking for. Results are ranked by matching words, meaning and symbol names.".into(),
         }
     }
     fn border_color_for(&self, panel: InputPanel, cx: &WindowContext) -> Hsla {
@@ -1459,6 +1850,7 @@ impl ProjectSearchBar {
         if let Some(search) = &self.active_project_search {
             search.update(cx, |this, cx| {
                 this.replace_enabled = !this.replace_enabled;
+                this.update_match_highlights(cx);
                 let editor_to_focus = if !this.replace_enabled {
                     this.query_editor.focus_handle(cx)
                 } else {
@@ -1470,6 +1862,29 @@ impl ProjectSearchBar {
         }
     }
 
+    fn toggle_preserve_case(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(search) = &self.active_project_search {
+            search.update(cx, |this, cx| {
+                this.toggle_search_option(SearchOptions::PRESERVE_CASE, cx);
+                this.update_match_highlights(cx);
+                cx.notify();
+            });
+            cx.notify();
+        }
+    }
+
+    fn toggle_match_exclusion(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(search) = &self.active_project_search {
+            search.update(cx, |this, cx| this.toggle_match_exclusion(cx));
+        }
+    }
+
+    fn toggle_file_exclusion(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(search) = &self.active_project_search {
+            search.update(cx, |this, cx| this.toggle_file_exclusion(cx));
+        }
+    }
+
     fn toggle_filters(&mut self, cx: &mut ViewContext<Self>) -> bool {
         if let Some(search_view) = self.active_project_search.as_ref() {
             search_view.update(cx, |search_view, cx| {
@@ -1697,7 +2112,7 @@ impl Render for ProjectSearchBar {
                                         .unwrap_or_default(),
                                 ),
                         )
-                        .when(search.current_mode != SearchMode::Semantic, |this| {
+                        .when(!search.current_mode.uses_semantic_index(), |this| {
                             this.child(
                                 IconButton::new(
                                     "project-search-case-sensitive",
@@ -1795,6 +2210,23 @@ impl Render for ProjectSearchBar {
                                             cx,
                                         )
                                     })
+                                    .middle(),
+                            )
+                            .child(
+                                ToggleButton::new("project-search-hybrid-button", "Hybrid")
+                                    .style(ButtonStyle::Filled)
+                                    .size(ButtonSize::Large)
+                                    .selected(search.current_mode == SearchMode::Hybrid)
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.activate_search_mode(SearchMode::Hybrid, cx)
+                                    }))
+                                    .tooltip(|cx| {
+                                        Tooltip::for_action(
+                                            "Toggle hybrid search",
+                                            &ActivateHybridMode,
+                                            cx,
+                                        )
+                                    })
                                     .last(),
                             )
                         }),
@@ -1819,6 +2251,10 @@ impl Render for ProjectSearchBar {
                 .rounded_lg()
                 .child(Icon::new(IconName::Replace).size(ui::IconSize::Small))
                 .child(self.render_text_input(&search.replacement_editor, cx))
+                .child(SearchOptions::PRESERVE_CASE.as_button(
+                    search.search_options.contains(SearchOptions::PRESERVE_CASE),
+                    cx.listener(|this, _, cx| this.toggle_preserve_case(cx)),
+                ))
         } else {
             // Fill out the space if we don't have a replacement editor.
             h_flex().flex_1()
@@ -1847,6 +2283,30 @@ impl Render for ProjectSearchBar {
                         }))
                         .tooltip(|cx| Tooltip::for_action("Replace all matches", &ReplaceAll, cx)),
                 )
+                .child(
+                    IconButton::new("project-search-exclude-match", IconName::XCircle)
+                        .disabled(search.active_match_index.is_none())
+                        .on_click(cx.listener(|this, _, cx| this.toggle_match_exclusion(cx)))
+                        .tooltip(|cx| {
+                            Tooltip::for_action(
+                                "Exclude match from replacement",
+                                &ToggleMatchExclusion,
+                                cx,
+                            )
+                        }),
+                )
+                .child(
+                    IconButton::new("project-search-exclude-file", IconName::File)
+                        .disabled(search.active_match_index.is_none())
+                        .on_click(cx.listener(|this, _, cx| this.toggle_file_exclusion(cx)))
+                        .tooltip(|cx| {
+                            Tooltip::for_action(
+                                "Exclude file from replacement",
+                                &ToggleFileExclusion,
+                                cx,
+                            )
+                        }),
+                )
             })
             .when_some(search.active_match_index, |mut this, index| {
                 let index = index + 1;
@@ -1855,6 +2315,12 @@ impl Render for ProjectSearchBar {
                     debug_assert!(match_quantity >= index);
                     this = this.child(Label::new(format!("{index}/{match_quantity}")))
                 }
+                let excluded_quantity = search.excluded_matches.len();
+                if search.replace_enabled && excluded_quantity > 0 {
+                    this = this.child(
+                        Label::new(format!("({excluded_quantity} excluded)")).color(Color::Muted),
+                    )
+                }
                 this
             })
             .child(
@@ -1901,6 +2367,9 @@ impl Render for ProjectSearchBar {
             .on_action(cx.listener(|this, _: &ActivateSemanticMode, cx| {
                 this.activate_search_mode(SearchMode::Semantic, cx)
             }))
+            .on_action(cx.listener(|this, _: &ActivateHybridMode, cx| {
+                this.activate_search_mode(SearchMode::Hybrid, cx)
+            }))
             .capture_action(cx.listener(|this, action, cx| {
                 this.tab(action, cx);
                 cx.stop_propagation();
@@ -1913,7 +2382,7 @@ impl Render for ProjectSearchBar {
             .on_action(cx.listener(|this, action, cx| {
                 this.cycle_mode(action, cx);
             }))
-            .when(search.current_mode != SearchMode::Semantic, |this| {
+            .when(!search.current_mode.uses_semantic_index(), |this| {
                 this.on_action(cx.listener(|this, action, cx| {
                     this.toggle_replace(action, cx);
                 }))
@@ -1937,6 +2406,15 @@ impl Render for ProjectSearchBar {
                         })
                     }
                 }))
+                .on_action(cx.listener(|this, _: &TogglePreserveCase, cx| {
+                    this.toggle_preserve_case(cx);
+                }))
+                .on_action(cx.listener(|this, _: &ToggleMatchExclusion, cx| {
+                    this.toggle_match_exclusion(cx);
+                }))
+                .on_action(cx.listener(|this, _: &ToggleFileExclusion, cx| {
+                    this.toggle_file_exclusion(cx);
+                }))
                 .when(search.filters_enabled, |this| {
                     this.on_action(cx.listener(|this, _: &ToggleIncludeIgnored, cx| {
                         this.toggle_search_option(SearchOptions::INCLUDE_IGNORED, cx);
@@ -1970,7 +2448,7 @@ impl Render for ProjectSearchBar {
                                 .border_color(search.border_color_for(InputPanel::Include, cx))
                                 .rounded_lg()
                                 .child(self.render_text_input(&search.included_files_editor, cx))
-                                .when(search.current_mode != SearchMode::Semantic, |this| {
+                                .when(!search.current_mode.uses_semantic_index(), |this| {
                                     this.child(
                                         SearchOptions::INCLUDE_IGNORED.as_button(
                                             search
@@ -2015,7 +2493,7 @@ impl ToolbarItemView for ProjectSearchBar {
         self.active_project_search = None;
         if let Some(search) = active_pane_item.and_then(|i| i.downcast::<ProjectSearchView>()) {
             search.update(cx, |search, cx| {
-                if search.current_mode == SearchMode::Semantic {
+                if search.current_mode.uses_semantic_index() {
                     search.index_project(cx);
                 }
             });
@@ -2234,6 +2712,147 @@ pub mod tests {
             .unwrap();
     }
 
+    #[gpui::test]
+    async fn test_project_search_replace_all(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+                "three.rs": "const THREE: usize = one::ONE + two::TWO;",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let search = cx.new_model(|cx| ProjectSearch::new(project, cx));
+        let search_view = cx.add_window(|cx| ProjectSearchView::new(search.clone(), cx, None));
+
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view
+                    .query_editor
+                    .update(cx, |query_editor, cx| query_editor.set_text("one", cx));
+                search_view
+                    .replacement_editor
+                    .update(cx, |replacement_editor, cx| {
+                        replacement_editor.set_text("uno", cx)
+                    });
+                search_view.replace_enabled = true;
+                search_view.toggle_search_option(SearchOptions::PRESERVE_CASE, cx);
+                search_view.search(cx);
+            })
+            .unwrap();
+        cx.background_executor.run_until_parked();
+
+        let original_texts = [
+            "const ONE: usize = 1;",
+            "const THREE: usize = one::ONE + two::TWO;",
+            "const TWO: usize = one::ONE + one::ONE;",
+        ];
+        let excluded_text = search_view
+            .update(cx, |search_view, cx| {
+                assert_eq!(search_view.active_match_index, Some(0));
+                assert_eq!(buffer_texts(search_view, cx), original_texts);
+                search_view.toggle_file_exclusion(cx);
+
+                let excerpts = search_view.model.read(cx).excerpts.read(cx);
+                let excluded_buffer_id = search_view.model.read(cx).match_ranges[0]
+                    .start
+                    .buffer_id
+                    .unwrap();
+                excerpts.buffer(excluded_buffer_id).unwrap().read(cx).text()
+            })
+            .unwrap();
+
+        let mut expected_texts = original_texts
+            .iter()
+            .map(|text| {
+                if *text == excluded_text {
+                    text.to_string()
+                } else {
+                    text.replace("one", "uno").replace("ONE", "UNO")
+                }
+            })
+            .collect::<Vec<_>>();
+        expected_texts.sort();
+        search_view
+            .update(cx, |search_view, cx| {
+                search_view.replace_all(&ReplaceAll, cx);
+                assert_eq!(buffer_texts(search_view, cx), expected_texts);
+
+                search_view
+                    .results_editor
+                    .update(cx, |editor, cx| editor.undo(&editor::actions::Undo, cx));
+                assert_eq!(
+                    buffer_texts(search_view, cx),
+                    original_texts,
+                    "a single undo should revert the replacements in all buffers"
+                );
+            })
+            .unwrap();
+
+        search_view
+            .update(cx, |search_view, cx| {
+                assert!(
+                    search_view.replaced_matches.is_empty(),
+                    "undone replacements should be pending again"
+                );
+                search_view.replace_all(&ReplaceAll, cx);
+                assert_eq!(buffer_texts(search_view, cx), expected_texts);
+
+                search_view
+                    .results_editor
+                    .update(cx, |editor, cx| editor.undo(&editor::actions::Undo, cx));
+                search_view
+                    .replacement_editor
+                    .update(cx, |replacement_editor, cx| {
+                        replacement_editor.set_text("one", cx)
+                    });
+            })
+            .unwrap();
+
+        search_view
+            .update(cx, |search_view, cx| {
+                assert!(search_view.replaced_matches.is_empty());
+                search_view.replace_all(&ReplaceAll, cx);
+                assert_eq!(buffer_texts(search_view, cx), original_texts);
+            })
+            .unwrap();
+        search_view
+            .update(cx, |search_view, cx| {
+                assert!(
+                    !search_view.replaced_matches.is_empty(),
+                    "replacements that kept the original text should stay replaced"
+                );
+                search_view
+                    .results_editor
+                    .update(cx, |editor, cx| editor.undo(&editor::actions::Undo, cx));
+            })
+            .unwrap();
+        search_view
+            .update(cx, |search_view, _| {
+                assert!(search_view.replaced_matches.is_empty());
+            })
+            .unwrap();
+    }
+
+    fn buffer_texts(search_view: &ProjectSearchView, cx: &AppContext) -> Vec<String> {
+        let mut texts = search_view
+            .model
+            .read(cx)
+            .excerpts
+            .read(cx)
+            .all_buffers()
+            .into_iter()
+            .map(|buffer| buffer.read(cx).text())
+            .collect::<Vec<_>>();
+        texts.sort();
+        texts
+    }
+
     #[gpui::test]
     async fn test_deploy_project_search_focus(cx: &mut TestAppContext) {
         init_test(cx);
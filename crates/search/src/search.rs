@@ -26,6 +26,7 @@ actions!(
         ToggleWholeWord,
         ToggleCaseSensitive,
         ToggleIncludeIgnored,
+        TogglePreserveCase,
         ToggleReplace,
         SelectNextMatch,
         SelectPrevMatch,
@@ -34,6 +35,7 @@ actions!(
         PreviousHistoryQuery,
         ActivateTextMode,
         ActivateSemanticMode,
+        ActivateHybridMode,
         ActivateRegexMode,
         ReplaceAll,
         ReplaceNext,
@@ -43,10 +45,11 @@ actions!(
 bitflags! {
     #[derive(Default)]
     pub struct SearchOptions: u8 {
-        const NONE = 0b000;
-        const WHOLE_WORD = 0b001;
-        const CASE_SENSITIVE = 0b010;
-        const INCLUDE_IGNORED = 0b100;
+        const NONE = 0b0000;
+        const WHOLE_WORD = 0b0001;
+        const CASE_SENSITIVE = 0b0010;
+        const INCLUDE_IGNORED = 0b0100;
+        const PRESERVE_CASE = 0b1000;
     }
 }
 
@@ -56,6 +59,7 @@ impl SearchOptions {
             SearchOptions::WHOLE_WORD => "Match Whole Word",
             SearchOptions::CASE_SENSITIVE => "Match Case",
             SearchOptions::INCLUDE_IGNORED => "Include ignored",
+            SearchOptions::PRESERVE_CASE => "Preserve Case",
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -65,6 +69,7 @@ impl SearchOptions {
             SearchOptions::WHOLE_WORD => ui::IconName::WholeWord,
             SearchOptions::CASE_SENSITIVE => ui::IconName::CaseSensitive,
             SearchOptions::INCLUDE_IGNORED => ui::IconName::FileGit,
+            SearchOptions::PRESERVE_CASE => ui::IconName::PreserveCase,
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -74,6 +79,7 @@ impl SearchOptions {
             SearchOptions::WHOLE_WORD => Box::new(ToggleWholeWord),
             SearchOptions::CASE_SENSITIVE => Box::new(ToggleCaseSensitive),
             SearchOptions::INCLUDE_IGNORED => Box::new(ToggleIncludeIgnored),
+            SearchOptions::PRESERVE_CASE => Box::new(TogglePreserveCase),
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -87,6 +93,7 @@ impl SearchOptions {
         options.set(SearchOptions::WHOLE_WORD, query.whole_word());
         options.set(SearchOptions::CASE_SENSITIVE, query.case_sensitive());
         options.set(SearchOptions::INCLUDE_IGNORED, query.include_ignored());
+        options.set(SearchOptions::PRESERVE_CASE, query.preserve_case());
         options
     }
 
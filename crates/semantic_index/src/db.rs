@@ -1,5 +1,7 @@
 use crate::{
+    hybrid_search::{bm25_idf, bm25_term_score, term_frequencies, LexicalScore},
     parsing::{Span, SpanDigest},
+    vector_index::SpanChange,
     SEMANTIC_INDEX_VERSION,
 };
 use ai::embedding::Embedding;
@@ -136,6 +138,8 @@ impl VectorDatabase {
             // `documents` without recreating it if it exists.
             db.execute("DROP TABLE IF EXISTS documents", [])
                 .context("failed to drop 'documents' table")?;
+            db.execute("DROP TABLE IF EXISTS span_terms", [])
+                .context("failed to drop 'span_terms' table")?;
             db.execute("DROP TABLE IF EXISTS spans", [])
                 .context("failed to drop 'spans' table")?;
             db.execute("DROP TABLE IF EXISTS files", [])
@@ -194,6 +198,8 @@ impl VectorDatabase {
                     name VARCHAR NOT NULL,
                     embedding BLOB NOT NULL,
                     digest BLOB NOT NULL,
+                    term_count INTEGER NOT NULL,
+                    symbols VARCHAR NOT NULL,
                     FOREIGN KEY(file_id) REFERENCES files(id) ON DELETE CASCADE
                 )",
                 [],
@@ -203,6 +209,18 @@ impl VectorDatabase {
                 [],
             )?;
 
+            db.execute(
+                "CREATE TABLE span_terms (
+                    span_id INTEGER NOT NULL,
+                    term VARCHAR NOT NULL,
+                    count INTEGER NOT NULL,
+                    FOREIGN KEY(span_id) REFERENCES spans(id) ON DELETE CASCADE
+                )",
+                [],
+            )?;
+            db.execute("CREATE INDEX span_terms_term ON span_terms (term)", [])?;
+            db.execute("CREATE INDEX span_terms_span_id ON span_terms (span_id)", [])?;
+
             log::trace!("vector database initialized with updated schema.");
             Ok(())
         })
@@ -212,13 +230,14 @@ impl VectorDatabase {
         &self,
         worktree_id: i64,
         delete_path: Arc<Path>,
-    ) -> impl Future<Output = Result<()>> {
+    ) -> impl Future<Output = Result<Vec<SpanChange>>> {
         self.transact(move |db| {
+            let changes = delete_spans_for_file(db, worktree_id, &delete_path)?;
             db.execute(
                 "DELETE FROM files WHERE worktree_id = ?1 AND relative_path = ?2",
                 params![worktree_id, delete_path.to_str()],
             )?;
-            Ok(())
+            Ok(changes)
         })
     }
 
@@ -228,8 +247,10 @@ impl VectorDatabase {
         path: Arc<Path>,
         mtime: SystemTime,
         spans: Vec<Span>,
-    ) -> impl Future<Output = Result<()>> {
+    ) -> impl Future<Output = Result<Vec<SpanChange>>> {
         self.transact(move |db| {
+            let mut changes = delete_spans_for_file(db, worktree_id, &path)?;
+
             // Return the existing ID, if both the file and mtime match
             let mtime = Timestamp::from(mtime);
 
@@ -247,23 +268,79 @@ impl VectorDatabase {
             let mut query = db.prepare(
                 "
                 INSERT INTO spans
-                (file_id, start_byte, end_byte, name, embedding, digest)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                (file_id, start_byte, end_byte, name, embedding, digest, term_count, symbols)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
                 ",
             )?;
+            let mut term_query =
+                db.prepare("INSERT INTO span_terms (span_id, term, count) VALUES (?1, ?2, ?3)")?;
 
             for span in spans {
+                let (terms, term_count) = term_frequencies(&span.content);
                 query.execute(params![
                     file_id,
                     span.range.start.to_string(),
                     span.range.end.to_string(),
                     span.name,
                     span.embedding,
-                    span.digest
+                    span.digest,
+                    term_count,
+                    span.symbols.join("\n"),
                 ])?;
+                let span_id = db.last_insert_rowid();
+                for (term, count) in terms {
+                    term_query.execute(params![span_id, term, count])?;
+                }
+                if let Some(embedding) = span.embedding {
+                    changes.push(SpanChange::Insert {
+                        span_id,
+                        file_id,
+                        embedding: embedding.0,
+                    });
+                }
             }
 
-            Ok(())
+            Ok(changes)
+        })
+    }
+
+    /// Returns the number of spans belonging to indexed files, and the largest
+    /// of their ids.
+    pub fn span_count_and_max_id(&self) -> impl Future<Output = Result<(usize, Option<i64>)>> {
+        self.transact(|db| {
+            let mut query = db.prepare(
+                "
+                SELECT COUNT(spans.id), MAX(spans.id)
+                FROM spans
+                JOIN files ON files.id = spans.file_id
+                ",
+            )?;
+            Ok(query.query_row([], |row| Ok((row.get::<_, usize>(0)?, row.get(1)?)))?)
+        })
+    }
+
+    /// Returns the ids, file ids and embeddings of up to `limit` spans whose
+    /// ids are greater than `after_id`, ordered by id.
+    pub fn spans_after(
+        &self,
+        after_id: i64,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<(i64, i64, Embedding)>>> {
+        self.transact(move |db| {
+            let mut query = db.prepare(
+                "
+                SELECT spans.id, spans.file_id, spans.embedding
+                FROM spans
+                JOIN files ON files.id = spans.file_id
+                WHERE spans.id > ?1
+                ORDER BY spans.id
+                LIMIT ?2
+                ",
+            )?;
+            let rows = query.query_map(params![after_id, limit], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get::<_, Embedding>(2)?))
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
         })
     }
 
@@ -504,6 +581,94 @@ impl VectorDatabase {
         })
     }
 
+    /// Scores the spans in the given files that contain any of the terms with
+    /// BM25, returning the `limit` best ones.
+    pub fn lexical_search(
+        &self,
+        terms: Vec<String>,
+        limit: usize,
+        file_ids: &[i64],
+    ) -> impl Future<Output = Result<Vec<(i64, LexicalScore)>>> {
+        let file_ids = file_ids.to_vec();
+        self.transact(move |db| {
+            let file_ids = ids_to_sql(&file_ids);
+            let (span_count, total_term_count) = db.query_row(
+                "SELECT COUNT(id), TOTAL(term_count) FROM spans WHERE file_id IN rarray(?1)",
+                params![file_ids.clone()],
+                |row| Ok((row.get::<_, usize>(0)?, row.get::<_, f64>(1)?)),
+            )?;
+            if span_count == 0 {
+                return Ok(Vec::new());
+            }
+            let average_term_count = (total_term_count / span_count as f64) as f32;
+
+            let mut postings_query = db.prepare(
+                "
+                SELECT span_terms.span_id, span_terms.count, spans.term_count
+                FROM span_terms
+                JOIN spans ON spans.id = span_terms.span_id
+                WHERE span_terms.term = ?1 AND spans.file_id IN rarray(?2)
+                ",
+            )?;
+            let mut scores = HashMap::<i64, LexicalScore>::default();
+            for term in terms {
+                let postings = postings_query
+                    .query_map(params![term, file_ids.clone()], |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, usize>(1)?,
+                            row.get::<_, usize>(2)?,
+                        ))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                if postings.is_empty() {
+                    continue;
+                }
+
+                let idf = bm25_idf(span_count, postings.len());
+                for (span_id, term_frequency, term_count) in postings {
+                    let score = scores.entry(span_id).or_default();
+                    score.score +=
+                        bm25_term_score(term_frequency, term_count, average_term_count, idf);
+                    score.matched_terms.push(term.clone());
+                }
+            }
+
+            let mut results = scores.into_iter().collect::<Vec<_>>();
+            results.sort_by(|(a_id, a), (b_id, b)| {
+                b.score.total_cmp(&a.score).then_with(|| a_id.cmp(b_id))
+            });
+            results.truncate(limit);
+            Ok(results)
+        })
+    }
+
+    /// Returns the names of the symbols defined within each of the given spans.
+    pub fn symbols_for_spans(
+        &self,
+        ids: &[i64],
+    ) -> impl Future<Output = Result<HashMap<i64, Vec<String>>>> {
+        let ids = ids.to_vec();
+        self.transact(move |db| {
+            let mut query = db.prepare("SELECT id, symbols FROM spans WHERE id IN rarray(?1)")?;
+            let rows = query.query_map(params![ids_to_sql(&ids)], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?;
+
+            let mut symbols_by_span_id = HashMap::default();
+            for row in rows {
+                let (id, symbols) = row?;
+                let symbols = symbols
+                    .lines()
+                    .filter(|symbol| !symbol.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                symbols_by_span_id.insert(id, symbols);
+            }
+            Ok(symbols_by_span_id)
+        })
+    }
+
     pub fn retrieve_included_file_ids(
         &self,
         worktree_ids: &[i64],
@@ -593,6 +758,33 @@ impl VectorDatabase {
     }
 }
 
+fn delete_spans_for_file(
+    db: &rusqlite::Transaction,
+    worktree_id: i64,
+    path: &Path,
+) -> Result<Vec<SpanChange>> {
+    let mut query = db.prepare(
+        "
+        SELECT spans.id
+        FROM spans
+        JOIN files ON files.id = spans.file_id
+        WHERE files.worktree_id = ?1 AND files.relative_path = ?2
+        ",
+    )?;
+    let span_ids = query
+        .query_map(params![worktree_id, path.to_str()], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+    db.execute(
+        "DELETE FROM span_terms WHERE span_id IN rarray(?1)",
+        params![ids_to_sql(&span_ids)],
+    )?;
+    db.execute(
+        "DELETE FROM spans WHERE id IN rarray(?1)",
+        params![ids_to_sql(&span_ids)],
+    )?;
+    Ok(span_ids.into_iter().map(SpanChange::Remove).collect())
+}
+
 fn ids_to_sql(ids: &[i64]) -> Rc<Vec<rusqlite::types::Value>> {
     Rc::new(
         ids.iter()
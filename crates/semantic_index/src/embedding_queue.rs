@@ -2,8 +2,14 @@ use crate::{parsing::Span, JobHandle};
 use ai::embedding::EmbeddingProvider;
 use gpui::BackgroundExecutor;
 use parking_lot::Mutex;
-use smol::channel;
-use std::{mem, ops::Range, path::Path, sync::Arc, time::SystemTime};
+use smol::{channel, lock::Semaphore};
+use std::{
+    mem,
+    ops::Range,
+    path::Path,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
 
 #[derive(Clone)]
 pub struct FileToEmbed {
@@ -39,6 +45,10 @@ pub struct EmbeddingQueue {
     pending_batch: Vec<FileFragmentToEmbed>,
     executor: BackgroundExecutor,
     pending_batch_token_count: usize,
+    pending_batch_span_count: usize,
+    /// Bounds the number of batches being embedded at once, so that flushing
+    /// many batches doesn't flood the provider with requests.
+    batch_limiter: Arc<Semaphore>,
     finished_files_tx: channel::Sender<FileToEmbed>,
     finished_files_rx: channel::Receiver<FileToEmbed>,
 }
@@ -55,11 +65,16 @@ impl EmbeddingQueue {
         executor: BackgroundExecutor,
     ) -> Self {
         let (finished_files_tx, finished_files_rx) = channel::unbounded();
+        let batch_limiter = Arc::new(Semaphore::new(
+            embedding_provider.max_concurrent_batches().max(1),
+        ));
         Self {
             embedding_provider,
             executor,
             pending_batch: Vec::new(),
             pending_batch_token_count: 0,
+            pending_batch_span_count: 0,
+            batch_limiter,
             finished_files_tx,
             finished_files_rx,
         }
@@ -80,14 +95,17 @@ impl EmbeddingQueue {
 
         let mut fragment_range = &mut self.pending_batch.last_mut().unwrap().span_range;
         for (ix, span) in file.lock().spans.iter().enumerate() {
-            let span_token_count = if span.embedding.is_none() {
-                span.token_count
+            let (span_token_count, span_count) = if span.embedding.is_none() {
+                (span.token_count, 1)
             } else {
-                0
+                (0, 0)
             };
 
             let next_token_count = self.pending_batch_token_count + span_token_count;
-            if next_token_count > self.embedding_provider.max_tokens_per_batch() {
+            let next_span_count = self.pending_batch_span_count + span_count;
+            if next_token_count > self.embedding_provider.max_tokens_per_batch()
+                || next_span_count > self.embedding_provider.max_spans_per_batch()
+            {
                 let range_end = fragment_range.end;
                 self.flush();
                 self.pending_batch.push(FileFragmentToEmbed {
@@ -99,18 +117,22 @@ impl EmbeddingQueue {
 
             fragment_range.end = ix + 1;
             self.pending_batch_token_count += span_token_count;
+            self.pending_batch_span_count += span_count;
         }
     }
 
     pub fn flush(&mut self) {
         let batch = mem::take(&mut self.pending_batch);
         self.pending_batch_token_count = 0;
+        self.pending_batch_span_count = 0;
         if batch.is_empty() {
             return;
         }
 
         let finished_files_tx = self.finished_files_tx.clone();
         let embedding_provider = self.embedding_provider.clone();
+        let batch_limiter = self.batch_limiter.clone();
+        let executor = self.executor.clone();
 
         self.executor
             .spawn(async move {
@@ -135,6 +157,17 @@ impl EmbeddingQueue {
                     return;
                 };
 
+                let _guard = batch_limiter.acquire().await;
+                // Wait out any rate limit the provider has reported before
+                // sending it another request.
+                while let Some(expiration) = embedding_provider.rate_limit_expiration() {
+                    let now = Instant::now();
+                    if expiration <= now {
+                        break;
+                    }
+                    executor.timer(expiration - now).await;
+                }
+
                 match embedding_provider.embed_batch(spans).await {
                     Ok(embeddings) => {
                         let mut embeddings = embeddings.into_iter();
@@ -0,0 +1,524 @@
+//! Hybrid search, which ranks spans by combining lexical relevance (BM25 over
+//! the words in each span), semantic relevance (the similarity of embeddings)
+//! and matches against the names of the symbols they define. Each signal
+//! ranks its own candidates, and the rankings are merged with reciprocal rank
+//! fusion (https://plg.uwaterloo.ca/~gvcormac/cormacksigir09-rrf.pdf), which
+//! doesn't require their scores to be comparable.
+
+use crate::{
+    db::VectorDatabase, open_span_buffers, search_span_embeddings, SemanticIndex, WorktreeState,
+};
+use anyhow::{Context as _, Result};
+use collections::{HashMap, HashSet};
+use gpui::{Model, ModelContext, Task};
+use language::{Anchor, Buffer, ToOffset};
+use project::{Project, WorktreeId};
+use std::{fmt, ops::Range, path::PathBuf};
+use util::{paths::PathMatcher, ResultExt};
+
+/// How many candidates the lexical and semantic rankings each contribute.
+const CANDIDATES_PER_RANKING: usize = 100;
+/// Dampens the advantage of the top ranks, so that a span ranked highly by
+/// several signals beats one ranked first by a single signal.
+const RRF_K: f32 = 60.;
+/// How quickly repeated occurrences of a term stop adding to a span's score.
+const BM25_K1: f32 = 1.2;
+/// How much a span's score is normalized by its length.
+const BM25_B: f32 = 0.75;
+const MIN_TERM_LEN: usize = 2;
+const MAX_TERM_LEN: usize = 64;
+
+#[derive(Clone)]
+pub struct HybridSearchResult {
+    pub buffer: Model<Buffer>,
+    pub range: Range<Anchor>,
+    pub score: f32,
+    pub explanation: ScoreExplanation,
+}
+
+/// A candidate's position in one of the rankings, and the score it was
+/// ranked by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankedScore {
+    /// The 1-based position in the ranking.
+    pub rank: usize,
+    pub score: f32,
+}
+
+/// Why a result was ranked where it was.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScoreExplanation {
+    /// The ranking by the cosine similarity of the embeddings.
+    pub semantic: Option<RankedScore>,
+    /// The ranking by BM25.
+    pub lexical: Option<RankedScore>,
+    /// The ranking by the fraction of the query's terms that appear in the
+    /// name of a symbol defined in the result.
+    pub symbol: Option<RankedScore>,
+    /// The query terms that occur in the result.
+    pub matched_terms: Vec<String>,
+    /// The best matching symbol defined in the result.
+    pub matched_symbol: Option<String>,
+}
+
+impl fmt::Display for ScoreExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(semantic) = self.semantic {
+            parts.push(format!(
+                "semantic #{} ({:.3})",
+                semantic.rank, semantic.score
+            ));
+        }
+        if let Some(lexical) = self.lexical {
+            parts.push(format!(
+                "lexical #{} (bm25 {:.2}: {})",
+                lexical.rank,
+                lexical.score,
+                self.matched_terms.join(", ")
+            ));
+        }
+        if let Some((symbol, name)) = self.symbol.zip(self.matched_symbol.as_ref()) {
+            parts.push(format!(
+                "symbol #{} (`{}`, {:.0}%)",
+                symbol.rank,
+                name,
+                symbol.score * 100.
+            ));
+        }
+        write!(f, "{}", parts.join(" · "))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct LexicalScore {
+    pub score: f32,
+    pub matched_terms: Vec<String>,
+}
+
+/// The signals gathered for a single candidate.
+#[derive(Clone, Debug, Default)]
+struct Candidate {
+    similarity: Option<f32>,
+    lexical: Option<LexicalScore>,
+    symbol: Option<(String, f32)>,
+}
+
+enum CandidateLocation {
+    /// A span stored in the database.
+    Span {
+        worktree_db_id: i64,
+        path: PathBuf,
+        range: Range<usize>,
+    },
+    /// A span in a buffer with unsaved changes, which was parsed and embedded
+    /// on the fly.
+    Buffer {
+        buffer: Model<Buffer>,
+        range: Range<Anchor>,
+    },
+}
+
+impl SemanticIndex {
+    /// Searches the project by combining lexical, semantic and symbol-name
+    /// relevance, returning up to `limit` results ordered by their fused score.
+    ///
+    /// Buffers with unsaved changes are only ranked semantically and by their
+    /// symbols, as their term statistics aren't stored.
+    pub fn hybrid_search_project(
+        &mut self,
+        project: Model<Project>,
+        query: String,
+        limit: usize,
+        includes: Vec<PathMatcher>,
+        excludes: Vec<PathMatcher>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HybridSearchResult>>> {
+        if query.trim().is_empty() {
+            return Task::ready(Ok(Vec::new()));
+        }
+
+        let mut query_terms = tokenize(&query);
+        let mut seen_terms = HashSet::default();
+        query_terms.retain(|term| seen_terms.insert(term.clone()));
+
+        let index = self.index_project(project.clone(), cx);
+        let embedding_provider = self.embedding_provider.clone();
+        let fs = self.fs.clone();
+        let db_path = self.db.path().clone();
+        let vector_index = self.vector_index.clone();
+        cx.spawn(|this, mut cx| async move {
+            index.await?;
+
+            let query_embedding = embedding_provider
+                .embed_batch(vec![query])
+                .await?
+                .pop()
+                .context("could not embed query")?;
+            let modified_buffer_results = this
+                .update(&mut cx, |this, cx| {
+                    this.search_modified_buffers(
+                        &project,
+                        query_embedding.clone(),
+                        CANDIDATES_PER_RANKING,
+                        &includes,
+                        &excludes,
+                        cx,
+                    )
+                })?
+                .await
+                .log_err()
+                .unwrap_or_default();
+
+            let database = VectorDatabase::new(
+                fs.clone(),
+                db_path.clone(),
+                cx.background_executor().clone(),
+            )
+            .await?;
+            let worktree_db_ids =
+                this.read_with(&cx, |this, _| this.registered_worktree_db_ids(&project))??;
+            let file_ids = database
+                .retrieve_included_file_ids(&worktree_db_ids, &includes, &excludes)
+                .await?;
+            let semantic_matches = search_span_embeddings(
+                vector_index,
+                fs,
+                db_path,
+                query_embedding,
+                CANDIDATES_PER_RANKING,
+                file_ids.clone(),
+                cx.background_executor().clone(),
+            )
+            .await;
+            let lexical_matches = database
+                .lexical_search(query_terms.clone(), CANDIDATES_PER_RANKING, &file_ids)
+                .await?;
+
+            let mut span_ids = Vec::new();
+            let mut candidates_by_span_id = HashMap::<i64, Candidate>::default();
+            for (span_id, similarity) in semantic_matches {
+                span_ids.push(span_id);
+                candidates_by_span_id.entry(span_id).or_default().similarity = Some(similarity.0);
+            }
+            for (span_id, score) in lexical_matches {
+                let candidate = candidates_by_span_id.entry(span_id).or_insert_with(|| {
+                    span_ids.push(span_id);
+                    Candidate::default()
+                });
+                candidate.lexical = Some(score);
+            }
+            let mut symbols_by_span_id = database.symbols_for_spans(&span_ids).await?;
+            let spans = database.spans_for_ids(&span_ids).await?;
+
+            // Spans stored for modified buffers are out of date, so those
+            // buffers are represented by the results of parsing them instead.
+            let modified_paths = this.read_with(&cx, |this, cx| {
+                let db_ids_by_worktree_id = this
+                    .projects
+                    .get(&project.downgrade())
+                    .map(|project_state| {
+                        project_state
+                            .worktrees
+                            .iter()
+                            .filter_map(|(worktree_id, worktree)| match worktree {
+                                WorktreeState::Registered(worktree) => {
+                                    Some((*worktree_id, worktree.db_id))
+                                }
+                                WorktreeState::Registering(_) => None,
+                            })
+                            .collect::<HashMap<_, _>>()
+                    })
+                    .unwrap_or_default();
+                modified_buffer_results
+                    .iter()
+                    .filter_map(|result| {
+                        let file = result.buffer.read(cx).file()?;
+                        let worktree_id = WorktreeId::from_usize(file.worktree_id());
+                        Some((
+                            *db_ids_by_worktree_id.get(&worktree_id)?,
+                            file.path().to_path_buf(),
+                        ))
+                    })
+                    .collect::<HashSet<_>>()
+            })?;
+
+            let mut candidates = Vec::new();
+            let mut locations = Vec::new();
+            for (span_id, (worktree_db_id, path, range)) in span_ids.into_iter().zip(spans) {
+                if modified_paths.contains(&(worktree_db_id, path.clone())) {
+                    continue;
+                }
+                let Some(mut candidate) = candidates_by_span_id.remove(&span_id) else {
+                    continue;
+                };
+                let symbols = symbols_by_span_id.remove(&span_id).unwrap_or_default();
+                candidate.symbol = best_symbol_match(&query_terms, symbols);
+                candidates.push(candidate);
+                locations.push(CandidateLocation::Span {
+                    worktree_db_id,
+                    path,
+                    range,
+                });
+            }
+            this.read_with(&cx, |_, cx| {
+                for result in modified_buffer_results {
+                    let snapshot = result.buffer.read(cx).snapshot();
+                    let result_range = result.range.start.to_offset(&snapshot)
+                        ..result.range.end.to_offset(&snapshot);
+                    let symbols = snapshot
+                        .outline_in_range(result_range.clone(), None)
+                        .map(|outline| {
+                            outline
+                                .items
+                                .into_iter()
+                                .filter(|item| {
+                                    result_range.start <= item.range.start.to_offset(&snapshot)
+                                        && item.range.end.to_offset(&snapshot) <= result_range.end
+                                })
+                                .map(|item| {
+                                    item.name_ranges
+                                        .iter()
+                                        .filter_map(|range| item.text.get(range.clone()))
+                                        .collect::<Vec<_>>()
+                                        .join(" ")
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    candidates.push(Candidate {
+                        similarity: Some(result.similarity.0),
+                        lexical: None,
+                        symbol: best_symbol_match(&query_terms, symbols),
+                    });
+                    locations.push(CandidateLocation::Buffer {
+                        buffer: result.buffer,
+                        range: result.range,
+                    });
+                }
+            })?;
+
+            let mut ranked = fuse(&candidates);
+            ranked.truncate(limit);
+
+            let mut locations = locations.into_iter().map(Some).collect::<Vec<_>>();
+            let mut span_locations = Vec::new();
+            for (candidate_ix, _, _) in &ranked {
+                if let Some(CandidateLocation::Span {
+                    worktree_db_id,
+                    path,
+                    range,
+                }) = &locations[*candidate_ix]
+                {
+                    span_locations.push((*worktree_db_id, path.clone(), range.clone()));
+                }
+            }
+            let mut span_buffers = open_span_buffers(&this, &project, span_locations, &mut cx)
+                .await?
+                .into_iter();
+
+            let mut results = Vec::new();
+            for (candidate_ix, score, explanation) in ranked {
+                let (buffer, range) = match locations[candidate_ix].take() {
+                    Some(CandidateLocation::Span { .. }) => {
+                        let Some(Some(buffer_and_range)) = span_buffers.next() else {
+                            continue;
+                        };
+                        buffer_and_range
+                    }
+                    Some(CandidateLocation::Buffer { buffer, range }) => (buffer, range),
+                    None => continue,
+                };
+                results.push(HybridSearchResult {
+                    buffer,
+                    range,
+                    score,
+                    explanation,
+                });
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// Ranks the candidates by each signal separately, then orders them by the sum
+/// of their reciprocal ranks. Returns the index of each candidate along with
+/// its fused score and an explanation, best first.
+fn fuse(candidates: &[Candidate]) -> Vec<(usize, f32, ScoreExplanation)> {
+    let mut explanations = vec![ScoreExplanation::default(); candidates.len()];
+
+    let rank = |score: &dyn Fn(&Candidate) -> Option<f32>| {
+        let mut ranking = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, candidate)| Some((ix, score(candidate)?)))
+            .collect::<Vec<_>>();
+        ranking.sort_by(|(a_ix, a), (b_ix, b)| b.total_cmp(a).then_with(|| a_ix.cmp(b_ix)));
+        ranking
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (ix, score))| {
+                (
+                    ix,
+                    RankedScore {
+                        rank: rank + 1,
+                        score,
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (ix, ranked) in rank(&|candidate: &Candidate| candidate.similarity) {
+        explanations[ix].semantic = Some(ranked);
+    }
+    for (ix, ranked) in
+        rank(&|candidate: &Candidate| candidate.lexical.as_ref().map(|lexical| lexical.score))
+    {
+        explanations[ix].lexical = Some(ranked);
+        explanations[ix].matched_terms = candidates[ix]
+            .lexical
+            .as_ref()
+            .map_or(Vec::new(), |lexical| lexical.matched_terms.clone());
+    }
+    for (ix, ranked) in
+        rank(&|candidate: &Candidate| candidate.symbol.as_ref().map(|(_, score)| *score))
+    {
+        explanations[ix].symbol = Some(ranked);
+        explanations[ix].matched_symbol =
+            candidates[ix].symbol.as_ref().map(|(name, _)| name.clone());
+    }
+
+    let mut fused = explanations
+        .into_iter()
+        .enumerate()
+        .map(|(ix, explanation)| {
+            let score = [
+                explanation.semantic,
+                explanation.lexical,
+                explanation.symbol,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|ranked| 1. / (RRF_K + ranked.rank as f32))
+            .sum::<f32>();
+            (ix, score, explanation)
+        })
+        .collect::<Vec<_>>();
+    fused.sort_by(|(a_ix, a, _), (b_ix, b, _)| b.total_cmp(a).then_with(|| a_ix.cmp(b_ix)));
+    fused
+}
+
+/// Returns the symbol whose name covers the largest fraction of the query's
+/// terms, along with that fraction.
+fn best_symbol_match(query_terms: &[String], symbols: Vec<String>) -> Option<(String, f32)> {
+    let mut best_match: Option<(String, f32)> = None;
+    for symbol in symbols {
+        let score = symbol_match_score(query_terms, &symbol);
+        if score > 0. && best_match.as_ref().map_or(true, |(_, best)| score > *best) {
+            best_match = Some((symbol, score));
+        }
+    }
+    best_match
+}
+
+pub(crate) fn symbol_match_score(query_terms: &[String], symbol: &str) -> f32 {
+    if query_terms.is_empty() {
+        return 0.;
+    }
+    let symbol_terms = tokenize(symbol).into_iter().collect::<HashSet<_>>();
+    let matched_terms = query_terms
+        .iter()
+        .filter(|term| symbol_terms.contains(*term))
+        .count();
+    matched_terms as f32 / query_terms.len() as f32
+}
+
+/// Splits text into lowercase terms. Identifiers yield themselves as a whole
+/// along with their snake_case and camelCase parts, so that `searchProject`
+/// matches queries for both `searchproject` and `search project`.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut push_term = |term: &str| {
+        if (MIN_TERM_LEN..=MAX_TERM_LEN).contains(&term.chars().count()) {
+            terms.push(term.to_lowercase());
+        }
+    };
+    for word in text.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        let word = word.trim_matches('_');
+        if word.is_empty() {
+            continue;
+        }
+        push_term(word);
+        let parts = identifier_parts(word);
+        if parts.len() > 1 {
+            for part in parts {
+                push_term(part);
+            }
+        }
+    }
+    terms
+}
+
+fn identifier_parts(word: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    for segment in word.split('_') {
+        let chars = segment.char_indices().collect::<Vec<_>>();
+        let mut start = 0;
+        for (i, &(offset, c)) in chars.iter().enumerate().skip(1) {
+            let prev = chars[i - 1].1;
+            let next_is_lowercase = chars
+                .get(i + 1)
+                .map_or(false, |(_, next)| next.is_lowercase());
+            // Split before an uppercase letter that follows a lowercase one,
+            // or that starts a word after an acronym, as in `HTTPServer`.
+            if c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next_is_lowercase))
+            {
+                parts.push(&segment[start..offset]);
+                start = offset;
+            }
+        }
+        parts.push(&segment[start..]);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Returns how many times each term occurs in the text, and the total number
+/// of terms.
+pub(crate) fn term_frequencies(text: &str) -> (HashMap<String, usize>, usize) {
+    let mut frequencies = HashMap::default();
+    let mut term_count = 0;
+    for term in tokenize(text) {
+        *frequencies.entry(term).or_insert(0) += 1;
+        term_count += 1;
+    }
+    (frequencies, term_count)
+}
+
+/// The inverse document frequency of a term that occurs in `document_frequency`
+/// of `span_count` spans. Rare terms weigh more.
+pub(crate) fn bm25_idf(span_count: usize, document_frequency: usize) -> f32 {
+    let span_count = span_count as f32;
+    let document_frequency = document_frequency as f32;
+    ((span_count - document_frequency + 0.5) / (document_frequency + 0.5) + 1.).ln()
+}
+
+pub(crate) fn bm25_term_score(
+    term_frequency: usize,
+    span_term_count: usize,
+    average_term_count: f32,
+    idf: f32,
+) -> f32 {
+    let term_frequency = term_frequency as f32;
+    let length_ratio = if average_term_count > 0. {
+        span_term_count as f32 / average_term_count
+    } else {
+        1.
+    };
+    idf * term_frequency * (BM25_K1 + 1.)
+        / (term_frequency + BM25_K1 * (1. - BM25_B + BM25_B * length_ratio))
+}
@@ -57,6 +57,9 @@ pub struct Span {
     pub embedding: Option<Embedding>,
     pub digest: SpanDigest,
     pub token_count: usize,
+    /// The names of the symbols defined within the span, according to the
+    /// language's outline queries.
+    pub symbols: Vec<String>,
 }
 
 const CODE_CONTEXT_TEMPLATE: &str =
@@ -126,6 +129,7 @@ impl CodeContextRetriever {
             name: language_name.to_string(),
             digest,
             token_count,
+            symbols: Vec::new(),
         }])
     }
 
@@ -157,6 +161,7 @@ impl CodeContextRetriever {
             name: "Markdown".to_string(),
             digest,
             token_count,
+            symbols: Vec::new(),
         }])
     }
 
@@ -164,7 +169,7 @@ impl CodeContextRetriever {
         &mut self,
         content: &str,
         grammar: &Arc<Grammar>,
-    ) -> Result<Vec<CodeContextMatch>> {
+    ) -> Result<(Vec<CodeContextMatch>, Vec<(Range<usize>, String)>)> {
         let embedding_config = grammar
             .embedding_config
             .as_ref()
@@ -213,7 +218,34 @@ impl CodeContextRetriever {
                 collapse_ranges: subtract_ranges(&collapse_ranges, &keep_ranges),
             });
         }
-        Ok(captures)
+
+        let mut symbols = Vec::new();
+        if let Some(outline_config) = grammar.outline_config.as_ref() {
+            for mat in
+                self.cursor
+                    .matches(&outline_config.query, tree.root_node(), content.as_bytes())
+            {
+                let mut item_range = None;
+                let mut name = String::new();
+                for capture in mat.captures {
+                    if capture.index == outline_config.item_capture_ix {
+                        item_range = Some(capture.node.byte_range());
+                    } else if capture.index == outline_config.name_capture_ix {
+                        if !name.is_empty() {
+                            name.push(' ');
+                        }
+                        name.push_str(content.get(capture.node.byte_range()).unwrap_or(""));
+                    }
+                }
+                if let Some(item_range) = item_range {
+                    if !name.is_empty() {
+                        symbols.push((item_range, name));
+                    }
+                }
+            }
+        }
+
+        Ok((captures, symbols))
     }
 
     pub fn parse_file_with_template(
@@ -260,7 +292,7 @@ impl CodeContextRetriever {
             .ok_or_else(|| anyhow!("no grammar for language"))?;
 
         // Iterate through query matches
-        let matches = self.get_matches_in_file(content, grammar)?;
+        let (matches, symbols) = self.get_matches_in_file(content, grammar)?;
 
         let language_scope = language.default_scope();
         let placeholder = language_scope.collapsed_placeholder();
@@ -343,6 +375,16 @@ impl CodeContextRetriever {
                 );
             }
 
+            let mut span_symbols = Vec::new();
+            for (symbol_range, symbol) in &symbols {
+                if item_range.start <= symbol_range.start
+                    && symbol_range.end <= item_range.end
+                    && !span_symbols.contains(symbol)
+                {
+                    span_symbols.push(symbol.clone());
+                }
+            }
+
             let sha1 = SpanDigest::from(span_content.as_str());
             spans.push(Span {
                 name,
@@ -351,6 +393,7 @@ impl CodeContextRetriever {
                 embedding: None,
                 digest: sha1,
                 token_count: 0,
+                symbols: span_symbols,
             })
         }
 
@@ -1,27 +1,33 @@
 mod db;
 mod embedding_queue;
+mod hybrid_search;
 mod parsing;
 pub mod semantic_index_settings;
+mod vector_index;
+
+pub use hybrid_search::{HybridSearchResult, RankedScore, ScoreExplanation};
 
 #[cfg(test)]
 mod semantic_index_tests;
 
-use crate::semantic_index_settings::SemanticIndexSettings;
+use crate::semantic_index_settings::{
+    EmbeddingProviderKind, EmbeddingProviderSettings, SemanticIndexSettings,
+};
 use ai::embedding::{Embedding, EmbeddingProvider};
-use ai::providers::open_ai::OpenAiEmbeddingProvider;
+use ai::providers::{ollama::OllamaEmbeddingProvider, open_ai::OpenAiEmbeddingProvider};
 use anyhow::{anyhow, Context as _, Result};
 use collections::{BTreeMap, HashMap, HashSet};
 use db::VectorDatabase;
 use embedding_queue::{EmbeddingQueue, FileToEmbed};
 use futures::{future, FutureExt, StreamExt};
 use gpui::{
-    AppContext, AsyncAppContext, BorrowWindow, Context, Global, Model, ModelContext, Task,
-    ViewContext, WeakModel,
+    AppContext, AsyncAppContext, BackgroundExecutor, BorrowWindow, Context, Global, Model,
+    ModelContext, Task, ViewContext, WeakModel,
 };
 use language::{Anchor, Bias, Buffer, Language, LanguageRegistry};
 use lazy_static::lazy_static;
 use ordered_float::OrderedFloat;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use parsing::{CodeContextRetriever, Span, SpanDigest, PARSEABLE_ENTIRE_FILE_TYPES};
 use postage::watch;
 use project::{Fs, PathChange, Project, ProjectEntryId, Worktree, WorktreeId};
@@ -40,11 +46,23 @@ use std::{
 };
 use util::paths::PathMatcher;
 use util::{http::HttpClient, paths::EMBEDDINGS_DIR, ResultExt};
+use vector_index::{VectorIndex, VectorIndexState};
 use workspace::Workspace;
 
-const SEMANTIC_INDEX_VERSION: usize = 11;
+const SEMANTIC_INDEX_VERSION: usize = 12;
 const BACKGROUND_INDEXING_DELAY: Duration = Duration::from_secs(5 * 60);
 const EMBEDDING_QUEUE_FLUSH_TIMEOUT: Duration = Duration::from_millis(250);
+/// The model that was used before the embedding provider was configurable,
+/// whose database keeps its original name.
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-ada-002";
+/// Below this many spans, scoring every span is fast enough and exact, so the
+/// vector index is only searched in larger projects.
+const MIN_SPANS_FOR_VECTOR_INDEX: usize = 20_000;
+/// How long the vector index must go unchanged before it's saved.
+const VECTOR_INDEX_SAVE_DELAY: Duration = Duration::from_secs(10);
+/// How many spans to read from the database at a time when rebuilding the
+/// vector index.
+const VECTOR_INDEX_REBUILD_BATCH_SIZE: usize = 10_000;
 
 lazy_static! {
     static ref OPENAI_API_KEY: Option<String> = env::var("OPENAI_API_KEY").ok();
@@ -58,9 +76,12 @@ pub fn init(
 ) {
     SemanticIndexSettings::register(cx);
 
+    let embedding_provider_settings = SemanticIndexSettings::get_global(cx)
+        .embedding_provider
+        .clone();
     let db_file_path = EMBEDDINGS_DIR
         .join(Path::new(ReleaseChannel::global(cx).dev_name()))
-        .join("embeddings_db");
+        .join(database_file_name(&embedding_provider_settings));
 
     cx.observe_new_views(
         |workspace: &mut Workspace, cx: &mut ViewContext<Workspace>| {
@@ -91,12 +112,16 @@ pub fn init(
     .detach();
 
     cx.spawn(move |cx| async move {
-        let embedding_provider =
-            OpenAiEmbeddingProvider::new(http_client, cx.background_executor().clone()).await;
+        let embedding_provider = build_embedding_provider(
+            embedding_provider_settings,
+            http_client,
+            cx.background_executor().clone(),
+        )
+        .await;
         let semantic_index = SemanticIndex::new(
             fs,
             db_file_path,
-            Arc::new(embedding_provider),
+            embedding_provider,
             language_registry,
             cx.clone(),
         )
@@ -109,6 +134,61 @@ pub fn init(
     .detach();
 }
 
+/// Embeddings from different models can't be compared, so each model is
+/// indexed into its own database.
+fn database_file_name(settings: &EmbeddingProviderSettings) -> String {
+    if settings.kind == EmbeddingProviderKind::OpenAi && settings.model == DEFAULT_EMBEDDING_MODEL {
+        return "embeddings_db".into();
+    }
+
+    let kind = match settings.kind {
+        EmbeddingProviderKind::OpenAi => "open_ai",
+        EmbeddingProviderKind::Ollama => "ollama",
+    };
+    let model = settings
+        .model
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    format!("embeddings_db-{kind}-{model}")
+}
+
+async fn build_embedding_provider(
+    settings: EmbeddingProviderSettings,
+    http_client: Arc<dyn HttpClient>,
+    executor: BackgroundExecutor,
+) -> Arc<dyn EmbeddingProvider> {
+    let headers = settings.headers.into_iter().collect::<Vec<_>>();
+    match settings.kind {
+        EmbeddingProviderKind::OpenAi => {
+            let mut provider =
+                OpenAiEmbeddingProvider::for_model(settings.model, http_client, executor)
+                    .await
+                    .with_api_url(settings.api_url)
+                    .with_headers(headers);
+            if let Some(max_tokens_per_batch) = settings.max_tokens_per_batch {
+                provider = provider.with_max_tokens_per_batch(max_tokens_per_batch);
+            }
+            if !settings.requires_api_key {
+                provider = provider.without_api_key();
+            }
+            Arc::new(provider)
+        }
+        EmbeddingProviderKind::Ollama => {
+            let mut provider = OllamaEmbeddingProvider::new(settings.model)
+                .with_api_url(settings.api_url)
+                .with_headers(headers);
+            if let Some(max_tokens_per_batch) = settings.max_tokens_per_batch {
+                provider = provider.with_max_tokens_per_batch(max_tokens_per_batch);
+            }
+            if let Some(context_window) = settings.context_window {
+                provider = provider.with_context_window(context_window);
+            }
+            Arc::new(provider)
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum SemanticIndexStatus {
     NotAuthenticated,
@@ -126,6 +206,11 @@ pub struct SemanticIndex {
     embedding_provider: Arc<dyn EmbeddingProvider>,
     language_registry: Arc<LanguageRegistry>,
     parsing_files_tx: channel::Sender<(Arc<HashMap<SpanDigest, Embedding>>, PendingFile)>,
+    /// An approximate nearest neighbor index over the database's embeddings,
+    /// saved next to the database.
+    vector_index: Arc<RwLock<VectorIndexState>>,
+    vector_index_changed_tx: channel::Sender<()>,
+    _vector_index_task: Task<()>,
     _embedding_task: Task<()>,
     _parsing_files_tasks: Vec<Task<()>>,
     projects: HashMap<WeakModel<Project>, ProjectState>,
@@ -341,6 +426,7 @@ impl SemanticIndex {
         mut cx: AsyncAppContext,
     ) -> Result<Model<Self>> {
         let t0 = Instant::now();
+        let vector_index_path = database_path.with_extension("hnsw");
         let database_path = Arc::from(database_path);
         let db = VectorDatabase::new(fs.clone(), database_path, cx.background_executor().clone())
             .await?;
@@ -352,16 +438,57 @@ impl SemanticIndex {
 
         cx.new_model(|cx| {
             let t0 = Instant::now();
+            let vector_index = Arc::new(RwLock::new(VectorIndexState::default()));
+            let (vector_index_changed_tx, vector_index_changed_rx) = channel::unbounded();
+            let _vector_index_task = cx.background_executor().spawn({
+                let db = db.clone();
+                let vector_index = vector_index.clone();
+                let executor = cx.background_executor().clone();
+                async move {
+                    let index = load_vector_index(&db, &vector_index_path)
+                        .await
+                        .log_err()
+                        .unwrap_or_default();
+                    vector_index.write().finish_loading(index);
+
+                    // Save the index once it stops changing.
+                    while let Ok(()) = vector_index_changed_rx.recv().await {
+                        loop {
+                            let mut timer = executor.timer(VECTOR_INDEX_SAVE_DELAY).fuse();
+                            let mut changed = vector_index_changed_rx.recv().fuse();
+                            futures::select_biased! {
+                                changed = changed => {
+                                    if changed.is_err() {
+                                        break;
+                                    }
+                                },
+                                _ = timer => break,
+                            }
+                        }
+                        if let Some(index) = vector_index.read().ready() {
+                            index.save(&vector_index_path).log_err();
+                        }
+                    }
+                }
+            });
+
             let embedding_queue =
                 EmbeddingQueue::new(embedding_provider.clone(), cx.background_executor().clone());
             let _embedding_task = cx.background_executor().spawn({
                 let embedded_files = embedding_queue.finished_files();
                 let db = db.clone();
+                let vector_index = vector_index.clone();
+                let vector_index_changed_tx = vector_index_changed_tx.clone();
                 async move {
                     while let Ok(file) = embedded_files.recv().await {
-                        db.insert_file(file.worktree_id, file.path, file.mtime, file.spans)
+                        if let Some(changes) = db
+                            .insert_file(file.worktree_id, file.path, file.mtime, file.spans)
                             .await
-                            .log_err();
+                            .log_err()
+                        {
+                            vector_index.write().apply(changes);
+                            vector_index_changed_tx.try_send(()).ok();
+                        }
                     }
                 }
             });
@@ -415,6 +542,9 @@ impl SemanticIndex {
                 embedding_provider,
                 language_registry,
                 parsing_files_tx,
+                vector_index,
+                vector_index_changed_tx,
+                _vector_index_task,
                 _embedding_task,
                 _parsing_files_tasks,
                 projects: Default::default(),
@@ -788,6 +918,7 @@ impl SemanticIndex {
     ) -> Task<Result<Vec<SearchResult>>> {
         let db_path = self.db.path().clone();
         let fs = self.fs.clone();
+        let vector_index = self.vector_index.clone();
         cx.spawn(|this, mut cx| async move {
             let database = VectorDatabase::new(
                 fs.clone(),
@@ -796,78 +927,22 @@ impl SemanticIndex {
             )
             .await?;
 
-            let worktree_db_ids = this.read_with(&cx, |this, _| {
-                let project_state = this
-                    .projects
-                    .get(&project.downgrade())
-                    .context("project was not indexed")?;
-                let worktree_db_ids = project_state
-                    .worktrees
-                    .values()
-                    .filter_map(|worktree| {
-                        if let WorktreeState::Registered(worktree) = worktree {
-                            Some(worktree.db_id)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<i64>>();
-                anyhow::Ok(worktree_db_ids)
-            })??;
-
+            let worktree_db_ids =
+                this.read_with(&cx, |this, _| this.registered_worktree_db_ids(&project))??;
             let file_ids = database
                 .retrieve_included_file_ids(&worktree_db_ids, &includes, &excludes)
                 .await?;
 
-            let batch_n = cx.background_executor().num_cpus();
-            let ids_len = file_ids.clone().len();
-            let minimum_batch_size = 50;
-
-            let batch_size = {
-                let size = ids_len / batch_n;
-                if size < minimum_batch_size {
-                    minimum_batch_size
-                } else {
-                    size
-                }
-            };
-
-            let mut batch_results = Vec::new();
-            for batch in file_ids.chunks(batch_size) {
-                let batch = batch.into_iter().map(|v| *v).collect::<Vec<i64>>();
-                let limit = limit.clone();
-                let fs = fs.clone();
-                let db_path = db_path.clone();
-                let query = query.clone();
-                if let Some(db) =
-                    VectorDatabase::new(fs, db_path.clone(), cx.background_executor().clone())
-                        .await
-                        .log_err()
-                {
-                    batch_results.push(async move {
-                        db.top_k_search(&query, limit, batch.as_slice()).await
-                    });
-                }
-            }
-
-            let batch_results = futures::future::join_all(batch_results).await;
-
-            let mut results = Vec::new();
-            for batch_result in batch_results {
-                if batch_result.is_ok() {
-                    for (id, similarity) in batch_result.unwrap() {
-                        let ix = match results
-                            .binary_search_by_key(&Reverse(similarity), |(_, s)| Reverse(*s))
-                        {
-                            Ok(ix) => ix,
-                            Err(ix) => ix,
-                        };
-
-                        results.insert(ix, (id, similarity));
-                        results.truncate(limit);
-                    }
-                }
-            }
+            let results = search_span_embeddings(
+                vector_index,
+                fs,
+                db_path,
+                query,
+                limit,
+                file_ids,
+                cx.background_executor().clone(),
+            )
+            .await;
 
             let ids = results.iter().map(|(id, _)| *id).collect::<Vec<i64>>();
             let scores = results
@@ -875,42 +950,12 @@ impl SemanticIndex {
                 .map(|(_, score)| score)
                 .collect::<Vec<_>>();
             let spans = database.spans_for_ids(ids.as_slice()).await?;
-
-            let mut tasks = Vec::new();
-            let mut ranges = Vec::new();
-            let weak_project = project.downgrade();
-            project.update(&mut cx, |project, cx| {
-                let this = this.upgrade().context("index was dropped")?;
-                for (worktree_db_id, file_path, byte_range) in spans {
-                    let project_state =
-                        if let Some(state) = this.read(cx).projects.get(&weak_project) {
-                            state
-                        } else {
-                            return Err(anyhow!("project not added"));
-                        };
-                    if let Some(worktree_id) = project_state.worktree_id_for_db_id(worktree_db_id) {
-                        tasks.push(project.open_buffer((worktree_id, file_path), cx));
-                        ranges.push(byte_range);
-                    }
-                }
-
-                Ok(())
-            })??;
-
-            let buffers = futures::future::join_all(tasks).await;
+            let buffers = open_span_buffers(&this, &project, spans, &mut cx).await?;
             Ok(buffers
                 .into_iter()
-                .zip(ranges)
                 .zip(scores)
-                .filter_map(|((buffer, range), similarity)| {
-                    let buffer = buffer.log_err()?;
-                    let range = buffer
-                        .read_with(&cx, |buffer, _| {
-                            let start = buffer.clip_offset(range.start, Bias::Left);
-                            let end = buffer.clip_offset(range.end, Bias::Right);
-                            buffer.anchor_before(start)..buffer.anchor_after(end)
-                        })
-                        .log_err()?;
+                .filter_map(|(buffer, similarity)| {
+                    let (buffer, range) = buffer?;
                     Some(SearchResult {
                         buffer,
                         range,
@@ -921,6 +966,26 @@ impl SemanticIndex {
         })
     }
 
+    /// Returns the database ids of the project's worktrees that have finished
+    /// registering.
+    fn registered_worktree_db_ids(&self, project: &Model<Project>) -> Result<Vec<i64>> {
+        let project_state = self
+            .projects
+            .get(&project.downgrade())
+            .context("project was not indexed")?;
+        Ok(project_state
+            .worktrees
+            .values()
+            .filter_map(|worktree| {
+                if let WorktreeState::Registered(worktree) = worktree {
+                    Some(worktree.db_id)
+                } else {
+                    None
+                }
+            })
+            .collect())
+    }
+
     fn search_modified_buffers(
         &self,
         project: &Model<Project>,
@@ -1058,6 +1123,8 @@ impl SemanticIndex {
 
         let mut pending_file_count_rx = project_state.pending_file_count_rx.clone();
         let db = self.db.clone();
+        let vector_index = self.vector_index.clone();
+        let vector_index_changed_tx = self.vector_index_changed_tx.clone();
         let language_registry = self.language_registry.clone();
         let parsing_files_tx = self.parsing_files_tx.clone();
         let worktree_registration = self.wait_for_worktree_registration(&project, cx);
@@ -1116,7 +1183,11 @@ impl SemanticIndex {
             cx.background_executor()
                 .spawn(async move {
                     for (worktree_db_id, path) in files_to_delete {
-                        db.delete_file(worktree_db_id, path).await.log_err();
+                        if let Some(changes) = db.delete_file(worktree_db_id, path).await.log_err()
+                        {
+                            vector_index.write().apply(changes);
+                            vector_index_changed_tx.try_send(()).ok();
+                        }
                     }
 
                     let embeddings_for_digest = {
@@ -1268,6 +1339,186 @@ impl SemanticIndex {
     }
 }
 
+/// Loads the vector index saved next to the database, rebuilding it from the
+/// database's embeddings if it's missing or out of date.
+async fn load_vector_index(db: &VectorDatabase, path: &Path) -> Result<VectorIndex> {
+    let (span_count, max_span_id) = db.span_count_and_max_id().await?;
+    match VectorIndex::load(path) {
+        Ok(index) if index.len() == span_count && index.max_span_id() == max_span_id => {
+            return Ok(index);
+        }
+        Ok(_) => log::info!("vector index at {path:?} is out of date, rebuilding it"),
+        Err(error) => log::trace!("could not load vector index at {path:?}: {error:?}"),
+    }
+
+    let t0 = Instant::now();
+    let mut index = VectorIndex::default();
+    let mut last_span_id = 0;
+    loop {
+        let spans = db
+            .spans_after(last_span_id, VECTOR_INDEX_REBUILD_BATCH_SIZE)
+            .await?;
+        let Some((span_id, _, _)) = spans.last() else {
+            break;
+        };
+        last_span_id = *span_id;
+        for (span_id, file_id, embedding) in spans {
+            index.insert(span_id, file_id, embedding.0);
+        }
+    }
+    log::trace!(
+        "rebuilding vector index with {} spans took {:?}",
+        index.len(),
+        t0.elapsed()
+    );
+
+    index.save(path)?;
+    Ok(index)
+}
+
+/// Opens the buffers containing the given spans, returning `None` for spans
+/// whose worktree is no longer part of the project or whose buffer failed to
+/// open.
+async fn open_span_buffers(
+    this: &WeakModel<SemanticIndex>,
+    project: &Model<Project>,
+    spans: Vec<(i64, PathBuf, Range<usize>)>,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<Option<(Model<Buffer>, Range<Anchor>)>>> {
+    let mut tasks = Vec::new();
+    let mut ranges = Vec::new();
+    let weak_project = project.downgrade();
+    project.update(cx, |project, cx| {
+        let this = this.upgrade().context("index was dropped")?;
+        for (worktree_db_id, file_path, byte_range) in spans {
+            let project_state = if let Some(state) = this.read(cx).projects.get(&weak_project) {
+                state
+            } else {
+                return Err(anyhow!("project not added"));
+            };
+            if let Some(worktree_id) = project_state.worktree_id_for_db_id(worktree_db_id) {
+                tasks.push(Some(project.open_buffer((worktree_id, file_path), cx)));
+            } else {
+                tasks.push(None);
+            }
+            ranges.push(byte_range);
+        }
+
+        Ok(())
+    })??;
+
+    let buffers = futures::future::join_all(
+        tasks
+            .into_iter()
+            .map(|task| async move { task?.await.log_err() }),
+    )
+    .await;
+    Ok(buffers
+        .into_iter()
+        .zip(ranges)
+        .map(|(buffer, range)| {
+            let buffer = buffer?;
+            let range = buffer
+                .read_with(cx, |buffer, _| {
+                    let start = buffer.clip_offset(range.start, Bias::Left);
+                    let end = buffer.clip_offset(range.end, Bias::Right);
+                    buffer.anchor_before(start)..buffer.anchor_after(end)
+                })
+                .log_err()?;
+            Some((buffer, range))
+        })
+        .collect())
+}
+
+/// Returns the spans whose embeddings are most similar to the query, using the
+/// vector index once the project is large enough for it to pay off.
+async fn search_span_embeddings(
+    vector_index: Arc<RwLock<VectorIndexState>>,
+    fs: Arc<dyn Fs>,
+    db_path: Arc<Path>,
+    query: Embedding,
+    limit: usize,
+    file_ids: Vec<i64>,
+    executor: BackgroundExecutor,
+) -> Vec<(i64, OrderedFloat<f32>)> {
+    let use_vector_index = vector_index
+        .read()
+        .ready()
+        .map_or(false, |index| index.len() >= MIN_SPANS_FOR_VECTOR_INDEX);
+    if use_vector_index {
+        executor
+            .spawn(async move {
+                let file_ids = file_ids.into_iter().collect::<HashSet<_>>();
+                vector_index.read().ready().map_or(Vec::new(), |index| {
+                    index.search(&query.0, limit, |file_id| file_ids.contains(&file_id))
+                })
+            })
+            .await
+    } else {
+        search_all_spans(fs, db_path, query, limit, file_ids, executor).await
+    }
+}
+
+/// Scores every span in the given files, splitting them into batches that
+/// are searched concurrently.
+async fn search_all_spans(
+    fs: Arc<dyn Fs>,
+    db_path: Arc<Path>,
+    query: Embedding,
+    limit: usize,
+    file_ids: Vec<i64>,
+    executor: BackgroundExecutor,
+) -> Vec<(i64, OrderedFloat<f32>)> {
+    let batch_n = executor.num_cpus();
+    let ids_len = file_ids.clone().len();
+    let minimum_batch_size = 50;
+
+    let batch_size = {
+        let size = ids_len / batch_n;
+        if size < minimum_batch_size {
+            minimum_batch_size
+        } else {
+            size
+        }
+    };
+
+    let mut batch_results = Vec::new();
+    for batch in file_ids.chunks(batch_size) {
+        let batch = batch.into_iter().map(|v| *v).collect::<Vec<i64>>();
+        let limit = limit.clone();
+        let fs = fs.clone();
+        let db_path = db_path.clone();
+        let query = query.clone();
+        if let Some(db) = VectorDatabase::new(fs, db_path.clone(), executor.clone())
+            .await
+            .log_err()
+        {
+            batch_results
+                .push(async move { db.top_k_search(&query, limit, batch.as_slice()).await });
+        }
+    }
+
+    let batch_results = futures::future::join_all(batch_results).await;
+
+    let mut results = Vec::new();
+    for batch_result in batch_results {
+        if batch_result.is_ok() {
+            for (id, similarity) in batch_result.unwrap() {
+                let ix = match results
+                    .binary_search_by_key(&Reverse(similarity), |(_, s)| Reverse(*s))
+                {
+                    Ok(ix) => ix,
+                    Err(ix) => ix,
+                };
+
+                results.insert(ix, (id, similarity));
+                results.truncate(limit);
+            }
+        }
+    }
+    results
+}
+
 impl Drop for JobHandle {
     fn drop(&mut self) {
         if let Some(inner) = Arc::get_mut(&mut self.tx) {
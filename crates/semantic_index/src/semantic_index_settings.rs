@@ -2,10 +2,52 @@ use anyhow;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Debug)]
 pub struct SemanticIndexSettings {
     pub enabled: bool,
+    pub embedding_provider: EmbeddingProviderSettings,
+}
+
+/// The API spoken by an embedding provider.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingProviderKind {
+    /// OpenAI's embeddings API, or any server compatible with it.
+    OpenAi,
+    /// The native embedding API of an Ollama server.
+    Ollama,
+}
+
+/// The server that computes embeddings for the code being indexed.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct EmbeddingProviderSettings {
+    /// The API spoken by the provider.
+    pub kind: EmbeddingProviderKind,
+    /// The base URL of the provider's API.
+    pub api_url: String,
+    /// The name of the embedding model, as sent to the provider.
+    pub model: String,
+    /// Whether requests must include an API key. Local OpenAI-compatible
+    /// servers usually don't need one.
+    #[serde(default = "default_requires_api_key")]
+    pub requires_api_key: bool,
+    /// Extra headers to send with every request.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// How many tokens a single request may contain. When omitted, a
+    /// provider-specific default is used.
+    #[serde(default)]
+    pub max_tokens_per_batch: Option<usize>,
+    /// How many tokens fit in the model's context window, which bounds the
+    /// size of each embedded span. Only used by Ollama.
+    #[serde(default)]
+    pub context_window: Option<usize>,
+}
+
+fn default_requires_api_key() -> bool {
+    true
 }
 
 /// Configuration of semantic index, an alternate search engine available in
@@ -16,6 +58,11 @@ pub struct SemanticIndexSettingsContent {
     ///
     /// Default: true
     pub enabled: Option<bool>,
+    /// The server that computes embeddings. Each model is indexed into its own
+    /// database, and changing this setting requires a restart.
+    ///
+    /// Default: {"kind": "open_ai", "api_url": "https://api.openai.com/v1", "model": "text-embedding-ada-002"}
+    pub embedding_provider: Option<EmbeddingProviderSettings>,
 }
 
 impl Settings for SemanticIndexSettings {
@@ -1,5 +1,6 @@
 use crate::{
     embedding_queue::EmbeddingQueue,
+    hybrid_search::tokenize,
     parsing::{subtract_ranges, CodeContextRetriever, Span, SpanDigest},
     semantic_index_settings::SemanticIndexSettings,
     FileToEmbed, JobHandle, SearchResult, SemanticIndex, EMBEDDING_QUEUE_FLUSH_TIMEOUT,
@@ -192,6 +193,155 @@ async fn test_semantic_index(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_hybrid_search(cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            "src": {
+                "lexer.rs": "
+                    fn tokenize_input() {
+                        split_whitespace();
+                    }
+                ".unindent(),
+                "parser.rs": "
+                    fn parse_tokens() {
+                        tokenize_input();
+                    }
+
+                    fn render_widget() {
+                        draw();
+                    }
+                ".unindent(),
+            }
+        }),
+    )
+    .await;
+
+    let languages = Arc::new(LanguageRegistry::new(Task::ready(())));
+    languages.add(rust_lang());
+
+    let db_dir = tempfile::Builder::new()
+        .prefix("vector-store")
+        .tempdir()
+        .unwrap();
+    let db_path = db_dir.path().join("db.sqlite");
+
+    let embedding_provider = Arc::new(FakeEmbeddingProvider::default());
+    let semantic_index = SemanticIndex::new(
+        fs.clone(),
+        db_path,
+        embedding_provider.clone(),
+        languages,
+        cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+
+    let search_results = semantic_index.update(cx, |store, cx| {
+        store.hybrid_search_project(
+            project.clone(),
+            "tokenize input".to_string(),
+            5,
+            vec![],
+            vec![],
+            cx,
+        )
+    });
+    cx.background_executor.run_until_parked();
+    cx.background_executor
+        .advance_clock(EMBEDDING_QUEUE_FLUSH_TIMEOUT);
+    let search_results = search_results.await.unwrap();
+
+    let paths_and_offsets = search_results
+        .iter()
+        .map(|result| {
+            result.buffer.read_with(cx, |buffer, _| {
+                (
+                    buffer.file().unwrap().path().clone(),
+                    result.range.start.to_offset(buffer),
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(paths_and_offsets.len(), 3);
+    assert_eq!(paths_and_offsets[0], (Path::new("src/lexer.rs").into(), 0));
+    assert!(search_results
+        .windows(2)
+        .all(|results| results[0].score >= results[1].score));
+
+    let explanation = &search_results[0].explanation;
+    assert_eq!(
+        explanation.matched_symbol.as_deref(),
+        Some("tokenize_input")
+    );
+    assert_eq!(explanation.symbol.unwrap().rank, 1);
+    assert!(explanation.lexical.is_some());
+    assert!(explanation.semantic.is_some());
+    assert!(explanation
+        .matched_terms
+        .iter()
+        .any(|term| term == "tokenize"));
+
+    // Spans that don't contain any of the query's terms are only found
+    // semantically.
+    let render_widget = search_results
+        .iter()
+        .zip(&paths_and_offsets)
+        .find(|(_, path_and_offset)| {
+            **path_and_offset != (Path::new("src/lexer.rs").into(), 0)
+                && **path_and_offset != (Path::new("src/parser.rs").into(), 0)
+        })
+        .unwrap()
+        .0;
+    assert!(render_widget.explanation.lexical.is_none());
+    assert!(render_widget.explanation.symbol.is_none());
+
+    let parser_only_results = semantic_index
+        .update(cx, |store, cx| {
+            store.hybrid_search_project(
+                project.clone(),
+                "tokenize input".to_string(),
+                5,
+                vec![PathMatcher::new("src/parser.rs").unwrap()],
+                vec![],
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(parser_only_results.len(), 2);
+    for result in &parser_only_results {
+        result.buffer.read_with(cx, |buffer, _| {
+            assert_eq!(
+                buffer.file().unwrap().path().as_ref(),
+                Path::new("src/parser.rs")
+            );
+        });
+    }
+}
+
+#[test]
+fn test_tokenize() {
+    assert_eq!(
+        tokenize("HTTPServer::search_project(x, 42)"),
+        [
+            "httpserver",
+            "http",
+            "server",
+            "search_project",
+            "search",
+            "project",
+            "42"
+        ]
+    );
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_embedding_batching(cx: &mut TestAppContext, mut rng: StdRng) {
     let (outstanding_job_count, _) = postage::watch::channel_with(0);
@@ -217,6 +367,7 @@ async fn test_embedding_batching(cx: &mut TestAppContext, mut rng: StdRng) {
                         content,
                         digest,
                         token_count: rng.gen_range(10..30),
+                        symbols: Vec::new(),
                     }
                 })
                 .collect(),
@@ -255,6 +406,49 @@ async fn test_embedding_batching(cx: &mut TestAppContext, mut rng: StdRng) {
     assert_eq!(embedded_files, expected_files);
 }
 
+#[gpui::test]
+async fn test_embedding_batching_span_limit(cx: &mut TestAppContext) {
+    let (outstanding_job_count, _) = postage::watch::channel_with(0);
+    let outstanding_job_count = Arc::new(Mutex::new(outstanding_job_count));
+
+    let file = FileToEmbed {
+        worktree_id: 5,
+        path: Path::new("path").into(),
+        mtime: SystemTime::now(),
+        spans: (0..10)
+            .map(|span_ix| {
+                let content = format!("span {span_ix}");
+                Span {
+                    range: 0..10,
+                    embedding: None,
+                    name: content.clone(),
+                    digest: SpanDigest::from(content.as_str()),
+                    content,
+                    token_count: 1,
+                    symbols: Vec::new(),
+                }
+            })
+            .collect(),
+        job_handle: JobHandle::new(&outstanding_job_count),
+    };
+
+    let embedding_provider = Arc::new(FakeEmbeddingProvider::default().with_max_spans_per_batch(4));
+    let mut queue = EmbeddingQueue::new(embedding_provider.clone(), cx.background_executor.clone());
+    queue.push(file);
+    queue.flush();
+
+    cx.background_executor.run_until_parked();
+    let embedded_file = queue.finished_files().try_recv().expect("no finished file");
+    assert!(embedded_file
+        .spans
+        .iter()
+        .all(|span| span.embedding.is_some()));
+
+    let mut batch_sizes = embedding_provider.batch_sizes();
+    batch_sizes.sort();
+    assert_eq!(batch_sizes, [2, 4, 4]);
+}
+
 #[track_caller]
 fn assert_search_results(
     actual: &[SearchResult],
@@ -1390,6 +1584,18 @@ fn rust_lang() -> Arc<Language> {
             (use_declaration) @collapse
             "#,
         )
+        .unwrap()
+        .with_outline_query(
+            r#"
+            (struct_item
+                "struct" @context
+                name: (_) @name) @item
+
+            (function_item
+                "fn" @context
+                name: (_) @name) @item
+            "#,
+        )
         .unwrap(),
     )
 }
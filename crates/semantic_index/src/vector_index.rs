@@ -0,0 +1,471 @@
+//! An approximate nearest neighbor index over span embeddings, implemented as a
+//! hierarchical navigable small world graph (https://arxiv.org/abs/1603.09320).
+//! Searching it only scores the spans along a greedy walk through the graph,
+//! rather than every span in the database.
+
+use anyhow::{anyhow, Result};
+use collections::{HashMap, HashSet};
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    mem,
+    path::Path,
+};
+
+const VECTOR_INDEX_VERSION: u32 = 1;
+/// How many neighbors each node keeps on the upper layers. Nodes keep twice
+/// as many on layer 0, which every node belongs to.
+const MAX_NEIGHBORS: usize = 16;
+const MAX_LEVEL: usize = 16;
+/// How many candidates to consider when connecting a new node.
+const EF_CONSTRUCTION: usize = 100;
+/// How many candidates to consider when searching.
+const EF_SEARCH: usize = 64;
+/// The graph is rebuilt once this many nodes have been removed, and they
+/// make up more than half of it.
+const MIN_DELETED_NODES_TO_COMPACT: usize = 1024;
+
+/// A change to the spans in the database, to be mirrored in the vector index.
+pub enum SpanChange {
+    Insert {
+        span_id: i64,
+        file_id: i64,
+        embedding: Vec<f32>,
+    },
+    Remove(i64),
+}
+
+pub enum VectorIndexState {
+    /// The index is being loaded or rebuilt from the database. Changes made
+    /// in the meantime are applied once it's ready.
+    Loading(Vec<SpanChange>),
+    Ready(VectorIndex),
+}
+
+impl Default for VectorIndexState {
+    fn default() -> Self {
+        Self::Loading(Vec::new())
+    }
+}
+
+impl VectorIndexState {
+    pub fn ready(&self) -> Option<&VectorIndex> {
+        match self {
+            Self::Loading(_) => None,
+            Self::Ready(index) => Some(index),
+        }
+    }
+
+    pub fn apply(&mut self, changes: impl IntoIterator<Item = SpanChange>) {
+        match self {
+            Self::Loading(pending_changes) => pending_changes.extend(changes),
+            Self::Ready(index) => {
+                for change in changes {
+                    index.apply(change);
+                }
+            }
+        }
+    }
+
+    pub fn finish_loading(&mut self, mut index: VectorIndex) {
+        if let Self::Loading(pending_changes) = mem::take(self) {
+            for change in pending_changes {
+                index.apply(change);
+            }
+        }
+        *self = Self::Ready(index);
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct VectorIndex {
+    nodes: Vec<Node>,
+    entry_point: Option<u32>,
+    deleted_count: usize,
+    #[serde(skip)]
+    node_ixs_by_span_id: HashMap<i64, u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Node {
+    span_id: i64,
+    file_id: i64,
+    embedding: Vec<f32>,
+    /// The node's neighbors on each of its layers, starting with layer 0.
+    neighbors: Vec<Vec<u32>>,
+    /// Removed nodes stay in the graph so that searches can still pass
+    /// through them, but they're never returned.
+    deleted: bool,
+}
+
+impl VectorIndex {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != VECTOR_INDEX_VERSION {
+            return Err(anyhow!("unsupported vector index version {version}"));
+        }
+        let mut index: Self = bincode::deserialize_from(&mut reader)?;
+        index.node_ixs_by_span_id = index
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !node.deleted)
+            .map(|(ix, node)| (node.span_id, ix as u32))
+            .collect();
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        // Write to a temporary file first, so that the index is never left
+        // half-written if we're interrupted.
+        let temp_path = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        bincode::serialize_into(&mut writer, &VECTOR_INDEX_VERSION)?;
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// The number of spans in the index.
+    pub fn len(&self) -> usize {
+        self.node_ixs_by_span_id.len()
+    }
+
+    pub fn max_span_id(&self) -> Option<i64> {
+        self.node_ixs_by_span_id.keys().copied().max()
+    }
+
+    pub fn apply(&mut self, change: SpanChange) {
+        match change {
+            SpanChange::Insert {
+                span_id,
+                file_id,
+                embedding,
+            } => self.insert(span_id, file_id, embedding),
+            SpanChange::Remove(span_id) => self.remove(span_id),
+        }
+    }
+
+    pub fn insert(&mut self, span_id: i64, file_id: i64, embedding: Vec<f32>) {
+        self.remove(span_id);
+
+        let level = level_for_span(span_id);
+        let node_ix = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            span_id,
+            file_id,
+            embedding,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.node_ixs_by_span_id.insert(span_id, node_ix);
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(node_ix);
+            return;
+        };
+        let top_level = self.nodes[entry_point as usize].neighbors.len() - 1;
+
+        let mut entry_points = vec![entry_point];
+        for layer in (level + 1..=top_level).rev() {
+            entry_points = self.closest_on_layer(node_ix, &entry_points, layer);
+        }
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self.search_layer(
+                &self.nodes[node_ix as usize].embedding,
+                &entry_points,
+                EF_CONSTRUCTION,
+                layer,
+            );
+            let neighbors = candidates
+                .iter()
+                .map(|(_, ix)| *ix)
+                .take(MAX_NEIGHBORS)
+                .collect::<Vec<_>>();
+            for neighbor_ix in &neighbors {
+                self.connect(*neighbor_ix, node_ix, layer);
+            }
+            self.nodes[node_ix as usize].neighbors[layer] = neighbors;
+            entry_points = candidates.into_iter().map(|(_, ix)| ix).collect();
+        }
+
+        if level > top_level {
+            self.entry_point = Some(node_ix);
+        }
+    }
+
+    pub fn remove(&mut self, span_id: i64) {
+        let Some(node_ix) = self.node_ixs_by_span_id.remove(&span_id) else {
+            return;
+        };
+        self.nodes[node_ix as usize].deleted = true;
+        self.deleted_count += 1;
+        if self.deleted_count >= MIN_DELETED_NODES_TO_COMPACT
+            && self.deleted_count * 2 > self.nodes.len()
+        {
+            self.compact();
+        }
+    }
+
+    /// Returns the ids of up to `limit` spans whose embeddings are most
+    /// similar to the query, along with their similarity, skipping spans in
+    /// files that `include_file` rejects.
+    pub fn search(
+        &self,
+        query: &[f32],
+        limit: usize,
+        include_file: impl Fn(i64) -> bool,
+    ) -> Vec<(i64, OrderedFloat<f32>)> {
+        let Some(entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        let top_level = self.nodes[entry_point as usize].neighbors.len() - 1;
+
+        let mut entry_points = vec![entry_point];
+        for layer in (1..=top_level).rev() {
+            entry_points = self
+                .search_layer(query, &entry_points, 1, layer)
+                .into_iter()
+                .map(|(_, ix)| ix)
+                .collect();
+        }
+
+        // Removed spans and spans in excluded files are skipped, so widen the
+        // search until it finds enough results or has visited every node.
+        let mut ef = EF_SEARCH.max(limit);
+        loop {
+            let results = self
+                .search_layer(query, &entry_points, ef, 0)
+                .into_iter()
+                .filter_map(|(similarity, ix)| {
+                    let node = &self.nodes[ix as usize];
+                    (!node.deleted && include_file(node.file_id))
+                        .then_some((node.span_id, similarity))
+                })
+                .take(limit)
+                .collect::<Vec<_>>();
+            if results.len() >= limit || ef >= self.nodes.len() {
+                return results;
+            }
+            ef *= 4;
+        }
+    }
+
+    fn closest_on_layer(&self, node_ix: u32, entry_points: &[u32], layer: usize) -> Vec<u32> {
+        self.search_layer(
+            &self.nodes[node_ix as usize].embedding,
+            entry_points,
+            1,
+            layer,
+        )
+        .into_iter()
+        .map(|(_, ix)| ix)
+        .collect()
+    }
+
+    /// Returns the `ef` nodes on the given layer closest to the query, found
+    /// by walking the graph from the entry points, most similar first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[u32],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<(OrderedFloat<f32>, u32)> {
+        let mut visited = HashSet::default();
+        let mut candidates = BinaryHeap::new();
+        let mut nearest = BinaryHeap::new();
+        for &node_ix in entry_points {
+            if visited.insert(node_ix) {
+                let similarity = similarity(query, &self.nodes[node_ix as usize].embedding);
+                candidates.push((similarity, node_ix));
+                nearest.push(Reverse((similarity, node_ix)));
+            }
+        }
+        while nearest.len() > ef {
+            nearest.pop();
+        }
+
+        while let Some((similarity, node_ix)) = candidates.pop() {
+            if nearest.len() >= ef && similarity < furthest(&nearest) {
+                break;
+            }
+
+            for &neighbor_ix in &self.nodes[node_ix as usize].neighbors[layer] {
+                if !visited.insert(neighbor_ix) {
+                    continue;
+                }
+                let similarity =
+                    self::similarity(query, &self.nodes[neighbor_ix as usize].embedding);
+                if nearest.len() < ef || similarity > furthest(&nearest) {
+                    candidates.push((similarity, neighbor_ix));
+                    nearest.push(Reverse((similarity, neighbor_ix)));
+                    if nearest.len() > ef {
+                        nearest.pop();
+                    }
+                }
+            }
+        }
+
+        let mut nearest = nearest
+            .into_iter()
+            .map(|Reverse(entry)| entry)
+            .collect::<Vec<_>>();
+        nearest.sort_unstable_by(|a, b| b.cmp(a));
+        nearest
+    }
+
+    fn connect(&mut self, from_ix: u32, to_ix: u32, layer: usize) {
+        let max_neighbors = if layer == 0 {
+            MAX_NEIGHBORS * 2
+        } else {
+            MAX_NEIGHBORS
+        };
+
+        let neighbors = &mut self.nodes[from_ix as usize].neighbors[layer];
+        neighbors.push(to_ix);
+        if neighbors.len() <= max_neighbors {
+            return;
+        }
+
+        let mut neighbors = mem::take(neighbors);
+        let embedding = &self.nodes[from_ix as usize].embedding;
+        neighbors.sort_by_cached_key(|neighbor_ix| {
+            Reverse(similarity(
+                embedding,
+                &self.nodes[*neighbor_ix as usize].embedding,
+            ))
+        });
+        neighbors.truncate(max_neighbors);
+        self.nodes[from_ix as usize].neighbors[layer] = neighbors;
+    }
+
+    fn compact(&mut self) {
+        let nodes = mem::take(&mut self.nodes);
+        *self = Self::default();
+        for node in nodes {
+            if !node.deleted {
+                self.insert(node.span_id, node.file_id, node.embedding);
+            }
+        }
+    }
+}
+
+fn furthest(nearest: &BinaryHeap<Reverse<(OrderedFloat<f32>, u32)>>) -> OrderedFloat<f32> {
+    nearest
+        .peek()
+        .map_or(OrderedFloat(f32::MIN), |Reverse((similarity, _))| {
+            *similarity
+        })
+}
+
+fn similarity(a: &[f32], b: &[f32]) -> OrderedFloat<f32> {
+    OrderedFloat(a.iter().zip(b).map(|(a, b)| a * b).sum())
+}
+
+/// Picks the highest layer a span belongs to from an exponentially decaying
+/// distribution. The choice is derived from the span's id rather than a random
+/// number generator, so the graph doesn't depend on any state besides its spans.
+fn level_for_span(span_id: i64) -> usize {
+    let mut hash = (span_id as u64).wrapping_add(0x9e3779b97f4a7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^= hash >> 31;
+    let uniform = (hash >> 11) as f64 / (1u64 << 53) as f64;
+    let level = -(1.0 - uniform).ln() / (MAX_NEIGHBORS as f64).ln();
+    (level as usize).min(MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[gpui::test(iterations = 10)]
+    fn test_vector_index_search(mut rng: StdRng) {
+        let dimensions = 16;
+        let mut embeddings = Vec::new();
+        let mut index = VectorIndex::default();
+        for span_id in 0..2000 {
+            let embedding = random_embedding(&mut rng, dimensions);
+            let file_id = span_id % 10;
+            index.insert(span_id, file_id, embedding.clone());
+            embeddings.push((span_id, file_id, embedding));
+        }
+        for span_id in 0..200 {
+            index.remove(span_id);
+        }
+        assert_eq!(index.len(), 1800);
+
+        let mut recalled = 0;
+        let queries = 20;
+        let limit = 10;
+        for _ in 0..queries {
+            let query = random_embedding(&mut rng, dimensions);
+            let mut expected = embeddings
+                .iter()
+                .filter(|(span_id, file_id, _)| *span_id >= 200 && *file_id < 5)
+                .map(|(span_id, _, embedding)| (similarity(&query, embedding), *span_id))
+                .collect::<Vec<_>>();
+            expected.sort_unstable_by(|a, b| b.cmp(a));
+            expected.truncate(limit);
+
+            let results = index.search(&query, limit, |file_id| file_id < 5);
+            assert_eq!(results.len(), limit);
+            for (span_id, _) in &results {
+                assert!(*span_id >= 200, "removed span {span_id} was returned");
+                assert!(
+                    *span_id % 10 < 5,
+                    "span {span_id} in excluded file was returned"
+                );
+            }
+            recalled += expected
+                .iter()
+                .filter(|(_, span_id)| results.iter().any(|(id, _)| id == span_id))
+                .count();
+        }
+
+        let recall = recalled as f32 / (queries * limit) as f32;
+        assert!(recall > 0.9, "recall was {recall}");
+    }
+
+    #[gpui::test]
+    fn test_vector_index_persistence(mut rng: StdRng) {
+        let mut index = VectorIndex::default();
+        for span_id in 0..100 {
+            index.insert(span_id, 1, random_embedding(&mut rng, 8));
+        }
+        index.remove(42);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db.hnsw");
+        index.save(&path).unwrap();
+        let loaded = VectorIndex::load(&path).unwrap();
+        assert_eq!(loaded.len(), 99);
+        assert_eq!(loaded.max_span_id(), Some(99));
+
+        let query = random_embedding(&mut rng, 8);
+        assert_eq!(
+            loaded.search(&query, 5, |_| true),
+            index.search(&query, 5, |_| true)
+        );
+    }
+
+    fn random_embedding(rng: &mut StdRng, dimensions: usize) -> Vec<f32> {
+        let mut embedding = (0..dimensions)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect::<Vec<f32>>();
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        for x in &mut embedding {
+            *x /= norm;
+        }
+        embedding
+    }
+}
@@ -15,10 +15,10 @@ use alacritty_terminal::{
         Config, RenderableCursor, TermMode,
     },
     tty::{self, setup_env},
-    vte::ansi::{ClearMode, Handler, NamedPrivateMode, PrivateMode, Rgb},
+    vte::ansi::{ClearMode, CursorShape, Handler, NamedPrivateMode, PrivateMode, Rgb},
     Term,
 };
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
@@ -216,6 +216,77 @@ impl Dimensions for TerminalSize {
     }
 }
 
+/// The dimensions of a terminal's grid, in cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GridSize {
+    pub rows: usize,
+    pub columns: usize,
+}
+
+impl GridSize {
+    fn of(size: &impl Dimensions) -> Self {
+        GridSize {
+            rows: size.screen_lines(),
+            columns: size.columns(),
+        }
+    }
+
+    /// The smallest grid that fits within both sizes.
+    pub fn min(self, other: GridSize) -> GridSize {
+        GridSize {
+            rows: min(self.rows, other.rows),
+            columns: min(self.columns, other.columns),
+        }
+    }
+}
+
+impl Dimensions for GridSize {
+    fn total_lines(&self) -> usize {
+        self.rows
+    }
+
+    fn screen_lines(&self) -> usize {
+        self.rows
+    }
+
+    fn columns(&self) -> usize {
+        self.columns
+    }
+}
+
+/// The visible screen of a terminal, as replicated to collaborators.
+///
+/// Each line is encoded on its own so that only the lines that changed
+/// since the last snapshot need to be sent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScreenSnapshot {
+    pub size: GridSize,
+    pub lines: Vec<Vec<u8>>,
+    pub cursor: AlacPoint,
+    pub mode: u32,
+    pub title: String,
+    pub breadcrumb_text: String,
+}
+
+/// A change to a replica terminal's screen, received from its owner.
+#[derive(Clone, Debug, Default)]
+pub struct ScreenUpdate {
+    pub size: GridSize,
+    /// Encoded lines that changed, keyed by their index on the screen.
+    pub lines: Vec<(usize, Vec<u8>)>,
+    pub cursor: AlacPoint,
+    pub mode: u32,
+    pub title: String,
+    pub breadcrumb_text: String,
+}
+
+/// Sent by a replica terminal to the collaborator that owns the real one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplicaRequest {
+    Input(Vec<u8>),
+    Resize(GridSize),
+}
+
 #[derive(Error, Debug)]
 pub struct TerminalError {
     pub directory: Option<PathBuf>,
@@ -289,7 +360,7 @@ impl TerminalBuilder {
         env: HashMap<String, String>,
         blink_settings: Option<TerminalBlink>,
         alternate_scroll: AlternateScroll,
-        window: AnyWindowHandle,
+        window: Option<AnyWindowHandle>,
     ) -> Result<TerminalBuilder> {
         let pty_options = {
             let alac_shell = match shell.clone() {
@@ -351,7 +422,7 @@ impl TerminalBuilder {
         let pty = match tty::new(
             &pty_options,
             TerminalSize::default().into(),
-            window.window_id().as_u64(),
+            window.map_or(0, |window| window.window_id().as_u64()),
         ) {
             Ok(pty) => pty,
             Err(error) => {
@@ -379,30 +450,15 @@ impl TerminalBuilder {
         let pty_tx = event_loop.channel();
         let _io_thread = event_loop.spawn(); // DANGER
 
-        let url_regex = RegexSearch::new(r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#).unwrap();
-        let word_regex = RegexSearch::new(r#"[\w.\[\]:/@\-~]+"#).unwrap();
-
-        let terminal = Terminal {
-            pty_tx: Notifier(pty_tx),
+        let terminal = Terminal::new(
+            TerminalType::Pty {
+                pty_tx: Notifier(pty_tx),
+                shell_fd: fd as u32,
+                shell_pid,
+                size_limit: None,
+            },
             term,
-            events: VecDeque::with_capacity(10), //Should never get this high.
-            last_content: Default::default(),
-            last_mouse: None,
-            matches: Vec::new(),
-            selection_head: None,
-            shell_fd: fd as u32,
-            shell_pid,
-            foreground_process_info: None,
-            breadcrumb_text: String::new(),
-            scroll_px: px(0.),
-            last_mouse_position: None,
-            next_link_id: 0,
-            selection_phase: SelectionPhase::Ended,
-            cmd_pressed: false,
-            hovered_word: false,
-            url_regex,
-            word_regex,
-        };
+        );
 
         Ok(TerminalBuilder {
             terminal,
@@ -536,8 +592,30 @@ pub enum SelectionPhase {
     Ended,
 }
 
+/// What drives a terminal's grid.
+enum TerminalType {
+    /// A shell running in a local PTY.
+    Pty {
+        pty_tx: Notifier,
+        shell_pid: u32,
+        shell_fd: u32,
+        /// The largest grid the PTY may have, so that every collaborator
+        /// viewing a shared terminal can fit its screen.
+        size_limit: Option<GridSize>,
+    },
+    /// A copy of a terminal owned by a collaborator, kept up to date with
+    /// screen updates. Input and resizes are handed to the owner.
+    Replica {
+        requests_tx: UnboundedSender<ReplicaRequest>,
+        requested_size: Option<GridSize>,
+        read_only: bool,
+        mode: TermMode,
+        title: String,
+    },
+}
+
 pub struct Terminal {
-    pty_tx: Notifier,
+    terminal_type: TerminalType,
     term: Arc<FairMutex<Term<ZedListener>>>,
     events: VecDeque<InternalEvent>,
     /// This is only used for mouse mode cell change detection
@@ -548,8 +626,6 @@ pub struct Terminal {
     pub last_content: TerminalContent,
     pub selection_head: Option<AlacPoint>,
     pub breadcrumb_text: String,
-    shell_pid: u32,
-    shell_fd: u32,
     pub foreground_process_info: Option<LocalProcessInfo>,
     scroll_px: Pixels,
     next_link_id: usize,
@@ -561,6 +637,169 @@ pub struct Terminal {
 }
 
 impl Terminal {
+    fn new(terminal_type: TerminalType, term: Arc<FairMutex<Term<ZedListener>>>) -> Self {
+        let url_regex = RegexSearch::new(r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#).unwrap();
+        let word_regex = RegexSearch::new(r#"[\w.\[\]:/@\-~]+"#).unwrap();
+
+        Terminal {
+            terminal_type,
+            term,
+            events: VecDeque::with_capacity(10), //Should never get this high.
+            last_content: Default::default(),
+            last_mouse: None,
+            matches: Vec::new(),
+            selection_head: None,
+            foreground_process_info: None,
+            breadcrumb_text: String::new(),
+            scroll_px: px(0.),
+            last_mouse_position: None,
+            next_link_id: 0,
+            selection_phase: SelectionPhase::Ended,
+            cmd_pressed: false,
+            hovered_word: false,
+            url_regex,
+            word_regex,
+        }
+    }
+
+    /// Creates a terminal that mirrors one owned by a collaborator.
+    ///
+    /// The replica has no process of its own: its screen is replaced by
+    /// [`Terminal::apply_screen_update`], and its input and size requests
+    /// are sent over `requests_tx`. Both are dropped while `read_only`.
+    pub fn replica(requests_tx: UnboundedSender<ReplicaRequest>, read_only: bool) -> Self {
+        let (events_tx, _) = unbounded();
+        let config = Config {
+            scrolling_history: 0,
+            ..Default::default()
+        };
+        let term = Term::new(config, &TerminalSize::default(), ZedListener(events_tx));
+        Self::new(
+            TerminalType::Replica {
+                requests_tx,
+                requested_size: None,
+                read_only,
+                mode: TermMode::default(),
+                title: String::new(),
+            },
+            Arc::new(FairMutex::new(term)),
+        )
+    }
+
+    pub fn is_replica(&self) -> bool {
+        matches!(self.terminal_type, TerminalType::Replica { .. })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self.terminal_type,
+            TerminalType::Replica {
+                read_only: true,
+                ..
+            }
+        )
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool, cx: &mut ModelContext<Self>) {
+        if let TerminalType::Replica {
+            read_only: current,
+            requested_size,
+            ..
+        } = &mut self.terminal_type
+        {
+            if *current != read_only {
+                *current = read_only;
+                // Ask for a size again the next time the replica is laid out.
+                *requested_size = None;
+                cx.notify();
+            }
+        }
+    }
+
+    /// Limits the size of a PTY's grid, regardless of the size of the view
+    /// displaying it.
+    pub fn set_size_limit(&mut self, limit: Option<GridSize>) {
+        if let TerminalType::Pty { size_limit, .. } = &mut self.terminal_type {
+            if *size_limit != limit {
+                *size_limit = limit;
+                self.events
+                    .push_back(InternalEvent::Resize(self.last_content.size));
+            }
+        }
+    }
+
+    /// Captures the visible screen, for replicating it to collaborators.
+    pub fn screen_snapshot(&self) -> ScreenSnapshot {
+        let term = self.term.lock();
+        let grid = term.grid();
+        let size = GridSize::of(grid);
+        let lines = (0..size.rows)
+            .map(|line| {
+                let row = &grid[Line(line as i32)];
+                let cells = (0..size.columns)
+                    .map(|column| &row[Column(column)])
+                    .collect::<Vec<_>>();
+                serde_json::to_vec(&cells).unwrap_or_default()
+            })
+            .collect();
+
+        ScreenSnapshot {
+            size,
+            lines,
+            cursor: grid.cursor.point,
+            mode: term.mode().bits(),
+            title: self.title(false),
+            breadcrumb_text: self.breadcrumb_text.clone(),
+        }
+    }
+
+    /// Replaces the lines of a replica's screen with those sent by its owner.
+    pub fn apply_screen_update(
+        &mut self,
+        update: ScreenUpdate,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let TerminalType::Replica { mode, title, .. } = &mut self.terminal_type else {
+            return Err(anyhow!("only replica terminals can receive screen updates"));
+        };
+        *mode = TermMode::from_bits_truncate(update.mode);
+        let title_changed = *title != update.title;
+        *title = update.title;
+
+        let mut term = self.term.lock();
+        if GridSize::of(&*term) != update.size {
+            term.resize(update.size);
+        }
+        for (line, encoded) in update.lines {
+            if line >= update.size.rows {
+                continue;
+            }
+            let cells: Vec<Cell> = serde_json::from_slice(&encoded)?;
+            let row = &mut term.grid_mut()[Line(line as i32)];
+            for (column, cell) in cells.into_iter().take(update.size.columns).enumerate() {
+                row[Column(column)] = cell;
+            }
+        }
+        term.grid_mut().cursor.point = AlacPoint::new(
+            Line(cmp::min(update.cursor.line.0, update.size.rows as i32 - 1).max(0)),
+            Column(cmp::min(
+                update.cursor.column.0,
+                update.size.columns.saturating_sub(1),
+            )),
+        );
+        drop(term);
+
+        cx.emit(Event::Wakeup);
+        if title_changed {
+            cx.emit(Event::TitleChanged);
+        }
+        if self.breadcrumb_text != update.breadcrumb_text {
+            self.breadcrumb_text = update.breadcrumb_text;
+            cx.emit(Event::BreadcrumbsChanged);
+        }
+        Ok(())
+    }
+
     fn process_event(&mut self, event: &AlacTermEvent, cx: &mut ModelContext<Self>) {
         match event {
             AlacTermEvent::Title(title) => {
@@ -613,9 +852,18 @@ impl Terminal {
 
     /// Updates the cached process info, returns whether the Zed-relevant info has changed
     fn update_process_info(&mut self) -> bool {
-        let mut pid = unsafe { libc::tcgetpgrp(self.shell_fd as i32) };
+        let TerminalType::Pty {
+            shell_pid,
+            shell_fd,
+            ..
+        } = self.terminal_type
+        else {
+            return false;
+        };
+
+        let mut pid = unsafe { libc::tcgetpgrp(shell_fd as i32) };
         if pid < 0 {
-            pid = self.shell_pid as i32;
+            pid = shell_pid as i32;
         }
 
         if let Some(process_info) = LocalProcessInfo::with_root_pid(pid as u32) {
@@ -661,9 +909,44 @@ impl Terminal {
 
                 self.last_content.size = new_size.clone();
 
-                self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
+                match &mut self.terminal_type {
+                    TerminalType::Pty {
+                        pty_tx, size_limit, ..
+                    } => {
+                        if let Some(limit) = size_limit {
+                            let limit = GridSize::of(&new_size).min(*limit);
+                            new_size.size.height = cmp::min(
+                                new_size.height(),
+                                new_size.line_height * limit.rows as f32,
+                            );
+                            new_size.size.width = cmp::min(
+                                new_size.width(),
+                                new_size.cell_width * limit.columns as f32,
+                            );
+                        }
+
+                        pty_tx.0.send(Msg::Resize(new_size.into())).ok();
 
-                term.resize(new_size);
+                        term.resize(new_size);
+                    }
+                    TerminalType::Replica {
+                        requests_tx,
+                        requested_size,
+                        read_only,
+                        ..
+                    } => {
+                        // The grid is sized by the terminal's owner, who
+                        // accounts for the viewport of every collaborator
+                        // that can write to it.
+                        let size = GridSize::of(&new_size);
+                        if !*read_only && *requested_size != Some(size) {
+                            *requested_size = Some(size);
+                            requests_tx
+                                .unbounded_send(ReplicaRequest::Resize(size))
+                                .ok();
+                        }
+                    }
+                }
             }
             InternalEvent::Clear => {
                 // Clear back buffer
@@ -888,6 +1171,17 @@ impl Terminal {
         &self.last_content
     }
 
+    /// Returns the text of the last `max_lines` lines of the terminal,
+    /// including the scrollback history, without trailing blank lines.
+    pub fn last_lines(&self, max_lines: usize) -> String {
+        let term = self.term.lock();
+        let end = AlacPoint::new(term.bottommost_line(), term.last_column());
+        let start_line = Line(term.bottommost_line().0 + 1 - max_lines as i32);
+        let start = AlacPoint::new(cmp::max(start_line, term.topmost_line()), Column(0));
+        let text = term.bounds_to_string(start, end);
+        text.trim_end().to_string()
+    }
+
     //To test:
     //- Activate match on terminal (scrolling and selection)
     //- Editor search snapping behavior
@@ -944,11 +1238,24 @@ impl Terminal {
 
     ///Write the Input payload to the tty.
     fn write_to_pty(&self, input: String) {
-        self.pty_tx.notify(input.into_bytes());
+        self.write_bytes_to_pty(input.into_bytes());
     }
 
     fn write_bytes_to_pty(&self, input: Vec<u8>) {
-        self.pty_tx.notify(input);
+        match &self.terminal_type {
+            TerminalType::Pty { pty_tx, .. } => pty_tx.notify(input),
+            TerminalType::Replica {
+                requests_tx,
+                read_only,
+                ..
+            } => {
+                if !read_only {
+                    requests_tx
+                        .unbounded_send(ReplicaRequest::Input(input))
+                        .ok();
+                }
+            }
+        }
     }
 
     pub fn input(&mut self, input: String) {
@@ -1006,6 +1313,15 @@ impl Terminal {
         }
 
         self.last_content = Self::make_content(&terminal, &self.last_content);
+
+        // A replica's grid never sees the escape sequences that set its
+        // modes, so they're taken from its owner instead.
+        if let TerminalType::Replica { mode, .. } = &self.terminal_type {
+            self.last_content.mode = *mode;
+            if !mode.contains(TermMode::SHOW_CURSOR) {
+                self.last_content.cursor.shape = CursorShape::Hidden;
+            }
+        }
     }
 
     fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
@@ -1082,7 +1398,7 @@ impl Terminal {
 
             if self.mouse_changed(point, side) {
                 if let Some(bytes) = mouse_moved_report(point, e, self.last_content.mode) {
-                    self.pty_tx.notify(bytes);
+                    self.write_bytes_to_pty(bytes);
                 }
             }
         } else if self.cmd_pressed {
@@ -1156,7 +1472,7 @@ impl Terminal {
             if let Some(bytes) =
                 mouse_button_report(point, e.button, e.modifiers, true, self.last_content.mode)
             {
-                self.pty_tx.notify(bytes);
+                self.write_bytes_to_pty(bytes);
             }
         } else if e.button == MouseButton::Left {
             let position = e.position - origin;
@@ -1203,7 +1519,7 @@ impl Terminal {
             if let Some(bytes) =
                 mouse_button_report(point, e.button, e.modifiers, false, self.last_content.mode)
             {
-                self.pty_tx.notify(bytes);
+                self.write_bytes_to_pty(bytes);
             }
         } else {
             if e.button == MouseButton::Left && setting.copy_on_select {
@@ -1242,7 +1558,7 @@ impl Terminal {
                     scroll_report(point, scroll_lines as i32, e, self.last_content.mode)
                 {
                     for scroll in scrolls {
-                        self.pty_tx.notify(scroll);
+                        self.write_bytes_to_pty(scroll);
                     }
                 };
             } else if self
@@ -1251,7 +1567,7 @@ impl Terminal {
                 .contains(TermMode::ALT_SCREEN | TermMode::ALTERNATE_SCROLL)
                 && !e.shift
             {
-                self.pty_tx.notify(alt_scroll(scroll_lines))
+                self.write_bytes_to_pty(alt_scroll(scroll_lines))
             } else {
                 if scroll_lines != 0 {
                     let scroll = AlacScroll::Delta(scroll_lines);
@@ -1307,6 +1623,14 @@ impl Terminal {
     }
 
     pub fn title(&self, truncate: bool) -> String {
+        if let TerminalType::Replica { title, .. } = &self.terminal_type {
+            return if truncate {
+                truncate_and_trailoff(title, 50)
+            } else {
+                title.clone()
+            };
+        }
+
         self.foreground_process_info
             .as_ref()
             .map(|fpi| {
@@ -1344,7 +1668,9 @@ impl Terminal {
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        self.pty_tx.0.send(Msg::Shutdown).ok();
+        if let TerminalType::Pty { pty_tx, .. } = &self.terminal_type {
+            pty_tx.0.send(Msg::Shutdown).ok();
+        }
     }
 }
 
@@ -1487,11 +1813,13 @@ mod tests {
         index::{Column, Line, Point as AlacPoint},
         term::cell::Cell,
     };
-    use gpui::{point, size, Pixels};
+    use futures::channel::mpsc::unbounded;
+    use gpui::{point, size, Context, Pixels, TestAppContext};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        content_index_for_mouse, rgb_for_index, GridSize, IndexedCell, ReplicaRequest,
+        ScreenUpdate, Terminal, TerminalContent, TerminalSize,
     };
 
     #[test]
@@ -1503,6 +1831,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_replica_input() {
+        let (requests_tx, mut requests_rx) = unbounded();
+        let mut terminal = Terminal::replica(requests_tx, false);
+        terminal.input("ls\r".to_string());
+        assert_eq!(
+            requests_rx.try_next().unwrap(),
+            Some(ReplicaRequest::Input(b"ls\r".to_vec()))
+        );
+
+        let (requests_tx, mut requests_rx) = unbounded();
+        let mut terminal = Terminal::replica(requests_tx, true);
+        terminal.input("rm -rf /\r".to_string());
+        terminal.paste("text");
+        assert!(requests_rx.try_next().is_err());
+    }
+
+    #[gpui::test]
+    fn test_replica_screen_updates(cx: &mut TestAppContext) {
+        let (requests_tx, _requests_rx) = unbounded();
+        let source = cx.new_model(|_| Terminal::replica(requests_tx.clone(), false));
+        let replica = cx.new_model(|_| Terminal::replica(requests_tx, false));
+
+        let size = GridSize {
+            rows: 3,
+            columns: 4,
+        };
+        source.update(cx, |source, _| {
+            let mut term = source.term.lock();
+            term.resize(size);
+            for (column, c) in "abc".chars().enumerate() {
+                term.grid_mut()[Line(1)][Column(column)].c = c;
+            }
+            term.grid_mut().cursor.point = AlacPoint::new(Line(1), Column(3));
+        });
+
+        let snapshot = source.read_with(cx, |source, _| source.screen_snapshot());
+        assert_eq!(snapshot.size, size);
+        replica
+            .update(cx, |replica, cx| {
+                replica.apply_screen_update(
+                    ScreenUpdate {
+                        size: snapshot.size,
+                        lines: snapshot.lines.iter().cloned().enumerate().collect(),
+                        cursor: snapshot.cursor,
+                        mode: snapshot.mode,
+                        title: "zsh".to_string(),
+                        breadcrumb_text: String::new(),
+                    },
+                    cx,
+                )
+            })
+            .unwrap();
+
+        replica.update(cx, |replica, cx| {
+            replica.sync(cx);
+            assert_eq!(replica.screen_snapshot().lines, snapshot.lines);
+            assert_eq!(
+                replica.last_content.cursor.point,
+                AlacPoint::new(Line(1), Column(3))
+            );
+            assert_eq!(replica.title(false), "zsh");
+            let text = replica
+                .last_content
+                .cells
+                .iter()
+                .filter(|cell| cell.point.line == Line(1))
+                .map(|cell| cell.c)
+                .collect::<String>();
+            assert_eq!(text, "abc ");
+        });
+    }
+
     #[test]
     fn test_mouse_to_cell_test() {
         let mut rng = thread_rng();
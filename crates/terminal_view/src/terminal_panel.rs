@@ -256,6 +256,10 @@ impl TerminalPanel {
         }
     }
 
+    pub fn pane(&self) -> &View<Pane> {
+        &self.pane
+    }
+
     pub fn open_terminal(
         workspace: &mut Workspace,
         action: &workspace::OpenTerminal,
@@ -299,7 +303,7 @@ impl TerminalPanel {
                 let window = cx.window_handle();
                 if let Some(terminal) = workspace.project().update(cx, |project, cx| {
                     project
-                        .create_terminal(working_directory, window, cx)
+                        .create_terminal(working_directory, Some(window), cx)
                         .log_err()
                 }) {
                     let terminal = Box::new(cx.new_view(|cx| {
@@ -2,13 +2,14 @@ mod persistence;
 pub mod terminal_element;
 pub mod terminal_panel;
 
+use client::proto::{self, PeerId};
 use collections::HashSet;
 use editor::{scroll::Autoscroll, Editor};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
-    div, impl_actions, overlay, AnyElement, AppContext, DismissEvent, EventEmitter, FocusHandle,
-    FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton, MouseDownEvent, Pixels,
-    Render, Styled, Subscription, Task, View, VisualContext, WeakView,
+    actions, div, impl_actions, overlay, AnyElement, AppContext, DismissEvent, EventEmitter,
+    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
+    MouseDownEvent, Pixels, Render, Styled, Subscription, Task, View, VisualContext, WeakView,
 };
 use language::Bias;
 use persistence::TERMINAL_DB;
@@ -25,11 +26,11 @@ use terminal_element::TerminalElement;
 use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label};
 use util::{paths::PathLikeWithPosition, ResultExt};
 use workspace::{
-    item::{BreadcrumbText, Item, ItemEvent},
+    item::{BreadcrumbText, FollowEvent, FollowableItem, Item, ItemEvent},
     notifications::NotifyResultExt,
-    register_deserializable_item,
+    register_deserializable_item, register_followable_item,
     searchable::{SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
-    CloseActiveItem, NewCenterTerminal, OpenVisible, Pane, ToolbarItemLocation, Workspace,
+    CloseActiveItem, NewCenterTerminal, OpenVisible, Pane, ToolbarItemLocation, ViewId, Workspace,
     WorkspaceId,
 };
 
@@ -60,11 +61,17 @@ pub struct SendKeystroke(String);
 
 impl_actions!(terminal, [SendText, SendKeystroke]);
 
+actions!(
+    terminal,
+    [ShareWithCollaborators, StopSharingWithCollaborators]
+);
+
 pub fn init(cx: &mut AppContext) {
     terminal_panel::init(cx);
     terminal::init(cx);
 
     register_deserializable_item::<TerminalView>(cx);
+    register_followable_item::<TerminalView>(cx);
 
     cx.observe_new_views(|workspace: &mut Workspace, _| {
         workspace.register_action(TerminalView::deploy);
@@ -86,6 +93,7 @@ pub struct TerminalView {
     blink_epoch: usize,
     can_navigate_to_selected_word: bool,
     workspace_id: WorkspaceId,
+    remote_id: Option<ViewId>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -114,7 +122,7 @@ impl TerminalView {
         let terminal = workspace
             .project()
             .update(cx, |project, cx| {
-                project.create_terminal(working_directory, window, cx)
+                project.create_terminal(working_directory, Some(window), cx)
             })
             .notify_err(workspace, cx);
 
@@ -314,6 +322,7 @@ impl TerminalView {
             blink_epoch: 0,
             can_navigate_to_selected_word: false,
             workspace_id,
+            remote_id: None,
             _subscriptions: vec![focus_in, focus_out],
         }
     }
@@ -336,7 +345,18 @@ impl TerminalView {
         position: gpui::Point<Pixels>,
         cx: &mut ViewContext<Self>,
     ) {
+        let is_shared = self.is_shared_with_collaborators(cx);
         let context_menu = ContextMenu::build(cx, |menu, _| {
+            let menu = match is_shared {
+                Some(false) => {
+                    menu.action("Share with Collaborators", Box::new(ShareWithCollaborators))
+                }
+                Some(true) => menu.action(
+                    "Stop Sharing with Collaborators",
+                    Box::new(StopSharingWithCollaborators),
+                ),
+                None => menu,
+            };
             menu.action("Clear", Box::new(Clear))
                 .action("Close", Box::new(CloseActiveItem { save_intent: None }))
         });
@@ -385,6 +405,48 @@ impl TerminalView {
         cx.notify();
     }
 
+    /// Lets guests of the shared project open this terminal and type into it.
+    fn share_with_collaborators(&mut self, _: &ShareWithCollaborators, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let terminal = self.terminal.clone();
+        workspace.update(cx, |workspace, cx| {
+            let shared = workspace
+                .project()
+                .update(cx, |project, cx| project.share_terminal(&terminal, cx))
+                .notify_err(workspace, cx);
+            // Let followers open the terminal if they're looking at it.
+            if shared.is_some() {
+                workspace.update_active_view_for_followers(cx);
+            }
+        });
+        cx.notify();
+    }
+
+    fn stop_sharing_with_collaborators(
+        &mut self,
+        _: &StopSharingWithCollaborators,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(workspace) = self.workspace.upgrade() {
+            workspace
+                .read(cx)
+                .project()
+                .clone()
+                .update(cx, |project, cx| {
+                    project.unshare_terminal(&self.terminal, cx)
+                });
+            cx.notify();
+        }
+    }
+
+    fn is_shared_with_collaborators(&self, cx: &AppContext) -> Option<bool> {
+        let project = self.workspace.upgrade()?.read(cx).project().read(cx);
+        (project.is_local() && project.remote_id().is_some())
+            .then(|| project.shared_terminal_id(&self.terminal).is_some())
+    }
+
     pub fn should_show_cursor(&self, focused: bool, cx: &mut gpui::ViewContext<Self>) -> bool {
         //Don't blink the cursor when not focused, blinking is disabled, or paused
         if !focused
@@ -708,6 +770,8 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::copy))
             .on_action(cx.listener(TerminalView::paste))
             .on_action(cx.listener(TerminalView::clear))
+            .on_action(cx.listener(TerminalView::share_with_collaborators))
+            .on_action(cx.listener(TerminalView::stop_sharing_with_collaborators))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
             .on_key_down(cx.listener(Self::key_down))
@@ -843,7 +907,7 @@ impl Item for TerminalView {
                 });
 
             let terminal = project.update(&mut cx, |project, cx| {
-                project.create_terminal(cwd, window, cx)
+                project.create_terminal(cwd, Some(window), cx)
             })??;
             pane.update(&mut cx, |_, cx| {
                 cx.new_view(|cx| TerminalView::new(terminal, workspace, workspace_id, cx))
@@ -867,6 +931,90 @@ impl Item for TerminalView {
     }
 }
 
+impl FollowableItem for TerminalView {
+    fn remote_id(&self) -> Option<ViewId> {
+        self.remote_id
+    }
+
+    fn to_state_proto(&self, cx: &WindowContext) -> Option<proto::view::Variant> {
+        let workspace = self.workspace.upgrade()?;
+        let terminal_id = workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .shared_terminal_id(&self.terminal)?;
+        Some(proto::view::Variant::Terminal(proto::view::Terminal {
+            terminal_id,
+        }))
+    }
+
+    fn from_state_proto(
+        pane: View<Pane>,
+        workspace: View<Workspace>,
+        remote_id: ViewId,
+        state: &mut Option<proto::view::Variant>,
+        cx: &mut WindowContext,
+    ) -> Option<Task<anyhow::Result<View<Self>>>> {
+        let Some(proto::view::Variant::Terminal(_)) = state else {
+            return None;
+        };
+        let Some(proto::view::Variant::Terminal(state)) = state.take() else {
+            unreachable!()
+        };
+
+        let workspace_id = workspace.read(cx).database_id();
+        let project = workspace.read(cx).project().clone();
+        let open_terminal = project.update(cx, |project, cx| {
+            project.terminal_for_id(state.terminal_id, cx)
+        });
+        let workspace = workspace.downgrade();
+        let pane = pane.downgrade();
+        Some(cx.spawn(|mut cx| async move {
+            let terminal = open_terminal.await?;
+            pane.update(&mut cx, |pane, cx| {
+                let existing_view = pane
+                    .items_of_type::<Self>()
+                    .find(|view| view.read(cx).terminal == terminal);
+                existing_view.unwrap_or_else(|| {
+                    cx.new_view(|cx| {
+                        let mut view = TerminalView::new(terminal, workspace, workspace_id, cx);
+                        view.remote_id = Some(remote_id);
+                        view
+                    })
+                })
+            })
+        }))
+    }
+
+    fn to_follow_event(_: &Self::Event) -> Option<FollowEvent> {
+        None
+    }
+
+    fn add_event_to_update_proto(
+        &self,
+        _: &Self::Event,
+        _: &mut Option<proto::update_view::Variant>,
+        _: &WindowContext,
+    ) -> bool {
+        false
+    }
+
+    fn apply_update_proto(
+        &mut self,
+        _: &Model<Project>,
+        _: proto::update_view::Variant,
+        _: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn is_project_item(&self, _: &WindowContext) -> bool {
+        true
+    }
+
+    fn set_leader_peer_id(&mut self, _: Option<PeerId>, _: &mut ViewContext<Self>) {}
+}
+
 impl SearchableItem for TerminalView {
     type Match = RangeInclusive<Point>;
 
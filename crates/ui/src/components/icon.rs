@@ -94,6 +94,7 @@ pub enum IconName {
     PageDown,
     PageUp,
     Plus,
+    PreserveCase,
     Public,
     Quote,
     Replace,
@@ -186,6 +187,7 @@ impl IconName {
             IconName::PageDown => "icons/page_down.svg",
             IconName::PageUp => "icons/page_up.svg",
             IconName::Plus => "icons/plus.svg",
+            IconName::PreserveCase => "icons/preserve_case.svg",
             IconName::Public => "icons/public.svg",
             IconName::Quote => "icons/quote.svg",
             IconName::Replace => "icons/replace.svg",
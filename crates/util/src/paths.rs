@@ -11,6 +11,7 @@ lazy_static::lazy_static! {
     pub static ref CONFIG_DIR: PathBuf = HOME.join(".config").join("zed");
     pub static ref CONVERSATIONS_DIR: PathBuf = CONFIG_DIR.join("conversations");
     pub static ref EMBEDDINGS_DIR: PathBuf = CONFIG_DIR.join("embeddings");
+    pub static ref PROMPTS_DIR: PathBuf = CONFIG_DIR.join("prompts");
     pub static ref THEMES_DIR: PathBuf = CONFIG_DIR.join("themes");
     pub static ref LOGS_DIR: PathBuf = if cfg!(target_os = "macos") {
         HOME.join("Library/Logs/Zed")
@@ -27,6 +28,7 @@ lazy_static::lazy_static! {
     pub static ref COPILOT_DIR: PathBuf = SUPPORT_DIR.join("copilot");
     pub static ref DEFAULT_PRETTIER_DIR: PathBuf = SUPPORT_DIR.join("prettier");
     pub static ref DB_DIR: PathBuf = SUPPORT_DIR.join("db");
+    pub static ref TRASH_DIR: PathBuf = SUPPORT_DIR.join("trash");
     pub static ref CRASHES_DIR: PathBuf = if cfg!(target_os = "macos") {
         HOME.join("Library/Logs/DiagnosticReports")
     } else {
@@ -43,6 +45,9 @@ lazy_static::lazy_static! {
     pub static ref LOG: PathBuf = LOGS_DIR.join("Zed.log");
     pub static ref OLD_LOG: PathBuf = LOGS_DIR.join("Zed.log.old");
     pub static ref LOCAL_SETTINGS_RELATIVE_PATH: &'static Path = Path::new(".zed/settings.json");
+    pub static ref LOCAL_TRASH_RELATIVE_PATH: &'static Path = Path::new(".zed/trash");
+    pub static ref LOCAL_PROMPTS_RELATIVE_PATH: &'static Path = Path::new(".zed/prompts");
+    pub static ref LOCAL_RULES_RELATIVE_PATH: &'static Path = Path::new(".zed/rules.md");
 }
 
 pub trait PathExt {
@@ -198,6 +198,15 @@ pub trait Item: FocusableView + EventEmitter<Self::Event> {
         None
     }
 
+    /// Persists the unsaved changes of this item, so that [`Item::deserialize`] can restore
+    /// them after quitting. Returns `None` if the item cannot preserve its unsaved changes.
+    fn serialize_unsaved_changes(
+        &mut self,
+        _cx: &mut ViewContext<Self>,
+    ) -> Option<Task<Result<()>>> {
+        None
+    }
+
     fn deserialize(
         _project: Model<Project>,
         _workspace: WeakView<Workspace>,
@@ -277,6 +286,7 @@ pub trait ItemHandle: 'static + Send {
     fn breadcrumb_location(&self, cx: &AppContext) -> ToolbarItemLocation;
     fn breadcrumbs(&self, theme: &Theme, cx: &AppContext) -> Option<Vec<BreadcrumbText>>;
     fn serialized_item_kind(&self) -> Option<&'static str>;
+    fn serialize_unsaved_changes(&self, cx: &mut WindowContext) -> Option<Task<Result<()>>>;
     fn show_toolbar(&self, cx: &AppContext) -> bool;
     fn pixel_position_of_cursor(&self, cx: &AppContext) -> Option<Point<Pixels>>;
 }
@@ -617,6 +627,10 @@ impl<T: Item> ItemHandle for View<T> {
         T::serialized_item_kind()
     }
 
+    fn serialize_unsaved_changes(&self, cx: &mut WindowContext) -> Option<Task<Result<()>>> {
+        self.update(cx, |item, cx| item.serialize_unsaved_changes(cx))
+    }
+
     fn show_toolbar(&self, cx: &AppContext) -> bool {
         self.read(cx).show_toolbar()
     }
@@ -794,6 +808,7 @@ pub mod test {
         pub is_dirty: bool,
         pub is_singleton: bool,
         pub has_conflict: bool,
+        pub preserves_unsaved_changes: bool,
         pub project_items: Vec<Model<TestProjectItem>>,
         pub nav_history: Option<ItemNavHistory>,
         pub tab_descriptions: Option<Vec<&'static str>>,
@@ -846,6 +861,7 @@ pub mod test {
                 reload_count: 0,
                 is_dirty: false,
                 has_conflict: false,
+                preserves_unsaved_changes: false,
                 project_items: Vec::new(),
                 is_singleton: true,
                 nav_history: None,
@@ -882,6 +898,11 @@ pub mod test {
             self
         }
 
+        pub fn with_preserved_unsaved_changes(mut self, preserves: bool) -> Self {
+            self.preserves_unsaved_changes = preserves;
+            self
+        }
+
         pub fn with_project_items(mut self, items: &[Model<TestProjectItem>]) -> Self {
             self.project_items.clear();
             self.project_items.extend(items.iter().cloned());
@@ -1051,6 +1072,14 @@ pub mod test {
             Some("TestItem")
         }
 
+        fn serialize_unsaved_changes(
+            &mut self,
+            _: &mut ViewContext<Self>,
+        ) -> Option<Task<anyhow::Result<()>>> {
+            self.preserves_unsaved_changes
+                .then(|| Task::ready(anyhow::Ok(())))
+        }
+
         fn deserialize(
             _project: Model<Project>,
             _workspace: WeakView<Workspace>,
@@ -5,6 +5,7 @@ use crate::{
     NewCenterTerminal, NewFile, NewSearch, OpenVisible, SplitDirection, ToggleZoom, Workspace,
 };
 use anyhow::Result;
+use call::ActiveCall;
 use collections::{HashMap, HashSet, VecDeque};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
@@ -32,7 +33,7 @@ use std::{
 use theme::ThemeSettings;
 
 use ui::{
-    prelude::*, right_click_menu, ButtonSize, Color, IconButton, IconButtonShape, IconName,
+    prelude::*, right_click_menu, Avatar, ButtonSize, Color, IconButton, IconButtonShape, IconName,
     IconSize, Indicator, Label, Tab, TabBar, TabPosition, Tooltip,
 };
 use ui::{v_flex, ContextMenu};
@@ -1274,6 +1275,34 @@ impl Pane {
         });
     }
 
+    /// Renders the avatars of the collaborators who have the item's file open.
+    fn render_tab_collaborators(
+        &self,
+        item: &dyn ItemHandle,
+        cx: &WindowContext,
+    ) -> Option<impl IntoElement> {
+        let project_path = item.project_path(cx)?;
+        let project_id = self.project.read(cx).remote_id()?;
+        let room = ActiveCall::try_global(cx)?.read(cx).room()?.read(cx);
+        let avatars = room
+            .remote_participants_at_path(project_id, &project_path)
+            .map(|participant| {
+                let player_color = cx
+                    .theme()
+                    .players()
+                    .color_for_participant(participant.participant_index.0);
+                Avatar::new(participant.user.avatar_uri.clone())
+                    .size(rems(0.75))
+                    .border_color(player_color.cursor)
+            })
+            .collect::<Vec<_>>();
+        if avatars.is_empty() {
+            None
+        } else {
+            Some(h_flex().gap_0p5().children(avatars))
+        }
+    }
+
     fn render_tab(
         &self,
         ix: usize,
@@ -1371,7 +1400,12 @@ impl Pane {
                             .detach_and_log_err(cx);
                     })),
             )
-            .child(label);
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(label)
+                    .children(self.render_tab_collaborators(item.as_ref(), cx)),
+            );
 
         let single_entry_to_resolve = {
             let item_entries = self.items[ix].project_entry_ids(cx);
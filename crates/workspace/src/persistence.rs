@@ -14,7 +14,7 @@ use sqlez::{
 use util::{unzip_option, ResultExt};
 use uuid::Uuid;
 
-use crate::WorkspaceId;
+use crate::{SessionId, WorkspaceId};
 
 use model::{
     GroupId, PaneId, SerializedItem, SerializedPane, SerializedPaneGroup, SerializedWorkspace,
@@ -155,6 +155,13 @@ define_connection! {
     //   window_width: Option<f32>, // WindowBounds::Fixed RectF width
     //   window_height: Option<f32>, // WindowBounds::Fixed RectF height
     //   display: Option<Uuid>, // Display id
+    //   session_id: Option<usize>, // References sessions table
+    // )
+    //
+    // sessions(
+    //   session_id: usize, // Primary key for sessions
+    //   name: String, // Unique, user facing name of the session
+    //   timestamp: String, // UTC YYYY-MM-DD HH:MM:SS, last time the session was active
     // )
     //
     // pane_groups(
@@ -291,33 +298,36 @@ define_connection! {
     // Add pane group flex data
     sql!(
         ALTER TABLE pane_groups ADD COLUMN flexes TEXT;
+    ),
+    // Add sessions, so that the windows open on quit can be restored together
+    sql!(
+        CREATE TABLE sessions(
+            session_id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            timestamp TEXT DEFAULT CURRENT_TIMESTAMP NOT NULL
+        ) STRICT;
+
+        ALTER TABLE workspaces ADD COLUMN session_id INTEGER REFERENCES sessions(session_id) ON DELETE SET NULL;
     )
     ];
 }
 
 impl WorkspaceDb {
-    /// Returns a serialized workspace for the given worktree_roots. If the passed array
-    /// is empty, the most recent workspace is returned instead. If no workspace for the
-    /// passed roots is stored, returns none.
+    /// Returns a serialized workspace for the given worktree_roots. If no workspace for the
+    /// passed roots is stored, returns none. Workspaces without any roots are never returned,
+    /// they can only be restored through their session.
     pub(crate) fn workspace_for_roots<P: AsRef<Path>>(
         &self,
         worktree_roots: &[P],
     ) -> Option<SerializedWorkspace> {
         let workspace_location: WorkspaceLocation = worktree_roots.into();
 
-        // Note that we re-assign the workspace_id here in case it's empty
-        // and we've grabbed the most recent workspace
-        let (workspace_id, workspace_location, bounds, display, docks): (
-            WorkspaceId,
-            WorkspaceLocation,
-            Option<SerializedWindowsBounds>,
-            Option<Uuid>,
-            DockStructure,
-        ) = self
+        let row = self
             .select_row_bound(sql! {
                 SELECT
                     workspace_id,
                     workspace_location,
+                    session_id,
                     window_state,
                     window_x,
                     window_y,
@@ -341,9 +351,62 @@ impl WorkspaceDb {
             .warn_on_err()
             .flatten()?;
 
+        self.serialized_workspace_for_row(row)
+    }
+
+    /// Returns the serialized workspace with the given id, including workspaces which
+    /// have no worktree roots.
+    pub(crate) fn workspace_for_id(
+        &self,
+        workspace_id: WorkspaceId,
+    ) -> Option<SerializedWorkspace> {
+        let row = self
+            .select_row_bound(sql! {
+                SELECT
+                    workspace_id,
+                    workspace_location,
+                    session_id,
+                    window_state,
+                    window_x,
+                    window_y,
+                    window_width,
+                    window_height,
+                    display,
+                    left_dock_visible,
+                    left_dock_active_panel,
+                    left_dock_zoom,
+                    right_dock_visible,
+                    right_dock_active_panel,
+                    right_dock_zoom,
+                    bottom_dock_visible,
+                    bottom_dock_active_panel,
+                    bottom_dock_zoom
+                FROM workspaces
+                WHERE workspace_id = ?
+            })
+            .and_then(|mut prepared_statement| (prepared_statement)(workspace_id))
+            .context("No workspaces found")
+            .warn_on_err()
+            .flatten()?;
+
+        self.serialized_workspace_for_row(row)
+    }
+
+    fn serialized_workspace_for_row(
+        &self,
+        (workspace_id, workspace_location, session_id, bounds, display, docks): (
+            WorkspaceId,
+            Option<WorkspaceLocation>,
+            Option<SessionId>,
+            Option<SerializedWindowsBounds>,
+            Option<Uuid>,
+            DockStructure,
+        ),
+    ) -> Option<SerializedWorkspace> {
         Some(SerializedWorkspace {
             id: workspace_id,
-            location: workspace_location.clone(),
+            location: workspace_location.unwrap_or_default(),
+            session_id,
             center_group: self
                 .get_center_pane_group(workspace_id)
                 .context("Getting center group")
@@ -365,9 +428,17 @@ impl WorkspaceDb {
                     DELETE FROM panes WHERE workspace_id = ?1;))?(workspace.id)
                 .expect("Clearing old panes");
 
+                // Workspaces without any roots are stored without a location, so that
+                // several of them can be restored as part of the same session.
+                let location = if workspace.location.paths().is_empty() {
+                    None
+                } else {
+                    Some(&workspace.location)
+                };
+
                 conn.exec_bound(sql!(
                     DELETE FROM workspaces WHERE workspace_location = ? AND workspace_id != ?
-                ))?((&workspace.location, workspace.id.clone()))
+                ))?((location, workspace.id.clone()))
                 .context("clearing out old locations")?;
 
                 // Upsert
@@ -384,9 +455,10 @@ impl WorkspaceDb {
                         bottom_dock_visible,
                         bottom_dock_active_panel,
                         bottom_dock_zoom,
+                        session_id,
                         timestamp
                     )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, CURRENT_TIMESTAMP)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, CURRENT_TIMESTAMP)
                     ON CONFLICT DO
                     UPDATE SET
                         workspace_location = ?2,
@@ -399,8 +471,14 @@ impl WorkspaceDb {
                         bottom_dock_visible = ?9,
                         bottom_dock_active_panel = ?10,
                         bottom_dock_zoom = ?11,
+                        session_id = ?12,
                         timestamp = CURRENT_TIMESTAMP
-                ))?((workspace.id, &workspace.location, workspace.docks))
+                ))?((
+                    workspace.id,
+                    location,
+                    workspace.docks,
+                    workspace.session_id,
+                ))
                 .context("Updating workspace")?;
 
                 // Save center pane group
@@ -420,6 +498,60 @@ impl WorkspaceDb {
         }
     }
 
+    /// Marks the session with the given name as the most recently active one,
+    /// creating it if it doesn't exist yet.
+    pub async fn activate_session(&self, name: String) -> Result<SessionId> {
+        self.write(move |conn| {
+            conn.select_row_bound::<String, SessionId>(sql!(
+                INSERT INTO sessions(name) VALUES (?)
+                ON CONFLICT DO
+                UPDATE SET timestamp = CURRENT_TIMESTAMP
+                RETURNING session_id
+            ))?(name)?
+            .context("Couldn't retrieve session_id from activated session")
+        })
+        .await
+    }
+
+    query! {
+        pub fn sessions() -> Result<Vec<(SessionId, String)>> {
+            SELECT session_id, name
+            FROM sessions
+            ORDER BY timestamp DESC
+        }
+    }
+
+    query! {
+        pub fn last_session() -> Result<Option<(SessionId, String)>> {
+            SELECT session_id, name
+            FROM sessions
+            ORDER BY timestamp DESC
+            LIMIT 1
+        }
+    }
+
+    // Returns the workspaces of a session, the most recently used one last
+    query! {
+        pub(crate) fn session_workspace_ids(session_id: SessionId) -> Result<Vec<WorkspaceId>> {
+            SELECT workspace_id
+            FROM workspaces
+            WHERE session_id = ?
+            ORDER BY timestamp
+        }
+    }
+
+    // Detaches a workspace from its session when its window is closed. Workspaces
+    // without any roots can't be reopened from anywhere else, so they're dropped.
+    query! {
+        pub(crate) async fn remove_from_session(workspace_id: WorkspaceId) -> Result<()> {
+            DELETE FROM workspaces
+            WHERE workspace_id = ?1 AND workspace_location IS NULL;
+            UPDATE workspaces
+            SET session_id = NULL
+            WHERE workspace_id = ?1
+        }
+    }
+
     query! {
         fn recent_workspaces() -> Result<Vec<(WorkspaceId, WorkspaceLocation)>> {
             SELECT workspace_id, workspace_location
@@ -746,6 +878,7 @@ mod tests {
         let mut workspace_1 = SerializedWorkspace {
             id: 1,
             location: (["/tmp", "/tmp2"]).into(),
+            session_id: None,
             center_group: Default::default(),
             bounds: Default::default(),
             display: Default::default(),
@@ -755,6 +888,7 @@ mod tests {
         let workspace_2 = SerializedWorkspace {
             id: 2,
             location: (["/tmp"]).into(),
+            session_id: None,
             center_group: Default::default(),
             bounds: Default::default(),
             display: Default::default(),
@@ -853,6 +987,7 @@ mod tests {
         let workspace = SerializedWorkspace {
             id: 5,
             location: (["/tmp", "/tmp2"]).into(),
+            session_id: None,
             center_group,
             bounds: Default::default(),
             display: Default::default(),
@@ -881,6 +1016,7 @@ mod tests {
         let workspace_1 = SerializedWorkspace {
             id: 1,
             location: (["/tmp", "/tmp2"]).into(),
+            session_id: None,
             center_group: Default::default(),
             bounds: Default::default(),
             display: Default::default(),
@@ -890,6 +1026,7 @@ mod tests {
         let mut workspace_2 = SerializedWorkspace {
             id: 2,
             location: (["/tmp"]).into(),
+            session_id: None,
             center_group: Default::default(),
             bounds: Default::default(),
             display: Default::default(),
@@ -926,6 +1063,7 @@ mod tests {
         let mut workspace_3 = SerializedWorkspace {
             id: 3,
             location: (&["/tmp", "/tmp2"]).into(),
+            session_id: None,
             center_group: Default::default(),
             bounds: Default::default(),
             display: Default::default(),
@@ -949,6 +1087,54 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_session_workspaces() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_session_workspaces").await);
+
+        let work_session = db.activate_session("Work".to_string()).await.unwrap();
+        let play_session = db.activate_session("Play".to_string()).await.unwrap();
+        assert_ne!(work_session, play_session);
+        assert_eq!(
+            db.activate_session("Work".to_string()).await.unwrap(),
+            work_session
+        );
+
+        let mut workspace_1 = default_workspace(&["/tmp"], &Default::default());
+        workspace_1.id = 1;
+        workspace_1.session_id = Some(work_session);
+        let mut workspace_2 = default_workspace(&[] as &[&str], &Default::default());
+        workspace_2.id = 2;
+        workspace_2.session_id = Some(work_session);
+        let mut workspace_3 = default_workspace(&[] as &[&str], &Default::default());
+        workspace_3.id = 3;
+        workspace_3.session_id = Some(play_session);
+
+        db.save_workspace(workspace_1.clone()).await;
+        db.save_workspace(workspace_2.clone()).await;
+        db.save_workspace(workspace_3.clone()).await;
+
+        // Workspaces without roots don't replace each other, and aren't looked up by roots
+        let mut work_workspaces = db.session_workspace_ids(work_session).unwrap();
+        work_workspaces.sort();
+        assert_eq!(work_workspaces, vec![1, 2]);
+        assert_eq!(db.session_workspace_ids(play_session).unwrap(), vec![3]);
+        assert_eq!(db.workspace_for_id(2).unwrap(), workspace_2);
+        assert_eq!(db.workspace_for_roots(&[] as &[&str]), None);
+
+        // Closing a window keeps its roots around as a recent project, but forgets
+        // workspaces that can't be reopened without their session
+        db.remove_from_session(1).await.unwrap();
+        db.remove_from_session(2).await.unwrap();
+        assert_eq!(
+            db.session_workspace_ids(work_session).unwrap(),
+            Vec::<i64>::new()
+        );
+        assert_eq!(db.workspace_for_id(1).unwrap().session_id, None);
+        assert_eq!(db.workspace_for_id(2), None);
+    }
+
     use crate::persistence::model::SerializedWorkspace;
     use crate::persistence::model::{SerializedItem, SerializedPane, SerializedPaneGroup};
 
@@ -959,6 +1145,7 @@ mod tests {
         SerializedWorkspace {
             id: 4,
             location: workspace_id.into(),
+            session_id: None,
             center_group: center_group.clone(),
             bounds: Default::default(),
             display: Default::default(),
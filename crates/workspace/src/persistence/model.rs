@@ -1,5 +1,7 @@
 use super::SerializedAxis;
-use crate::{item::ItemHandle, ItemDeserializers, Member, Pane, PaneAxis, Workspace, WorkspaceId};
+use crate::{
+    item::ItemHandle, ItemDeserializers, Member, Pane, PaneAxis, SessionId, Workspace, WorkspaceId,
+};
 use anyhow::{Context, Result};
 use async_recursion::async_recursion;
 use db::sqlez::{
@@ -15,7 +17,7 @@ use std::{
 use util::ResultExt;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct WorkspaceLocation(Arc<Vec<PathBuf>>);
 
 impl WorkspaceLocation {
@@ -36,6 +38,7 @@ impl<P: AsRef<Path>, T: IntoIterator<Item = P>> From<T> for WorkspaceLocation {
 }
 
 impl StaticColumnCount for WorkspaceLocation {}
+impl StaticColumnCount for &WorkspaceLocation {}
 impl Bind for &WorkspaceLocation {
     fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
         bincode::serialize(&self.0)
@@ -58,6 +61,7 @@ impl Column for WorkspaceLocation {
 pub(crate) struct SerializedWorkspace {
     pub(crate) id: WorkspaceId,
     pub(crate) location: WorkspaceLocation,
+    pub(crate) session_id: Option<SessionId>,
     pub(crate) center_group: SerializedPaneGroup,
     pub(crate) bounds: Option<WindowBounds>,
     pub(crate) display: Option<Uuid>,
@@ -147,6 +151,15 @@ impl Default for SerializedPaneGroup {
 }
 
 impl SerializedPaneGroup {
+    pub(crate) fn has_items(&self) -> bool {
+        match self {
+            SerializedPaneGroup::Group { children, .. } => {
+                children.iter().any(|child| child.has_items())
+            }
+            SerializedPaneGroup::Pane(pane) => !pane.children.is_empty(),
+        }
+    }
+
     #[async_recursion(?Send)]
     pub(crate) async fn deserialize(
         self,
@@ -3,7 +3,7 @@ use crate::{
     ItemNavHistory, WorkspaceId,
 };
 use anyhow::Result;
-use call::participant::{Frame, RemoteVideoTrack};
+use call::participant::{RemoteVideoTrack, VideoFrame};
 use client::{proto::PeerId, User};
 use futures::StreamExt;
 use gpui::{
@@ -19,8 +19,8 @@ pub enum Event {
 }
 
 pub struct SharedScreen {
-    track: Weak<RemoteVideoTrack>,
-    frame: Option<Frame>,
+    track: Weak<dyn RemoteVideoTrack>,
+    frame: Option<Arc<dyn VideoFrame>>,
     pub peer_id: PeerId,
     user: Arc<User>,
     nav_history: Option<ItemNavHistory>,
@@ -30,7 +30,7 @@ pub struct SharedScreen {
 
 impl SharedScreen {
     pub fn new(
-        track: &Arc<RemoteVideoTrack>,
+        track: &Arc<dyn RemoteVideoTrack>,
         peer_id: PeerId,
         user: Arc<User>,
         cx: &mut ViewContext<Self>,
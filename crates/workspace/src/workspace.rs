@@ -29,10 +29,10 @@ use gpui::{
     actions, canvas, div, impl_actions, point, px, size, Action, AnyElement, AnyModel, AnyView,
     AnyWeakView, AppContext, AsyncAppContext, AsyncWindowContext, Bounds, Context, Div,
     DragMoveEvent, Element, ElementContext, Entity, EntityId, EventEmitter, FocusHandle,
-    FocusableView, Global, GlobalPixels, InteractiveElement, IntoElement, KeyContext, LayoutId,
-    ManagedView, Model, ModelContext, ParentElement, PathPromptOptions, Pixels, Point, PromptLevel,
-    Render, SharedString, Size, Styled, Subscription, Task, View, ViewContext, VisualContext,
-    WeakView, WindowBounds, WindowContext, WindowHandle, WindowOptions,
+    FocusableView, Global, GlobalPixels, InteractiveElement, IntoElement, KeyContext, KeyUpEvent,
+    LayoutId, ManagedView, Model, ModelContext, ParentElement, PathPromptOptions, Pixels, Point,
+    PromptLevel, Render, SharedString, Size, Styled, Subscription, Task, View, ViewContext,
+    VisualContext, WeakView, WindowBounds, WindowContext, WindowHandle, WindowOptions,
 };
 use item::{FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings, ProjectItem};
 use itertools::Itertools;
@@ -58,7 +58,7 @@ pub use status_bar::StatusItemView;
 use std::{
     any::TypeId,
     borrow::Cow,
-    cmp, env,
+    cmp, env, mem,
     path::{Path, PathBuf},
     sync::Weak,
     sync::{atomic::AtomicUsize, Arc},
@@ -222,6 +222,16 @@ pub struct OpenTerminal {
 }
 
 pub type WorkspaceId = i64;
+pub type SessionId = i64;
+
+/// The name of the session that's created the first time Zed is launched.
+pub const DEFAULT_SESSION_NAME: &str = "Default";
+
+/// The session that workspaces are currently being serialized into.
+#[derive(Clone, Copy)]
+struct ActiveSession(SessionId);
+
+impl Global for ActiveSession {}
 
 pub fn init_settings(cx: &mut AppContext) {
     WorkspaceSettings::register(cx);
@@ -464,6 +474,7 @@ pub enum Event {
     PaneAdded(View<Pane>),
     ContactRequestedJoin(u64),
     WorkspaceCreated(WeakView<Workspace>),
+    ActiveItemChanged,
 }
 
 pub enum OpenVisible {
@@ -496,6 +507,7 @@ pub struct Workspace {
     last_leaders_by_pane: HashMap<WeakView<Pane>, PeerId>,
     window_edited: bool,
     active_call: Option<(Model<ActiveCall>, Vec<Subscription>)>,
+    push_to_talk_key: Option<PushToTalkKey>,
     leader_updates_tx: mpsc::UnboundedSender<(PeerId, proto::UpdateFollowers)>,
     database_id: WorkspaceId,
     app_state: Arc<AppState>,
@@ -509,6 +521,13 @@ pub struct Workspace {
 
 impl EventEmitter<Event> for Workspace {}
 
+/// The key whose release ends push-to-talk. The key is only known once the
+/// keystroke that dispatched the push-to-talk action has been observed.
+enum PushToTalkKey {
+    Pending,
+    Held(String),
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ViewId {
     pub creator: PeerId,
@@ -661,6 +680,23 @@ impl Workspace {
 
         let subscriptions = vec![
             cx.observe_window_activation(Self::on_window_activation_changed),
+            cx.observe_keystrokes({
+                let window = cx.window_handle();
+                let workspace = weak_handle.clone();
+                move |event, cx| {
+                    if cx.window_handle() != window {
+                        return;
+                    }
+                    workspace
+                        .update(cx, |workspace, _| {
+                            if let Some(PushToTalkKey::Pending) = workspace.push_to_talk_key {
+                                workspace.push_to_talk_key =
+                                    Some(PushToTalkKey::Held(event.keystroke.key.clone()));
+                            }
+                        })
+                        .ok();
+                }
+            }),
             cx.observe_window_bounds(move |_, cx| {
                 if let Some(display) = cx.display() {
                     // Transform fixed bounds to be stored in terms of the containing display
@@ -735,6 +771,7 @@ impl Workspace {
             last_leaders_by_pane: Default::default(),
             window_edited: false,
             active_call,
+            push_to_talk_key: None,
             database_id: workspace_id,
             app_state,
             _observe_current_user,
@@ -759,6 +796,28 @@ impl Workspace {
             WindowHandle<Workspace>,
             Vec<Option<Result<Box<dyn ItemHandle>, anyhow::Error>>>,
         )>,
+    > {
+        let serialized_workspace = persistence::DB.workspace_for_roots(abs_paths.as_slice());
+        Self::new_local_with_serialized(
+            abs_paths,
+            serialized_workspace,
+            app_state,
+            requesting_window,
+            cx,
+        )
+    }
+
+    fn new_local_with_serialized(
+        abs_paths: Vec<PathBuf>,
+        serialized_workspace: Option<SerializedWorkspace>,
+        app_state: Arc<AppState>,
+        requesting_window: Option<WindowHandle<Workspace>>,
+        cx: &mut AppContext,
+    ) -> Task<
+        anyhow::Result<(
+            WindowHandle<Workspace>,
+            Vec<Option<Result<Box<dyn ItemHandle>, anyhow::Error>>>,
+        )>,
     > {
         let project_handle = Project::local(
             app_state.client.clone(),
@@ -770,9 +829,6 @@ impl Workspace {
         );
 
         cx.spawn(|mut cx| async move {
-            let serialized_workspace: Option<SerializedWorkspace> =
-                persistence::DB.workspace_for_roots(abs_paths.as_slice());
-
             let paths_to_open = Arc::new(abs_paths);
 
             // Get project paths for all of the abs_paths
@@ -1165,12 +1221,18 @@ impl Workspace {
 
     pub fn close_window(&mut self, _: &CloseWindow, cx: &mut ViewContext<Self>) {
         let window = cx.window_handle();
+        let workspace_id = self.database_id;
         let prepare = self.prepare_to_close(false, cx);
         cx.spawn(|_, mut cx| async move {
             if prepare.await? {
                 window.update(&mut cx, |_, cx| {
                     cx.remove_window();
                 })?;
+                // Windows closed explicitly shouldn't be reopened with their session.
+                persistence::DB
+                    .remove_from_session(workspace_id)
+                    .await
+                    .log_err();
             }
             anyhow::Ok(())
         })
@@ -1218,9 +1280,34 @@ impl Workspace {
                 }
             }
 
+            let mut dirty_items = this.update(&mut cx, |this, cx| this.dirty_items(cx))?;
+            let restore_unsaved_buffers =
+                cx.update(|cx| WorkspaceSettings::get_global(cx).restore_unsaved_buffers)?;
+            if quitting && restore_unsaved_buffers {
+                // Items that persist their unsaved changes are restored on the next launch,
+                // so there's no need to ask the user to save them.
+                let mut serialized_items = Vec::new();
+                let mut serializations = Vec::new();
+                for (pane, item) in mem::take(&mut dirty_items) {
+                    match cx.update(|cx| item.serialize_unsaved_changes(cx)) {
+                        Ok(Some(serialization)) => {
+                            serialized_items.push((pane, item));
+                            serializations.push(serialization);
+                        }
+                        _ => dirty_items.push((pane, item)),
+                    }
+                }
+                let results = futures::future::join_all(serializations).await;
+                for (dirty_item, result) in serialized_items.into_iter().zip(results) {
+                    if result.log_err().is_none() {
+                        dirty_items.push(dirty_item);
+                    }
+                }
+            }
+
             Ok(this
                 .update(&mut cx, |this, cx| {
-                    this.save_all_internal(SaveIntent::Close, cx)
+                    this.save_dirty_items(dirty_items, SaveIntent::Close, cx)
                 })?
                 .await?)
         })
@@ -1233,14 +1320,15 @@ impl Workspace {
 
     fn save_all_internal(
         &mut self,
-        mut save_intent: SaveIntent,
+        save_intent: SaveIntent,
         cx: &mut ViewContext<Self>,
     ) -> Task<Result<bool>> {
-        if self.project.read(cx).is_disconnected() {
-            return Task::ready(Ok(true));
-        }
-        let dirty_items = self
-            .panes
+        let dirty_items = self.dirty_items(cx);
+        self.save_dirty_items(dirty_items, save_intent, cx)
+    }
+
+    fn dirty_items(&self, cx: &AppContext) -> Vec<(WeakView<Pane>, Box<dyn ItemHandle>)> {
+        self.panes
             .iter()
             .flat_map(|pane| {
                 pane.read(cx).items().filter_map(|item| {
@@ -1251,7 +1339,18 @@ impl Workspace {
                     }
                 })
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
+
+    fn save_dirty_items(
+        &mut self,
+        dirty_items: Vec<(WeakView<Pane>, Box<dyn ItemHandle>)>,
+        mut save_intent: SaveIntent,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<bool>> {
+        if self.project.read(cx).is_disconnected() || dirty_items.is_empty() {
+            return Task::ready(Ok(true));
+        }
 
         let project = self.project.clone();
         cx.spawn(|workspace, mut cx| async move {
@@ -1880,13 +1979,25 @@ impl Workspace {
         abs_path: PathBuf,
         visible: bool,
         cx: &mut ViewContext<Self>,
+    ) -> Task<anyhow::Result<Box<dyn ItemHandle>>> {
+        self.split_abs_path_in_direction(abs_path, visible, SplitDirection::Right, cx)
+    }
+
+    pub fn split_abs_path_in_direction(
+        &mut self,
+        abs_path: PathBuf,
+        visible: bool,
+        direction: SplitDirection,
+        cx: &mut ViewContext<Self>,
     ) -> Task<anyhow::Result<Box<dyn ItemHandle>>> {
         let project_path_task =
             Workspace::project_path_for_path(self.project.clone(), &abs_path, visible, cx);
         cx.spawn(|this, mut cx| async move {
             let (_, path) = project_path_task.await?;
-            this.update(&mut cx, |this, cx| this.split_path(path, cx))?
-                .await
+            this.update(&mut cx, |this, cx| {
+                this.split_path_in_direction(path, direction, cx)
+            })?
+            .await
         })
     }
 
@@ -1919,6 +2030,15 @@ impl Workspace {
         &mut self,
         path: impl Into<ProjectPath>,
         cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Box<dyn ItemHandle>, anyhow::Error>> {
+        self.split_path_in_direction(path, SplitDirection::Right, cx)
+    }
+
+    pub fn split_path_in_direction(
+        &mut self,
+        path: impl Into<ProjectPath>,
+        direction: SplitDirection,
+        cx: &mut ViewContext<Self>,
     ) -> Task<Result<Box<dyn ItemHandle>, anyhow::Error>> {
         let pane = self.last_active_center_pane.clone().unwrap_or_else(|| {
             self.panes
@@ -1938,7 +2058,7 @@ impl Workspace {
             let (project_entry_id, build_item) = task.await?;
             this.update(&mut cx, move |this, cx| -> Option<_> {
                 let pane = pane.upgrade()?;
-                let new_pane = this.split_pane(pane, SplitDirection::Right, cx);
+                let new_pane = this.split_pane(pane, direction, cx);
                 new_pane.update(cx, |new_pane, cx| {
                     Some(new_pane.open_item(project_entry_id, true, cx, build_item))
                 })
@@ -2218,6 +2338,7 @@ impl Workspace {
             });
             self.active_item_path_changed(cx);
             self.last_active_center_pane = Some(pane.downgrade());
+            cx.emit(Event::ActiveItemChanged);
         }
 
         self.dismiss_zoomed_items_to_reveal(None, cx);
@@ -2251,6 +2372,7 @@ impl Workspace {
                 if &pane == self.active_pane() {
                     self.active_item_path_changed(cx);
                     self.update_active_view_for_followers(cx);
+                    cx.emit(Event::ActiveItemChanged);
                 }
             }
             pane::Event::ChangeItemTitle => {
@@ -2266,6 +2388,9 @@ impl Workspace {
                         entry.remove();
                     }
                 }
+                if pane == self.active_pane {
+                    cx.emit(Event::ActiveItemChanged);
+                }
             }
             pane::Event::Focus => {
                 self.handle_pane_focused(pane.clone(), cx);
@@ -2538,6 +2663,31 @@ impl Workspace {
             .map(|task| task.detach_and_log_err(cx));
     }
 
+    /// Opens the file a collaborator has open, without following them. If the
+    /// file is in another shared project, that project is joined first.
+    pub fn open_participant_location(&mut self, peer_id: PeerId, cx: &mut ViewContext<Self>) {
+        let Some(room) = ActiveCall::global(cx).read(cx).room() else {
+            return;
+        };
+        let Some(participant) = room.read(cx).remote_participant_for_peer_id(peer_id) else {
+            return;
+        };
+        let call::ParticipantLocation::SharedProject { project_id } = participant.location else {
+            return;
+        };
+
+        let project_path = participant.active_path.clone();
+        if Some(project_id) == self.project.read(cx).remote_id() {
+            if let Some(project_path) = project_path {
+                self.open_path(project_path, None, true, cx)
+                    .detach_and_log_err(cx);
+            }
+        } else {
+            join_remote_project_at_path(project_id, project_path, self.app_state.clone(), cx)
+                .detach_and_log_err(cx);
+        }
+    }
+
     pub fn follow(&mut self, leader_id: PeerId, cx: &mut ViewContext<Self>) {
         let Some(room) = ActiveCall::global(cx).read(cx).room() else {
             return;
@@ -2619,9 +2769,26 @@ impl Workspace {
         let active_entry = self.active_project_path(cx);
         self.project
             .update(cx, |project, cx| project.set_active_path(active_entry, cx));
+        self.update_active_path_for_collaborators(cx);
         self.update_window_title(cx);
     }
 
+    /// Lets the other participants in the call know which file is open in this workspace.
+    fn update_active_path_for_collaborators(&self, cx: &mut WindowContext) {
+        if !cx.is_window_active() {
+            return;
+        }
+        let Some(active_call) = self.active_call().cloned() else {
+            return;
+        };
+        let active_path = self.active_project_path(cx);
+        active_call
+            .update(cx, |call, cx| {
+                call.set_active_path(&self.project, active_path, cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
     fn update_window_title(&mut self, cx: &mut WindowContext) {
         let project = self.project().read(cx);
         let mut title = String::new();
@@ -2894,6 +3061,8 @@ impl Workspace {
     }
 
     pub fn update_active_view_for_followers(&mut self, cx: &mut WindowContext) {
+        self.update_active_path_for_collaborators(cx);
+
         let mut is_project_item = true;
         let mut update = proto::UpdateActiveView::default();
 
@@ -3036,6 +3205,7 @@ impl Workspace {
                 .spawn(persistence::DB.update_timestamp(self.database_id()))
                 .detach();
         } else {
+            self.end_push_to_talk(cx);
             for pane in &self.panes {
                 pane.update(cx, |pane, cx| {
                     if let Some(item) = pane.active_item() {
@@ -3059,6 +3229,33 @@ impl Workspace {
         self.active_call.as_ref().map(|(call, _)| call)
     }
 
+    /// Unmutes the microphone until the key that dispatched this is released
+    /// or the window loses focus.
+    pub fn start_push_to_talk(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(room) = self
+            .active_call()
+            .and_then(|call| call.read(cx).room().cloned())
+        else {
+            return;
+        };
+        let was_pushing_to_talk = room.read(cx).is_pushing_to_talk();
+        room.update(cx, |room, cx| room.start_push_to_talk(cx));
+        // Repeated key-downs while the key is held keep the original key.
+        if !was_pushing_to_talk && room.read(cx).is_pushing_to_talk() {
+            self.push_to_talk_key = Some(PushToTalkKey::Pending);
+        }
+    }
+
+    fn end_push_to_talk(&mut self, cx: &mut ViewContext<Self>) {
+        self.push_to_talk_key = None;
+        if let Some(room) = self
+            .active_call()
+            .and_then(|call| call.read(cx).room().cloned())
+        {
+            room.update(cx, |room, cx| room.end_push_to_talk(cx));
+        }
+    }
+
     fn on_active_call_event(
         &mut self,
         _: Model<ActiveCall>,
@@ -3066,8 +3263,14 @@ impl Workspace {
         cx: &mut ViewContext<Self>,
     ) {
         match event {
-            call::room::Event::ParticipantLocationChanged { participant_id }
-            | call::room::Event::RemoteVideoTracksChanged { participant_id } => {
+            call::room::Event::ParticipantLocationChanged { participant_id } => {
+                // Tabs show which collaborators have each file open.
+                for pane in &self.panes {
+                    pane.update(cx, |_, cx| cx.notify());
+                }
+                self.leader_updated(*participant_id, cx);
+            }
+            call::room::Event::RemoteVideoTracksChanged { participant_id } => {
                 self.leader_updated(*participant_id, cx);
             }
             _ => {}
@@ -3226,25 +3429,27 @@ impl Workspace {
         }
 
         if let Some(location) = self.location(cx) {
-            // Load bearing special case:
-            //  - with_local_workspace() relies on this to not have other stuff open
-            //    when you open your log
-            if !location.paths().is_empty() {
-                let center_group = build_serialized_pane_group(&self.center.root, cx);
-                let docks = build_serialized_docks(self, cx);
-
-                let serialized_workspace = SerializedWorkspace {
-                    id: self.database_id,
-                    location,
-                    center_group,
-                    bounds: Default::default(),
-                    display: Default::default(),
-                    docks,
-                };
+            let center_group = build_serialized_pane_group(&self.center.root, cx);
 
-                cx.spawn(|_| persistence::DB.save_workspace(serialized_workspace))
-                    .detach();
+            // Workspaces without any roots can only be restored through their session,
+            // so there's no point in storing them until they contain items.
+            if location.paths().is_empty() && !center_group.has_items() {
+                return;
             }
+
+            let docks = build_serialized_docks(self, cx);
+            let serialized_workspace = SerializedWorkspace {
+                id: self.database_id,
+                location,
+                session_id: cx.try_global::<ActiveSession>().map(|session| session.0),
+                center_group,
+                bounds: Default::default(),
+                display: Default::default(),
+                docks,
+            };
+
+            cx.spawn(|_| persistence::DB.save_workspace(serialized_workspace))
+                .detach();
         }
     }
 
@@ -3625,6 +3830,13 @@ impl Render for Workspace {
 
         self.actions(div(), cx)
             .key_context(context)
+            .capture_key_up(cx.listener(|this, event: &KeyUpEvent, cx| {
+                if let Some(PushToTalkKey::Held(key)) = &this.push_to_talk_key {
+                    if *key == event.keystroke.key {
+                        this.end_push_to_talk(cx);
+                    }
+                }
+            }))
             .relative()
             .size_full()
             .flex()
@@ -3977,6 +4189,112 @@ pub async fn last_opened_workspace_paths() -> Option<WorkspaceLocation> {
     DB.last_workspace().await.log_err().flatten()
 }
 
+/// Returns the name of the session that workspaces are currently serialized into.
+pub fn active_session_name(cx: &AppContext) -> Option<String> {
+    let session_id = cx.try_global::<ActiveSession>()?.0;
+    DB.sessions()
+        .log_err()?
+        .into_iter()
+        .find_map(|(id, name)| (id == session_id).then_some(name))
+}
+
+/// Makes the session with the given name the one that workspaces are serialized
+/// into, creating it if needed.
+pub fn activate_session(name: String, cx: &mut AppContext) -> Task<Result<SessionId>> {
+    cx.spawn(|mut cx| async move {
+        let session_id = DB.activate_session(name).await?;
+        cx.update(|cx| cx.set_global(ActiveSession(session_id)))?;
+        Ok(session_id)
+    })
+}
+
+/// Activates the session that was active when Zed last quit.
+pub fn activate_last_session(cx: &mut AppContext) -> Task<Result<SessionId>> {
+    let name = DB
+        .last_session()
+        .log_err()
+        .flatten()
+        .map(|(_, name)| name)
+        .unwrap_or_else(|| DEFAULT_SESSION_NAME.to_string());
+    activate_session(name, cx)
+}
+
+/// Reopens the windows that were open in the given session, along with their layout
+/// and any unsaved changes. Returns the number of windows that were restored.
+pub fn restore_session_windows(
+    session_id: SessionId,
+    app_state: &Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<usize>> {
+    let app_state = app_state.clone();
+    cx.spawn(|mut cx| async move {
+        let mut restored = 0;
+        for workspace_id in DB.session_workspace_ids(session_id)? {
+            let Some(serialized_workspace) = DB.workspace_for_id(workspace_id) else {
+                continue;
+            };
+            let abs_paths = serialized_workspace.location.paths().to_vec();
+            if !abs_paths.iter().all(|path| path.exists()) {
+                continue;
+            }
+
+            let task = cx.update(|cx| {
+                Workspace::new_local_with_serialized(
+                    abs_paths,
+                    Some(serialized_workspace),
+                    app_state.clone(),
+                    None,
+                    cx,
+                )
+            })?;
+            if task.await.log_err().is_some() {
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    })
+}
+
+/// Closes all workspace windows, keeping their unsaved changes in the current
+/// session, and reopens the windows of the session with the given name.
+pub fn switch_session(
+    name: String,
+    app_state: &Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    let windows = cx
+        .windows()
+        .into_iter()
+        .filter_map(|window| window.downcast::<Workspace>())
+        .collect::<Vec<_>>();
+    let app_state = app_state.clone();
+    cx.spawn(|mut cx| async move {
+        for window in &windows {
+            let prepare = window.update(&mut cx, |workspace, cx| {
+                workspace.prepare_to_close(true, cx)
+            })?;
+            if !prepare.await? {
+                return Ok(());
+            }
+        }
+
+        cx.update(|cx| {
+            for window in windows {
+                window.update(cx, |_, cx| cx.remove_window()).log_err();
+            }
+        })?;
+
+        let session_id = cx.update(|cx| activate_session(name, cx))?.await?;
+        let restored = cx
+            .update(|cx| restore_session_windows(session_id, &app_state, cx))?
+            .await?;
+        if restored == 0 {
+            cx.update(|cx| open_new(&app_state, cx, |_, _| {}))?.await;
+        }
+        Ok(())
+    })
+}
+
 async fn join_channel_internal(
     channel_id: u64,
     app_state: &Arc<AppState>,
@@ -4281,6 +4599,93 @@ pub fn join_remote_project(
     app_state: Arc<AppState>,
     cx: &mut AppContext,
 ) -> Task<Result<()>> {
+    join_remote_project_internal(project_id, None, Some(follow_user_id), app_state, cx)
+}
+
+/// Joins a project shared in the current call using an invite from its host,
+/// and follows the host.
+pub fn join_remote_project_with_invite(
+    project_id: u64,
+    invite_token: String,
+    app_state: Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    join_remote_project_internal(project_id, Some(invite_token), None, app_state, cx)
+}
+
+/// Joins a project shared in the current call and opens the given file in it,
+/// without following anyone.
+pub fn join_remote_project_at_path(
+    project_id: u64,
+    project_path: Option<ProjectPath>,
+    app_state: Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    let workspace = open_remote_project_window(project_id, None, app_state, cx);
+    cx.spawn(|mut cx| async move {
+        let workspace = workspace.await?;
+        let open_path = workspace.update(&mut cx, |workspace, cx| {
+            cx.activate(true);
+            cx.activate_window();
+            project_path.map(|project_path| workspace.open_path(project_path, None, true, cx))
+        })?;
+        if let Some(open_path) = open_path {
+            open_path.await?;
+        }
+        anyhow::Ok(())
+    })
+}
+
+fn join_remote_project_internal(
+    project_id: u64,
+    invite_token: Option<String>,
+    follow_user_id: Option<u64>,
+    app_state: Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    let workspace = open_remote_project_window(project_id, invite_token, app_state, cx);
+    cx.spawn(|mut cx| async move {
+        let workspace = workspace.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            cx.activate(true);
+            cx.activate_window();
+
+            if let Some(room) = ActiveCall::global(cx).read(cx).room().cloned() {
+                let follow_peer_id = room
+                    .read(cx)
+                    .remote_participants()
+                    .iter()
+                    .find(|(_, participant)| Some(participant.user.id) == follow_user_id)
+                    .map(|(_, p)| p.peer_id)
+                    .or_else(|| {
+                        // If we couldn't follow the given user, follow the host instead.
+                        let collaborator = workspace
+                            .project()
+                            .read(cx)
+                            .collaborators()
+                            .values()
+                            .find(|collaborator| collaborator.replica_id == 0)?;
+                        Some(collaborator.peer_id)
+                    });
+
+                if let Some(follow_peer_id) = follow_peer_id {
+                    workspace.follow(follow_peer_id, cx);
+                }
+            }
+        })?;
+
+        anyhow::Ok(())
+    })
+}
+
+/// Returns the window of the workspace for the given remote project, joining
+/// the project in a new window if it isn't open yet.
+fn open_remote_project_window(
+    project_id: u64,
+    invite_token: Option<String>,
+    app_state: Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<WindowHandle<Workspace>>> {
     let windows = cx.windows();
     cx.spawn(|mut cx| async move {
         let existing_workspace = windows.into_iter().find_map(|window| {
@@ -4306,8 +4711,9 @@ pub fn join_remote_project(
                 .ok_or_else(|| anyhow!("not in a call"))?;
             let project = room
                 .update(&mut cx, |room, cx| {
-                    room.join_project(
+                    room.join_project_with_invite(
                         project_id,
+                        invite_token,
                         app_state.languages.clone(),
                         app_state.fs.clone(),
                         cx,
@@ -4324,35 +4730,7 @@ pub fn join_remote_project(
             })?
         };
 
-        workspace.update(&mut cx, |workspace, cx| {
-            cx.activate(true);
-            cx.activate_window();
-
-            if let Some(room) = ActiveCall::global(cx).read(cx).room().cloned() {
-                let follow_peer_id = room
-                    .read(cx)
-                    .remote_participants()
-                    .iter()
-                    .find(|(_, participant)| participant.user.id == follow_user_id)
-                    .map(|(_, p)| p.peer_id)
-                    .or_else(|| {
-                        // If we couldn't follow the given user, follow the host instead.
-                        let collaborator = workspace
-                            .project()
-                            .read(cx)
-                            .collaborators()
-                            .values()
-                            .find(|collaborator| collaborator.replica_id == 0)?;
-                        Some(collaborator.peer_id)
-                    });
-
-                if let Some(follow_peer_id) = follow_peer_id {
-                    workspace.follow(follow_peer_id, cx);
-                }
-            }
-        })?;
-
-        anyhow::Ok(())
+        anyhow::Ok(workspace)
     })
 }
 
@@ -4672,6 +5050,50 @@ mod tests {
         assert!(!task.await.unwrap());
     }
 
+    #[gpui::test]
+    async fn test_quit_preserves_unsaved_changes(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        // Items that preserve their unsaved changes don't need to be saved when quitting.
+        let item1 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_preserved_unsaved_changes(true)
+        });
+        workspace.update(cx, |w, cx| w.add_item(Box::new(item1.clone()), cx));
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(true, cx));
+        cx.executor().run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        assert!(task.await.unwrap());
+
+        // They still prompt when closing the window.
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(false, cx));
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(2);
+        cx.executor().run_until_parked();
+        assert!(!task.await.unwrap());
+
+        // When restoring unsaved buffers is disabled, quitting prompts to save them too.
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.restore_unsaved_buffers = Some(false);
+                })
+            })
+        });
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(true, cx));
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(2);
+        cx.executor().run_until_parked();
+        assert!(!task.await.unwrap());
+    }
+
     #[gpui::test]
     async fn test_close_pane_items(cx: &mut TestAppContext) {
         init_test(cx);
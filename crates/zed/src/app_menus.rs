@@ -37,6 +37,7 @@ pub fn app_menus() -> Vec<Menu<'static>> {
                 MenuItem::separator(),
                 MenuItem::action("Open…", workspace::Open),
                 MenuItem::action("Open Recent...", recent_projects::OpenRecent),
+                MenuItem::action("Switch Session...", recent_projects::SwitchSession),
                 MenuItem::separator(),
                 MenuItem::action("Add Folder to Project…", workspace::AddFolderToProject),
                 MenuItem::action("Save", workspace::Save { save_intent: None }),
@@ -116,6 +117,7 @@ pub fn app_menus() -> Vec<Menu<'static>> {
                 }),
                 MenuItem::separator(),
                 MenuItem::action("Project Panel", project_panel::ToggleFocus),
+                MenuItem::action("Outline Panel", outline_panel::ToggleFocus),
                 MenuItem::action("Command Palette", command_palette::Toggle),
                 MenuItem::action("Diagnostics", diagnostics::Deploy),
                 MenuItem::separator(),
@@ -0,0 +1,114 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use assistant::FillInTheMiddleCompletionProvider;
+use client::telemetry::Telemetry;
+use collections::HashMap;
+use copilot::Copilot;
+use copilot_ui::CopilotCompletionProvider;
+use editor::{Editor, EditorMode};
+use gpui::{AnyWindowHandle, AppContext, Context, ViewContext, WeakView};
+use language::language_settings::{all_language_settings, InlineCompletionProvider};
+use settings::SettingsStore;
+
+/// Assigns the configured inline completion provider to every full-size editor,
+/// and swaps it out whenever `features.inline_completion_provider` changes, or
+/// the assistant's inline completion model does while it provides them.
+pub fn init(telemetry: Arc<Telemetry>, cx: &mut AppContext) {
+    let editors: Rc<RefCell<HashMap<WeakView<Editor>, AnyWindowHandle>>> = Rc::default();
+    cx.observe_new_views({
+        let editors = editors.clone();
+        let telemetry = telemetry.clone();
+        move |editor: &mut Editor, cx: &mut ViewContext<Editor>| {
+            if editor.mode() != EditorMode::Full {
+                return;
+            }
+
+            register_backward_compatible_actions(editor, cx);
+
+            let editor_handle = cx.view().downgrade();
+            cx.on_release({
+                let editor_handle = editor_handle.clone();
+                let editors = editors.clone();
+                move |_, _, _| {
+                    editors.borrow_mut().remove(&editor_handle);
+                }
+            })
+            .detach();
+            editors
+                .borrow_mut()
+                .insert(editor_handle, cx.window_handle());
+            let provider = all_language_settings(None, cx).inline_completion_provider;
+            assign_inline_completion_provider(editor, provider, &telemetry, cx);
+        }
+    })
+    .detach();
+
+    let mut provider = all_language_settings(None, cx).inline_completion_provider;
+    let mut assistant_model = FillInTheMiddleCompletionProvider::configured_model(cx);
+    cx.observe_global::<SettingsStore>(move |cx| {
+        let new_provider = all_language_settings(None, cx).inline_completion_provider;
+        let new_assistant_model = FillInTheMiddleCompletionProvider::configured_model(cx);
+        let assistant_model_changed = new_assistant_model != assistant_model;
+        assistant_model = new_assistant_model;
+        if new_provider != provider
+            || (new_provider == InlineCompletionProvider::Assistant && assistant_model_changed)
+        {
+            provider = new_provider;
+            for (editor, window) in editors.borrow().iter() {
+                _ = window.update(cx, |_window, cx| {
+                    _ = editor.update(cx, |editor, cx| {
+                        assign_inline_completion_provider(editor, provider, &telemetry, cx);
+                    })
+                });
+            }
+        }
+    })
+    .detach();
+}
+
+fn register_backward_compatible_actions(editor: &mut Editor, cx: &ViewContext<Editor>) {
+    // We renamed some of these actions to not be copilot-specific, but that
+    // would have not been backwards-compatible. So here we are re-registering
+    // the actions with the old names to not break people's keymaps.
+    editor
+        .register_action(cx.listener(
+            |editor, _: &copilot::Suggest, cx: &mut ViewContext<Editor>| {
+                editor.show_inline_completion(&Default::default(), cx);
+            },
+        ))
+        .register_action(cx.listener(
+            |editor, _: &copilot::NextSuggestion, cx: &mut ViewContext<Editor>| {
+                editor.next_inline_completion(&Default::default(), cx);
+            },
+        ))
+        .register_action(cx.listener(
+            |editor, _: &copilot::PreviousSuggestion, cx: &mut ViewContext<Editor>| {
+                editor.previous_inline_completion(&Default::default(), cx);
+            },
+        ));
+}
+
+fn assign_inline_completion_provider(
+    editor: &mut Editor,
+    provider: InlineCompletionProvider,
+    telemetry: &Arc<Telemetry>,
+    cx: &mut ViewContext<Editor>,
+) {
+    match provider {
+        InlineCompletionProvider::None => {
+            editor.set_inline_completion_provider::<CopilotCompletionProvider>(None, cx);
+        }
+        InlineCompletionProvider::Copilot => {
+            if let Some(copilot) = Copilot::global(cx) {
+                let provider = cx.new_model(|_| {
+                    CopilotCompletionProvider::new(copilot).with_telemetry(telemetry.clone())
+                });
+                editor.set_inline_completion_provider(Some(provider), cx);
+            }
+        }
+        InlineCompletionProvider::Assistant => {
+            let provider = cx.new_model(|cx| FillInTheMiddleCompletionProvider::load(cx));
+            editor.set_inline_completion_provider(Some(provider), cx);
+        }
+    }
+}
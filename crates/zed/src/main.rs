@@ -52,7 +52,7 @@ use util::{
 };
 use uuid::Uuid;
 use welcome::{show_welcome_view, BaseKeymap, FIRST_OPEN};
-use workspace::{AppState, WorkspaceStore};
+use workspace::{AppState, SessionId, WorkspaceStore};
 use zed::{
     app_menus, build_window_options, ensure_only_instance, handle_cli_connection,
     handle_keymap_file_changes, initialize_workspace, languages, IsOnlyInstance, OpenListener,
@@ -172,10 +172,14 @@ fn main() {
             cx,
         );
         assistant::init(cx);
+        zed::inline_completion_registry::init(client.telemetry().clone(), cx);
 
         extension::init(fs.clone(), languages.clone(), ThemeRegistry::global(cx), cx);
 
         load_user_themes_in_background(fs.clone(), cx);
+        cx.background_executor()
+            .spawn(project::delete_stale_trash(fs.clone()))
+            .detach_and_log_err(cx);
         #[cfg(target_os = "macos")]
         watch_themes(fs.clone(), cx);
 
@@ -236,6 +240,7 @@ fn main() {
         go_to_line::init(cx);
         file_finder::init(cx);
         outline::init(cx);
+        outline_panel::init(cx);
         project_symbols::init(cx);
         project_panel::init(Assets, cx);
         channel::init(&client, user_store.clone(), cx);
@@ -250,6 +255,7 @@ fn main() {
         language_tools::init(cx);
         call::init(app_state.client.clone(), app_state.user_store.clone(), cx);
         notifications::init(app_state.client.clone(), app_state.user_store.clone(), cx);
+        comments::init(app_state.client.clone(), app_state.user_store.clone(), cx);
         collab_ui::init(&app_state, cx);
         feedback::init(cx);
         markdown_preview::init(cx);
@@ -298,6 +304,7 @@ fn main() {
             .detach();
         }
 
+        let mut activate_session = Some(workspace::activate_last_session(cx));
         match open_rx.try_next() {
             Ok(Some(OpenRequest::Paths { paths })) => {
                 open_paths_and_log_errs(&paths, &app_state, cx)
@@ -341,13 +348,45 @@ fn main() {
                 })
                 .detach_and_log_err(cx);
             }
+            Ok(Some(OpenRequest::JoinProject {
+                project_id,
+                invite_token,
+            })) => {
+                triggered_authentication = true;
+                let app_state = app_state.clone();
+                let client = client.clone();
+                cx.spawn(|cx| async move {
+                    // ignore errors here, we'll show a generic "not signed in"
+                    let _ = authenticate(client, &cx).await;
+                    cx.update(|cx| {
+                        workspace::join_remote_project_with_invite(
+                            project_id,
+                            invite_token,
+                            app_state,
+                            cx,
+                        )
+                    })?
+                    .await?;
+                    anyhow::Ok(())
+                })
+                .detach_and_log_err(cx);
+            }
+            Ok(Some(OpenRequest::OpenSshProject { host, paths })) => {
+                zed::open_ssh_project(host, paths, app_state.clone(), cx).detach_and_log_err(cx);
+            }
             Ok(None) | Err(_) => cx
                 .spawn({
                     let app_state = app_state.clone();
-                    |cx| async move { restore_or_create_workspace(&app_state, cx).await }
+                    let activate_session = activate_session.take();
+                    |cx| async move {
+                        restore_or_create_workspace(&app_state, activate_session, cx).await
+                    }
                 })
                 .detach(),
         }
+        if let Some(activate_session) = activate_session {
+            activate_session.detach_and_log_err(cx);
+        }
 
         let app_state = app_state.clone();
         cx.spawn(move |cx| async move {
@@ -396,6 +435,30 @@ fn main() {
                         cx.update(|cx| open_notes_task.detach_and_log_err(cx))
                             .log_err();
                     }
+                    OpenRequest::JoinProject {
+                        project_id,
+                        invite_token,
+                    } => {
+                        let app_state = app_state.clone();
+                        cx.update(|cx| {
+                            workspace::join_remote_project_with_invite(
+                                project_id,
+                                invite_token,
+                                app_state,
+                                cx,
+                            )
+                            .detach_and_log_err(cx);
+                        })
+                        .log_err();
+                    }
+                    OpenRequest::OpenSshProject { host, paths } => {
+                        let app_state = app_state.clone();
+                        cx.update(|cx| {
+                            zed::open_ssh_project(host, paths, app_state, cx)
+                                .detach_and_log_err(cx);
+                        })
+                        .log_err();
+                    }
                 }
             }
         })
@@ -445,8 +508,24 @@ async fn installation_id() -> Result<(String, bool)> {
     Ok((installation_id, false))
 }
 
-async fn restore_or_create_workspace(app_state: &Arc<AppState>, cx: AsyncAppContext) {
+async fn restore_or_create_workspace(
+    app_state: &Arc<AppState>,
+    activate_session: Option<Task<Result<SessionId>>>,
+    cx: AsyncAppContext,
+) {
     async_maybe!({
+        if let Some(activate_session) = activate_session {
+            let session_id = activate_session.await?;
+            let restored_windows = cx
+                .update(|cx| workspace::restore_session_windows(session_id, app_state, cx))?
+                .await
+                .log_err()
+                .unwrap_or(0);
+            if restored_windows > 0 {
+                return Ok(());
+            }
+        }
+
         if let Some(location) = workspace::last_opened_workspace_paths().await {
             cx.update(|cx| workspace::open_paths(location.paths().as_ref(), app_state, None, cx))?
                 .await
@@ -856,7 +935,7 @@ fn collect_url_args() -> Vec<String> {
         .filter_map(|arg| match std::fs::canonicalize(Path::new(&arg)) {
             Ok(path) => Some(format!("file://{}", path.to_string_lossy())),
             Err(error) => {
-                if let Some(_) = parse_zed_link(&arg) {
+                if parse_zed_link(&arg).is_some() || arg.starts_with("ssh://") {
                     Some(arg)
                 } else {
                     log::error!("error parsing path argument: {}", error);
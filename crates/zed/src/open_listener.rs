@@ -1,13 +1,14 @@
 use anyhow::{anyhow, Context, Result};
 use cli::{ipc, IpcHandshake};
 use cli::{ipc::IpcSender, CliRequest, CliResponse};
+use client::Client;
 use collections::HashMap;
 use editor::scroll::Autoscroll;
 use editor::Editor;
 use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 use futures::channel::{mpsc, oneshot};
 use futures::{FutureExt, SinkExt, StreamExt};
-use gpui::{AppContext, AsyncAppContext, Global};
+use gpui::{AppContext, AsyncAppContext, Global, Task, VisualContext as _};
 use itertools::Itertools;
 use language::{Bias, Point};
 use release_channel::parse_zed_link;
@@ -21,7 +22,7 @@ use std::time::Duration;
 use std::{path::PathBuf, sync::atomic::AtomicBool};
 use util::paths::PathLikeWithPosition;
 use util::ResultExt;
-use workspace::AppState;
+use workspace::{AppState, Workspace, WORKSPACE_DB};
 
 pub enum OpenRequest {
     Paths {
@@ -37,6 +38,14 @@ pub enum OpenRequest {
         channel_id: u64,
         heading: Option<String>,
     },
+    JoinProject {
+        project_id: u64,
+        invite_token: String,
+    },
+    OpenSshProject {
+        host: String,
+        paths: Vec<PathBuf>,
+    },
 }
 
 pub struct OpenListener {
@@ -76,6 +85,8 @@ impl OpenListener {
             self.handle_cli_connection(server_name)
         } else if let Some(request_path) = urls.first().and_then(|url| parse_zed_link(url)) {
             self.handle_zed_url_scheme(request_path)
+        } else if urls.first().map_or(false, |url| url.starts_with("ssh://")) {
+            self.handle_ssh_urls(urls)
         } else {
             self.handle_file_urls(urls)
         };
@@ -98,7 +109,17 @@ impl OpenListener {
 
     fn handle_zed_url_scheme(&self, request_path: &str) -> Option<OpenRequest> {
         let mut parts = request_path.split("/");
-        if parts.next() == Some("channel") {
+        let first_part = parts.next();
+        if first_part == Some("project") {
+            if let Some(Ok(project_id)) = parts.next().map(|id| id.parse::<u64>()) {
+                if let (Some("invite"), Some(invite_token)) = (parts.next(), parts.next()) {
+                    return Some(OpenRequest::JoinProject {
+                        project_id,
+                        invite_token: invite_token.to_string(),
+                    });
+                }
+            }
+        } else if first_part == Some("channel") {
             if let Some(slug) = parts.next() {
                 if let Some(id_str) = slug.split("-").last() {
                     if let Ok(channel_id) = id_str.parse::<u64>() {
@@ -125,6 +146,33 @@ impl OpenListener {
         None
     }
 
+    /// Handles `ssh://[user@]host/path` URLs, which must all name the same host.
+    fn handle_ssh_urls(&self, urls: &[String]) -> Option<OpenRequest> {
+        let mut host = None;
+        let mut paths = Vec::new();
+        for url in urls {
+            let Some((url_host, path)) = url
+                .strip_prefix("ssh://")
+                .and_then(|url| url.split_once('/'))
+                .filter(|(url_host, _)| !url_host.is_empty())
+            else {
+                log::error!("invalid ssh url: {}", url);
+                return None;
+            };
+            if *host.get_or_insert(url_host) != url_host {
+                log::error!("ssh urls must all refer to the same host");
+                return None;
+            }
+            let decoded = urlencoding::decode_binary(path.as_bytes());
+            paths.push(Path::new("/").join(OsStr::from_bytes(decoded.as_ref())));
+        }
+
+        Some(OpenRequest::OpenSshProject {
+            host: host?.to_string(),
+            paths,
+        })
+    }
+
     fn handle_file_urls(&self, urls: &[String]) -> Option<OpenRequest> {
         let paths: Vec<_> = urls
             .iter()
@@ -139,6 +187,39 @@ impl OpenListener {
     }
 }
 
+/// Opens the given paths on `host` in a new window, through a remote server
+/// that is started over SSH and kept running for as long as the project.
+pub fn open_ssh_project(
+    host: String,
+    paths: Vec<PathBuf>,
+    app_state: Arc<AppState>,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    let client = Client::new(app_state.client.http_client(), cx);
+    cx.spawn(|mut cx| async move {
+        let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+        let (connection, server) = remote_server::spawn_over_ssh(&host, &paths)?;
+        let project = remote_server::open_remote_project(
+            client,
+            connection,
+            app_state.languages.clone(),
+            app_state.fs.clone(),
+            cx.clone(),
+        )
+        .await?;
+        project.update(&mut cx, |_, cx| {
+            cx.on_release(move |_, _| drop(server)).detach();
+        })?;
+
+        let workspace_id = WORKSPACE_DB.next_id().await.unwrap_or(0);
+        let options = cx.update(|cx| (app_state.build_window_options)(None, None, cx))?;
+        cx.open_window(options, |cx| {
+            cx.new_view(|cx| Workspace::new(workspace_id, project, app_state.clone(), cx))
+        })?;
+        anyhow::Ok(())
+    })
+}
+
 fn connect_to_cli(
     server_name: &str,
 ) -> Result<(mpsc::Receiver<CliRequest>, IpcSender<CliResponse>)> {
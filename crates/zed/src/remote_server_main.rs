@@ -0,0 +1,92 @@
+//! The headless host for remote development. It serves the projects at the
+//! given paths to a single client, speaking rpc over stdin and stdout, which
+//! the client connects to over SSH. Logs are written to stderr.
+
+use client::{Client, UserStore};
+use fs::RealFs;
+use futures::{channel::mpsc, StreamExt as _};
+use gpui::{App, Context, Task};
+use language::LanguageRegistry;
+use node_runtime::RealNodeRuntime;
+use project::Project;
+use remote_server::HeadlessProject;
+use rpc::Connection;
+use settings::{default_settings, Settings, SettingsStore};
+use std::{path::PathBuf, sync::Arc};
+use util::{http, paths, ResultExt};
+use zed::languages;
+
+fn main() {
+    env_logger::init();
+
+    let paths = std::env::args()
+        .skip(1)
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    App::headless().run(move |cx| {
+        release_channel::init(env!("CARGO_PKG_VERSION"), cx);
+
+        let mut store = SettingsStore::default();
+        store
+            .set_default_settings(default_settings().as_ref(), cx)
+            .unwrap();
+        cx.set_global(store);
+        client::init_settings(cx);
+
+        let http = http::zed_client(&client::ClientSettings::get_global(cx).server_url);
+        let client = Client::new(http.clone(), cx);
+        let mut languages = LanguageRegistry::new(Task::ready(()));
+        languages.set_executor(cx.background_executor().clone());
+        languages.set_language_server_download_dir(paths::LANGUAGES_DIR.clone());
+        let languages = Arc::new(languages);
+        let node_runtime = RealNodeRuntime::new(http.clone());
+
+        language::init(cx);
+        languages::init(languages.clone(), node_runtime.clone(), cx);
+        project::Project::init(&client, cx);
+        terminal::init(cx);
+
+        let user_store = cx.new_model(|cx| UserStore::new(client.clone(), cx));
+        let project = Project::local(
+            client.clone(),
+            node_runtime,
+            user_store,
+            languages,
+            Arc::new(RealFs),
+            cx,
+        );
+
+        cx.spawn(|mut cx| async move {
+            for path in paths {
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.find_or_create_local_worktree(path, true, cx)
+                    })?
+                    .await
+                    .log_err();
+            }
+
+            let stdio = Connection::from_byte_streams(
+                smol::Unblock::new(std::io::stdin()),
+                smol::Unblock::new(std::io::stdout()),
+            );
+            let headless_project =
+                cx.new_model(|cx| HeadlessProject::new(project, client, stdio, cx))?;
+
+            let (disconnected_tx, mut disconnected_rx) = mpsc::unbounded();
+            let _subscription = cx.update(|cx| {
+                cx.subscribe(&headless_project, move |_, event, _| match event {
+                    remote_server::Event::GuestDisconnected => {
+                        disconnected_tx.unbounded_send(()).ok();
+                    }
+                })
+            })?;
+            disconnected_rx.next().await;
+            drop(headless_project);
+            cx.update(|cx| cx.quit())?;
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    });
+}